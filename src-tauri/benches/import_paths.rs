@@ -0,0 +1,144 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use image::{ImageFormat, Rgb, RgbImage};
+use stumble_app_lib::bench_support;
+
+const HASH_FILE_MB_ENV: &str = "STUMBLE_BENCH_HASH_MB";
+const DEFAULT_HASH_FILE_MB: u64 = 256;
+const INSERT_BATCH_ITEMS: usize = 10_000;
+const LOAD_STATE_ITEMS: usize = 50_000;
+
+fn bench_root(name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join("stumble-bench")
+        .join(format!("{}-{}", name, std::process::id()))
+}
+
+fn write_patterned_file(path: &Path, size_bytes: u64) {
+    let file = File::create(path).expect("failed to create hash fixture");
+    let mut writer = BufWriter::new(file);
+    let chunk: Vec<u8> = (0..1024 * 1024)
+        .map(|index| (index * 31 % 251) as u8)
+        .collect();
+    let mut written = 0;
+    while written < size_bytes {
+        let remaining = (size_bytes - written).min(chunk.len() as u64) as usize;
+        writer
+            .write_all(&chunk[..remaining])
+            .expect("failed to write hash fixture");
+        written += remaining as u64;
+    }
+    writer.flush().expect("failed to flush hash fixture");
+}
+
+fn write_fixture_image(path: &Path, width: u32, height: u32, format: ImageFormat) {
+    let image = RgbImage::from_fn(width, height, |x, y| {
+        let noise = (x.wrapping_mul(7919) ^ y.wrapping_mul(104_729)) % 64;
+        Rgb([
+            ((x * 255 / width) as u8).wrapping_add(noise as u8),
+            ((y * 255 / height) as u8).wrapping_add(noise as u8),
+            (((x + y) * 127 / (width + height)) as u8).wrapping_add(noise as u8),
+        ])
+    });
+    image
+        .save_with_format(path, format)
+        .expect("failed to write image fixture");
+}
+
+fn bench_sha256_for_file(c: &mut Criterion) {
+    let size_mb = std::env::var(HASH_FILE_MB_ENV)
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_HASH_FILE_MB);
+    let root = bench_root("hash");
+    fs::create_dir_all(&root).expect("failed to create hash bench root");
+    let path = root.join("payload.bin");
+    write_patterned_file(&path, size_mb * 1024 * 1024);
+
+    let mut group = c.benchmark_group("sha256_for_file");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(20));
+    group.throughput(Throughput::Bytes(size_mb * 1024 * 1024));
+    group.bench_function(format!("{}mb", size_mb), |b| {
+        b.iter(|| bench_support::hash_file(&path).expect("hash failed"))
+    });
+    group.finish();
+    let _ = fs::remove_dir_all(&root);
+}
+
+fn bench_generate_thumbnail(c: &mut Criterion) {
+    let root = bench_root("thumbs");
+    fs::create_dir_all(&root).expect("failed to create thumbnail bench root");
+    let fixtures = [
+        ("photo_4000x3000.jpg", 4000, 3000, ImageFormat::Jpeg),
+        ("screenshot_2560x1440.png", 2560, 1440, ImageFormat::Png),
+        ("icon_256x256.png", 256, 256, ImageFormat::Png),
+    ];
+
+    let mut group = c.benchmark_group("generate_thumbnail_internal");
+    group.sample_size(10);
+    for (name, width, height, format) in fixtures {
+        let input = root.join(name);
+        write_fixture_image(&input, width, height, format);
+        let output = root.join(format!("{}.webp", name));
+        // The generator skips outputs that already exist, so every sample starts without one.
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || {
+                    let _ = fs::remove_file(&output);
+                },
+                |_| bench_support::thumbnail_file(&input, &output).expect("thumbnail failed"),
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+    let _ = fs::remove_dir_all(&root);
+}
+
+fn bench_insert_items_batch(c: &mut Criterion) {
+    let root = bench_root("insert");
+    let mut group = c.benchmark_group("insert_items_batch");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(INSERT_BATCH_ITEMS as u64));
+    group.bench_function(format!("{}_items", INSERT_BATCH_ITEMS), |b| {
+        b.iter_batched(
+            || bench_support::reset_app_root(&root).expect("failed to reset app root"),
+            |_| {
+                bench_support::insert_synthetic_items("bench", INSERT_BATCH_ITEMS)
+                    .expect("insert failed")
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+    let _ = fs::remove_dir_all(&root);
+}
+
+fn bench_load_app_state(c: &mut Criterion) {
+    let root = bench_root("load");
+    bench_support::build_synthetic_library(&root, LOAD_STATE_ITEMS)
+        .expect("failed to build synthetic library");
+
+    let mut group = c.benchmark_group("load_app_state");
+    group.sample_size(10);
+    group.bench_function(format!("{}_items", LOAD_STATE_ITEMS), |b| {
+        b.iter(|| bench_support::load_app_state_item_count().expect("load failed"))
+    });
+    group.finish();
+    let _ = fs::remove_dir_all(&root);
+}
+
+criterion_group!(
+    benches,
+    bench_sha256_for_file,
+    bench_generate_thumbnail,
+    bench_insert_items_batch,
+    bench_load_app_state
+);
+criterion_main!(benches);
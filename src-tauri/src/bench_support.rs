@@ -0,0 +1,99 @@
+use std::fs;
+use std::path::Path;
+
+use crate::db::*;
+use crate::items::*;
+use crate::thumbs::*;
+use crate::vault::*;
+
+const SYNTHETIC_INSERT_BATCH_SIZE: usize = 1_000;
+const SYNTHETIC_TAG_COUNT: usize = 50;
+
+pub fn use_app_root(app_root: &Path) -> Result<(), String> {
+    fs::create_dir_all(app_root).map_err(|err| {
+        format!(
+            "failed to create bench app root {}: {}",
+            app_root.display(),
+            err
+        )
+    })?;
+    switch_app_root(app_root);
+    initialize_db()
+}
+
+pub fn reset_app_root(app_root: &Path) -> Result<(), String> {
+    if app_root.exists() {
+        fs::remove_dir_all(app_root).map_err(|err| {
+            format!(
+                "failed to clear bench app root {}: {}",
+                app_root.display(),
+                err
+            )
+        })?;
+    }
+    use_app_root(app_root)
+}
+
+fn synthetic_item_input(id_prefix: &str, index: usize, created_at: i64) -> InsertItemInput {
+    let is_bookmark = index.is_multiple_of(2);
+    InsertItemInput {
+        id: format!("{}-{}", id_prefix, index),
+        collection_id: None,
+        item_type: if is_bookmark { "bookmark" } else { "note" }.to_string(),
+        title: format!("Synthetic item {}", index),
+        filename: None,
+        vault_key: None,
+        vault_path: None,
+        preview_url: None,
+        width: None,
+        height: None,
+        thumb_status: "skipped".to_string(),
+        import_status: DEFAULT_IMPORT_STATUS.to_string(),
+        url: is_bookmark.then(|| format!("https://example.com/{}/{}", id_prefix, index)),
+        favicon_path: None,
+        meta_status: Some(DEFAULT_META_STATUS.to_string()),
+        description: Some(format!("Synthetic description for item {}", index)),
+        description_format: None,
+        rating: (index % 6) as i64,
+        is_favorite: index.is_multiple_of(17),
+        created_at,
+        updated_at: created_at,
+        tags: vec![format!("tag-{}", index % SYNTHETIC_TAG_COUNT)],
+        original_source_path: None,
+        source: None,
+    }
+}
+
+pub fn insert_synthetic_items(id_prefix: &str, count: usize) -> Result<usize, String> {
+    let base_created_at = chrono::Utc::now().timestamp_millis();
+    let mut inserted = 0;
+    let mut start = 0;
+    while start < count {
+        let end = (start + SYNTHETIC_INSERT_BATCH_SIZE).min(count);
+        let batch = (start..end)
+            .map(|index| {
+                synthetic_item_input(id_prefix, index, base_created_at - index as i64 * 60_000)
+            })
+            .collect();
+        inserted += insert_items_batch(batch)?.len();
+        start = end;
+    }
+    Ok(inserted)
+}
+
+pub fn build_synthetic_library(app_root: &Path, item_count: usize) -> Result<usize, String> {
+    reset_app_root(app_root)?;
+    insert_synthetic_items("synthetic", item_count)
+}
+
+pub fn load_app_state_item_count() -> Result<usize, String> {
+    Ok(load_app_state(None, None)?.items.len())
+}
+
+pub fn hash_file(path: &Path) -> Result<String, String> {
+    sha256_for_file(path)
+}
+
+pub fn thumbnail_file(input_path: &Path, output_path: &Path) -> Result<(), String> {
+    generate_thumbnail_internal(input_path, output_path, IMPORT_THUMB_MAX_SIZE)
+}
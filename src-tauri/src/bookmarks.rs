@@ -0,0 +1,3046 @@
+use chrono::Utc;
+use image::{imageops::FilterType, GenericImageView, ImageReader};
+use reqwest::header::{
+    ACCEPT, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+use url::Url;
+use uuid::Uuid;
+
+use crate::collections::*;
+use crate::commands::*;
+use crate::db::*;
+use crate::items::*;
+use crate::tags::*;
+use crate::vault::*;
+
+pub(crate) const BOOKMARK_HTML_MAX_BYTES: usize = 1_500_000;
+pub(crate) const BOOKMARK_HTML_PARSE_BUDGET_MS: u64 = 1_500;
+pub(crate) const BOOKMARK_FAVICON_MAX_BYTES: usize = 512 * 1024;
+pub(crate) const ITEM_PREVIEW_MAX_BYTES: usize = 8 * 1024 * 1024;
+pub(crate) const BOOKMARK_FETCH_TIMEOUT_SECS: u64 = 7;
+pub(crate) const BOOKMARK_FETCH_RETRIES: usize = 1;
+pub(crate) const SETTING_FETCH_HOST_MAX_CONCURRENT: &str = "fetch_host_max_concurrent";
+pub(crate) const SETTING_FETCH_HOST_MIN_SPACING_MS: &str = "fetch_host_min_spacing_ms";
+pub(crate) const DEFAULT_FETCH_HOST_MAX_CONCURRENT: usize = 2;
+pub(crate) const DEFAULT_FETCH_HOST_MIN_SPACING_MS: u64 = 500;
+pub(crate) const FETCH_COORDINATOR_MAX_HOSTS: usize = 256;
+pub(crate) const FETCH_COORDINATOR_MIN_WAIT_MS: u64 = 25;
+pub(crate) const BOOKMARK_USER_AGENT: &str =
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Stumble/0.1 Safari/537.36";
+pub(crate) const FAVICON_PROGRESS_EVENT: &str = "favicon://progress";
+pub(crate) const FAVICON_BATCH_MAX_LIMIT: u32 = 500;
+pub(crate) const FAVICON_FETCH_CONCURRENCY: usize = 4;
+pub(crate) const FAVICON_HOST_RETRY_AFTER_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+pub(crate) const FAVICON_NORMALIZED_SIZE: u32 = 64;
+pub(crate) const DEEP_LINK_SCHEME: &str = "stumble";
+pub(crate) const DEEP_LINK_ITEM_ADDED_EVENT: &str = "deep-link://item-added";
+pub(crate) const DEEP_LINK_ERROR_EVENT: &str = "deep-link://error";
+pub(crate) const SETTING_BOOKMARK_REFRESH_DAYS: &str = "bookmark_refresh_days";
+pub(crate) const SETTING_BOOKMARK_REFRESH_PER_HOUR: &str = "bookmark_refresh_per_hour";
+pub(crate) const DEFAULT_BOOKMARK_REFRESH_DAYS: u32 = 90;
+pub(crate) const DEFAULT_BOOKMARK_REFRESH_PER_HOUR: u32 = 20;
+pub(crate) const BOOKMARK_REFRESH_MAX_PER_HOUR: u32 = 500;
+pub(crate) const BOOKMARK_REFRESH_STARTUP_DELAY_SECS: u64 = 10 * 60;
+pub(crate) const BOOKMARK_REFRESH_POLL_SECS: u64 = 60 * 60;
+pub(crate) const BOOKMARK_REFRESH_BACKOFF_BASE_MS: i64 = 60 * 60 * 1000;
+pub(crate) const BOOKMARK_REFRESH_BACKOFF_MAX_MS: i64 = 14 * 24 * 60 * 60 * 1000;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateItemBookmarkMetadataInput {
+    pub(crate) item_id: String,
+    pub(crate) url: Option<String>,
+    pub(crate) title: Option<String>,
+    pub(crate) filename: Option<String>,
+    pub(crate) favicon_path: Option<String>,
+    pub(crate) meta_status: String,
+    #[serde(default)]
+    pub(crate) clear_url: bool,
+    #[serde(default)]
+    pub(crate) clear_favicon_path: bool,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct FetchHostLimits {
+    pub(crate) max_concurrent: usize,
+    pub(crate) min_spacing_ms: u64,
+}
+
+pub(crate) struct FetchHostSlot {
+    pub(crate) in_flight: usize,
+    pub(crate) queued: usize,
+    pub(crate) last_request_at: Option<Instant>,
+    pub(crate) last_request_ms: Option<i64>,
+    pub(crate) last_used: u64,
+}
+
+pub(crate) struct FetchCoordinator {
+    pub(crate) limits: FetchHostLimits,
+    pub(crate) hosts: BTreeMap<String, FetchHostSlot>,
+    pub(crate) clock: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FetchHostStats {
+    pub(crate) host: String,
+    pub(crate) in_flight: usize,
+    pub(crate) queued: usize,
+    pub(crate) last_request_at: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FetchCoordinatorStats {
+    pub(crate) max_concurrent_per_host: usize,
+    pub(crate) min_spacing_ms: u64,
+    pub(crate) hosts: Vec<FetchHostStats>,
+}
+
+pub(crate) static FETCH_COORDINATOR: Mutex<FetchCoordinator> = Mutex::new(FetchCoordinator {
+    limits: FetchHostLimits {
+        max_concurrent: DEFAULT_FETCH_HOST_MAX_CONCURRENT,
+        min_spacing_ms: DEFAULT_FETCH_HOST_MIN_SPACING_MS,
+    },
+    hosts: BTreeMap::new(),
+    clock: 0,
+});
+
+pub(crate) type BookmarkFetchAbort = Box<dyn Fn() + Send>;
+
+pub(crate) const BOOKMARK_FETCH_RUNNING: u8 = 0;
+pub(crate) const BOOKMARK_FETCH_COMMITTED: u8 = 1;
+pub(crate) const BOOKMARK_FETCH_CANCELLED: u8 = 2;
+
+/// Shared between a cancellable fetch task and `cancel_bookmark_fetch`. The task calls
+/// `begin_commit` before its first write (favicon file or database row); whichever side moves
+/// the state first wins, so a cancelled fetch never leaves files behind and a late cancel
+/// reports that the fetch already finished.
+#[derive(Clone, Default)]
+pub(crate) struct BookmarkFetchCancel(Arc<AtomicU8>);
+
+impl BookmarkFetchCancel {
+    pub(crate) fn begin_commit(&self) -> Result<(), String> {
+        match self.0.compare_exchange(
+            BOOKMARK_FETCH_RUNNING,
+            BOOKMARK_FETCH_COMMITTED,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) | Err(BOOKMARK_FETCH_COMMITTED) => Ok(()),
+            Err(_) => Err("bookmark fetch cancelled".to_string()),
+        }
+    }
+
+    pub(crate) fn cancel(&self) -> bool {
+        self.0
+            .compare_exchange(
+                BOOKMARK_FETCH_RUNNING,
+                BOOKMARK_FETCH_CANCELLED,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok()
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire) == BOOKMARK_FETCH_CANCELLED
+    }
+}
+
+pub(crate) struct BookmarkFetchHandle {
+    pub(crate) cancel: BookmarkFetchCancel,
+    pub(crate) abort: BookmarkFetchAbort,
+}
+
+#[derive(Default)]
+pub(crate) struct BookmarkFetchState {
+    pub(crate) in_flight: Mutex<HashMap<String, BookmarkFetchHandle>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FetchBookmarkMetadataResult {
+    pub(crate) final_url: String,
+    pub(crate) title: Option<String>,
+    pub(crate) favicon_path: Option<String>,
+    pub(crate) favicon_ext: Option<String>,
+    pub(crate) favicon_url_candidate: Option<String>,
+    pub(crate) html_parse_ms: Option<i64>,
+    pub(crate) html_parse_timed_out: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FaviconProgressPayload {
+    pub(crate) processed: usize,
+    pub(crate) total: usize,
+    pub(crate) item_id: String,
+    pub(crate) host: String,
+    pub(crate) status: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FetchMissingFaviconsResult {
+    pub(crate) processed: usize,
+    pub(crate) updated: usize,
+    pub(crate) reused_cached: usize,
+    pub(crate) failed: usize,
+    pub(crate) skipped_hosts: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReprocessFaviconsResult {
+    pub(crate) scanned: usize,
+    pub(crate) normalized: usize,
+    pub(crate) already_normalized: usize,
+    pub(crate) skipped: usize,
+    pub(crate) updated_items: usize,
+    pub(crate) removed_files: usize,
+    pub(crate) bytes_before: u64,
+    pub(crate) bytes_after: u64,
+    pub(crate) bytes_saved: u64,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CreateBookmarkItemResult {
+    pub(crate) item_id: String,
+    pub(crate) url: String,
+    pub(crate) collection_id: Option<String>,
+    pub(crate) meta_status: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeepLinkErrorPayload {
+    pub(crate) link: String,
+    pub(crate) message: String,
+}
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BookmarkRefreshSettings {
+    pub(crate) refresh_days: u32,
+    pub(crate) per_hour: u32,
+}
+
+pub(crate) struct BookmarkRefreshHostBackoff {
+    pub(crate) failures: u32,
+    pub(crate) retry_at: i64,
+}
+
+pub(crate) struct BookmarkRefreshState {
+    pub(crate) paused: bool,
+    pub(crate) running: bool,
+    pub(crate) last_run_at: Option<i64>,
+    pub(crate) last_checked: usize,
+    pub(crate) last_updated: usize,
+    pub(crate) last_failed: usize,
+    pub(crate) host_backoff: BTreeMap<String, BookmarkRefreshHostBackoff>,
+}
+
+pub(crate) static BOOKMARK_REFRESH_STATE: Mutex<BookmarkRefreshState> =
+    Mutex::new(BookmarkRefreshState {
+        paused: false,
+        running: false,
+        last_run_at: None,
+        last_checked: 0,
+        last_updated: 0,
+        last_failed: 0,
+        host_backoff: BTreeMap::new(),
+    });
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BookmarkRefreshStatus {
+    pub(crate) enabled: bool,
+    pub(crate) paused: bool,
+    pub(crate) running: bool,
+    pub(crate) refresh_days: u32,
+    pub(crate) per_hour: u32,
+    pub(crate) stale_count: i64,
+    pub(crate) last_run_at: Option<i64>,
+    pub(crate) last_checked: usize,
+    pub(crate) last_updated: usize,
+    pub(crate) last_failed: usize,
+    pub(crate) backed_off_hosts: Vec<String>,
+}
+
+pub(crate) struct BookmarkRefreshCandidate {
+    pub(crate) item_id: String,
+    pub(crate) url: String,
+    pub(crate) title: String,
+    pub(crate) filename: String,
+    pub(crate) favicon_path: Option<String>,
+    pub(crate) meta_fetched_title: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ItemPreviewCacheResult {
+    pub(crate) item_id: String,
+    pub(crate) preview_url: Option<String>,
+    pub(crate) source_url: Option<String>,
+    pub(crate) downloaded: bool,
+    pub(crate) not_modified: bool,
+}
+
+pub(crate) struct ItemPreviewState {
+    pub(crate) preview_url: Option<String>,
+    pub(crate) source_url: Option<String>,
+    pub(crate) etag: Option<String>,
+    pub(crate) last_modified: Option<String>,
+}
+
+pub(crate) enum PreviewDownload {
+    NotModified,
+    Fetched {
+        bytes: Vec<u8>,
+        ext: String,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+}
+
+pub(crate) struct DeepLinkAddRequest {
+    pub(crate) url: Url,
+    pub(crate) tags: Vec<String>,
+    pub(crate) collection_id: Option<String>,
+}
+
+pub(crate) fn favicons_root_path() -> Result<PathBuf, String> {
+    Ok(app_root_path()?.join("favicons"))
+}
+
+pub(crate) fn ensure_favicons_root_internal() -> Result<PathBuf, String> {
+    let root = favicons_root_path()?;
+    fs::create_dir_all(&root)
+        .map_err(|err| format!("failed to create favicons root {}: {}", root.display(), err))?;
+    Ok(root)
+}
+
+pub(crate) fn remove_favicon_file(favicon_path: &str) -> Result<bool, String> {
+    let trimmed = favicon_path.trim();
+    if trimmed.is_empty() {
+        return Ok(false);
+    }
+
+    let path = PathBuf::from(trimmed);
+    if !path.exists() || !path.is_file() {
+        return Ok(false);
+    }
+
+    fs::remove_file(&path)
+        .map_err(|err| format!("failed to remove favicon {}: {}", path.display(), err))?;
+    Ok(true)
+}
+
+pub(crate) fn previews_root_path() -> Result<PathBuf, String> {
+    Ok(app_root_path()?.join("previews"))
+}
+
+pub(crate) fn ensure_previews_root_internal() -> Result<PathBuf, String> {
+    let root = previews_root_path()?;
+    fs::create_dir_all(&root)
+        .map_err(|err| format!("failed to create previews root {}: {}", root.display(), err))?;
+    Ok(root)
+}
+
+/// True when `preview_url` points into the managed `previews/` root, i.e. it is a cached
+/// copy this app owns (and may delete) rather than a remote URL or a user file.
+pub(crate) fn is_cached_preview_path(preview_url: &str) -> bool {
+    let trimmed = preview_url.trim();
+    !trimmed.is_empty()
+        && previews_root_path()
+            .map(|root| Path::new(trimmed).starts_with(root))
+            .unwrap_or(false)
+}
+
+pub(crate) fn store_preview_bytes(bytes: &[u8], ext: &str) -> Result<PathBuf, String> {
+    let root = ensure_previews_root_internal()?;
+    let filename = format!("{}.{}", sha256_for_bytes(bytes), normalize_ext(ext));
+    let path = root.join(filename);
+    if !path.exists() {
+        fs::write(&path, bytes)
+            .map_err(|err| format!("failed to write preview {}: {}", path.display(), err))?;
+    }
+    Ok(path)
+}
+
+pub(crate) fn remove_preview_file(preview_path: &str) -> Result<bool, String> {
+    if !is_cached_preview_path(preview_path) {
+        return Ok(false);
+    }
+    let path = PathBuf::from(preview_path.trim());
+    if !path.is_file() {
+        return Ok(false);
+    }
+    fs::remove_file(&path)
+        .map_err(|err| format!("failed to remove preview {}: {}", path.display(), err))?;
+    Ok(true)
+}
+
+pub(crate) fn is_http_or_https_url(url: &Url) -> bool {
+    matches!(url.scheme(), "http" | "https")
+}
+
+pub(crate) fn normalize_bookmark_url_input(raw: &str) -> Result<Url, String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err("bookmark url cannot be empty".to_string());
+    }
+
+    let parsed = Url::parse(trimmed).map_err(|err| format!("invalid bookmark url: {}", err))?;
+    if !is_http_or_https_url(&parsed) {
+        return Err("only http:// and https:// URLs are supported".to_string());
+    }
+    Ok(parsed)
+}
+
+pub(crate) fn build_bookmark_http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::limited(8))
+        .timeout(Duration::from_secs(BOOKMARK_FETCH_TIMEOUT_SECS))
+        .connect_timeout(Duration::from_secs(4))
+        .user_agent(BOOKMARK_USER_AGENT)
+        .build()
+        .map_err(|err| format!("failed to build bookmark http client: {}", err))
+}
+
+pub(crate) struct FetchHostPermit {
+    pub(crate) host: String,
+}
+
+pub(crate) enum FetchHostTicket {
+    Granted,
+    Wait(Duration),
+}
+
+impl FetchCoordinator {
+    pub(crate) fn slot(&mut self, host: &str) -> &mut FetchHostSlot {
+        self.clock += 1;
+        let clock = self.clock;
+        if !self.hosts.contains_key(host) && self.hosts.len() >= FETCH_COORDINATOR_MAX_HOSTS {
+            let evicted = self
+                .hosts
+                .iter()
+                .filter(|(_, slot)| slot.in_flight == 0 && slot.queued == 0)
+                .min_by_key(|(_, slot)| slot.last_used)
+                .map(|(host, _)| host.clone());
+            if let Some(evicted) = evicted {
+                self.hosts.remove(&evicted);
+            }
+        }
+        let slot = self.hosts.entry(host.to_string()).or_insert(FetchHostSlot {
+            in_flight: 0,
+            queued: 0,
+            last_request_at: None,
+            last_request_ms: None,
+            last_used: clock,
+        });
+        slot.last_used = clock;
+        slot
+    }
+
+    pub(crate) fn try_acquire(&mut self, host: &str, waiting: bool) -> FetchHostTicket {
+        let limits = self.limits;
+        let slot = self.slot(host);
+        let spacing = Duration::from_millis(limits.min_spacing_ms);
+        let since_last = slot
+            .last_request_at
+            .map(|at| at.elapsed())
+            .unwrap_or(spacing);
+        if slot.in_flight < limits.max_concurrent.max(1) && since_last >= spacing {
+            if waiting {
+                slot.queued = slot.queued.saturating_sub(1);
+            }
+            slot.in_flight += 1;
+            slot.last_request_at = Some(Instant::now());
+            slot.last_request_ms = Some(Utc::now().timestamp_millis());
+            return FetchHostTicket::Granted;
+        }
+        if !waiting {
+            slot.queued += 1;
+        }
+        let wait = spacing
+            .saturating_sub(since_last)
+            .max(Duration::from_millis(FETCH_COORDINATOR_MIN_WAIT_MS));
+        FetchHostTicket::Wait(wait)
+    }
+}
+
+pub(crate) struct FetchHostQueueGuard {
+    pub(crate) host: String,
+    pub(crate) active: bool,
+}
+
+impl Drop for FetchHostQueueGuard {
+    fn drop(&mut self) {
+        if !self.active {
+            return;
+        }
+        if let Ok(mut coordinator) = FETCH_COORDINATOR.lock() {
+            if let Some(slot) = coordinator.hosts.get_mut(&self.host) {
+                slot.queued = slot.queued.saturating_sub(1);
+            }
+        }
+    }
+}
+
+impl Drop for FetchHostPermit {
+    fn drop(&mut self) {
+        if let Ok(mut coordinator) = FETCH_COORDINATOR.lock() {
+            if let Some(slot) = coordinator.hosts.get_mut(&self.host) {
+                slot.in_flight = slot.in_flight.saturating_sub(1);
+            }
+        }
+    }
+}
+
+pub(crate) async fn acquire_fetch_host_permit(url: &Url) -> Result<FetchHostPermit, String> {
+    let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+    let mut queue_guard = FetchHostQueueGuard {
+        host: host.clone(),
+        active: false,
+    };
+    loop {
+        let ticket = FETCH_COORDINATOR
+            .lock()
+            .map_err(|_| "fetch coordinator lock poisoned".to_string())?
+            .try_acquire(&host, queue_guard.active);
+        match ticket {
+            FetchHostTicket::Granted => {
+                queue_guard.active = false;
+                return Ok(FetchHostPermit { host });
+            }
+            FetchHostTicket::Wait(wait) => {
+                queue_guard.active = true;
+                tokio::time::sleep(wait).await;
+            }
+        }
+    }
+}
+
+pub(crate) fn load_fetch_host_limits(connection: &Connection) -> Result<FetchHostLimits, String> {
+    let max_concurrent = read_app_setting(connection, SETTING_FETCH_HOST_MAX_CONCURRENT)?
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_FETCH_HOST_MAX_CONCURRENT);
+    let min_spacing_ms = read_app_setting(connection, SETTING_FETCH_HOST_MIN_SPACING_MS)?
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_FETCH_HOST_MIN_SPACING_MS);
+    Ok(FetchHostLimits {
+        max_concurrent,
+        min_spacing_ms,
+    })
+}
+
+pub(crate) fn apply_fetch_host_limits(limits: FetchHostLimits) -> Result<(), String> {
+    FETCH_COORDINATOR
+        .lock()
+        .map_err(|_| "fetch coordinator lock poisoned".to_string())?
+        .limits = limits;
+    Ok(())
+}
+
+pub(crate) fn load_fetch_coordinator_settings() -> Result<(), String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    apply_fetch_host_limits(load_fetch_host_limits(&connection)?)
+}
+
+#[tauri::command]
+pub(crate) fn get_fetch_coordinator_stats() -> Result<FetchCoordinatorStats, String> {
+    let coordinator = FETCH_COORDINATOR
+        .lock()
+        .map_err(|_| "fetch coordinator lock poisoned".to_string())?;
+    Ok(FetchCoordinatorStats {
+        max_concurrent_per_host: coordinator.limits.max_concurrent,
+        min_spacing_ms: coordinator.limits.min_spacing_ms,
+        hosts: coordinator
+            .hosts
+            .iter()
+            .map(|(host, slot)| FetchHostStats {
+                host: host.clone(),
+                in_flight: slot.in_flight,
+                queued: slot.queued,
+                last_request_at: slot.last_request_ms,
+            })
+            .collect(),
+    })
+}
+
+#[tauri::command]
+pub(crate) fn set_fetch_host_limits(
+    max_concurrent: usize,
+    min_spacing_ms: u64,
+) -> Result<FetchCoordinatorStats, String> {
+    if max_concurrent == 0 {
+        return Err("max concurrent requests per host must be at least 1".to_string());
+    }
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    write_app_setting(
+        &connection,
+        SETTING_FETCH_HOST_MAX_CONCURRENT,
+        &max_concurrent.to_string(),
+    )?;
+    write_app_setting(
+        &connection,
+        SETTING_FETCH_HOST_MIN_SPACING_MS,
+        &min_spacing_ms.to_string(),
+    )?;
+    apply_fetch_host_limits(FetchHostLimits {
+        max_concurrent,
+        min_spacing_ms,
+    })?;
+    get_fetch_coordinator_stats()
+}
+
+pub(crate) async fn fetch_bookmark_page_html(
+    client: &reqwest::Client,
+    url: &Url,
+) -> Result<(Url, Option<String>), String> {
+    let mut last_error: Option<String> = None;
+
+    for attempt in 1..=(BOOKMARK_FETCH_RETRIES + 1) {
+        let _permit = acquire_fetch_host_permit(url).await?;
+        let response_result = client
+            .get(url.clone())
+            .header(ACCEPT, "text/html,application/xhtml+xml;q=0.9,*/*;q=0.8")
+            .send()
+            .await;
+
+        let response = match response_result {
+            Ok(response) => response,
+            Err(err) => {
+                let message = format!(
+                    "bookmark html request failed (attempt {}): {}",
+                    attempt, err
+                );
+                log_eprintln!("{}", message);
+                last_error = Some(message);
+                continue;
+            }
+        };
+
+        let final_url = response.url().clone();
+        if !is_http_or_https_url(&final_url) {
+            return Err(format!(
+                "redirected to unsupported url scheme: {}",
+                final_url.as_str()
+            ));
+        }
+
+        if !response.status().is_success() {
+            log_eprintln!(
+                "bookmark html request returned status {} for {}",
+                response.status(),
+                final_url
+            );
+            return Ok((final_url, None));
+        }
+
+        if let Some(content_length) = response.content_length() {
+            if content_length as usize > BOOKMARK_HTML_MAX_BYTES {
+                log_eprintln!(
+                    "bookmark html skipped due to content-length {} > {} for {}",
+                    content_length,
+                    BOOKMARK_HTML_MAX_BYTES,
+                    final_url
+                );
+                return Ok((final_url, None));
+            }
+        }
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_ascii_lowercase());
+        let is_html = content_type
+            .as_deref()
+            .map(|value| value.contains("text/html") || value.contains("application/xhtml"))
+            .unwrap_or(true);
+        if !is_html {
+            return Ok((final_url, None));
+        }
+
+        let mut response = response;
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|err| format!("failed to read bookmark html response: {}", err))?
+        {
+            if bytes.len() + chunk.len() > BOOKMARK_HTML_MAX_BYTES {
+                log_eprintln!(
+                    "bookmark html download aborted after exceeding {} bytes for {}",
+                    BOOKMARK_HTML_MAX_BYTES,
+                    final_url
+                );
+                return Ok((final_url, None));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        let html = String::from_utf8_lossy(&bytes).into_owned();
+        return Ok((final_url, Some(html)));
+    }
+
+    Err(last_error.unwrap_or_else(|| "bookmark html request failed".to_string()))
+}
+
+pub(crate) fn default_favicon_candidates(final_url: &Url) -> Vec<Url> {
+    let mut candidates = Vec::new();
+    if let Ok(fallback) = final_url.join("/favicon.ico") {
+        if is_http_or_https_url(&fallback) {
+            candidates.push(fallback);
+        }
+    }
+    candidates
+}
+
+pub(crate) fn html_title_fallback(html: &str) -> Option<String> {
+    let lowered = html.to_ascii_lowercase();
+    let open_start = lowered.find("<title")?;
+    let content_start = open_start + lowered[open_start..].find('>')? + 1;
+    let content_end = content_start + lowered[content_start..].find("</title")?;
+    let title = collapse_whitespace(&html[content_start..content_end]);
+    (!title.is_empty()).then_some(title)
+}
+
+pub(crate) async fn parse_bookmark_html_with_budget(
+    html: String,
+    final_url: Url,
+) -> Result<(Option<String>, Vec<Url>, i64, bool), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let started = Instant::now();
+        let html: std::sync::Arc<str> = html.into();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let parse_html = html.clone();
+        let parse_url = final_url.clone();
+        std::thread::spawn(move || {
+            let _ = sender.send(html_title_and_favicon_candidates(&parse_html, &parse_url));
+        });
+        let (title, candidates, timed_out) =
+            match receiver.recv_timeout(Duration::from_millis(BOOKMARK_HTML_PARSE_BUDGET_MS)) {
+                Ok((title, candidates)) => (title, candidates, false),
+                Err(_) => {
+                    log_eprintln!(
+                        "bookmark html parse exceeded {} ms for {}; falling back to <title> scan",
+                        BOOKMARK_HTML_PARSE_BUDGET_MS,
+                        final_url
+                    );
+                    (
+                        html_title_fallback(&html),
+                        default_favicon_candidates(&final_url),
+                        true,
+                    )
+                }
+            };
+        let parse_ms = started.elapsed().as_millis() as i64;
+        log_println!(
+            "[bookmark] parsed html for {} in {} ms",
+            final_url,
+            parse_ms
+        );
+        (title, candidates, parse_ms, timed_out)
+    })
+    .await
+    .map_err(|err| format!("bookmark html parse thread join failed: {}", err))
+}
+
+pub(crate) fn html_title_and_favicon_candidates(
+    html: &str,
+    final_url: &Url,
+) -> (Option<String>, Vec<Url>) {
+    let document = Html::parse_document(html);
+    let mut title: Option<String> = None;
+    let mut og_title: Option<String> = None;
+    let mut weighted_candidates: Vec<(u8, Url)> = Vec::new();
+
+    if let Ok(title_selector) = Selector::parse("title") {
+        if let Some(node) = document.select(&title_selector).next() {
+            let text = collapse_whitespace(&node.text().collect::<Vec<_>>().join(" "));
+            if !text.is_empty() {
+                title = Some(text);
+            }
+        }
+    }
+
+    if let Ok(meta_selector) = Selector::parse("meta") {
+        for node in document.select(&meta_selector) {
+            let property = node
+                .value()
+                .attr("property")
+                .or_else(|| node.value().attr("name"))
+                .map(|value| value.trim().to_ascii_lowercase());
+            if property.as_deref() != Some("og:title") {
+                continue;
+            }
+            let content = node
+                .value()
+                .attr("content")
+                .map(collapse_whitespace)
+                .filter(|value| !value.is_empty());
+            if content.is_some() {
+                og_title = content;
+                break;
+            }
+        }
+    }
+
+    if let Ok(link_selector) = Selector::parse("link[href]") {
+        for node in document.select(&link_selector) {
+            let rel = node
+                .value()
+                .attr("rel")
+                .map(|value| value.to_ascii_lowercase())
+                .unwrap_or_default();
+            if rel.is_empty() {
+                continue;
+            }
+
+            let priority = if rel.contains("shortcut icon") {
+                Some(0)
+            } else if rel
+                .split_whitespace()
+                .any(|token| token == "icon" || token == "shortcut")
+            {
+                Some(1)
+            } else if rel.contains("apple-touch-icon") {
+                Some(2)
+            } else {
+                None
+            };
+            let Some(priority) = priority else {
+                continue;
+            };
+
+            let href = match node.value().attr("href") {
+                Some(href) if !href.trim().is_empty() => href.trim(),
+                _ => continue,
+            };
+
+            let resolved = match final_url.join(href) {
+                Ok(url) => url,
+                Err(_) => continue,
+            };
+            if !is_http_or_https_url(&resolved) {
+                continue;
+            }
+
+            weighted_candidates.push((priority, resolved));
+        }
+    }
+
+    weighted_candidates.sort_by_key(|(priority, _)| *priority);
+    let mut candidates = Vec::new();
+    let mut seen = BTreeSet::new();
+    for (_, candidate) in weighted_candidates {
+        if seen.insert(candidate.as_str().to_string()) {
+            candidates.push(candidate);
+        }
+    }
+
+    if let Ok(fallback) = final_url.join("/favicon.ico") {
+        if is_http_or_https_url(&fallback) && seen.insert(fallback.as_str().to_string()) {
+            candidates.push(fallback);
+        }
+    }
+
+    (title.or(og_title), candidates)
+}
+
+pub(crate) fn infer_favicon_extension(
+    content_type_header: Option<&str>,
+    source_url: &Url,
+    bytes: &[u8],
+) -> String {
+    let content_type = content_type_header
+        .map(|value| value.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    if let Some(ext) = sniff_extension(bytes)
+        .filter(|ext| matches!(*ext, "png" | "jpg" | "gif" | "webp" | "ico" | "svg"))
+    {
+        return ext.to_string();
+    }
+    for (needle, ext) in [
+        ("image/png", "png"),
+        ("image/jpeg", "jpg"),
+        ("image/gif", "gif"),
+        ("image/webp", "webp"),
+        ("image/x-icon", "ico"),
+        ("vnd.microsoft.icon", "ico"),
+        ("image/ico", "ico"),
+        ("image/svg", "svg"),
+    ] {
+        if content_type.contains(needle) {
+            return ext.to_string();
+        }
+    }
+
+    if let Some(ext) = source_url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .and_then(|segment| Path::new(segment).extension())
+        .and_then(OsStr::to_str)
+    {
+        let normalized = normalize_ext(ext);
+        if matches!(
+            normalized.as_str(),
+            "png" | "jpg" | "jpeg" | "gif" | "webp" | "ico" | "svg"
+        ) {
+            return if normalized == "jpeg" {
+                "jpg".to_string()
+            } else {
+                normalized
+            };
+        }
+    }
+
+    "ico".to_string()
+}
+
+pub(crate) async fn download_favicon_candidate(
+    client: &reqwest::Client,
+    favicon_url: &Url,
+) -> Result<(Vec<u8>, String), String> {
+    let mut last_error: Option<String> = None;
+
+    for attempt in 1..=(BOOKMARK_FETCH_RETRIES + 1) {
+        let _permit = acquire_fetch_host_permit(favicon_url).await?;
+        let response_result = client
+            .get(favicon_url.clone())
+            .header(ACCEPT, "image/*,*/*;q=0.8")
+            .send()
+            .await;
+
+        let response = match response_result {
+            Ok(response) => response,
+            Err(err) => {
+                let message = format!(
+                    "favicon request failed for {} (attempt {}): {}",
+                    favicon_url, attempt, err
+                );
+                last_error = Some(message.clone());
+                log_eprintln!("{}", message);
+                continue;
+            }
+        };
+
+        if !response.status().is_success() {
+            let message = format!(
+                "favicon request returned status {} for {}",
+                response.status(),
+                favicon_url
+            );
+            last_error = Some(message.clone());
+            log_eprintln!("{}", message);
+            continue;
+        }
+
+        if let Some(content_length) = response.content_length() {
+            if content_length as usize > BOOKMARK_FAVICON_MAX_BYTES {
+                let message = format!(
+                    "favicon too large for {} ({} bytes > {} bytes)",
+                    favicon_url, content_length, BOOKMARK_FAVICON_MAX_BYTES
+                );
+                last_error = Some(message.clone());
+                log_eprintln!("{}", message);
+                continue;
+            }
+        }
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|err| format!("failed to read favicon response {}: {}", favicon_url, err))?;
+        if bytes.is_empty() {
+            last_error = Some(format!("favicon response empty: {}", favicon_url));
+            continue;
+        }
+        if bytes.len() > BOOKMARK_FAVICON_MAX_BYTES {
+            let message = format!(
+                "favicon exceeded max size after download for {} ({} bytes > {} bytes)",
+                favicon_url,
+                bytes.len(),
+                BOOKMARK_FAVICON_MAX_BYTES
+            );
+            last_error = Some(message.clone());
+            log_eprintln!("{}", message);
+            continue;
+        }
+
+        let ext = infer_favicon_extension(content_type.as_deref(), favicon_url, &bytes);
+        return Ok((bytes.to_vec(), ext));
+    }
+
+    Err(last_error.unwrap_or_else(|| format!("failed to download favicon: {}", favicon_url)))
+}
+
+pub(crate) fn store_favicon_bytes(bytes: &[u8], ext: &str) -> Result<PathBuf, String> {
+    let root = ensure_favicons_root_internal()?;
+    let filename = format!("{}.{}", sha256_for_bytes(bytes), normalize_ext(ext));
+    let path = root.join(filename);
+    if !path.exists() {
+        fs::write(&path, bytes)
+            .map_err(|err| format!("failed to write favicon {}: {}", path.display(), err))?;
+    }
+    Ok(path)
+}
+
+#[tauri::command]
+pub(crate) fn update_item_bookmark_metadata(
+    input: UpdateItemBookmarkMetadataInput,
+) -> Result<i64, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let updated_at = Utc::now().timestamp_millis();
+
+    let normalized_url = match normalize_optional_trimmed_string(input.url) {
+        Some(value) => Some(normalize_bookmark_url_input(&value)?.as_str().to_string()),
+        None => None,
+    };
+    let normalized_title = normalize_optional_trimmed_string(input.title);
+    let normalized_filename = normalize_optional_trimmed_string(input.filename);
+    let normalized_favicon_path = normalize_optional_trimmed_string(input.favicon_path);
+    let normalized_meta_status = normalize_meta_status(&input.meta_status);
+
+    let affected_rows = connection
+        .execute(
+            "UPDATE items
+             SET url = CASE WHEN ?8 THEN NULL ELSE COALESCE(?1, url) END,
+                 title = COALESCE(?2, title),
+                 filename = COALESCE(?3, filename),
+                 favicon_path = CASE WHEN ?9 THEN NULL ELSE COALESCE(?4, favicon_path) END,
+                 meta_status = ?5,
+                 meta_fetched_at = CASE WHEN ?5 = 'ready' THEN ?6 ELSE meta_fetched_at END,
+                 meta_fetched_title = CASE WHEN ?5 = 'ready' THEN COALESCE(?2, meta_fetched_title) ELSE meta_fetched_title END,
+                 updated_at = ?6
+             WHERE id = ?7 AND type = 'bookmark'",
+            params![
+                normalized_url,
+                normalized_title,
+                normalized_filename,
+                normalized_favicon_path,
+                normalized_meta_status,
+                updated_at,
+                input.item_id,
+                input.clear_url,
+                input.clear_favicon_path
+            ],
+        )
+        .map_err(|err| format!("failed to update bookmark metadata: {}", err))?;
+
+    if affected_rows == 0 {
+        return Err("bookmark item not found while updating metadata".to_string());
+    }
+
+    Ok(updated_at)
+}
+
+pub(crate) fn record_bookmark_fetch_in_tx(
+    transaction: &Transaction<'_>,
+    item_id: &str,
+    fetched_title: Option<&str>,
+    fetched_at: i64,
+) -> Result<(), String> {
+    transaction
+        .execute(
+            "UPDATE items
+             SET meta_fetched_at = ?1,
+                 meta_fetched_title = COALESCE(?2, meta_fetched_title)
+             WHERE id = ?3",
+            params![fetched_at, fetched_title, item_id],
+        )
+        .map_err(|err| format!("failed to record bookmark fetch: {}", err))?;
+    Ok(())
+}
+
+pub(crate) async fn download_item_preview(
+    client: &reqwest::Client,
+    source_url: &Url,
+    etag: Option<&str>,
+    last_modified: Option<&str>,
+) -> Result<PreviewDownload, String> {
+    let _permit = acquire_fetch_host_permit(source_url).await?;
+    let mut request = client
+        .get(source_url.clone())
+        .header(ACCEPT, "image/*,*/*;q=0.8");
+    if let Some(etag) = etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|err| format!("preview request failed for {}: {}", source_url, err))?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(PreviewDownload::NotModified);
+    }
+    if !response.status().is_success() {
+        return Err(format!(
+            "preview request returned status {} for {}",
+            response.status(),
+            source_url
+        ));
+    }
+    if let Some(content_length) = response.content_length() {
+        if content_length as usize > ITEM_PREVIEW_MAX_BYTES {
+            return Err(format!(
+                "preview too large for {} ({} bytes > {} bytes)",
+                source_url, content_length, ITEM_PREVIEW_MAX_BYTES
+            ));
+        }
+    }
+
+    let header_value = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    let next_etag = header_value(ETAG);
+    let next_last_modified = header_value(LAST_MODIFIED);
+    let mut response = response;
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|err| format!("failed to read preview response {}: {}", source_url, err))?
+    {
+        if bytes.len() + chunk.len() > ITEM_PREVIEW_MAX_BYTES {
+            return Err(format!(
+                "preview download aborted after exceeding {} bytes for {}",
+                ITEM_PREVIEW_MAX_BYTES, source_url
+            ));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    let format = image::guess_format(&bytes)
+        .map_err(|_| format!("preview at {} is not a recognized image", source_url))?;
+    let ext = format
+        .extensions_str()
+        .first()
+        .copied()
+        .unwrap_or("img")
+        .to_string();
+    Ok(PreviewDownload::Fetched {
+        bytes,
+        ext,
+        etag: next_etag,
+        last_modified: next_last_modified,
+    })
+}
+
+pub(crate) fn load_item_preview_state(
+    connection: &Connection,
+    item_id: &str,
+) -> Result<ItemPreviewState, String> {
+    connection
+        .query_row(
+            "SELECT preview_url, preview_source_url, preview_etag, preview_last_modified
+             FROM items
+             WHERE id = ?1",
+            params![item_id],
+            |row| {
+                Ok(ItemPreviewState {
+                    preview_url: row.get(0)?,
+                    source_url: row.get(1)?,
+                    etag: row.get(2)?,
+                    last_modified: row.get(3)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|err| format!("failed to read item preview: {}", err))?
+        .ok_or_else(|| format!("item not found: {}", item_id))
+}
+
+/// `preview_url` is either NULL, a remote http(s) URL, or a local path. Caching downloads
+/// the remote image into `previews/` (content-addressed, shared between items), keeps the
+/// remote address in `preview_source_url` and rewrites `preview_url` to the local path.
+/// With `revalidate`, an already cached preview is re-requested conditionally on the stored
+/// ETag / Last-Modified and only replaced when the remote changed.
+pub(crate) async fn cache_item_preview_internal(
+    item_id: String,
+    revalidate: bool,
+) -> Result<ItemPreviewCacheResult, String> {
+    let item_id = item_id.trim().to_string();
+    initialize_db()?;
+    let state = {
+        let connection = open_db_connection()?;
+        load_item_preview_state(&connection, &item_id)?
+    };
+    let mut result = ItemPreviewCacheResult {
+        item_id: item_id.clone(),
+        preview_url: state.preview_url.clone(),
+        source_url: state.source_url.clone(),
+        downloaded: false,
+        not_modified: false,
+    };
+
+    let remote_preview = state
+        .preview_url
+        .as_deref()
+        .and_then(|value| Url::parse(value.trim()).ok())
+        .filter(is_http_or_https_url);
+    let cached_file_present = state
+        .preview_url
+        .as_deref()
+        .is_some_and(|value| is_cached_preview_path(value) && Path::new(value.trim()).is_file());
+    let source_url = match (remote_preview, state.source_url.as_deref()) {
+        (Some(remote), _) => remote,
+        (None, Some(source))
+            if state
+                .preview_url
+                .as_deref()
+                .is_some_and(is_cached_preview_path) =>
+        {
+            Url::parse(source)
+                .map_err(|err| format!("invalid stored preview source url: {}", err))?
+        }
+        // No preview, or a local file this app does not manage: nothing to cache.
+        _ => return Ok(result),
+    };
+    if cached_file_present && !revalidate {
+        return Ok(result);
+    }
+
+    let (etag, last_modified) = if cached_file_present {
+        (state.etag.as_deref(), state.last_modified.as_deref())
+    } else {
+        (None, None)
+    };
+    let client = build_bookmark_http_client()?;
+    let download = download_item_preview(&client, &source_url, etag, last_modified).await?;
+    let now = Utc::now().timestamp_millis();
+    let connection = open_db_connection()?;
+    let (bytes, ext, next_etag, next_last_modified) = match download {
+        PreviewDownload::NotModified => {
+            connection
+                .execute(
+                    "UPDATE items SET preview_cached_at = ?1 WHERE id = ?2",
+                    params![now, &item_id],
+                )
+                .map_err(|err| format!("failed to record preview revalidation: {}", err))?;
+            result.not_modified = true;
+            return Ok(result);
+        }
+        PreviewDownload::Fetched {
+            bytes,
+            ext,
+            etag,
+            last_modified,
+        } => (bytes, ext, etag, last_modified),
+    };
+
+    let stored_path = path_to_string(&store_preview_bytes(&bytes, &ext)?)?;
+    connection
+        .execute(
+            "UPDATE items
+             SET preview_url = ?1,
+                 preview_source_url = ?2,
+                 preview_etag = ?3,
+                 preview_last_modified = ?4,
+                 preview_cached_at = ?5
+             WHERE id = ?6",
+            params![
+                &stored_path,
+                source_url.as_str(),
+                next_etag,
+                next_last_modified,
+                now,
+                &item_id
+            ],
+        )
+        .map_err(|err| format!("failed to store cached preview: {}", err))?;
+
+    if let Some(previous) = state
+        .preview_url
+        .as_deref()
+        .filter(|previous| *previous != stored_path)
+    {
+        if is_cached_preview_path(previous) {
+            let remaining_refs: i64 = connection
+                .query_row(
+                    "SELECT COUNT(*) FROM items WHERE preview_url = ?1",
+                    params![previous],
+                    |row| row.get(0),
+                )
+                .map_err(|err| format!("failed to verify remaining preview refs: {}", err))?;
+            if remaining_refs == 0 {
+                if let Err(err) = remove_preview_file(previous) {
+                    log_eprintln!("failed to remove preview {}: {}", previous, err);
+                }
+            }
+        }
+    }
+
+    result.preview_url = Some(stored_path);
+    result.source_url = Some(source_url.to_string());
+    result.downloaded = true;
+    Ok(result)
+}
+
+#[tauri::command]
+pub(crate) async fn cache_item_preview(item_id: String) -> Result<ItemPreviewCacheResult, String> {
+    cache_item_preview_internal(item_id, false).await
+}
+
+#[tauri::command]
+pub(crate) async fn refresh_item_preview(
+    item_id: String,
+) -> Result<ItemPreviewCacheResult, String> {
+    cache_item_preview_internal(item_id, true).await
+}
+
+pub(crate) async fn download_first_favicon(
+    client: &reqwest::Client,
+    candidates: Vec<Url>,
+    cancel: Option<&BookmarkFetchCancel>,
+) -> Result<Option<(String, String, String)>, String> {
+    for candidate in candidates {
+        match download_favicon_candidate(client, &candidate).await {
+            Ok((bytes, ext)) => {
+                if let Some(cancel) = cancel {
+                    cancel.begin_commit()?;
+                }
+                match store_favicon_bytes(&bytes, &ext) {
+                    Ok(stored_path) => {
+                        return Ok(Some((
+                            path_to_string(&stored_path)?,
+                            ext,
+                            candidate.as_str().to_string(),
+                        )));
+                    }
+                    Err(error) => {
+                        log_eprintln!("failed to store favicon from {}: {}", candidate, error);
+                    }
+                }
+            }
+            Err(error) => {
+                log_eprintln!("favicon candidate failed {}: {}", candidate, error);
+            }
+        }
+    }
+    Ok(None)
+}
+
+pub(crate) async fn resolve_host_favicon(
+    client: &reqwest::Client,
+    url: &Url,
+) -> Result<String, String> {
+    let fallback = url
+        .join("/favicon.ico")
+        .map_err(|err| format!("failed to build favicon url for {}: {}", url, err))?;
+    if let Some((path, _, _)) = download_first_favicon(client, vec![fallback.clone()], None).await?
+    {
+        return Ok(path);
+    }
+
+    let (final_url, html_opt) = fetch_bookmark_page_html(client, url).await?;
+    let Some(html) = html_opt else {
+        return Err(format!("no favicon found for {}", final_url));
+    };
+    let (_, candidates) = html_title_and_favicon_candidates(&html, &final_url);
+    let candidates = candidates
+        .into_iter()
+        .filter(|candidate| candidate != &fallback)
+        .collect::<Vec<Url>>();
+    match download_first_favicon(client, candidates, None).await? {
+        Some((path, _, _)) => Ok(path),
+        None => Err(format!("no favicon found for {}", final_url)),
+    }
+}
+
+pub(crate) fn favicon_host_for_url(url: &Url) -> Option<String> {
+    url.host_str()
+        .map(|host| host.trim_start_matches("www.").to_ascii_lowercase())
+        .filter(|host| !host.is_empty())
+}
+
+pub(crate) fn apply_host_favicon(
+    connection: &mut Connection,
+    item_ids: &[String],
+    favicon_path: &str,
+) -> Result<usize, String> {
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    let updated_at = Utc::now().timestamp_millis();
+    let mut updated = 0_usize;
+    for item_id in item_ids {
+        updated += transaction
+            .execute(
+                "UPDATE items
+                 SET favicon_path = ?1,
+                     updated_at = ?2
+                 WHERE id = ?3 AND (favicon_path IS NULL OR TRIM(favicon_path) = '')",
+                params![favicon_path, updated_at, item_id],
+            )
+            .map_err(|err| format!("failed to update item favicon: {}", err))?;
+    }
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit favicon update transaction: {}", err))?;
+    Ok(updated)
+}
+
+#[tauri::command]
+pub(crate) async fn fetch_missing_favicons(
+    app: AppHandle,
+    limit: Option<u32>,
+) -> Result<FetchMissingFaviconsResult, String> {
+    let batch_limit = limit.unwrap_or(100).clamp(1, FAVICON_BATCH_MAX_LIMIT);
+    let now = Utc::now().timestamp_millis();
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+
+    let mut pending_by_host: BTreeMap<String, (Url, Vec<String>)> = BTreeMap::new();
+    let mut cached_by_host: HashMap<String, String> = HashMap::new();
+    let mut recently_failed_hosts: BTreeSet<String> = BTreeSet::new();
+    {
+        let mut stmt = connection
+            .prepare(
+                "SELECT id, url
+                 FROM items
+                 WHERE type = 'bookmark'
+                   AND url IS NOT NULL AND TRIM(url) <> ''
+                   AND (favicon_path IS NULL OR TRIM(favicon_path) = '')
+                 ORDER BY created_at DESC
+                 LIMIT ?1",
+            )
+            .map_err(|err| format!("failed to prepare missing favicons query: {}", err))?;
+        let rows = stmt
+            .query_map(params![batch_limit], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|err| format!("failed to query bookmarks missing favicons: {}", err))?;
+        for row_result in rows {
+            let (item_id, raw_url) = row_result
+                .map_err(|err| format!("failed to read bookmark missing favicon: {}", err))?;
+            let Ok(url) = Url::parse(&raw_url) else {
+                continue;
+            };
+            let Some(host) = favicon_host_for_url(&url) else {
+                continue;
+            };
+            pending_by_host
+                .entry(host)
+                .or_insert_with(|| (url, Vec::new()))
+                .1
+                .push(item_id);
+        }
+
+        let mut stmt = connection
+            .prepare(
+                "SELECT url, favicon_path
+                 FROM items
+                 WHERE type = 'bookmark'
+                   AND url IS NOT NULL
+                   AND favicon_path IS NOT NULL AND TRIM(favicon_path) <> ''
+                 ORDER BY updated_at DESC",
+            )
+            .map_err(|err| format!("failed to prepare cached favicons query: {}", err))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|err| format!("failed to query cached favicons: {}", err))?;
+        for row_result in rows {
+            let (raw_url, favicon_path) =
+                row_result.map_err(|err| format!("failed to read cached favicon: {}", err))?;
+            let Some(host) = Url::parse(&raw_url)
+                .ok()
+                .as_ref()
+                .and_then(favicon_host_for_url)
+            else {
+                continue;
+            };
+            if pending_by_host.contains_key(&host)
+                && !cached_by_host.contains_key(&host)
+                && Path::new(&favicon_path).is_file()
+            {
+                cached_by_host.insert(host, favicon_path);
+            }
+        }
+
+        let mut stmt = connection
+            .prepare("SELECT host FROM favicon_fetch_failures WHERE failed_at > ?1")
+            .map_err(|err| format!("failed to prepare favicon failures query: {}", err))?;
+        let rows = stmt
+            .query_map(params![now - FAVICON_HOST_RETRY_AFTER_MS], |row| {
+                row.get::<_, String>(0)
+            })
+            .map_err(|err| format!("failed to query favicon failures: {}", err))?;
+        for row_result in rows {
+            recently_failed_hosts.insert(
+                row_result.map_err(|err| format!("failed to read favicon failure: {}", err))?,
+            );
+        }
+    }
+
+    let total = pending_by_host
+        .values()
+        .map(|(_, item_ids)| item_ids.len())
+        .sum();
+    let mut result = FetchMissingFaviconsResult {
+        processed: 0,
+        updated: 0,
+        reused_cached: 0,
+        failed: 0,
+        skipped_hosts: Vec::new(),
+    };
+    let emit_progress =
+        |result: &mut FetchMissingFaviconsResult, item_ids: &[String], host: &str, status: &str| {
+            for item_id in item_ids {
+                result.processed += 1;
+                let _ = app.emit(
+                    FAVICON_PROGRESS_EVENT,
+                    FaviconProgressPayload {
+                        processed: result.processed,
+                        total,
+                        item_id: item_id.clone(),
+                        host: host.to_string(),
+                        status: status.to_string(),
+                    },
+                );
+            }
+        };
+
+    let mut fetch_queue: Vec<(String, Url, Vec<String>)> = Vec::new();
+    for (host, (url, item_ids)) in pending_by_host {
+        if let Some(favicon_path) = cached_by_host.get(&host) {
+            let updated = apply_host_favicon(&mut connection, &item_ids, favicon_path)?;
+            result.updated += updated;
+            result.reused_cached += updated;
+            emit_progress(&mut result, &item_ids, &host, "cached");
+        } else if recently_failed_hosts.contains(&host) {
+            emit_progress(&mut result, &item_ids, &host, "skipped");
+            result.skipped_hosts.push(host);
+        } else {
+            fetch_queue.push((host, url, item_ids));
+        }
+    }
+
+    let client = build_bookmark_http_client()?;
+    while !fetch_queue.is_empty() {
+        let wave_size = fetch_queue.len().min(FAVICON_FETCH_CONCURRENCY);
+        let wave = fetch_queue.drain(..wave_size).collect::<Vec<_>>();
+        let mut handles = Vec::new();
+        for (host, url, item_ids) in wave {
+            let client = client.clone();
+            handles.push(tauri::async_runtime::spawn(async move {
+                let outcome = resolve_host_favicon(&client, &url).await;
+                (host, item_ids, outcome)
+            }));
+        }
+
+        for handle in handles {
+            let (host, item_ids, outcome) = handle
+                .await
+                .map_err(|err| format!("favicon fetch task join failed: {}", err))?;
+            let failed_at = Utc::now().timestamp_millis();
+            match outcome {
+                Ok(favicon_path) => {
+                    result.updated +=
+                        apply_host_favicon(&mut connection, &item_ids, &favicon_path)?;
+                    connection
+                        .execute(
+                            "DELETE FROM favicon_fetch_failures WHERE host = ?1",
+                            params![&host],
+                        )
+                        .map_err(|err| format!("failed to clear favicon failure: {}", err))?;
+                    emit_progress(&mut result, &item_ids, &host, "ready");
+                }
+                Err(error) => {
+                    log_eprintln!("[favicons] failed to fetch favicon for {}: {}", host, error);
+                    connection
+                        .execute(
+                            "INSERT INTO favicon_fetch_failures (host, last_error, failed_at)
+                             VALUES (?1, ?2, ?3)
+                             ON CONFLICT(host) DO UPDATE SET
+                               last_error = excluded.last_error,
+                               failed_at = excluded.failed_at",
+                            params![&host, error, failed_at],
+                        )
+                        .map_err(|err| format!("failed to record favicon failure: {}", err))?;
+                    result.failed += item_ids.len();
+                    emit_progress(&mut result, &item_ids, &host, "failed");
+                }
+            }
+        }
+    }
+
+    log_println!(
+        "[favicons] processed={} updated={} reused_cached={} failed={} skipped_hosts={}",
+        result.processed,
+        result.updated,
+        result.reused_cached,
+        result.failed,
+        result.skipped_hosts.len()
+    );
+    Ok(result)
+}
+
+pub(crate) fn favicons_directory_size(root: &Path) -> Result<u64, String> {
+    let entries = read_directory(root)
+        .map_err(|err| format!("failed to read favicons root {}: {}", root.display(), err))?;
+    let mut total = 0_u64;
+    for entry_result in entries {
+        let entry = entry_result.map_err(|err| format!("failed to read favicon entry: {}", err))?;
+        if let Ok(metadata) = entry.metadata() {
+            if metadata.is_file() {
+                total += metadata.len();
+            }
+        }
+    }
+    Ok(total)
+}
+
+pub(crate) fn normalize_favicon_file(path: &Path) -> Result<Option<PathBuf>, String> {
+    let bytes = fs::read(path)
+        .map_err(|err| format!("failed to read favicon {}: {}", path.display(), err))?;
+    if looks_like_svg(&bytes) {
+        return Ok(None);
+    }
+    let source_image = match ImageReader::new(std::io::Cursor::new(&bytes))
+        .with_guessed_format()
+        .map_err(|err| {
+            format!(
+                "failed to detect favicon format {}: {}",
+                path.display(),
+                err
+            )
+        })?
+        .decode()
+    {
+        Ok(image) => image,
+        Err(err) => {
+            log_eprintln!("[favicons] cannot decode {}: {}", path.display(), err);
+            return Ok(None);
+        }
+    };
+    let (width, height) = source_image.dimensions();
+    if width == 0 || height == 0 {
+        return Ok(None);
+    }
+    let is_png = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(|ext| ext.eq_ignore_ascii_case("png"))
+        .unwrap_or(false);
+    if is_png && width.max(height) <= FAVICON_NORMALIZED_SIZE {
+        return Ok(Some(path.to_path_buf()));
+    }
+
+    let resized_image = if width.max(height) > FAVICON_NORMALIZED_SIZE {
+        source_image.resize(
+            FAVICON_NORMALIZED_SIZE,
+            FAVICON_NORMALIZED_SIZE,
+            FilterType::Triangle,
+        )
+    } else {
+        source_image
+    };
+    let mut encoded = Vec::new();
+    resized_image
+        .write_to(
+            &mut std::io::Cursor::new(&mut encoded),
+            image::ImageFormat::Png,
+        )
+        .map_err(|err| format!("failed to encode favicon {}: {}", path.display(), err))?;
+    store_favicon_bytes(&encoded, "png").map(Some)
+}
+
+#[tauri::command]
+pub(crate) async fn reprocess_favicons() -> Result<ReprocessFaviconsResult, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        initialize_db()?;
+        let mut connection = open_db_connection()?;
+        let root = ensure_favicons_root_internal()?;
+        let bytes_before = favicons_directory_size(&root)?;
+
+        let entries = read_directory(&root)
+            .map_err(|err| format!("failed to read favicons root {}: {}", root.display(), err))?;
+        let mut favicon_files = Vec::new();
+        for entry_result in entries {
+            let entry =
+                entry_result.map_err(|err| format!("failed to read favicon entry: {}", err))?;
+            let path = entry.path();
+            if path.is_file() {
+                favicon_files.push(path);
+            }
+        }
+        favicon_files.sort();
+
+        let mut result = ReprocessFaviconsResult {
+            scanned: favicon_files.len(),
+            normalized: 0,
+            already_normalized: 0,
+            skipped: 0,
+            updated_items: 0,
+            removed_files: 0,
+            bytes_before,
+            bytes_after: bytes_before,
+            bytes_saved: 0,
+        };
+        for original_path in favicon_files {
+            let normalized_path = match normalize_favicon_file(&original_path) {
+                Ok(Some(path)) => path,
+                Ok(None) => {
+                    result.skipped += 1;
+                    continue;
+                }
+                Err(err) => {
+                    log_eprintln!("[favicons] failed to normalize {}: {}", original_path.display(), err);
+                    result.skipped += 1;
+                    continue;
+                }
+            };
+            if normalized_path == original_path {
+                result.already_normalized += 1;
+                continue;
+            }
+
+            let original = path_to_string(&original_path)?;
+            let normalized = path_to_string(&normalized_path)?;
+            let transaction = connection
+                .transaction()
+                .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+            result.updated_items += transaction
+                .execute(
+                    "UPDATE items SET favicon_path = ?1 WHERE favicon_path = ?2",
+                    params![&normalized, &original],
+                )
+                .map_err(|err| format!("failed to rewrite favicon references: {}", err))?;
+            let remaining_refs: i64 = transaction
+                .query_row(
+                    "SELECT COUNT(*) FROM items WHERE favicon_path = ?1",
+                    params![&original],
+                    |row| row.get(0),
+                )
+                .map_err(|err| format!("failed to verify remaining favicon refs: {}", err))?;
+            transaction
+                .commit()
+                .map_err(|err| format!("failed to commit favicon reference update: {}", err))?;
+            result.normalized += 1;
+
+            if remaining_refs == 0 {
+                match remove_favicon_file(&original) {
+                    Ok(true) => result.removed_files += 1,
+                    Ok(false) => {}
+                    Err(err) => log_eprintln!("[favicons] {}", err),
+                }
+            }
+        }
+
+        result.bytes_after = favicons_directory_size(&root)?;
+        result.bytes_saved = result.bytes_before.saturating_sub(result.bytes_after);
+        log_println!(
+            "[favicons] reprocessed scanned={} normalized={} already_normalized={} skipped={} updated_items={} removed_files={} bytes_saved={}",
+            result.scanned,
+            result.normalized,
+            result.already_normalized,
+            result.skipped,
+            result.updated_items,
+            result.removed_files,
+            result.bytes_saved
+        );
+        Ok(result)
+    })
+    .await
+    .map_err(|err| format!("reprocess favicons thread join failed: {}", err))?
+}
+
+pub(crate) async fn fetch_bookmark_metadata_internal(
+    normalized_url: Url,
+    cancel: Option<&BookmarkFetchCancel>,
+) -> Result<FetchBookmarkMetadataResult, String> {
+    let client = build_bookmark_http_client()?;
+
+    let (final_url, html_opt) = match fetch_bookmark_page_html(&client, &normalized_url).await {
+        Ok((final_url, html_opt)) => (final_url, html_opt),
+        Err(error) => {
+            log_eprintln!(
+                "bookmark html fetch failed for {}: {}. Falling back to favicon-only resolution.",
+                normalized_url,
+                error
+            );
+            (normalized_url.clone(), None)
+        }
+    };
+
+    let (title, favicon_candidates, html_parse_ms, html_parse_timed_out) = match html_opt {
+        Some(html) => {
+            let (title, candidates, parse_ms, timed_out) =
+                parse_bookmark_html_with_budget(html, final_url.clone()).await?;
+            (title, candidates, Some(parse_ms), timed_out)
+        }
+        None => (None, default_favicon_candidates(&final_url), None, false),
+    };
+
+    let (favicon_path, favicon_ext, favicon_url_candidate) =
+        match download_first_favicon(&client, favicon_candidates, cancel).await? {
+            Some((path, ext, candidate)) => (Some(path), Some(ext), Some(candidate)),
+            None => (None, None, None),
+        };
+
+    Ok(FetchBookmarkMetadataResult {
+        final_url: final_url.as_str().to_string(),
+        title,
+        favicon_path,
+        favicon_ext,
+        favicon_url_candidate,
+        html_parse_ms,
+        html_parse_timed_out,
+    })
+}
+
+pub(crate) async fn run_cancellable_bookmark_fetch<T, F>(
+    state: &BookmarkFetchState,
+    request_id: Option<String>,
+    task: impl FnOnce(BookmarkFetchCancel) -> F,
+) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: std::future::Future<Output = Result<T, String>> + Send + 'static,
+{
+    let request_id = request_id.and_then(|value| normalize_trimmed_id(&value));
+    let cancel = BookmarkFetchCancel::default();
+    let handle = tauri::async_runtime::spawn(task(cancel.clone()));
+    if let Some(request_id) = request_id.as_ref() {
+        let abort_handle = handle.inner().abort_handle();
+        state
+            .in_flight
+            .lock()
+            .map_err(|_| "bookmark fetch state lock poisoned".to_string())?
+            .insert(
+                request_id.clone(),
+                BookmarkFetchHandle {
+                    cancel: cancel.clone(),
+                    abort: Box::new(move || abort_handle.abort()),
+                },
+            );
+    }
+
+    let outcome = handle.await;
+    if let Some(request_id) = request_id.as_ref() {
+        state
+            .in_flight
+            .lock()
+            .map_err(|_| "bookmark fetch state lock poisoned".to_string())?
+            .remove(request_id);
+    }
+    if cancel.is_cancelled() {
+        return Err("bookmark fetch cancelled".to_string());
+    }
+    outcome.map_err(|err| format!("bookmark fetch task failed: {}", err))?
+}
+
+/// Returns false when the fetch is unknown or already past its first write; that fetch
+/// completes normally and its result is delivered to the caller.
+pub(crate) fn cancel_bookmark_fetch_internal(
+    state: &BookmarkFetchState,
+    request_id: &str,
+) -> Result<bool, String> {
+    let handle = state
+        .in_flight
+        .lock()
+        .map_err(|_| "bookmark fetch state lock poisoned".to_string())?
+        .remove(request_id.trim());
+    match handle {
+        Some(handle) if handle.cancel.cancel() => {
+            (handle.abort)();
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn fetch_bookmark_metadata(
+    state: State<'_, BookmarkFetchState>,
+    url: String,
+    request_id: Option<String>,
+) -> Result<FetchBookmarkMetadataResult, String> {
+    let normalized_url = normalize_bookmark_url_input(&url)?;
+    run_cancellable_bookmark_fetch(&state, request_id, |cancel| async move {
+        fetch_bookmark_metadata_internal(normalized_url, Some(&cancel)).await
+    })
+    .await
+}
+
+#[tauri::command]
+pub(crate) fn cancel_bookmark_fetch(
+    state: State<'_, BookmarkFetchState>,
+    request_id: String,
+) -> Result<bool, String> {
+    cancel_bookmark_fetch_internal(&state, &request_id)
+}
+
+pub(crate) async fn create_bookmark_item_internal(
+    url: Url,
+    tags: Vec<String>,
+    collection_id: Option<String>,
+    cancel: Option<&BookmarkFetchCancel>,
+) -> Result<CreateBookmarkItemResult, String> {
+    let (final_url, fetched_title, favicon_path, meta_status) =
+        match fetch_bookmark_metadata_internal(url.clone(), cancel).await {
+            Ok(metadata) => {
+                let final_url = Url::parse(&metadata.final_url).unwrap_or_else(|_| url.clone());
+                (final_url, metadata.title, metadata.favicon_path, "ready")
+            }
+            Err(error) => {
+                log_eprintln!("bookmark metadata fetch failed for {}: {}", url, error);
+                (url.clone(), None, None, "error")
+            }
+        };
+
+    let hostname = final_url
+        .host_str()
+        .map(|host| host.trim_start_matches("www.").to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "bookmark".to_string());
+    let item_id = Uuid::new_v4().to_string();
+    let now = Utc::now().timestamp_millis();
+    if let Some(cancel) = cancel {
+        cancel.begin_commit()?;
+    }
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    if let Some(target_id) = collection_id.as_deref() {
+        validate_collection_exists_in_tx(&transaction, target_id)?;
+    }
+
+    insert_item_in_tx(
+        &transaction,
+        InsertItemInput {
+            id: item_id.clone(),
+            collection_id: collection_id.clone(),
+            item_type: "bookmark".to_string(),
+            title: fetched_title.clone().unwrap_or_else(|| hostname.clone()),
+            filename: Some(hostname),
+            vault_key: None,
+            vault_path: None,
+            preview_url: None,
+            width: None,
+            height: None,
+            thumb_status: "ready".to_string(),
+            import_status: "ready".to_string(),
+            url: Some(final_url.as_str().to_string()),
+            favicon_path,
+            meta_status: Some(meta_status.to_string()),
+            description: None,
+            description_format: None,
+            rating: 0,
+            is_favorite: false,
+            created_at: now,
+            updated_at: now,
+            tags,
+            original_source_path: None,
+            source: None,
+        },
+    )?;
+
+    if meta_status == "ready" {
+        record_bookmark_fetch_in_tx(&transaction, &item_id, fetched_title.as_deref(), now)?;
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit create bookmark transaction: {}", err))?;
+
+    Ok(CreateBookmarkItemResult {
+        item_id,
+        url: final_url.as_str().to_string(),
+        collection_id,
+        meta_status: meta_status.to_string(),
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn create_bookmark_item(
+    state: State<'_, BookmarkFetchState>,
+    url: String,
+    tags: Option<Vec<String>>,
+    collection_id: Option<String>,
+    request_id: Option<String>,
+) -> Result<CreateBookmarkItemResult, String> {
+    let normalized_url = normalize_bookmark_url_input(&url)?;
+    let tags = tags.unwrap_or_default();
+    let collection_id = normalize_optional_trimmed_id(collection_id);
+    run_cancellable_bookmark_fetch(&state, request_id, |cancel| async move {
+        create_bookmark_item_internal(normalized_url, tags, collection_id, Some(&cancel)).await
+    })
+    .await
+}
+
+pub(crate) fn load_bookmark_refresh_settings(
+    connection: &Connection,
+) -> Result<BookmarkRefreshSettings, String> {
+    let refresh_days = read_app_setting(connection, SETTING_BOOKMARK_REFRESH_DAYS)?
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .unwrap_or(DEFAULT_BOOKMARK_REFRESH_DAYS);
+    let per_hour = read_app_setting(connection, SETTING_BOOKMARK_REFRESH_PER_HOUR)?
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_BOOKMARK_REFRESH_PER_HOUR)
+        .min(BOOKMARK_REFRESH_MAX_PER_HOUR);
+    Ok(BookmarkRefreshSettings {
+        refresh_days,
+        per_hour,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn get_bookmark_refresh_settings() -> Result<BookmarkRefreshSettings, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    load_bookmark_refresh_settings(&connection)
+}
+
+#[tauri::command]
+pub(crate) fn set_bookmark_refresh_settings(
+    refresh_days: u32,
+    per_hour: u32,
+) -> Result<BookmarkRefreshSettings, String> {
+    if per_hour == 0 {
+        return Err("bookmark refresh rate must be at least 1 per hour".to_string());
+    }
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    write_app_setting(
+        &connection,
+        SETTING_BOOKMARK_REFRESH_DAYS,
+        &refresh_days.to_string(),
+    )?;
+    write_app_setting(
+        &connection,
+        SETTING_BOOKMARK_REFRESH_PER_HOUR,
+        &per_hour.min(BOOKMARK_REFRESH_MAX_PER_HOUR).to_string(),
+    )?;
+    load_bookmark_refresh_settings(&connection)
+}
+
+pub(crate) fn bookmark_refresh_stale_before(settings: &BookmarkRefreshSettings, now: i64) -> i64 {
+    now - i64::from(settings.refresh_days) * 24 * 60 * 60 * 1000
+}
+
+pub(crate) const BOOKMARK_REFRESH_STALE_CONDITION: &str = "type = 'bookmark'
+       AND is_archived = 0
+       AND url IS NOT NULL AND TRIM(url) <> ''
+       AND COALESCE(meta_fetched_at, created_at) < ?1";
+
+pub(crate) fn bookmark_refresh_status(
+    connection: &Connection,
+) -> Result<BookmarkRefreshStatus, String> {
+    let settings = load_bookmark_refresh_settings(connection)?;
+    let now = Utc::now().timestamp_millis();
+    let stale_count = if settings.refresh_days == 0 {
+        0
+    } else {
+        connection
+            .query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM items WHERE {}",
+                    BOOKMARK_REFRESH_STALE_CONDITION
+                ),
+                params![bookmark_refresh_stale_before(&settings, now)],
+                |row| row.get::<_, i64>(0),
+            )
+            .map_err(|err| format!("failed to count stale bookmarks: {}", err))?
+    };
+    let state = BOOKMARK_REFRESH_STATE
+        .lock()
+        .map_err(|_| "bookmark refresh state lock poisoned".to_string())?;
+    Ok(BookmarkRefreshStatus {
+        enabled: settings.refresh_days > 0,
+        paused: state.paused,
+        running: state.running,
+        refresh_days: settings.refresh_days,
+        per_hour: settings.per_hour,
+        stale_count,
+        last_run_at: state.last_run_at,
+        last_checked: state.last_checked,
+        last_updated: state.last_updated,
+        last_failed: state.last_failed,
+        backed_off_hosts: state
+            .host_backoff
+            .iter()
+            .filter(|(_, backoff)| backoff.retry_at > now)
+            .map(|(host, _)| host.clone())
+            .collect(),
+    })
+}
+
+pub(crate) fn set_bookmark_refresh_paused(paused: bool) -> Result<BookmarkRefreshStatus, String> {
+    BOOKMARK_REFRESH_STATE
+        .lock()
+        .map_err(|_| "bookmark refresh state lock poisoned".to_string())?
+        .paused = paused;
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    bookmark_refresh_status(&connection)
+}
+
+#[tauri::command]
+pub(crate) fn pause_background_refresh() -> Result<BookmarkRefreshStatus, String> {
+    set_bookmark_refresh_paused(true)
+}
+
+#[tauri::command]
+pub(crate) fn resume_background_refresh() -> Result<BookmarkRefreshStatus, String> {
+    set_bookmark_refresh_paused(false)
+}
+
+pub(crate) fn bookmark_refresh_paused() -> bool {
+    BOOKMARK_REFRESH_STATE
+        .lock()
+        .map(|state| state.paused)
+        .unwrap_or(true)
+}
+
+pub(crate) fn bookmark_refresh_host_ready(host: &str, now: i64) -> bool {
+    BOOKMARK_REFRESH_STATE
+        .lock()
+        .map(|state| {
+            state
+                .host_backoff
+                .get(host)
+                .is_none_or(|backoff| backoff.retry_at <= now)
+        })
+        .unwrap_or(false)
+}
+
+pub(crate) fn record_bookmark_refresh_host_result(host: &str, succeeded: bool, now: i64) {
+    let Ok(mut state) = BOOKMARK_REFRESH_STATE.lock() else {
+        return;
+    };
+    if succeeded {
+        state.host_backoff.remove(host);
+        return;
+    }
+    let backoff =
+        state
+            .host_backoff
+            .entry(host.to_string())
+            .or_insert(BookmarkRefreshHostBackoff {
+                failures: 0,
+                retry_at: now,
+            });
+    backoff.failures = backoff.failures.saturating_add(1);
+    let delay = BOOKMARK_REFRESH_BACKOFF_BASE_MS
+        .saturating_mul(1_i64 << backoff.failures.saturating_sub(1).min(20))
+        .min(BOOKMARK_REFRESH_BACKOFF_MAX_MS);
+    backoff.retry_at = now + delay;
+}
+
+pub(crate) fn load_bookmark_refresh_candidates(
+    connection: &Connection,
+    stale_before: i64,
+    limit: u32,
+) -> Result<Vec<BookmarkRefreshCandidate>, String> {
+    let mut stmt = connection
+        .prepare(&format!(
+            "SELECT id, url, title, filename, favicon_path, meta_fetched_title
+             FROM items
+             WHERE {}
+             ORDER BY COALESCE(meta_fetched_at, created_at) ASC, id ASC
+             LIMIT ?2",
+            BOOKMARK_REFRESH_STALE_CONDITION
+        ))
+        .map_err(|err| format!("failed to prepare stale bookmark query: {}", err))?;
+    let rows = stmt
+        .query_map(params![stale_before, limit], |row| {
+            Ok(BookmarkRefreshCandidate {
+                item_id: row.get(0)?,
+                url: row.get(1)?,
+                title: row.get(2)?,
+                filename: row.get(3)?,
+                favicon_path: row.get(4)?,
+                meta_fetched_title: row.get(5)?,
+            })
+        })
+        .map_err(|err| format!("failed to query stale bookmarks: {}", err))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to read stale bookmark row: {}", err))
+}
+
+/// Re-fetches one bookmark page. A page that cannot be fetched at all is a hard failure
+/// (and backs the host off); a page without a usable title or favicon is not.
+pub(crate) async fn fetch_bookmark_refresh_metadata(
+    client: &reqwest::Client,
+    url: &Url,
+) -> Result<(Option<String>, Option<String>), String> {
+    let (final_url, html_opt) = fetch_bookmark_page_html(client, url).await?;
+    let (title, favicon_candidates) = match html_opt {
+        Some(html) => {
+            let (title, candidates, _, _) =
+                parse_bookmark_html_with_budget(html, final_url.clone()).await?;
+            (title, candidates)
+        }
+        None => (None, default_favicon_candidates(&final_url)),
+    };
+    let favicon_path = download_first_favicon(client, favicon_candidates, None)
+        .await?
+        .map(|(path, _, _)| path);
+    Ok((title, favicon_path))
+}
+
+/// Writes a refresh result, touching `updated_at` only when the title or favicon really
+/// changed. Titles are only replaced while the item still shows the previously fetched title
+/// (or the host fallback), so user renames are never overwritten.
+pub(crate) fn apply_bookmark_refresh(
+    connection: &Connection,
+    candidate: &BookmarkRefreshCandidate,
+    fetched_title: Option<String>,
+    favicon_path: Option<String>,
+    fetched_at: i64,
+) -> Result<bool, String> {
+    let fetched_title = normalize_optional_trimmed_string(fetched_title);
+    let title_is_fetched = candidate.meta_fetched_title.as_deref()
+        == Some(candidate.title.as_str())
+        || candidate.title == candidate.filename;
+    let next_title = fetched_title
+        .as_deref()
+        .filter(|title| title_is_fetched && *title != candidate.title);
+    let next_favicon_path = favicon_path
+        .as_deref()
+        .filter(|path| candidate.favicon_path.as_deref() != Some(*path));
+    let changed = next_title.is_some() || next_favicon_path.is_some();
+    connection
+        .execute(
+            "UPDATE items
+             SET title = COALESCE(?1, title),
+                 favicon_path = COALESCE(?2, favicon_path),
+                 meta_status = 'ready',
+                 meta_fetched_at = ?3,
+                 meta_fetched_title = COALESCE(?4, meta_fetched_title),
+                 updated_at = CASE WHEN ?5 THEN ?3 ELSE updated_at END
+             WHERE id = ?6 AND type = 'bookmark'",
+            params![
+                next_title,
+                next_favicon_path,
+                fetched_at,
+                fetched_title,
+                changed,
+                candidate.item_id
+            ],
+        )
+        .map_err(|err| format!("failed to apply bookmark refresh: {}", err))?;
+    Ok(changed)
+}
+
+pub(crate) async fn run_bookmark_refresh_batch() -> Result<(usize, usize, usize), String> {
+    let (settings, candidates) = {
+        initialize_db()?;
+        let connection = open_db_connection()?;
+        let settings = load_bookmark_refresh_settings(&connection)?;
+        if settings.refresh_days == 0 {
+            return Ok((0, 0, 0));
+        }
+        let stale_before = bookmark_refresh_stale_before(&settings, Utc::now().timestamp_millis());
+        // Over-fetch so hosts that are backing off do not starve the hourly budget.
+        let candidates = load_bookmark_refresh_candidates(
+            &connection,
+            stale_before,
+            settings.per_hour.saturating_mul(4),
+        )?;
+        (settings, candidates)
+    };
+    let client = build_bookmark_http_client()?;
+    let (mut checked, mut updated, mut failed) = (0_usize, 0_usize, 0_usize);
+    for candidate in candidates {
+        if checked >= settings.per_hour as usize || bookmark_refresh_paused() {
+            break;
+        }
+        let Ok(url) = Url::parse(&candidate.url) else {
+            continue;
+        };
+        let Some(host) = favicon_host_for_url(&url) else {
+            continue;
+        };
+        if !bookmark_refresh_host_ready(&host, Utc::now().timestamp_millis()) {
+            continue;
+        }
+        checked += 1;
+        let outcome = fetch_bookmark_refresh_metadata(&client, &url).await;
+        let now = Utc::now().timestamp_millis();
+        record_bookmark_refresh_host_result(&host, outcome.is_ok(), now);
+        match outcome.and_then(|(title, favicon_path)| {
+            let connection = open_db_connection()?;
+            apply_bookmark_refresh(&connection, &candidate, title, favicon_path, now)
+        }) {
+            Ok(true) => updated += 1,
+            Ok(false) => {}
+            Err(err) => {
+                failed += 1;
+                log_eprintln!("[bookmark-refresh] {} failed: {}", candidate.url, err);
+            }
+        }
+    }
+    Ok((checked, updated, failed))
+}
+
+pub(crate) fn start_bookmark_refresh_scheduler() {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(BOOKMARK_REFRESH_STARTUP_DELAY_SECS)).await;
+        loop {
+            let idle = DB_ACTIVITY
+                .lock()
+                .map(|activity| activity.imports_in_flight == 0 && !activity.maintenance_running)
+                .unwrap_or(false);
+            if idle && !bookmark_refresh_paused() {
+                if let Ok(mut state) = BOOKMARK_REFRESH_STATE.lock() {
+                    state.running = true;
+                }
+                let outcome = run_bookmark_refresh_batch().await;
+                if let Ok(mut state) = BOOKMARK_REFRESH_STATE.lock() {
+                    state.running = false;
+                    state.last_run_at = Some(Utc::now().timestamp_millis());
+                    let (checked, updated, failed) = outcome.as_ref().copied().unwrap_or_default();
+                    state.last_checked = checked;
+                    state.last_updated = updated;
+                    state.last_failed = failed;
+                }
+                match outcome {
+                    Ok((checked, updated, failed)) if checked > 0 => log_println!(
+                        "[bookmark-refresh] checked {} bookmark(s), {} updated, {} failed",
+                        checked,
+                        updated,
+                        failed
+                    ),
+                    Ok(_) => {}
+                    Err(err) => log_eprintln!("[bookmark-refresh] batch failed: {}", err),
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(BOOKMARK_REFRESH_POLL_SECS)).await;
+        }
+    });
+}
+
+pub(crate) fn parse_deep_link_add_request(raw_link: &str) -> Result<DeepLinkAddRequest, String> {
+    let link = Url::parse(raw_link.trim()).map_err(|err| format!("invalid deep link: {}", err))?;
+    if link.scheme() != DEEP_LINK_SCHEME {
+        return Err(format!("unsupported deep link scheme: {}", link.scheme()));
+    }
+
+    let action = match link.host_str() {
+        Some(host) if !host.is_empty() => host.to_ascii_lowercase(),
+        _ => link.path().trim_matches('/').to_ascii_lowercase(),
+    };
+    if action != "add" {
+        return Err(format!("unsupported deep link action: {}", action));
+    }
+
+    let mut raw_url: Option<String> = None;
+    let mut tags = Vec::new();
+    let mut seen_tags = BTreeSet::new();
+    let mut collection_id: Option<String> = None;
+    for (key, value) in link.query_pairs() {
+        match key.as_ref() {
+            "url" => raw_url = Some(value.into_owned()),
+            "tags" => {
+                for raw_tag in value.split(',') {
+                    if let Ok(tag_name) = normalize_tag_name(raw_tag) {
+                        if seen_tags.insert(tag_name.clone()) {
+                            tags.push(tag_name);
+                        }
+                    }
+                }
+            }
+            "collection" => collection_id = normalize_trimmed_id(&value),
+            _ => {}
+        }
+    }
+
+    let raw_url = raw_url.ok_or_else(|| "deep link is missing the url parameter".to_string())?;
+    let url = normalize_bookmark_url_input(&raw_url)?;
+    Ok(DeepLinkAddRequest {
+        url,
+        tags,
+        collection_id,
+    })
+}
+
+pub(crate) fn handle_deep_link_urls(app: &AppHandle, links: Vec<String>) {
+    if links.is_empty() {
+        return;
+    }
+    focus_main_window(app);
+
+    for link in links {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let outcome = match parse_deep_link_add_request(&link) {
+                Ok(request) => {
+                    create_bookmark_item_internal(
+                        request.url,
+                        request.tags,
+                        request.collection_id,
+                        None,
+                    )
+                    .await
+                }
+                Err(error) => Err(error),
+            };
+
+            let emitted = match outcome {
+                Ok(created) => app.emit(DEEP_LINK_ITEM_ADDED_EVENT, created),
+                Err(message) => {
+                    log_eprintln!("[deep-link] failed to handle {}: {}", link, message);
+                    app.emit(
+                        DEEP_LINK_ERROR_EVENT,
+                        DeepLinkErrorPayload {
+                            link: link.clone(),
+                            message,
+                        },
+                    )
+                }
+            };
+            if let Err(err) = emitted {
+                log_eprintln!("[deep-link] failed to emit event for {}: {}", link, err);
+            }
+        });
+    }
+}
+
+pub(crate) fn parse_url_shortcut(contents: &str) -> Option<String> {
+    let mut in_shortcut_section = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') && line.ends_with(']') {
+            in_shortcut_section = line.eq_ignore_ascii_case("[InternetShortcut]");
+            continue;
+        }
+        if !in_shortcut_section {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            if key.trim().eq_ignore_ascii_case("URL") && !value.trim().is_empty() {
+                return Some(value.trim().to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Length of the binary plist ASCII string object whose payload starts at `start`, read
+/// from the marker bytes in front of it (`0x5N`, or `0x5F` followed by an int object).
+pub(crate) fn bplist_ascii_string_len(bytes: &[u8], start: usize) -> Option<usize> {
+    let marker = |offset: usize| start.checked_sub(offset).map(|index| bytes[index]);
+    let last = marker(1)?;
+    if (0x50..=0x5E).contains(&last) {
+        return Some(usize::from(last & 0x0F));
+    }
+    if marker(3) == Some(0x5F) && marker(2) == Some(0x10) {
+        return marker(1).map(usize::from);
+    }
+    if marker(4) == Some(0x5F) && marker(3) == Some(0x11) {
+        return Some(usize::from(u16::from_be_bytes([marker(2)?, marker(1)?])));
+    }
+    None
+}
+
+pub(crate) fn parse_webloc(bytes: &[u8]) -> Option<String> {
+    if bytes.starts_with(b"bplist") {
+        let start = bytes
+            .windows(7)
+            .position(|window| window == b"http://" || window.starts_with(b"https:"))?;
+        let payload = match bplist_ascii_string_len(bytes, start) {
+            Some(len) => bytes.get(start..start + len)?,
+            None => &bytes[start..],
+        };
+        let url: String = payload
+            .iter()
+            .take_while(|byte| byte.is_ascii_graphic())
+            .map(|byte| *byte as char)
+            .collect();
+        return Some(url).filter(|url| !url.is_empty());
+    }
+
+    let contents = String::from_utf8_lossy(bytes);
+    let key_end = contents.find("<key>URL</key>")? + "<key>URL</key>".len();
+    let rest = &contents[key_end..];
+    let value_start = rest.find("<string>")? + "<string>".len();
+    let value_end = rest[value_start..].find("</string>")? + value_start;
+    let url = rest[value_start..value_end]
+        .trim()
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'");
+    Some(url).filter(|url| !url.is_empty())
+}
+
+pub(crate) fn read_dropped_shortcut_url(path: &Path, ext: &str) -> Result<Url, String> {
+    let bytes = fs::read(path)
+        .map_err(|err| format!("failed to read shortcut {}: {}", path.display(), err))?;
+    let url = match ext {
+        "url" => parse_url_shortcut(&String::from_utf8_lossy(&bytes)),
+        _ => parse_webloc(&bytes),
+    }
+    .ok_or_else(|| format!("no URL found in shortcut {}", path.display()))?;
+    normalize_bookmark_url_input(&url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use std::sync::atomic::AtomicBool;
+
+    fn serve_bookmark_page() -> Url {
+        let html = b"<html><head><title>Fixture page</title>\
+            <link rel=\"icon\" href=\"/icon.png\"></head><body></body></html>"
+            .to_vec();
+        let base_url = serve_http(vec![
+            ("/", "text/html; charset=utf-8", html),
+            ("/icon.png", "image/png", fixture_bytes("images/sample.png")),
+        ]);
+        Url::parse(&format!("{}/", base_url)).unwrap()
+    }
+
+    fn favicon_file_count() -> usize {
+        match fs::read_dir(favicons_root_path().unwrap()) {
+            Ok(entries) => entries.count(),
+            Err(_) => 0,
+        }
+    }
+
+    async fn wait_until(flag: &AtomicBool) {
+        while !flag.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    async fn wait_for_registration(state: &BookmarkFetchState, request_id: &str) {
+        while !state.in_flight.lock().unwrap().contains_key(request_id) {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    #[test]
+    fn cancel_token_states_are_exclusive() {
+        let cancelled = BookmarkFetchCancel::default();
+        assert!(cancelled.cancel());
+        assert!(!cancelled.cancel());
+        assert!(cancelled.begin_commit().is_err());
+        assert!(cancelled.is_cancelled());
+
+        let committed = BookmarkFetchCancel::default();
+        assert!(committed.begin_commit().is_ok());
+        assert!(committed.begin_commit().is_ok());
+        assert!(!committed.cancel());
+        assert!(!committed.is_cancelled());
+    }
+
+    #[test]
+    fn uncancelled_fetch_stores_the_favicon() {
+        let _library = TestLibrary::new();
+        let url = serve_bookmark_page();
+        let cancel = BookmarkFetchCancel::default();
+        let metadata =
+            tauri::async_runtime::block_on(fetch_bookmark_metadata_internal(url, Some(&cancel)))
+                .unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Fixture page"));
+        let favicon_path = metadata.favicon_path.expect("favicon stored");
+        assert!(Path::new(&favicon_path).is_file());
+        assert_eq!(favicon_file_count(), 1);
+    }
+
+    #[test]
+    fn cancelled_token_stops_the_favicon_write() {
+        let _library = TestLibrary::new();
+        let url = serve_bookmark_page();
+        let cancel = BookmarkFetchCancel::default();
+        assert!(cancel.cancel());
+
+        let result =
+            tauri::async_runtime::block_on(fetch_bookmark_metadata_internal(url, Some(&cancel)));
+        assert_eq!(result.err().as_deref(), Some("bookmark fetch cancelled"));
+        assert_eq!(favicon_file_count(), 0);
+    }
+
+    #[test]
+    fn cancelled_create_bookmark_writes_nothing() {
+        let library = TestLibrary::new();
+        let url = serve_bookmark_page();
+        let state = Arc::new(BookmarkFetchState::default());
+        let release = Arc::new(AtomicBool::new(false));
+
+        let runner = {
+            let state = Arc::clone(&state);
+            let release = Arc::clone(&release);
+            tauri::async_runtime::spawn(async move {
+                run_cancellable_bookmark_fetch(
+                    &state,
+                    Some("req-1".to_string()),
+                    |cancel| async move {
+                        wait_until(&release).await;
+                        create_bookmark_item_internal(url, Vec::new(), None, Some(&cancel)).await
+                    },
+                )
+                .await
+            })
+        };
+        tauri::async_runtime::block_on(wait_for_registration(&state, "req-1"));
+        assert!(cancel_bookmark_fetch_internal(&state, "req-1").unwrap());
+        release.store(true, Ordering::SeqCst);
+
+        let result = tauri::async_runtime::block_on(runner).unwrap();
+        assert_eq!(result.err().as_deref(), Some("bookmark fetch cancelled"));
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(favicon_file_count(), 0);
+        assert_eq!(library.count("SELECT COUNT(*) FROM items"), 0);
+        assert!(state.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn late_cancel_reports_the_completed_fetch() {
+        let _library = TestLibrary::new();
+        let url = serve_bookmark_page();
+        let state = Arc::new(BookmarkFetchState::default());
+        let fetched = Arc::new(AtomicBool::new(false));
+        let release = Arc::new(AtomicBool::new(false));
+
+        let runner = {
+            let state = Arc::clone(&state);
+            let fetched = Arc::clone(&fetched);
+            let release = Arc::clone(&release);
+            tauri::async_runtime::spawn(async move {
+                run_cancellable_bookmark_fetch(
+                    &state,
+                    Some("req-2".to_string()),
+                    |cancel| async move {
+                        let metadata = fetch_bookmark_metadata_internal(url, Some(&cancel)).await;
+                        fetched.store(true, Ordering::SeqCst);
+                        wait_until(&release).await;
+                        metadata
+                    },
+                )
+                .await
+            })
+        };
+        tauri::async_runtime::block_on(wait_until(&fetched));
+        assert!(!cancel_bookmark_fetch_internal(&state, "req-2").unwrap());
+        release.store(true, Ordering::SeqCst);
+
+        let metadata = tauri::async_runtime::block_on(runner).unwrap().unwrap();
+        let favicon_path = metadata.favicon_path.expect("favicon stored");
+        assert!(Path::new(&favicon_path).is_file());
+        assert_eq!(favicon_file_count(), 1);
+    }
+
+    fn deep_link_error(link: &str) -> String {
+        match parse_deep_link_add_request(link) {
+            Ok(request) => panic!("{} parsed to {}", link, request.url),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn deep_link_add_parses_url_tags_and_collection() {
+        let request = parse_deep_link_add_request(
+            " stumble://add?url=https%3A%2F%2Fexample.com%2Fa%3Fb%3D1&tags=design,%20ui%20&collection=%20col-1%20 ",
+        )
+        .unwrap();
+
+        assert_eq!(request.url.as_str(), "https://example.com/a?b=1");
+        assert_eq!(request.tags, vec!["design", "ui"]);
+        assert_eq!(request.collection_id.as_deref(), Some("col-1"));
+    }
+
+    #[test]
+    fn deep_link_add_accepts_the_path_style_action() {
+        for link in [
+            "stumble:add?url=https://example.com",
+            "stumble:/add?url=https://example.com",
+            "stumble://ADD/?url=https://example.com",
+        ] {
+            let request = parse_deep_link_add_request(link)
+                .unwrap_or_else(|error| panic!("{}: {}", link, error));
+            assert_eq!(request.url.as_str(), "https://example.com/", "{}", link);
+        }
+        assert!(deep_link_error("stumble://open?url=https://example.com")
+            .contains("unsupported deep link action: open"));
+        assert!(deep_link_error("stumble:remove?url=https://example.com")
+            .contains("unsupported deep link action: remove"));
+    }
+
+    #[test]
+    fn deep_link_add_requires_an_http_url() {
+        assert_eq!(
+            deep_link_error("stumble://add?tags=design"),
+            "deep link is missing the url parameter"
+        );
+        assert_eq!(
+            deep_link_error("stumble://add?url=%20"),
+            "bookmark url cannot be empty"
+        );
+        for target in [
+            "file:///etc/passwd",
+            "javascript:alert(1)",
+            "ftp://example.com",
+        ] {
+            let link = format!("stumble://add?url={}", target);
+            assert_eq!(
+                deep_link_error(&link),
+                "only http:// and https:// URLs are supported",
+                "{}",
+                target
+            );
+        }
+        assert!(deep_link_error("https://add?url=https://example.com")
+            .starts_with("unsupported deep link scheme"));
+    }
+
+    #[test]
+    fn deep_link_add_dedupes_tags_and_drops_blank_ones() {
+        let request = parse_deep_link_add_request(
+            "stumble://add?url=https://example.com&tags=ui,%20ui,,%20%20,ux%20%20kit&tags=ui,ux%20kit,ref",
+        )
+        .unwrap();
+
+        assert_eq!(request.tags, vec!["ui", "ux kit", "ref"]);
+    }
+
+    #[test]
+    fn deep_link_add_treats_an_empty_collection_as_none() {
+        for link in [
+            "stumble://add?url=https://example.com&collection=",
+            "stumble://add?url=https://example.com&collection=%20%20",
+            "stumble://add?url=https://example.com",
+        ] {
+            let request = parse_deep_link_add_request(link).unwrap();
+            assert_eq!(request.collection_id, None, "{}", link);
+        }
+    }
+
+    fn insert_test_bookmark(item_id: &str) {
+        let mut input = note_input(item_id, "Bookmark");
+        input.item_type = "bookmark".to_string();
+        input.url = Some("https://example.com/page".to_string());
+        input.favicon_path = Some("favicons/example.png".to_string());
+        input.meta_status = Some("ready".to_string());
+        insert_item(input).unwrap();
+    }
+
+    fn bookmark_columns(
+        library: &TestLibrary,
+        item_id: &str,
+    ) -> (Option<String>, Option<String>, String) {
+        library
+            .connection()
+            .query_row(
+                "SELECT url, favicon_path, title FROM items WHERE id = ?1",
+                params![item_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap()
+    }
+
+    fn bookmark_metadata_input(item_id: &str) -> UpdateItemBookmarkMetadataInput {
+        UpdateItemBookmarkMetadataInput {
+            item_id: item_id.to_string(),
+            url: None,
+            title: None,
+            filename: None,
+            favicon_path: None,
+            meta_status: "ready".to_string(),
+            clear_url: false,
+            clear_favicon_path: false,
+        }
+    }
+
+    #[test]
+    fn bookmark_metadata_update_keeps_omitted_fields() {
+        let library = TestLibrary::new();
+        insert_test_bookmark("bookmark-1");
+
+        update_item_bookmark_metadata(UpdateItemBookmarkMetadataInput {
+            title: Some("  Renamed ".to_string()),
+            favicon_path: Some("   ".to_string()),
+            ..bookmark_metadata_input("bookmark-1")
+        })
+        .unwrap();
+
+        assert_eq!(
+            bookmark_columns(&library, "bookmark-1"),
+            (
+                Some("https://example.com/page".to_string()),
+                Some("favicons/example.png".to_string()),
+                "Renamed".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn bookmark_metadata_update_clears_url_and_favicon() {
+        let library = TestLibrary::new();
+        insert_test_bookmark("bookmark-1");
+        insert_test_bookmark("bookmark-2");
+
+        update_item_bookmark_metadata(UpdateItemBookmarkMetadataInput {
+            clear_favicon_path: true,
+            ..bookmark_metadata_input("bookmark-1")
+        })
+        .unwrap();
+        assert_eq!(
+            bookmark_columns(&library, "bookmark-1"),
+            (
+                Some("https://example.com/page".to_string()),
+                None,
+                "Bookmark".to_string()
+            )
+        );
+
+        update_item_bookmark_metadata(UpdateItemBookmarkMetadataInput {
+            url: Some("https://example.com/other".to_string()),
+            favicon_path: Some("favicons/other.png".to_string()),
+            clear_url: true,
+            clear_favicon_path: true,
+            ..bookmark_metadata_input("bookmark-2")
+        })
+        .unwrap();
+        assert_eq!(
+            bookmark_columns(&library, "bookmark-2"),
+            (None, None, "Bookmark".to_string())
+        );
+    }
+
+    #[test]
+    fn bookmark_metadata_update_only_touches_bookmarks() {
+        let _library = TestLibrary::new();
+        insert_note("note-1", "Note", None);
+
+        let error = update_item_bookmark_metadata(UpdateItemBookmarkMetadataInput {
+            clear_url: true,
+            ..bookmark_metadata_input("note-1")
+        })
+        .unwrap_err();
+        assert_eq!(error, "bookmark item not found while updating metadata");
+    }
+
+    fn shortcut_fixture(name: &str) -> Vec<u8> {
+        fixture_bytes(&format!("shortcuts/{}", name))
+    }
+
+    #[test]
+    fn url_shortcut_reads_only_the_internet_shortcut_section() {
+        let contents = String::from_utf8(shortcut_fixture("example.url")).unwrap();
+        assert_eq!(
+            parse_url_shortcut(&contents).as_deref(),
+            Some("https://example.com/docs?a=1&b=2")
+        );
+        let contents = String::from_utf8(shortcut_fixture("no-shortcut-section.url")).unwrap();
+        assert_eq!(parse_url_shortcut(&contents), None);
+        assert_eq!(
+            parse_url_shortcut("[internetshortcut]\n url = https://example.com/x \n"),
+            Some("https://example.com/x".to_string())
+        );
+        assert_eq!(parse_url_shortcut("[InternetShortcut]\nURL=\n"), None);
+    }
+
+    #[test]
+    fn xml_webloc_unescapes_the_url_string() {
+        assert_eq!(
+            parse_webloc(&shortcut_fixture("example.webloc")).as_deref(),
+            Some("https://example.com/search?q=cats&page=2")
+        );
+        assert_eq!(parse_webloc(b"<plist><dict></dict></plist>"), None);
+    }
+
+    #[test]
+    fn binary_webloc_reads_the_url_string_object() {
+        assert_eq!(
+            parse_webloc(&shortcut_fixture("example-binary.webloc")).as_deref(),
+            Some("https://example.com/binary/path?x=1")
+        );
+        assert_eq!(
+            parse_webloc(&shortcut_fixture("extra-keys-binary.webloc")).as_deref(),
+            Some("https://example.com/a")
+        );
+        assert_eq!(
+            parse_webloc(&shortcut_fixture("file-url-binary.webloc")),
+            None
+        );
+    }
+
+    #[test]
+    fn binary_webloc_without_a_length_marker_stops_at_non_graphic_bytes() {
+        let mut bytes = b"bplist00\x00\x00https://example.com/raw".to_vec();
+        bytes.extend_from_slice(&[0x08, 0x0b]);
+        assert_eq!(
+            parse_webloc(&bytes).as_deref(),
+            Some("https://example.com/raw")
+        );
+    }
+
+    #[test]
+    fn dropped_shortcut_fixtures_resolve_to_bookmark_urls() {
+        let url = read_dropped_shortcut_url(&fixture_path("shortcuts/example.url"), "url").unwrap();
+        assert_eq!(url.as_str(), "https://example.com/docs?a=1&b=2");
+        let url =
+            read_dropped_shortcut_url(&fixture_path("shortcuts/example-binary.webloc"), "webloc")
+                .unwrap();
+        assert_eq!(url.as_str(), "https://example.com/binary/path?x=1");
+        assert!(read_dropped_shortcut_url(
+            &fixture_path("shortcuts/no-shortcut-section.url"),
+            "url"
+        )
+        .unwrap_err()
+        .starts_with("no URL found in shortcut"));
+    }
+
+    /// Answers every request with an image body that never ends and has no Content-Length.
+    fn serve_endless_preview() -> Url {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                    line.clear();
+                }
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nConnection: close\r\n\r\n",
+                );
+                let chunk = vec![0_u8; 64 * 1024];
+                while stream.write_all(&chunk).is_ok() {}
+            }
+        });
+        Url::parse(&format!("http://{}/endless.png", address)).unwrap()
+    }
+
+    #[test]
+    fn preview_download_without_content_length_stops_at_the_cap() {
+        let url = serve_endless_preview();
+        let client = build_bookmark_http_client().unwrap();
+        let error =
+            tauri::async_runtime::block_on(download_item_preview(&client, &url, None, None))
+                .err()
+                .expect("endless preview must be rejected");
+        assert!(error.contains("aborted after exceeding"), "{}", error);
+    }
+
+    fn insert_remote_preview_note(id: &str, preview_url: &str) {
+        let mut input = note_input(id, id);
+        input.preview_url = Some(preview_url.to_string());
+        insert_item(input).unwrap();
+    }
+
+    fn preview_file_count() -> usize {
+        match fs::read_dir(previews_root_path().unwrap()) {
+            Ok(entries) => entries.count(),
+            Err(_) => 0,
+        }
+    }
+
+    #[test]
+    fn cached_previews_are_shared_refreshed_and_removed_with_their_last_item() {
+        let library = TestLibrary::new();
+        let base_url = serve_http(vec![
+            ("/a.png", "image/png", fixture_bytes("images/sample.png")),
+            ("/b.gif", "image/gif", fixture_bytes("images/sample.gif")),
+        ]);
+        let remote_a = format!("{}/a.png", base_url);
+        insert_remote_preview_note("first", &remote_a);
+        insert_remote_preview_note("second", &remote_a);
+        let cache =
+            |id: &str| tauri::async_runtime::block_on(cache_item_preview(id.to_string())).unwrap();
+        let refresh = |id: &str| {
+            tauri::async_runtime::block_on(refresh_item_preview(id.to_string())).unwrap()
+        };
+
+        let first = cache("first");
+        assert!(first.downloaded);
+        assert_eq!(first.source_url.as_deref(), Some(remote_a.as_str()));
+        let cached_a = first.preview_url.unwrap();
+        assert!(is_cached_preview_path(&cached_a));
+        assert!(Path::new(&cached_a).is_file());
+        assert!(!cache("first").downloaded);
+        assert_eq!(
+            cache("second").preview_url.as_deref(),
+            Some(cached_a.as_str())
+        );
+        assert_eq!(preview_file_count(), 1);
+
+        let unchanged = refresh("first");
+        assert!(unchanged.downloaded);
+        assert_eq!(unchanged.preview_url.as_deref(), Some(cached_a.as_str()));
+        assert!(Path::new(&cached_a).is_file());
+
+        library
+            .connection()
+            .execute(
+                "UPDATE items SET preview_source_url = ?1",
+                params![format!("{}/b.gif", base_url)],
+            )
+            .unwrap();
+        let cached_b = refresh("first").preview_url.unwrap();
+        assert_ne!(cached_b, cached_a);
+        assert!(Path::new(&cached_a).is_file(), "second still uses a.png");
+        assert_eq!(preview_file_count(), 2);
+
+        delete_items(vec!["second".to_string()]).unwrap();
+        assert!(!Path::new(&cached_a).exists());
+        assert!(Path::new(&cached_b).is_file());
+        delete_items(vec!["first".to_string()]).unwrap();
+        assert_eq!(preview_file_count(), 0);
+    }
+
+    #[test]
+    fn caching_skips_previews_it_does_not_manage() {
+        let _library = TestLibrary::new();
+        insert_remote_preview_note("local", "/somewhere/else/preview.png");
+        insert_note("none", "No preview", None);
+        for id in ["local", "none"] {
+            let result =
+                tauri::async_runtime::block_on(cache_item_preview(id.to_string())).unwrap();
+            assert!(!result.downloaded);
+            assert!(result.source_url.is_none());
+        }
+        assert_eq!(preview_file_count(), 0);
+    }
+}
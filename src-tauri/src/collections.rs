@@ -0,0 +1,4235 @@
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use chrono::Utc;
+use image::{imageops::FilterType, GenericImageView, ImageReader};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+use crate::commands::*;
+use crate::db::*;
+use crate::items::*;
+use crate::thumbs::*;
+use crate::vault::*;
+
+pub(crate) const DEFAULT_ROOT_COLLECTION_ID: &str = "root";
+pub(crate) const DEFAULT_ROOT_COLLECTION_NAME: &str = "Root";
+pub(crate) const DEFAULT_ROOT_COLLECTION_ICON: &str = "folder";
+pub(crate) const DEFAULT_ROOT_COLLECTION_COLOR: &str = "#60a5fa";
+pub(crate) const DEFAULT_COLLECTION_ICON_TYPE: &str = "named";
+pub(crate) const COLLECTION_ICON_MAX_BYTES: usize = 1024 * 1024;
+pub(crate) const COLLECTION_ICON_SIZE: u32 = 64;
+pub(crate) const COLLECTION_ICON_EMOJI_MAX_CHARS: usize = 16;
+pub(crate) const ITEMS_COLLECTION_MODE_ADD: &str = "add";
+pub(crate) const ITEMS_COLLECTION_MODE_MOVE_PRIMARY: &str = "move_primary";
+pub(crate) const ITEMS_COLLECTION_MODE_MOVE_ALL: &str = "move_all";
+pub(crate) const COLLECTION_NAME_CONFLICT_ALLOW: &str = "allow";
+pub(crate) const COLLECTION_NAME_CONFLICT_REJECT: &str = "reject";
+pub(crate) const COLLECTION_NAME_CONFLICT_REUSE: &str = "reuse";
+pub(crate) const SETTING_COLLECTION_LOCK_PREFIX: &str = "collection_lock_";
+pub(crate) const SETTING_COLLECTION_UNLOCK_TIMEOUT_MINUTES: &str =
+    "collection_unlock_timeout_minutes";
+pub(crate) const DEFAULT_COLLECTION_UNLOCK_TIMEOUT_MINUTES: i64 = 15;
+pub(crate) const COLLECTION_PASSPHRASE_MIN_CHARS: usize = 4;
+pub(crate) const LOCKED_SCOPE_VISIBLE_CONDITION: &str = "(NOT EXISTS (
+           SELECT 1 FROM collection_items AS lci
+           WHERE lci.item_id = i.id AND lci.collection_id IN (SELECT id FROM temp.locked_collection_scope)
+         )
+         OR EXISTS (
+           SELECT 1 FROM collection_items AS uci
+           WHERE uci.item_id = i.id AND uci.collection_id NOT IN (SELECT id FROM temp.locked_collection_scope)
+         ))";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DbCollectionRow {
+    pub(crate) id: String,
+    pub(crate) parent_id: Option<String>,
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) icon: String,
+    pub(crate) icon_type: String,
+    pub(crate) color: String,
+    pub(crate) is_archived: bool,
+    pub(crate) cover_item_id: Option<String>,
+    pub(crate) cover_thumb_path: Option<String>,
+    pub(crate) sort_index: i64,
+    pub(crate) created_at: i64,
+    pub(crate) updated_at: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectionSearchResult {
+    pub(crate) id: String,
+    pub(crate) parent_id: Option<String>,
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) path: String,
+    pub(crate) is_archived: bool,
+    pub(crate) item_count: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MoveCollectionResult {
+    pub(crate) collection: DbCollectionRow,
+    pub(crate) reordered_sibling_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectionCover {
+    pub(crate) collection_id: String,
+    pub(crate) item_id: Option<String>,
+    pub(crate) thumb_path: Option<String>,
+    pub(crate) is_explicit: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DbCollectionItemRow {
+    pub(crate) id: String,
+    pub(crate) collection_id: String,
+    pub(crate) item_id: String,
+    pub(crate) custom_title: Option<String>,
+    pub(crate) custom_description: Option<String>,
+    pub(crate) sort_index: i64,
+    pub(crate) is_pinned: bool,
+    pub(crate) created_at: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectionLockState {
+    pub(crate) locked_collection_ids: Vec<String>,
+    pub(crate) unlocked_collection_ids: Vec<String>,
+    pub(crate) unlock_timeout_minutes: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectionCounts {
+    pub(crate) counts: Vec<LibraryCountEntry>,
+    pub(crate) locked_count: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeleteCollectionResult {
+    pub(crate) deleted_collections: usize,
+    pub(crate) deleted_items: usize,
+    pub(crate) removed_memberships: usize,
+    pub(crate) cleanup: Vec<VaultCleanupEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DuplicateCollectionGroup {
+    pub(crate) parent_id: Option<String>,
+    pub(crate) name: String,
+    pub(crate) collection_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MergeCollectionsResult {
+    pub(crate) target_collection_id: String,
+    pub(crate) merged_collections: usize,
+    pub(crate) moved_memberships: usize,
+    pub(crate) collapsed_memberships: usize,
+    pub(crate) moved_children: usize,
+    pub(crate) updated_at: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectionRepairEntry {
+    pub(crate) collection_id: String,
+    pub(crate) previous_parent_id: Option<String>,
+    pub(crate) new_parent_id: Option<String>,
+    pub(crate) reason: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RepairCollectionTreeResult {
+    pub(crate) repaired: Vec<CollectionRepairEntry>,
+    pub(crate) repaired_at: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectionTreeNode {
+    pub(crate) id: String,
+    pub(crate) parent_id: Option<String>,
+    pub(crate) name: String,
+    pub(crate) icon: String,
+    pub(crate) icon_type: String,
+    pub(crate) color: String,
+    pub(crate) is_archived: bool,
+    pub(crate) is_locked: bool,
+    pub(crate) item_count: i64,
+    pub(crate) recursive_item_count: i64,
+    pub(crate) has_children: bool,
+    pub(crate) children: Vec<CollectionTreeNode>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectionTree {
+    pub(crate) roots: Vec<CollectionTreeNode>,
+    pub(crate) repaired: Vec<CollectionRepairEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateItemsCollectionResult {
+    pub(crate) updated_rows: usize,
+    pub(crate) updated_at: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ItemsCollectionUpdateResult {
+    pub(crate) updated_rows: usize,
+    pub(crate) updated_at: i64,
+    pub(crate) mode: String,
+    pub(crate) items: Vec<ItemMembershipChange>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateCollectionMembershipsResult {
+    pub(crate) created_rows: usize,
+    pub(crate) updated_rows: usize,
+    pub(crate) deleted_rows: usize,
+    pub(crate) skipped_rows: usize,
+    pub(crate) updated_at: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectionLayoutFile {
+    pub(crate) version: i64,
+    pub(crate) collection_id: String,
+    pub(crate) exported_at: i64,
+    pub(crate) items: Vec<CollectionLayoutEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectionLayoutEntry {
+    pub(crate) item_id: String,
+    #[serde(default)]
+    pub(crate) sort_index: i64,
+    #[serde(default)]
+    pub(crate) is_pinned: bool,
+    #[serde(default)]
+    pub(crate) custom_title: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ApplyCollectionLayoutResult {
+    pub(crate) applied: usize,
+    pub(crate) skipped: usize,
+    pub(crate) appended: usize,
+    pub(crate) updated_at: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateCollectionOrderResult {
+    pub(crate) updated_rows: usize,
+    pub(crate) skipped_rows: usize,
+    pub(crate) updated_at: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExportCollectionMarkdownResult {
+    pub(crate) path: String,
+    pub(crate) collections: usize,
+    pub(crate) items: usize,
+    pub(crate) assets: usize,
+}
+
+pub(crate) static UNLOCKED_COLLECTIONS: Mutex<BTreeMap<String, i64>> = Mutex::new(BTreeMap::new());
+
+pub(crate) fn ensure_default_root_collection(connection: &Connection) -> Result<(), String> {
+    let now = Utc::now().timestamp_millis();
+    connection
+        .execute(
+            "INSERT OR IGNORE INTO collections (
+                id,
+                name,
+                description,
+                icon,
+                color,
+                parent_id,
+                created_at,
+                updated_at
+            ) VALUES (?1, ?2, NULL, ?3, ?4, NULL, ?5, ?5)",
+            params![
+                DEFAULT_ROOT_COLLECTION_ID,
+                DEFAULT_ROOT_COLLECTION_NAME,
+                DEFAULT_ROOT_COLLECTION_ICON,
+                DEFAULT_ROOT_COLLECTION_COLOR,
+                now
+            ],
+        )
+        .map_err(|err| format!("failed to create default root collection: {}", err))?;
+    connection
+        .execute(
+            "UPDATE collections SET parent_id = NULL WHERE id = ?1 AND parent_id IS NOT NULL",
+            params![DEFAULT_ROOT_COLLECTION_ID],
+        )
+        .map_err(|err| format!("failed to detach default root collection: {}", err))?;
+
+    Ok(())
+}
+
+pub(crate) fn collection_lock_setting_key(collection_id: &str) -> String {
+    format!("{}{}", SETTING_COLLECTION_LOCK_PREFIX, collection_id)
+}
+
+pub(crate) fn load_locked_collection_ids(connection: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = connection
+        .prepare(
+            "SELECT substr(key, length(?1) + 1)
+             FROM app_settings
+             WHERE substr(key, 1, length(?1)) = ?1
+             ORDER BY key",
+        )
+        .map_err(|err| format!("failed to prepare collection locks query: {}", err))?;
+    let rows = stmt
+        .query_map(params![SETTING_COLLECTION_LOCK_PREFIX], |row| {
+            row.get::<_, String>(0)
+        })
+        .map_err(|err| format!("failed to query collection locks: {}", err))?;
+    let mut collection_ids = Vec::new();
+    for row_result in rows {
+        collection_ids.push(
+            row_result.map_err(|err| format!("failed to read collection lock row: {}", err))?,
+        );
+    }
+    Ok(collection_ids)
+}
+
+pub(crate) fn collection_unlock_timeout_minutes(connection: &Connection) -> Result<i64, String> {
+    Ok(
+        read_app_setting(connection, SETTING_COLLECTION_UNLOCK_TIMEOUT_MINUTES)?
+            .and_then(|value| value.trim().parse::<i64>().ok())
+            .filter(|value| *value > 0)
+            .unwrap_or(DEFAULT_COLLECTION_UNLOCK_TIMEOUT_MINUTES),
+    )
+}
+
+pub(crate) fn active_unlocked_collections(
+    connection: &Connection,
+) -> Result<BTreeSet<String>, String> {
+    let timeout_ms = collection_unlock_timeout_minutes(connection)? * 60_000;
+    let now = Utc::now().timestamp_millis();
+    let mut unlocked = UNLOCKED_COLLECTIONS
+        .lock()
+        .map_err(|_| "collection unlock state poisoned".to_string())?;
+    unlocked.retain(|_, last_active_at| now - *last_active_at <= timeout_ms);
+    for last_active_at in unlocked.values_mut() {
+        *last_active_at = now;
+    }
+    Ok(unlocked.keys().cloned().collect())
+}
+
+pub(crate) fn prepare_locked_collection_scope(
+    connection: &Connection,
+) -> Result<Vec<String>, String> {
+    connection
+        .execute_batch(
+            "CREATE TEMP TABLE IF NOT EXISTS locked_collection_scope (id TEXT PRIMARY KEY);
+             DELETE FROM temp.locked_collection_scope;",
+        )
+        .map_err(|err| format!("failed to prepare locked collection scope: {}", err))?;
+    let unlocked = active_unlocked_collections(connection)?;
+    let mut locked_roots = Vec::new();
+    for collection_id in load_locked_collection_ids(connection)? {
+        if unlocked.contains(&collection_id) {
+            continue;
+        }
+        connection
+            .execute(
+                "WITH RECURSIVE subtree(id) AS (
+                    SELECT ?1
+                    UNION
+                    SELECT c.id FROM collections AS c JOIN subtree AS s ON c.parent_id = s.id
+                 )
+                 INSERT OR IGNORE INTO temp.locked_collection_scope (id) SELECT id FROM subtree",
+                params![&collection_id],
+            )
+            .map_err(|err| format!("failed to expand locked collection scope: {}", err))?;
+        locked_roots.push(collection_id);
+    }
+    Ok(locked_roots)
+}
+
+pub(crate) fn locked_hidden_item_ids(connection: &Connection) -> Result<BTreeSet<String>, String> {
+    prepare_locked_collection_scope(connection)?;
+    let mut stmt = connection
+        .prepare(&format!(
+            "SELECT i.id FROM items AS i WHERE NOT {}",
+            LOCKED_SCOPE_VISIBLE_CONDITION
+        ))
+        .map_err(|err| format!("failed to prepare locked items query: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to query locked items: {}", err))?;
+    let mut item_ids = BTreeSet::new();
+    for row_result in rows {
+        item_ids
+            .insert(row_result.map_err(|err| format!("failed to read locked item row: {}", err))?);
+    }
+    Ok(item_ids)
+}
+
+pub(crate) fn verify_collection_passphrase(
+    connection: &Connection,
+    collection_id: &str,
+    passphrase: &str,
+) -> Result<(), String> {
+    let stored = read_app_setting(connection, &collection_lock_setting_key(collection_id))?
+        .ok_or_else(|| "collection is not locked".to_string())?;
+    let (salt, expected_hash) = stored
+        .split_once(':')
+        .and_then(|(salt, hash)| Some((hex_decode(salt)?, hex_decode(hash)?)))
+        .ok_or_else(|| "stored collection lock is invalid".to_string())?;
+    let derived = derive_passphrase_key(passphrase, &salt)?;
+    if !bool::from(derived.as_slice().ct_eq(expected_hash.as_slice())) {
+        return Err("incorrect collection passphrase".to_string());
+    }
+    Ok(())
+}
+
+pub(crate) fn query_db_collection_rows(
+    connection: &Connection,
+    include_archived: bool,
+) -> Result<Vec<DbCollectionRow>, String> {
+    let mut stmt = connection
+        .prepare(
+            "WITH RECURSIVE archived_subtree(id) AS (
+                SELECT id FROM collections WHERE is_archived <> 0
+                UNION
+                SELECT c.id FROM collections AS c JOIN archived_subtree AS a ON c.parent_id = a.id
+             )
+             SELECT
+                id,
+                parent_id,
+                name,
+                description,
+                icon,
+                color,
+                is_archived,
+                created_at,
+                updated_at,
+                cover_item_id,
+                icon_type,
+                sort_index
+             FROM collections
+             WHERE ?1 OR id NOT IN (SELECT id FROM archived_subtree)
+             ORDER BY sort_index ASC, created_at ASC",
+        )
+        .map_err(|err| format!("failed to prepare collections query: {}", err))?;
+    let rows = stmt
+        .query_map(params![include_archived], db_collection_row_from_row)
+        .map_err(|err| format!("failed to query collections: {}", err))?;
+
+    let mut collections = Vec::new();
+    for row_result in rows {
+        collections
+            .push(row_result.map_err(|err| format!("failed to read collection row: {}", err))?);
+    }
+
+    let mut covers = resolve_collection_covers(connection)?;
+    for collection in &mut collections {
+        collection.cover_thumb_path = covers
+            .remove(&collection.id)
+            .and_then(|cover| cover.thumb_path);
+    }
+    Ok(collections)
+}
+
+pub(crate) const DB_COLLECTION_ROW_COLUMNS: &str = "id,
+                parent_id,
+                name,
+                description,
+                icon,
+                color,
+                is_archived,
+                created_at,
+                updated_at,
+                cover_item_id,
+                icon_type,
+                sort_index";
+
+/// Maps a row selected with [`DB_COLLECTION_ROW_COLUMNS`]; covers are resolved separately.
+pub(crate) fn db_collection_row_from_row(
+    row: &rusqlite::Row<'_>,
+) -> rusqlite::Result<DbCollectionRow> {
+    Ok(DbCollectionRow {
+        id: row.get(0)?,
+        parent_id: row.get(1)?,
+        name: row.get(2)?,
+        description: row.get(3)?,
+        icon: row.get(4)?,
+        icon_type: row.get(10)?,
+        color: row.get(5)?,
+        is_archived: row.get::<_, i64>(6)? != 0,
+        cover_item_id: row.get(9)?,
+        cover_thumb_path: None,
+        sort_index: row.get(11)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+pub(crate) fn load_db_collection_row(
+    connection: &Connection,
+    collection_id: &str,
+) -> Result<DbCollectionRow, String> {
+    let mut collection = connection
+        .query_row(
+            &format!(
+                "SELECT {} FROM collections WHERE id = ?1",
+                DB_COLLECTION_ROW_COLUMNS
+            ),
+            params![collection_id],
+            db_collection_row_from_row,
+        )
+        .optional()
+        .map_err(|err| format!("failed to read collection: {}", err))?
+        .ok_or_else(|| format!("collection not found: {}", collection_id))?;
+    collection.cover_thumb_path = resolve_collection_covers(connection)?
+        .remove(collection_id)
+        .and_then(|cover| cover.thumb_path);
+    Ok(collection)
+}
+
+/// Sibling names are compared trimmed and case-insensitively (Unicode lowercase), so
+/// "References" and " references " count as the same name under one parent.
+pub(crate) fn collection_name_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+pub(crate) fn normalize_collection_name_conflict(
+    value: Option<String>,
+) -> Result<&'static str, String> {
+    match normalize_optional_trimmed_string(value).as_deref() {
+        None | Some(COLLECTION_NAME_CONFLICT_ALLOW) => Ok(COLLECTION_NAME_CONFLICT_ALLOW),
+        Some(COLLECTION_NAME_CONFLICT_REJECT) => Ok(COLLECTION_NAME_CONFLICT_REJECT),
+        Some(COLLECTION_NAME_CONFLICT_REUSE) => Ok(COLLECTION_NAME_CONFLICT_REUSE),
+        Some(other) => Err(format!(
+            "unsupported collection name conflict mode: {} (expected allow, reject or reuse)",
+            other
+        )),
+    }
+}
+
+pub(crate) fn find_sibling_collection_by_name(
+    connection: &Connection,
+    parent_id: Option<&str>,
+    name: &str,
+    exclude_id: Option<&str>,
+) -> Result<Option<String>, String> {
+    let mut stmt = connection
+        .prepare(
+            "SELECT id, name
+             FROM collections
+             WHERE parent_id IS ?1
+             ORDER BY created_at ASC, id ASC",
+        )
+        .map_err(|err| format!("failed to prepare sibling collection query: {}", err))?;
+    let rows = stmt
+        .query_map(params![parent_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|err| format!("failed to query sibling collections: {}", err))?;
+    let key = collection_name_key(name);
+    for row_result in rows {
+        let (sibling_id, sibling_name) =
+            row_result.map_err(|err| format!("failed to read sibling collection row: {}", err))?;
+        if Some(sibling_id.as_str()) != exclude_id && collection_name_key(&sibling_name) == key {
+            return Ok(Some(sibling_id));
+        }
+    }
+    Ok(None)
+}
+
+pub(crate) fn resolve_collection_covers(
+    connection: &Connection,
+) -> Result<HashMap<String, CollectionCover>, String> {
+    let thumbs_root = thumbs_root_path()?;
+    let mut stmt = connection
+        .prepare(
+            "WITH resolved AS (
+                SELECT
+                    c.id AS collection_id,
+                    explicit.id AS explicit_id,
+                    COALESCE(
+                        explicit.id,
+                        (
+                            SELECT i.id
+                            FROM collection_items AS ci
+                            JOIN items AS i ON i.id = ci.item_id
+                            WHERE ci.collection_id = c.id
+                              AND i.type = 'image'
+                              AND i.thumb_status = 'ready'
+                              AND i.is_archived = 0
+                            ORDER BY i.created_at DESC, i.id DESC
+                            LIMIT 1
+                        )
+                    ) AS cover_id
+                FROM collections AS c
+                LEFT JOIN items AS explicit ON explicit.id = c.cover_item_id
+             )
+             SELECT r.collection_id, r.explicit_id, r.cover_id, i.vault_key, i.thumb_status
+             FROM resolved AS r
+             LEFT JOIN items AS i ON i.id = r.cover_id",
+        )
+        .map_err(|err| format!("failed to prepare collection covers query: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })
+        .map_err(|err| format!("failed to query collection covers: {}", err))?;
+
+    let mut covers = HashMap::new();
+    for row_result in rows {
+        let (collection_id, explicit_id, cover_id, vault_key, thumb_status) =
+            row_result.map_err(|err| format!("failed to read collection cover row: {}", err))?;
+        let thumb_path = match (vault_key, thumb_status.as_deref()) {
+            (Some(vault_key), Some("ready")) => thumb_filename_for_vault_key(&vault_key)
+                .ok()
+                .map(|filename| thumbs_root.join(filename).to_string_lossy().to_string()),
+            _ => None,
+        };
+        covers.insert(
+            collection_id.clone(),
+            CollectionCover {
+                collection_id,
+                item_id: cover_id,
+                thumb_path,
+                is_explicit: explicit_id.is_some(),
+            },
+        );
+    }
+    Ok(covers)
+}
+
+pub(crate) fn db_collection_item_row_from_row(
+    row: &rusqlite::Row<'_>,
+) -> rusqlite::Result<DbCollectionItemRow> {
+    Ok(DbCollectionItemRow {
+        id: row.get(0)?,
+        collection_id: row.get(1)?,
+        item_id: row.get(2)?,
+        custom_title: row.get(3)?,
+        custom_description: row.get(4)?,
+        sort_index: row.get(5)?,
+        is_pinned: row.get::<_, i64>(6)? != 0,
+        created_at: row.get(7)?,
+    })
+}
+
+pub(crate) fn normalize_collection_text_icon(
+    icon: &str,
+    icon_type: &str,
+) -> Result<String, String> {
+    let normalized_icon = icon.trim().to_string();
+    if normalized_icon.is_empty() {
+        return Err("collection icon cannot be empty".to_string());
+    }
+    match icon_type {
+        "named" => Ok(normalized_icon),
+        "emoji" => {
+            if normalized_icon.chars().count() > COLLECTION_ICON_EMOJI_MAX_CHARS {
+                return Err("collection emoji icon is too long".to_string());
+            }
+            Ok(normalized_icon)
+        }
+        "image" => Err("use set_collection_icon_image to set an image icon".to_string()),
+        other => Err(format!(
+            "invalid collection icon type \"{}\"; expected one of: named, emoji, image",
+            other
+        )),
+    }
+}
+
+pub(crate) fn store_collection_icon_bytes(bytes: &[u8]) -> Result<PathBuf, String> {
+    if bytes.is_empty() {
+        return Err("collection icon image cannot be empty".to_string());
+    }
+    if bytes.len() > COLLECTION_ICON_MAX_BYTES {
+        return Err(format!(
+            "collection icon image is too large ({} bytes, max {})",
+            bytes.len(),
+            COLLECTION_ICON_MAX_BYTES
+        ));
+    }
+
+    let source_image = ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|err| format!("failed to detect collection icon format: {}", err))?
+        .decode()
+        .map_err(|err| format!("failed to decode collection icon image: {}", err))?;
+    let (width, height) = source_image.dimensions();
+    if width == 0 || height == 0 {
+        return Err("collection icon image has invalid dimensions".to_string());
+    }
+    let resized_image = if width.max(height) > COLLECTION_ICON_SIZE {
+        source_image.resize(
+            COLLECTION_ICON_SIZE,
+            COLLECTION_ICON_SIZE,
+            FilterType::Triangle,
+        )
+    } else {
+        source_image
+    };
+
+    let mut encoded = Vec::new();
+    resized_image
+        .write_to(
+            &mut std::io::Cursor::new(&mut encoded),
+            image::ImageFormat::Png,
+        )
+        .map_err(|err| format!("failed to encode collection icon: {}", err))?;
+
+    let root = ensure_icons_root_internal()?;
+    let path = root.join(format!("{}.png", sha256_for_bytes(&encoded)));
+    if !path.exists() {
+        fs::write(&path, &encoded).map_err(|err| {
+            format!(
+                "failed to write collection icon {}: {}",
+                path.display(),
+                err
+            )
+        })?;
+    }
+    Ok(path)
+}
+
+pub(crate) fn cleanup_unreferenced_collection_icon(
+    connection: &Connection,
+    icon_path: &str,
+) -> Result<(), String> {
+    let remaining_refs: i64 = connection
+        .query_row(
+            "SELECT COUNT(*) FROM collections WHERE icon_type = 'image' AND icon = ?1",
+            params![icon_path],
+            |row| row.get(0),
+        )
+        .map_err(|err| format!("failed to verify remaining icon refs: {}", err))?;
+    if remaining_refs > 0 {
+        return Ok(());
+    }
+
+    let path = PathBuf::from(icon_path);
+    let icons_root = canonicalize_for_comparison(&icons_root_path()?);
+    if !canonicalize_for_comparison(&path).starts_with(&icons_root) {
+        return Ok(());
+    }
+    if path.is_file() {
+        fs::remove_file(&path).map_err(|err| {
+            format!(
+                "failed to remove collection icon {}: {}",
+                path.display(),
+                err
+            )
+        })?;
+    }
+    Ok(())
+}
+
+pub(crate) fn replace_collection_icon(
+    collection_id: &str,
+    icon: &str,
+    icon_type: &str,
+) -> Result<i64, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let previous = connection
+        .query_row(
+            "SELECT icon, icon_type FROM collections WHERE id = ?1",
+            params![collection_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()
+        .map_err(|err| format!("failed to read collection icon: {}", err))?
+        .ok_or_else(|| "collection not found while updating icon".to_string())?;
+
+    let updated_at = Utc::now().timestamp_millis();
+    connection
+        .execute(
+            "UPDATE collections
+             SET icon = ?1,
+                 icon_type = ?2,
+                 updated_at = ?3
+             WHERE id = ?4",
+            params![icon, icon_type, updated_at, collection_id],
+        )
+        .map_err(|err| format!("failed to update collection icon: {}", err))?;
+
+    let (previous_icon, previous_icon_type) = previous;
+    if previous_icon_type == "image" && previous_icon != icon {
+        if let Err(err) = cleanup_unreferenced_collection_icon(&connection, &previous_icon) {
+            log_eprintln!(
+                "failed to clean up collection icon {}: {}",
+                previous_icon,
+                err
+            );
+        }
+    }
+    Ok(updated_at)
+}
+
+/// `on_name_conflict` decides what happens when a sibling already uses the same name
+/// (see [`collection_name_key`]): `allow` (default) creates a duplicate, `reject` errors,
+/// and `reuse` returns the existing sibling unchanged instead of creating a new one.
+#[tauri::command]
+pub(crate) fn create_collection(
+    name: String,
+    parent_id: Option<String>,
+    icon: String,
+    color: String,
+    description: Option<String>,
+    icon_type: Option<String>,
+    on_name_conflict: Option<String>,
+) -> Result<DbCollectionRow, String> {
+    let name_conflict = normalize_collection_name_conflict(on_name_conflict)?;
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+
+    let normalized_name = name.trim().to_string();
+    if normalized_name.is_empty() {
+        return Err("collection name cannot be empty".to_string());
+    }
+
+    let normalized_icon_type = normalize_optional_trimmed_string(icon_type)
+        .unwrap_or_else(|| DEFAULT_COLLECTION_ICON_TYPE.to_string());
+    let normalized_icon = normalize_collection_text_icon(&icon, &normalized_icon_type)?;
+
+    let normalized_color = color.trim().to_string();
+    if normalized_color.is_empty() {
+        return Err("collection color cannot be empty".to_string());
+    }
+
+    let normalized_parent_id = parent_id
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+
+    let normalized_description = description
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+
+    let now = Utc::now().timestamp_millis();
+    let collection_id = Uuid::new_v4().to_string();
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    validate_collection_parent(
+        &transaction,
+        &collection_id,
+        normalized_parent_id.as_deref(),
+    )?;
+    if name_conflict != COLLECTION_NAME_CONFLICT_ALLOW {
+        if let Some(existing_id) = find_sibling_collection_by_name(
+            &transaction,
+            normalized_parent_id.as_deref(),
+            &normalized_name,
+            None,
+        )? {
+            if name_conflict == COLLECTION_NAME_CONFLICT_REJECT {
+                return Err(format!(
+                    "a collection named \"{}\" already exists under this parent",
+                    normalized_name
+                ));
+            }
+            drop(transaction);
+            return load_db_collection_row(&connection, &existing_id);
+        }
+    }
+    let sort_index =
+        next_collection_sort_index_in_tx(&transaction, normalized_parent_id.as_deref())?;
+    transaction
+        .execute(
+            "INSERT INTO collections (
+                id,
+                name,
+                description,
+                icon,
+                icon_type,
+                color,
+                parent_id,
+                sort_index,
+                created_at,
+                updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)",
+            params![
+                &collection_id,
+                &normalized_name,
+                normalized_description.as_deref(),
+                &normalized_icon,
+                &normalized_icon_type,
+                &normalized_color,
+                normalized_parent_id.as_deref(),
+                sort_index,
+                now
+            ],
+        )
+        .map_err(|err| format!("failed to create collection: {}", err))?;
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit create collection transaction: {}", err))?;
+
+    Ok(DbCollectionRow {
+        id: collection_id,
+        parent_id: normalized_parent_id,
+        name: normalized_name,
+        description: normalized_description,
+        icon: normalized_icon,
+        icon_type: normalized_icon_type,
+        color: normalized_color,
+        is_archived: false,
+        cover_item_id: None,
+        cover_thumb_path: None,
+        sort_index,
+        created_at: now,
+        updated_at: now,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn get_all_collections(
+    include_archived: Option<bool>,
+) -> Result<Vec<DbCollectionRow>, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    query_db_collection_rows(&connection, include_archived.unwrap_or(false))
+}
+
+/// Renames accept the same `on_name_conflict` modes as [`create_collection`]; there is no
+/// new collection to skip on rename, so `reuse` behaves like `reject`.
+#[tauri::command]
+pub(crate) fn update_collection_name(
+    id: String,
+    name: String,
+    on_name_conflict: Option<String>,
+) -> Result<i64, String> {
+    let name_conflict = normalize_collection_name_conflict(on_name_conflict)?;
+    if id.trim() == DEFAULT_ROOT_COLLECTION_ID {
+        return Err("the root collection cannot be renamed".to_string());
+    }
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+
+    let normalized_name = name.trim().to_string();
+    if normalized_name.is_empty() {
+        return Err("collection name cannot be empty".to_string());
+    }
+
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    if name_conflict != COLLECTION_NAME_CONFLICT_ALLOW {
+        let parent_id = transaction
+            .query_row(
+                "SELECT parent_id FROM collections WHERE id = ?1",
+                params![&id],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()
+            .map_err(|err| format!("failed to read collection before rename: {}", err))?
+            .ok_or_else(|| "collection not found while updating name".to_string())?;
+        if find_sibling_collection_by_name(
+            &transaction,
+            parent_id.as_deref(),
+            &normalized_name,
+            Some(&id),
+        )?
+        .is_some()
+        {
+            return Err(format!(
+                "a collection named \"{}\" already exists under this parent",
+                normalized_name
+            ));
+        }
+    }
+
+    let updated_at = Utc::now().timestamp_millis();
+    let updated_rows = transaction
+        .execute(
+            "UPDATE collections
+             SET name = ?1,
+                 updated_at = ?2
+             WHERE id = ?3",
+            params![normalized_name, updated_at, id],
+        )
+        .map_err(|err| format!("failed to update collection name: {}", err))?;
+
+    if updated_rows == 0 {
+        return Err("collection not found while updating name".to_string());
+    }
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit collection rename: {}", err))?;
+
+    Ok(updated_at)
+}
+
+pub(crate) fn next_collection_sort_index_in_tx(
+    transaction: &Transaction<'_>,
+    parent_id: Option<&str>,
+) -> Result<i64, String> {
+    transaction
+        .query_row(
+            "SELECT COALESCE(MAX(sort_index) + 1, 0) FROM collections WHERE parent_id IS ?1",
+            params![parent_id],
+            |row| row.get(0),
+        )
+        .map_err(|err| format!("failed to read next collection sort index: {}", err))
+}
+
+#[tauri::command]
+pub(crate) fn move_collection(
+    collection_id: String,
+    new_parent_id: Option<String>,
+    insert_index: Option<usize>,
+) -> Result<MoveCollectionResult, String> {
+    let normalized_collection_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    let normalized_parent_id = normalize_optional_trimmed_id(new_parent_id);
+    let updated_at = Utc::now().timestamp_millis();
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    validate_collection_exists_in_tx(&transaction, &normalized_collection_id)?;
+    validate_collection_parent(
+        &transaction,
+        &normalized_collection_id,
+        normalized_parent_id.as_deref(),
+    )?;
+
+    let mut siblings = Vec::new();
+    {
+        let mut stmt = transaction
+            .prepare(
+                "SELECT id, sort_index FROM collections
+                 WHERE parent_id IS ?1 AND id <> ?2
+                 ORDER BY sort_index ASC, created_at ASC, id ASC",
+            )
+            .map_err(|err| format!("failed to prepare sibling collections query: {}", err))?;
+        let rows = stmt
+            .query_map(
+                params![normalized_parent_id, normalized_collection_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+            )
+            .map_err(|err| format!("failed to query sibling collections: {}", err))?;
+        for row_result in rows {
+            siblings.push(
+                row_result
+                    .map_err(|err| format!("failed to read sibling collection row: {}", err))?,
+            );
+        }
+    }
+
+    let position = insert_index.unwrap_or(siblings.len()).min(siblings.len());
+    let mut reordered_sibling_ids = Vec::new();
+    for (index, (sibling_id, sort_index)) in siblings.iter().enumerate() {
+        let next_sort_index = if index < position { index } else { index + 1 } as i64;
+        if next_sort_index == *sort_index {
+            continue;
+        }
+        transaction
+            .execute(
+                "UPDATE collections SET sort_index = ?1 WHERE id = ?2",
+                params![next_sort_index, sibling_id],
+            )
+            .map_err(|err| format!("failed to shift sibling collection: {}", err))?;
+        reordered_sibling_ids.push(sibling_id.clone());
+    }
+    transaction
+        .execute(
+            "UPDATE collections
+             SET parent_id = ?1, sort_index = ?2, updated_at = ?3
+             WHERE id = ?4",
+            params![
+                normalized_parent_id,
+                position as i64,
+                updated_at,
+                normalized_collection_id
+            ],
+        )
+        .map_err(|err| format!("failed to move collection: {}", err))?;
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit move collection transaction: {}", err))?;
+
+    let collection = query_db_collection_rows(&connection, true)?
+        .into_iter()
+        .find(|collection| collection.id == normalized_collection_id)
+        .ok_or_else(|| "collection not found after move".to_string())?;
+    Ok(MoveCollectionResult {
+        collection,
+        reordered_sibling_ids,
+    })
+}
+
+pub(crate) fn validate_collection_parent(
+    transaction: &Transaction<'_>,
+    collection_id: &str,
+    new_parent_id: Option<&str>,
+) -> Result<(), String> {
+    let Some(parent_id) = new_parent_id else {
+        return Ok(());
+    };
+    if parent_id == collection_id {
+        return Err("a collection cannot be its own parent".to_string());
+    }
+    if collection_id == DEFAULT_ROOT_COLLECTION_ID {
+        return Err("the root collection cannot have a parent".to_string());
+    }
+
+    let mut visited_ids = BTreeSet::new();
+    let mut current_id = Some(parent_id.to_string());
+    let mut is_first = true;
+    while let Some(ancestor_id) = current_id {
+        if ancestor_id == collection_id {
+            return Err(
+                "a collection cannot be moved under one of its own descendants".to_string(),
+            );
+        }
+        if !visited_ids.insert(ancestor_id.clone()) {
+            break;
+        }
+        let ancestor_parent = transaction
+            .query_row(
+                "SELECT parent_id FROM collections WHERE id = ?1",
+                params![&ancestor_id],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()
+            .map_err(|err| format!("failed to validate parent collection: {}", err))?;
+        match ancestor_parent {
+            Some(next_parent_id) => current_id = next_parent_id,
+            None if is_first => return Err("parent collection not found".to_string()),
+            None => current_id = None,
+        }
+        is_first = false;
+    }
+    Ok(())
+}
+
+pub(crate) fn load_child_collection_ids_in_tx(
+    transaction: &Transaction<'_>,
+    parent_id: &str,
+) -> Result<Vec<String>, String> {
+    let mut stmt = transaction
+        .prepare("SELECT id FROM collections WHERE parent_id = ?1")
+        .map_err(|err| format!("failed to prepare child collection query: {}", err))?;
+    let row_iter = stmt
+        .query_map(params![parent_id], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to query child collections: {}", err))?;
+
+    let mut child_ids = Vec::new();
+    for row_result in row_iter {
+        child_ids.push(
+            row_result.map_err(|err| format!("failed to read child collection row: {}", err))?,
+        );
+    }
+    Ok(child_ids)
+}
+
+pub(crate) fn collect_collection_subtree_ids_in_tx(
+    transaction: &Transaction<'_>,
+    root_collection_id: &str,
+) -> Result<Vec<String>, String> {
+    let mut stack = vec![root_collection_id.to_string()];
+    let mut visited_ids = BTreeSet::new();
+    let mut collected_ids = Vec::new();
+
+    while let Some(collection_id) = stack.pop() {
+        if !visited_ids.insert(collection_id.clone()) {
+            continue;
+        }
+
+        collected_ids.push(collection_id.clone());
+        let child_ids = load_child_collection_ids_in_tx(transaction, &collection_id)?;
+        for child_id in child_ids {
+            stack.push(child_id);
+        }
+    }
+
+    Ok(collected_ids)
+}
+
+pub(crate) fn plan_collection_tree_repairs(
+    parent_by_id: &mut BTreeMap<String, Option<String>>,
+) -> Vec<CollectionRepairEntry> {
+    let fallback_parent_id = parent_by_id
+        .contains_key(DEFAULT_ROOT_COLLECTION_ID)
+        .then(|| DEFAULT_ROOT_COLLECTION_ID.to_string());
+    let mut repaired = Vec::new();
+    let mut reattach =
+        |parent_by_id: &mut BTreeMap<String, Option<String>>, id: &str, reason: &str| {
+            let new_parent_id = if id == DEFAULT_ROOT_COLLECTION_ID {
+                None
+            } else {
+                fallback_parent_id.clone()
+            };
+            let previous_parent_id = parent_by_id
+                .insert(id.to_string(), new_parent_id.clone())
+                .flatten();
+            repaired.push(CollectionRepairEntry {
+                collection_id: id.to_string(),
+                previous_parent_id,
+                new_parent_id,
+                reason: reason.to_string(),
+            });
+        };
+
+    if let Some(Some(_)) = parent_by_id.get(DEFAULT_ROOT_COLLECTION_ID) {
+        reattach(parent_by_id, DEFAULT_ROOT_COLLECTION_ID, "root");
+    }
+
+    let ids: Vec<String> = parent_by_id.keys().cloned().collect();
+    for id in &ids {
+        let orphaned = match parent_by_id.get(id) {
+            Some(Some(parent_id)) => !parent_by_id.contains_key(parent_id),
+            _ => false,
+        };
+        if orphaned {
+            reattach(parent_by_id, id, "orphan");
+        }
+    }
+
+    for id in &ids {
+        let mut visited_ids = BTreeSet::new();
+        let mut current_id = id.clone();
+        visited_ids.insert(current_id.clone());
+        while let Some(Some(parent_id)) = parent_by_id.get(&current_id).cloned() {
+            if !visited_ids.insert(parent_id.clone()) {
+                reattach(parent_by_id, &current_id, "cycle");
+                break;
+            }
+            current_id = parent_id;
+        }
+    }
+    repaired
+}
+
+#[tauri::command]
+pub(crate) fn repair_collection_tree() -> Result<RepairCollectionTreeResult, String> {
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    let mut parent_by_id: BTreeMap<String, Option<String>> = BTreeMap::new();
+    {
+        let mut stmt = transaction
+            .prepare("SELECT id, parent_id FROM collections")
+            .map_err(|err| format!("failed to prepare collection tree scan: {}", err))?;
+        let row_iter = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+            })
+            .map_err(|err| format!("failed to scan collection tree: {}", err))?;
+        for row_result in row_iter {
+            let (id, parent_id) =
+                row_result.map_err(|err| format!("failed to read collection tree row: {}", err))?;
+            parent_by_id.insert(id, parent_id);
+        }
+    }
+
+    let repaired = plan_collection_tree_repairs(&mut parent_by_id);
+
+    let updated_at = Utc::now().timestamp_millis();
+    for entry in &repaired {
+        transaction
+            .execute(
+                "UPDATE collections SET parent_id = ?1, updated_at = ?2 WHERE id = ?3",
+                params![&entry.new_parent_id, updated_at, &entry.collection_id],
+            )
+            .map_err(|err| format!("failed to repair collection parent: {}", err))?;
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit collection tree repair: {}", err))?;
+
+    Ok(RepairCollectionTreeResult {
+        repaired,
+        repaired_at: updated_at,
+    })
+}
+
+pub(crate) struct CollectionTreeEntry {
+    pub(crate) node: CollectionTreeNode,
+    pub(crate) item_ids: BTreeSet<String>,
+}
+
+pub(crate) fn assemble_collection_tree_node(
+    id: &str,
+    entries: &mut BTreeMap<String, CollectionTreeEntry>,
+    children_by_parent: &BTreeMap<String, Vec<String>>,
+) -> Option<(CollectionTreeNode, BTreeSet<String>)> {
+    let CollectionTreeEntry {
+        mut node,
+        mut item_ids,
+    } = entries.remove(id)?;
+    node.item_count = item_ids.len() as i64;
+    for child_id in children_by_parent.get(id).into_iter().flatten() {
+        if let Some((child, child_item_ids)) =
+            assemble_collection_tree_node(child_id, entries, children_by_parent)
+        {
+            item_ids.extend(child_item_ids);
+            node.children.push(child);
+        }
+    }
+    node.has_children = !node.children.is_empty();
+    node.recursive_item_count = item_ids.len() as i64;
+    Some((node, item_ids))
+}
+
+#[tauri::command]
+pub(crate) fn get_collection_tree(
+    include_archived: Option<bool>,
+) -> Result<CollectionTree, String> {
+    let include_archived = include_archived.unwrap_or(false);
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let locked_roots: BTreeSet<String> = prepare_locked_collection_scope(&connection)?
+        .into_iter()
+        .collect();
+
+    let mut stmt = connection
+        .prepare(
+            "WITH RECURSIVE archived_subtree(id) AS (
+                SELECT id FROM collections WHERE is_archived <> 0
+                UNION
+                SELECT c.id FROM collections AS c JOIN archived_subtree AS a ON c.parent_id = a.id
+             )
+             SELECT c.id, c.parent_id, c.name, c.icon, c.icon_type, c.color, c.is_archived, i.id
+             FROM collections AS c
+             LEFT JOIN collection_items AS ci
+                ON ci.collection_id = c.id
+               AND ci.collection_id NOT IN (SELECT id FROM temp.locked_collection_scope)
+             LEFT JOIN items AS i
+                ON i.id = ci.item_id
+               AND (?1 OR i.is_archived = 0)
+             WHERE ?1 OR c.id NOT IN (SELECT id FROM archived_subtree)
+             ORDER BY c.sort_index ASC, c.created_at ASC, c.id ASC",
+        )
+        .map_err(|err| format!("failed to prepare collection tree query: {}", err))?;
+    let rows = stmt
+        .query_map(params![include_archived], |row| {
+            Ok((
+                CollectionTreeNode {
+                    id: row.get(0)?,
+                    parent_id: row.get(1)?,
+                    name: row.get(2)?,
+                    icon: row.get(3)?,
+                    icon_type: row.get(4)?,
+                    color: row.get(5)?,
+                    is_archived: row.get::<_, i64>(6)? != 0,
+                    is_locked: false,
+                    item_count: 0,
+                    recursive_item_count: 0,
+                    has_children: false,
+                    children: Vec::new(),
+                },
+                row.get::<_, Option<String>>(7)?,
+            ))
+        })
+        .map_err(|err| format!("failed to query collection tree: {}", err))?;
+
+    let mut ordered_ids = Vec::new();
+    let mut entries: BTreeMap<String, CollectionTreeEntry> = BTreeMap::new();
+    for row_result in rows {
+        let (node, item_id) =
+            row_result.map_err(|err| format!("failed to read collection tree row: {}", err))?;
+        let entry = entries.entry(node.id.clone()).or_insert_with(|| {
+            ordered_ids.push(node.id.clone());
+            CollectionTreeEntry {
+                node: CollectionTreeNode {
+                    is_locked: locked_roots.contains(&node.id),
+                    ..node
+                },
+                item_ids: BTreeSet::new(),
+            }
+        });
+        if let Some(item_id) = item_id {
+            entry.item_ids.insert(item_id);
+        }
+    }
+
+    let mut parent_by_id: BTreeMap<String, Option<String>> = entries
+        .iter()
+        .map(|(id, entry)| (id.clone(), entry.node.parent_id.clone()))
+        .collect();
+    let repaired = plan_collection_tree_repairs(&mut parent_by_id);
+
+    let mut root_ids = Vec::new();
+    let mut children_by_parent: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for id in &ordered_ids {
+        let parent_id = parent_by_id.get(id).cloned().flatten();
+        if let Some(entry) = entries.get_mut(id) {
+            entry.node.parent_id = parent_id.clone();
+        }
+        match parent_id {
+            Some(parent_id) => children_by_parent
+                .entry(parent_id)
+                .or_default()
+                .push(id.clone()),
+            None => root_ids.push(id.clone()),
+        }
+    }
+
+    let mut roots = Vec::new();
+    for id in &root_ids {
+        if let Some((node, _)) =
+            assemble_collection_tree_node(id, &mut entries, &children_by_parent)
+        {
+            roots.push(node);
+        }
+    }
+
+    Ok(CollectionTree { roots, repaired })
+}
+
+pub(crate) const COLLECTION_SEARCH_MAX_DEPTH: i64 = 64;
+pub(crate) const COLLECTION_SEARCH_LIMIT: i64 = 100;
+
+#[tauri::command]
+pub(crate) fn search_collections(query: String) -> Result<Vec<CollectionSearchResult>, String> {
+    let trimmed_query = query.trim().to_string();
+    if trimmed_query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let lowered_query = trimmed_query.to_lowercase();
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let mut stmt = connection
+        .prepare(
+            "WITH RECURSIVE matches(id) AS (
+                SELECT id FROM collections
+                WHERE id = ?1
+                   OR instr(LOWER(name), ?2) > 0
+                   OR instr(LOWER(COALESCE(description, '')), ?2) > 0
+             ),
+             ancestry(match_id, ancestor_id, depth, path) AS (
+                SELECT c.id, c.parent_id, 0, c.name
+                FROM collections AS c JOIN matches AS m ON m.id = c.id
+                UNION ALL
+                SELECT a.match_id, p.parent_id, a.depth + 1, p.name || ' / ' || a.path
+                FROM ancestry AS a JOIN collections AS p ON p.id = a.ancestor_id
+                WHERE a.depth < ?3
+             )
+             SELECT
+                c.id,
+                c.parent_id,
+                c.name,
+                c.description,
+                c.is_archived,
+                (SELECT a.path FROM ancestry AS a WHERE a.match_id = c.id ORDER BY a.depth DESC LIMIT 1),
+                (SELECT COUNT(*)
+                   FROM collection_items AS ci JOIN items AS i ON i.id = ci.item_id
+                  WHERE ci.collection_id = c.id AND i.is_archived = 0)
+             FROM collections AS c
+             JOIN matches AS m ON m.id = c.id
+             ORDER BY c.id = ?1 DESC, instr(LOWER(c.name), ?2) > 0 DESC, LOWER(c.name) ASC, c.id ASC
+             LIMIT ?4",
+        )
+        .map_err(|err| format!("failed to prepare collection search query: {}", err))?;
+    let rows = stmt
+        .query_map(
+            params![
+                trimmed_query,
+                lowered_query,
+                COLLECTION_SEARCH_MAX_DEPTH,
+                COLLECTION_SEARCH_LIMIT
+            ],
+            |row| {
+                Ok(CollectionSearchResult {
+                    id: row.get(0)?,
+                    parent_id: row.get(1)?,
+                    name: row.get(2)?,
+                    description: row.get(3)?,
+                    is_archived: row.get::<_, i64>(4)? != 0,
+                    path: row.get(5)?,
+                    item_count: row.get(6)?,
+                })
+            },
+        )
+        .map_err(|err| format!("failed to search collections: {}", err))?;
+
+    let mut results = Vec::new();
+    for row_result in rows {
+        results.push(
+            row_result.map_err(|err| format!("failed to read collection search row: {}", err))?,
+        );
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+pub(crate) fn delete_collection(id: String) -> Result<DeleteCollectionResult, String> {
+    initialize_db()?;
+    let trimmed_id = id.trim().to_string();
+    if trimmed_id.is_empty() {
+        return Err("collection id cannot be empty".to_string());
+    }
+    if trimmed_id == DEFAULT_ROOT_COLLECTION_ID {
+        return Err("the root collection cannot be deleted".to_string());
+    }
+
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    let exists = transaction
+        .query_row(
+            "SELECT 1 FROM collections WHERE id = ?1",
+            params![&trimmed_id],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to verify collection before delete: {}", err))?;
+    if exists.is_none() {
+        return Ok(DeleteCollectionResult {
+            deleted_collections: 0,
+            deleted_items: 0,
+            removed_memberships: 0,
+            cleanup: Vec::new(),
+        });
+    }
+
+    let subtree_ids = collect_collection_subtree_ids_in_tx(&transaction, &trimmed_id)?;
+    if subtree_ids
+        .iter()
+        .any(|collection_id| collection_id == DEFAULT_ROOT_COLLECTION_ID)
+    {
+        return Err(
+            "the root collection cannot be deleted; run repair_collection_tree first".to_string(),
+        );
+    }
+    let subtree_id_set: BTreeSet<String> = subtree_ids.iter().cloned().collect();
+    let mut candidate_item_ids = Vec::new();
+    let mut seen_item_ids = BTreeSet::new();
+    for collection_id in &subtree_ids {
+        let mut stmt = transaction
+            .prepare("SELECT DISTINCT item_id FROM collection_items WHERE collection_id = ?1")
+            .map_err(|err| format!("failed to prepare collection membership query: {}", err))?;
+        let row_iter = stmt
+            .query_map(params![collection_id], |row| row.get::<_, String>(0))
+            .map_err(|err| format!("failed to query collection membership item ids: {}", err))?;
+
+        for row_result in row_iter {
+            let item_id =
+                row_result.map_err(|err| format!("failed to read collection item id: {}", err))?;
+            if seen_item_ids.insert(item_id.clone()) {
+                candidate_item_ids.push(item_id);
+            }
+        }
+    }
+
+    let mut item_ids = Vec::new();
+    for item_id in candidate_item_ids {
+        let mut membership_stmt = transaction
+            .prepare("SELECT collection_id FROM collection_items WHERE item_id = ?1")
+            .map_err(|err| format!("failed to prepare item membership scan: {}", err))?;
+        let membership_iter = membership_stmt
+            .query_map(params![&item_id], |row| row.get::<_, String>(0))
+            .map_err(|err| {
+                format!(
+                    "failed to query item memberships for delete preflight: {}",
+                    err
+                )
+            })?;
+
+        let mut has_membership_outside_subtree = false;
+        for membership_row in membership_iter {
+            let membership_collection_id = membership_row.map_err(|err| {
+                format!(
+                    "failed to read item membership row during delete preflight: {}",
+                    err
+                )
+            })?;
+            if !subtree_id_set.contains(&membership_collection_id) {
+                has_membership_outside_subtree = true;
+                break;
+            }
+        }
+
+        if !has_membership_outside_subtree {
+            item_ids.push(item_id);
+        }
+    }
+
+    let mut removed_memberships = 0usize;
+    for collection_id in &subtree_ids {
+        let memberships: i64 = transaction
+            .query_row(
+                "SELECT COUNT(*) FROM collection_items WHERE collection_id = ?1",
+                params![collection_id],
+                |row| row.get(0),
+            )
+            .map_err(|err| {
+                format!(
+                    "failed to count collection memberships before delete: {}",
+                    err
+                )
+            })?;
+        removed_memberships += memberships as usize;
+    }
+
+    let (deleted_items, pending_cleanup) = delete_items_in_tx(&transaction, item_ids)?;
+
+    let mut icon_paths_to_check = BTreeSet::new();
+    for collection_id in &subtree_ids {
+        let icon_path = transaction
+            .query_row(
+                "SELECT icon FROM collections WHERE id = ?1 AND icon_type = 'image'",
+                params![collection_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(|err| format!("failed to read collection icon before delete: {}", err))?;
+        if let Some(icon_path) = icon_path {
+            icon_paths_to_check.insert(icon_path);
+        }
+    }
+
+    let mut deleted_collections = 0usize;
+    for collection_id in subtree_ids.iter().rev() {
+        let affected = transaction
+            .execute(
+                "DELETE FROM collections WHERE id = ?1",
+                params![collection_id],
+            )
+            .map_err(|err| format!("failed to delete collection row: {}", err))?;
+        deleted_collections += affected;
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit delete collection transaction: {}", err))?;
+
+    for icon_path in icon_paths_to_check {
+        if let Err(err) = cleanup_unreferenced_collection_icon(&connection, &icon_path) {
+            log_eprintln!("failed to clean up collection icon {}: {}", icon_path, err);
+        }
+    }
+    let cleanup = run_post_delete_cleanup(pending_cleanup)?;
+
+    Ok(DeleteCollectionResult {
+        deleted_collections,
+        deleted_items,
+        removed_memberships,
+        cleanup,
+    })
+}
+
+/// Reports sibling collections sharing a name (per [`collection_name_key`]). Each group
+/// lists ids oldest first, which is the merge target [`merge_duplicate_collections`] keeps.
+#[tauri::command]
+pub(crate) fn find_duplicate_sibling_collections() -> Result<Vec<DuplicateCollectionGroup>, String>
+{
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let mut stmt = connection
+        .prepare(
+            "SELECT id, parent_id, name
+             FROM collections
+             ORDER BY created_at ASC, id ASC",
+        )
+        .map_err(|err| format!("failed to prepare duplicate collection query: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map_err(|err| format!("failed to query collections for duplicates: {}", err))?;
+
+    let mut groups: BTreeMap<(Option<String>, String), DuplicateCollectionGroup> = BTreeMap::new();
+    for row_result in rows {
+        let (id, parent_id, name) =
+            row_result.map_err(|err| format!("failed to read collection row: {}", err))?;
+        groups
+            .entry((parent_id.clone(), collection_name_key(&name)))
+            .or_insert_with(|| DuplicateCollectionGroup {
+                parent_id,
+                name: name.trim().to_string(),
+                collection_ids: Vec::new(),
+            })
+            .collection_ids
+            .push(id);
+    }
+    Ok(groups
+        .into_values()
+        .filter(|group| group.collection_ids.len() > 1)
+        .collect())
+}
+
+/// Folds `source_collection_id` into `target_collection_id`: memberships move over (an
+/// item already in the target keeps the target's membership), child collections are
+/// re-parented under the target, and the source row is deleted. Items are never deleted.
+pub(crate) fn merge_collections_in_tx(
+    transaction: &Transaction<'_>,
+    source_collection_id: &str,
+    target_collection_id: &str,
+    locked_collection_ids: &[String],
+    updated_at: i64,
+) -> Result<(UpdateCollectionMembershipsResult, usize, Option<String>), String> {
+    if source_collection_id == target_collection_id {
+        return Err("cannot merge a collection into itself".to_string());
+    }
+    if source_collection_id == DEFAULT_ROOT_COLLECTION_ID {
+        return Err("the root collection cannot be merged away".to_string());
+    }
+    validate_collection_exists_in_tx(transaction, source_collection_id)?;
+    validate_collection_exists_in_tx(transaction, target_collection_id)?;
+    if locked_collection_ids
+        .iter()
+        .any(|id| id == source_collection_id)
+    {
+        return Err("remove the lock from a collection before merging it".to_string());
+    }
+    let source_subtree = collect_collection_subtree_ids_in_tx(transaction, source_collection_id)?;
+    if source_subtree.iter().any(|id| id == target_collection_id) {
+        return Err("cannot merge a collection into one of its own descendants".to_string());
+    }
+
+    let mut stmt = transaction
+        .prepare(
+            "SELECT item_id
+             FROM collection_items
+             WHERE collection_id = ?1
+             ORDER BY sort_index ASC, created_at ASC, id ASC",
+        )
+        .map_err(|err| format!("failed to prepare merge membership query: {}", err))?;
+    let item_ids = stmt
+        .query_map(params![source_collection_id], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to query memberships to merge: {}", err))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to read membership to merge: {}", err))?;
+    drop(stmt);
+    let memberships = move_collection_item_memberships_in_tx(
+        transaction,
+        &item_ids,
+        Some(source_collection_id),
+        Some(target_collection_id),
+        updated_at,
+    )?;
+
+    let child_ids = load_child_collection_ids_in_tx(transaction, source_collection_id)?;
+    for child_id in &child_ids {
+        let sort_index = next_collection_sort_index_in_tx(transaction, Some(target_collection_id))?;
+        transaction
+            .execute(
+                "UPDATE collections
+                 SET parent_id = ?1,
+                     sort_index = ?2,
+                     updated_at = ?3
+                 WHERE id = ?4",
+                params![target_collection_id, sort_index, updated_at, child_id],
+            )
+            .map_err(|err| format!("failed to re-parent child collection during merge: {}", err))?;
+    }
+
+    let icon_path = transaction
+        .query_row(
+            "SELECT icon FROM collections WHERE id = ?1 AND icon_type = 'image'",
+            params![source_collection_id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to read collection icon before merge: {}", err))?;
+    transaction
+        .execute(
+            "DELETE FROM collections WHERE id = ?1",
+            params![source_collection_id],
+        )
+        .map_err(|err| format!("failed to delete merged collection: {}", err))?;
+    transaction
+        .execute(
+            "UPDATE collections SET updated_at = ?1 WHERE id = ?2",
+            params![updated_at, target_collection_id],
+        )
+        .map_err(|err| format!("failed to touch merge target collection: {}", err))?;
+
+    Ok((memberships, child_ids.len(), icon_path))
+}
+
+#[tauri::command]
+pub(crate) fn merge_collections(
+    source_collection_id: String,
+    target_collection_id: String,
+) -> Result<MergeCollectionsResult, String> {
+    merge_collection_ids(target_collection_id, vec![source_collection_id])
+}
+
+/// Merges a group reported by [`find_duplicate_sibling_collections`] into its first
+/// (oldest) id. Re-parented children may themselves form new duplicate groups.
+#[tauri::command]
+pub(crate) fn merge_duplicate_collections(
+    collection_ids: Vec<String>,
+) -> Result<MergeCollectionsResult, String> {
+    let mut collection_ids = normalize_item_ids_input(collection_ids);
+    if collection_ids.len() < 2 {
+        return Err("at least two collections are required to merge".to_string());
+    }
+    let target_collection_id = collection_ids.remove(0);
+    merge_collection_ids(target_collection_id, collection_ids)
+}
+
+pub(crate) fn merge_collection_ids(
+    target_collection_id: String,
+    source_collection_ids: Vec<String>,
+) -> Result<MergeCollectionsResult, String> {
+    let target_collection_id = target_collection_id.trim().to_string();
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let locked_collection_ids = load_locked_collection_ids(&connection)?;
+    let updated_at = Utc::now().timestamp_millis();
+    let transaction = connection
+        .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    let mut result = MergeCollectionsResult {
+        target_collection_id: target_collection_id.clone(),
+        merged_collections: 0,
+        moved_memberships: 0,
+        collapsed_memberships: 0,
+        moved_children: 0,
+        updated_at,
+    };
+    let mut icon_paths_to_check = BTreeSet::new();
+    for source_collection_id in &source_collection_ids {
+        let (memberships, moved_children, icon_path) = merge_collections_in_tx(
+            &transaction,
+            source_collection_id.trim(),
+            &target_collection_id,
+            &locked_collection_ids,
+            updated_at,
+        )?;
+        result.merged_collections += 1;
+        result.moved_memberships += memberships.updated_rows;
+        result.collapsed_memberships += memberships.deleted_rows;
+        result.moved_children += moved_children;
+        icon_paths_to_check.extend(icon_path);
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit merge collections transaction: {}", err))?;
+    for icon_path in icon_paths_to_check {
+        if let Err(err) = cleanup_unreferenced_collection_icon(&connection, &icon_path) {
+            log_eprintln!("failed to clean up collection icon {}: {}", icon_path, err);
+        }
+    }
+    Ok(result)
+}
+
+pub(crate) fn validate_collection_exists_in_tx(
+    transaction: &Transaction<'_>,
+    collection_id: &str,
+) -> Result<(), String> {
+    let exists = transaction
+        .query_row(
+            "SELECT 1 FROM collections WHERE id = ?1",
+            params![collection_id],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to verify collection existence: {}", err))?;
+    if exists.is_none() {
+        return Err(format!("collection not found: {}", collection_id));
+    }
+    Ok(())
+}
+
+pub(crate) fn collection_membership_exists_in_tx(
+    transaction: &Transaction<'_>,
+    item_id: &str,
+    collection_id: &str,
+) -> Result<bool, String> {
+    let exists = transaction
+        .query_row(
+            "SELECT 1
+             FROM collection_items
+             WHERE item_id = ?1 AND collection_id = ?2
+             LIMIT 1",
+            params![item_id, collection_id],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to verify collection membership: {}", err))?;
+    Ok(exists.is_some())
+}
+
+pub(crate) fn next_collection_item_sort_index_in_tx(
+    transaction: &Transaction<'_>,
+    collection_id: &str,
+) -> Result<i64, String> {
+    transaction
+        .query_row(
+            "SELECT COALESCE(MAX(sort_index), -1) + 1
+             FROM collection_items
+             WHERE collection_id = ?1",
+            params![collection_id],
+            |row| row.get::<_, i64>(0),
+        )
+        .map_err(|err| format!("failed to resolve next collection item sort index: {}", err))
+}
+
+pub(crate) fn insert_collection_membership_in_tx(
+    transaction: &Transaction<'_>,
+    item_id: &str,
+    collection_id: &str,
+    sort_index: i64,
+    created_at: i64,
+) -> Result<usize, String> {
+    let membership_id = Uuid::new_v4().to_string();
+    transaction
+        .execute(
+            "INSERT OR IGNORE INTO collection_items (
+                id,
+                collection_id,
+                item_id,
+                custom_title,
+                custom_description,
+                sort_index,
+                created_at
+             ) VALUES (?1, ?2, ?3, NULL, NULL, ?4, ?5)",
+            params![
+                membership_id,
+                collection_id,
+                item_id,
+                sort_index,
+                created_at
+            ],
+        )
+        .map_err(|err| format!("failed to insert collection membership: {}", err))
+}
+
+pub(crate) fn sync_item_primary_collection_in_tx(
+    transaction: &Transaction<'_>,
+    item_id: &str,
+    preferred_collection_id: Option<&str>,
+    updated_at: i64,
+) -> Result<(), String> {
+    let current_collection_id = transaction
+        .query_row(
+            "SELECT collection_id FROM items WHERE id = ?1",
+            params![item_id],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()
+        .map_err(|err| {
+            format!(
+                "failed to read item while syncing primary collection: {}",
+                err
+            )
+        })?
+        .ok_or_else(|| {
+            format!(
+                "item not found while syncing primary collection: {}",
+                item_id
+            )
+        })?;
+
+    let preferred_valid = match preferred_collection_id {
+        Some(preferred) => collection_membership_exists_in_tx(transaction, item_id, preferred)?,
+        None => false,
+    };
+    let current_valid = match current_collection_id.as_deref() {
+        Some(current_id) => collection_membership_exists_in_tx(transaction, item_id, current_id)?,
+        None => false,
+    };
+
+    let next_collection_id = if preferred_valid {
+        preferred_collection_id.map(str::to_string)
+    } else if current_valid {
+        current_collection_id
+    } else {
+        transaction
+            .query_row(
+                "SELECT collection_id
+                 FROM collection_items
+                 WHERE item_id = ?1
+                 ORDER BY sort_index ASC, created_at ASC, id ASC
+                 LIMIT 1",
+                params![item_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(|err| format!("failed to resolve fallback primary collection: {}", err))?
+    };
+
+    transaction
+        .execute(
+            "UPDATE items
+             SET collection_id = ?1,
+                 updated_at = ?2
+             WHERE id = ?3",
+            params![next_collection_id.as_deref(), updated_at, item_id],
+        )
+        .map_err(|err| format!("failed to sync item primary collection: {}", err))?;
+
+    Ok(())
+}
+
+/// Moving re-targets the existing membership row, so its `created_at` ("added at") carries
+/// over to the target collection. If the item is already in the target, that membership
+/// and its timestamp win and the source row is dropped.
+#[tauri::command]
+pub(crate) fn move_collection_item_memberships(
+    item_ids: Vec<String>,
+    source_collection_id: Option<String>,
+    target_collection_id: Option<String>,
+) -> Result<UpdateCollectionMembershipsResult, String> {
+    let normalized_item_ids = normalize_item_ids_input(item_ids);
+    let normalized_source_collection_id = normalize_optional_trimmed_id(source_collection_id);
+    let normalized_target_collection_id = normalize_optional_trimmed_id(target_collection_id);
+
+    let updated_at = Utc::now().timestamp_millis();
+    if normalized_item_ids.is_empty() {
+        return Ok(UpdateCollectionMembershipsResult {
+            created_rows: 0,
+            updated_rows: 0,
+            deleted_rows: 0,
+            skipped_rows: 0,
+            updated_at,
+        });
+    }
+
+    if normalized_source_collection_id == normalized_target_collection_id
+        && normalized_source_collection_id.is_some()
+    {
+        return Ok(UpdateCollectionMembershipsResult {
+            created_rows: 0,
+            updated_rows: 0,
+            deleted_rows: 0,
+            skipped_rows: normalized_item_ids.len(),
+            updated_at,
+        });
+    }
+
+    with_db_transaction(|transaction| {
+        move_collection_item_memberships_in_tx(
+            transaction,
+            &normalized_item_ids,
+            normalized_source_collection_id.as_deref(),
+            normalized_target_collection_id.as_deref(),
+            updated_at,
+        )
+    })
+}
+
+pub(crate) fn move_collection_item_memberships_in_tx(
+    transaction: &Transaction<'_>,
+    item_ids: &[String],
+    source_collection_id: Option<&str>,
+    target_collection_id: Option<&str>,
+    updated_at: i64,
+) -> Result<UpdateCollectionMembershipsResult, String> {
+    if let Some(target_id) = target_collection_id {
+        validate_collection_exists_in_tx(transaction, target_id)?;
+    }
+    if let Some(source_id) = source_collection_id {
+        validate_collection_exists_in_tx(transaction, source_id)?;
+    }
+
+    let mut created_rows = 0usize;
+    let mut updated_rows = 0usize;
+    let mut deleted_rows = 0usize;
+    let mut skipped_rows = 0usize;
+    let audit_enabled = item_audit_enabled(transaction)?;
+
+    for item_id in item_ids {
+        let source_membership =
+            resolve_source_membership_for_move_in_tx(transaction, item_id, source_collection_id)?;
+        if audit_enabled {
+            let previous_collection_id = source_membership
+                .as_ref()
+                .map(|(_membership_id, current_collection_id)| current_collection_id.as_str());
+            record_item_audit_in_tx(
+                transaction,
+                item_id,
+                "collection",
+                previous_collection_id,
+                target_collection_id,
+                updated_at,
+            )?;
+        }
+
+        match (source_membership, target_collection_id) {
+            (None, None) => {
+                skipped_rows += 1;
+            }
+            (None, Some(target_id)) => {
+                let next_sort_index =
+                    next_collection_item_sort_index_in_tx(transaction, target_id)?;
+                let inserted = insert_collection_membership_in_tx(
+                    transaction,
+                    item_id,
+                    target_id,
+                    next_sort_index,
+                    updated_at,
+                )?;
+                if inserted == 0 {
+                    skipped_rows += 1;
+                } else {
+                    created_rows += inserted;
+                }
+                sync_item_primary_collection_in_tx(
+                    transaction,
+                    item_id,
+                    Some(target_id),
+                    updated_at,
+                )?;
+            }
+            (Some((_membership_id, current_collection_id)), Some(target_id)) => {
+                if current_collection_id == target_id {
+                    skipped_rows += 1;
+                    sync_item_primary_collection_in_tx(
+                        transaction,
+                        item_id,
+                        Some(target_id),
+                        updated_at,
+                    )?;
+                    continue;
+                }
+
+                let target_exists =
+                    collection_membership_exists_in_tx(transaction, item_id, target_id)?;
+                if target_exists {
+                    let affected = transaction
+                        .execute(
+                            "DELETE FROM collection_items
+                             WHERE item_id = ?1 AND collection_id = ?2",
+                            params![item_id, current_collection_id],
+                        )
+                        .map_err(|err| {
+                            format!(
+                                "failed to collapse duplicate membership during move: {}",
+                                err
+                            )
+                        })?;
+                    if affected == 0 {
+                        skipped_rows += 1;
+                    } else {
+                        deleted_rows += affected;
+                    }
+                } else {
+                    let next_sort_index =
+                        next_collection_item_sort_index_in_tx(transaction, target_id)?;
+                    let affected = transaction
+                        .execute(
+                            "UPDATE collection_items
+                             SET collection_id = ?1,
+                                 sort_index = ?2
+                             WHERE item_id = ?3 AND collection_id = ?4",
+                            params![target_id, next_sort_index, item_id, current_collection_id],
+                        )
+                        .map_err(|err| format!("failed to move collection membership: {}", err))?;
+                    if affected == 0 {
+                        skipped_rows += 1;
+                    } else {
+                        updated_rows += affected;
+                    }
+                }
+
+                sync_item_primary_collection_in_tx(
+                    transaction,
+                    item_id,
+                    Some(target_id),
+                    updated_at,
+                )?;
+            }
+            (Some((_membership_id, current_collection_id)), None) => {
+                let affected = transaction
+                    .execute(
+                        "DELETE FROM collection_items
+                         WHERE item_id = ?1 AND collection_id = ?2",
+                        params![item_id, current_collection_id],
+                    )
+                    .map_err(|err| format!("failed to remove collection membership: {}", err))?;
+                if affected == 0 {
+                    skipped_rows += 1;
+                } else {
+                    deleted_rows += affected;
+                }
+                sync_item_primary_collection_in_tx(transaction, item_id, None, updated_at)?;
+            }
+        }
+    }
+
+    Ok(UpdateCollectionMembershipsResult {
+        created_rows,
+        updated_rows,
+        deleted_rows,
+        skipped_rows,
+        updated_at,
+    })
+}
+
+/// Adding always stamps a fresh membership `created_at` (the collection's "added at"),
+/// even if the item was previously in another collection.
+#[tauri::command]
+pub(crate) fn add_items_to_collection(
+    item_ids: Vec<String>,
+    collection_id: String,
+) -> Result<UpdateCollectionMembershipsResult, String> {
+    let normalized_item_ids = normalize_item_ids_input(item_ids);
+    let normalized_collection_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    let updated_at = Utc::now().timestamp_millis();
+
+    if normalized_item_ids.is_empty() {
+        return Ok(UpdateCollectionMembershipsResult {
+            created_rows: 0,
+            updated_rows: 0,
+            deleted_rows: 0,
+            skipped_rows: 0,
+            updated_at,
+        });
+    }
+
+    with_db_transaction(|transaction| {
+        add_items_to_collection_in_tx(
+            transaction,
+            &normalized_item_ids,
+            &normalized_collection_id,
+            updated_at,
+        )
+    })
+}
+
+pub(crate) fn add_items_to_collection_in_tx(
+    transaction: &Transaction<'_>,
+    item_ids: &[String],
+    collection_id: &str,
+    updated_at: i64,
+) -> Result<UpdateCollectionMembershipsResult, String> {
+    validate_collection_exists_in_tx(transaction, collection_id)?;
+
+    let mut created_rows = 0usize;
+    let mut skipped_rows = 0usize;
+
+    for item_id in item_ids {
+        let next_sort_index = next_collection_item_sort_index_in_tx(transaction, collection_id)?;
+        let inserted = insert_collection_membership_in_tx(
+            transaction,
+            item_id,
+            collection_id,
+            next_sort_index,
+            updated_at,
+        )?;
+        if inserted == 0 {
+            skipped_rows += 1;
+        } else {
+            created_rows += inserted;
+        }
+        sync_item_primary_collection_in_tx(transaction, item_id, None, updated_at)?;
+    }
+
+    Ok(UpdateCollectionMembershipsResult {
+        created_rows,
+        updated_rows: 0,
+        deleted_rows: 0,
+        skipped_rows,
+        updated_at,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn get_collection_items(
+    collection_id: String,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    sort: Option<String>,
+    include_sensitive: Option<bool>,
+    cursor: Option<String>,
+) -> Result<ItemPage, String> {
+    let normalized_collection_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    let spec = collection_item_sort_spec(sort.as_deref())?;
+    let cursor = normalize_optional_trimmed_string(cursor);
+    let (limit, offset) = normalize_item_page(limit, offset, cursor.as_deref());
+    let keyset = item_keyset_condition(spec, cursor.as_deref(), 5)?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let show_sensitive = resolve_show_sensitive(&connection, include_sensitive)?;
+    prepare_locked_collection_scope(&connection)?;
+    let (matched, sensitive_count) = connection
+        .query_row(
+            &format!(
+                "SELECT COUNT(*), COALESCE(SUM(i.is_sensitive), 0)
+                 FROM collection_items AS cm
+                 JOIN items AS i ON i.id = cm.item_id
+                 WHERE cm.collection_id = ?1 AND i.is_archived = 0 AND {}",
+                LOCKED_SCOPE_VISIBLE_CONDITION
+            ),
+            params![&normalized_collection_id],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .map_err(|err| format!("failed to count collection items: {}", err))?;
+    let hidden_count = hidden_sensitive_count(show_sensitive, sensitive_count);
+    let items = query_db_item_rows_with(
+        &connection,
+        &format!(
+            "{}
+             JOIN collection_items AS cm ON cm.item_id = i.id AND cm.collection_id = ?1
+             WHERE i.is_archived = 0
+               AND (?4 OR i.is_sensitive = 0)
+               AND {}
+               AND {}
+             GROUP BY i.id
+             ORDER BY {}
+             LIMIT ?2 OFFSET ?3",
+            DB_ITEM_ROW_WITH_ADDED_AT_SELECT,
+            LOCKED_SCOPE_VISIBLE_CONDITION,
+            keyset.condition,
+            spec.order_by
+        ),
+        item_page_params(
+            vec![
+                normalized_collection_id.clone().into(),
+                limit.into(),
+                offset.into(),
+                show_sensitive.into(),
+            ],
+            keyset,
+        ),
+        db_item_row_with_added_at_from_row,
+    )?;
+
+    build_item_page(
+        spec,
+        items,
+        matched - hidden_count,
+        hidden_count,
+        limit,
+        offset,
+    )
+}
+
+#[tauri::command]
+pub(crate) fn reorder_collection_items(
+    collection_id: String,
+    ordered_item_ids: Vec<String>,
+) -> Result<UpdateCollectionOrderResult, String> {
+    let normalized_collection_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    let normalized_item_ids = normalize_item_ids_input(ordered_item_ids);
+    let updated_at = Utc::now().timestamp_millis();
+
+    if normalized_item_ids.is_empty() {
+        return Ok(UpdateCollectionOrderResult {
+            updated_rows: 0,
+            skipped_rows: 0,
+            updated_at,
+        });
+    }
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    validate_collection_exists_in_tx(&transaction, &normalized_collection_id)?;
+
+    let mut updated_rows = 0usize;
+    let mut skipped_rows = 0usize;
+    let mut next_sort_index = 0i64;
+    for item_id in &normalized_item_ids {
+        let affected = transaction
+            .execute(
+                "UPDATE collection_items
+                 SET sort_index = ?1
+                 WHERE collection_id = ?2 AND item_id = ?3 AND is_pinned = 0",
+                params![next_sort_index, normalized_collection_id, item_id],
+            )
+            .map_err(|err| format!("failed to reorder collection_items row: {}", err))?;
+        if affected == 0 {
+            skipped_rows += 1;
+        } else {
+            updated_rows += affected;
+            next_sort_index += 1;
+        }
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit sqlite transaction: {}", err))?;
+
+    Ok(UpdateCollectionOrderResult {
+        updated_rows,
+        skipped_rows,
+        updated_at,
+    })
+}
+
+pub(crate) const COLLECTION_LAYOUT_VERSION: i64 = 1;
+
+pub(crate) fn load_collection_layout_in_tx(
+    transaction: &rusqlite::Transaction<'_>,
+    collection_id: &str,
+) -> Result<Vec<CollectionLayoutEntry>, String> {
+    let mut stmt = transaction
+        .prepare(
+            "SELECT item_id, sort_index, is_pinned, custom_title
+             FROM collection_items
+             WHERE collection_id = ?1
+             ORDER BY is_pinned DESC, sort_index ASC, created_at ASC, item_id ASC",
+        )
+        .map_err(|err| format!("failed to prepare collection layout query: {}", err))?;
+    let rows = stmt
+        .query_map(params![collection_id], |row| {
+            Ok(CollectionLayoutEntry {
+                item_id: row.get(0)?,
+                sort_index: row.get(1)?,
+                is_pinned: row.get::<_, i64>(2)? != 0,
+                custom_title: row.get(3)?,
+            })
+        })
+        .map_err(|err| format!("failed to query collection layout: {}", err))?;
+    let mut entries = Vec::new();
+    for row_result in rows {
+        entries.push(
+            row_result.map_err(|err| format!("failed to read collection layout row: {}", err))?,
+        );
+    }
+    Ok(entries)
+}
+
+#[tauri::command]
+pub(crate) fn export_collection_layout(collection_id: String) -> Result<String, String> {
+    let normalized_collection_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    validate_collection_exists_in_tx(&transaction, &normalized_collection_id)?;
+    let items = load_collection_layout_in_tx(&transaction, &normalized_collection_id)?;
+    drop(transaction);
+
+    let layout = CollectionLayoutFile {
+        version: COLLECTION_LAYOUT_VERSION,
+        collection_id: normalized_collection_id,
+        exported_at: Utc::now().timestamp_millis(),
+        items,
+    };
+    serde_json::to_string(&layout)
+        .map_err(|err| format!("failed to serialize collection layout: {}", err))
+}
+
+#[tauri::command]
+pub(crate) fn apply_collection_layout(
+    collection_id: String,
+    layout_json: String,
+) -> Result<ApplyCollectionLayoutResult, String> {
+    let normalized_collection_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    let layout: CollectionLayoutFile = serde_json::from_str(&layout_json)
+        .map_err(|err| format!("failed to parse collection layout: {}", err))?;
+    if layout.version > COLLECTION_LAYOUT_VERSION {
+        return Err(format!(
+            "collection layout version {} is newer than supported version {}",
+            layout.version, COLLECTION_LAYOUT_VERSION
+        ));
+    }
+    let updated_at = Utc::now().timestamp_millis();
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    validate_collection_exists_in_tx(&transaction, &normalized_collection_id)?;
+    let current = load_collection_layout_in_tx(&transaction, &normalized_collection_id)?;
+    let mut remaining: BTreeSet<String> =
+        current.iter().map(|entry| entry.item_id.clone()).collect();
+
+    let mut applied = 0usize;
+    let mut skipped = 0usize;
+    let mut next_sort_index = 0i64;
+    for entry in &layout.items {
+        let Some(item_id) = normalize_trimmed_id(&entry.item_id) else {
+            skipped += 1;
+            continue;
+        };
+        if !remaining.remove(&item_id) {
+            skipped += 1;
+            continue;
+        }
+        let custom_title = entry
+            .custom_title
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+        transaction
+            .execute(
+                "UPDATE collection_items
+                 SET sort_index = ?1, is_pinned = ?2, custom_title = ?3
+                 WHERE collection_id = ?4 AND item_id = ?5",
+                params![
+                    next_sort_index,
+                    i64::from(entry.is_pinned),
+                    custom_title,
+                    normalized_collection_id,
+                    item_id
+                ],
+            )
+            .map_err(|err| format!("failed to apply collection layout row: {}", err))?;
+        applied += 1;
+        next_sort_index += 1;
+    }
+
+    let mut appended = 0usize;
+    for entry in current
+        .iter()
+        .filter(|entry| remaining.contains(&entry.item_id))
+    {
+        transaction
+            .execute(
+                "UPDATE collection_items
+                 SET sort_index = ?1
+                 WHERE collection_id = ?2 AND item_id = ?3",
+                params![next_sort_index, normalized_collection_id, entry.item_id],
+            )
+            .map_err(|err| format!("failed to append collection layout row: {}", err))?;
+        appended += 1;
+        next_sort_index += 1;
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit sqlite transaction: {}", err))?;
+
+    Ok(ApplyCollectionLayoutResult {
+        applied,
+        skipped,
+        appended,
+        updated_at,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn set_collection_cover(
+    collection_id: String,
+    item_id: Option<String>,
+) -> Result<i64, String> {
+    let normalized_collection_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    let normalized_item_id = normalize_optional_trimmed_id(item_id);
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    validate_collection_exists_in_tx(&transaction, &normalized_collection_id)?;
+
+    if let Some(item_id) = normalized_item_id.as_deref() {
+        let subtree_ids =
+            collect_collection_subtree_ids_in_tx(&transaction, &normalized_collection_id)?;
+        let mut is_member = false;
+        for subtree_collection_id in &subtree_ids {
+            if collection_membership_exists_in_tx(&transaction, item_id, subtree_collection_id)? {
+                is_member = true;
+                break;
+            }
+        }
+        if !is_member {
+            return Err(
+                "cover item must belong to the collection or one of its descendants".to_string(),
+            );
+        }
+    }
+
+    let updated_at = Utc::now().timestamp_millis();
+    transaction
+        .execute(
+            "UPDATE collections
+             SET cover_item_id = ?1,
+                 updated_at = ?2
+             WHERE id = ?3",
+            params![normalized_item_id, updated_at, normalized_collection_id],
+        )
+        .map_err(|err| format!("failed to update collection cover: {}", err))?;
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit sqlite transaction: {}", err))?;
+    Ok(updated_at)
+}
+
+#[tauri::command]
+pub(crate) fn get_collection_covers() -> Result<Vec<CollectionCover>, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let mut covers: Vec<CollectionCover> = resolve_collection_covers(&connection)?
+        .into_values()
+        .collect();
+    covers.sort_by(|left, right| left.collection_id.cmp(&right.collection_id));
+    Ok(covers)
+}
+
+#[tauri::command]
+pub(crate) fn set_collection_icon(
+    collection_id: String,
+    icon: String,
+    icon_type: String,
+) -> Result<i64, String> {
+    let normalized_collection_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    let normalized_icon_type = icon_type.trim().to_string();
+    let normalized_icon = normalize_collection_text_icon(&icon, &normalized_icon_type)?;
+    replace_collection_icon(
+        &normalized_collection_id,
+        &normalized_icon,
+        &normalized_icon_type,
+    )
+}
+
+#[tauri::command]
+pub(crate) async fn set_collection_icon_image(
+    collection_id: String,
+    bytes: Vec<u8>,
+    ext: Option<String>,
+) -> Result<DbCollectionRow, String> {
+    let normalized_collection_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    if let Some(ext) = ext.as_deref() {
+        let normalized_ext = normalize_ext(ext);
+        if item_type_for_extension(&normalized_ext) != "image" {
+            return Err(format!(
+                "unsupported collection icon extension: {}",
+                normalized_ext
+            ));
+        }
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let icon_path = store_collection_icon_bytes(&bytes)?;
+        let icon = icon_path.to_string_lossy().to_string();
+        replace_collection_icon(&normalized_collection_id, &icon, "image")?;
+
+        let connection = open_db_connection()?;
+        query_db_collection_rows(&connection, true)?
+            .into_iter()
+            .find(|collection| collection.id == normalized_collection_id)
+            .ok_or_else(|| "collection not found after updating icon".to_string())
+    })
+    .await
+    .map_err(|err| format!("collection icon thread join failed: {}", err))?
+}
+
+pub(crate) fn markdown_export_file_stem(name: &str) -> String {
+    let stem: String = name
+        .trim()
+        .chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let stem = stem.trim_matches('-');
+    if stem.is_empty() {
+        "collection".to_string()
+    } else {
+        stem.chars().take(64).collect()
+    }
+}
+
+pub(crate) fn escape_markdown_link_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' | '[' | ']' | '*' | '_' | '`' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            '\r' | '\n' => escaped.push(' '),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+pub(crate) fn escape_markdown_link_target(target: &str) -> String {
+    target
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+pub(crate) fn write_markdown_blockquote(output: &mut String, indent: &str, text: &str) {
+    for line in text.trim().lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            output.push_str(&format!("{}>\n", indent));
+        } else {
+            output.push_str(&format!("{}> {}\n", indent, line));
+        }
+    }
+}
+
+pub(crate) fn load_markdown_export_collections(
+    connection: &Connection,
+    root_collection_id: &str,
+    include_subcollections: bool,
+) -> Result<Vec<(usize, DbCollectionRow)>, String> {
+    let mut root = None;
+    let mut children_by_parent: HashMap<String, Vec<DbCollectionRow>> = HashMap::new();
+    for collection in query_db_collection_rows(connection, true)? {
+        if collection.id == root_collection_id {
+            root = Some(collection);
+        } else if include_subcollections && !collection.is_archived {
+            if let Some(parent_id) = collection.parent_id.clone() {
+                children_by_parent
+                    .entry(parent_id)
+                    .or_default()
+                    .push(collection);
+            }
+        }
+    }
+    let root = root.ok_or_else(|| "collection not found".to_string())?;
+
+    let mut ordered = Vec::new();
+    let mut visited_ids = BTreeSet::new();
+    let mut stack = vec![(0usize, root)];
+    while let Some((depth, collection)) = stack.pop() {
+        if !visited_ids.insert(collection.id.clone()) {
+            continue;
+        }
+        if let Some(children) = children_by_parent.remove(&collection.id) {
+            for child in children.into_iter().rev() {
+                stack.push((depth + 1, child));
+            }
+        }
+        ordered.push((depth, collection));
+    }
+    Ok(ordered)
+}
+
+pub(crate) fn export_collection_markdown_internal(
+    collection_id: &str,
+    include_subcollections: bool,
+    path: Option<PathBuf>,
+    export_assets: bool,
+) -> Result<ExportCollectionMarkdownResult, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let collections =
+        load_markdown_export_collections(&connection, collection_id, include_subcollections)?;
+    let root_name = collections
+        .first()
+        .map(|(_, collection)| collection.name.clone())
+        .unwrap_or_default();
+
+    let output_path = match path {
+        Some(path) => {
+            ensure_path_outside_storage(&path)?;
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                fs::create_dir_all(parent).map_err(|err| {
+                    format!(
+                        "failed to create export directory {}: {}",
+                        parent.display(),
+                        err
+                    )
+                })?;
+            }
+            path
+        }
+        None => ensure_exports_root_internal()?.join(format!(
+            "{}-{}.md",
+            markdown_export_file_stem(&root_name),
+            Utc::now().format("%Y%m%d-%H%M%S")
+        )),
+    };
+    let output_stem = output_path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| "collection".to_string());
+    let assets_dir_name = format!("{}_assets", output_stem);
+    let assets_dir = output_path
+        .parent()
+        .map(|parent| parent.join(&assets_dir_name))
+        .unwrap_or_else(|| PathBuf::from(&assets_dir_name));
+
+    let mut stmt = connection
+        .prepare(
+            "SELECT COALESCE(NULLIF(TRIM(ci.custom_title), ''), i.title),
+                    COALESCE(NULLIF(TRIM(ci.custom_description), ''), i.description),
+                    i.type,
+                    i.url,
+                    i.vault_key,
+                    i.vault_path,
+                    (
+                        SELECT GROUP_CONCAT(ordered_tags.name, char(31))
+                        FROM (
+                            SELECT t.name
+                            FROM item_tags it
+                            JOIN tags t ON t.id = it.tag_id
+                            WHERE it.item_id = i.id
+                            ORDER BY t.sort_index ASC, t.name COLLATE NATURAL_TITLE ASC
+                        ) ordered_tags
+                    ),
+                    i.filename
+             FROM collection_items ci
+             JOIN items i ON i.id = ci.item_id
+             WHERE ci.collection_id = ?1
+               AND i.is_archived = 0
+             ORDER BY ci.is_pinned DESC, ci.sort_index ASC, ci.created_at ASC, i.id ASC",
+        )
+        .map_err(|err| format!("failed to prepare markdown export item query: {}", err))?;
+
+    let mut output = String::new();
+    let mut item_count = 0usize;
+    let mut exported_assets: HashMap<String, String> = HashMap::new();
+    let mut used_asset_names = BTreeSet::new();
+    for (depth, collection) in &collections {
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(&format!(
+            "{} {}\n\n",
+            "#".repeat((depth + 1).min(6)),
+            collection.name.trim()
+        ));
+        if let Some(description) = collection
+            .description
+            .as_deref()
+            .filter(|description| !description.trim().is_empty())
+        {
+            output.push_str(description.trim());
+            output.push_str("\n\n");
+        }
+
+        let rows = stmt
+            .query_map(params![collection.id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, String>(7)?,
+                ))
+            })
+            .map_err(|err| format!("failed to query markdown export items: {}", err))?;
+
+        for row_result in rows {
+            let (title, description, item_type, url, vault_key, vault_path, tags, filename) =
+                row_result
+                    .map_err(|err| format!("failed to read markdown export item row: {}", err))?;
+            let title = if title.trim().is_empty() {
+                "Untitled".to_string()
+            } else {
+                escape_markdown_link_text(title.trim())
+            };
+
+            let link_target = if item_type == "bookmark" {
+                url.filter(|url| !url.trim().is_empty())
+                    .map(|url| escape_markdown_link_target(url.trim()))
+            } else if export_assets && !vault_key.is_empty() && Path::new(&vault_path).is_file() {
+                if let Some(relative_path) = exported_assets.get(&vault_key) {
+                    Some(relative_path.clone())
+                } else {
+                    fs::create_dir_all(&assets_dir).map_err(|err| {
+                        format!(
+                            "failed to create export assets directory {}: {}",
+                            assets_dir.display(),
+                            err
+                        )
+                    })?;
+                    let preferred_name = sanitize_export_filename(if filename.trim().is_empty() {
+                        &vault_key
+                    } else {
+                        filename.trim()
+                    });
+                    let mut asset_name = preferred_name.clone();
+                    let mut suffix = 2;
+                    while !used_asset_names.insert(asset_name.to_lowercase()) {
+                        asset_name = match preferred_name.rsplit_once('.') {
+                            Some((stem, ext)) => format!("{}-{}.{}", stem, suffix, ext),
+                            None => format!("{}-{}", preferred_name, suffix),
+                        };
+                        suffix += 1;
+                    }
+                    copy_vault_file_plaintext(
+                        Path::new(&vault_path),
+                        &assets_dir.join(&asset_name),
+                    )
+                    .map_err(|err| format!("failed to export asset {}: {}", vault_path, err))?;
+                    let relative_path =
+                        escape_markdown_link_target(&format!("{}/{}", assets_dir_name, asset_name));
+                    exported_assets.insert(vault_key, relative_path.clone());
+                    Some(relative_path)
+                }
+            } else {
+                None
+            };
+
+            let mut line = match link_target {
+                Some(target) => format!("- [{}]({})", title, target),
+                None => format!("- {}", title),
+            };
+            for tag in tags
+                .as_deref()
+                .unwrap_or_default()
+                .split('\u{1f}')
+                .filter(|tag| !tag.is_empty())
+            {
+                line.push_str(&format!(" `{}`", tag.replace('`', "'")));
+            }
+            output.push_str(&line);
+            output.push('\n');
+            if let Some(description) =
+                description.filter(|description| !description.trim().is_empty())
+            {
+                write_markdown_blockquote(&mut output, "  ", &description);
+            }
+            item_count += 1;
+        }
+    }
+
+    fs::write(&output_path, output).map_err(|err| {
+        format!(
+            "failed to write markdown export {}: {}",
+            output_path.display(),
+            err
+        )
+    })?;
+
+    Ok(ExportCollectionMarkdownResult {
+        path: path_to_string(&output_path)?,
+        collections: collections.len(),
+        items: item_count,
+        assets: exported_assets.len(),
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn export_collection_markdown(
+    collection_id: String,
+    include_subcollections: Option<bool>,
+    path: Option<String>,
+    export_assets: Option<bool>,
+) -> Result<ExportCollectionMarkdownResult, String> {
+    let normalized_collection_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    let output_path = path
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .map(|path| path_from_ipc_string(&path));
+
+    tauri::async_runtime::spawn_blocking(move || {
+        export_collection_markdown_internal(
+            &normalized_collection_id,
+            include_subcollections.unwrap_or(true),
+            output_path,
+            export_assets.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|err| format!("markdown export thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn set_collection_archived(id: String, archived: bool) -> Result<i64, String> {
+    let normalized_id =
+        normalize_trimmed_id(&id).ok_or_else(|| "collection id cannot be empty".to_string())?;
+    if normalized_id == DEFAULT_ROOT_COLLECTION_ID && archived {
+        return Err("the root collection cannot be archived".to_string());
+    }
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let updated_at = Utc::now().timestamp_millis();
+    let affected_rows = connection
+        .execute(
+            "UPDATE collections
+             SET is_archived = ?1,
+                 updated_at = ?2
+             WHERE id = ?3",
+            params![i64::from(archived), updated_at, normalized_id],
+        )
+        .map_err(|err| format!("failed to update collection archive flag: {}", err))?;
+
+    if affected_rows == 0 {
+        return Err("collection not found while updating archive flag".to_string());
+    }
+
+    Ok(updated_at)
+}
+
+#[tauri::command]
+pub(crate) fn set_collection_lock(
+    collection_id: String,
+    passphrase: String,
+) -> Result<i64, String> {
+    let normalized_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    if passphrase.chars().count() < COLLECTION_PASSPHRASE_MIN_CHARS {
+        return Err(format!(
+            "passphrase must be at least {} characters",
+            COLLECTION_PASSPHRASE_MIN_CHARS
+        ));
+    }
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let exists = connection
+        .query_row(
+            "SELECT 1 FROM collections WHERE id = ?1",
+            params![&normalized_id],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to read collection: {}", err))?
+        .is_some();
+    if !exists {
+        return Err("collection not found".to_string());
+    }
+    let setting_key = collection_lock_setting_key(&normalized_id);
+    if read_app_setting(&connection, &setting_key)?.is_some()
+        && !active_unlocked_collections(&connection)?.contains(&normalized_id)
+    {
+        return Err("unlock the collection before changing its passphrase".to_string());
+    }
+
+    let mut salt = [0_u8; VAULT_ENCRYPTION_SALT_BYTES];
+    OsRng.fill_bytes(&mut salt);
+    let hash = derive_passphrase_key(&passphrase, &salt)?;
+    write_app_setting(
+        &connection,
+        &setting_key,
+        &format!("{}:{}", hex_encode(&salt), hex_encode(&hash)),
+    )?;
+    UNLOCKED_COLLECTIONS
+        .lock()
+        .map_err(|_| "collection unlock state poisoned".to_string())?
+        .remove(&normalized_id);
+    Ok(Utc::now().timestamp_millis())
+}
+
+#[tauri::command]
+pub(crate) fn unlock_collection(collection_id: String, passphrase: String) -> Result<bool, String> {
+    let normalized_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    verify_collection_passphrase(&connection, &normalized_id, &passphrase)?;
+    UNLOCKED_COLLECTIONS
+        .lock()
+        .map_err(|_| "collection unlock state poisoned".to_string())?
+        .insert(normalized_id, Utc::now().timestamp_millis());
+    Ok(true)
+}
+
+#[tauri::command]
+pub(crate) fn lock_collection(collection_id: String) -> Result<bool, String> {
+    let normalized_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    Ok(UNLOCKED_COLLECTIONS
+        .lock()
+        .map_err(|_| "collection unlock state poisoned".to_string())?
+        .remove(&normalized_id)
+        .is_some())
+}
+
+#[tauri::command]
+pub(crate) fn remove_collection_lock(
+    collection_id: String,
+    passphrase: String,
+) -> Result<bool, String> {
+    let normalized_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    verify_collection_passphrase(&connection, &normalized_id, &passphrase)?;
+    connection
+        .execute(
+            "DELETE FROM app_settings WHERE key = ?1",
+            params![collection_lock_setting_key(&normalized_id)],
+        )
+        .map_err(|err| format!("failed to remove collection lock: {}", err))?;
+    UNLOCKED_COLLECTIONS
+        .lock()
+        .map_err(|_| "collection unlock state poisoned".to_string())?
+        .remove(&normalized_id);
+    Ok(true)
+}
+
+#[tauri::command]
+pub(crate) fn get_collection_lock_state() -> Result<CollectionLockState, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let unlocked = active_unlocked_collections(&connection)?;
+    let locked_collection_ids = load_locked_collection_ids(&connection)?;
+    let unlocked_collection_ids = locked_collection_ids
+        .iter()
+        .filter(|collection_id| unlocked.contains(*collection_id))
+        .cloned()
+        .collect();
+    Ok(CollectionLockState {
+        locked_collection_ids,
+        unlocked_collection_ids,
+        unlock_timeout_minutes: collection_unlock_timeout_minutes(&connection)?,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn set_collection_unlock_timeout(minutes: u32) -> Result<i64, String> {
+    if minutes == 0 {
+        return Err("unlock timeout must be at least one minute".to_string());
+    }
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    write_app_setting(
+        &connection,
+        SETTING_COLLECTION_UNLOCK_TIMEOUT_MINUTES,
+        &minutes.to_string(),
+    )?;
+    Ok(i64::from(minutes))
+}
+
+#[tauri::command]
+pub(crate) fn get_collection_counts(
+    include_archived: Option<bool>,
+) -> Result<CollectionCounts, String> {
+    let include_archived = include_archived.unwrap_or(false);
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    prepare_locked_collection_scope(&connection)?;
+
+    let mut stmt = connection
+        .prepare(
+            "SELECT ci.collection_id, COUNT(DISTINCT ci.item_id)
+             FROM collection_items AS ci
+             JOIN items AS i ON i.id = ci.item_id
+             WHERE ci.collection_id NOT IN (SELECT id FROM temp.locked_collection_scope)
+               AND (?1 OR i.is_archived = 0)
+             GROUP BY ci.collection_id
+             ORDER BY ci.collection_id",
+        )
+        .map_err(|err| format!("failed to prepare collection counts query: {}", err))?;
+    let rows = stmt
+        .query_map(params![include_archived], |row| {
+            Ok(LibraryCountEntry {
+                key: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })
+        .map_err(|err| format!("failed to query collection counts: {}", err))?;
+    let mut counts = Vec::new();
+    for row_result in rows {
+        counts.push(
+            row_result.map_err(|err| format!("failed to read collection count row: {}", err))?,
+        );
+    }
+
+    let locked_count = connection
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) FROM items AS i WHERE (?1 OR i.is_archived = 0) AND NOT {}",
+                LOCKED_SCOPE_VISIBLE_CONDITION
+            ),
+            params![include_archived],
+            |row| row.get::<_, i64>(0),
+        )
+        .map_err(|err| format!("failed to count locked items: {}", err))?;
+
+    Ok(CollectionCounts {
+        counts,
+        locked_count,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn set_collection_item_pinned(
+    collection_id: String,
+    item_id: String,
+    pinned: bool,
+) -> Result<i64, String> {
+    let normalized_collection_id = normalize_trimmed_id(&collection_id)
+        .ok_or_else(|| "collection id cannot be empty".to_string())?;
+    let normalized_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let updated_at = Utc::now().timestamp_millis();
+    let affected_rows = connection
+        .execute(
+            "UPDATE collection_items
+             SET is_pinned = ?1
+             WHERE collection_id = ?2 AND item_id = ?3",
+            params![
+                i64::from(pinned),
+                normalized_collection_id,
+                normalized_item_id
+            ],
+        )
+        .map_err(|err| format!("failed to update collection item pin: {}", err))?;
+
+    if affected_rows == 0 {
+        return Err("collection membership not found while updating pin".to_string());
+    }
+
+    Ok(updated_at)
+}
+
+pub(crate) fn normalize_items_collection_mode(value: Option<&str>) -> Result<String, String> {
+    let normalized = value
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| ITEMS_COLLECTION_MODE_ADD.to_string());
+    match normalized.as_str() {
+        ITEMS_COLLECTION_MODE_ADD
+        | ITEMS_COLLECTION_MODE_MOVE_PRIMARY
+        | ITEMS_COLLECTION_MODE_MOVE_ALL => Ok(normalized),
+        _ => Err(format!(
+            "unsupported collection update mode: {}",
+            normalized
+        )),
+    }
+}
+
+pub(crate) fn apply_item_collection_mode_in_tx(
+    transaction: &Transaction<'_>,
+    item_id: &str,
+    mode: &str,
+    target_collection_id: Option<&str>,
+    updated_at: i64,
+) -> Result<(), String> {
+    if mode == ITEMS_COLLECTION_MODE_MOVE_ALL {
+        transaction
+            .execute(
+                "DELETE FROM collection_items
+                 WHERE item_id = ?1 AND (?2 IS NULL OR collection_id <> ?2)",
+                params![item_id, target_collection_id],
+            )
+            .map_err(|err| format!("failed to remove other collection memberships: {}", err))?;
+    }
+    if let Some(target_id) = target_collection_id {
+        if !collection_membership_exists_in_tx(transaction, item_id, target_id)? {
+            let next_sort_index = next_collection_item_sort_index_in_tx(transaction, target_id)?;
+            insert_collection_membership_in_tx(
+                transaction,
+                item_id,
+                target_id,
+                next_sort_index,
+                updated_at,
+            )?;
+        }
+    }
+    let preferred_collection_id = if mode == ITEMS_COLLECTION_MODE_MOVE_ALL {
+        target_collection_id
+    } else {
+        None
+    };
+    sync_item_primary_collection_in_tx(transaction, item_id, preferred_collection_id, updated_at)
+}
+
+#[tauri::command]
+pub(crate) fn update_items_collection(
+    item_ids: Vec<String>,
+    collection_id: Option<String>,
+    mode: Option<String>,
+) -> Result<ItemsCollectionUpdateResult, String> {
+    let mode = normalize_items_collection_mode(mode.as_deref())?;
+    let normalized_item_ids = normalize_item_ids_input(item_ids);
+    let normalized_collection_id = normalize_optional_trimmed_id(collection_id);
+    let updated_at = Utc::now().timestamp_millis();
+    with_db_transaction(|transaction| {
+        update_items_collection_in_tx(
+            transaction,
+            normalized_item_ids,
+            normalized_collection_id.as_deref(),
+            mode,
+            updated_at,
+        )
+    })
+}
+
+pub(crate) fn update_items_collection_in_tx(
+    transaction: &Transaction<'_>,
+    item_ids: Vec<String>,
+    collection_id: Option<&str>,
+    mode: String,
+    updated_at: i64,
+) -> Result<ItemsCollectionUpdateResult, String> {
+    if let Some(target_id) = collection_id {
+        validate_collection_exists_in_tx(transaction, target_id)?;
+    }
+
+    let mut before = Vec::with_capacity(item_ids.len());
+    for item_id in &item_ids {
+        before.push(load_item_memberships_in_tx(transaction, item_id)?);
+    }
+
+    if mode == ITEMS_COLLECTION_MODE_MOVE_PRIMARY {
+        move_collection_item_memberships_in_tx(
+            transaction,
+            &item_ids,
+            None,
+            collection_id,
+            updated_at,
+        )?;
+    } else {
+        let audit_enabled = item_audit_enabled(transaction)?;
+        for (item_id, (_, previous_primary)) in item_ids.iter().zip(&before) {
+            apply_item_collection_mode_in_tx(
+                transaction,
+                item_id,
+                &mode,
+                collection_id,
+                updated_at,
+            )?;
+            if audit_enabled {
+                let (_, next_primary) = load_item_memberships_in_tx(transaction, item_id)?;
+                record_item_audit_in_tx(
+                    transaction,
+                    item_id,
+                    "collection",
+                    previous_primary.as_deref(),
+                    next_primary.as_deref(),
+                    updated_at,
+                )?;
+            }
+        }
+    }
+
+    let mut updated_rows = 0usize;
+    let mut items = Vec::with_capacity(item_ids.len());
+    for (item_id, (previous_ids, _)) in item_ids.into_iter().zip(before) {
+        let (next_ids, primary_collection_id) = load_item_memberships_in_tx(transaction, &item_id)?;
+        let added: Vec<String> = next_ids.difference(&previous_ids).cloned().collect();
+        let removed: Vec<String> = previous_ids.difference(&next_ids).cloned().collect();
+        updated_rows += added.len() + removed.len();
+        items.push(ItemMembershipChange {
+            item_id,
+            added,
+            removed,
+            primary_collection_id,
+        });
+    }
+
+    Ok(ItemsCollectionUpdateResult {
+        updated_rows,
+        updated_at,
+        mode,
+        items,
+    })
+}
+
+pub(crate) fn ensure_mirrored_collection_chain(
+    root_collection_id: Option<&str>,
+    relative_dir: &Path,
+    cache: &mut HashMap<PathBuf, String>,
+    collections_created: &mut usize,
+) -> Result<Option<String>, String> {
+    let mut parent_id = root_collection_id.map(str::to_string);
+    let mut current_dir = PathBuf::new();
+    for component in relative_dir.components() {
+        let name = collapse_whitespace(&component.as_os_str().to_string_lossy());
+        if name.is_empty() {
+            continue;
+        }
+        current_dir.push(component);
+        if let Some(cached_id) = cache.get(&current_dir) {
+            parent_id = Some(cached_id.clone());
+            continue;
+        }
+
+        let connection = open_db_connection()?;
+        let collection_id = match find_sibling_collection_by_name(
+            &connection,
+            parent_id.as_deref(),
+            &name,
+            None,
+        )? {
+            Some(existing_id) => existing_id,
+            None => {
+                let created = create_collection(
+                    name,
+                    parent_id.clone(),
+                    DEFAULT_ROOT_COLLECTION_ICON.to_string(),
+                    DEFAULT_ROOT_COLLECTION_COLOR.to_string(),
+                    None,
+                    None,
+                    Some(COLLECTION_NAME_CONFLICT_REUSE.to_string()),
+                )?;
+                *collections_created += 1;
+                created.id
+            }
+        };
+        cache.insert(current_dir.clone(), collection_id.clone());
+        parent_id = Some(collection_id);
+    }
+    Ok(parent_id)
+}
+
+pub(crate) fn root_collection_id_if_present() -> Result<Option<String>, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    connection
+        .query_row(
+            "SELECT id FROM collections WHERE id = ?1",
+            params![DEFAULT_ROOT_COLLECTION_ID],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to look up root collection: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    #[test]
+    fn move_collection_item_memberships_retargets_membership() {
+        let _library = TestLibrary::new();
+        let source = create_test_collection("Source", None);
+        let target = create_test_collection("Target", None);
+        insert_note("note-1", "Moving", Some(&source));
+
+        let result = move_collection_item_memberships(
+            vec!["note-1".to_string()],
+            Some(source.clone()),
+            Some(target.clone()),
+        )
+        .unwrap();
+        assert_eq!(result.updated_rows, 1);
+        assert_eq!(item_collection_ids("note-1"), vec![target.clone()]);
+        let detail = get_item("note-1".to_string()).unwrap();
+        assert_eq!(detail.item.collection_id.as_deref(), Some(target.as_str()));
+    }
+
+    #[test]
+    fn add_items_to_collection_keeps_existing_memberships() {
+        let _library = TestLibrary::new();
+        let first = create_test_collection("First", None);
+        let second = create_test_collection("Second", None);
+        insert_note("note-1", "Shared", Some(&first));
+
+        let result = add_items_to_collection(
+            vec!["note-1".to_string(), "note-1".to_string()],
+            second.clone(),
+        )
+        .unwrap();
+        assert_eq!(result.created_rows, 1);
+        let mut expected = vec![first, second.clone()];
+        expected.sort();
+        assert_eq!(item_collection_ids("note-1"), expected);
+
+        let again = add_items_to_collection(vec!["note-1".to_string()], second).unwrap();
+        assert_eq!(again.created_rows, 0);
+        assert_eq!(again.skipped_rows, 1);
+    }
+
+    #[test]
+    fn add_items_to_missing_collection_fails_without_changes() {
+        let library = TestLibrary::new();
+        insert_note("note-1", "Orphan", None);
+        assert!(add_items_to_collection(vec!["note-1".to_string()], "nope".to_string()).is_err());
+        assert_eq!(library.count("SELECT COUNT(*) FROM collection_items"), 0);
+    }
+
+    #[test]
+    fn delete_collection_waits_for_a_concurrent_membership_insert() {
+        let library = TestLibrary::new();
+        let doomed = create_test_collection("Doomed", None);
+        let keeper = create_test_collection("Keeper", None);
+        insert_note("shared", "Shared", Some(&doomed));
+        insert_note("only-doomed", "Only doomed", Some(&doomed));
+
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let writer_keeper = keeper.clone();
+        let writer = std::thread::spawn(move || {
+            let mut connection = open_db_connection().unwrap();
+            let transaction = connection
+                .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+                .unwrap();
+            add_items_to_collection_in_tx(
+                &transaction,
+                &["shared".to_string()],
+                &writer_keeper,
+                Utc::now().timestamp_millis(),
+            )
+            .unwrap();
+            locked_tx.send(()).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(300));
+            transaction.commit().unwrap();
+        });
+
+        locked_rx.recv().unwrap();
+        let result = delete_collection(doomed.clone()).unwrap();
+        writer.join().unwrap();
+
+        assert_eq!(result.deleted_collections, 1);
+        assert_eq!(result.deleted_items, 1);
+        assert_eq!(item_collection_ids("shared"), vec![keeper]);
+        assert_eq!(
+            library.count("SELECT COUNT(*) FROM items WHERE id = 'only-doomed'"),
+            0
+        );
+    }
+
+    fn collection_exists(library: &TestLibrary, collection_id: &str) -> bool {
+        library.count(&format!(
+            "SELECT COUNT(*) FROM collections WHERE id = '{}'",
+            collection_id
+        )) == 1
+    }
+
+    #[test]
+    fn root_collection_cannot_be_deleted_or_renamed() {
+        let library = TestLibrary::new();
+        insert_note("in-root", "In root", Some(DEFAULT_ROOT_COLLECTION_ID));
+
+        assert_eq!(
+            delete_collection(format!(" {} ", DEFAULT_ROOT_COLLECTION_ID))
+                .err()
+                .expect("deleting root must fail"),
+            "the root collection cannot be deleted"
+        );
+        assert_eq!(
+            update_collection_name(
+                DEFAULT_ROOT_COLLECTION_ID.to_string(),
+                "Renamed".to_string(),
+                None
+            )
+            .unwrap_err(),
+            "the root collection cannot be renamed"
+        );
+        assert_eq!(
+            update_collection_name(
+                DEFAULT_ROOT_COLLECTION_ID.to_string(),
+                "  ".to_string(),
+                None
+            )
+            .unwrap_err(),
+            "the root collection cannot be renamed"
+        );
+
+        assert!(collection_exists(&library, DEFAULT_ROOT_COLLECTION_ID));
+        assert_eq!(
+            library.count("SELECT COUNT(*) FROM collections WHERE id = 'root' AND name = 'Root'"),
+            1
+        );
+        assert_eq!(
+            item_collection_ids("in-root"),
+            vec![DEFAULT_ROOT_COLLECTION_ID.to_string()]
+        );
+    }
+
+    #[test]
+    fn deleting_a_subtree_that_contains_root_is_rejected() {
+        let library = TestLibrary::new();
+        let parent = create_test_collection("Parent", None);
+        library
+            .connection()
+            .execute(
+                "UPDATE collections SET parent_id = ?1 WHERE id = ?2",
+                params![parent, DEFAULT_ROOT_COLLECTION_ID],
+            )
+            .unwrap();
+
+        let error = delete_collection(parent.clone())
+            .err()
+            .expect("deleting a subtree with root must fail");
+        assert!(
+            error.contains("run repair_collection_tree first"),
+            "{}",
+            error
+        );
+        assert!(collection_exists(&library, &parent));
+        assert!(collection_exists(&library, DEFAULT_ROOT_COLLECTION_ID));
+    }
+
+    #[test]
+    fn ensure_default_root_collection_recreates_and_detaches_root() {
+        let library = TestLibrary::new();
+        let other = create_test_collection("Other", None);
+        let connection = library.connection();
+        connection
+            .execute(
+                "DELETE FROM collections WHERE id = ?1",
+                params![DEFAULT_ROOT_COLLECTION_ID],
+            )
+            .unwrap();
+        assert!(!collection_exists(&library, DEFAULT_ROOT_COLLECTION_ID));
+
+        ensure_default_root_collection(&connection).unwrap();
+        assert!(collection_exists(&library, DEFAULT_ROOT_COLLECTION_ID));
+        assert!(collection_exists(&library, &other));
+
+        connection
+            .execute(
+                "UPDATE collections SET parent_id = ?1 WHERE id = ?2",
+                params![other, DEFAULT_ROOT_COLLECTION_ID],
+            )
+            .unwrap();
+        ensure_default_root_collection(&connection).unwrap();
+        assert_eq!(
+            library
+                .count("SELECT COUNT(*) FROM collections WHERE id = 'root' AND parent_id IS NULL"),
+            1
+        );
+    }
+
+    #[test]
+    fn load_app_state_always_includes_root() {
+        let library = TestLibrary::new();
+        create_test_collection("Other", None);
+        library
+            .connection()
+            .execute(
+                "DELETE FROM collections WHERE id = ?1",
+                params![DEFAULT_ROOT_COLLECTION_ID],
+            )
+            .unwrap();
+        library.simulate_restart();
+
+        let state = load_app_state(None, None).unwrap();
+        assert!(state
+            .collections
+            .iter()
+            .any(|collection| collection.id == DEFAULT_ROOT_COLLECTION_ID));
+    }
+
+    const TEST_PASSPHRASE: &str = "correct horse";
+
+    fn hidden_item_ids() -> Vec<String> {
+        locked_hidden_item_ids(&open_db_connection().unwrap())
+            .unwrap()
+            .into_iter()
+            .collect()
+    }
+
+    fn visible_item_ids() -> Vec<String> {
+        let mut ids: Vec<String> = load_app_state(None, None)
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|item| item.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Locked collection with a child, an unlocked sibling, and items in each combination.
+    fn seed_locked_library() -> (String, String) {
+        let locked = create_test_collection("Private", None);
+        let locked_child = create_test_collection("Private child", Some(&locked));
+        let open = create_test_collection("Open", None);
+        insert_note("only-locked", "Only locked", Some(&locked));
+        insert_note("only-child", "Only child", Some(&locked_child));
+        insert_note("only-open", "Only open", Some(&open));
+        insert_note("loose", "Loose", None);
+        insert_note("both", "Both", Some(&locked));
+        add_items_to_collection(vec!["both".to_string()], open.clone()).unwrap();
+        set_collection_lock(locked.clone(), TEST_PASSPHRASE.to_string()).unwrap();
+        (locked, locked_child)
+    }
+
+    #[test]
+    fn locked_scope_hides_items_only_reachable_through_locked_collections() {
+        let library = TestLibrary::new();
+        let (locked, locked_child) = seed_locked_library();
+
+        let connection = library.connection();
+        assert_eq!(
+            prepare_locked_collection_scope(&connection).unwrap(),
+            vec![locked.clone()]
+        );
+        let mut scope: Vec<String> = connection
+            .prepare("SELECT id FROM temp.locked_collection_scope")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        scope.sort();
+        let mut expected_scope = vec![locked, locked_child];
+        expected_scope.sort();
+        assert_eq!(scope, expected_scope);
+
+        assert_eq!(hidden_item_ids(), vec!["only-child", "only-locked"]);
+        assert_eq!(visible_item_ids(), vec!["both", "loose", "only-open"]);
+    }
+
+    #[test]
+    fn unlocking_and_relocking_toggles_the_scope() {
+        let _library = TestLibrary::new();
+        let (locked, _) = seed_locked_library();
+
+        assert_eq!(
+            unlock_collection(locked.clone(), "wrong passphrase".to_string()).unwrap_err(),
+            "incorrect collection passphrase"
+        );
+        assert_eq!(hidden_item_ids().len(), 2);
+
+        assert!(unlock_collection(locked.clone(), TEST_PASSPHRASE.to_string()).unwrap());
+        assert!(hidden_item_ids().is_empty());
+        assert_eq!(visible_item_ids().len(), 5);
+
+        lock_collection(locked).unwrap();
+        assert_eq!(hidden_item_ids(), vec!["only-child", "only-locked"]);
+    }
+
+    #[test]
+    fn verify_collection_passphrase_rejects_tampered_hashes() {
+        let library = TestLibrary::new();
+        let (locked, _) = seed_locked_library();
+        let connection = library.connection();
+        let setting_key = collection_lock_setting_key(&locked);
+        let stored = read_app_setting(&connection, &setting_key)
+            .unwrap()
+            .unwrap();
+
+        verify_collection_passphrase(&connection, &locked, TEST_PASSPHRASE).unwrap();
+
+        let truncated = &stored[..stored.len() - 2];
+        write_app_setting(&connection, &setting_key, truncated).unwrap();
+        assert_eq!(
+            verify_collection_passphrase(&connection, &locked, TEST_PASSPHRASE).unwrap_err(),
+            "incorrect collection passphrase"
+        );
+
+        write_app_setting(&connection, &setting_key, "not-hex").unwrap();
+        assert_eq!(
+            verify_collection_passphrase(&connection, &locked, TEST_PASSPHRASE).unwrap_err(),
+            "stored collection lock is invalid"
+        );
+        assert_eq!(
+            verify_collection_passphrase(&connection, "missing", TEST_PASSPHRASE).unwrap_err(),
+            "collection is not locked"
+        );
+    }
+
+    fn primary_collection_id(item_id: &str) -> Option<String> {
+        get_item(item_id.to_string()).unwrap().item.collection_id
+    }
+
+    fn sorted(mut ids: Vec<String>) -> Vec<String> {
+        ids.sort();
+        ids
+    }
+
+    /// Seeds `none` (no collections), `single` (first) and `many` (first as primary, plus
+    /// second) and returns (first, second, target).
+    fn seed_membership_modes() -> (String, String, String) {
+        let first = create_test_collection("First", None);
+        let second = create_test_collection("Second", None);
+        let target = create_test_collection("Target", None);
+        insert_note("none", "None", None);
+        insert_note("single", "Single", Some(&first));
+        insert_note("many", "Many", Some(&first));
+        add_items_to_collection(vec!["many".to_string()], second.clone()).unwrap();
+        assert_eq!(primary_collection_id("many"), Some(first.clone()));
+        (first, second, target)
+    }
+
+    fn seeded_item_ids() -> Vec<String> {
+        vec!["none".to_string(), "single".to_string(), "many".to_string()]
+    }
+
+    #[test]
+    fn update_items_collection_defaults_to_add() {
+        let _library = TestLibrary::new();
+        let (first, second, target) = seed_membership_modes();
+
+        let result =
+            update_items_collection(seeded_item_ids(), Some(target.clone()), None).unwrap();
+        assert_eq!(result.mode, ITEMS_COLLECTION_MODE_ADD);
+        assert_eq!(result.updated_rows, 3);
+        assert!(result
+            .items
+            .iter()
+            .all(|change| change.added == vec![target.clone()] && change.removed.is_empty()));
+
+        assert_eq!(item_collection_ids("none"), vec![target.clone()]);
+        assert_eq!(primary_collection_id("none"), Some(target.clone()));
+        assert_eq!(
+            item_collection_ids("single"),
+            sorted(vec![first.clone(), target.clone()])
+        );
+        assert_eq!(primary_collection_id("single"), Some(first.clone()));
+        assert_eq!(
+            item_collection_ids("many"),
+            sorted(vec![first.clone(), second, target])
+        );
+        assert_eq!(primary_collection_id("many"), Some(first));
+    }
+
+    #[test]
+    fn update_items_collection_move_primary_replaces_only_the_primary() {
+        let _library = TestLibrary::new();
+        let (first, second, target) = seed_membership_modes();
+
+        let result = update_items_collection(
+            seeded_item_ids(),
+            Some(target.clone()),
+            Some(" Move_Primary ".to_string()),
+        )
+        .unwrap();
+        assert_eq!(result.mode, ITEMS_COLLECTION_MODE_MOVE_PRIMARY);
+
+        assert_eq!(item_collection_ids("none"), vec![target.clone()]);
+        assert_eq!(item_collection_ids("single"), vec![target.clone()]);
+        assert_eq!(
+            item_collection_ids("many"),
+            sorted(vec![second.clone(), target.clone()])
+        );
+        for item_id in ["none", "single", "many"] {
+            assert_eq!(primary_collection_id(item_id), Some(target.clone()));
+        }
+        let many = result
+            .items
+            .iter()
+            .find(|change| change.item_id == "many")
+            .unwrap();
+        assert_eq!(many.added, vec![target.clone()]);
+        assert_eq!(many.removed, vec![first]);
+        assert_eq!(many.primary_collection_id, Some(target));
+    }
+
+    #[test]
+    fn update_items_collection_move_all_leaves_only_the_target() {
+        let _library = TestLibrary::new();
+        let (first, second, target) = seed_membership_modes();
+
+        let result = update_items_collection(
+            seeded_item_ids(),
+            Some(target.clone()),
+            Some(ITEMS_COLLECTION_MODE_MOVE_ALL.to_string()),
+        )
+        .unwrap();
+        assert_eq!(result.mode, ITEMS_COLLECTION_MODE_MOVE_ALL);
+        for item_id in ["none", "single", "many"] {
+            assert_eq!(item_collection_ids(item_id), vec![target.clone()]);
+            assert_eq!(primary_collection_id(item_id), Some(target.clone()));
+        }
+        let many = result
+            .items
+            .iter()
+            .find(|change| change.item_id == "many")
+            .unwrap();
+        assert_eq!(many.removed, sorted(vec![first, second]));
+    }
+
+    #[test]
+    fn update_items_collection_without_a_target_removes_by_mode() {
+        let _library = TestLibrary::new();
+        let (first, second, _target) = seed_membership_modes();
+
+        let added = update_items_collection(seeded_item_ids(), None, None).unwrap();
+        assert_eq!(added.updated_rows, 0);
+        assert_eq!(
+            item_collection_ids("many"),
+            sorted(vec![first.clone(), second.clone()])
+        );
+
+        update_items_collection(
+            seeded_item_ids(),
+            None,
+            Some(ITEMS_COLLECTION_MODE_MOVE_PRIMARY.to_string()),
+        )
+        .unwrap();
+        assert!(item_collection_ids("none").is_empty());
+        assert!(item_collection_ids("single").is_empty());
+        assert_eq!(primary_collection_id("single"), None);
+        assert_eq!(item_collection_ids("many"), vec![second.clone()]);
+        assert_eq!(primary_collection_id("many"), Some(second));
+
+        update_items_collection(
+            seeded_item_ids(),
+            None,
+            Some(ITEMS_COLLECTION_MODE_MOVE_ALL.to_string()),
+        )
+        .unwrap();
+        assert!(item_collection_ids("many").is_empty());
+        assert_eq!(primary_collection_id("many"), None);
+    }
+
+    #[test]
+    fn update_items_collection_rejects_unknown_modes_and_targets() {
+        let library = TestLibrary::new();
+        let (_first, _second, target) = seed_membership_modes();
+        let before = library.count("SELECT COUNT(*) FROM collection_items");
+
+        assert_eq!(
+            update_items_collection(seeded_item_ids(), Some(target), Some("copy".to_string()))
+                .err()
+                .as_deref(),
+            Some("unsupported collection update mode: copy")
+        );
+        assert!(
+            update_items_collection(seeded_item_ids(), Some("missing".to_string()), None).is_err()
+        );
+        assert_eq!(
+            library.count("SELECT COUNT(*) FROM collection_items"),
+            before
+        );
+    }
+
+    #[test]
+    fn collection_keyset_pages_survive_inserts_for_every_sort() {
+        for spec in ITEM_SORT_SPECS.iter().chain(COLLECTION_ITEM_SORT_SPECS) {
+            let _library = TestLibrary::new();
+            let collection = create_test_collection("Paged", None);
+            let other = create_test_collection("Other", None);
+            let insert_into = |note: (&str, &str, i64, i64), collection_id: &str| {
+                let mut input = keyset_note_input(note);
+                input.collection_id = Some(collection_id.to_string());
+                insert_item(input).unwrap();
+            };
+            for note in KEYSET_SEED_NOTES {
+                insert_into(*note, &collection);
+            }
+            insert_into(("elsewhere", "alpha", 1_700_000_001_000, 3), &other);
+            assert_keyset_walk(
+                spec.name,
+                |limit, cursor| {
+                    let page = get_collection_items(
+                        collection.clone(),
+                        Some(limit),
+                        None,
+                        Some(spec.name.to_string()),
+                        Some(true),
+                        cursor,
+                    )
+                    .unwrap();
+                    assert!(page.items.iter().all(|item| item.id != "elsewhere"));
+                    page
+                },
+                |step| {
+                    let note = KEYSET_INSERTED_NOTES.get(step)?;
+                    insert_into(*note, &collection);
+                    Some(note.0.to_string())
+                },
+            );
+        }
+    }
+
+    fn collection_added_at(collection_id: &str) -> Vec<(String, Option<i64>)> {
+        get_collection_items(
+            collection_id.to_string(),
+            None,
+            None,
+            Some("added-desc".to_string()),
+            Some(true),
+            None,
+        )
+        .unwrap()
+        .items
+        .into_iter()
+        .map(|item| (item.id, item.added_at))
+        .collect()
+    }
+
+    #[test]
+    fn moving_keeps_the_membership_timestamp_and_adding_stamps_a_new_one() {
+        let _library = TestLibrary::new();
+        let source = create_test_collection("Source", None);
+        let target = create_test_collection("Target", None);
+        insert_note("moved", "Moved", Some(&source));
+        insert_note("added", "Added", None);
+        let seeded_at = 1_700_000_000_000;
+        assert_eq!(
+            collection_added_at(&source),
+            vec![("moved".to_string(), Some(seeded_at))]
+        );
+
+        move_collection_item_memberships(
+            vec!["moved".to_string()],
+            Some(source.clone()),
+            Some(target.clone()),
+        )
+        .unwrap();
+        let before_add = Utc::now().timestamp_millis();
+        add_items_to_collection(vec!["added".to_string()], target.clone()).unwrap();
+
+        let listed = collection_added_at(&target);
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].0, "added");
+        assert!(listed[0].1.unwrap() >= before_add);
+        assert_eq!(listed[1], ("moved".to_string(), Some(seeded_at)));
+        assert!(collection_added_at(&source).is_empty());
+
+        update_items_collection(
+            vec!["moved".to_string()],
+            Some(source.clone()),
+            Some(ITEMS_COLLECTION_MODE_MOVE_PRIMARY.to_string()),
+        )
+        .unwrap();
+        assert_eq!(
+            collection_added_at(&source),
+            vec![("moved".to_string(), Some(seeded_at))]
+        );
+    }
+
+    #[test]
+    fn moving_onto_an_existing_membership_keeps_the_target_timestamp() {
+        let _library = TestLibrary::new();
+        let source = create_test_collection("Source", None);
+        let target = create_test_collection("Target", None);
+        insert_note("shared", "Shared", Some(&source));
+        add_items_to_collection(vec!["shared".to_string()], target.clone()).unwrap();
+        let target_added_at = collection_added_at(&target)[0].1;
+
+        move_collection_item_memberships(
+            vec!["shared".to_string()],
+            Some(source.clone()),
+            Some(target.clone()),
+        )
+        .unwrap();
+        assert_eq!(item_collection_ids("shared"), vec![target.clone()]);
+        assert_eq!(
+            collection_added_at(&target),
+            vec![("shared".to_string(), target_added_at)]
+        );
+    }
+
+    fn create_named_collection(
+        name: &str,
+        parent_id: Option<&str>,
+        on_name_conflict: Option<&str>,
+    ) -> Result<DbCollectionRow, String> {
+        create_collection(
+            name.to_string(),
+            parent_id.map(str::to_string),
+            DEFAULT_ROOT_COLLECTION_ICON.to_string(),
+            DEFAULT_ROOT_COLLECTION_COLOR.to_string(),
+            None,
+            None,
+            on_name_conflict.map(str::to_string),
+        )
+    }
+
+    /// Creation times are per millisecond, so tests pin them to make "oldest first" exact.
+    fn set_collection_created_at(library: &TestLibrary, collection_id: &str, created_at: i64) {
+        library
+            .connection()
+            .execute(
+                "UPDATE collections SET created_at = ?1 WHERE id = ?2",
+                params![created_at, collection_id],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn create_collection_name_conflict_modes() {
+        let library = TestLibrary::new();
+        let existing = create_named_collection("References", None, None).unwrap();
+        let count = || library.count("SELECT COUNT(*) FROM collections");
+        let before = count();
+
+        let reused = create_named_collection(" references ", None, Some("reuse")).unwrap();
+        assert_eq!(reused.id, existing.id);
+        assert_eq!(reused.name, "References");
+        assert_eq!(count(), before);
+
+        assert_eq!(
+            create_named_collection("REFERENCES", None, Some("reject"))
+                .err()
+                .as_deref(),
+            Some("a collection named \"REFERENCES\" already exists under this parent")
+        );
+        assert_eq!(count(), before);
+
+        let nested =
+            create_named_collection("References", Some(&existing.id), Some("reuse")).unwrap();
+        assert_ne!(nested.id, existing.id);
+        let duplicate = create_named_collection("References", None, Some("allow")).unwrap();
+        assert_ne!(duplicate.id, existing.id);
+        assert_eq!(count(), before + 2);
+
+        assert!(create_named_collection("Other", None, Some("merge"))
+            .err()
+            .unwrap()
+            .starts_with("unsupported collection name conflict mode: merge"));
+    }
+
+    #[test]
+    fn rename_collection_name_conflict_modes() {
+        let _library = TestLibrary::new();
+        let taken = create_test_collection("Taken", None);
+        let renamed = create_test_collection("Renamed", None);
+
+        for mode in ["reject", "reuse"] {
+            assert_eq!(
+                update_collection_name(renamed.clone(), " taken".to_string(), Some(mode.into()))
+                    .err()
+                    .as_deref(),
+                Some("a collection named \"taken\" already exists under this parent"),
+                "{}",
+                mode
+            );
+        }
+        update_collection_name(
+            renamed.clone(),
+            "RENAMED".to_string(),
+            Some("reject".into()),
+        )
+        .unwrap();
+        update_collection_name(renamed.clone(), "Taken".to_string(), None).unwrap();
+        let names: Vec<String> = [taken, renamed]
+            .iter()
+            .map(|id| {
+                load_db_collection_row(&open_db_connection().unwrap(), id)
+                    .unwrap()
+                    .name
+            })
+            .collect();
+        assert_eq!(names, ["Taken", "Taken"]);
+    }
+
+    #[test]
+    fn duplicate_sibling_groups_are_reported_once_oldest_first() {
+        let library = TestLibrary::new();
+        let parent = create_test_collection("Parent", None);
+        let mut top = Vec::new();
+        for (offset, name) in ["Refs", "refs", " REFS "].into_iter().enumerate() {
+            let id = create_test_collection(name, None);
+            set_collection_created_at(&library, &id, 1_000 - offset as i64);
+            top.push(id);
+        }
+        let nested_first = create_test_collection("Refs", Some(&parent));
+        let nested_second = create_test_collection("refs", Some(&parent));
+        set_collection_created_at(&library, &nested_first, 10);
+        set_collection_created_at(&library, &nested_second, 20);
+        create_test_collection("Unique", None);
+
+        let groups = find_duplicate_sibling_collections().unwrap();
+        assert_eq!(groups.len(), 2);
+        let top_group = groups
+            .iter()
+            .find(|group| group.parent_id.is_none())
+            .unwrap();
+        assert_eq!(
+            top_group.collection_ids,
+            vec![top[2].clone(), top[1].clone(), top[0].clone()]
+        );
+        let nested_group = groups
+            .iter()
+            .find(|group| group.parent_id.as_deref() == Some(parent.as_str()))
+            .unwrap();
+        assert_eq!(
+            nested_group.collection_ids,
+            vec![nested_first, nested_second]
+        );
+    }
+
+    #[test]
+    fn merging_duplicates_reparents_children_and_keeps_one_membership_per_item() {
+        let library = TestLibrary::new();
+        let target = create_test_collection("Refs", None);
+        let source = create_test_collection("refs", None);
+        set_collection_created_at(&library, &target, 1);
+        set_collection_created_at(&library, &source, 2);
+        let child = create_test_collection("Child", Some(&source));
+        insert_note("shared", "Shared", Some(&target));
+        add_items_to_collection(vec!["shared".to_string()], source.clone()).unwrap();
+        insert_note("only-source", "Only source", Some(&source));
+        insert_note("only-target", "Only target", Some(&target));
+
+        let group = find_duplicate_sibling_collections().unwrap();
+        assert_eq!(group.len(), 1);
+        let result = merge_duplicate_collections(group[0].collection_ids.clone()).unwrap();
+        assert_eq!(result.target_collection_id, target);
+        assert_eq!(result.merged_collections, 1);
+        assert_eq!(result.moved_memberships, 1);
+        assert_eq!(result.collapsed_memberships, 1);
+        assert_eq!(result.moved_children, 1);
+
+        assert!(!collection_exists(&library, &source));
+        let child_parent: Option<String> = library
+            .connection()
+            .query_row(
+                "SELECT parent_id FROM collections WHERE id = ?1",
+                params![&child],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(child_parent.as_deref(), Some(target.as_str()));
+        for item_id in ["shared", "only-source", "only-target"] {
+            assert_eq!(
+                item_collection_ids(item_id),
+                vec![target.clone()],
+                "{}",
+                item_id
+            );
+        }
+        assert_eq!(
+            library.count(
+                "SELECT COUNT(*) FROM (
+                   SELECT item_id, collection_id FROM collection_items
+                   GROUP BY item_id, collection_id HAVING COUNT(*) > 1
+                 )"
+            ),
+            0
+        );
+        assert_eq!(library.count("SELECT COUNT(*) FROM items"), 3);
+        assert!(find_duplicate_sibling_collections().unwrap().is_empty());
+        assert_eq!(
+            merge_duplicate_collections(vec![target]).err().as_deref(),
+            Some("at least two collections are required to merge")
+        );
+    }
+}
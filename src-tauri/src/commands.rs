@@ -0,0 +1,859 @@
+use rfd::FileDialog;
+use serde::{Deserialize, Serialize};
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+use url::Url;
+
+use crate::bookmarks::*;
+use crate::collections::*;
+use crate::db::*;
+use crate::items::*;
+use crate::thumbs::*;
+use crate::vault::*;
+
+pub(crate) const SETTING_LAST_DIALOG_DIR_PREFIX: &str = "last_dialog_dir_";
+pub(crate) const STARTUP_IMPORT_RESULTS_EVENT: &str = "startup://import-results";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DialogFileFilter {
+    pub(crate) name: String,
+    pub(crate) extensions: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StartupArgumentResult {
+    pub(crate) argument: String,
+    pub(crate) kind: String,
+    pub(crate) status: String,
+    pub(crate) item_id: Option<String>,
+    pub(crate) message: Option<String>,
+}
+
+pub(crate) enum StartupArgument {
+    File(PathBuf),
+    Bookmark(Url),
+    DeepLink,
+    Unsupported(String),
+}
+
+#[derive(Default)]
+pub(crate) struct StartupImportState(pub(crate) Mutex<Vec<StartupArgumentResult>>);
+
+pub(crate) fn path_to_string(path: &Path) -> Result<String, String> {
+    Ok(path_to_ipc_string(path))
+}
+
+#[cfg(unix)]
+pub(crate) fn raw_path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(windows)]
+pub(crate) fn raw_path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::windows::ffi::OsStrExt;
+    path.as_os_str()
+        .encode_wide()
+        .flat_map(u16::to_le_bytes)
+        .collect()
+}
+
+#[cfg(unix)]
+pub(crate) fn path_from_raw_bytes(bytes: Vec<u8>) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStringExt;
+    Some(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+}
+
+#[cfg(windows)]
+pub(crate) fn path_from_raw_bytes(bytes: Vec<u8>) -> Option<PathBuf> {
+    use std::os::windows::ffi::OsStringExt;
+    if bytes.len() % 2 != 0 {
+        return None;
+    }
+    let wide: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    Some(PathBuf::from(std::ffi::OsString::from_wide(&wide)))
+}
+
+pub(crate) fn path_to_ipc_string(path: &Path) -> String {
+    if let Some(value) = path.to_str() {
+        if !value.starts_with(RAW_PATH_IPC_PREFIX) {
+            return value.to_owned();
+        }
+    }
+    let mut encoded = RAW_PATH_IPC_PREFIX.to_string();
+    for byte in raw_path_bytes(path) {
+        if (byte.is_ascii_graphic() && byte != b'%') || byte == b' ' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+pub(crate) fn path_from_ipc_string(value: &str) -> PathBuf {
+    let Some(encoded) = value.strip_prefix(RAW_PATH_IPC_PREFIX) else {
+        return PathBuf::from(value);
+    };
+    let input = encoded.as_bytes();
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut index = 0;
+    while index < input.len() {
+        if input[index] == b'%' {
+            let decoded = encoded
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match decoded {
+                Some(byte) => {
+                    bytes.push(byte);
+                    index += 3;
+                    continue;
+                }
+                None => return PathBuf::from(value),
+            }
+        }
+        bytes.push(input[index]);
+        index += 1;
+    }
+    path_from_raw_bytes(bytes).unwrap_or_else(|| PathBuf::from(value))
+}
+
+#[cfg(windows)]
+pub(crate) fn extended_length_path(path: &Path) -> PathBuf {
+    const WINDOWS_SAFE_PATH_LEN: usize = 240;
+    let raw = path.as_os_str().to_string_lossy();
+    if raw.len() < WINDOWS_SAFE_PATH_LEN || raw.starts_with(r"\\?\") || !path.is_absolute() {
+        return path.to_path_buf();
+    }
+    let normalized = raw.replace('/', r"\");
+    match normalized.strip_prefix(r"\\") {
+        Some(unc) => PathBuf::from(format!(r"\\?\UNC\{}", unc)),
+        None => PathBuf::from(format!(r"\\?\{}", normalized)),
+    }
+}
+
+#[cfg(not(windows))]
+pub(crate) fn extended_length_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+pub(crate) fn sanitize_export_filename(name: &str) -> String {
+    const RESERVED_NAMES: [&str; 22] = [
+        "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+        "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+    ];
+    let cleaned: String = name
+        .chars()
+        .map(|ch| match ch {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            ch if ch.is_control() => '_',
+            ch => ch,
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_end_matches(['.', ' ']);
+    let (stem, ext) = match cleaned.rsplit_once('.') {
+        Some((stem, ext)) if !stem.trim().is_empty() && !ext.is_empty() => (stem, Some(ext)),
+        _ => (cleaned, None),
+    };
+    let mut stem = stem.trim().trim_end_matches(['.', ' ']).to_string();
+    if stem.is_empty() {
+        stem = "untitled".to_string();
+    }
+    let base_name = stem
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if RESERVED_NAMES.contains(&base_name.as_str()) {
+        stem.insert(0, '_');
+    }
+    match ext {
+        Some(ext) => format!("{}.{}", stem, ext),
+        None => stem,
+    }
+}
+
+pub(crate) fn focus_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Arguments that are not valid Unicode can only be file paths, so they skip the URL and
+/// option checks and keep their raw bytes.
+pub(crate) fn classify_startup_argument(raw_argument: &OsStr, cwd: &Path) -> StartupArgument {
+    let candidate = match raw_argument.to_str() {
+        Some(text) => match classify_startup_text_argument(text) {
+            Ok(candidate) => candidate,
+            Err(argument) => return argument,
+        },
+        None => PathBuf::from(raw_argument),
+    };
+    let resolved = if candidate.is_absolute() {
+        candidate
+    } else {
+        cwd.join(candidate)
+    };
+    if resolved.is_file() {
+        StartupArgument::File(resolved)
+    } else if resolved.is_dir() {
+        StartupArgument::Unsupported(format!(
+            "directories are not supported: {}",
+            resolved.display()
+        ))
+    } else {
+        StartupArgument::Unsupported(format!("file not found: {}", resolved.display()))
+    }
+}
+
+pub(crate) fn classify_startup_text_argument(
+    raw_argument: &str,
+) -> Result<PathBuf, StartupArgument> {
+    let trimmed = raw_argument.trim().trim_matches('"').trim();
+    if trimmed.is_empty() {
+        return Err(StartupArgument::Unsupported("empty argument".to_string()));
+    }
+    if trimmed
+        .get(..DEEP_LINK_SCHEME.len() + 1)
+        .map(|prefix| prefix.eq_ignore_ascii_case(&format!("{}:", DEEP_LINK_SCHEME)))
+        .unwrap_or(false)
+    {
+        return Err(StartupArgument::DeepLink);
+    }
+
+    let lowered = trimmed.to_ascii_lowercase();
+    if lowered.starts_with("http://") || lowered.starts_with("https://") {
+        return Err(match normalize_bookmark_url_input(trimmed) {
+            Ok(url) => StartupArgument::Bookmark(url),
+            Err(error) => StartupArgument::Unsupported(error),
+        });
+    }
+    if trimmed.starts_with('-') {
+        return Err(StartupArgument::Unsupported(format!(
+            "unrecognized option: {}",
+            trimmed
+        )));
+    }
+    Ok(PathBuf::from(trimmed))
+}
+
+pub(crate) async fn import_startup_arguments(
+    arguments: Vec<OsString>,
+    cwd: PathBuf,
+) -> Vec<StartupArgumentResult> {
+    let root_collection_id = match root_collection_id_if_present() {
+        Ok(root_collection_id) => root_collection_id,
+        Err(error) => {
+            log_eprintln!(
+                "[startup-import] failed to resolve root collection: {}",
+                error
+            );
+            None
+        }
+    };
+
+    let mut results = Vec::new();
+    for raw_argument in arguments {
+        let argument = path_to_ipc_string(Path::new(&raw_argument));
+        let (kind, outcome) = match classify_startup_argument(&raw_argument, &cwd) {
+            StartupArgument::DeepLink => continue,
+            StartupArgument::Unsupported(reason) => ("unsupported", Err(reason)),
+            StartupArgument::Bookmark(url) => (
+                "bookmark",
+                create_bookmark_item_internal(url, Vec::new(), root_collection_id.clone(), None)
+                    .await
+                    .map(|created| created.item_id),
+            ),
+            StartupArgument::File(path) => {
+                let collection_id = root_collection_id.clone();
+                let joined = tauri::async_runtime::spawn_blocking(move || {
+                    let thumb_policy = load_import_thumb_policy(IMPORT_THUMB_POLICY_IMMEDIATE)?;
+                    import_file_item_internal(path, collection_id, None, &thumb_policy, None, None)
+                        .map(|imported| imported.item_id)
+                })
+                .await
+                .map_err(|err| format!("startup import thread join failed: {}", err));
+                ("file", joined.and_then(|result| result))
+            }
+        };
+
+        let result = match outcome {
+            Ok(item_id) => StartupArgumentResult {
+                argument,
+                kind: kind.to_string(),
+                status: "imported".to_string(),
+                item_id: Some(item_id),
+                message: None,
+            },
+            Err(message) => {
+                log_eprintln!(
+                    "[startup-import] failed to import {}: {}",
+                    argument,
+                    message
+                );
+                StartupArgumentResult {
+                    argument,
+                    kind: kind.to_string(),
+                    status: if kind == "unsupported" {
+                        "skipped".to_string()
+                    } else {
+                        "error".to_string()
+                    },
+                    item_id: None,
+                    message: Some(message),
+                }
+            }
+        };
+        results.push(result);
+    }
+    results
+}
+
+pub(crate) fn handle_startup_arguments(app: &AppHandle, arguments: Vec<OsString>, cwd: PathBuf) {
+    if arguments.is_empty() {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let results = import_startup_arguments(arguments, cwd).await;
+        if results.is_empty() {
+            return;
+        }
+
+        if let Some(state) = app.try_state::<StartupImportState>() {
+            if let Ok(mut pending) = state.0.lock() {
+                pending.extend(results.iter().cloned());
+            }
+        }
+        if let Err(err) = app.emit(STARTUP_IMPORT_RESULTS_EVENT, results) {
+            log_eprintln!("[startup-import] failed to emit results: {}", err);
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) fn take_startup_import_results(
+    state: State<'_, StartupImportState>,
+) -> Result<Vec<StartupArgumentResult>, String> {
+    let mut pending = state
+        .0
+        .lock()
+        .map_err(|_| "startup import results lock poisoned".to_string())?;
+    Ok(std::mem::take(&mut *pending))
+}
+
+#[tauri::command]
+pub(crate) fn file_exists(path: String) -> Result<bool, String> {
+    let target = path_from_ipc_string(&path);
+    Ok(target.exists() && target.is_file())
+}
+
+#[tauri::command]
+pub(crate) fn pick_files() -> Result<Vec<String>, String> {
+    let selected = FileDialog::new().pick_files();
+    let mut paths = Vec::new();
+
+    if let Some(files) = selected {
+        for path in files {
+            paths.push(path_to_string(&path)?);
+        }
+    }
+
+    Ok(paths)
+}
+
+pub(crate) fn dialog_purpose_setting_key(purpose: Option<&str>) -> Result<String, String> {
+    match purpose.map(str::trim).unwrap_or("import") {
+        "import" => Ok(format!("{}import", SETTING_LAST_DIALOG_DIR_PREFIX)),
+        "export" => Ok(format!("{}export", SETTING_LAST_DIALOG_DIR_PREFIX)),
+        other => Err(format!("unsupported dialog purpose: {}", other)),
+    }
+}
+
+pub(crate) fn last_dialog_directory(setting_key: &str) -> Option<PathBuf> {
+    initialize_db().ok()?;
+    let connection = open_db_connection().ok()?;
+    read_app_setting(&connection, setting_key)
+        .ok()
+        .flatten()
+        .map(|value| path_from_ipc_string(&value))
+        .filter(|path| path.is_dir())
+}
+
+pub(crate) fn remember_dialog_directory(setting_key: &str, directory: Option<&Path>) {
+    let Some(directory) = directory.filter(|directory| !directory.as_os_str().is_empty()) else {
+        return;
+    };
+    let outcome = initialize_db()
+        .and_then(|_| open_db_connection())
+        .and_then(|connection| {
+            write_app_setting(&connection, setting_key, &path_to_ipc_string(directory))
+        });
+    if let Err(err) = outcome {
+        log_eprintln!("[dialogs] failed to remember {}: {}", setting_key, err);
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn pick_folder(purpose: Option<String>) -> Result<Option<String>, String> {
+    let setting_key = dialog_purpose_setting_key(purpose.as_deref())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut dialog = FileDialog::new();
+        if let Some(directory) = last_dialog_directory(&setting_key) {
+            dialog = dialog.set_directory(directory);
+        }
+        let Some(folder) = dialog.pick_folder() else {
+            return Ok(None);
+        };
+        remember_dialog_directory(&setting_key, Some(&folder));
+        Ok(Some(path_to_ipc_string(&folder)))
+    })
+    .await
+    .map_err(|err| format!("folder picker thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) async fn pick_save_path(
+    default_name: Option<String>,
+    filters: Option<Vec<DialogFileFilter>>,
+    purpose: Option<String>,
+) -> Result<Option<String>, String> {
+    let setting_key = dialog_purpose_setting_key(Some(purpose.as_deref().unwrap_or("export")))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut dialog = FileDialog::new();
+        if let Some(directory) = last_dialog_directory(&setting_key) {
+            dialog = dialog.set_directory(directory);
+        }
+        if let Some(name) = normalize_optional_trimmed_string(default_name) {
+            dialog = dialog.set_file_name(sanitize_export_filename(&name));
+        }
+        for filter in filters.unwrap_or_default() {
+            let extensions: Vec<String> = filter
+                .extensions
+                .iter()
+                .map(|ext| ext.trim().trim_start_matches('.').to_string())
+                .filter(|ext| !ext.is_empty())
+                .collect();
+            if !extensions.is_empty() {
+                dialog = dialog.add_filter(filter.name.trim(), &extensions);
+            }
+        }
+        let Some(path) = dialog.save_file() else {
+            return Ok(None);
+        };
+        remember_dialog_directory(&setting_key, path.parent());
+        Ok(Some(path_to_ipc_string(&path)))
+    })
+    .await
+    .map_err(|err| format!("save dialog thread join failed: {}", err))?
+}
+
+pub(crate) fn invoke_handler() -> impl Fn(tauri::ipc::Invoke) -> bool + Send + Sync + 'static {
+    tauri::generate_handler![
+        crate::db::init_db,
+        crate::db::load_app_state,
+        crate::db::get_library_stats,
+        crate::db::get_processing_health,
+        crate::db::export_diagnostics,
+        crate::db::repair_stuck_states,
+        crate::db::take_startup_stuck_state_repair,
+        crate::db::check_library_available,
+        crate::db::reconnect_library,
+        crate::db::run_db_maintenance,
+        crate::db::backup_database,
+        crate::db::restore_database,
+        crate::db::get_backup_status,
+        crate::db::update_backup_settings,
+        crate::db::get_startup_health,
+        crate::db::get_running_maintenance,
+        crate::db::get_hide_sensitive,
+        crate::db::set_hide_sensitive,
+        crate::db::get_auto_verify_on_open,
+        crate::db::set_auto_verify_on_open,
+        crate::vault::get_disk_usage,
+        crate::vault::get_recently_imported,
+        crate::vault::get_vault_file_info,
+        crate::vault::compact_vault,
+        crate::vault::list_largest_vault_files,
+        crate::vault::list_protected_vault_files,
+        crate::vault::list_quarantined_files,
+        crate::vault::purge_quarantined_files,
+        crate::vault::set_vault_file_protected,
+        crate::vault::protect_item_files,
+        crate::vault::get_import_metrics_summary,
+        crate::vault::get_import_limits,
+        crate::vault::update_import_limits,
+        crate::vault::get_import_hash_parallelism,
+        crate::vault::set_import_hash_parallelism,
+        crate::vault::inspect_archive,
+        crate::vault::import_from_archive,
+        crate::vault::get_vault_maintenance_status,
+        crate::vault::acquire_vault_keys,
+        crate::vault::release_vault_keys,
+        crate::vault::run_vault_cleanup,
+        crate::vault::get_vault_encryption_status,
+        crate::vault::enable_vault_encryption,
+        crate::vault::unlock_library,
+        crate::vault::lock_library,
+        crate::vault::change_passphrase,
+        crate::vault::verify_item_file,
+        crate::vault::record_item_opened,
+        crate::vault::repair_modified_vault_file,
+        crate::vault::list_item_file_versions,
+        crate::vault::restore_item_file_version,
+        crate::vault::get_file_versions_to_keep,
+        crate::vault::set_file_versions_to_keep,
+        crate::vault::finalize_item_import,
+        crate::vault::mark_item_import_error,
+        crate::vault::list_failed_imports,
+        crate::vault::retry_failed_imports,
+        crate::vault::validate_vault_keys,
+        crate::vault::find_broken_items,
+        crate::vault::fix_mismatched_extensions,
+        crate::vault::relink_item_file,
+        crate::vault::ensure_storage_root,
+        crate::vault::compute_sha256,
+        crate::vault::process_import_path_job,
+        crate::vault::process_import_bytes_job,
+        crate::vault::import_to_vault,
+        crate::vault::import_directory,
+        crate::vault::get_last_import_report,
+        crate::vault::list_import_batches,
+        crate::vault::get_batch_items,
+        crate::vault::delete_batch_items,
+        crate::vault::handle_dropped_paths,
+        crate::vault::preview_import_directory,
+        crate::vault::import_bytes_to_vault,
+        crate::vault::remove_from_vault,
+        crate::thumbs::get_import_thumb_policy,
+        crate::thumbs::set_import_thumb_policy,
+        crate::thumbs::ocr_item,
+        crate::thumbs::ocr_pending_items,
+        crate::thumbs::cancel_ocr_batch,
+        crate::thumbs::generate_thumbnail_for_item,
+        crate::thumbs::backfill_item_dimensions,
+        crate::thumbs::ensure_thumbs_root,
+        crate::thumbs::generate_thumbnail,
+        crate::bookmarks::update_item_bookmark_metadata,
+        crate::bookmarks::fetch_missing_favicons,
+        crate::bookmarks::reprocess_favicons,
+        crate::bookmarks::fetch_bookmark_metadata,
+        crate::bookmarks::cancel_bookmark_fetch,
+        crate::bookmarks::get_fetch_coordinator_stats,
+        crate::bookmarks::set_fetch_host_limits,
+        crate::bookmarks::cache_item_preview,
+        crate::bookmarks::refresh_item_preview,
+        crate::bookmarks::get_bookmark_refresh_settings,
+        crate::bookmarks::set_bookmark_refresh_settings,
+        crate::bookmarks::pause_background_refresh,
+        crate::bookmarks::resume_background_refresh,
+        crate::bookmarks::create_bookmark_item,
+        crate::tags::get_items_by_tag,
+        crate::tags::get_untagged_items,
+        crate::tags::count_untagged_items,
+        crate::tags::get_tag_rating_summary,
+        crate::tags::create_tag,
+        crate::tags::suggest_tag_color,
+        crate::tags::get_all_tags,
+        crate::tags::reorder_tags,
+        crate::tags::update_tag_name,
+        crate::tags::update_tag_color,
+        crate::tags::duplicate_tag,
+        crate::tags::delete_tag,
+        crate::tags::export_tags_json,
+        crate::tags::import_tags_json,
+        crate::tags::update_item_tags,
+        crate::collections::create_collection,
+        crate::collections::find_duplicate_sibling_collections,
+        crate::collections::merge_collections,
+        crate::collections::merge_duplicate_collections,
+        crate::collections::get_all_collections,
+        crate::collections::update_collection_name,
+        crate::collections::delete_collection,
+        crate::collections::repair_collection_tree,
+        crate::collections::get_collection_tree,
+        crate::collections::move_collection,
+        crate::collections::search_collections,
+        crate::collections::move_collection_item_memberships,
+        crate::collections::add_items_to_collection,
+        crate::collections::get_collection_items,
+        crate::collections::reorder_collection_items,
+        crate::collections::export_collection_layout,
+        crate::collections::apply_collection_layout,
+        crate::collections::set_collection_item_pinned,
+        crate::collections::set_collection_archived,
+        crate::collections::export_collection_markdown,
+        crate::collections::set_collection_cover,
+        crate::collections::set_collection_icon,
+        crate::collections::set_collection_icon_image,
+        crate::collections::get_collection_covers,
+        crate::collections::set_collection_lock,
+        crate::collections::unlock_collection,
+        crate::collections::lock_collection,
+        crate::collections::remove_collection_lock,
+        crate::collections::get_collection_lock_state,
+        crate::collections::set_collection_unlock_timeout,
+        crate::collections::get_collection_counts,
+        crate::collections::update_items_collection,
+        crate::items::get_item_types,
+        crate::items::get_item,
+        crate::items::get_items_by_ids,
+        crate::items::get_random_items,
+        crate::items::get_items_by_month,
+        crate::items::get_favorite_items,
+        crate::items::get_items_by_min_rating,
+        crate::items::get_activity_heatmap,
+        crate::items::get_weekly_digest,
+        crate::items::get_rating_stats,
+        crate::items::insert_item,
+        crate::items::insert_items_batch,
+        crate::items::delete_items,
+        crate::items::delete_items_with_cleanup,
+        crate::items::preview_delete_by_filter,
+        crate::items::delete_by_filter,
+        crate::items::link_items,
+        crate::items::unlink_items,
+        crate::items::get_linked_items,
+        crate::items::add_item_note,
+        crate::items::update_item_note,
+        crate::items::delete_item_note,
+        crate::items::list_item_notes,
+        crate::items::set_items_archived,
+        crate::items::set_items_sensitive,
+        crate::items::duplicate_item,
+        crate::items::merge_items,
+        crate::items::update_item_description,
+        crate::items::update_item_description_v2,
+        crate::items::load_item_overlay,
+        crate::items::save_item_overlay,
+        crate::items::update_item_preferences,
+        crate::items::get_item_history,
+        crate::items::prune_item_history,
+        crate::items::get_item_audit_enabled,
+        crate::items::set_item_audit_enabled,
+        crate::items::update_item_media_state,
+        file_exists,
+        take_startup_import_results,
+        pick_files,
+        pick_folder,
+        pick_save_path
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn classify(argument: &str, cwd: &Path) -> StartupArgument {
+        classify_startup_argument(OsStr::new(argument), cwd)
+    }
+
+    #[test]
+    fn classify_startup_argument_recognizes_text_arguments() {
+        let cwd = tempfile::tempdir().unwrap();
+        fs::write(cwd.path().join("note.txt"), b"hello").unwrap();
+
+        assert!(matches!(
+            classify("stumble://add?url=https://example.com", cwd.path()),
+            StartupArgument::DeepLink
+        ));
+        assert!(matches!(
+            classify(" \"https://example.com/page\" ", cwd.path()),
+            StartupArgument::Bookmark(_)
+        ));
+        assert!(matches!(
+            classify("--verbose", cwd.path()),
+            StartupArgument::Unsupported(reason) if reason.contains("unrecognized option")
+        ));
+        assert!(matches!(
+            classify("   ", cwd.path()),
+            StartupArgument::Unsupported(reason) if reason == "empty argument"
+        ));
+        assert!(matches!(
+            classify("note.txt", cwd.path()),
+            StartupArgument::File(path) if path == cwd.path().join("note.txt")
+        ));
+        assert!(matches!(
+            classify(".", cwd.path()),
+            StartupArgument::Unsupported(reason) if reason.contains("directories")
+        ));
+        assert!(matches!(
+            classify("missing.txt", cwd.path()),
+            StartupArgument::Unsupported(reason) if reason.contains("file not found")
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn classify_startup_argument_accepts_non_unicode_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let cwd = tempfile::tempdir().unwrap();
+        let name = OsStr::from_bytes(b"caf\xE9.txt");
+        fs::write(cwd.path().join(name), b"hello").unwrap();
+
+        match classify_startup_argument(name, cwd.path()) {
+            StartupArgument::File(path) => assert_eq!(path, cwd.path().join(name)),
+            _ => panic!("expected a file argument"),
+        }
+        let absolute = cwd.path().join(name);
+        assert!(matches!(
+            classify_startup_argument(absolute.as_os_str(), Path::new("/")),
+            StartupArgument::File(_)
+        ));
+        assert!(matches!(
+            classify_startup_argument(OsStr::from_bytes(b"-\xFF"), cwd.path()),
+            StartupArgument::Unsupported(reason) if reason.contains("file not found")
+        ));
+        assert!(path_to_ipc_string(Path::new(name)).starts_with(RAW_PATH_IPC_PREFIX));
+    }
+
+    #[test]
+    fn sanitize_export_filename_prefixes_reserved_device_names() {
+        for (input, expected) in [
+            ("CON", "_CON"),
+            ("con.txt", "_con.txt"),
+            ("Nul.tar.gz", "_Nul.tar.gz"),
+            ("lpt9 . ", "_lpt9"),
+            ("com1.", "_com1"),
+            ("aux.png", "_aux.png"),
+            ("console.txt", "console.txt"),
+            ("com10.txt", "com10.txt"),
+            ("my con.txt", "my con.txt"),
+        ] {
+            assert_eq!(sanitize_export_filename(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn sanitize_export_filename_replaces_forbidden_characters() {
+        assert_eq!(
+            sanitize_export_filename("a<b>c:d\"e/f\\g|h?i*j"),
+            "a_b_c_d_e_f_g_h_i_j"
+        );
+        assert_eq!(sanitize_export_filename("tab\there.png"), "tab_here.png");
+        assert_eq!(sanitize_export_filename("  ...  "), "untitled");
+        assert_eq!(sanitize_export_filename(".hidden"), ".hidden");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn extended_length_path_is_a_no_op_off_windows() {
+        let long = PathBuf::from(format!("/tmp/{}", "a".repeat(300)));
+        assert_eq!(extended_length_path(&long), long);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn extended_length_path_prefixes_long_absolute_paths() {
+        let short = PathBuf::from(r"C:\Users\me\file.png");
+        assert_eq!(extended_length_path(&short), short);
+
+        let segment = "a".repeat(250);
+        let long_drive = PathBuf::from(format!("C:/Users/{}/file.png", segment));
+        assert_eq!(
+            extended_length_path(&long_drive),
+            PathBuf::from(format!(r"\\?\C:\Users\{}\file.png", segment))
+        );
+
+        let long_unc = PathBuf::from(format!(r"\\server\share\{}\file.png", segment));
+        assert_eq!(
+            extended_length_path(&long_unc),
+            PathBuf::from(format!(r"\\?\UNC\server\share\{}\file.png", segment))
+        );
+
+        let prefixed = PathBuf::from(format!(r"\\?\C:\{}\file.png", segment));
+        assert_eq!(extended_length_path(&prefixed), prefixed);
+
+        let relative = PathBuf::from(format!(r"{}\file.png", segment));
+        assert_eq!(extended_length_path(&relative), relative);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn extended_length_path_reaches_files_beyond_max_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut deep = dir.path().to_path_buf();
+        for index in 0..6 {
+            deep.push(format!("{}{}", index, "d".repeat(60)));
+        }
+        let file = deep.join("note.txt");
+        assert!(file.as_os_str().len() > 260);
+
+        fs::create_dir_all(extended_length_path(&deep)).unwrap();
+        fs::write(extended_length_path(&file), b"deep").unwrap();
+        assert_eq!(fs::read(extended_length_path(&file)).unwrap(), b"deep");
+    }
+
+    #[cfg(unix)]
+    fn unix_path(bytes: &[u8]) -> PathBuf {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(OsStr::from_bytes(bytes))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ipc_strings_keep_utf8_paths_readable() {
+        let path = unix_path("/home/me/Fotos/été 100%.png".as_bytes());
+        assert_eq!(path_to_ipc_string(&path), "/home/me/Fotos/été 100%.png");
+        assert_eq!(path_from_ipc_string("/home/me/Fotos/été 100%.png"), path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ipc_strings_encode_non_utf8_and_prefix_lookalike_paths() {
+        let latin1 = unix_path(b"/tmp/caf\xe9 %.png");
+        let encoded = path_to_ipc_string(&latin1);
+        assert_eq!(
+            encoded,
+            format!("{}/tmp/caf%E9 %25.png", RAW_PATH_IPC_PREFIX)
+        );
+        assert_eq!(path_from_ipc_string(&encoded), latin1);
+
+        let lookalike = unix_path(format!("{}%41", RAW_PATH_IPC_PREFIX).as_bytes());
+        let encoded = path_to_ipc_string(&lookalike);
+        assert_ne!(encoded, lookalike.to_str().unwrap());
+        assert_eq!(path_from_ipc_string(&encoded), lookalike);
+    }
+
+    #[test]
+    fn malformed_raw_ipc_strings_fall_back_to_the_literal_path() {
+        for value in ["raw-path:%", "raw-path:%4", "raw-path:%zz.png"] {
+            let value = value.replacen("raw-path:", RAW_PATH_IPC_PREFIX, 1);
+            assert_eq!(path_from_ipc_string(&value), PathBuf::from(&value));
+        }
+    }
+
+    #[cfg(unix)]
+    mod ipc_path_properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        proptest! {
+            #[test]
+            fn arbitrary_unix_paths_round_trip(bytes in proptest::collection::vec(any::<u8>(), 0..64)) {
+                let path = unix_path(&bytes);
+                let encoded = path_to_ipc_string(&path);
+                prop_assert_eq!(path_from_ipc_string(&encoded), path);
+            }
+
+            #[test]
+            fn utf8_paths_are_sent_verbatim(text in "[^\\x00]{0,40}") {
+                prop_assume!(!text.starts_with(RAW_PATH_IPC_PREFIX));
+                let path = unix_path(text.as_bytes());
+                prop_assert_eq!(path_to_ipc_string(&path), text);
+            }
+        }
+    }
+}
@@ -0,0 +1,4388 @@
+use chrono::{Datelike, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use uuid::Uuid;
+
+use crate::bookmarks::*;
+use crate::collections::*;
+use crate::commands::*;
+use crate::items::*;
+use crate::tags::*;
+use crate::vault::*;
+
+pub(crate) const APP_ROOT_ENV_OVERRIDE: &str = "STUMBLE_APP_ROOT";
+pub(crate) const NATURAL_COLLATION: &str = "NATURAL_TITLE";
+pub(crate) const DIAGNOSTICS_SECRET_SETTING_PREFIXES: [&str; 3] = [
+    SETTING_VAULT_ENCRYPTION_SALT,
+    SETTING_VAULT_ENCRYPTION_WRAPPED_KEY,
+    SETTING_COLLECTION_LOCK_PREFIX,
+];
+pub(crate) const DIAGNOSTICS_REDACTED: &str = "<redacted>";
+pub(crate) const DIAGNOSTICS_LOG_MAX_LINES: usize = 2000;
+pub(crate) const DEFAULT_BACKUP_KEEP_COUNT: usize = 10;
+pub(crate) const BACKUP_FILE_PREFIX: &str = "stumble-";
+pub(crate) const BACKUP_FILE_EXT: &str = "db";
+pub(crate) const DEFAULT_BACKUP_INTERVAL_HOURS: i64 = 24;
+pub(crate) const BACKUP_MIN_FREE_SPACE_BYTES: u64 = 512 * 1024 * 1024;
+pub(crate) const BACKUP_SCHEDULER_POLL_SECS: u64 = 30 * 60;
+pub(crate) const BACKUP_WARNING_EVENT: &str = "backup://warning";
+pub(crate) const SETTING_BACKUP_INTERVAL_HOURS: &str = "backup_interval_hours";
+pub(crate) const SETTING_BACKUP_KEEP_COUNT: &str = "backup_keep_count";
+pub(crate) const SETTING_ITEM_AUDIT_ENABLED: &str = "item_audit_enabled";
+pub(crate) const SETTING_HIDE_SENSITIVE: &str = "hide_sensitive";
+pub(crate) const SETTING_AUTO_VERIFY_ON_OPEN: &str = "auto_verify_on_open";
+pub(crate) const LIBRARY_UNAVAILABLE_ERROR_PREFIX: &str = "library unavailable";
+pub(crate) const LIBRARY_STATUS_OK: &str = "ok";
+pub(crate) const LIBRARY_STATUS_MISSING_ROOT: &str = "missing_root";
+pub(crate) const LIBRARY_STATUS_READ_ONLY: &str = "read_only";
+pub(crate) const LIBRARY_STATUS_DB_LOCKED: &str = "db_locked";
+pub(crate) const LIBRARY_READY_EVENT: &str = "library://ready";
+pub(crate) const LIBRARY_WRITE_PROBE_FILENAME: &str = ".stumble-write-probe";
+pub(crate) const LIBRARY_DB_LOCK_PROBE_TIMEOUT_MS: u64 = 500;
+pub(crate) const MAINTENANCE_VACUUM_MIN_FREE_BYTES: i64 = 4 * 1024 * 1024;
+pub(crate) const MAINTENANCE_BUSY_TIMEOUT_MS: u64 = 5_000;
+pub(crate) const MAINTENANCE_PROGRESS_EVENT: &str = "maintenance-progress";
+pub(crate) const STUCK_STATES_REPAIRED_EVENT: &str = "processing://stuck-states-repaired";
+pub(crate) const STUCK_STATE_STALE_MS: i64 = 60 * 60 * 1000;
+pub(crate) const PROCESSING_HEALTH_ID_LIMIT: i64 = 500;
+pub(crate) const STUCK_IMPORT_CONDITION: &str = "import_status = 'processing'";
+pub(crate) const STUCK_THUMB_CONDITION: &str =
+    "type = 'image' AND thumb_status = 'pending' AND import_status = 'ready'";
+pub(crate) const STUCK_META_CONDITION: &str =
+    "type = 'bookmark' AND meta_status = 'pending' AND import_status = 'ready'";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DbAppState {
+    pub(crate) collections: Vec<DbCollectionRow>,
+    pub(crate) collection_items: Vec<DbCollectionItemRow>,
+    pub(crate) tags: Vec<DbTagRow>,
+    pub(crate) items: Vec<DbItemRow>,
+    pub(crate) hidden_count: i64,
+    pub(crate) locked_collection_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProcessingHealth {
+    pub(crate) thumb_status: Vec<LibraryCountEntry>,
+    pub(crate) import_status: Vec<LibraryCountEntry>,
+    pub(crate) meta_status: Vec<LibraryCountEntry>,
+    pub(crate) stale_before: i64,
+    pub(crate) stuck_import_item_ids: Vec<String>,
+    pub(crate) stuck_thumb_item_ids: Vec<String>,
+    pub(crate) stuck_meta_item_ids: Vec<String>,
+    pub(crate) bookmark_refresh: BookmarkRefreshStatus,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StuckStateRepairResult {
+    pub(crate) stale_before: i64,
+    pub(crate) failed_import_item_ids: Vec<String>,
+    pub(crate) requeued_thumb_item_ids: Vec<String>,
+    pub(crate) requeued_meta_item_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LibraryCountEntry {
+    pub(crate) key: String,
+    pub(crate) count: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LibraryMonthEntry {
+    pub(crate) month: String,
+    pub(crate) count: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LibraryLargestItem {
+    pub(crate) id: String,
+    #[serde(rename = "type")]
+    pub(crate) item_type: String,
+    pub(crate) title: String,
+    pub(crate) filename: String,
+    pub(crate) size_bytes: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LibraryStats {
+    pub(crate) total_items: i64,
+    pub(crate) items_by_type: Vec<LibraryCountEntry>,
+    pub(crate) total_collections: i64,
+    pub(crate) total_tags: i64,
+    pub(crate) vault_file_count: i64,
+    pub(crate) vault_size_bytes: i64,
+    pub(crate) items_per_month: Vec<LibraryMonthEntry>,
+    pub(crate) items_by_rating: Vec<LibraryCountEntry>,
+    pub(crate) favorites_count: i64,
+    pub(crate) archived_items: i64,
+    pub(crate) archived_collections: i64,
+    pub(crate) bookmarks_by_meta_status: Vec<LibraryCountEntry>,
+    pub(crate) thumbnails_by_status: Vec<LibraryCountEntry>,
+    pub(crate) largest_items: Vec<LibraryLargestItem>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BackupDatabaseResult {
+    pub(crate) backup_path: String,
+    pub(crate) size_bytes: u64,
+    pub(crate) created_at: i64,
+    pub(crate) duration_ms: u64,
+    pub(crate) pruned_paths: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BackupStatus {
+    pub(crate) interval_hours: i64,
+    pub(crate) keep_count: usize,
+    pub(crate) backup_dir: String,
+    pub(crate) last_backup_at: Option<i64>,
+    pub(crate) last_backup_path: Option<String>,
+    pub(crate) last_backup_size_bytes: Option<u64>,
+    pub(crate) next_scheduled_at: Option<i64>,
+    pub(crate) last_error: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BackupWarningPayload {
+    pub(crate) message: String,
+}
+
+pub(crate) struct BackupSettings {
+    pub(crate) interval_hours: i64,
+    pub(crate) keep_count: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DbMaintenanceResult {
+    pub(crate) size_before_bytes: u64,
+    pub(crate) size_after_bytes: u64,
+    pub(crate) freelist_pages_before: i64,
+    pub(crate) vacuum_mode: String,
+    pub(crate) duration_ms: u64,
+    pub(crate) completed_at: i64,
+}
+
+pub(crate) struct DbActivity {
+    pub(crate) imports_in_flight: usize,
+    pub(crate) maintenance_running: bool,
+}
+
+pub(crate) static DB_INITIALIZED: AtomicBool = AtomicBool::new(false);
+#[cfg(test)]
+thread_local! {
+    pub(crate) static DIRECTORY_READS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+pub(crate) static DB_ACTIVITY: Mutex<DbActivity> = Mutex::new(DbActivity {
+    imports_in_flight: 0,
+    maintenance_running: false,
+});
+pub(crate) static RECENT_LOG_LINES: Mutex<LogBuffer> =
+    Mutex::new(LogBuffer::new(DIAGNOSTICS_LOG_MAX_LINES));
+
+/// The newest `max_lines` log lines, oldest first.
+pub(crate) struct LogBuffer {
+    pub(crate) lines: VecDeque<String>,
+    pub(crate) max_lines: usize,
+}
+
+impl LogBuffer {
+    pub(crate) const fn new(max_lines: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            max_lines,
+        }
+    }
+
+    pub(crate) fn push(&mut self, timestamp: &str, message: &str) {
+        for line in message.lines() {
+            if self.lines.len() == self.max_lines {
+                self.lines.pop_front();
+            }
+            self.lines.push_back(format!("{} {}", timestamp, line));
+        }
+    }
+}
+
+/// Called by `log_println!` / `log_eprintln!`; the lines end up in diagnostics exports.
+pub(crate) fn record_log_line(message: &str) {
+    if let Ok(mut buffer) = RECENT_LOG_LINES.lock() {
+        let timestamp = Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+        buffer.push(&timestamp, message);
+    }
+}
+
+pub(crate) fn recent_log_lines() -> Vec<String> {
+    RECENT_LOG_LINES
+        .lock()
+        .map(|buffer| buffer.lines.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub(crate) struct ImportActivityGuard;
+
+impl ImportActivityGuard {
+    pub(crate) fn begin() -> Result<Self, String> {
+        let mut activity = DB_ACTIVITY
+            .lock()
+            .map_err(|_| "database activity lock poisoned".to_string())?;
+        if activity.maintenance_running {
+            return Err("database maintenance is running; retry the import shortly".to_string());
+        }
+        activity.imports_in_flight += 1;
+        Ok(Self)
+    }
+}
+
+impl Drop for ImportActivityGuard {
+    fn drop(&mut self) {
+        if let Ok(mut activity) = DB_ACTIVITY.lock() {
+            activity.imports_in_flight = activity.imports_in_flight.saturating_sub(1);
+        }
+    }
+}
+
+pub(crate) struct MaintenanceActivityGuard;
+
+impl MaintenanceActivityGuard {
+    pub(crate) fn begin() -> Result<Self, String> {
+        let mut activity = DB_ACTIVITY
+            .lock()
+            .map_err(|_| "database activity lock poisoned".to_string())?;
+        if activity.maintenance_running {
+            return Err("database maintenance is already running".to_string());
+        }
+        if activity.imports_in_flight > 0 {
+            return Err(format!(
+                "cannot run database maintenance while {} import(s) are in flight",
+                activity.imports_in_flight
+            ));
+        }
+        activity.maintenance_running = true;
+        Ok(Self)
+    }
+}
+
+impl Drop for MaintenanceActivityGuard {
+    fn drop(&mut self) {
+        if let Ok(mut activity) = DB_ACTIVITY.lock() {
+            activity.maintenance_running = false;
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct BackupSchedulerState {
+    pub(crate) last_error: Mutex<Option<String>>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LibraryAvailability {
+    pub(crate) status: String,
+    pub(crate) app_root: Option<String>,
+    pub(crate) storage_root: Option<String>,
+    pub(crate) message: Option<String>,
+    pub(crate) checked_at: i64,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StartupHealthReport {
+    pub(crate) status: String,
+    pub(crate) checked_at: i64,
+    pub(crate) message: Option<String>,
+    pub(crate) corrupt_copy_path: Option<String>,
+    pub(crate) backup_path: Option<String>,
+    pub(crate) backup_created_at: Option<i64>,
+    pub(crate) items_before: Option<i64>,
+    pub(crate) items_after: Option<i64>,
+    pub(crate) missing_items_estimate: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExportDiagnosticsResult {
+    pub(crate) path: String,
+    pub(crate) size_bytes: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct StartupHealthState(pub(crate) Mutex<Option<StartupHealthReport>>);
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MaintenanceProgressPayload {
+    pub(crate) operation_id: String,
+    pub(crate) operation: String,
+    pub(crate) phase: String,
+    pub(crate) done: u64,
+    pub(crate) total: u64,
+    pub(crate) detail: Option<String>,
+    pub(crate) started_at: i64,
+    pub(crate) import_batch_id: Option<String>,
+}
+
+#[derive(Default)]
+pub(crate) struct MaintenanceProgressState(
+    pub(crate) Mutex<BTreeMap<String, MaintenanceProgressPayload>>,
+);
+
+pub(crate) struct ProgressReporter {
+    pub(crate) app: Option<AppHandle>,
+    pub(crate) operation_id: String,
+    pub(crate) operation: String,
+    pub(crate) started_at: i64,
+    pub(crate) import_batch_id: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RestoreDatabaseResult {
+    pub(crate) restored_from: String,
+    pub(crate) pre_restore_backup_path: Option<String>,
+    pub(crate) restored_at: i64,
+}
+
+#[derive(Default)]
+pub(crate) struct StartupStuckStateRepair(pub(crate) Mutex<Option<StuckStateRepairResult>>);
+
+pub(crate) fn app_root_path() -> Result<PathBuf, String> {
+    if let Some(app_root) =
+        std::env::var_os(APP_ROOT_ENV_OVERRIDE).filter(|value| !value.is_empty())
+    {
+        return Ok(PathBuf::from(app_root));
+    }
+    let app_data = std::env::var_os("APPDATA")
+        .ok_or_else(|| "APPDATA environment variable is not available".to_string())?;
+    Ok(PathBuf::from(app_data).join("Stumble"))
+}
+
+pub(crate) fn db_path() -> Result<PathBuf, String> {
+    Ok(app_root_path()?.join("stumble.db"))
+}
+
+/// `fs::read_dir`, counted per thread in tests so command paths can assert they never
+/// scan the library.
+pub(crate) fn read_directory(path: impl AsRef<Path>) -> std::io::Result<fs::ReadDir> {
+    #[cfg(test)]
+    DIRECTORY_READS.with(|reads| reads.set(reads.get() + 1));
+    fs::read_dir(path)
+}
+
+pub(crate) fn open_db_connection() -> Result<Connection, String> {
+    let app_root = app_root_path()?;
+    fs::create_dir_all(&app_root).map_err(|err| {
+        format!(
+            "failed to create app root directory {}: {}",
+            app_root.display(),
+            err
+        )
+    })?;
+
+    let database_path = db_path()?;
+    let connection = Connection::open(&database_path).map_err(|err| {
+        format!(
+            "failed to open sqlite database {}: {}",
+            database_path.display(),
+            err
+        )
+    })?;
+    connection
+        .execute_batch("PRAGMA foreign_keys = ON;")
+        .map_err(|err| format!("failed to enable sqlite foreign keys: {}", err))?;
+    connection
+        .create_collation(NATURAL_COLLATION, natural_title_cmp)
+        .map_err(|err| format!("failed to register natural collation: {}", err))?;
+    Ok(connection)
+}
+
+/// Lowercases and strips common Latin diacritics so "É" sorts with "e".
+pub(crate) fn fold_title_char(c: char) -> char {
+    match c.to_lowercase().next().unwrap_or(c) {
+        'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ď' | 'đ' => 'd',
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' | 'ħ' => 'h',
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò'..='ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' => 's',
+        'ţ' | 'ť' | 'ŧ' => 't',
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        other => other,
+    }
+}
+
+pub(crate) fn take_ascii_digits(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits
+}
+
+/// Collation behind `COLLATE NATURAL_TITLE`: case- and accent-insensitive, with digit runs
+/// compared by value ("img2" < "img10"). Ties fall back to case, then raw bytes, so only
+/// identical strings compare equal and keyset cursors stay stable.
+pub(crate) fn natural_title_cmp(left: &str, right: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let mut left_chars = left.chars().peekable();
+    let mut right_chars = right.chars().peekable();
+    let primary = loop {
+        match (left_chars.peek().copied(), right_chars.peek().copied()) {
+            (None, None) => break Ordering::Equal,
+            (None, Some(_)) => break Ordering::Less,
+            (Some(_), None) => break Ordering::Greater,
+            (Some(l), Some(r)) if l.is_ascii_digit() && r.is_ascii_digit() => {
+                let left_digits = take_ascii_digits(&mut left_chars);
+                let right_digits = take_ascii_digits(&mut right_chars);
+                let left_value = left_digits.trim_start_matches('0');
+                let right_value = right_digits.trim_start_matches('0');
+                let ordering = left_value
+                    .len()
+                    .cmp(&right_value.len())
+                    .then_with(|| left_value.cmp(right_value))
+                    .then_with(|| left_digits.len().cmp(&right_digits.len()));
+                if ordering != Ordering::Equal {
+                    break ordering;
+                }
+            }
+            (Some(l), Some(r)) => {
+                let ordering = fold_title_char(l).cmp(&fold_title_char(r));
+                if ordering != Ordering::Equal {
+                    break ordering;
+                }
+                left_chars.next();
+                right_chars.next();
+            }
+        }
+    };
+    primary
+        .then_with(|| left.to_lowercase().cmp(&right.to_lowercase()))
+        .then_with(|| left.cmp(right))
+}
+
+pub(crate) fn run_db_migrations(connection: &Connection) -> Result<(), String> {
+    connection
+        .execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS collections (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT NULL,
+                icon TEXT NOT NULL,
+                icon_type TEXT NOT NULL DEFAULT 'named' CHECK(icon_type IN ('named', 'emoji', 'image')),
+                color TEXT NOT NULL,
+                parent_id TEXT NULL,
+                is_archived INTEGER NOT NULL DEFAULT 0 CHECK(is_archived IN (0, 1)),
+                cover_item_id TEXT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                FOREIGN KEY (parent_id) REFERENCES collections(id) ON DELETE SET NULL,
+                FOREIGN KEY (cover_item_id) REFERENCES items(id) ON DELETE SET NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS items (
+                id TEXT PRIMARY KEY,
+                collection_id TEXT NULL,
+                type TEXT NOT NULL,
+                title TEXT NOT NULL,
+                filename TEXT NOT NULL,
+                vault_key TEXT NOT NULL,
+                vault_path TEXT NOT NULL,
+                preview_url TEXT NULL,
+                width INTEGER NULL,
+                height INTEGER NULL,
+                thumb_status TEXT NOT NULL DEFAULT 'pending',
+                import_status TEXT NOT NULL DEFAULT 'ready',
+                url TEXT NULL,
+                favicon_path TEXT NULL,
+                meta_status TEXT NOT NULL DEFAULT 'ready',
+                description TEXT NULL,
+                rating INTEGER NOT NULL DEFAULT 0 CHECK(rating BETWEEN 0 AND 5),
+                is_favorite INTEGER NOT NULL DEFAULT 0 CHECK(is_favorite IN (0, 1)),
+                is_archived INTEGER NOT NULL DEFAULT 0 CHECK(is_archived IN (0, 1)),
+                original_source_path TEXT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                FOREIGN KEY (collection_id) REFERENCES collections(id) ON DELETE SET NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS collection_items (
+                id TEXT PRIMARY KEY,
+                collection_id TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                custom_title TEXT NULL,
+                custom_description TEXT NULL,
+                sort_index INTEGER NOT NULL DEFAULT 0,
+                is_pinned INTEGER NOT NULL DEFAULT 0 CHECK(is_pinned IN (0, 1)),
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY (collection_id) REFERENCES collections(id) ON DELETE CASCADE,
+                FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE
+            );
+
+            CREATE TABLE IF NOT EXISTS tags (
+                id TEXT PRIMARY KEY,
+                name TEXT UNIQUE NOT NULL,
+                color TEXT NOT NULL DEFAULT '#64748b',
+                sort_index INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL DEFAULT 0,
+                updated_at INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE TABLE IF NOT EXISTS item_tags (
+                item_id TEXT NOT NULL,
+                tag_id TEXT NOT NULL,
+                PRIMARY KEY (item_id, tag_id),
+                FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE,
+                FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+            );
+
+            CREATE TABLE IF NOT EXISTS vault_files (
+                vault_key TEXT PRIMARY KEY,
+                vault_path TEXT NOT NULL,
+                sha256 TEXT NOT NULL,
+                ext TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL DEFAULT 0,
+                ref_count INTEGER NOT NULL DEFAULT 0 CHECK(ref_count >= 0),
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS item_overlays (
+                item_id TEXT PRIMARY KEY,
+                strokes_json TEXT NOT NULL DEFAULT '[]',
+                updated_at INTEGER NOT NULL,
+                FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE
+            );
+
+            CREATE TABLE IF NOT EXISTS item_links (
+                from_item_id TEXT NOT NULL,
+                to_item_id TEXT NOT NULL,
+                link_type TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (from_item_id, to_item_id, link_type),
+                FOREIGN KEY (from_item_id) REFERENCES items(id) ON DELETE CASCADE,
+                FOREIGN KEY (to_item_id) REFERENCES items(id) ON DELETE CASCADE,
+                CHECK (from_item_id <> to_item_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS item_notes (
+                id TEXT PRIMARY KEY,
+                item_id TEXT NOT NULL,
+                body TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE
+            );
+
+            CREATE TABLE IF NOT EXISTS item_ocr (
+                item_id TEXT PRIMARY KEY,
+                status TEXT NOT NULL CHECK(status IN ('done', 'empty', 'failed', 'unavailable')),
+                text TEXT NOT NULL DEFAULT '',
+                confidence REAL NULL,
+                engine TEXT NULL,
+                error TEXT NULL,
+                updated_at INTEGER NOT NULL,
+                FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE
+            );
+
+            CREATE TABLE IF NOT EXISTS favicon_fetch_failures (
+                host TEXT PRIMARY KEY,
+                last_error TEXT NOT NULL,
+                failed_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS app_settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS import_metrics (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at INTEGER NOT NULL,
+                filename TEXT NOT NULL,
+                ext TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL DEFAULT 0,
+                hash_ms INTEGER NOT NULL DEFAULT 0,
+                copy_ms INTEGER NOT NULL DEFAULT 0,
+                metadata_ms INTEGER NOT NULL DEFAULT 0,
+                thumb_ms INTEGER NOT NULL DEFAULT 0,
+                total_ms INTEGER NOT NULL DEFAULT 0,
+                deduped INTEGER NOT NULL DEFAULT 0 CHECK(deduped IN (0, 1)),
+                success INTEGER NOT NULL DEFAULT 1 CHECK(success IN (0, 1))
+            );
+
+            CREATE TABLE IF NOT EXISTS import_reports (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                operation TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                report_json TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS import_batches (
+                id TEXT PRIMARY KEY,
+                source TEXT NOT NULL,
+                description TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                finished_at INTEGER NULL,
+                imported_count INTEGER NOT NULL DEFAULT 0,
+                failed_count INTEGER NOT NULL DEFAULT 0,
+                skipped_count INTEGER NOT NULL DEFAULT 0,
+                deduplicated_count INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE TABLE IF NOT EXISTS seen_sources (
+                source_path TEXT PRIMARY KEY,
+                source_root TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL,
+                mtime_ms INTEGER NOT NULL,
+                sha256 TEXT NOT NULL,
+                seen_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS quarantined_files (
+                file_name TEXT PRIMARY KEY,
+                vault_key TEXT NOT NULL,
+                original_filename TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                detail TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL DEFAULT 0,
+                quarantined_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS item_file_versions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                item_id TEXT NOT NULL,
+                vault_key TEXT NOT NULL,
+                vault_path TEXT NOT NULL,
+                replaced_at INTEGER NOT NULL,
+                FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE
+            );
+
+            CREATE TABLE IF NOT EXISTS item_audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                item_id TEXT NOT NULL,
+                field TEXT NOT NULL,
+                old_value TEXT,
+                new_value TEXT,
+                changed_at INTEGER NOT NULL,
+                FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_vault_files_ref_count ON vault_files(ref_count);
+            CREATE INDEX IF NOT EXISTS idx_import_metrics_created_at ON import_metrics(created_at);
+            CREATE INDEX IF NOT EXISTS idx_seen_sources_root ON seen_sources(source_root);
+            CREATE INDEX IF NOT EXISTS idx_item_links_to_item_id ON item_links(to_item_id);
+            CREATE INDEX IF NOT EXISTS idx_item_notes_item_id ON item_notes(item_id, created_at);
+            CREATE INDEX IF NOT EXISTS idx_item_tags_tag_id ON item_tags(tag_id, item_id);
+            CREATE INDEX IF NOT EXISTS idx_item_audit_log_item_id ON item_audit_log(item_id, id);
+            CREATE INDEX IF NOT EXISTS idx_item_file_versions_item_id ON item_file_versions(item_id, id);
+            CREATE INDEX IF NOT EXISTS idx_item_file_versions_vault_key ON item_file_versions(vault_key);
+            CREATE INDEX IF NOT EXISTS idx_item_audit_log_changed_at ON item_audit_log(changed_at);
+            CREATE INDEX IF NOT EXISTS idx_import_batches_started_at ON import_batches(started_at);
+            "#,
+        )
+        .map_err(|err| format!("failed to run sqlite migrations: {}", err))?;
+    ensure_items_status_columns(connection)?;
+    ensure_items_bookmark_columns(connection)?;
+    ensure_items_meta_fetch_columns(connection)?;
+    ensure_items_preview_cache_columns(connection)?;
+    ensure_items_last_opened_column(connection)?;
+    ensure_items_rating_favorite_columns(connection)?;
+    ensure_items_archive_columns(connection)?;
+    ensure_items_import_source_column(connection)?;
+    ensure_items_description_format_columns(connection)?;
+    ensure_items_sensitive_column(connection)?;
+    ensure_items_integrity_column(connection)?;
+    ensure_items_import_batch_column(connection)?;
+    ensure_items_import_error_column(connection)?;
+    ensure_vault_files_columns(connection)?;
+    ensure_collections_columns(connection)?;
+    ensure_collection_items_columns(connection)?;
+    ensure_tags_columns(connection)?;
+    normalize_stored_tag_colors(connection)?;
+    ensure_import_metrics_columns(connection)?;
+    ensure_collection_items_indexes(connection)?;
+    ensure_items_indexes(connection)?;
+    backfill_collection_items_from_items(connection)?;
+    sync_legacy_item_collection_ids(connection)?;
+    coerce_unknown_item_types(connection)?;
+    Ok(())
+}
+
+pub(crate) fn coerce_unknown_item_types(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("SELECT DISTINCT type FROM items")
+        .map_err(|err| format!("failed to prepare item type scan: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to scan item types: {}", err))?;
+    let mut stored_types = Vec::new();
+    for row_result in rows {
+        stored_types.push(row_result.map_err(|err| format!("failed to read item type: {}", err))?);
+    }
+
+    for stored_type in stored_types {
+        let coerced =
+            normalize_item_type(&stored_type).unwrap_or_else(|_| FALLBACK_ITEM_TYPE.to_string());
+        if coerced == stored_type {
+            continue;
+        }
+        let updated = connection
+            .execute(
+                "UPDATE items SET type = ?1 WHERE type = ?2",
+                params![&coerced, &stored_type],
+            )
+            .map_err(|err| format!("failed to coerce item type {}: {}", stored_type, err))?;
+        log_eprintln!(
+            "[db-migrations] coerced {} item(s) with type \"{}\" to \"{}\"",
+            updated,
+            stored_type,
+            coerced
+        );
+    }
+    Ok(())
+}
+
+pub(crate) fn ensure_items_status_columns(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(items)")
+        .map_err(|err| format!("failed to inspect items table info: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| format!("failed to read items table info: {}", err))?;
+
+    let mut has_thumb_status = false;
+    let mut has_import_status = false;
+    for row_result in rows {
+        let column_name =
+            row_result.map_err(|err| format!("failed to parse items table column: {}", err))?;
+        if column_name == "thumb_status" {
+            has_thumb_status = true;
+        }
+        if column_name == "import_status" {
+            has_import_status = true;
+        }
+    }
+
+    if !has_thumb_status {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN thumb_status TEXT NOT NULL DEFAULT 'pending'",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.thumb_status column: {}", err))?;
+    }
+
+    if !has_import_status {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN import_status TEXT NOT NULL DEFAULT 'ready'",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.import_status column: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn ensure_items_bookmark_columns(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(items)")
+        .map_err(|err| {
+            format!(
+                "failed to inspect items table info for bookmark columns: {}",
+                err
+            )
+        })?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| {
+            format!(
+                "failed to read items table info for bookmark columns: {}",
+                err
+            )
+        })?;
+
+    let mut has_url = false;
+    let mut has_favicon_path = false;
+    let mut has_meta_status = false;
+    for row_result in rows {
+        let column_name = row_result
+            .map_err(|err| format!("failed to parse items table column for bookmarks: {}", err))?;
+        if column_name == "url" {
+            has_url = true;
+        }
+        if column_name == "favicon_path" {
+            has_favicon_path = true;
+        }
+        if column_name == "meta_status" {
+            has_meta_status = true;
+        }
+    }
+
+    if !has_url {
+        connection
+            .execute("ALTER TABLE items ADD COLUMN url TEXT NULL", [])
+            .map_err(|err| format!("failed to add items.url column: {}", err))?;
+    }
+
+    if !has_favicon_path {
+        connection
+            .execute("ALTER TABLE items ADD COLUMN favicon_path TEXT NULL", [])
+            .map_err(|err| format!("failed to add items.favicon_path column: {}", err))?;
+    }
+
+    if !has_meta_status {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN meta_status TEXT NOT NULL DEFAULT 'ready'",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.meta_status column: {}", err))?;
+    }
+
+    connection
+        .execute(
+            "UPDATE items
+             SET meta_status = CASE
+                 WHEN type = 'bookmark'
+                      AND (url IS NULL OR TRIM(url) = '')
+                 THEN 'error'
+                 ELSE 'ready'
+             END
+             WHERE meta_status IS NULL OR TRIM(meta_status) = ''",
+            [],
+        )
+        .map_err(|err| format!("failed to backfill items.meta_status values: {}", err))?;
+
+    Ok(())
+}
+
+pub(crate) fn ensure_items_last_opened_column(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(items)")
+        .map_err(|err| {
+            format!(
+                "failed to inspect items table info for last_opened_at: {}",
+                err
+            )
+        })?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| {
+            format!(
+                "failed to read items table info for last_opened_at: {}",
+                err
+            )
+        })?;
+
+    let mut has_last_opened_at = false;
+    for row_result in rows {
+        let column_name = row_result.map_err(|err| {
+            format!(
+                "failed to parse items table column for last_opened_at: {}",
+                err
+            )
+        })?;
+        if column_name == "last_opened_at" {
+            has_last_opened_at = true;
+        }
+    }
+
+    if !has_last_opened_at {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN last_opened_at INTEGER NULL",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.last_opened_at column: {}", err))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn ensure_items_preview_cache_columns(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(items)")
+        .map_err(|err| {
+            format!(
+                "failed to inspect items table info for preview cache columns: {}",
+                err
+            )
+        })?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| {
+            format!(
+                "failed to read items table info for preview cache columns: {}",
+                err
+            )
+        })?;
+
+    let mut existing = BTreeSet::new();
+    for row_result in rows {
+        existing.insert(row_result.map_err(|err| {
+            format!(
+                "failed to parse items table column for preview cache columns: {}",
+                err
+            )
+        })?);
+    }
+
+    for (column_name, column_type) in [
+        ("preview_source_url", "TEXT"),
+        ("preview_etag", "TEXT"),
+        ("preview_last_modified", "TEXT"),
+        ("preview_cached_at", "INTEGER"),
+    ] {
+        if !existing.contains(column_name) {
+            connection
+                .execute(
+                    &format!(
+                        "ALTER TABLE items ADD COLUMN {} {} NULL",
+                        column_name, column_type
+                    ),
+                    [],
+                )
+                .map_err(|err| format!("failed to add items.{} column: {}", column_name, err))?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn ensure_items_meta_fetch_columns(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(items)")
+        .map_err(|err| {
+            format!(
+                "failed to inspect items table info for meta fetch columns: {}",
+                err
+            )
+        })?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| {
+            format!(
+                "failed to read items table info for meta fetch columns: {}",
+                err
+            )
+        })?;
+
+    let mut has_meta_fetched_at = false;
+    let mut has_meta_fetched_title = false;
+    for row_result in rows {
+        let column_name = row_result.map_err(|err| {
+            format!(
+                "failed to parse items table column for meta fetch columns: {}",
+                err
+            )
+        })?;
+        if column_name == "meta_fetched_at" {
+            has_meta_fetched_at = true;
+        }
+        if column_name == "meta_fetched_title" {
+            has_meta_fetched_title = true;
+        }
+    }
+
+    if !has_meta_fetched_at {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN meta_fetched_at INTEGER NULL",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.meta_fetched_at column: {}", err))?;
+    }
+
+    if !has_meta_fetched_title {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN meta_fetched_title TEXT NULL",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.meta_fetched_title column: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn ensure_items_rating_favorite_columns(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(items)")
+        .map_err(|err| {
+            format!(
+                "failed to inspect items table info for rating/favorite columns: {}",
+                err
+            )
+        })?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| {
+            format!(
+                "failed to read items table info for rating/favorite columns: {}",
+                err
+            )
+        })?;
+
+    let mut has_rating = false;
+    let mut has_is_favorite = false;
+    for row_result in rows {
+        let column_name = row_result.map_err(|err| {
+            format!(
+                "failed to parse items table column for rating/favorite columns: {}",
+                err
+            )
+        })?;
+        if column_name == "rating" {
+            has_rating = true;
+        }
+        if column_name == "is_favorite" {
+            has_is_favorite = true;
+        }
+    }
+
+    if !has_rating {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN rating INTEGER NOT NULL DEFAULT 0",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.rating column: {}", err))?;
+    }
+
+    if !has_is_favorite {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN is_favorite INTEGER NOT NULL DEFAULT 0",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.is_favorite column: {}", err))?;
+    }
+
+    connection
+        .execute(
+            "UPDATE items
+             SET rating = CASE
+                 WHEN rating IS NULL THEN 0
+                 WHEN CAST(rating AS INTEGER) < 0 THEN 0
+                 WHEN CAST(rating AS INTEGER) > 5 THEN 5
+                 ELSE CAST(rating AS INTEGER)
+             END",
+            [],
+        )
+        .map_err(|err| format!("failed to backfill items.rating values: {}", err))?;
+
+    connection
+        .execute(
+            "UPDATE items
+             SET is_favorite = CASE
+                 WHEN is_favorite IS NULL THEN 0
+                 WHEN CAST(is_favorite AS INTEGER) <> 0 THEN 1
+                 ELSE 0
+             END",
+            [],
+        )
+        .map_err(|err| format!("failed to backfill items.is_favorite values: {}", err))?;
+
+    Ok(())
+}
+
+pub(crate) fn ensure_items_archive_columns(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(items)")
+        .map_err(|err| {
+            format!(
+                "failed to inspect items table info for archive column: {}",
+                err
+            )
+        })?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| {
+            format!(
+                "failed to read items table info for archive column: {}",
+                err
+            )
+        })?;
+
+    let mut has_is_archived = false;
+    for row_result in rows {
+        let column_name = row_result.map_err(|err| {
+            format!(
+                "failed to parse items table column for archive column: {}",
+                err
+            )
+        })?;
+        if column_name == "is_archived" {
+            has_is_archived = true;
+        }
+    }
+
+    if !has_is_archived {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN is_archived INTEGER NOT NULL DEFAULT 0 CHECK(is_archived IN (0, 1))",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.is_archived column: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn ensure_items_import_source_column(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(items)")
+        .map_err(|err| {
+            format!(
+                "failed to inspect items table info for import source column: {}",
+                err
+            )
+        })?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| {
+            format!(
+                "failed to read items table info for import source column: {}",
+                err
+            )
+        })?;
+
+    let mut has_original_source_path = false;
+    for row_result in rows {
+        let column_name = row_result.map_err(|err| {
+            format!(
+                "failed to parse items table column for import source column: {}",
+                err
+            )
+        })?;
+        if column_name == "original_source_path" {
+            has_original_source_path = true;
+        }
+    }
+
+    if !has_original_source_path {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN original_source_path TEXT NULL",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.original_source_path column: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn ensure_items_description_format_columns(
+    connection: &Connection,
+) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(items)")
+        .map_err(|err| {
+            format!(
+                "failed to inspect items table info for description columns: {}",
+                err
+            )
+        })?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| {
+            format!(
+                "failed to read items table info for description columns: {}",
+                err
+            )
+        })?;
+
+    let mut has_description_format = false;
+    let mut has_description_first_url = false;
+    let mut has_description_heading = false;
+    for row_result in rows {
+        let column_name = row_result.map_err(|err| {
+            format!(
+                "failed to parse items table column for description columns: {}",
+                err
+            )
+        })?;
+        if column_name == "description_format" {
+            has_description_format = true;
+        }
+        if column_name == "description_first_url" {
+            has_description_first_url = true;
+        }
+        if column_name == "description_heading" {
+            has_description_heading = true;
+        }
+    }
+
+    if !has_description_format {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN description_format TEXT NOT NULL DEFAULT 'plain'",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.description_format column: {}", err))?;
+    }
+    if !has_description_first_url {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN description_first_url TEXT NULL",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.description_first_url column: {}", err))?;
+    }
+    if !has_description_heading {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN description_heading TEXT NULL",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.description_heading column: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn ensure_items_sensitive_column(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(items)")
+        .map_err(|err| {
+            format!(
+                "failed to inspect items table info for sensitive column: {}",
+                err
+            )
+        })?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| {
+            format!(
+                "failed to read items table info for sensitive column: {}",
+                err
+            )
+        })?;
+
+    let mut has_is_sensitive = false;
+    for row_result in rows {
+        let column_name = row_result.map_err(|err| {
+            format!(
+                "failed to parse items table column for sensitive column: {}",
+                err
+            )
+        })?;
+        if column_name == "is_sensitive" {
+            has_is_sensitive = true;
+        }
+    }
+
+    if !has_is_sensitive {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN is_sensitive INTEGER NOT NULL DEFAULT 0 CHECK(is_sensitive IN (0, 1))",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.is_sensitive column: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn ensure_items_integrity_column(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(items)")
+        .map_err(|err| {
+            format!(
+                "failed to inspect items table info for integrity column: {}",
+                err
+            )
+        })?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| {
+            format!(
+                "failed to read items table info for integrity column: {}",
+                err
+            )
+        })?;
+
+    let mut has_integrity_status = false;
+    for row_result in rows {
+        let column_name = row_result.map_err(|err| {
+            format!(
+                "failed to parse items table column for integrity column: {}",
+                err
+            )
+        })?;
+        if column_name == "integrity_status" {
+            has_integrity_status = true;
+        }
+    }
+
+    if !has_integrity_status {
+        connection
+            .execute(
+                "ALTER TABLE items ADD COLUMN integrity_status TEXT NOT NULL DEFAULT 'ok'",
+                [],
+            )
+            .map_err(|err| format!("failed to add items.integrity_status column: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn ensure_items_import_error_column(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(items)")
+        .map_err(|err| {
+            format!(
+                "failed to inspect items table info for import error column: {}",
+                err
+            )
+        })?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| {
+            format!(
+                "failed to read items table info for import error column: {}",
+                err
+            )
+        })?;
+
+    let mut has_import_error = false;
+    for row_result in rows {
+        let column_name = row_result.map_err(|err| {
+            format!(
+                "failed to parse items table column for import error column: {}",
+                err
+            )
+        })?;
+        if column_name == "import_error" {
+            has_import_error = true;
+        }
+    }
+
+    if !has_import_error {
+        connection
+            .execute("ALTER TABLE items ADD COLUMN import_error TEXT NULL", [])
+            .map_err(|err| format!("failed to add items.import_error column: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn ensure_items_import_batch_column(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(items)")
+        .map_err(|err| {
+            format!(
+                "failed to inspect items table info for import batch column: {}",
+                err
+            )
+        })?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| {
+            format!(
+                "failed to read items table info for import batch column: {}",
+                err
+            )
+        })?;
+
+    let mut has_import_batch_id = false;
+    for row_result in rows {
+        let column_name = row_result.map_err(|err| {
+            format!(
+                "failed to parse items table column for import batch column: {}",
+                err
+            )
+        })?;
+        if column_name == "import_batch_id" {
+            has_import_batch_id = true;
+        }
+    }
+
+    if !has_import_batch_id {
+        connection
+            .execute("ALTER TABLE items ADD COLUMN import_batch_id TEXT NULL", [])
+            .map_err(|err| format!("failed to add items.import_batch_id column: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn ensure_vault_files_columns(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(vault_files)")
+        .map_err(|err| format!("failed to inspect vault_files table info: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| format!("failed to read vault_files table info: {}", err))?;
+
+    let mut has_is_encrypted = false;
+    let mut has_disk_size_bytes = false;
+    let mut has_disk_mtime_ms = false;
+    let mut has_is_protected = false;
+    for row_result in rows {
+        let column_name = row_result
+            .map_err(|err| format!("failed to parse vault_files table column: {}", err))?;
+        if column_name == "is_encrypted" {
+            has_is_encrypted = true;
+        }
+        if column_name == "is_protected" {
+            has_is_protected = true;
+        }
+        if column_name == "disk_size_bytes" {
+            has_disk_size_bytes = true;
+        }
+        if column_name == "disk_mtime_ms" {
+            has_disk_mtime_ms = true;
+        }
+    }
+
+    if !has_is_encrypted {
+        connection
+            .execute(
+                "ALTER TABLE vault_files ADD COLUMN is_encrypted INTEGER NOT NULL DEFAULT 0 CHECK(is_encrypted IN (0, 1))",
+                [],
+            )
+            .map_err(|err| format!("failed to add vault_files.is_encrypted column: {}", err))?;
+    }
+
+    if !has_disk_size_bytes {
+        connection
+            .execute(
+                "ALTER TABLE vault_files ADD COLUMN disk_size_bytes INTEGER NULL",
+                [],
+            )
+            .map_err(|err| format!("failed to add vault_files.disk_size_bytes column: {}", err))?;
+    }
+
+    if !has_disk_mtime_ms {
+        connection
+            .execute(
+                "ALTER TABLE vault_files ADD COLUMN disk_mtime_ms INTEGER NULL",
+                [],
+            )
+            .map_err(|err| format!("failed to add vault_files.disk_mtime_ms column: {}", err))?;
+    }
+
+    if !has_is_protected {
+        connection
+            .execute(
+                "ALTER TABLE vault_files ADD COLUMN is_protected INTEGER NOT NULL DEFAULT 0 CHECK(is_protected IN (0, 1))",
+                [],
+            )
+            .map_err(|err| format!("failed to add vault_files.is_protected column: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn ensure_import_metrics_columns(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(import_metrics)")
+        .map_err(|err| format!("failed to inspect import_metrics table info: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| format!("failed to read import_metrics table info: {}", err))?;
+
+    let mut existing_columns = Vec::new();
+    for row_result in rows {
+        existing_columns.push(
+            row_result
+                .map_err(|err| format!("failed to parse import_metrics table column: {}", err))?,
+        );
+    }
+
+    for column_name in ["decode_ms", "resize_ms", "encode_ms"] {
+        if !existing_columns
+            .iter()
+            .any(|existing| existing == column_name)
+        {
+            connection
+                .execute(
+                    &format!(
+                        "ALTER TABLE import_metrics ADD COLUMN {} INTEGER NOT NULL DEFAULT 0",
+                        column_name
+                    ),
+                    [],
+                )
+                .map_err(|err| {
+                    format!(
+                        "failed to add import_metrics.{} column: {}",
+                        column_name, err
+                    )
+                })?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn ensure_collections_columns(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(collections)")
+        .map_err(|err| format!("failed to inspect collections table info: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| format!("failed to read collections table info: {}", err))?;
+
+    let mut has_description = false;
+    let mut has_icon = false;
+    let mut has_updated_at = false;
+    let mut has_is_archived = false;
+    let mut has_cover_item_id = false;
+    let mut has_icon_type = false;
+    let mut has_sort_index = false;
+
+    for row_result in rows {
+        let column_name = row_result
+            .map_err(|err| format!("failed to parse collections table column: {}", err))?;
+        if column_name == "description" {
+            has_description = true;
+        }
+        if column_name == "icon" {
+            has_icon = true;
+        }
+        if column_name == "updated_at" {
+            has_updated_at = true;
+        }
+        if column_name == "is_archived" {
+            has_is_archived = true;
+        }
+        if column_name == "cover_item_id" {
+            has_cover_item_id = true;
+        }
+        if column_name == "icon_type" {
+            has_icon_type = true;
+        }
+        if column_name == "sort_index" {
+            has_sort_index = true;
+        }
+    }
+
+    if !has_description {
+        connection
+            .execute(
+                "ALTER TABLE collections ADD COLUMN description TEXT NULL",
+                [],
+            )
+            .map_err(|err| format!("failed to add collections.description column: {}", err))?;
+    }
+
+    if !has_icon {
+        connection
+            .execute(
+                "ALTER TABLE collections ADD COLUMN icon TEXT NOT NULL DEFAULT 'folder'",
+                [],
+            )
+            .map_err(|err| format!("failed to add collections.icon column: {}", err))?;
+    }
+
+    if !has_updated_at {
+        connection
+            .execute(
+                "ALTER TABLE collections ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0",
+                [],
+            )
+            .map_err(|err| format!("failed to add collections.updated_at column: {}", err))?;
+    }
+
+    if !has_is_archived {
+        connection
+            .execute(
+                "ALTER TABLE collections ADD COLUMN is_archived INTEGER NOT NULL DEFAULT 0 CHECK(is_archived IN (0, 1))",
+                [],
+            )
+            .map_err(|err| format!("failed to add collections.is_archived column: {}", err))?;
+    }
+
+    if !has_cover_item_id {
+        connection
+            .execute(
+                "ALTER TABLE collections ADD COLUMN cover_item_id TEXT NULL REFERENCES items(id) ON DELETE SET NULL",
+                [],
+            )
+            .map_err(|err| format!("failed to add collections.cover_item_id column: {}", err))?;
+    }
+
+    if !has_icon_type {
+        connection
+            .execute(
+                "ALTER TABLE collections ADD COLUMN icon_type TEXT NOT NULL DEFAULT 'named' CHECK(icon_type IN ('named', 'emoji', 'image'))",
+                [],
+            )
+            .map_err(|err| format!("failed to add collections.icon_type column: {}", err))?;
+    }
+
+    if !has_sort_index {
+        connection
+            .execute(
+                "ALTER TABLE collections ADD COLUMN sort_index INTEGER NOT NULL DEFAULT 0",
+                [],
+            )
+            .map_err(|err| format!("failed to add collections.sort_index column: {}", err))?;
+        connection
+            .execute(
+                "UPDATE collections
+                 SET sort_index = (
+                   SELECT COUNT(*) FROM collections AS sibling
+                   WHERE sibling.parent_id IS collections.parent_id
+                     AND (sibling.created_at < collections.created_at
+                       OR (sibling.created_at = collections.created_at AND sibling.id < collections.id))
+                 )",
+                [],
+            )
+            .map_err(|err| format!("failed to backfill collections.sort_index values: {}", err))?;
+    }
+
+    connection
+        .execute(
+            "UPDATE collections
+             SET updated_at = created_at
+             WHERE updated_at = 0",
+            [],
+        )
+        .map_err(|err| format!("failed to backfill collections.updated_at values: {}", err))?;
+
+    Ok(())
+}
+
+pub(crate) fn ensure_collection_items_columns(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(collection_items)")
+        .map_err(|err| format!("failed to inspect collection_items table info: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| format!("failed to read collection_items table info: {}", err))?;
+
+    let mut has_sort_index = false;
+    let mut has_is_pinned = false;
+    for row_result in rows {
+        let column_name = row_result
+            .map_err(|err| format!("failed to parse collection_items table column: {}", err))?;
+        if column_name == "sort_index" {
+            has_sort_index = true;
+        }
+        if column_name == "is_pinned" {
+            has_is_pinned = true;
+        }
+    }
+
+    if !has_sort_index {
+        connection
+            .execute(
+                "ALTER TABLE collection_items ADD COLUMN sort_index INTEGER NOT NULL DEFAULT 0",
+                [],
+            )
+            .map_err(|err| format!("failed to add collection_items.sort_index column: {}", err))?;
+    }
+
+    if !has_sort_index {
+        connection
+            .execute(
+                "UPDATE collection_items
+                 SET sort_index = CASE
+                    WHEN created_at IS NULL THEN 0
+                    ELSE created_at
+                 END",
+                [],
+            )
+            .map_err(|err| {
+                format!(
+                    "failed to backfill collection_items.sort_index values: {}",
+                    err
+                )
+            })?;
+    }
+
+    if !has_is_pinned {
+        connection
+            .execute(
+                "ALTER TABLE collection_items ADD COLUMN is_pinned INTEGER NOT NULL DEFAULT 0 CHECK(is_pinned IN (0, 1))",
+                [],
+            )
+            .map_err(|err| format!("failed to add collection_items.is_pinned column: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn ensure_tags_columns(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("PRAGMA table_info(tags)")
+        .map_err(|err| format!("failed to inspect tags table info: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| format!("failed to read tags table info: {}", err))?;
+
+    let mut has_color = false;
+    let mut has_sort_index = false;
+    let mut has_created_at = false;
+    let mut has_updated_at = false;
+    let mut has_is_system = false;
+    for row_result in rows {
+        let column_name =
+            row_result.map_err(|err| format!("failed to parse tags table column: {}", err))?;
+        if column_name == "color" {
+            has_color = true;
+        }
+        if column_name == "sort_index" {
+            has_sort_index = true;
+        }
+        if column_name == "created_at" {
+            has_created_at = true;
+        }
+        if column_name == "updated_at" {
+            has_updated_at = true;
+        }
+        if column_name == "is_system" {
+            has_is_system = true;
+        }
+    }
+
+    if !has_color {
+        connection
+            .execute(
+                "ALTER TABLE tags ADD COLUMN color TEXT NOT NULL DEFAULT '#64748b'",
+                [],
+            )
+            .map_err(|err| format!("failed to add tags.color column: {}", err))?;
+    }
+
+    if !has_created_at {
+        connection
+            .execute(
+                "ALTER TABLE tags ADD COLUMN created_at INTEGER NOT NULL DEFAULT 0",
+                [],
+            )
+            .map_err(|err| format!("failed to add tags.created_at column: {}", err))?;
+    }
+
+    if !has_updated_at {
+        connection
+            .execute(
+                "ALTER TABLE tags ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0",
+                [],
+            )
+            .map_err(|err| format!("failed to add tags.updated_at column: {}", err))?;
+    }
+
+    if !has_sort_index {
+        connection
+            .execute(
+                "ALTER TABLE tags ADD COLUMN sort_index INTEGER NOT NULL DEFAULT 0",
+                [],
+            )
+            .map_err(|err| format!("failed to add tags.sort_index column: {}", err))?;
+    }
+
+    if !has_is_system {
+        connection
+            .execute(
+                "ALTER TABLE tags ADD COLUMN is_system INTEGER NOT NULL DEFAULT 0",
+                [],
+            )
+            .map_err(|err| format!("failed to add tags.is_system column: {}", err))?;
+    }
+
+    let now = Utc::now().timestamp_millis();
+    connection
+        .execute(
+            "UPDATE tags
+             SET color = COALESCE(NULLIF(TRIM(color), ''), ?1)
+             WHERE color IS NULL OR TRIM(color) = ''",
+            params![DEFAULT_TAG_COLOR],
+        )
+        .map_err(|err| format!("failed to backfill tags.color values: {}", err))?;
+    connection
+        .execute(
+            "UPDATE tags
+             SET created_at = ?1
+             WHERE created_at = 0",
+            params![now],
+        )
+        .map_err(|err| format!("failed to backfill tags.created_at values: {}", err))?;
+    connection
+        .execute(
+            "UPDATE tags
+             SET updated_at = created_at
+             WHERE updated_at = 0",
+            [],
+        )
+        .map_err(|err| format!("failed to backfill tags.updated_at values: {}", err))?;
+    if !has_sort_index {
+        connection
+            .execute(
+                "UPDATE tags
+                 SET sort_index = created_at
+                 WHERE sort_index = 0",
+                [],
+            )
+            .map_err(|err| format!("failed to backfill tags.sort_index values: {}", err))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn ensure_collection_items_indexes(connection: &Connection) -> Result<(), String> {
+    connection
+        .execute_batch(
+            r#"
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_collection_items_collection_item_unique
+            ON collection_items(collection_id, item_id);
+            CREATE INDEX IF NOT EXISTS idx_collection_items_item_id
+            ON collection_items(item_id);
+            CREATE INDEX IF NOT EXISTS idx_collection_items_collection_id
+            ON collection_items(collection_id);
+            CREATE INDEX IF NOT EXISTS idx_collection_items_collection_sort
+            ON collection_items(collection_id, sort_index);
+            "#,
+        )
+        .map_err(|err| format!("failed to ensure collection_items indexes: {}", err))?;
+    Ok(())
+}
+
+pub(crate) fn ensure_items_indexes(connection: &Connection) -> Result<(), String> {
+    connection
+        .execute_batch(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_items_favorite
+            ON items(is_favorite, created_at) WHERE is_favorite = 1;
+            CREATE INDEX IF NOT EXISTS idx_items_rating
+            ON items(rating, created_at) WHERE rating > 0;
+            CREATE INDEX IF NOT EXISTS idx_items_sensitive
+            ON items(is_sensitive) WHERE is_sensitive = 1;
+            CREATE INDEX IF NOT EXISTS idx_items_created_at
+            ON items(created_at);
+            CREATE INDEX IF NOT EXISTS idx_items_import_batch_id
+            ON items(import_batch_id) WHERE import_batch_id IS NOT NULL;
+            "#,
+        )
+        .map_err(|err| format!("failed to ensure items indexes: {}", err))?;
+    Ok(())
+}
+
+pub(crate) fn backfill_collection_items_from_items(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare(
+            "SELECT id, collection_id, created_at
+             FROM items
+             WHERE collection_id IS NOT NULL AND TRIM(collection_id) <> ''",
+        )
+        .map_err(|err| format!("failed to prepare collection_items backfill query: {}", err))?;
+
+    let row_iter = stmt
+        .query_map([], |row| {
+            let item_id: String = row.get(0)?;
+            let collection_id: String = row.get(1)?;
+            let created_at: i64 = row.get(2)?;
+            Ok((item_id, collection_id, created_at))
+        })
+        .map_err(|err| {
+            format!(
+                "failed to query items for collection_items backfill: {}",
+                err
+            )
+        })?;
+
+    let mut rows = Vec::new();
+    for row_result in row_iter {
+        rows.push(
+            row_result
+                .map_err(|err| format!("failed to read collection_items backfill row: {}", err))?,
+        );
+    }
+
+    for (item_id, collection_id, created_at) in rows {
+        connection
+            .execute(
+                "INSERT OR IGNORE INTO collection_items (
+                    id,
+                    collection_id,
+                    item_id,
+                    custom_title,
+                    custom_description,
+                    sort_index,
+                    created_at
+                ) VALUES (?1, ?2, ?3, NULL, NULL, ?4, ?5)",
+                params![
+                    Uuid::new_v4().to_string(),
+                    collection_id,
+                    item_id,
+                    created_at.max(0),
+                    created_at.max(0)
+                ],
+            )
+            .map_err(|err| format!("failed to backfill collection_items row: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn sync_legacy_item_collection_ids(connection: &Connection) -> Result<(), String> {
+    connection
+        .execute(
+            "UPDATE items
+             SET collection_id = NULL
+             WHERE collection_id IS NOT NULL
+               AND NOT EXISTS (
+                 SELECT 1
+                 FROM collection_items AS ci
+                 WHERE ci.item_id = items.id
+                   AND ci.collection_id = items.collection_id
+               )",
+            [],
+        )
+        .map_err(|err| {
+            format!(
+                "failed to clear stale legacy item.collection_id values: {}",
+                err
+            )
+        })?;
+
+    connection
+        .execute(
+            "UPDATE items
+             SET collection_id = (
+               SELECT ci.collection_id
+               FROM collection_items AS ci
+               WHERE ci.item_id = items.id
+               ORDER BY ci.created_at ASC, ci.id ASC
+               LIMIT 1
+             )
+             WHERE collection_id IS NULL
+               AND EXISTS (
+                 SELECT 1
+                 FROM collection_items AS ci
+                 WHERE ci.item_id = items.id
+               )",
+            [],
+        )
+        .map_err(|err| {
+            format!(
+                "failed to backfill legacy item.collection_id values: {}",
+                err
+            )
+        })?;
+
+    Ok(())
+}
+
+pub(crate) fn backups_root_path() -> Result<PathBuf, String> {
+    Ok(app_root_path()?.join("backups"))
+}
+
+pub(crate) fn ensure_backups_root_internal() -> Result<PathBuf, String> {
+    let root = backups_root_path()?;
+    fs::create_dir_all(&root)
+        .map_err(|err| format!("failed to create backups root {}: {}", root.display(), err))?;
+    Ok(root)
+}
+
+pub(crate) fn exports_root_path() -> Result<PathBuf, String> {
+    Ok(app_root_path()?.join("exports"))
+}
+
+pub(crate) fn ensure_exports_root_internal() -> Result<PathBuf, String> {
+    let root = exports_root_path()?;
+    fs::create_dir_all(&root)
+        .map_err(|err| format!("failed to create exports root {}: {}", root.display(), err))?;
+    Ok(root)
+}
+
+pub(crate) fn canonicalize_for_comparison(path: &Path) -> PathBuf {
+    if let Ok(canonical) = fs::canonicalize(path) {
+        return canonical;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            canonicalize_for_comparison(parent).join(name)
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+pub(crate) fn ensure_path_outside_storage(path: &Path) -> Result<(), String> {
+    let storage_root = canonicalize_for_comparison(&storage_root_path()?);
+    if canonicalize_for_comparison(path).starts_with(&storage_root) {
+        return Err(format!(
+            "refusing to use a path inside the vault storage directory: {}",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn verify_sqlite_integrity(connection: &Connection, pragma: &str) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare(&format!("PRAGMA {}", pragma))
+        .map_err(|err| format!("failed to prepare {}: {}", pragma, err))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to run {}: {}", pragma, err))?;
+
+    let mut problems = Vec::new();
+    for row_result in rows {
+        let message =
+            row_result.map_err(|err| format!("failed to read {} row: {}", pragma, err))?;
+        if message != "ok" {
+            problems.push(message);
+        }
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} failed: {}", pragma, problems.join("; ")))
+    }
+}
+
+pub(crate) fn verify_backup_file(path: &Path) -> Result<(), String> {
+    let connection = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|err| format!("failed to open backup {}: {}", path.display(), err))?;
+    verify_sqlite_integrity(&connection, "integrity_check")?;
+    let has_items_table = connection
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'items'",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|err| {
+            format!(
+                "failed to inspect backup schema {}: {}",
+                path.display(),
+                err
+            )
+        })?;
+    if has_items_table.is_none() {
+        return Err(format!(
+            "backup is not a Stumble database: {}",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn list_backup_files(directory: &Path) -> Result<Vec<PathBuf>, String> {
+    if !directory.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = read_directory(directory).map_err(|err| {
+        format!(
+            "failed to read backups directory {}: {}",
+            directory.display(),
+            err
+        )
+    })?;
+    let mut backups = Vec::new();
+    for entry_result in entries {
+        let entry = entry_result.map_err(|err| format!("failed to read backup entry: {}", err))?;
+        let path = entry.path();
+        let is_backup = path.is_file()
+            && path
+                .file_name()
+                .and_then(OsStr::to_str)
+                .map(|name| {
+                    name.starts_with(BACKUP_FILE_PREFIX)
+                        && name.ends_with(&format!(".{}", BACKUP_FILE_EXT))
+                })
+                .unwrap_or(false);
+        if is_backup {
+            backups.push(path);
+        }
+    }
+    backups.sort();
+    Ok(backups)
+}
+
+pub(crate) fn prune_old_backups(
+    directory: &Path,
+    keep_count: usize,
+) -> Result<Vec<String>, String> {
+    let backups = list_backup_files(directory)?;
+    let prune_count = backups.len().saturating_sub(keep_count.max(1));
+    let mut pruned = Vec::new();
+    for path in backups.into_iter().take(prune_count) {
+        match fs::remove_file(&path) {
+            Ok(_) => pruned.push(path_to_string(&path)?),
+            Err(err) => log_eprintln!("failed to prune backup {}: {}", path.display(), err),
+        }
+    }
+    Ok(pruned)
+}
+
+pub(crate) fn backup_database_internal(
+    target_dir: Option<PathBuf>,
+    keep_count: usize,
+) -> Result<BackupDatabaseResult, String> {
+    let started_at = Instant::now();
+    let uses_default_dir = target_dir.is_none();
+    let directory = match target_dir {
+        Some(directory) => {
+            ensure_path_outside_storage(&directory)?;
+            fs::create_dir_all(&directory).map_err(|err| {
+                format!(
+                    "failed to create backup directory {}: {}",
+                    directory.display(),
+                    err
+                )
+            })?;
+            directory
+        }
+        None => ensure_backups_root_internal()?,
+    };
+
+    let now = Utc::now();
+    let mut backup_path = directory.join(format!(
+        "{}{}.{}",
+        BACKUP_FILE_PREFIX,
+        now.format("%Y%m%d-%H%M%S"),
+        BACKUP_FILE_EXT
+    ));
+    let mut suffix = 2;
+    while backup_path.exists() {
+        backup_path = directory.join(format!(
+            "{}{}-{}.{}",
+            BACKUP_FILE_PREFIX,
+            now.format("%Y%m%d-%H%M%S"),
+            suffix,
+            BACKUP_FILE_EXT
+        ));
+        suffix += 1;
+    }
+
+    initialize_db()?;
+    let source = open_db_connection()?;
+    let mut destination = Connection::open(&backup_path).map_err(|err| {
+        format!(
+            "failed to create backup file {}: {}",
+            backup_path.display(),
+            err
+        )
+    })?;
+    {
+        let backup = rusqlite::backup::Backup::new(&source, &mut destination)
+            .map_err(|err| format!("failed to start sqlite backup: {}", err))?;
+        backup
+            .run_to_completion(256, Duration::from_millis(25), None)
+            .map_err(|err| format!("failed to run sqlite backup: {}", err))?;
+    }
+    drop(destination);
+
+    if let Err(err) = verify_backup_file(&backup_path) {
+        let _ = fs::remove_file(&backup_path);
+        return Err(format!("backup verification failed: {}", err));
+    }
+
+    let size_bytes = fs::metadata(&backup_path)
+        .map_err(|err| {
+            format!(
+                "failed to read backup metadata {}: {}",
+                backup_path.display(),
+                err
+            )
+        })?
+        .len();
+    let pruned_paths = if uses_default_dir {
+        prune_old_backups(&directory, keep_count)?
+    } else {
+        Vec::new()
+    };
+
+    Ok(BackupDatabaseResult {
+        backup_path: path_to_string(&backup_path)?,
+        size_bytes,
+        created_at: now.timestamp_millis(),
+        duration_ms: started_at.elapsed().as_millis() as u64,
+        pruned_paths,
+    })
+}
+
+pub(crate) fn read_app_setting(
+    connection: &Connection,
+    key: &str,
+) -> Result<Option<String>, String> {
+    connection
+        .query_row(
+            "SELECT value FROM app_settings WHERE key = ?1",
+            params![key],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to read app setting {}: {}", key, err))
+}
+
+pub(crate) fn write_app_setting(
+    connection: &Connection,
+    key: &str,
+    value: &str,
+) -> Result<(), String> {
+    connection
+        .execute(
+            "INSERT INTO app_settings (key, value, updated_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(key) DO UPDATE SET
+               value = excluded.value,
+               updated_at = excluded.updated_at",
+            params![key, value, Utc::now().timestamp_millis()],
+        )
+        .map_err(|err| format!("failed to write app setting {}: {}", key, err))?;
+    Ok(())
+}
+
+pub(crate) fn hide_sensitive_enabled(connection: &Connection) -> Result<bool, String> {
+    Ok(read_app_setting(connection, SETTING_HIDE_SENSITIVE)?
+        .map(|value| matches!(value.trim(), "1" | "true"))
+        .unwrap_or(false))
+}
+
+pub(crate) fn auto_verify_on_open_enabled(connection: &Connection) -> Result<bool, String> {
+    Ok(read_app_setting(connection, SETTING_AUTO_VERIFY_ON_OPEN)?
+        .map(|value| matches!(value.trim(), "1" | "true"))
+        .unwrap_or(false))
+}
+
+pub(crate) fn resolve_show_sensitive(
+    connection: &Connection,
+    include_sensitive: Option<bool>,
+) -> Result<bool, String> {
+    if include_sensitive == Some(true) {
+        return Ok(true);
+    }
+    Ok(!hide_sensitive_enabled(connection)?)
+}
+
+pub(crate) fn hidden_sensitive_count(show_sensitive: bool, sensitive_count: i64) -> i64 {
+    if show_sensitive {
+        0
+    } else {
+        sensitive_count
+    }
+}
+
+pub(crate) fn load_backup_settings(connection: &Connection) -> Result<BackupSettings, String> {
+    let interval_hours = read_app_setting(connection, SETTING_BACKUP_INTERVAL_HOURS)?
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .unwrap_or(DEFAULT_BACKUP_INTERVAL_HOURS)
+        .max(0);
+    let keep_count = read_app_setting(connection, SETTING_BACKUP_KEEP_COUNT)?
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_BACKUP_KEEP_COUNT)
+        .max(1);
+    Ok(BackupSettings {
+        interval_hours,
+        keep_count,
+    })
+}
+
+pub(crate) fn latest_backup_file(directory: &Path) -> Result<Option<(PathBuf, i64, u64)>, String> {
+    let mut latest: Option<(PathBuf, i64, u64)> = None;
+    for path in list_backup_files(directory)? {
+        let Ok(metadata) = fs::metadata(&path) else {
+            continue;
+        };
+        let modified_ms = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|duration| duration.as_millis() as i64)
+            .unwrap_or(0);
+        if latest
+            .as_ref()
+            .map(|(_, latest_ms, _)| modified_ms >= *latest_ms)
+            .unwrap_or(true)
+        {
+            latest = Some((path, modified_ms, metadata.len()));
+        }
+    }
+    Ok(latest)
+}
+
+pub(crate) fn ensure_backup_disk_space(directory: &Path) -> Result<(), String> {
+    let available = read_available_disk_space(directory)?;
+    let database_size = fs::metadata(db_path()?)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    let required = BACKUP_MIN_FREE_SPACE_BYTES.saturating_add(database_size);
+    if available < required {
+        return Err(format!(
+            "skipping backup: only {} bytes free in {} (need at least {})",
+            available,
+            directory.display(),
+            required
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn run_scheduled_backup_if_due() -> Result<Option<BackupDatabaseResult>, String> {
+    initialize_db()?;
+    let settings = {
+        let connection = open_db_connection()?;
+        load_backup_settings(&connection)?
+    };
+    if settings.interval_hours <= 0 {
+        return Ok(None);
+    }
+
+    let directory = ensure_backups_root_internal()?;
+    let now_ms = Utc::now().timestamp_millis();
+    let interval_ms = settings.interval_hours * 60 * 60 * 1000;
+    if let Some((_, last_backup_ms, _)) = latest_backup_file(&directory)? {
+        if last_backup_ms + interval_ms > now_ms {
+            return Ok(None);
+        }
+    }
+
+    ensure_backup_disk_space(&directory)?;
+    backup_database_internal(None, settings.keep_count).map(Some)
+}
+
+pub(crate) fn start_backup_scheduler(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let outcome = run_scheduled_backup_if_due();
+        let last_error = match outcome {
+            Ok(Some(result)) => {
+                log_println!(
+                    "[backup] scheduled backup written to {} ({} bytes, {} ms)",
+                    result.backup_path,
+                    result.size_bytes,
+                    result.duration_ms
+                );
+                None
+            }
+            Ok(None) => None,
+            Err(message) => {
+                log_eprintln!("[backup] scheduled backup failed: {}", message);
+                if let Err(err) = app.emit(
+                    BACKUP_WARNING_EVENT,
+                    BackupWarningPayload {
+                        message: message.clone(),
+                    },
+                ) {
+                    log_eprintln!("[backup] failed to emit backup warning: {}", err);
+                }
+                Some(message)
+            }
+        };
+
+        if let Some(state) = app.try_state::<BackupSchedulerState>() {
+            if let Ok(mut current) = state.last_error.lock() {
+                *current = last_error;
+            }
+        }
+        std::thread::sleep(Duration::from_secs(BACKUP_SCHEDULER_POLL_SECS));
+    });
+}
+
+pub(crate) fn build_backup_status(last_error: Option<String>) -> Result<BackupStatus, String> {
+    initialize_db()?;
+    let settings = {
+        let connection = open_db_connection()?;
+        load_backup_settings(&connection)?
+    };
+    let directory = backups_root_path()?;
+    let latest = latest_backup_file(&directory)?;
+    let interval_ms = settings.interval_hours * 60 * 60 * 1000;
+    let next_scheduled_at = if settings.interval_hours <= 0 {
+        None
+    } else {
+        Some(
+            latest
+                .as_ref()
+                .map(|(_, last_backup_ms, _)| last_backup_ms + interval_ms)
+                .unwrap_or_else(|| Utc::now().timestamp_millis()),
+        )
+    };
+
+    let (last_backup_path, last_backup_at, last_backup_size_bytes) = match latest {
+        Some((path, modified_ms, size)) => {
+            (Some(path_to_string(&path)?), Some(modified_ms), Some(size))
+        }
+        None => (None, None, None),
+    };
+
+    Ok(BackupStatus {
+        interval_hours: settings.interval_hours,
+        keep_count: settings.keep_count,
+        backup_dir: path_to_string(&directory)?,
+        last_backup_at,
+        last_backup_path,
+        last_backup_size_bytes,
+        next_scheduled_at,
+        last_error,
+    })
+}
+
+pub(crate) fn startup_health_report(status: &str, message: Option<String>) -> StartupHealthReport {
+    StartupHealthReport {
+        status: status.to_string(),
+        checked_at: Utc::now().timestamp_millis(),
+        message,
+        corrupt_copy_path: None,
+        backup_path: None,
+        backup_created_at: None,
+        items_before: None,
+        items_after: None,
+        missing_items_estimate: None,
+    }
+}
+
+pub(crate) fn quick_check_database_file(database_path: &Path) -> Result<(), String> {
+    let connection = Connection::open(database_path).map_err(|err| {
+        format!(
+            "failed to open sqlite database {}: {}",
+            database_path.display(),
+            err
+        )
+    })?;
+    verify_sqlite_integrity(&connection, "quick_check")
+}
+
+pub(crate) fn move_database_files_aside(
+    database_path: &Path,
+    target_path: &Path,
+) -> Result<(), String> {
+    fs::rename(database_path, target_path).map_err(|err| {
+        format!(
+            "failed to move database {} aside to {}: {}",
+            database_path.display(),
+            target_path.display(),
+            err
+        )
+    })?;
+    for sidecar in ["wal", "shm", "journal"] {
+        let sidecar_path = PathBuf::from(format!("{}-{}", database_path.display(), sidecar));
+        if sidecar_path.exists() {
+            let moved_path = PathBuf::from(format!("{}-{}", target_path.display(), sidecar));
+            if let Err(err) = fs::rename(&sidecar_path, &moved_path) {
+                log_eprintln!(
+                    "[startup-health] failed to move sidecar {}: {}",
+                    sidecar_path.display(),
+                    err
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn table_column_names(
+    connection: &Connection,
+    table: &str,
+) -> Result<Vec<String>, String> {
+    let mut stmt = connection
+        .prepare(&format!("PRAGMA table_info(\"{}\")", table))
+        .map_err(|err| format!("failed to inspect table {}: {}", table, err))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| format!("failed to read table info for {}: {}", table, err))?;
+    let mut columns = Vec::new();
+    for row_result in rows {
+        columns.push(
+            row_result.map_err(|err| format!("failed to read column of {}: {}", table, err))?,
+        );
+    }
+    Ok(columns)
+}
+
+pub(crate) fn salvage_table_rows(
+    source: &Connection,
+    destination: &Connection,
+    table: &str,
+) -> Result<usize, String> {
+    let destination_columns = table_column_names(destination, table)?;
+    let source_columns: BTreeSet<String> = table_column_names(source, table)?.into_iter().collect();
+    let columns: Vec<String> = destination_columns
+        .into_iter()
+        .filter(|column| source_columns.contains(column))
+        .collect();
+    if columns.is_empty() {
+        return Ok(0);
+    }
+
+    let column_list = columns
+        .iter()
+        .map(|column| format!("\"{}\"", column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let placeholders = (1..=columns.len())
+        .map(|index| format!("?{}", index))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut select_stmt = source
+        .prepare(&format!("SELECT {} FROM \"{}\"", column_list, table))
+        .map_err(|err| format!("failed to prepare salvage read for {}: {}", table, err))?;
+    let mut insert_stmt = destination
+        .prepare(&format!(
+            "INSERT OR IGNORE INTO \"{}\" ({}) VALUES ({})",
+            table, column_list, placeholders
+        ))
+        .map_err(|err| format!("failed to prepare salvage write for {}: {}", table, err))?;
+
+    let mut rows = select_stmt
+        .query([])
+        .map_err(|err| format!("failed to scan {} for salvage: {}", table, err))?;
+    let mut salvaged = 0usize;
+    loop {
+        let row = match rows.next() {
+            Ok(Some(row)) => row,
+            Ok(None) => break,
+            Err(err) => {
+                log_eprintln!(
+                    "[startup-health] stopped salvaging {} after {} rows: {}",
+                    table,
+                    salvaged,
+                    err
+                );
+                break;
+            }
+        };
+        let mut values = Vec::with_capacity(columns.len());
+        for index in 0..columns.len() {
+            values.push(
+                row.get::<_, rusqlite::types::Value>(index)
+                    .unwrap_or(rusqlite::types::Value::Null),
+            );
+        }
+        match insert_stmt.execute(rusqlite::params_from_iter(values)) {
+            Ok(inserted) => salvaged += inserted,
+            Err(err) => log_eprintln!("[startup-health] skipped unreadable {} row: {}", table, err),
+        }
+    }
+    Ok(salvaged)
+}
+
+/// Opens a damaged database read-only. `writable_schema` lets SQLite read a
+/// truncated file whose header still claims the original page count.
+pub(crate) fn open_corrupt_database(path: &Path) -> Result<Connection, String> {
+    let connection = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|err| format!("failed to open corrupt database for salvage: {}", err))?;
+    connection
+        .pragma_update(None, "writable_schema", "ON")
+        .map_err(|err| format!("failed to relax schema checks for salvage: {}", err))?;
+    Ok(connection)
+}
+
+pub(crate) fn salvage_database_file(
+    corrupt_path: &Path,
+    database_path: &Path,
+) -> Result<i64, String> {
+    let source = open_corrupt_database(corrupt_path)?;
+    let destination = Connection::open(database_path)
+        .map_err(|err| format!("failed to create salvage database: {}", err))?;
+    run_db_migrations(&destination)?;
+
+    let mut tables_stmt = destination
+        .prepare(
+            "SELECT name FROM sqlite_master
+             WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+             ORDER BY name ASC",
+        )
+        .map_err(|err| format!("failed to list salvage tables: {}", err))?;
+    let table_rows = tables_stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to query salvage tables: {}", err))?;
+    let mut tables = Vec::new();
+    for row_result in table_rows {
+        tables.push(row_result.map_err(|err| format!("failed to read salvage table: {}", err))?);
+    }
+    drop(tables_stmt);
+
+    for table in tables {
+        match salvage_table_rows(&source, &destination, &table) {
+            Ok(count) => log_println!("[startup-health] salvaged {} rows from {}", count, table),
+            Err(err) => log_eprintln!("[startup-health] could not salvage {}: {}", table, err),
+        }
+    }
+
+    verify_sqlite_integrity(&destination, "integrity_check")?;
+    destination
+        .query_row("SELECT COUNT(*) FROM items", [], |row| row.get::<_, i64>(0))
+        .map_err(|err| format!("failed to count salvaged items: {}", err))
+}
+
+pub(crate) fn count_items_in_database_file(path: &Path) -> Option<i64> {
+    let connection = open_corrupt_database(path).ok()?;
+    connection
+        .query_row("SELECT COUNT(*) FROM items", [], |row| row.get::<_, i64>(0))
+        .ok()
+}
+
+pub(crate) fn recover_corrupt_database(
+    database_path: &Path,
+    backups_dir: &Path,
+    check_error: String,
+) -> StartupHealthReport {
+    let corrupt_path = database_path.with_file_name(format!(
+        "stumble.corrupt-{}.db",
+        Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+    if let Err(err) = move_database_files_aside(database_path, &corrupt_path) {
+        return startup_health_report(
+            "failed",
+            Some(format!("{}; additionally {}", check_error, err)),
+        );
+    }
+
+    let items_before = count_items_in_database_file(&corrupt_path);
+    let mut report = startup_health_report("recovered", Some(check_error));
+    report.corrupt_copy_path = corrupt_path.to_str().map(str::to_string);
+    report.items_before = items_before;
+
+    match salvage_database_file(&corrupt_path, database_path) {
+        Ok(items_after) if items_after > 0 || items_before == Some(0) => {
+            report.items_after = Some(items_after);
+            report.missing_items_estimate =
+                items_before.map(|before| (before - items_after).max(0));
+            return report;
+        }
+        Ok(_) => {
+            log_eprintln!("[startup-health] salvage recovered no items, falling back to backup");
+        }
+        Err(err) => {
+            log_eprintln!("[startup-health] salvage failed: {}", err);
+        }
+    }
+    let _ = fs::remove_file(database_path);
+
+    let latest_backup = match latest_backup_file(backups_dir) {
+        Ok(latest) => latest,
+        Err(err) => {
+            log_eprintln!("[startup-health] failed to look up backups: {}", err);
+            None
+        }
+    };
+    let Some((backup_path, backup_created_at, _)) = latest_backup else {
+        report.status = "failed".to_string();
+        report.message = Some(format!(
+            "{}; no rows could be salvaged and no backup is available. The damaged database was kept at {}",
+            report.message.clone().unwrap_or_default(),
+            corrupt_path.display()
+        ));
+        return report;
+    };
+
+    let restored = verify_backup_file(&backup_path).and_then(|_| {
+        fs::copy(&backup_path, database_path)
+            .map(|_| ())
+            .map_err(|err| format!("failed to copy backup into place: {}", err))
+    });
+    if let Err(err) = restored {
+        let _ = fs::remove_file(database_path);
+        report.status = "failed".to_string();
+        report.message = Some(format!(
+            "{}; restoring backup {} failed: {}",
+            report.message.clone().unwrap_or_default(),
+            backup_path.display(),
+            err
+        ));
+        return report;
+    }
+
+    let items_after = count_items_in_database_file(database_path);
+    report.status = "restored_from_backup".to_string();
+    report.backup_path = backup_path.to_str().map(str::to_string);
+    report.backup_created_at = Some(backup_created_at);
+    report.items_after = items_after;
+    report.missing_items_estimate = match (items_before, items_after) {
+        (Some(before), Some(after)) => Some((before - after).max(0)),
+        _ => None,
+    };
+    report
+}
+
+pub(crate) fn run_startup_integrity_check() -> StartupHealthReport {
+    let (database_path, backups_dir) = match (db_path(), backups_root_path()) {
+        (Ok(database_path), Ok(backups_dir)) => (database_path, backups_dir),
+        (Err(err), _) | (_, Err(err)) => return startup_health_report("failed", Some(err)),
+    };
+    if !database_path.exists() {
+        return startup_health_report("ok", None);
+    }
+
+    match quick_check_database_file(&database_path) {
+        Ok(()) => startup_health_report("ok", None),
+        Err(check_error) => {
+            log_eprintln!(
+                "[startup-health] database failed quick_check: {}",
+                check_error
+            );
+            let report = recover_corrupt_database(&database_path, &backups_dir, check_error);
+            log_eprintln!(
+                "[startup-health] recovery finished with status {}: {}",
+                report.status,
+                report.message.clone().unwrap_or_default()
+            );
+            report
+        }
+    }
+}
+
+pub(crate) fn restore_database_internal(
+    backup_path: &Path,
+) -> Result<RestoreDatabaseResult, String> {
+    if !backup_path.is_file() {
+        return Err(format!(
+            "backup file does not exist: {}",
+            backup_path.display()
+        ));
+    }
+    ensure_path_outside_storage(backup_path)?;
+    verify_backup_file(backup_path)?;
+
+    let database_path = db_path()?;
+    let pre_restore_backup_path = if database_path.exists() {
+        match backup_database_internal(None, usize::MAX) {
+            Ok(snapshot) => Some(snapshot.backup_path),
+            Err(err) => {
+                return Err(format!(
+                "refusing to restore because the current database could not be backed up first: {}",
+                err
+            ))
+            }
+        }
+    } else {
+        None
+    };
+
+    let staging_path = database_path.with_extension("db.restore-tmp");
+    fs::copy(backup_path, &staging_path).map_err(|err| {
+        format!(
+            "failed to stage backup {} for restore: {}",
+            backup_path.display(),
+            err
+        )
+    })?;
+    if let Err(err) = verify_backup_file(&staging_path) {
+        let _ = fs::remove_file(&staging_path);
+        return Err(format!("staged backup failed verification: {}", err));
+    }
+
+    for sidecar in ["db-wal", "db-shm", "db-journal"] {
+        let sidecar_path = database_path.with_extension(sidecar);
+        if sidecar_path.exists() {
+            fs::remove_file(&sidecar_path).map_err(|err| {
+                format!(
+                    "failed to remove database sidecar {}: {}",
+                    sidecar_path.display(),
+                    err
+                )
+            })?;
+        }
+    }
+    fs::rename(&staging_path, &database_path).map_err(|err| {
+        format!(
+            "failed to swap restored database into place {}: {}",
+            database_path.display(),
+            err
+        )
+    })?;
+
+    DB_INITIALIZED.store(false, Ordering::Release);
+    initialize_db()?;
+    Ok(RestoreDatabaseResult {
+        restored_from: path_to_string(backup_path)?,
+        pre_restore_backup_path,
+        restored_at: Utc::now().timestamp_millis(),
+    })
+}
+
+pub(crate) fn library_root_reachable(app_root: &Path) -> bool {
+    app_root.is_dir() || app_root.parent().map(Path::is_dir).unwrap_or(false)
+}
+
+pub(crate) fn library_unavailable_error(status: &str, detail: &str) -> String {
+    format!(
+        "{} ({}): {}",
+        LIBRARY_UNAVAILABLE_ERROR_PREFIX, status, detail
+    )
+}
+
+/// Points the process at `app_root` through [`APP_ROOT_ENV_OVERRIDE`] and drops state cached
+/// from a previously opened library. Benches and tests use this to work on a throwaway root.
+#[cfg(any(test, feature = "bench-support"))]
+pub(crate) fn switch_app_root(app_root: &Path) {
+    std::env::set_var(APP_ROOT_ENV_OVERRIDE, app_root);
+    DB_INITIALIZED.store(false, Ordering::Release);
+    invalidate_vault_write_directory();
+    let _ = set_vault_data_key(None);
+    if let Ok(mut unlocked) = UNLOCKED_COLLECTIONS.lock() {
+        unlocked.clear();
+    }
+}
+
+/// Runs `operation` inside one transaction on a fresh connection. Commands are thin
+/// wrappers over `*_in_tx` functions so tests can drive the same code paths.
+pub(crate) fn with_db_transaction<T>(
+    operation: impl FnOnce(&Transaction<'_>) -> Result<T, String>,
+) -> Result<T, String> {
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    let result = operation(&transaction)?;
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit sqlite transaction: {}", err))?;
+    Ok(result)
+}
+
+pub(crate) fn initialize_db() -> Result<(), String> {
+    let app_root = app_root_path()?;
+    if !library_root_reachable(&app_root) {
+        DB_INITIALIZED.store(false, Ordering::Release);
+        return Err(library_unavailable_error(
+            LIBRARY_STATUS_MISSING_ROOT,
+            &format!("library folder {} is not reachable", app_root.display()),
+        ));
+    }
+    if DB_INITIALIZED.load(Ordering::Acquire) {
+        return Ok(());
+    }
+    let connection = open_db_connection()?;
+    run_db_migrations(&connection)?;
+    ensure_default_root_collection(&connection)?;
+    DB_INITIALIZED.store(true, Ordering::Release);
+    Ok(())
+}
+
+impl ProgressReporter {
+    pub(crate) fn start(app: &AppHandle, operation: &str) -> Self {
+        Self::start_with_import_batch(app, operation, None)
+    }
+
+    pub(crate) fn start_with_import_batch(
+        app: &AppHandle,
+        operation: &str,
+        import_batch_id: Option<&str>,
+    ) -> Self {
+        let reporter = Self {
+            app: Some(app.clone()),
+            operation_id: Uuid::new_v4().to_string(),
+            operation: operation.to_string(),
+            started_at: Utc::now().timestamp_millis(),
+            import_batch_id: import_batch_id.map(str::to_string),
+        };
+        reporter.report("started", 0, 0, None);
+        reporter
+    }
+
+    pub(crate) fn silent(operation: &str) -> Self {
+        Self {
+            app: None,
+            operation_id: String::new(),
+            operation: operation.to_string(),
+            started_at: Utc::now().timestamp_millis(),
+            import_batch_id: None,
+        }
+    }
+
+    pub(crate) fn report(&self, phase: &str, done: u64, total: u64, detail: Option<&str>) {
+        let Some(app) = self.app.as_ref() else {
+            return;
+        };
+        let payload = MaintenanceProgressPayload {
+            operation_id: self.operation_id.clone(),
+            operation: self.operation.clone(),
+            phase: phase.to_string(),
+            done,
+            total,
+            detail: detail.map(str::to_string),
+            started_at: self.started_at,
+            import_batch_id: self.import_batch_id.clone(),
+        };
+        if let Some(state) = app.try_state::<MaintenanceProgressState>() {
+            if let Ok(mut running) = state.0.lock() {
+                running.insert(self.operation_id.clone(), payload.clone());
+            }
+        }
+        if let Err(err) = app.emit(MAINTENANCE_PROGRESS_EVENT, payload) {
+            log_eprintln!(
+                "[maintenance] failed to emit progress for {}: {}",
+                self.operation,
+                err
+            );
+        }
+    }
+
+    pub(crate) fn finish(&self, outcome: &Result<(), String>) {
+        let (phase, detail) = match outcome {
+            Ok(()) => ("finished", None),
+            Err(err) => ("failed", Some(err.as_str())),
+        };
+        self.report(phase, 0, 0, detail);
+    }
+}
+
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        let Some(app) = self.app.as_ref() else {
+            return;
+        };
+        if let Some(state) = app.try_state::<MaintenanceProgressState>() {
+            if let Ok(mut running) = state.0.lock() {
+                running.remove(&self.operation_id);
+            }
+        }
+    }
+}
+
+pub(crate) fn database_files_size(database_path: &Path) -> u64 {
+    let wal_path = PathBuf::from(format!("{}-wal", database_path.display()));
+    [database_path.to_path_buf(), wal_path]
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+pub(crate) fn read_pragma_i64(connection: &Connection, pragma: &str) -> Result<i64, String> {
+    connection
+        .query_row(&format!("PRAGMA {}", pragma), [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .map_err(|err| format!("failed to read PRAGMA {}: {}", pragma, err))
+}
+
+pub(crate) fn run_db_maintenance_internal() -> Result<DbMaintenanceResult, String> {
+    let _activity = MaintenanceActivityGuard::begin()?;
+    initialize_db()?;
+    let started_at = Instant::now();
+    let database_path = db_path()?;
+    let size_before_bytes = database_files_size(&database_path);
+
+    let connection = open_db_connection()?;
+    connection
+        .busy_timeout(Duration::from_millis(MAINTENANCE_BUSY_TIMEOUT_MS))
+        .map_err(|err| format!("failed to set maintenance busy timeout: {}", err))?;
+
+    let page_size = read_pragma_i64(&connection, "page_size")?;
+    let freelist_pages_before = read_pragma_i64(&connection, "freelist_count")?;
+    let auto_vacuum = read_pragma_i64(&connection, "auto_vacuum")?;
+
+    connection
+        .execute_batch("PRAGMA optimize; ANALYZE;")
+        .map_err(|err| format!("failed to analyze database: {}", err))?;
+
+    let vacuum_mode = if freelist_pages_before * page_size >= MAINTENANCE_VACUUM_MIN_FREE_BYTES {
+        connection
+            .execute_batch("VACUUM;")
+            .map_err(|err| format!("failed to vacuum database: {}", err))?;
+        "full"
+    } else if auto_vacuum == 2 && freelist_pages_before > 0 {
+        connection
+            .execute_batch("PRAGMA incremental_vacuum;")
+            .map_err(|err| format!("failed to run incremental vacuum: {}", err))?;
+        "incremental"
+    } else {
+        "skipped"
+    };
+
+    connection
+        .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+        .map_err(|err| format!("failed to checkpoint database wal: {}", err))?;
+    drop(connection);
+
+    let result = DbMaintenanceResult {
+        size_before_bytes,
+        size_after_bytes: database_files_size(&database_path),
+        freelist_pages_before,
+        vacuum_mode: vacuum_mode.to_string(),
+        duration_ms: started_at.elapsed().as_millis() as u64,
+        completed_at: Utc::now().timestamp_millis(),
+    };
+    log_println!(
+        "[db-maintenance] vacuum={} size {} -> {} bytes in {}ms",
+        result.vacuum_mode,
+        result.size_before_bytes,
+        result.size_after_bytes,
+        result.duration_ms
+    );
+    Ok(result)
+}
+
+#[tauri::command]
+pub(crate) async fn run_db_maintenance() -> Result<DbMaintenanceResult, String> {
+    tauri::async_runtime::spawn_blocking(run_db_maintenance_internal)
+        .await
+        .map_err(|err| format!("database maintenance thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) async fn backup_database(
+    target_path: Option<String>,
+    keep_count: Option<usize>,
+) -> Result<BackupDatabaseResult, String> {
+    let target_dir =
+        normalize_optional_trimmed_string(target_path).map(|path| path_from_ipc_string(&path));
+    tauri::async_runtime::spawn_blocking(move || {
+        let bounded_keep_count = match keep_count {
+            Some(value) => value.max(1),
+            None => {
+                initialize_db()?;
+                let connection = open_db_connection()?;
+                load_backup_settings(&connection)?.keep_count
+            }
+        };
+        backup_database_internal(target_dir, bounded_keep_count)
+    })
+    .await
+    .map_err(|err| format!("backup database thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn get_backup_status(
+    state: State<'_, BackupSchedulerState>,
+) -> Result<BackupStatus, String> {
+    let last_error = state
+        .last_error
+        .lock()
+        .map_err(|_| "backup scheduler state lock poisoned".to_string())?
+        .clone();
+    build_backup_status(last_error)
+}
+
+#[tauri::command]
+pub(crate) fn update_backup_settings(
+    interval_hours: Option<i64>,
+    keep_count: Option<usize>,
+    state: State<'_, BackupSchedulerState>,
+) -> Result<BackupStatus, String> {
+    initialize_db()?;
+    {
+        let connection = open_db_connection()?;
+        if let Some(value) = interval_hours {
+            if value < 0 {
+                return Err("backup interval cannot be negative".to_string());
+            }
+            write_app_setting(
+                &connection,
+                SETTING_BACKUP_INTERVAL_HOURS,
+                &value.to_string(),
+            )?;
+        }
+        if let Some(value) = keep_count {
+            if value == 0 {
+                return Err("backup keep count must be at least 1".to_string());
+            }
+            write_app_setting(&connection, SETTING_BACKUP_KEEP_COUNT, &value.to_string())?;
+        }
+    }
+    get_backup_status(state)
+}
+
+#[tauri::command]
+pub(crate) fn get_running_maintenance(
+    state: State<'_, MaintenanceProgressState>,
+) -> Result<Vec<MaintenanceProgressPayload>, String> {
+    let running = state
+        .0
+        .lock()
+        .map_err(|_| "maintenance progress state lock poisoned".to_string())?;
+    Ok(running.values().cloned().collect())
+}
+
+pub(crate) fn probe_directory_writable(directory: &Path) -> Result<(), String> {
+    let probe_path = directory.join(LIBRARY_WRITE_PROBE_FILENAME);
+    fs::write(&probe_path, b"probe")
+        .map_err(|err| format!("cannot write to {}: {}", directory.display(), err))?;
+    fs::remove_file(&probe_path).map_err(|err| {
+        format!(
+            "cannot remove write probe in {}: {}",
+            directory.display(),
+            err
+        )
+    })
+}
+
+pub(crate) fn probe_database_unlocked(database_path: &Path) -> Result<(), String> {
+    let connection = Connection::open(database_path)
+        .map_err(|err| format!("cannot open database {}: {}", database_path.display(), err))?;
+    connection
+        .busy_timeout(Duration::from_millis(LIBRARY_DB_LOCK_PROBE_TIMEOUT_MS))
+        .map_err(|err| format!("failed to set database busy timeout: {}", err))?;
+    connection
+        .execute_batch("BEGIN IMMEDIATE; ROLLBACK;")
+        .map_err(|err| format!("database {} is locked: {}", database_path.display(), err))
+}
+
+pub(crate) fn check_library_available_internal() -> LibraryAvailability {
+    let checked_at = Utc::now().timestamp_millis();
+    let availability = |status: &str, message: Option<String>| LibraryAvailability {
+        status: status.to_string(),
+        app_root: app_root_path()
+            .ok()
+            .map(|path| path.to_string_lossy().to_string()),
+        storage_root: storage_root_path()
+            .ok()
+            .map(|path| path.to_string_lossy().to_string()),
+        message,
+        checked_at,
+    };
+    let app_root = match app_root_path() {
+        Ok(app_root) => app_root,
+        Err(err) => return availability(LIBRARY_STATUS_MISSING_ROOT, Some(err)),
+    };
+    if !library_root_reachable(&app_root) {
+        return availability(
+            LIBRARY_STATUS_MISSING_ROOT,
+            Some(format!(
+                "library folder {} is not reachable",
+                app_root.display()
+            )),
+        );
+    }
+    if !app_root.is_dir() {
+        return availability(LIBRARY_STATUS_OK, None);
+    }
+    if let Err(err) = probe_directory_writable(&app_root) {
+        return availability(LIBRARY_STATUS_READ_ONLY, Some(err));
+    }
+    if let Some(storage_root) = storage_root_path().ok().filter(|path| path.is_dir()) {
+        if let Err(err) = probe_directory_writable(&storage_root) {
+            return availability(LIBRARY_STATUS_READ_ONLY, Some(err));
+        }
+    }
+    if let Some(database_path) = db_path().ok().filter(|path| path.is_file()) {
+        if let Err(err) = probe_database_unlocked(&database_path) {
+            return availability(LIBRARY_STATUS_DB_LOCKED, Some(err));
+        }
+    }
+    availability(LIBRARY_STATUS_OK, None)
+}
+
+#[tauri::command]
+pub(crate) async fn check_library_available() -> Result<LibraryAvailability, String> {
+    tauri::async_runtime::spawn_blocking(check_library_available_internal)
+        .await
+        .map_err(|err| format!("library availability check thread join failed: {}", err))
+}
+
+#[tauri::command]
+pub(crate) async fn reconnect_library(app: AppHandle) -> Result<LibraryAvailability, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        DB_INITIALIZED.store(false, Ordering::Release);
+        invalidate_vault_write_directory();
+        let availability = check_library_available_internal();
+        if availability.status != LIBRARY_STATUS_OK {
+            return Ok(availability);
+        }
+        initialize_db()?;
+        ensure_storage_root_internal()?;
+        log_println!(
+            "[library] reconnected at {}",
+            availability.app_root.as_deref().unwrap_or("")
+        );
+        if let Err(err) = app.emit(LIBRARY_READY_EVENT, availability.clone()) {
+            log_eprintln!("[library] failed to emit ready event: {}", err);
+        }
+        Ok(availability)
+    })
+    .await
+    .map_err(|err| format!("reconnect library thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn get_startup_health(
+    state: State<'_, StartupHealthState>,
+) -> Result<StartupHealthReport, String> {
+    let report = state
+        .0
+        .lock()
+        .map_err(|_| "startup health state lock poisoned".to_string())?
+        .clone();
+    Ok(report.unwrap_or_else(|| startup_health_report("ok", None)))
+}
+
+#[tauri::command]
+pub(crate) async fn restore_database(backup_path: String) -> Result<RestoreDatabaseResult, String> {
+    let path = normalize_optional_trimmed_string(Some(backup_path))
+        .map(|path| path_from_ipc_string(&path))
+        .ok_or_else(|| "backup path cannot be empty".to_string())?;
+    tauri::async_runtime::spawn_blocking(move || restore_database_internal(&path))
+        .await
+        .map_err(|err| format!("restore database thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn init_db() -> Result<String, String> {
+    initialize_db()?;
+    let path = db_path()?;
+    path_to_string(&path)
+}
+
+#[tauri::command]
+pub(crate) fn load_app_state(
+    include_archived: Option<bool>,
+    include_sensitive: Option<bool>,
+) -> Result<DbAppState, String> {
+    let include_archived = include_archived.unwrap_or(false);
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let show_sensitive = resolve_show_sensitive(&connection, include_sensitive)?;
+    let locked_collection_ids = prepare_locked_collection_scope(&connection)?;
+
+    let collections = query_db_collection_rows(&connection, include_archived)?;
+
+    let mut collection_items_stmt = connection
+        .prepare(
+            "SELECT
+                id,
+                collection_id,
+                item_id,
+                custom_title,
+                custom_description,
+                sort_index,
+                is_pinned,
+                created_at
+             FROM collection_items
+             WHERE collection_id NOT IN (SELECT id FROM temp.locked_collection_scope)
+             ORDER BY collection_id ASC, is_pinned DESC, sort_index ASC, created_at ASC, id ASC",
+        )
+        .map_err(|err| format!("failed to prepare collection_items query: {}", err))?;
+
+    let collection_items_iter = collection_items_stmt
+        .query_map([], db_collection_item_row_from_row)
+        .map_err(|err| format!("failed to query collection_items: {}", err))?;
+
+    let mut collection_items = Vec::new();
+    for row_result in collection_items_iter {
+        collection_items.push(
+            row_result.map_err(|err| format!("failed to read collection_items row: {}", err))?,
+        );
+    }
+
+    let mut tags_stmt = connection
+        .prepare(
+            "SELECT
+                id,
+                name,
+                color,
+                sort_index,
+                created_at,
+                updated_at,
+                is_system
+             FROM tags
+             WHERE is_system = 0
+             ORDER BY sort_index ASC, created_at ASC, LOWER(name) ASC, name ASC",
+        )
+        .map_err(|err| format!("failed to prepare tags query: {}", err))?;
+
+    let tags_iter = tags_stmt
+        .query_map([], db_tag_row_from_row)
+        .map_err(|err| format!("failed to query tags: {}", err))?;
+
+    let mut tags = Vec::new();
+    for row_result in tags_iter {
+        tags.push(row_result.map_err(|err| format!("failed to read tag row: {}", err))?);
+    }
+
+    let items = query_db_item_rows(
+        &connection,
+        &format!(
+            "{} WHERE (?1 OR i.is_archived = 0) AND (?2 OR i.is_sensitive = 0) AND {}
+             GROUP BY i.id
+             ORDER BY i.created_at DESC",
+            DB_ITEM_ROW_SELECT, LOCKED_SCOPE_VISIBLE_CONDITION
+        ),
+        params![include_archived, show_sensitive],
+    )?;
+    let sensitive_count: i64 = connection
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) FROM items AS i
+                 WHERE i.is_sensitive = 1 AND (?1 OR i.is_archived = 0) AND {}",
+                LOCKED_SCOPE_VISIBLE_CONDITION
+            ),
+            params![include_archived],
+            |row| row.get(0),
+        )
+        .map_err(|err| format!("failed to count sensitive items: {}", err))?;
+
+    Ok(DbAppState {
+        collections,
+        collection_items,
+        tags,
+        items,
+        hidden_count: hidden_sensitive_count(show_sensitive, sensitive_count),
+        locked_collection_ids,
+    })
+}
+
+pub(crate) fn query_stuck_item_ids(
+    connection: &Connection,
+    condition: &str,
+    stale_before: i64,
+    limit: i64,
+) -> Result<Vec<String>, String> {
+    let mut stmt = connection
+        .prepare(&format!(
+            "SELECT id FROM items
+             WHERE {} AND updated_at < ?1
+             ORDER BY updated_at ASC, id ASC
+             LIMIT ?2",
+            condition
+        ))
+        .map_err(|err| format!("failed to prepare stuck items query: {}", err))?;
+    let rows = stmt
+        .query_map(params![stale_before, limit], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to query stuck items: {}", err))?;
+    let mut item_ids = Vec::new();
+    for row_result in rows {
+        item_ids.push(row_result.map_err(|err| format!("failed to read stuck item row: {}", err))?);
+    }
+    Ok(item_ids)
+}
+
+#[tauri::command]
+pub(crate) fn get_processing_health() -> Result<ProcessingHealth, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let stale_before = Utc::now().timestamp_millis() - STUCK_STATE_STALE_MS;
+
+    Ok(ProcessingHealth {
+        thumb_status: query_library_count_entries(
+            &connection,
+            "SELECT thumb_status, COUNT(*) FROM items WHERE type = 'image' GROUP BY thumb_status ORDER BY thumb_status ASC",
+            "thumbnails by status",
+        )?,
+        import_status: query_library_count_entries(
+            &connection,
+            "SELECT import_status, COUNT(*) FROM items GROUP BY import_status ORDER BY import_status ASC",
+            "items by import status",
+        )?,
+        meta_status: query_library_count_entries(
+            &connection,
+            "SELECT meta_status, COUNT(*) FROM items WHERE type = 'bookmark' GROUP BY meta_status ORDER BY meta_status ASC",
+            "bookmarks by meta status",
+        )?,
+        stale_before,
+        stuck_import_item_ids: query_stuck_item_ids(
+            &connection,
+            STUCK_IMPORT_CONDITION,
+            stale_before,
+            PROCESSING_HEALTH_ID_LIMIT,
+        )?,
+        stuck_thumb_item_ids: query_stuck_item_ids(
+            &connection,
+            STUCK_THUMB_CONDITION,
+            stale_before,
+            PROCESSING_HEALTH_ID_LIMIT,
+        )?,
+        stuck_meta_item_ids: query_stuck_item_ids(
+            &connection,
+            STUCK_META_CONDITION,
+            stale_before,
+            PROCESSING_HEALTH_ID_LIMIT,
+        )?,
+        bookmark_refresh: bookmark_refresh_status(&connection)?,
+    })
+}
+
+pub(crate) fn repair_stuck_states_internal(
+    requeue: bool,
+) -> Result<StuckStateRepairResult, String> {
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let now = Utc::now().timestamp_millis();
+    let stale_before = now - STUCK_STATE_STALE_MS;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    let failed_import_item_ids =
+        query_stuck_item_ids(&transaction, STUCK_IMPORT_CONDITION, stale_before, i64::MAX)?;
+    let (requeued_thumb_item_ids, requeued_meta_item_ids) = if requeue {
+        (
+            query_stuck_item_ids(&transaction, STUCK_THUMB_CONDITION, stale_before, i64::MAX)?,
+            query_stuck_item_ids(&transaction, STUCK_META_CONDITION, stale_before, i64::MAX)?,
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    for item_id in &failed_import_item_ids {
+        transaction
+            .execute(
+                "UPDATE items SET import_status = 'error', updated_at = ?1 WHERE id = ?2",
+                params![now, item_id],
+            )
+            .map_err(|err| format!("failed to mark stuck import as failed: {}", err))?;
+    }
+    for item_id in requeued_thumb_item_ids
+        .iter()
+        .chain(&requeued_meta_item_ids)
+    {
+        transaction
+            .execute(
+                "UPDATE items SET updated_at = ?1 WHERE id = ?2",
+                params![now, item_id],
+            )
+            .map_err(|err| format!("failed to requeue stuck item: {}", err))?;
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit stuck state repair: {}", err))?;
+
+    Ok(StuckStateRepairResult {
+        stale_before,
+        failed_import_item_ids,
+        requeued_thumb_item_ids,
+        requeued_meta_item_ids,
+    })
+}
+
+pub(crate) fn emit_stuck_state_repair(app: &AppHandle, result: &StuckStateRepairResult) {
+    log_println!(
+        "[processing] stuck states repaired failed_imports={} requeued_thumbs={} requeued_meta={}",
+        result.failed_import_item_ids.len(),
+        result.requeued_thumb_item_ids.len(),
+        result.requeued_meta_item_ids.len()
+    );
+    if let Err(err) = app.emit(STUCK_STATES_REPAIRED_EVENT, result.clone()) {
+        log_eprintln!("[processing] failed to emit stuck state repair: {}", err);
+    }
+}
+
+#[tauri::command]
+pub(crate) fn repair_stuck_states(app: AppHandle) -> Result<StuckStateRepairResult, String> {
+    let result = repair_stuck_states_internal(true)?;
+    emit_stuck_state_repair(&app, &result);
+    Ok(result)
+}
+
+pub(crate) fn run_startup_stuck_state_repair(app: &AppHandle) {
+    match repair_stuck_states_internal(false) {
+        Ok(result) if !result.failed_import_item_ids.is_empty() => {
+            if let Some(state) = app.try_state::<StartupStuckStateRepair>() {
+                if let Ok(mut pending) = state.0.lock() {
+                    *pending = Some(result.clone());
+                }
+            }
+            emit_stuck_state_repair(app, &result);
+        }
+        Ok(_) => {}
+        Err(err) => log_eprintln!("[processing] startup stuck state repair failed: {}", err),
+    }
+}
+
+#[tauri::command]
+pub(crate) fn take_startup_stuck_state_repair(
+    state: State<'_, StartupStuckStateRepair>,
+) -> Result<Option<StuckStateRepairResult>, String> {
+    let mut pending = state
+        .0
+        .lock()
+        .map_err(|_| "startup stuck state repair lock poisoned".to_string())?;
+    Ok(pending.take())
+}
+
+pub(crate) fn query_library_count_entries(
+    connection: &Connection,
+    sql: &str,
+    context: &str,
+) -> Result<Vec<LibraryCountEntry>, String> {
+    let mut stmt = connection
+        .prepare(sql)
+        .map_err(|err| format!("failed to prepare {} query: {}", context, err))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(LibraryCountEntry {
+                key: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })
+        .map_err(|err| format!("failed to query {}: {}", context, err))?;
+
+    let mut entries = Vec::new();
+    for row_result in rows {
+        entries.push(row_result.map_err(|err| format!("failed to read {} row: {}", context, err))?);
+    }
+    Ok(entries)
+}
+
+pub(crate) fn library_stats_month_keys(month_count: u32) -> (Vec<String>, i64) {
+    let now = Utc::now();
+    let current_index = now.year() * 12 + now.month0() as i32;
+    let first_index = current_index - month_count.saturating_sub(1) as i32;
+
+    let keys = (first_index..=current_index)
+        .map(|index| {
+            format!(
+                "{:04}-{:02}",
+                index.div_euclid(12),
+                index.rem_euclid(12) + 1
+            )
+        })
+        .collect();
+    let cutoff_ms = chrono::NaiveDate::from_ymd_opt(
+        first_index.div_euclid(12),
+        (first_index.rem_euclid(12) + 1) as u32,
+        1,
+    )
+    .and_then(|date| date.and_hms_opt(0, 0, 0))
+    .map(|datetime| datetime.and_utc().timestamp_millis())
+    .unwrap_or(0);
+    (keys, cutoff_ms)
+}
+
+#[tauri::command]
+pub(crate) fn get_library_stats() -> Result<LibraryStats, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    backfill_vault_file_sizes(&connection)?;
+
+    let (total_items, favorites_count) = connection
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(CASE WHEN is_favorite <> 0 THEN 1 ELSE 0 END), 0)
+             FROM items",
+            [],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .map_err(|err| format!("failed to count items: {}", err))?;
+    let (total_collections, total_tags) = connection
+        .query_row(
+            "SELECT (SELECT COUNT(*) FROM collections), (SELECT COUNT(*) FROM tags)",
+            [],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .map_err(|err| format!("failed to count collections and tags: {}", err))?;
+    let (archived_items, archived_collections) = connection
+        .query_row(
+            "SELECT
+                (SELECT COUNT(*) FROM items WHERE is_archived <> 0),
+                (SELECT COUNT(*) FROM collections WHERE is_archived <> 0)",
+            [],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .map_err(|err| format!("failed to count archived items and collections: {}", err))?;
+    let (vault_file_count, vault_size_bytes) = connection
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(size_bytes), 0) FROM vault_files WHERE ref_count > 0",
+            [],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .map_err(|err| format!("failed to sum vault sizes: {}", err))?;
+
+    let items_by_type = query_library_count_entries(
+        &connection,
+        "SELECT type, COUNT(*) FROM items GROUP BY type ORDER BY COUNT(*) DESC, type ASC",
+        "items by type",
+    )?;
+    let bookmarks_by_meta_status = query_library_count_entries(
+        &connection,
+        "SELECT meta_status, COUNT(*)
+         FROM items
+         WHERE type = 'bookmark'
+         GROUP BY meta_status
+         ORDER BY meta_status ASC",
+        "bookmarks by meta status",
+    )?;
+    let thumbnails_by_status = query_library_count_entries(
+        &connection,
+        "SELECT thumb_status, COUNT(*)
+         FROM items
+         WHERE type = 'image'
+         GROUP BY thumb_status
+         ORDER BY thumb_status ASC",
+        "thumbnails by status",
+    )?;
+
+    let rating_counts: HashMap<String, i64> = query_library_count_entries(
+        &connection,
+        "SELECT CAST(rating AS TEXT), COUNT(*) FROM items GROUP BY rating",
+        "items by rating",
+    )?
+    .into_iter()
+    .map(|entry| (entry.key, entry.count))
+    .collect();
+    let items_by_rating = (0..=5)
+        .map(|rating| {
+            let key = rating.to_string();
+            let count = rating_counts.get(&key).copied().unwrap_or(0);
+            LibraryCountEntry { key, count }
+        })
+        .collect();
+
+    let (month_keys, month_cutoff_ms) = library_stats_month_keys(24);
+    let mut month_stmt = connection
+        .prepare(
+            "SELECT strftime('%Y-%m', created_at / 1000, 'unixepoch'), COUNT(*)
+             FROM items
+             WHERE created_at >= ?1
+             GROUP BY 1",
+        )
+        .map_err(|err| format!("failed to prepare items per month query: {}", err))?;
+    let month_rows = month_stmt
+        .query_map(params![month_cutoff_ms], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(|err| format!("failed to query items per month: {}", err))?;
+    let mut month_counts = HashMap::new();
+    for row_result in month_rows {
+        let (month, count) =
+            row_result.map_err(|err| format!("failed to read items per month row: {}", err))?;
+        month_counts.insert(month, count);
+    }
+    let items_per_month = month_keys
+        .into_iter()
+        .map(|month| {
+            let count = month_counts.get(&month).copied().unwrap_or(0);
+            LibraryMonthEntry { month, count }
+        })
+        .collect();
+
+    let mut largest_stmt = connection
+        .prepare(
+            "SELECT i.id, i.type, i.title, i.filename, v.size_bytes
+             FROM items AS i
+             JOIN vault_files AS v ON v.vault_key = i.vault_key
+             WHERE i.vault_key <> ''
+             ORDER BY v.size_bytes DESC, i.created_at DESC
+             LIMIT 10",
+        )
+        .map_err(|err| format!("failed to prepare largest items query: {}", err))?;
+    let largest_rows = largest_stmt
+        .query_map([], |row| {
+            Ok(LibraryLargestItem {
+                id: row.get(0)?,
+                item_type: row.get(1)?,
+                title: row.get(2)?,
+                filename: row.get(3)?,
+                size_bytes: row.get(4)?,
+            })
+        })
+        .map_err(|err| format!("failed to query largest items: {}", err))?;
+    let mut largest_items = Vec::new();
+    for row_result in largest_rows {
+        largest_items
+            .push(row_result.map_err(|err| format!("failed to read largest item row: {}", err))?);
+    }
+
+    Ok(LibraryStats {
+        total_items,
+        items_by_type,
+        total_collections,
+        total_tags,
+        vault_file_count,
+        vault_size_bytes,
+        items_per_month,
+        items_by_rating,
+        favorites_count,
+        archived_items,
+        archived_collections,
+        bookmarks_by_meta_status,
+        thumbnails_by_status,
+        largest_items,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn get_hide_sensitive() -> Result<bool, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    hide_sensitive_enabled(&connection)
+}
+
+#[tauri::command]
+pub(crate) fn set_hide_sensitive(hidden: bool) -> Result<bool, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    write_app_setting(
+        &connection,
+        SETTING_HIDE_SENSITIVE,
+        if hidden { "1" } else { "0" },
+    )?;
+    Ok(hidden)
+}
+
+#[tauri::command]
+pub(crate) fn get_auto_verify_on_open() -> Result<bool, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    auto_verify_on_open_enabled(&connection)
+}
+
+#[tauri::command]
+pub(crate) fn set_auto_verify_on_open(enabled: bool) -> Result<bool, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    write_app_setting(
+        &connection,
+        SETTING_AUTO_VERIFY_ON_OPEN,
+        if enabled { "1" } else { "0" },
+    )?;
+    Ok(enabled)
+}
+
+/// Replaces the user's home directory (and other `/home/<user>`, `/Users/<user>` or
+/// `<drive>:\Users\<user>` prefixes) with `~` so diagnostics never carry account names.
+pub(crate) fn redact_home_paths(text: &str) -> String {
+    let homes: Vec<String> = ["HOME", "USERPROFILE"]
+        .into_iter()
+        .filter_map(std::env::var_os)
+        .map(|value| value.to_string_lossy().into_owned())
+        .collect();
+    redact_home_paths_with(text, &homes)
+}
+
+/// Replaces `home` with `~` only where it is a whole path prefix, so `/root` leaves
+/// `/rootfs` alone.
+pub(crate) fn replace_home_prefix(text: &str, home: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find(home) {
+        output.push_str(&rest[..index]);
+        let after = &rest[index + home.len()..];
+        if after.is_empty() || after.starts_with(['/', '\\']) {
+            output.push('~');
+        } else {
+            output.push_str(home);
+        }
+        rest = after;
+    }
+    output.push_str(rest);
+    output
+}
+
+pub(crate) fn redact_home_paths_with(text: &str, homes: &[String]) -> String {
+    let mut redacted = text.to_string();
+    for home in homes {
+        let home = home.trim_end_matches(['/', '\\']);
+        if home.len() > 1 {
+            redacted = replace_home_prefix(&redacted, home);
+            redacted = replace_home_prefix(&redacted, &home.replace('\\', "/"));
+        }
+    }
+    for marker in [":\\Users\\", ":/Users/", "/home/", "/Users/"] {
+        let mut output = String::with_capacity(redacted.len());
+        let mut rest = redacted.as_str();
+        while let Some(index) = rest.find(marker) {
+            // Drop the drive letter in front of `:\Users\` as well.
+            let start = if marker.starts_with(':')
+                && rest[..index].ends_with(|c: char| c.is_ascii_alphabetic())
+            {
+                index - 1
+            } else {
+                index
+            };
+            output.push_str(&rest[..start]);
+            let after = &rest[index + marker.len()..];
+            let user_len = after
+                .find(['/', '\\', ' ', '"', '\''])
+                .unwrap_or(after.len());
+            output.push('~');
+            rest = &after[user_len..];
+        }
+        output.push_str(rest);
+        redacted = output;
+    }
+    redacted
+}
+
+pub(crate) fn redact_diagnostics_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(text) => *text = redact_home_paths(text),
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact_diagnostics_json),
+        serde_json::Value::Object(map) => map.values_mut().for_each(redact_diagnostics_json),
+        _ => {}
+    }
+}
+
+pub(crate) fn diagnostics_settings(connection: &Connection) -> Result<serde_json::Value, String> {
+    let mut stmt = connection
+        .prepare("SELECT key, value FROM app_settings ORDER BY key ASC")
+        .map_err(|err| format!("failed to prepare settings query: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|err| format!("failed to query settings: {}", err))?;
+    let mut settings = serde_json::Map::new();
+    for row_result in rows {
+        let (key, value) =
+            row_result.map_err(|err| format!("failed to read setting row: {}", err))?;
+        let value = if DIAGNOSTICS_SECRET_SETTING_PREFIXES
+            .iter()
+            .any(|prefix| key.starts_with(prefix))
+        {
+            DIAGNOSTICS_REDACTED.to_string()
+        } else {
+            redact_home_paths(&value)
+        };
+        settings.insert(key, serde_json::Value::String(value));
+    }
+    Ok(serde_json::Value::Object(settings))
+}
+
+pub(crate) fn diagnostics_schema_and_counts(
+    connection: &Connection,
+) -> Result<(String, serde_json::Value), String> {
+    let mut stmt = connection
+        .prepare(
+            "SELECT type, name, sql
+             FROM sqlite_master
+             WHERE name NOT LIKE 'sqlite_%'
+             ORDER BY type ASC, name ASC",
+        )
+        .map_err(|err| format!("failed to prepare schema query: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })
+        .map_err(|err| format!("failed to query schema: {}", err))?;
+
+    let mut schema = String::new();
+    let mut row_counts = serde_json::Map::new();
+    for row_result in rows {
+        let (object_type, name, sql) =
+            row_result.map_err(|err| format!("failed to read schema row: {}", err))?;
+        if let Some(sql) = sql {
+            schema.push_str(&sql);
+            schema.push_str(";\n\n");
+        }
+        if object_type == "table" {
+            let count = connection
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\"")),
+                    [],
+                    |row| row.get::<_, i64>(0),
+                )
+                .map_err(|err| format!("failed to count rows in {}: {}", name, err))?;
+            row_counts.insert(name, count.into());
+        }
+    }
+    Ok((schema, serde_json::Value::Object(row_counts)))
+}
+
+pub(crate) fn diagnostics_log_text() -> String {
+    recent_log_lines()
+        .iter()
+        .map(|line| redact_home_paths(line) + "\n")
+        .collect()
+}
+
+pub(crate) fn diagnostics_integrity_check(connection: &Connection) -> Result<String, String> {
+    let mut stmt = connection
+        .prepare("PRAGMA integrity_check")
+        .map_err(|err| format!("failed to prepare integrity_check: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to run integrity_check: {}", err))?;
+    let mut output = String::new();
+    for row_result in rows {
+        let line =
+            row_result.map_err(|err| format!("failed to read integrity_check row: {}", err))?;
+        output.push_str(&redact_home_paths(&line));
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Writes a zip for bug reports: app/version info, schema, per-table row counts, settings
+/// (secrets redacted), integrity_check output and processing/startup health. No item
+/// titles, URLs, descriptions or file contents are included, bookmark refresh host names
+/// are reduced to a count, and home-directory paths are replaced with `~`, including in the
+/// last [`DIAGNOSTICS_LOG_MAX_LINES`] log lines written to `logs.txt`.
+#[tauri::command]
+pub(crate) fn export_diagnostics(
+    path: Option<String>,
+    state: State<'_, StartupHealthState>,
+) -> Result<ExportDiagnosticsResult, String> {
+    use std::io::Write;
+
+    let output_path = match path
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+    {
+        Some(path) => {
+            let path = path_from_ipc_string(&path);
+            ensure_path_outside_storage(&path)?;
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                fs::create_dir_all(parent).map_err(|err| {
+                    format!(
+                        "failed to create export directory {}: {}",
+                        parent.display(),
+                        err
+                    )
+                })?;
+            }
+            path
+        }
+        None => ensure_exports_root_internal()?.join(format!(
+            "diagnostics-{}.zip",
+            Utc::now().format("%Y%m%d-%H%M%S")
+        )),
+    };
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let (schema, row_counts) = diagnostics_schema_and_counts(&connection)?;
+    let settings = diagnostics_settings(&connection)?;
+    let integrity = diagnostics_integrity_check(&connection)?;
+
+    let mut processing_health = serde_json::to_value(get_processing_health()?)
+        .map_err(|err| format!("failed to serialize processing health: {}", err))?;
+    if let Some(refresh) = processing_health
+        .get_mut("bookmarkRefresh")
+        .and_then(serde_json::Value::as_object_mut)
+    {
+        let host_count = refresh
+            .remove("backedOffHosts")
+            .and_then(|hosts| hosts.as_array().map(Vec::len))
+            .unwrap_or(0);
+        refresh.insert("backedOffHostCount".to_string(), host_count.into());
+    }
+    redact_diagnostics_json(&mut processing_health);
+    let startup_health = state
+        .0
+        .lock()
+        .map_err(|_| "startup health state lock poisoned".to_string())?
+        .clone()
+        .unwrap_or_else(|| startup_health_report("ok", None));
+    let mut startup_health = serde_json::to_value(startup_health)
+        .map_err(|err| format!("failed to serialize startup health: {}", err))?;
+    redact_diagnostics_json(&mut startup_health);
+
+    let app_info = serde_json::json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "sqliteVersion": rusqlite::version(),
+        "exportedAt": Utc::now().timestamp_millis(),
+    });
+
+    let to_json = |value: &serde_json::Value| {
+        serde_json::to_string_pretty(value)
+            .map_err(|err| format!("failed to serialize diagnostics: {}", err))
+    };
+    let entries = [
+        ("app.json", to_json(&app_info)?),
+        ("schema.sql", schema),
+        ("row_counts.json", to_json(&row_counts)?),
+        ("settings.json", to_json(&settings)?),
+        ("integrity_check.txt", integrity),
+        ("processing_health.json", to_json(&processing_health)?),
+        ("startup_health.json", to_json(&startup_health)?),
+        ("logs.txt", diagnostics_log_text()),
+    ];
+
+    let file = fs::File::create(&output_path).map_err(|err| {
+        format!(
+            "failed to create diagnostics file {}: {}",
+            output_path.display(),
+            err
+        )
+    })?;
+    let mut archive = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for (name, contents) in entries {
+        archive
+            .start_file(name, options)
+            .map_err(|err| format!("failed to add {} to diagnostics: {}", name, err))?;
+        archive
+            .write_all(contents.as_bytes())
+            .map_err(|err| format!("failed to write {} to diagnostics: {}", name, err))?;
+    }
+    archive
+        .finish()
+        .map_err(|err| format!("failed to finish diagnostics archive: {}", err))?;
+
+    let size_bytes = fs::metadata(&output_path)
+        .map_err(|err| {
+            format!(
+                "failed to read diagnostics file {}: {}",
+                output_path.display(),
+                err
+            )
+        })?
+        .len();
+    Ok(ExportDiagnosticsResult {
+        path: path_to_string(&output_path)?,
+        size_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use std::cmp::Ordering;
+
+    fn natural_sorted(titles: &[&str]) -> Vec<String> {
+        let mut sorted: Vec<String> = titles.iter().map(|title| title.to_string()).collect();
+        sorted.sort_by(|left, right| natural_title_cmp(left, right));
+        sorted
+    }
+
+    #[test]
+    fn natural_title_cmp_orders_digit_runs_by_value() {
+        assert_eq!(
+            natural_sorted(&["img10.png", "img2.png", "img1.png", "img100.png"]),
+            vec!["img1.png", "img2.png", "img10.png", "img100.png"]
+        );
+        assert_eq!(
+            natural_sorted(&["v1.10", "v1.9", "v1.2"]),
+            vec!["v1.2", "v1.9", "v1.10"]
+        );
+        assert_eq!(natural_title_cmp("a 2", "a 10"), Ordering::Less);
+        assert_eq!(
+            natural_title_cmp("99999999999999999999999", "1"),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn natural_title_cmp_breaks_leading_zero_ties_deterministically() {
+        assert_eq!(natural_title_cmp("file007", "file7"), Ordering::Greater);
+        assert_eq!(natural_title_cmp("file7", "file007"), Ordering::Less);
+        assert_eq!(natural_title_cmp("file007", "file8"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_title_cmp_ignores_case_before_falling_back_to_it() {
+        assert_eq!(
+            natural_sorted(&["Zebra.png", "apple.png", "Banana.png"]),
+            vec!["apple.png", "Banana.png", "Zebra.png"]
+        );
+        assert_eq!(natural_title_cmp("Apple", "apple"), Ordering::Less);
+        assert_eq!(natural_title_cmp("apple", "Apple"), Ordering::Greater);
+        assert_eq!(natural_title_cmp("apple", "apple"), Ordering::Equal);
+    }
+
+    #[test]
+    fn natural_title_cmp_folds_latin_accents() {
+        assert_eq!(
+            natural_sorted(&["Zoë", "Émile", "eve", "Ångström", "ecole"]),
+            vec!["Ångström", "ecole", "Émile", "eve", "Zoë"]
+        );
+        assert_eq!(fold_title_char('Ç'), 'c');
+        assert_eq!(fold_title_char('ł'), 'l');
+        assert_eq!(fold_title_char('ß'), 'ß');
+        assert_ne!(natural_title_cmp("cafe", "café"), Ordering::Equal);
+    }
+
+    #[test]
+    fn natural_title_cmp_is_antisymmetric() {
+        let titles = [
+            "", "a", "A", "a1", "a01", "a10", "a2b", "É", "e", "10", "9", "img 2", "img2",
+        ];
+        for left in titles {
+            assert_eq!(natural_title_cmp(left, left), Ordering::Equal);
+            for right in titles {
+                assert_eq!(
+                    natural_title_cmp(left, right),
+                    natural_title_cmp(right, left).reverse(),
+                    "{:?} vs {:?}",
+                    left,
+                    right
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn natural_collation_is_usable_from_sql() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .create_collation(NATURAL_COLLATION, natural_title_cmp)
+            .unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE titles (title TEXT NOT NULL);
+                 INSERT INTO titles (title) VALUES ('img10'), ('Img2'), ('img1');",
+            )
+            .unwrap();
+        let mut stmt = connection
+            .prepare(&format!(
+                "SELECT title FROM titles ORDER BY title COLLATE {} ASC",
+                NATURAL_COLLATION
+            ))
+            .unwrap();
+        let titles: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        assert_eq!(titles, vec!["img1", "Img2", "img10"]);
+    }
+
+    /// Writes a clean copy of a library holding `count` padded notes.
+    fn write_database_snapshot(destination: &Path, count: usize) -> i64 {
+        let _library = TestLibrary::new();
+        for index in 0..count {
+            insert_note(&format!("note-{}", index), &format!("Note {}", index), None);
+        }
+        let connection = open_db_connection().unwrap();
+        connection
+            .execute("UPDATE items SET description = printf('%.2000c', 'x')", [])
+            .unwrap();
+        connection
+            .execute("VACUUM INTO ?1", params![destination.to_string_lossy()])
+            .unwrap();
+        count as i64
+    }
+
+    /// Appends filler pages after everything else so truncating them leaves
+    /// the schema and `items` readable.
+    fn append_trailing_pages(path: &Path) -> u64 {
+        let intact_length = fs::metadata(path).unwrap().len();
+        let connection = Connection::open(path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE trailing_padding (data BLOB NOT NULL);
+                 WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 64)
+                 INSERT INTO trailing_padding (data) SELECT zeroblob(3000) FROM n;",
+            )
+            .unwrap();
+        intact_length
+    }
+
+    fn truncate_file(path: &Path, length: u64) {
+        let file = fs::OpenOptions::new().write(true).open(path).unwrap();
+        file.set_len(length).unwrap();
+    }
+
+    #[test]
+    fn recover_corrupt_database_salvages_readable_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let database_path = dir.path().join("stumble.db");
+        let backups_dir = dir.path().join("backups");
+        let expected = write_database_snapshot(&database_path, 40);
+        let intact_length = append_trailing_pages(&database_path);
+        let full_length = fs::metadata(&database_path).unwrap().len();
+        truncate_file(
+            &database_path,
+            intact_length + (full_length - intact_length) / 2,
+        );
+        let check_error = quick_check_database_file(&database_path).unwrap_err();
+
+        let report = recover_corrupt_database(&database_path, &backups_dir, check_error);
+
+        assert_eq!(report.status, "recovered", "{:?}", report.message);
+        assert_eq!(report.items_before, Some(expected));
+        assert_eq!(report.items_after, Some(expected));
+        assert_eq!(report.missing_items_estimate, Some(0));
+        assert!(Path::new(report.corrupt_copy_path.as_deref().unwrap()).is_file());
+        assert!(quick_check_database_file(&database_path).is_ok());
+        assert_eq!(count_items_in_database_file(&database_path), Some(expected));
+    }
+
+    #[test]
+    fn recover_corrupt_database_falls_back_to_the_latest_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let database_path = dir.path().join("stumble.db");
+        let backups_dir = dir.path().join("backups");
+        fs::create_dir_all(&backups_dir).unwrap();
+        let backup_path = backups_dir.join(format!(
+            "{}20260101-000000.{}",
+            BACKUP_FILE_PREFIX, BACKUP_FILE_EXT
+        ));
+        let expected = write_database_snapshot(&backup_path, 12);
+        write_database_snapshot(&database_path, 30);
+        truncate_file(&database_path, 4096);
+        let check_error = quick_check_database_file(&database_path).unwrap_err();
+
+        let report = recover_corrupt_database(&database_path, &backups_dir, check_error);
+
+        assert_eq!(
+            report.status, "restored_from_backup",
+            "{:?}",
+            report.message
+        );
+        assert_eq!(report.backup_path.as_deref(), backup_path.to_str());
+        assert_eq!(report.items_after, Some(expected));
+        assert!(Path::new(report.corrupt_copy_path.as_deref().unwrap()).is_file());
+        assert_eq!(count_items_in_database_file(&database_path), Some(expected));
+    }
+
+    #[test]
+    fn recover_corrupt_database_fails_without_salvage_or_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let database_path = dir.path().join("stumble.db");
+        let backups_dir = dir.path().join("backups");
+        write_database_snapshot(&database_path, 30);
+        truncate_file(&database_path, 4096);
+        let check_error = quick_check_database_file(&database_path).unwrap_err();
+
+        let report = recover_corrupt_database(&database_path, &backups_dir, check_error);
+
+        assert_eq!(report.status, "failed");
+        assert!(report
+            .message
+            .as_deref()
+            .unwrap()
+            .contains("no backup is available"));
+        assert!(!database_path.exists());
+        assert!(Path::new(report.corrupt_copy_path.as_deref().unwrap()).is_file());
+    }
+
+    #[test]
+    fn recover_corrupt_database_fails_when_the_backup_is_damaged() {
+        let dir = tempfile::tempdir().unwrap();
+        let database_path = dir.path().join("stumble.db");
+        let backups_dir = dir.path().join("backups");
+        fs::create_dir_all(&backups_dir).unwrap();
+        let backup_path = backups_dir.join(format!(
+            "{}20260101-000000.{}",
+            BACKUP_FILE_PREFIX, BACKUP_FILE_EXT
+        ));
+        write_database_snapshot(&backup_path, 12);
+        truncate_file(&backup_path, 4096);
+        write_database_snapshot(&database_path, 30);
+        truncate_file(&database_path, 4096);
+        let check_error = quick_check_database_file(&database_path).unwrap_err();
+
+        let report = recover_corrupt_database(&database_path, &backups_dir, check_error);
+
+        assert_eq!(report.status, "failed");
+        assert!(report
+            .message
+            .as_deref()
+            .unwrap()
+            .contains("restoring backup"));
+        assert!(!database_path.exists());
+    }
+
+    #[test]
+    fn home_paths_are_redacted_on_every_platform() {
+        let no_home: Vec<String> = Vec::new();
+        let cases = [
+            ("/home/alice/Pictures/a.png", "~/Pictures/a.png"),
+            ("/Users/bob/Library", "~/Library"),
+            ("C:\\Users\\carol\\Documents", "~\\Documents"),
+            ("d:/Users/dave/x", "~/x"),
+            ("open \"/home/erin\" failed", "open \"~\" failed"),
+            ("/srv/library/a.png", "/srv/library/a.png"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                redact_home_paths_with(input, &no_home),
+                expected,
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn home_is_only_redacted_as_a_whole_path_prefix() {
+        let homes = vec!["/root/".to_string()];
+        let cases = [
+            ("/root", "~"),
+            ("/root/library/db", "~/library/db"),
+            ("/rootfs/x", "/rootfs/x"),
+            ("/root2 and /root/x", "/root2 and ~/x"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(redact_home_paths_with(input, &homes), expected, "{}", input);
+        }
+
+        let windows_home = vec!["C:\\Data\\frank".to_string()];
+        assert_eq!(
+            redact_home_paths_with("C:\\Data\\frank\\a and C:/Data/frank/b", &windows_home),
+            "~\\a and ~/b"
+        );
+        assert_eq!(
+            redact_home_paths_with("C:\\Data\\frankly", &windows_home),
+            "C:\\Data\\frankly"
+        );
+    }
+
+    #[test]
+    fn diagnostics_settings_redact_secrets_and_home_paths() {
+        let library = TestLibrary::new();
+        let connection = library.connection();
+        let lock_key = format!("{}some-collection", SETTING_COLLECTION_LOCK_PREFIX);
+        for (key, value) in [
+            (SETTING_VAULT_ENCRYPTION_SALT, "00ff"),
+            (SETTING_VAULT_ENCRYPTION_WRAPPED_KEY, "abcd"),
+            (lock_key.as_str(), "salt:hash"),
+            ("last_import_directory", "/home/grace/Downloads"),
+        ] {
+            write_app_setting(&connection, key, value).unwrap();
+        }
+
+        let settings = diagnostics_settings(&connection).unwrap();
+        for key in [
+            SETTING_VAULT_ENCRYPTION_SALT,
+            SETTING_VAULT_ENCRYPTION_WRAPPED_KEY,
+            lock_key.as_str(),
+        ] {
+            assert_eq!(settings[key], DIAGNOSTICS_REDACTED, "{}", key);
+        }
+        assert_eq!(settings["last_import_directory"], "~/Downloads");
+    }
+
+    #[test]
+    fn log_buffer_keeps_only_the_newest_lines() {
+        let mut buffer = LogBuffer::new(3);
+        buffer.push("t1", "one");
+        buffer.push("t2", "two\nthree");
+        assert_eq!(buffer.lines, ["t1 one", "t2 two", "t2 three"]);
+        buffer.push("t3", "four");
+        assert_eq!(buffer.lines, ["t2 two", "t2 three", "t3 four"]);
+
+        let mut full = LogBuffer::new(DIAGNOSTICS_LOG_MAX_LINES);
+        for index in 0..DIAGNOSTICS_LOG_MAX_LINES + 5 {
+            full.push("t", &format!("line {}", index));
+        }
+        assert_eq!(full.lines.len(), DIAGNOSTICS_LOG_MAX_LINES);
+        assert_eq!(full.lines.front().map(String::as_str), Some("t line 5"));
+    }
+
+    #[test]
+    fn logged_lines_reach_the_diagnostics_log_redacted() {
+        let marker = format!("diagnostics-log-{}", Uuid::new_v4());
+        log_eprintln!("{} opened /home/heidi/library.db", marker);
+        let logs = diagnostics_log_text();
+        let line = logs
+            .lines()
+            .find(|line| line.contains(&marker))
+            .expect("logged line is kept");
+        assert!(line.ends_with(&format!("{} opened ~/library.db", marker)));
+    }
+}
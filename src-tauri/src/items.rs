@@ -0,0 +1,3991 @@
+use chrono::Utc;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use crate::bookmarks::*;
+use crate::collections::*;
+use crate::db::*;
+use crate::tags::*;
+use crate::thumbs::*;
+use crate::vault::*;
+
+pub(crate) const DEFAULT_ITEM_LINK_TYPE: &str = "related";
+pub(crate) const DEFAULT_THUMB_STATUS: &str = "pending";
+pub(crate) const DEFAULT_IMPORT_STATUS: &str = "ready";
+pub(crate) const DEFAULT_META_STATUS: &str = "ready";
+pub(crate) const ITEM_TYPES: [&str; 6] = ["image", "bookmark", "video", "pdf", "file", "note"];
+pub(crate) const FALLBACK_ITEM_TYPE: &str = "file";
+pub(crate) const RANDOM_ITEMS_MAX_COUNT: u32 = 200;
+pub(crate) const ITEM_PAGE_DEFAULT_LIMIT: u32 = 100;
+pub(crate) const ITEM_PAGE_MAX_LIMIT: u32 = 500;
+pub(crate) const MAX_UTC_OFFSET_MINUTES: i32 = 14 * 60;
+pub(crate) const WEEKLY_DIGEST_SAMPLE_LIMIT: i64 = 12;
+pub(crate) const WEEKLY_DIGEST_TAG_LIMIT: i64 = 10;
+pub(crate) const WEEKLY_DIGEST_MAX_WEEK_OFFSET: i32 = 52 * 20;
+pub(crate) const DESCRIPTION_FORMAT_PLAIN: &str = "plain";
+pub(crate) const DESCRIPTION_FORMAT_MARKDOWN: &str = "markdown";
+pub(crate) const DESCRIPTION_PREVIEW_MAX_CHARS: usize = 200;
+pub(crate) const ITEM_AUDIT_MAX_ENTRIES_PER_ITEM: i64 = 200;
+pub(crate) const ITEM_HISTORY_DEFAULT_LIMIT: usize = 50;
+pub(crate) const DELETE_BY_FILTER_PROGRESS_EVENT: &str = "delete-by-filter://progress";
+pub(crate) const DELETE_BY_FILTER_CHUNK_SIZE: usize = 200;
+pub(crate) const DELETE_BY_FILTER_SAMPLE_SIZE: usize = 10;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DbItemRow {
+    pub(crate) id: String,
+    pub(crate) collection_id: Option<String>,
+    pub(crate) collection_ids: Vec<String>,
+    #[serde(rename = "type")]
+    pub(crate) item_type: String,
+    pub(crate) title: String,
+    pub(crate) filename: String,
+    pub(crate) vault_key: String,
+    pub(crate) vault_path: String,
+    pub(crate) preview_url: Option<String>,
+    pub(crate) width: Option<i64>,
+    pub(crate) height: Option<i64>,
+    pub(crate) thumb_status: String,
+    pub(crate) import_status: String,
+    pub(crate) import_error: Option<String>,
+    pub(crate) url: Option<String>,
+    pub(crate) favicon_path: Option<String>,
+    pub(crate) meta_status: String,
+    pub(crate) description: Option<String>,
+    pub(crate) description_format: String,
+    pub(crate) description_preview: Option<String>,
+    pub(crate) rating: i64,
+    pub(crate) is_favorite: bool,
+    pub(crate) is_archived: bool,
+    pub(crate) is_sensitive: bool,
+    pub(crate) integrity_status: String,
+    pub(crate) note_count: i64,
+    pub(crate) created_at: i64,
+    pub(crate) updated_at: i64,
+    pub(crate) tag_ids: Vec<String>,
+    pub(crate) tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) added_at: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ItemNote {
+    pub(crate) id: String,
+    pub(crate) item_id: String,
+    pub(crate) body: String,
+    pub(crate) created_at: i64,
+    pub(crate) updated_at: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DbItemDetail {
+    pub(crate) item: DbItemRow,
+    pub(crate) collection_items: Vec<DbCollectionItemRow>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LinkedItem {
+    pub(crate) item: DbItemRow,
+    pub(crate) link_type: String,
+    pub(crate) direction: String,
+    pub(crate) created_at: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GetItemsByIdsResult {
+    pub(crate) items: Vec<DbItemDetail>,
+    pub(crate) missing: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ItemPage {
+    pub(crate) items: Vec<DbItemRow>,
+    pub(crate) total: i64,
+    pub(crate) hidden_count: i64,
+    pub(crate) limit: u32,
+    pub(crate) offset: u32,
+    pub(crate) next_cursor: Option<String>,
+}
+
+pub(crate) struct ItemSortKey {
+    pub(crate) expr: &'static str,
+    pub(crate) descending: bool,
+    pub(crate) value: fn(&DbItemRow) -> serde_json::Value,
+}
+
+pub(crate) struct ItemSortSpec {
+    pub(crate) name: &'static str,
+    pub(crate) order_by: &'static str,
+    pub(crate) keys: &'static [ItemSortKey],
+    pub(crate) id_descending: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ItemPageCursor {
+    pub(crate) sort: String,
+    pub(crate) keys: Vec<serde_json::Value>,
+    pub(crate) id: String,
+}
+
+pub(crate) struct ItemKeyset {
+    pub(crate) condition: String,
+    pub(crate) values: Vec<Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ActivityHeatmapEntry {
+    pub(crate) day: String,
+    pub(crate) count: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WeeklyDigestSection {
+    pub(crate) count: i64,
+    pub(crate) item_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WeeklyDigestTag {
+    pub(crate) tag_id: String,
+    pub(crate) name: String,
+    pub(crate) color: String,
+    pub(crate) count: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WeeklyDigest {
+    pub(crate) week_start: String,
+    pub(crate) iso_year: i32,
+    pub(crate) iso_week: u32,
+    pub(crate) from: i64,
+    pub(crate) to: i64,
+    pub(crate) added: WeeklyDigestSection,
+    pub(crate) added_by_type: Vec<LibraryCountEntry>,
+    pub(crate) bookmarks_read: WeeklyDigestSection,
+    pub(crate) rated_high: WeeklyDigestSection,
+    pub(crate) top_tags: Vec<WeeklyDigestTag>,
+    pub(crate) bytes_imported: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InsertItemInput {
+    pub(crate) id: String,
+    pub(crate) collection_id: Option<String>,
+    #[serde(rename = "type")]
+    pub(crate) item_type: String,
+    pub(crate) title: String,
+    #[serde(default)]
+    pub(crate) filename: Option<String>,
+    #[serde(default)]
+    pub(crate) vault_key: Option<String>,
+    #[serde(default)]
+    pub(crate) vault_path: Option<String>,
+    pub(crate) preview_url: Option<String>,
+    pub(crate) width: Option<i64>,
+    pub(crate) height: Option<i64>,
+    pub(crate) thumb_status: String,
+    pub(crate) import_status: String,
+    pub(crate) url: Option<String>,
+    pub(crate) favicon_path: Option<String>,
+    pub(crate) meta_status: Option<String>,
+    pub(crate) description: Option<String>,
+    #[serde(default)]
+    pub(crate) description_format: Option<String>,
+    #[serde(default)]
+    pub(crate) rating: i64,
+    #[serde(default)]
+    pub(crate) is_favorite: bool,
+    pub(crate) created_at: i64,
+    pub(crate) updated_at: i64,
+    #[serde(default)]
+    pub(crate) tags: Vec<String>,
+    #[serde(default)]
+    pub(crate) original_source_path: Option<String>,
+    /// Import provenance ("clipboard", "watch", "web"), recorded as a hidden system tag.
+    #[serde(default)]
+    pub(crate) source: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateItemMediaStateInput {
+    pub(crate) item_id: String,
+    pub(crate) width: Option<i64>,
+    pub(crate) height: Option<i64>,
+    pub(crate) thumb_status: Option<String>,
+    #[serde(default)]
+    pub(crate) clear_dimensions: bool,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RandomItemsFilter {
+    pub(crate) collection_id: Option<String>,
+    #[serde(default)]
+    pub(crate) tag_ids: Vec<String>,
+    #[serde(default)]
+    pub(crate) sources: Vec<String>,
+    #[serde(rename = "type")]
+    pub(crate) item_type: Option<String>,
+    pub(crate) min_rating: Option<i64>,
+    #[serde(default)]
+    pub(crate) include_archived: bool,
+    #[serde(default)]
+    pub(crate) include_sensitive: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeleteItemsFilter {
+    pub(crate) collection_id: Option<String>,
+    #[serde(default)]
+    pub(crate) tag_ids: Vec<String>,
+    #[serde(default)]
+    pub(crate) sources: Vec<String>,
+    #[serde(rename = "type")]
+    pub(crate) item_type: Option<String>,
+    pub(crate) min_rating: Option<i64>,
+    pub(crate) max_rating: Option<i64>,
+    pub(crate) created_before: Option<i64>,
+    pub(crate) is_favorite: Option<bool>,
+    #[serde(default)]
+    pub(crate) include_archived: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateItemPreferencesInput {
+    pub(crate) item_id: String,
+    pub(crate) rating: Option<i64>,
+    pub(crate) is_favorite: Option<bool>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ItemAuditEntry {
+    pub(crate) id: i64,
+    pub(crate) item_id: String,
+    pub(crate) field: String,
+    pub(crate) old_value: Option<String>,
+    pub(crate) new_value: Option<String>,
+    pub(crate) changed_at: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RatingStats {
+    pub(crate) total: i64,
+    pub(crate) items_by_rating: Vec<LibraryCountEntry>,
+    pub(crate) favorites_count: i64,
+    pub(crate) average_rating: Option<f64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InsertItemOutcome {
+    pub(crate) item_id: String,
+    pub(crate) already_existed: bool,
+    pub(crate) duplicate_of_item_id: Option<String>,
+    pub(crate) needs_thumbnail: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeleteItemsResult {
+    pub(crate) deleted_rows: usize,
+    pub(crate) cleanup: Vec<VaultCleanupEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MergeItemsResult {
+    pub(crate) kept_item_id: String,
+    pub(crate) merged_item_ids: Vec<String>,
+    pub(crate) tags_added: usize,
+    pub(crate) collections_added: usize,
+    pub(crate) notes_moved: usize,
+    pub(crate) links_moved: usize,
+    pub(crate) overlay_moved: bool,
+    pub(crate) description_merged: bool,
+    pub(crate) rating: i64,
+    pub(crate) is_favorite: bool,
+    pub(crate) deleted: DeleteItemsResult,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeleteByFilterPreview {
+    pub(crate) count: usize,
+    pub(crate) reclaimable_bytes: i64,
+    pub(crate) sample_titles: Vec<String>,
+    pub(crate) confirm_token: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeleteByFilterProgressPayload {
+    pub(crate) deleted: usize,
+    pub(crate) total: usize,
+}
+
+#[derive(Default)]
+pub(crate) struct PendingItemDeleteCleanup {
+    pub(crate) zero_ref_candidates: Vec<(String, String, String, String)>,
+    pub(crate) favicon_cleanup_candidates: Vec<String>,
+    pub(crate) preview_cleanup_candidates: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ItemMembershipChange {
+    pub(crate) item_id: String,
+    pub(crate) added: Vec<String>,
+    pub(crate) removed: Vec<String>,
+    pub(crate) primary_collection_id: Option<String>,
+}
+
+pub(crate) fn normalize_item_type(value: &str) -> Result<String, String> {
+    let normalized = value.trim().to_ascii_lowercase();
+    if ITEM_TYPES.contains(&normalized.as_str()) {
+        Ok(normalized)
+    } else {
+        Err(format!(
+            "invalid item type \"{}\"; expected one of: {}",
+            value,
+            ITEM_TYPES.join(", ")
+        ))
+    }
+}
+
+pub(crate) fn normalize_thumb_status(value: &str) -> String {
+    match value.trim() {
+        "ready" => "ready".to_string(),
+        "pending" => "pending".to_string(),
+        "skipped" => "skipped".to_string(),
+        "error" => "error".to_string(),
+        _ => DEFAULT_THUMB_STATUS.to_string(),
+    }
+}
+
+pub(crate) fn normalize_import_status(value: &str) -> String {
+    match value.trim() {
+        "ready" => "ready".to_string(),
+        "processing" => "processing".to_string(),
+        "error" => "error".to_string(),
+        _ => DEFAULT_IMPORT_STATUS.to_string(),
+    }
+}
+
+pub(crate) fn normalize_meta_status(value: &str) -> String {
+    match value.trim() {
+        "ready" => "ready".to_string(),
+        "pending" => "pending".to_string(),
+        "error" => "error".to_string(),
+        _ => DEFAULT_META_STATUS.to_string(),
+    }
+}
+
+pub(crate) fn normalize_item_rating(value: i64) -> i64 {
+    value.clamp(0, 5)
+}
+
+pub(crate) fn normalize_is_favorite_int(value: bool) -> i64 {
+    if value {
+        1
+    } else {
+        0
+    }
+}
+
+pub(crate) fn item_audit_enabled(connection: &Connection) -> Result<bool, String> {
+    Ok(read_app_setting(connection, SETTING_ITEM_AUDIT_ENABLED)?
+        .map(|value| !matches!(value.trim(), "0" | "false"))
+        .unwrap_or(true))
+}
+
+pub(crate) fn record_item_audit_in_tx(
+    connection: &Connection,
+    item_id: &str,
+    field: &str,
+    old_value: Option<&str>,
+    new_value: Option<&str>,
+    changed_at: i64,
+) -> Result<(), String> {
+    if old_value == new_value {
+        return Ok(());
+    }
+    connection
+        .execute(
+            "INSERT INTO item_audit_log (item_id, field, old_value, new_value, changed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![item_id, field, old_value, new_value, changed_at],
+        )
+        .map_err(|err| format!("failed to write item audit entry: {}", err))?;
+    connection
+        .execute(
+            "DELETE FROM item_audit_log
+             WHERE item_id = ?1
+               AND id <= (
+                 SELECT id FROM item_audit_log
+                 WHERE item_id = ?1
+                 ORDER BY id DESC
+                 LIMIT 1 OFFSET ?2
+               )",
+            params![item_id, ITEM_AUDIT_MAX_ENTRIES_PER_ITEM],
+        )
+        .map_err(|err| format!("failed to cap item audit entries: {}", err))?;
+    Ok(())
+}
+
+pub(crate) fn normalize_optional_trimmed_string(value: Option<String>) -> Option<String> {
+    value
+        .map(|candidate| candidate.trim().to_string())
+        .filter(|candidate| !candidate.is_empty())
+}
+
+pub(crate) fn collapse_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// The column list and joins are literals so collection views can append `cm.created_at`
+// without repeating them; rows map by position in [`db_item_row_from_row`].
+macro_rules! db_item_row_columns {
+    () => {
+        "
+        i.id,
+        i.collection_id,
+        i.type,
+        i.title,
+        i.filename,
+        i.vault_key,
+        i.vault_path,
+        i.preview_url,
+        i.width,
+        i.height,
+        i.thumb_status,
+        i.import_status,
+        i.url,
+        i.favicon_path,
+        i.meta_status,
+        i.description,
+        i.rating,
+        i.is_favorite,
+        i.created_at,
+        i.updated_at,
+        COALESCE(GROUP_CONCAT(it.tag_id, '|'), ''),
+        COALESCE(GROUP_CONCAT(t.name, '|'), ''),
+        i.is_archived,
+        (SELECT COUNT(*) FROM item_notes AS n WHERE n.item_id = i.id),
+        (SELECT COALESCE(GROUP_CONCAT(m.collection_id, '|'), '')
+         FROM (
+           SELECT ci.collection_id
+           FROM collection_items AS ci
+           WHERE ci.item_id = i.id
+           ORDER BY ci.created_at ASC, ci.collection_id ASC
+         ) AS m),
+        i.description_format,
+        i.is_sensitive,
+        i.integrity_status,
+        i.import_error"
+    };
+}
+
+macro_rules! db_item_row_from {
+    () => {
+        "
+     FROM items AS i
+     LEFT JOIN item_tags AS it
+       ON it.item_id = i.id
+      AND it.tag_id NOT IN (SELECT id FROM tags WHERE is_system = 1)
+     LEFT JOIN tags AS t ON t.id = it.tag_id"
+    };
+}
+
+pub(crate) const DB_ITEM_ROW_SELECT: &str =
+    concat!("SELECT", db_item_row_columns!(), db_item_row_from!());
+
+/// Same row as [`DB_ITEM_ROW_SELECT`] plus the membership's `created_at` at index 29, for
+/// queries that join the collection membership as `cm`.
+pub(crate) const DB_ITEM_ROW_WITH_ADDED_AT_SELECT: &str = concat!(
+    "SELECT",
+    db_item_row_columns!(),
+    ",
+        cm.created_at",
+    db_item_row_from!()
+);
+
+pub(crate) fn db_item_row_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<DbItemRow> {
+    let tag_ids_raw: String = row.get(20)?;
+    let tag_names: String = row.get(21)?;
+    let collection_ids_raw: String = row.get(24)?;
+    let tag_ids = if tag_ids_raw.is_empty() {
+        Vec::new()
+    } else {
+        tag_ids_raw.split('|').map(str::to_string).collect()
+    };
+    let tags = if tag_names.is_empty() {
+        Vec::new()
+    } else {
+        tag_names.split('|').map(str::to_string).collect()
+    };
+    let collection_ids = if collection_ids_raw.is_empty() {
+        Vec::new()
+    } else {
+        collection_ids_raw.split('|').map(str::to_string).collect()
+    };
+    let description: Option<String> = row.get(15)?;
+    let description_format = normalize_description_format(&row.get::<_, String>(25)?)
+        .unwrap_or_else(|_| DESCRIPTION_FORMAT_PLAIN.to_string());
+    let description_preview = description
+        .as_deref()
+        .and_then(|value| description_preview(value, &description_format));
+
+    Ok(DbItemRow {
+        id: row.get(0)?,
+        collection_id: row.get(1)?,
+        collection_ids,
+        item_type: row.get(2)?,
+        title: row.get(3)?,
+        filename: row.get(4)?,
+        vault_key: row.get(5)?,
+        vault_path: row.get(6)?,
+        preview_url: row.get(7)?,
+        width: row.get(8)?,
+        height: row.get(9)?,
+        thumb_status: normalize_thumb_status(&row.get::<_, String>(10)?),
+        import_status: normalize_import_status(&row.get::<_, String>(11)?),
+        import_error: row.get(28)?,
+        url: row.get(12)?,
+        favicon_path: row.get(13)?,
+        meta_status: normalize_meta_status(&row.get::<_, String>(14)?),
+        description,
+        description_format,
+        description_preview,
+        rating: normalize_item_rating(row.get::<_, i64>(16)?),
+        is_favorite: row.get::<_, i64>(17)? != 0,
+        is_archived: row.get::<_, i64>(22)? != 0,
+        is_sensitive: row.get::<_, i64>(26)? != 0,
+        integrity_status: row.get(27)?,
+        note_count: row.get(23)?,
+        created_at: row.get(18)?,
+        updated_at: row.get(19)?,
+        tag_ids,
+        tags,
+        added_at: None,
+    })
+}
+
+pub(crate) fn query_db_item_rows<P: rusqlite::Params>(
+    connection: &Connection,
+    sql: &str,
+    params: P,
+) -> Result<Vec<DbItemRow>, String> {
+    query_db_item_rows_with(connection, sql, params, db_item_row_from_row)
+}
+
+pub(crate) fn query_db_item_rows_with<P: rusqlite::Params>(
+    connection: &Connection,
+    sql: &str,
+    params: P,
+    map_row: fn(&rusqlite::Row<'_>) -> rusqlite::Result<DbItemRow>,
+) -> Result<Vec<DbItemRow>, String> {
+    let mut stmt = connection
+        .prepare(sql)
+        .map_err(|err| format!("failed to prepare items query: {}", err))?;
+    let rows = stmt
+        .query_map(params, map_row)
+        .map_err(|err| format!("failed to query items: {}", err))?;
+
+    let mut items = Vec::new();
+    for row_result in rows {
+        items.push(row_result.map_err(|err| format!("failed to read item row: {}", err))?);
+    }
+    Ok(items)
+}
+
+pub(crate) fn db_item_row_with_added_at_from_row(
+    row: &rusqlite::Row<'_>,
+) -> rusqlite::Result<DbItemRow> {
+    let mut item = db_item_row_from_row(row)?;
+    item.added_at = row.get(29)?;
+    Ok(item)
+}
+
+pub(crate) fn load_item_details(
+    connection: &Connection,
+    item_ids: &[String],
+) -> Result<Vec<DbItemDetail>, String> {
+    let mut details = Vec::new();
+    for chunk in item_ids.chunks(500) {
+        let placeholders = (1..=chunk.len())
+            .map(|index| format!("?{}", index))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let items = query_db_item_rows(
+            connection,
+            &format!(
+                "{} WHERE i.id IN ({}) GROUP BY i.id",
+                DB_ITEM_ROW_SELECT, placeholders
+            ),
+            rusqlite::params_from_iter(chunk.iter()),
+        )?;
+
+        let mut memberships_stmt = connection
+            .prepare(&format!(
+                "SELECT
+                    id,
+                    collection_id,
+                    item_id,
+                    custom_title,
+                    custom_description,
+                    sort_index,
+                    is_pinned,
+                    created_at
+                 FROM collection_items
+                 WHERE item_id IN ({})
+                 ORDER BY collection_id ASC, is_pinned DESC, sort_index ASC, created_at ASC, id ASC",
+                placeholders
+            ))
+            .map_err(|err| format!("failed to prepare item memberships query: {}", err))?;
+        let membership_rows = memberships_stmt
+            .query_map(
+                rusqlite::params_from_iter(chunk.iter()),
+                db_collection_item_row_from_row,
+            )
+            .map_err(|err| format!("failed to query item memberships: {}", err))?;
+        let mut memberships_by_item: HashMap<String, Vec<DbCollectionItemRow>> = HashMap::new();
+        for row_result in membership_rows {
+            let membership =
+                row_result.map_err(|err| format!("failed to read item membership row: {}", err))?;
+            memberships_by_item
+                .entry(membership.item_id.clone())
+                .or_default()
+                .push(membership);
+        }
+
+        for item in items {
+            let collection_items = memberships_by_item.remove(&item.id).unwrap_or_default();
+            details.push(DbItemDetail {
+                item,
+                collection_items,
+            });
+        }
+    }
+    Ok(details)
+}
+
+#[tauri::command]
+pub(crate) fn get_item_types() -> Vec<String> {
+    ITEM_TYPES
+        .iter()
+        .map(|item_type| item_type.to_string())
+        .collect()
+}
+
+#[tauri::command]
+pub(crate) fn get_item(item_id: String) -> Result<DbItemDetail, String> {
+    let Some(normalized_id) = normalize_trimmed_id(&item_id) else {
+        return Err("item id cannot be empty".to_string());
+    };
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    if locked_hidden_item_ids(&connection)?.contains(&normalized_id) {
+        return Err("item not found".to_string());
+    }
+    load_item_details(&connection, &[normalized_id])?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "item not found".to_string())
+}
+
+#[tauri::command]
+pub(crate) fn get_items_by_ids(item_ids: Vec<String>) -> Result<GetItemsByIdsResult, String> {
+    let normalized_ids = normalize_item_ids_input(item_ids);
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let locked_item_ids = locked_hidden_item_ids(&connection)?;
+    let mut details_by_id: HashMap<String, DbItemDetail> =
+        load_item_details(&connection, &normalized_ids)?
+            .into_iter()
+            .filter(|detail| !locked_item_ids.contains(&detail.item.id))
+            .map(|detail| (detail.item.id.clone(), detail))
+            .collect();
+
+    let mut items = Vec::new();
+    let mut missing = Vec::new();
+    for item_id in normalized_ids {
+        match details_by_id.remove(&item_id) {
+            Some(detail) => items.push(detail),
+            None => missing.push(item_id),
+        }
+    }
+    Ok(GetItemsByIdsResult { items, missing })
+}
+
+pub(crate) fn push_sql_param(
+    values: &mut Vec<rusqlite::types::Value>,
+    value: rusqlite::types::Value,
+) -> String {
+    values.push(value);
+    format!("?{}", values.len())
+}
+
+/// Matches items carrying any of the system tags for `sources`.
+pub(crate) fn item_sources_condition(
+    values: &mut Vec<rusqlite::types::Value>,
+    sources: &[String],
+) -> Result<Option<String>, String> {
+    let mut tag_names = BTreeSet::new();
+    for source in sources.iter().filter(|source| !source.trim().is_empty()) {
+        tag_names.insert(system_tag_for_item_source(source)?);
+    }
+    if tag_names.is_empty() {
+        return Ok(None);
+    }
+    let placeholders = tag_names
+        .into_iter()
+        .map(|tag_name| push_sql_param(values, tag_name.to_string().into()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Ok(Some(format!(
+        "i.id IN (SELECT st.item_id FROM item_tags AS st JOIN tags AS t ON t.id = st.tag_id WHERE t.is_system = 1 AND t.name IN ({}))",
+        placeholders
+    )))
+}
+
+pub(crate) fn load_item_rows_in_order(
+    connection: &Connection,
+    item_ids: &[String],
+) -> Result<Vec<DbItemRow>, String> {
+    let mut rows_by_id: HashMap<String, DbItemRow> = HashMap::new();
+    for chunk in item_ids.chunks(500) {
+        let placeholders = (1..=chunk.len())
+            .map(|index| format!("?{}", index))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let rows = query_db_item_rows(
+            connection,
+            &format!(
+                "{} WHERE i.id IN ({}) GROUP BY i.id",
+                DB_ITEM_ROW_SELECT, placeholders
+            ),
+            rusqlite::params_from_iter(chunk.iter()),
+        )?;
+        for row in rows {
+            rows_by_id.insert(row.id.clone(), row);
+        }
+    }
+    Ok(item_ids
+        .iter()
+        .filter_map(|item_id| rows_by_id.remove(item_id))
+        .collect())
+}
+
+#[tauri::command]
+pub(crate) fn get_random_items(
+    count: u32,
+    filter: Option<RandomItemsFilter>,
+    exclude_item_ids: Option<Vec<String>>,
+    weighted: Option<bool>,
+) -> Result<Vec<DbItemRow>, String> {
+    let limit = count.clamp(1, RANDOM_ITEMS_MAX_COUNT);
+    let filter = filter.unwrap_or_default();
+    initialize_db()?;
+    let connection = open_db_connection()?;
+
+    let mut conditions: Vec<String> = Vec::new();
+    let mut values: Vec<rusqlite::types::Value> = Vec::new();
+    let mut cte = String::new();
+    if let Some(collection_id) = normalize_optional_trimmed_id(filter.collection_id) {
+        let placeholder = push_sql_param(&mut values, collection_id.into());
+        cte = format!(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT {}
+                UNION
+                SELECT c.id FROM collections AS c JOIN subtree AS s ON c.parent_id = s.id
+             ) ",
+            placeholder
+        );
+        conditions.push(
+            "i.id IN (SELECT ci.item_id FROM collection_items AS ci JOIN subtree AS s ON s.id = ci.collection_id)"
+                .to_string(),
+        );
+    }
+
+    let tag_ids = normalize_item_ids_input(filter.tag_ids);
+    if !tag_ids.is_empty() {
+        let tag_count = tag_ids.len() as i64;
+        let placeholders = tag_ids
+            .into_iter()
+            .map(|tag_id| push_sql_param(&mut values, tag_id.into()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let count_placeholder = push_sql_param(&mut values, tag_count.into());
+        conditions.push(format!(
+            "i.id IN (SELECT item_id FROM item_tags WHERE tag_id IN ({}) GROUP BY item_id HAVING COUNT(DISTINCT tag_id) = {})",
+            placeholders, count_placeholder
+        ));
+    }
+
+    if let Some(condition) = item_sources_condition(&mut values, &filter.sources)? {
+        conditions.push(condition);
+    }
+
+    if let Some(item_type) = normalize_optional_trimmed_string(filter.item_type) {
+        let placeholder = push_sql_param(&mut values, normalize_item_type(&item_type)?.into());
+        conditions.push(format!("i.type = {}", placeholder));
+    }
+
+    if !filter.include_archived {
+        conditions.push("i.is_archived = 0".to_string());
+    }
+
+    if !resolve_show_sensitive(&connection, Some(filter.include_sensitive))? {
+        conditions.push("i.is_sensitive = 0".to_string());
+    }
+
+    prepare_locked_collection_scope(&connection)?;
+    conditions.push(LOCKED_SCOPE_VISIBLE_CONDITION.to_string());
+
+    if let Some(min_rating) = filter.min_rating {
+        let placeholder = push_sql_param(&mut values, normalize_item_rating(min_rating).into());
+        conditions.push(format!("i.rating >= {}", placeholder));
+    }
+
+    let excluded_ids = normalize_item_ids_input(exclude_item_ids.unwrap_or_default());
+    if !excluded_ids.is_empty() {
+        let placeholders = excluded_ids
+            .into_iter()
+            .map(|item_id| push_sql_param(&mut values, item_id.into()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        conditions.push(format!("i.id NOT IN ({})", placeholders));
+    }
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    let order_clause = if weighted.unwrap_or(false) {
+        "ORDER BY (ABS(RANDOM()) % 1000000) * (1.0 + 0.15 * rating + 0.5 * is_favorite) DESC"
+    } else {
+        "ORDER BY RANDOM()"
+    };
+    let limit_placeholder = push_sql_param(&mut values, i64::from(limit).into());
+    let sql = format!(
+        "{}SELECT id FROM (
+            SELECT i.id, i.rating, i.is_favorite FROM items AS i {}
+         ) {} LIMIT {}",
+        cte, where_clause, order_clause, limit_placeholder
+    );
+
+    let mut stmt = connection
+        .prepare(&sql)
+        .map_err(|err| format!("failed to prepare random items query: {}", err))?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(values.iter()), |row| {
+            row.get::<_, String>(0)
+        })
+        .map_err(|err| format!("failed to query random items: {}", err))?;
+    let mut item_ids = Vec::new();
+    for row_result in rows {
+        item_ids.push(row_result.map_err(|err| format!("failed to read random item id: {}", err))?);
+    }
+
+    load_item_rows_in_order(&connection, &item_ids)
+}
+
+pub(crate) fn normalize_utc_offset_minutes(value: Option<i32>) -> Result<i64, String> {
+    let offset = value.unwrap_or(0);
+    if offset.abs() > MAX_UTC_OFFSET_MINUTES {
+        return Err(format!(
+            "utc offset must be within +/-{} minutes",
+            MAX_UTC_OFFSET_MINUTES
+        ));
+    }
+    Ok(i64::from(offset))
+}
+
+pub(crate) fn local_date_start_utc_ms(date: chrono::NaiveDate, utc_offset_minutes: i64) -> i64 {
+    date.and_hms_opt(0, 0, 0)
+        .map(|datetime| datetime.and_utc().timestamp_millis())
+        .unwrap_or(0)
+        - utc_offset_minutes * 60_000
+}
+
+pub(crate) fn parse_local_date(value: &str) -> Result<chrono::NaiveDate, String> {
+    chrono::NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|err| format!("invalid date {} (expected YYYY-MM-DD): {}", value, err))
+}
+
+#[tauri::command]
+pub(crate) fn get_items_by_month(
+    year: i32,
+    month: u32,
+    item_type: Option<String>,
+    utc_offset_minutes: Option<i32>,
+    include_archived: Option<bool>,
+    include_sensitive: Option<bool>,
+) -> Result<Vec<DbItemRow>, String> {
+    let offset = normalize_utc_offset_minutes(utc_offset_minutes)?;
+    let month_start = chrono::NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| format!("invalid month {}-{}", year, month))?;
+    let next_month_start = if month == 12 {
+        chrono::NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        chrono::NaiveDate::from_ymd_opt(year, month + 1, 1)
+    }
+    .ok_or_else(|| format!("invalid month {}-{}", year, month))?;
+    let normalized_type = match normalize_optional_trimmed_string(item_type) {
+        Some(value) => Some(normalize_item_type(&value)?),
+        None => None,
+    };
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let show_sensitive = resolve_show_sensitive(&connection, include_sensitive)?;
+    prepare_locked_collection_scope(&connection)?;
+    query_db_item_rows(
+        &connection,
+        &format!(
+            "{} WHERE i.created_at >= ?1 AND i.created_at < ?2 AND (?3 IS NULL OR i.type = ?3)
+               AND (?4 OR i.is_archived = 0)
+               AND (?5 OR i.is_sensitive = 0)
+               AND {}
+             GROUP BY i.id
+             ORDER BY i.created_at DESC",
+            DB_ITEM_ROW_SELECT, LOCKED_SCOPE_VISIBLE_CONDITION
+        ),
+        params![
+            local_date_start_utc_ms(month_start, offset),
+            local_date_start_utc_ms(next_month_start, offset),
+            normalized_type,
+            include_archived.unwrap_or(false),
+            show_sensitive
+        ],
+    )
+}
+
+pub(crate) fn item_created_at_sort_value(row: &DbItemRow) -> serde_json::Value {
+    row.created_at.into()
+}
+
+pub(crate) fn item_title_sort_value(row: &DbItemRow) -> serde_json::Value {
+    row.title.clone().into()
+}
+
+pub(crate) fn item_rating_sort_value(row: &DbItemRow) -> serde_json::Value {
+    row.rating.into()
+}
+
+// Every sort ends on i.id so the order is total and a (keys, id) cursor is unambiguous.
+pub(crate) const ITEM_SORT_SPECS: &[ItemSortSpec] = &[
+    ItemSortSpec {
+        name: "newest",
+        order_by: "i.created_at DESC, i.id DESC",
+        keys: &[ItemSortKey {
+            expr: "i.created_at",
+            descending: true,
+            value: item_created_at_sort_value,
+        }],
+        id_descending: true,
+    },
+    ItemSortSpec {
+        name: "oldest",
+        order_by: "i.created_at ASC, i.id ASC",
+        keys: &[ItemSortKey {
+            expr: "i.created_at",
+            descending: false,
+            value: item_created_at_sort_value,
+        }],
+        id_descending: false,
+    },
+    ItemSortSpec {
+        name: "name-asc",
+        order_by: "i.title COLLATE NOCASE ASC, i.id DESC",
+        keys: &[ItemSortKey {
+            expr: "i.title COLLATE NOCASE",
+            descending: false,
+            value: item_title_sort_value,
+        }],
+        id_descending: true,
+    },
+    ItemSortSpec {
+        name: "name-natural",
+        order_by: "i.title COLLATE NATURAL_TITLE ASC, i.id DESC",
+        keys: &[ItemSortKey {
+            expr: "i.title COLLATE NATURAL_TITLE",
+            descending: false,
+            value: item_title_sort_value,
+        }],
+        id_descending: true,
+    },
+    ItemSortSpec {
+        name: "rating-desc",
+        order_by: "i.rating DESC, i.created_at DESC, i.id DESC",
+        keys: &[
+            ItemSortKey {
+                expr: "i.rating",
+                descending: true,
+                value: item_rating_sort_value,
+            },
+            ItemSortKey {
+                expr: "i.created_at",
+                descending: true,
+                value: item_created_at_sort_value,
+            },
+        ],
+        id_descending: true,
+    },
+];
+
+pub(crate) fn item_added_at_sort_value(row: &DbItemRow) -> serde_json::Value {
+    row.added_at.into()
+}
+
+// Collection-scoped views join the membership row as `cm`, so they can also sort by when
+// the item was added to that collection.
+pub(crate) const COLLECTION_ITEM_SORT_SPECS: &[ItemSortSpec] = &[
+    ItemSortSpec {
+        name: "added-desc",
+        order_by: "cm.created_at DESC, i.id DESC",
+        keys: &[ItemSortKey {
+            expr: "cm.created_at",
+            descending: true,
+            value: item_added_at_sort_value,
+        }],
+        id_descending: true,
+    },
+    ItemSortSpec {
+        name: "added-asc",
+        order_by: "cm.created_at ASC, i.id ASC",
+        keys: &[ItemSortKey {
+            expr: "cm.created_at",
+            descending: false,
+            value: item_added_at_sort_value,
+        }],
+        id_descending: false,
+    },
+];
+
+pub(crate) fn item_sort_spec(sort: Option<&str>) -> Result<&'static ItemSortSpec, String> {
+    let name = match sort.map(str::trim).unwrap_or("newest") {
+        "" => "newest",
+        other => other,
+    };
+    ITEM_SORT_SPECS
+        .iter()
+        .find(|spec| spec.name == name)
+        .ok_or_else(|| {
+            format!(
+                "invalid item sort \"{}\"; expected one of: newest, oldest, name-asc, name-natural, rating-desc",
+                name
+            )
+        })
+}
+
+pub(crate) fn collection_item_sort_spec(
+    sort: Option<&str>,
+) -> Result<&'static ItemSortSpec, String> {
+    let name = sort.map(str::trim).unwrap_or_default();
+    match COLLECTION_ITEM_SORT_SPECS.iter().find(|spec| spec.name == name) {
+        Some(spec) => Ok(spec),
+        None => item_sort_spec(sort).map_err(|_| {
+            format!(
+                "invalid collection item sort \"{}\"; expected one of: newest, oldest, name-asc, name-natural, rating-desc, added-desc, added-asc",
+                name
+            )
+        }),
+    }
+}
+
+pub(crate) fn encode_item_page_cursor(
+    spec: &ItemSortSpec,
+    row: &DbItemRow,
+) -> Result<String, String> {
+    let cursor = ItemPageCursor {
+        sort: spec.name.to_string(),
+        keys: spec.keys.iter().map(|key| (key.value)(row)).collect(),
+        id: row.id.clone(),
+    };
+    let encoded = serde_json::to_vec(&cursor)
+        .map_err(|err| format!("failed to encode item cursor: {}", err))?;
+    Ok(hex_encode(&encoded))
+}
+
+pub(crate) fn decode_item_page_cursor(
+    spec: &ItemSortSpec,
+    cursor: &str,
+) -> Result<ItemPageCursor, String> {
+    let invalid = || "invalid item cursor".to_string();
+    let bytes = hex_decode(cursor).ok_or_else(invalid)?;
+    let decoded: ItemPageCursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+    if decoded.sort != spec.name {
+        return Err(format!(
+            "item cursor was issued for sort \"{}\", not \"{}\"",
+            decoded.sort, spec.name
+        ));
+    }
+    if decoded.keys.len() != spec.keys.len() {
+        return Err(invalid());
+    }
+    Ok(decoded)
+}
+
+pub(crate) fn item_cursor_sql_value(value: &serde_json::Value) -> Result<Value, String> {
+    match value {
+        serde_json::Value::Number(number) => number
+            .as_i64()
+            .map(Value::Integer)
+            .ok_or_else(|| "invalid item cursor".to_string()),
+        serde_json::Value::String(text) => Ok(Value::Text(text.clone())),
+        _ => Err("invalid item cursor".to_string()),
+    }
+}
+
+/// Builds the "rows after the cursor" condition for `spec`, binding the cursor values to
+/// placeholders starting at `?first_param`. Without a cursor the condition is always true.
+pub(crate) fn item_keyset_condition(
+    spec: &ItemSortSpec,
+    cursor: Option<&str>,
+    first_param: usize,
+) -> Result<ItemKeyset, String> {
+    let Some(cursor) = cursor else {
+        return Ok(ItemKeyset {
+            condition: "1".to_string(),
+            values: Vec::new(),
+        });
+    };
+    let cursor = decode_item_page_cursor(spec, cursor)?;
+    let mut values = cursor
+        .keys
+        .iter()
+        .map(item_cursor_sql_value)
+        .collect::<Result<Vec<_>, _>>()?;
+    values.push(Value::Text(cursor.id));
+
+    let columns: Vec<(&str, bool)> = spec
+        .keys
+        .iter()
+        .map(|key| (key.expr, key.descending))
+        .chain([("i.id", spec.id_descending)])
+        .collect();
+    let clauses: Vec<String> = (0..columns.len())
+        .map(|position| {
+            let mut parts: Vec<String> = columns[..position]
+                .iter()
+                .enumerate()
+                .map(|(index, (expr, _))| format!("{} = ?{}", expr, first_param + index))
+                .collect();
+            let (expr, descending) = columns[position];
+            parts.push(format!(
+                "{} {} ?{}",
+                expr,
+                if descending { "<" } else { ">" },
+                first_param + position
+            ));
+            format!("({})", parts.join(" AND "))
+        })
+        .collect();
+    Ok(ItemKeyset {
+        condition: format!("({})", clauses.join(" OR ")),
+        values,
+    })
+}
+
+pub(crate) fn item_page_params(
+    base: Vec<Value>,
+    keyset: ItemKeyset,
+) -> rusqlite::ParamsFromIter<Vec<Value>> {
+    let mut values = base;
+    values.extend(keyset.values);
+    params_from_iter(values)
+}
+
+pub(crate) fn build_item_page(
+    spec: &ItemSortSpec,
+    items: Vec<DbItemRow>,
+    total: i64,
+    hidden_count: i64,
+    limit: u32,
+    offset: u32,
+) -> Result<ItemPage, String> {
+    let next_cursor = match items.last() {
+        Some(last) if items.len() >= limit as usize => Some(encode_item_page_cursor(spec, last)?),
+        _ => None,
+    };
+    Ok(ItemPage {
+        items,
+        total,
+        hidden_count,
+        limit,
+        offset,
+        next_cursor,
+    })
+}
+
+/// A cursor takes precedence over `offset`; offsets stay available for jumping to a page.
+pub(crate) fn normalize_item_page(
+    limit: Option<u32>,
+    offset: Option<u32>,
+    cursor: Option<&str>,
+) -> (u32, u32) {
+    (
+        limit
+            .unwrap_or(ITEM_PAGE_DEFAULT_LIMIT)
+            .clamp(1, ITEM_PAGE_MAX_LIMIT),
+        if cursor.is_some() {
+            0
+        } else {
+            offset.unwrap_or(0)
+        },
+    )
+}
+
+#[tauri::command]
+pub(crate) fn get_favorite_items(
+    limit: Option<u32>,
+    offset: Option<u32>,
+    sort: Option<String>,
+    include_sensitive: Option<bool>,
+    cursor: Option<String>,
+) -> Result<ItemPage, String> {
+    let spec = item_sort_spec(sort.as_deref())?;
+    let cursor = normalize_optional_trimmed_string(cursor);
+    let (limit, offset) = normalize_item_page(limit, offset, cursor.as_deref());
+    let keyset = item_keyset_condition(spec, cursor.as_deref(), 4)?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let show_sensitive = resolve_show_sensitive(&connection, include_sensitive)?;
+    prepare_locked_collection_scope(&connection)?;
+    let (matched, sensitive_count) = connection
+        .query_row(
+            &format!(
+                "SELECT COUNT(*), COALESCE(SUM(i.is_sensitive), 0)
+                 FROM items AS i
+                 WHERE i.is_favorite = 1 AND i.is_archived = 0 AND {}",
+                LOCKED_SCOPE_VISIBLE_CONDITION
+            ),
+            [],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .map_err(|err| format!("failed to count favorite items: {}", err))?;
+    let hidden_count = hidden_sensitive_count(show_sensitive, sensitive_count);
+    let items = query_db_item_rows(
+        &connection,
+        &format!(
+            "{} WHERE i.is_favorite = 1 AND i.is_archived = 0 AND (?3 OR i.is_sensitive = 0) AND {}
+               AND {}
+             GROUP BY i.id
+             ORDER BY {}
+             LIMIT ?1 OFFSET ?2",
+            DB_ITEM_ROW_SELECT, LOCKED_SCOPE_VISIBLE_CONDITION, keyset.condition, spec.order_by
+        ),
+        item_page_params(
+            vec![limit.into(), offset.into(), show_sensitive.into()],
+            keyset,
+        ),
+    )?;
+
+    build_item_page(
+        spec,
+        items,
+        matched - hidden_count,
+        hidden_count,
+        limit,
+        offset,
+    )
+}
+
+#[tauri::command]
+pub(crate) fn get_items_by_min_rating(
+    min_rating: i64,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    include_sensitive: Option<bool>,
+    cursor: Option<String>,
+) -> Result<ItemPage, String> {
+    let min_rating = min_rating.clamp(1, 5);
+    let spec = item_sort_spec(Some("rating-desc"))?;
+    let cursor = normalize_optional_trimmed_string(cursor);
+    let (limit, offset) = normalize_item_page(limit, offset, cursor.as_deref());
+    let keyset = item_keyset_condition(spec, cursor.as_deref(), 5)?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let show_sensitive = resolve_show_sensitive(&connection, include_sensitive)?;
+    prepare_locked_collection_scope(&connection)?;
+    let (matched, sensitive_count) = connection
+        .query_row(
+            &format!(
+                "SELECT COUNT(*), COALESCE(SUM(i.is_sensitive), 0)
+                 FROM items AS i
+                 WHERE i.rating > 0 AND i.rating >= ?1 AND i.is_archived = 0 AND {}",
+                LOCKED_SCOPE_VISIBLE_CONDITION
+            ),
+            params![min_rating],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .map_err(|err| format!("failed to count rated items: {}", err))?;
+    let hidden_count = hidden_sensitive_count(show_sensitive, sensitive_count);
+    let items = query_db_item_rows(
+        &connection,
+        &format!(
+            "{} WHERE i.rating > 0 AND i.rating >= ?1 AND i.is_archived = 0
+               AND (?4 OR i.is_sensitive = 0)
+               AND {}
+               AND {}
+             GROUP BY i.id
+             ORDER BY {}
+             LIMIT ?2 OFFSET ?3",
+            DB_ITEM_ROW_SELECT, LOCKED_SCOPE_VISIBLE_CONDITION, keyset.condition, spec.order_by
+        ),
+        item_page_params(
+            vec![
+                min_rating.into(),
+                limit.into(),
+                offset.into(),
+                show_sensitive.into(),
+            ],
+            keyset,
+        ),
+    )?;
+
+    build_item_page(
+        spec,
+        items,
+        matched - hidden_count,
+        hidden_count,
+        limit,
+        offset,
+    )
+}
+
+#[tauri::command]
+pub(crate) fn get_rating_stats(
+    collection_id: Option<String>,
+    tag_id: Option<String>,
+) -> Result<RatingStats, String> {
+    let collection_id = normalize_optional_trimmed_id(collection_id);
+    let tag_id = normalize_optional_trimmed_id(tag_id);
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let show_sensitive = resolve_show_sensitive(&connection, None)?;
+    prepare_locked_collection_scope(&connection)?;
+
+    let mut stmt = connection
+        .prepare(&format!(
+            "{}SELECT i.rating, COUNT(*), COALESCE(SUM(i.is_favorite), 0)
+             FROM items AS i
+             WHERE i.is_archived = 0
+               AND (?3 OR i.is_sensitive = 0)
+               AND (?1 IS NULL OR i.id IN (
+                 SELECT ci.item_id FROM collection_items AS ci JOIN subtree AS s ON s.id = ci.collection_id
+               ))
+               AND (?2 IS NULL OR EXISTS (
+                 SELECT 1 FROM item_tags AS rt WHERE rt.item_id = i.id AND rt.tag_id = ?2
+               ))
+               AND {}
+             GROUP BY i.rating",
+            UNTAGGED_ITEMS_SUBTREE_CTE, LOCKED_SCOPE_VISIBLE_CONDITION
+        ))
+        .map_err(|err| format!("failed to prepare rating stats query: {}", err))?;
+    let rows = stmt
+        .query_map(params![collection_id, tag_id, show_sensitive], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })
+        .map_err(|err| format!("failed to query rating stats: {}", err))?;
+
+    let mut counts = [0_i64; 6];
+    let mut favorites_count = 0;
+    for row_result in rows {
+        let (rating, count, favorites) =
+            row_result.map_err(|err| format!("failed to read rating stats row: {}", err))?;
+        counts[normalize_item_rating(rating) as usize] += count;
+        favorites_count += favorites;
+    }
+    let rated_count: i64 = counts[1..].iter().sum();
+    let rating_sum: i64 = counts
+        .iter()
+        .enumerate()
+        .map(|(rating, count)| rating as i64 * count)
+        .sum();
+
+    Ok(RatingStats {
+        total: counts.iter().sum(),
+        items_by_rating: counts
+            .iter()
+            .enumerate()
+            .map(|(rating, count)| LibraryCountEntry {
+                key: rating.to_string(),
+                count: *count,
+            })
+            .collect(),
+        favorites_count,
+        average_rating: (rated_count > 0).then(|| rating_sum as f64 / rated_count as f64),
+    })
+}
+
+#[tauri::command]
+pub(crate) fn get_activity_heatmap(
+    from: String,
+    to: String,
+    utc_offset_minutes: Option<i32>,
+) -> Result<Vec<ActivityHeatmapEntry>, String> {
+    let offset = normalize_utc_offset_minutes(utc_offset_minutes)?;
+    let from_date = parse_local_date(&from)?;
+    let to_date = parse_local_date(&to)?;
+    if to_date < from_date {
+        return Err("heatmap end date must not be before start date".to_string());
+    }
+    let end_exclusive = to_date
+        .succ_opt()
+        .ok_or_else(|| format!("invalid heatmap end date {}", to))?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let mut stmt = connection
+        .prepare(
+            "SELECT date((created_at + ?3) / 1000, 'unixepoch') AS day, COUNT(*)
+             FROM items
+             WHERE created_at >= ?1 AND created_at < ?2
+             GROUP BY day
+             ORDER BY day ASC",
+        )
+        .map_err(|err| format!("failed to prepare activity heatmap query: {}", err))?;
+    let rows = stmt
+        .query_map(
+            params![
+                local_date_start_utc_ms(from_date, offset),
+                local_date_start_utc_ms(end_exclusive, offset),
+                offset * 60_000
+            ],
+            |row| {
+                Ok(ActivityHeatmapEntry {
+                    day: row.get(0)?,
+                    count: row.get(1)?,
+                })
+            },
+        )
+        .map_err(|err| format!("failed to query activity heatmap: {}", err))?;
+
+    let mut entries = Vec::new();
+    for row_result in rows {
+        entries.push(
+            row_result.map_err(|err| format!("failed to read activity heatmap row: {}", err))?,
+        );
+    }
+    Ok(entries)
+}
+
+/// Counts every matching item; the sample ids skip archived, sensitive (unless shown) and
+/// locked items so the digest never renders thumbnails the grid would hide.
+pub(crate) fn weekly_digest_section(
+    connection: &Connection,
+    condition: &str,
+    order_by: &str,
+    from: i64,
+    to: i64,
+    show_sensitive: bool,
+) -> Result<WeeklyDigestSection, String> {
+    let count = connection
+        .query_row(
+            &format!("SELECT COUNT(*) FROM items AS i WHERE {}", condition),
+            params![from, to],
+            |row| row.get::<_, i64>(0),
+        )
+        .map_err(|err| format!("failed to count weekly digest items: {}", err))?;
+    let mut stmt = connection
+        .prepare(&format!(
+            "SELECT i.id
+             FROM items AS i
+             WHERE {}
+               AND i.is_archived = 0
+               AND (?3 OR i.is_sensitive = 0)
+               AND {}
+             ORDER BY {}
+             LIMIT ?4",
+            condition, LOCKED_SCOPE_VISIBLE_CONDITION, order_by
+        ))
+        .map_err(|err| format!("failed to prepare weekly digest sample query: {}", err))?;
+    let item_ids = stmt
+        .query_map(
+            params![from, to, show_sensitive, WEEKLY_DIGEST_SAMPLE_LIMIT],
+            |row| row.get::<_, String>(0),
+        )
+        .map_err(|err| format!("failed to query weekly digest sample: {}", err))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to read weekly digest sample id: {}", err))?;
+    Ok(WeeklyDigestSection { count, item_ids })
+}
+
+/// Summarizes one local ISO week (Monday to Monday in `utc_offset_minutes`). `week_offset`
+/// is relative to the current week: 0 is this week, -1 last week.
+///
+/// "Read" bookmarks are those opened during the week per `items.last_opened_at`, so a
+/// bookmark reopened later only counts for its latest week. "Rated 4+" comes from the
+/// rating audit log and is empty while item auditing is disabled. Top tags are counted
+/// over items added that week; imported bytes exclude deduplicated imports.
+#[tauri::command]
+pub(crate) fn get_weekly_digest(
+    week_offset: i32,
+    utc_offset_minutes: Option<i32>,
+) -> Result<WeeklyDigest, String> {
+    use chrono::Datelike;
+
+    let offset = normalize_utc_offset_minutes(utc_offset_minutes)?;
+    if week_offset.abs() > WEEKLY_DIGEST_MAX_WEEK_OFFSET {
+        return Err(format!(
+            "week offset must be within +/-{} weeks",
+            WEEKLY_DIGEST_MAX_WEEK_OFFSET
+        ));
+    }
+    let today = (Utc::now() + chrono::Duration::minutes(offset)).date_naive();
+    let week_start = today
+        - chrono::Duration::days(i64::from(today.weekday().num_days_from_monday()))
+        + chrono::Duration::weeks(i64::from(week_offset));
+    let iso_week = week_start.iso_week();
+    let from = local_date_start_utc_ms(week_start, offset);
+    let to = local_date_start_utc_ms(week_start + chrono::Duration::weeks(1), offset);
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let show_sensitive = resolve_show_sensitive(&connection, None)?;
+    prepare_locked_collection_scope(&connection)?;
+
+    let added = weekly_digest_section(
+        &connection,
+        "i.created_at >= ?1 AND i.created_at < ?2",
+        "i.created_at DESC",
+        from,
+        to,
+        show_sensitive,
+    )?;
+    let bookmarks_read = weekly_digest_section(
+        &connection,
+        "i.type = 'bookmark' AND i.last_opened_at >= ?1 AND i.last_opened_at < ?2",
+        "i.last_opened_at DESC",
+        from,
+        to,
+        show_sensitive,
+    )?;
+    let rated_high = weekly_digest_section(
+        &connection,
+        "EXISTS (
+            SELECT 1 FROM item_audit_log AS a
+            WHERE a.item_id = i.id
+              AND a.field = 'rating'
+              AND CAST(a.new_value AS INTEGER) >= 4
+              AND a.changed_at >= ?1 AND a.changed_at < ?2
+         )",
+        "i.rating DESC, i.updated_at DESC",
+        from,
+        to,
+        show_sensitive,
+    )?;
+
+    let mut type_stmt = connection
+        .prepare(
+            "SELECT type, COUNT(*)
+             FROM items
+             WHERE created_at >= ?1 AND created_at < ?2
+             GROUP BY type
+             ORDER BY COUNT(*) DESC, type ASC",
+        )
+        .map_err(|err| format!("failed to prepare weekly digest type query: {}", err))?;
+    let added_by_type = type_stmt
+        .query_map(params![from, to], |row| {
+            Ok(LibraryCountEntry {
+                key: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })
+        .map_err(|err| format!("failed to query weekly digest types: {}", err))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to read weekly digest type row: {}", err))?;
+
+    let mut tag_stmt = connection
+        .prepare(
+            "SELECT t.id, t.name, t.color, COUNT(*)
+             FROM item_tags AS it
+             JOIN tags AS t ON t.id = it.tag_id
+             JOIN items AS i ON i.id = it.item_id
+             WHERE i.created_at >= ?1 AND i.created_at < ?2 AND t.is_system = 0
+             GROUP BY t.id
+             ORDER BY COUNT(*) DESC, t.name ASC
+             LIMIT ?3",
+        )
+        .map_err(|err| format!("failed to prepare weekly digest tag query: {}", err))?;
+    let top_tags = tag_stmt
+        .query_map(params![from, to, WEEKLY_DIGEST_TAG_LIMIT], |row| {
+            Ok(WeeklyDigestTag {
+                tag_id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                count: row.get(3)?,
+            })
+        })
+        .map_err(|err| format!("failed to query weekly digest tags: {}", err))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to read weekly digest tag row: {}", err))?;
+
+    let bytes_imported = connection
+        .query_row(
+            "SELECT COALESCE(SUM(size_bytes), 0)
+             FROM import_metrics
+             WHERE success = 1 AND deduped = 0 AND created_at >= ?1 AND created_at < ?2",
+            params![from, to],
+            |row| row.get::<_, i64>(0),
+        )
+        .map_err(|err| format!("failed to sum weekly imported bytes: {}", err))?;
+
+    Ok(WeeklyDigest {
+        week_start: week_start.format("%Y-%m-%d").to_string(),
+        iso_year: iso_week.year(),
+        iso_week: iso_week.week(),
+        from,
+        to,
+        added,
+        added_by_type,
+        bookmarks_read,
+        rated_high,
+        top_tags,
+        bytes_imported,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn duplicate_item(
+    item_id: String,
+    target_collection_id: Option<String>,
+    include_overlay: Option<bool>,
+) -> Result<DbItemRow, String> {
+    let source_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+    let normalized_target_collection_id = normalize_optional_trimmed_id(target_collection_id);
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    let source = query_db_item_rows(
+        &transaction,
+        &format!("{} WHERE i.id = ?1 GROUP BY i.id", DB_ITEM_ROW_SELECT),
+        params![&source_item_id],
+    )?
+    .into_iter()
+    .next()
+    .ok_or_else(|| "item not found while duplicating".to_string())?;
+
+    let collection_id = normalized_target_collection_id.or_else(|| source.collection_id.clone());
+    if let Some(collection_id) = collection_id.as_deref() {
+        validate_collection_exists_in_tx(&transaction, collection_id)?;
+    }
+
+    let new_item_id = Uuid::new_v4().to_string();
+    let now = Utc::now().timestamp_millis();
+    insert_item_in_tx(
+        &transaction,
+        InsertItemInput {
+            id: new_item_id.clone(),
+            collection_id,
+            item_type: source.item_type,
+            title: format!("{} copy", source.title.trim()),
+            filename: Some(source.filename),
+            vault_key: Some(source.vault_key),
+            vault_path: Some(source.vault_path),
+            preview_url: source.preview_url,
+            width: source.width,
+            height: source.height,
+            thumb_status: source.thumb_status,
+            import_status: source.import_status,
+            url: source.url,
+            favicon_path: source.favicon_path,
+            meta_status: Some(source.meta_status),
+            description: source.description,
+            description_format: Some(source.description_format),
+            rating: source.rating,
+            is_favorite: source.is_favorite,
+            created_at: now,
+            updated_at: now,
+            tags: source.tags,
+            original_source_path: None,
+            source: None,
+        },
+    )?;
+
+    if include_overlay.unwrap_or(true) {
+        transaction
+            .execute(
+                "INSERT INTO item_overlays (item_id, strokes_json, updated_at)
+                 SELECT ?1, strokes_json, ?2
+                 FROM item_overlays
+                 WHERE item_id = ?3",
+                params![&new_item_id, now, &source_item_id],
+            )
+            .map_err(|err| format!("failed to copy item overlay: {}", err))?;
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit duplicate item transaction: {}", err))?;
+
+    query_db_item_rows(
+        &connection,
+        &format!("{} WHERE i.id = ?1 GROUP BY i.id", DB_ITEM_ROW_SELECT),
+        params![&new_item_id],
+    )?
+    .into_iter()
+    .next()
+    .ok_or_else(|| "item not found after duplicating".to_string())
+}
+
+#[tauri::command]
+pub(crate) fn merge_items(
+    keep_item_id: String,
+    merge_item_ids: Vec<String>,
+    force: Option<bool>,
+) -> Result<MergeItemsResult, String> {
+    let keep_item_id =
+        normalize_trimmed_id(&keep_item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+    let merge_item_ids: Vec<String> = normalize_item_ids_input(merge_item_ids)
+        .into_iter()
+        .filter(|item_id| *item_id != keep_item_id)
+        .collect();
+    if merge_item_ids.is_empty() {
+        return Err("no items to merge".to_string());
+    }
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    let load_merge_fields = |item_id: &str| {
+        transaction
+            .query_row(
+                "SELECT type, description, rating, is_favorite FROM items WHERE id = ?1",
+                params![item_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, i64>(3)? != 0,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(|err| format!("failed to read item for merge: {}", err))?
+            .ok_or_else(|| format!("item not found while merging: {}", item_id))
+    };
+    let (keep_type, keep_description, mut rating, mut is_favorite) =
+        load_merge_fields(&keep_item_id)?;
+    let mut descriptions: Vec<String> = keep_description
+        .map(|description| description.trim().to_string())
+        .filter(|description| !description.is_empty())
+        .into_iter()
+        .collect();
+    let mut description_merged = false;
+    for item_id in &merge_item_ids {
+        let (item_type, description, item_rating, item_is_favorite) = load_merge_fields(item_id)?;
+        if item_type != keep_type && !force.unwrap_or(false) {
+            return Err(format!(
+                "cannot merge a {} item into a {} item without force",
+                item_type, keep_type
+            ));
+        }
+        rating = rating.max(item_rating);
+        is_favorite = is_favorite || item_is_favorite;
+        if let Some(description) = description
+            .map(|description| description.trim().to_string())
+            .filter(|description| !description.is_empty())
+        {
+            if !descriptions.contains(&description) {
+                descriptions.push(description);
+                description_merged = true;
+            }
+        }
+    }
+
+    let now = Utc::now().timestamp_millis();
+    let mut tags_added = 0usize;
+    let mut collections_added = 0usize;
+    let mut notes_moved = 0usize;
+    let mut links_moved = 0usize;
+    let mut overlay_moved = false;
+    for item_id in &merge_item_ids {
+        tags_added += transaction
+            .execute(
+                "INSERT OR IGNORE INTO item_tags (item_id, tag_id)
+                 SELECT ?1, tag_id FROM item_tags WHERE item_id = ?2",
+                params![&keep_item_id, item_id],
+            )
+            .map_err(|err| format!("failed to merge item tags: {}", err))?;
+
+        let mut memberships_stmt = transaction
+            .prepare(
+                "SELECT collection_id, custom_title, custom_description, sort_index, is_pinned, created_at
+                 FROM collection_items
+                 WHERE item_id = ?1
+                 ORDER BY created_at ASC",
+            )
+            .map_err(|err| format!("failed to prepare merge membership query: {}", err))?;
+        let memberships = memberships_stmt
+            .query_map(params![item_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, i64>(5)?,
+                ))
+            })
+            .map_err(|err| format!("failed to query merge memberships: {}", err))?;
+        for membership_result in memberships {
+            let (
+                collection_id,
+                custom_title,
+                custom_description,
+                sort_index,
+                is_pinned,
+                created_at,
+            ) = membership_result
+                .map_err(|err| format!("failed to read merge membership row: {}", err))?;
+            collections_added += transaction
+                .execute(
+                    "INSERT OR IGNORE INTO collection_items (
+                        id, collection_id, item_id, custom_title, custom_description,
+                        sort_index, is_pinned, created_at
+                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        Uuid::new_v4().to_string(),
+                        collection_id,
+                        &keep_item_id,
+                        custom_title,
+                        custom_description,
+                        sort_index,
+                        is_pinned,
+                        created_at
+                    ],
+                )
+                .map_err(|err| format!("failed to merge collection membership: {}", err))?;
+        }
+        drop(memberships_stmt);
+
+        notes_moved += transaction
+            .execute(
+                "UPDATE item_notes SET item_id = ?1 WHERE item_id = ?2",
+                params![&keep_item_id, item_id],
+            )
+            .map_err(|err| format!("failed to move item notes: {}", err))?;
+
+        links_moved += transaction
+            .execute(
+                "INSERT OR IGNORE INTO item_links (from_item_id, to_item_id, link_type, created_at)
+                 SELECT ?1, to_item_id, link_type, created_at
+                 FROM item_links
+                 WHERE from_item_id = ?2 AND to_item_id <> ?1",
+                params![&keep_item_id, item_id],
+            )
+            .map_err(|err| format!("failed to move outgoing item links: {}", err))?;
+        links_moved += transaction
+            .execute(
+                "INSERT OR IGNORE INTO item_links (from_item_id, to_item_id, link_type, created_at)
+                 SELECT from_item_id, ?1, link_type, created_at
+                 FROM item_links
+                 WHERE to_item_id = ?2 AND from_item_id <> ?1",
+                params![&keep_item_id, item_id],
+            )
+            .map_err(|err| format!("failed to move incoming item links: {}", err))?;
+
+        overlay_moved |= transaction
+            .execute(
+                "INSERT OR IGNORE INTO item_overlays (item_id, strokes_json, updated_at)
+                 SELECT ?1, strokes_json, ?2
+                 FROM item_overlays
+                 WHERE item_id = ?3 AND strokes_json NOT IN ('', '[]')",
+                params![&keep_item_id, now, item_id],
+            )
+            .map_err(|err| format!("failed to move item overlay: {}", err))?
+            > 0;
+    }
+
+    let merged_description = if descriptions.is_empty() {
+        None
+    } else {
+        Some(descriptions.join("\n\n"))
+    };
+    transaction
+        .execute(
+            "UPDATE items
+             SET rating = ?1,
+                 is_favorite = ?2,
+                 description = ?3,
+                 updated_at = ?4
+             WHERE id = ?5",
+            params![
+                rating,
+                i64::from(is_favorite),
+                merged_description,
+                now,
+                &keep_item_id
+            ],
+        )
+        .map_err(|err| format!("failed to update merged item: {}", err))?;
+    refresh_item_description_hints_in_tx(&transaction, &keep_item_id)?;
+    if item_audit_enabled(&transaction)? {
+        record_item_audit_in_tx(
+            &transaction,
+            &keep_item_id,
+            "merged_from",
+            None,
+            Some(&merge_item_ids.join(", ")),
+            now,
+        )?;
+    }
+
+    let (deleted_rows, pending_cleanup) = delete_items_in_tx(&transaction, merge_item_ids.clone())?;
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit merge items transaction: {}", err))?;
+    let cleanup = run_post_delete_cleanup(pending_cleanup)?;
+
+    Ok(MergeItemsResult {
+        kept_item_id: keep_item_id,
+        merged_item_ids: merge_item_ids,
+        tags_added,
+        collections_added,
+        notes_moved,
+        links_moved,
+        overlay_moved,
+        description_merged,
+        rating,
+        is_favorite,
+        deleted: DeleteItemsResult {
+            deleted_rows,
+            cleanup,
+        },
+    })
+}
+
+pub(crate) fn find_duplicate_item_in_tx(
+    transaction: &Transaction<'_>,
+    item_id: &str,
+    vault_key: &str,
+    title: &str,
+    url: Option<&str>,
+) -> Result<Option<String>, String> {
+    if !vault_key.trim().is_empty() {
+        return transaction
+            .query_row(
+                "SELECT id FROM items
+                 WHERE vault_key = ?1 AND title = ?2 AND id <> ?3
+                 ORDER BY created_at ASC
+                 LIMIT 1",
+                params![vault_key, title, item_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(|err| format!("failed to check for duplicate item: {}", err));
+    }
+    match url.map(str::trim).filter(|value| !value.is_empty()) {
+        Some(url) => transaction
+            .query_row(
+                "SELECT id FROM items
+                 WHERE type = 'bookmark' AND url = ?1 AND id <> ?2
+                 ORDER BY created_at ASC
+                 LIMIT 1",
+                params![url, item_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(|err| format!("failed to check for duplicate bookmark: {}", err)),
+        None => Ok(None),
+    }
+}
+
+pub(crate) fn validate_item_source(
+    item_type: &str,
+    import_status: &str,
+    vault_key: &str,
+    vault_path: &str,
+    url: Option<&str>,
+) -> Result<(), String> {
+    match item_type {
+        "bookmark" => {
+            if url.map(str::trim).unwrap_or_default().is_empty() {
+                return Err("bookmark items require a url".to_string());
+            }
+            if !vault_key.is_empty() || !vault_path.is_empty() {
+                return Err("bookmark items cannot reference a vault file".to_string());
+            }
+        }
+        "note" => {}
+        _ if import_status == "ready" && (vault_key.is_empty() || vault_path.is_empty()) => {
+            return Err(format!(
+                "{} items require a vault key and path once imported",
+                item_type
+            ));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+pub(crate) fn insert_item_in_tx(
+    transaction: &Transaction<'_>,
+    item: InsertItemInput,
+) -> Result<InsertItemOutcome, String> {
+    let InsertItemInput {
+        id,
+        collection_id,
+        item_type,
+        title,
+        filename,
+        vault_key,
+        vault_path,
+        preview_url,
+        width,
+        height,
+        thumb_status,
+        import_status,
+        url,
+        favicon_path,
+        meta_status,
+        description,
+        description_format,
+        rating,
+        is_favorite,
+        created_at,
+        updated_at,
+        tags,
+        original_source_path,
+        source,
+    } = item;
+    let source_tag = source
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(system_tag_for_item_source)
+        .transpose()?;
+    let description_format = description_format
+        .as_deref()
+        .map(normalize_description_format)
+        .transpose()?
+        .unwrap_or_else(|| DESCRIPTION_FORMAT_PLAIN.to_string());
+    let (description_first_url, description_heading) = description
+        .as_deref()
+        .map(|value| extract_description_hints(value, &description_format))
+        .unwrap_or((None, None));
+    let item_type = normalize_item_type(&item_type)?;
+    let filename = normalize_optional_trimmed_string(filename).unwrap_or_default();
+    let vault_key = normalize_optional_trimmed_string(vault_key).unwrap_or_default();
+    let vault_path = normalize_optional_trimmed_string(vault_path).unwrap_or_default();
+    validate_item_source(
+        &item_type,
+        &normalize_import_status(&import_status),
+        &vault_key,
+        &vault_path,
+        url.as_deref(),
+    )?;
+    let original_source_path = original_source_path
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty());
+    let collection_id_for_membership = collection_id.clone();
+    let tag_timestamp = Utc::now().timestamp_millis();
+    let duplicate_of_item_id =
+        find_duplicate_item_in_tx(transaction, &id, &vault_key, &title, url.as_deref())?;
+
+    let inserted_rows = transaction
+        .execute(
+            "INSERT OR IGNORE INTO items (
+                id,
+                collection_id,
+                type,
+                title,
+                filename,
+                vault_key,
+                vault_path,
+                preview_url,
+                width,
+                height,
+                thumb_status,
+                import_status,
+                url,
+                favicon_path,
+                meta_status,
+                description,
+                rating,
+                is_favorite,
+                created_at,
+                updated_at,
+                original_source_path,
+                description_format,
+                description_first_url,
+                description_heading
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)",
+            params![
+                &id,
+                collection_id,
+                item_type,
+                title,
+                filename,
+                vault_key,
+                vault_path,
+                preview_url,
+                width,
+                height,
+                normalize_thumb_status(&thumb_status),
+                normalize_import_status(&import_status),
+                url,
+                favicon_path,
+                meta_status
+                    .as_deref()
+                    .map(normalize_meta_status)
+                    .unwrap_or_else(|| DEFAULT_META_STATUS.to_string()),
+                description,
+                normalize_item_rating(rating),
+                normalize_is_favorite_int(is_favorite),
+                created_at,
+                updated_at,
+                original_source_path,
+                description_format,
+                description_first_url,
+                description_heading,
+            ],
+        )
+        .map_err(|err| format!("failed to insert item row: {}", err))?;
+    if inserted_rows == 0 {
+        return Ok(InsertItemOutcome {
+            item_id: id,
+            already_existed: true,
+            duplicate_of_item_id,
+            needs_thumbnail: false,
+        });
+    }
+    let needs_thumbnail = item_type == "image"
+        && normalize_thumb_status(&thumb_status) == "pending"
+        && normalize_import_status(&import_status) == "ready"
+        && !vault_key.is_empty()
+        && thumb_output_path_for_vault_key(&vault_key)
+            .map(|path| !path.is_file())
+            .unwrap_or(false);
+
+    if let Some(collection_id) = collection_id_for_membership.as_deref() {
+        let sort_index = next_collection_item_sort_index_in_tx(transaction, collection_id)?;
+        insert_collection_membership_in_tx(
+            transaction,
+            &id,
+            collection_id,
+            sort_index,
+            created_at,
+        )?;
+    }
+
+    increment_vault_ref_in_tx(transaction, &vault_key, &vault_path)?;
+
+    transaction
+        .execute("DELETE FROM item_tags WHERE item_id = ?1", params![&id])
+        .map_err(|err| format!("failed to clear existing item tags: {}", err))?;
+
+    let mut unique_tags = BTreeSet::new();
+    for raw_tag in tags {
+        let trimmed = raw_tag.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        unique_tags.insert(trimmed.to_string());
+    }
+
+    for tag_name in unique_tags {
+        let tag_id = ensure_tag_exists_by_name_in_tx(transaction, &tag_name, tag_timestamp)?;
+        transaction
+            .execute(
+                "INSERT OR IGNORE INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+                params![&id, &tag_id],
+            )
+            .map_err(|err| format!("failed to map item tag row: {}", err))?;
+    }
+
+    if let Some(tag_name) = source_tag {
+        let tag_id = ensure_system_tag_in_tx(transaction, tag_name, tag_timestamp)?;
+        transaction
+            .execute(
+                "INSERT OR IGNORE INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+                params![&id, &tag_id],
+            )
+            .map_err(|err| format!("failed to map item source tag: {}", err))?;
+    }
+
+    Ok(InsertItemOutcome {
+        item_id: id,
+        already_existed: false,
+        duplicate_of_item_id,
+        needs_thumbnail,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn insert_item(item: InsertItemInput) -> Result<InsertItemOutcome, String> {
+    with_db_transaction(|transaction| insert_item_in_tx(transaction, item))
+}
+
+#[tauri::command]
+pub(crate) fn insert_items_batch(
+    items: Vec<InsertItemInput>,
+) -> Result<Vec<InsertItemOutcome>, String> {
+    if items.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    with_db_transaction(|transaction| {
+        items
+            .into_iter()
+            .map(|item| insert_item_in_tx(transaction, item))
+            .collect()
+    })
+}
+
+pub(crate) fn delete_items_with_cleanup_internal(
+    item_ids: Vec<String>,
+) -> Result<DeleteItemsResult, String> {
+    if item_ids.is_empty() {
+        return Ok(DeleteItemsResult {
+            deleted_rows: 0,
+            cleanup: Vec::new(),
+        });
+    }
+
+    let (deleted_rows, pending_cleanup) =
+        with_db_transaction(|transaction| delete_items_in_tx(transaction, item_ids))?;
+
+    let cleanup_entries = run_post_delete_cleanup(pending_cleanup)?;
+
+    Ok(DeleteItemsResult {
+        deleted_rows,
+        cleanup: cleanup_entries,
+    })
+}
+
+pub(crate) fn delete_items_in_tx(
+    transaction: &Transaction<'_>,
+    item_ids: Vec<String>,
+) -> Result<(usize, PendingItemDeleteCleanup), String> {
+    let mut vault_counts_by_key: HashMap<String, i64> = HashMap::new();
+    let mut vault_path_by_key: HashMap<String, String> = HashMap::new();
+    let mut favicon_paths_to_check: BTreeSet<String> = BTreeSet::new();
+    let mut preview_paths_to_check: BTreeSet<String> = BTreeSet::new();
+    let mut deleted_rows = 0usize;
+
+    for item_id in &item_ids {
+        for (vault_key, vault_path) in load_item_file_version_keys_in_tx(transaction, item_id)? {
+            *vault_counts_by_key.entry(vault_key.clone()).or_insert(0) += 1;
+            vault_path_by_key.entry(vault_key).or_insert(vault_path);
+        }
+        let maybe_item_assets = transaction
+            .query_row(
+                "SELECT vault_key, vault_path, favicon_path, preview_url FROM items WHERE id = ?1",
+                params![item_id],
+                |row| {
+                    let vault_key: String = row.get(0)?;
+                    let vault_path: String = row.get(1)?;
+                    let favicon_path: Option<String> = row.get(2)?;
+                    let preview_url: Option<String> = row.get(3)?;
+                    Ok((vault_key, vault_path, favicon_path, preview_url))
+                },
+            )
+            .optional()
+            .map_err(|err| format!("failed to read item before delete: {}", err))?;
+
+        if let Some((vault_key, vault_path, favicon_path, preview_url)) = maybe_item_assets {
+            if !vault_key.trim().is_empty() {
+                let next_count = vault_counts_by_key.entry(vault_key.clone()).or_insert(0);
+                *next_count += 1;
+                vault_path_by_key.entry(vault_key).or_insert(vault_path);
+            }
+            if let Some(path) = favicon_path {
+                let trimmed = path.trim();
+                if !trimmed.is_empty() {
+                    favicon_paths_to_check.insert(trimmed.to_string());
+                }
+            }
+            if let Some(preview_url) = preview_url.filter(|value| is_cached_preview_path(value)) {
+                preview_paths_to_check.insert(preview_url);
+            }
+        }
+    }
+
+    for item_id in item_ids {
+        let affected = transaction
+            .execute("DELETE FROM items WHERE id = ?1", params![item_id])
+            .map_err(|err| format!("failed to delete item row: {}", err))?;
+        deleted_rows += affected;
+    }
+
+    let mut zero_ref_candidates: Vec<(String, String, String, String)> = Vec::new();
+    for (vault_key, decrement_by) in vault_counts_by_key {
+        let refs_after_delete = decrement_vault_ref_in_tx(transaction, &vault_key, decrement_by)?;
+        let remaining_item_refs: i64 = transaction
+            .query_row(
+                "SELECT COUNT(*) FROM items WHERE vault_key = ?1",
+                params![&vault_key],
+                |row| row.get(0),
+            )
+            .map_err(|err| format!("failed to verify remaining item refs: {}", err))?;
+
+        if refs_after_delete == 0 && remaining_item_refs == 0 {
+            match parse_vault_key(&vault_key) {
+                Ok((sha256, ext)) => {
+                    let vault_path = vault_path_by_key
+                        .get(&vault_key)
+                        .cloned()
+                        .unwrap_or_default();
+                    zero_ref_candidates.push((vault_key, vault_path, sha256, ext));
+                }
+                Err(err) => log_eprintln!("cannot cleanup vault file after delete: {}", err),
+            }
+        }
+    }
+
+    let mut favicon_cleanup_candidates: Vec<String> = Vec::new();
+    for favicon_path in favicon_paths_to_check {
+        let remaining_item_refs: i64 = transaction
+            .query_row(
+                "SELECT COUNT(*) FROM items WHERE favicon_path = ?1",
+                params![&favicon_path],
+                |row| row.get(0),
+            )
+            .map_err(|err| format!("failed to verify remaining favicon refs: {}", err))?;
+
+        if remaining_item_refs == 0 {
+            favicon_cleanup_candidates.push(favicon_path);
+        }
+    }
+
+    let mut preview_cleanup_candidates: Vec<String> = Vec::new();
+    for preview_path in preview_paths_to_check {
+        let remaining_item_refs: i64 = transaction
+            .query_row(
+                "SELECT COUNT(*) FROM items WHERE preview_url = ?1",
+                params![&preview_path],
+                |row| row.get(0),
+            )
+            .map_err(|err| format!("failed to verify remaining preview refs: {}", err))?;
+
+        if remaining_item_refs == 0 {
+            preview_cleanup_candidates.push(preview_path);
+        }
+    }
+
+    Ok((
+        deleted_rows,
+        PendingItemDeleteCleanup {
+            zero_ref_candidates,
+            favicon_cleanup_candidates,
+            preview_cleanup_candidates,
+        },
+    ))
+}
+
+pub(crate) fn run_post_delete_cleanup(
+    pending: PendingItemDeleteCleanup,
+) -> Result<Vec<VaultCleanupEntry>, String> {
+    let PendingItemDeleteCleanup {
+        zero_ref_candidates,
+        favicon_cleanup_candidates,
+        preview_cleanup_candidates,
+    } = pending;
+    if zero_ref_candidates.is_empty()
+        && favicon_cleanup_candidates.is_empty()
+        && preview_cleanup_candidates.is_empty()
+    {
+        return Ok(Vec::new());
+    }
+
+    let storage_root = ensure_storage_root_internal()?;
+    let mut rows_to_prune: Vec<String> = Vec::new();
+    let mut cleanup_entries = Vec::new();
+    let protected_keys = {
+        let connection = open_db_connection()?;
+        load_protected_vault_keys(&connection)?
+    };
+
+    for (vault_key, vault_path, sha256, ext) in zero_ref_candidates {
+        if protected_keys.contains(&vault_key) {
+            log_println!("[vault-cleanup] keeping protected vault file {}", vault_key);
+            cleanup_entries.push(VaultCleanupEntry {
+                vault_key,
+                vault_path,
+                sha256,
+                ext,
+                deleted_from_disk: false,
+                is_protected: true,
+            });
+            continue;
+        }
+        if vault_key_in_use(&vault_key) {
+            DEFERRED_VAULT_CLEANUPS.fetch_add(1, Ordering::Relaxed);
+            cleanup_entries.push(VaultCleanupEntry {
+                vault_key,
+                vault_path,
+                sha256,
+                ext,
+                deleted_from_disk: false,
+                is_protected: false,
+            });
+            continue;
+        }
+        let vault_filename = build_vault_filename(&sha256, &ext);
+        let existing_paths = find_vault_files(&storage_root, &vault_filename)
+            .map_err(|err| format!("failed to locate vault cleanup targets: {}", err))?;
+
+        let mut deleted_from_disk = false;
+        let mut cleanup_ok = true;
+        let mut cleanup_deferred = false;
+        for path in existing_paths {
+            match remove_cleanup_file(&path) {
+                Ok(true) => deleted_from_disk = true,
+                Ok(false) => cleanup_deferred = true,
+                Err(err) => {
+                    cleanup_ok = false;
+                    log_eprintln!("failed to remove vault file: {}", err);
+                }
+            }
+        }
+
+        match remove_thumbnail_for_vault_key(&vault_key) {
+            Ok(true) => {}
+            Ok(false) => cleanup_deferred = true,
+            Err(err) => {
+                cleanup_ok = false;
+                log_eprintln!(
+                    "failed to remove thumbnail for vault key {}: {}",
+                    vault_key,
+                    err
+                );
+            }
+        }
+
+        if cleanup_deferred {
+            DEFERRED_VAULT_CLEANUPS.fetch_add(1, Ordering::Relaxed);
+        } else if cleanup_ok {
+            rows_to_prune.push(vault_key.clone());
+        }
+
+        cleanup_entries.push(VaultCleanupEntry {
+            vault_key,
+            vault_path,
+            sha256,
+            ext,
+            deleted_from_disk,
+            is_protected: false,
+        });
+    }
+
+    for favicon_path in favicon_cleanup_candidates {
+        if let Err(err) = remove_favicon_file(&favicon_path) {
+            log_eprintln!("failed to remove favicon {}: {}", favicon_path, err);
+        }
+    }
+
+    for preview_path in preview_cleanup_candidates {
+        if let Err(err) = remove_preview_file(&preview_path) {
+            log_eprintln!("failed to remove preview {}: {}", preview_path, err);
+        }
+    }
+
+    if !rows_to_prune.is_empty() {
+        let mut prune_connection = open_db_connection()?;
+        let prune_tx = prune_connection
+            .transaction()
+            .map_err(|err| format!("failed to start vault prune transaction: {}", err))?;
+        for vault_key in rows_to_prune {
+            prune_tx
+                .execute(
+                    "DELETE FROM vault_files WHERE vault_key = ?1",
+                    params![vault_key],
+                )
+                .map_err(|err| format!("failed to prune vault row: {}", err))?;
+        }
+        prune_tx
+            .commit()
+            .map_err(|err| format!("failed to commit vault prune transaction: {}", err))?;
+    }
+
+    Ok(cleanup_entries)
+}
+
+pub(crate) fn query_delete_filter_item_ids(
+    connection: &Connection,
+    filter: DeleteItemsFilter,
+) -> Result<Vec<String>, String> {
+    let mut conditions: Vec<String> = Vec::new();
+    let mut values: Vec<rusqlite::types::Value> = Vec::new();
+    let mut cte = String::new();
+    if let Some(collection_id) = normalize_optional_trimmed_id(filter.collection_id) {
+        let placeholder = push_sql_param(&mut values, collection_id.into());
+        cte = format!(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT {}
+                UNION
+                SELECT c.id FROM collections AS c JOIN subtree AS s ON c.parent_id = s.id
+             ) ",
+            placeholder
+        );
+        conditions.push(
+            "i.id IN (SELECT ci.item_id FROM collection_items AS ci JOIN subtree AS s ON s.id = ci.collection_id)"
+                .to_string(),
+        );
+    }
+
+    let tag_ids = normalize_item_ids_input(filter.tag_ids);
+    if !tag_ids.is_empty() {
+        let tag_count = tag_ids.len() as i64;
+        let placeholders = tag_ids
+            .into_iter()
+            .map(|tag_id| push_sql_param(&mut values, tag_id.into()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let count_placeholder = push_sql_param(&mut values, tag_count.into());
+        conditions.push(format!(
+            "i.id IN (SELECT item_id FROM item_tags WHERE tag_id IN ({}) GROUP BY item_id HAVING COUNT(DISTINCT tag_id) = {})",
+            placeholders, count_placeholder
+        ));
+    }
+
+    if let Some(condition) = item_sources_condition(&mut values, &filter.sources)? {
+        conditions.push(condition);
+    }
+
+    if let Some(item_type) = normalize_optional_trimmed_string(filter.item_type) {
+        let placeholder = push_sql_param(&mut values, normalize_item_type(&item_type)?.into());
+        conditions.push(format!("i.type = {}", placeholder));
+    }
+    if let Some(min_rating) = filter.min_rating {
+        let placeholder = push_sql_param(&mut values, normalize_item_rating(min_rating).into());
+        conditions.push(format!("i.rating >= {}", placeholder));
+    }
+    if let Some(max_rating) = filter.max_rating {
+        let placeholder = push_sql_param(&mut values, normalize_item_rating(max_rating).into());
+        conditions.push(format!("i.rating <= {}", placeholder));
+    }
+    if let Some(created_before) = filter.created_before {
+        let placeholder = push_sql_param(&mut values, created_before.into());
+        conditions.push(format!("i.created_at < {}", placeholder));
+    }
+    if let Some(is_favorite) = filter.is_favorite {
+        let placeholder =
+            push_sql_param(&mut values, normalize_is_favorite_int(is_favorite).into());
+        conditions.push(format!("i.is_favorite = {}", placeholder));
+    }
+    if conditions.is_empty() {
+        return Err("delete filter must specify at least one condition".to_string());
+    }
+    if !filter.include_archived {
+        conditions.push("i.is_archived = 0".to_string());
+    }
+
+    let sql = format!(
+        "{}SELECT i.id FROM items AS i WHERE {} ORDER BY i.id ASC",
+        cte,
+        conditions.join(" AND ")
+    );
+    let mut stmt = connection
+        .prepare(&sql)
+        .map_err(|err| format!("failed to prepare delete filter query: {}", err))?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(values.iter()), |row| {
+            row.get::<_, String>(0)
+        })
+        .map_err(|err| format!("failed to query delete filter items: {}", err))?;
+    let mut item_ids = Vec::new();
+    for row_result in rows {
+        item_ids
+            .push(row_result.map_err(|err| format!("failed to read delete filter item: {}", err))?);
+    }
+    Ok(item_ids)
+}
+
+pub(crate) fn delete_filter_confirm_token(item_ids: &[String]) -> String {
+    format!(
+        "{}:{}",
+        item_ids.len(),
+        sha256_for_bytes(item_ids.join("\n").as_bytes())
+    )
+}
+
+#[tauri::command]
+pub(crate) fn preview_delete_by_filter(
+    filter: DeleteItemsFilter,
+) -> Result<DeleteByFilterPreview, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let item_ids = query_delete_filter_item_ids(&connection, filter)?;
+    let confirm_token = delete_filter_confirm_token(&item_ids);
+
+    let mut matched_refs_by_key: HashMap<String, i64> = HashMap::new();
+    let mut sample_titles = Vec::new();
+    for chunk in item_ids.chunks(500) {
+        let placeholders = (1..=chunk.len())
+            .map(|index| format!("?{}", index))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut stmt = connection
+            .prepare(&format!(
+                "SELECT vault_key, title FROM items WHERE id IN ({}) ORDER BY created_at DESC",
+                placeholders
+            ))
+            .map_err(|err| format!("failed to prepare delete preview query: {}", err))?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(chunk.iter()), |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|err| format!("failed to query delete preview items: {}", err))?;
+        for row_result in rows {
+            let (vault_key, title) =
+                row_result.map_err(|err| format!("failed to read delete preview item: {}", err))?;
+            if !vault_key.trim().is_empty() {
+                *matched_refs_by_key.entry(vault_key).or_insert(0) += 1;
+            }
+            if sample_titles.len() < DELETE_BY_FILTER_SAMPLE_SIZE {
+                sample_titles.push(title);
+            }
+        }
+    }
+
+    let mut reclaimable_bytes = 0_i64;
+    for (vault_key, matched_refs) in matched_refs_by_key {
+        let (total_refs, size_bytes): (i64, Option<i64>) = connection
+            .query_row(
+                "SELECT
+                    (SELECT COUNT(*) FROM items WHERE vault_key = ?1),
+                    (SELECT size_bytes FROM vault_files WHERE vault_key = ?1)",
+                params![&vault_key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|err| format!("failed to read vault usage for delete preview: {}", err))?;
+        if total_refs <= matched_refs {
+            reclaimable_bytes += size_bytes.unwrap_or(0);
+        }
+    }
+
+    Ok(DeleteByFilterPreview {
+        count: item_ids.len(),
+        reclaimable_bytes,
+        sample_titles,
+        confirm_token,
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn delete_by_filter(
+    app: AppHandle,
+    filter: DeleteItemsFilter,
+    confirm_token: String,
+) -> Result<DeleteItemsResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        initialize_db()?;
+        let connection = open_db_connection()?;
+        let item_ids = query_delete_filter_item_ids(&connection, filter)?;
+        drop(connection);
+        if delete_filter_confirm_token(&item_ids) != confirm_token.trim() {
+            return Err(format!(
+                "conflict: the filter now matches {} items, which differs from the preview; preview again before deleting",
+                item_ids.len()
+            ));
+        }
+
+        let total = item_ids.len();
+        let mut result = DeleteItemsResult {
+            deleted_rows: 0,
+            cleanup: Vec::new(),
+        };
+        for chunk in item_ids.chunks(DELETE_BY_FILTER_CHUNK_SIZE) {
+            let chunk_result = delete_items_with_cleanup_internal(chunk.to_vec())?;
+            result.deleted_rows += chunk_result.deleted_rows;
+            result.cleanup.extend(chunk_result.cleanup);
+            let _ = app.emit(
+                DELETE_BY_FILTER_PROGRESS_EVENT,
+                DeleteByFilterProgressPayload {
+                    deleted: result.deleted_rows,
+                    total,
+                },
+            );
+        }
+        Ok(result)
+    })
+    .await
+    .map_err(|err| format!("delete by filter thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn delete_items_with_cleanup(
+    item_ids: Vec<String>,
+) -> Result<DeleteItemsResult, String> {
+    delete_items_with_cleanup_internal(item_ids)
+}
+
+#[tauri::command]
+pub(crate) fn delete_items(item_ids: Vec<String>) -> Result<usize, String> {
+    let result = delete_items_with_cleanup_internal(item_ids)?;
+    Ok(result.deleted_rows)
+}
+
+pub(crate) fn normalize_trimmed_id(value: &str) -> Option<String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+pub(crate) fn normalize_optional_trimmed_id(value: Option<String>) -> Option<String> {
+    value.and_then(|entry| normalize_trimmed_id(&entry))
+}
+
+pub(crate) fn normalize_item_ids_input(item_ids: Vec<String>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    let mut normalized = Vec::new();
+    for item_id in item_ids {
+        if let Some(trimmed) = normalize_trimmed_id(&item_id) {
+            if seen.insert(trimmed.clone()) {
+                normalized.push(trimmed);
+            }
+        }
+    }
+    normalized
+}
+
+pub(crate) fn resolve_source_membership_for_move_in_tx(
+    transaction: &Transaction<'_>,
+    item_id: &str,
+    source_collection_id: Option<&str>,
+) -> Result<Option<(String, String)>, String> {
+    if let Some(source_collection_id) = source_collection_id {
+        return transaction
+            .query_row(
+                "SELECT id, collection_id
+                 FROM collection_items
+                 WHERE item_id = ?1 AND collection_id = ?2
+                 LIMIT 1",
+                params![item_id, source_collection_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()
+            .map_err(|err| format!("failed to resolve explicit source membership: {}", err));
+    }
+
+    transaction
+        .query_row(
+            "SELECT ci.id, ci.collection_id
+             FROM collection_items AS ci
+             LEFT JOIN items AS i ON i.id = ci.item_id
+             WHERE ci.item_id = ?1
+             ORDER BY
+               CASE
+                 WHEN i.collection_id IS NOT NULL AND ci.collection_id = i.collection_id THEN 0
+                 ELSE 1
+               END,
+               ci.sort_index ASC,
+               ci.created_at ASC,
+               ci.id ASC
+             LIMIT 1",
+            params![item_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()
+        .map_err(|err| format!("failed to resolve fallback source membership: {}", err))
+}
+
+#[tauri::command]
+pub(crate) fn set_items_archived(
+    item_ids: Vec<String>,
+    archived: bool,
+) -> Result<UpdateItemsCollectionResult, String> {
+    let normalized_item_ids = normalize_item_ids_input(item_ids);
+    let updated_at = Utc::now().timestamp_millis();
+    if normalized_item_ids.is_empty() {
+        return Ok(UpdateItemsCollectionResult {
+            updated_rows: 0,
+            updated_at,
+        });
+    }
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    let mut updated_rows = 0usize;
+    for item_id in &normalized_item_ids {
+        updated_rows += transaction
+            .execute(
+                "UPDATE items
+                 SET is_archived = ?1,
+                     updated_at = ?2
+                 WHERE id = ?3",
+                params![i64::from(archived), updated_at, item_id],
+            )
+            .map_err(|err| format!("failed to update item archive flag: {}", err))?;
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit sqlite transaction: {}", err))?;
+
+    Ok(UpdateItemsCollectionResult {
+        updated_rows,
+        updated_at,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn set_items_sensitive(
+    item_ids: Vec<String>,
+    sensitive: bool,
+) -> Result<UpdateItemsCollectionResult, String> {
+    let normalized_item_ids = normalize_item_ids_input(item_ids);
+    let updated_at = Utc::now().timestamp_millis();
+    if normalized_item_ids.is_empty() {
+        return Ok(UpdateItemsCollectionResult {
+            updated_rows: 0,
+            updated_at,
+        });
+    }
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    let mut updated_rows = 0usize;
+    for item_id in &normalized_item_ids {
+        updated_rows += transaction
+            .execute(
+                "UPDATE items
+                 SET is_sensitive = ?1,
+                     updated_at = ?2
+                 WHERE id = ?3",
+                params![i64::from(sensitive), updated_at, item_id],
+            )
+            .map_err(|err| format!("failed to update item sensitive flag: {}", err))?;
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit sqlite transaction: {}", err))?;
+
+    Ok(UpdateItemsCollectionResult {
+        updated_rows,
+        updated_at,
+    })
+}
+
+pub(crate) fn load_item_memberships_in_tx(
+    transaction: &Transaction<'_>,
+    item_id: &str,
+) -> Result<(BTreeSet<String>, Option<String>), String> {
+    let mut stmt = transaction
+        .prepare("SELECT collection_id FROM collection_items WHERE item_id = ?1")
+        .map_err(|err| format!("failed to prepare item membership query: {}", err))?;
+    let rows = stmt
+        .query_map(params![item_id], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to query item memberships: {}", err))?;
+    let mut collection_ids = BTreeSet::new();
+    for row_result in rows {
+        collection_ids
+            .insert(row_result.map_err(|err| format!("failed to read item membership: {}", err))?);
+    }
+    let primary_collection_id = transaction
+        .query_row(
+            "SELECT collection_id FROM items WHERE id = ?1",
+            params![item_id],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to read item primary collection: {}", err))?
+        .ok_or_else(|| format!("item not found: {}", item_id))?;
+    Ok((collection_ids, primary_collection_id))
+}
+
+pub(crate) fn normalize_description_format(value: &str) -> Result<String, String> {
+    let normalized = value.trim().to_ascii_lowercase();
+    match normalized.as_str() {
+        DESCRIPTION_FORMAT_PLAIN | DESCRIPTION_FORMAT_MARKDOWN => Ok(normalized),
+        _ => Err(format!("unsupported description format: {}", value.trim())),
+    }
+}
+
+pub(crate) fn extract_description_first_url(text: &str) -> Option<String> {
+    text.split(|ch: char| ch.is_whitespace() || matches!(ch, '(' | '<' | '[' | '"' | '\''))
+        .find_map(|token| {
+            let start = token.find("https://").or_else(|| token.find("http://"))?;
+            let url = token[start..].trim_end_matches(|ch: char| {
+                matches!(
+                    ch,
+                    ')' | '>' | ']' | '.' | ',' | ';' | ':' | '!' | '?' | '"' | '\'' | '*' | '_'
+                )
+            });
+            if url.len() > "https://".len() {
+                Some(url.to_string())
+            } else {
+                None
+            }
+        })
+}
+
+pub(crate) fn markdown_heading_text(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|ch| *ch == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let heading = rest.trim().trim_end_matches('#').trim();
+    if heading.is_empty() {
+        None
+    } else {
+        Some(heading)
+    }
+}
+
+pub(crate) fn extract_description_hints(
+    description: &str,
+    format: &str,
+) -> (Option<String>, Option<String>) {
+    let first_url = extract_description_first_url(description);
+    let heading = if format == DESCRIPTION_FORMAT_MARKDOWN {
+        description
+            .lines()
+            .find_map(markdown_heading_text)
+            .map(strip_markdown_inline)
+            .filter(|value| !value.is_empty())
+    } else {
+        None
+    };
+    (first_url, heading)
+}
+
+pub(crate) fn strip_markdown_inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut index = 0;
+    while index < chars.len() {
+        let ch = chars[index];
+        let is_image = ch == '!' && chars.get(index + 1) == Some(&'[');
+        if ch == '[' || is_image {
+            let label_start = if is_image { index + 2 } else { index + 1 };
+            if let Some(label_len) = chars[label_start..].iter().position(|value| *value == ']') {
+                let label_end = label_start + label_len;
+                if chars.get(label_end + 1) == Some(&'(') {
+                    if let Some(target_len) = chars[label_end + 2..]
+                        .iter()
+                        .position(|value| *value == ')')
+                    {
+                        output.extend(&chars[label_start..label_end]);
+                        index = label_end + 2 + target_len + 1;
+                        continue;
+                    }
+                }
+            }
+        }
+        if !matches!(ch, '*' | '_' | '`' | '~') {
+            output.push(ch);
+        }
+        index += 1;
+    }
+    output.trim().to_string()
+}
+
+pub(crate) fn strip_markdown_line(line: &str) -> String {
+    if let Some(heading) = markdown_heading_text(line) {
+        return strip_markdown_inline(heading);
+    }
+    let mut content = line.trim_start();
+    while let Some(rest) = content.strip_prefix('>') {
+        content = rest.trim_start();
+    }
+    for marker in ["- [ ] ", "- [x] ", "- [X] ", "- ", "* ", "+ "] {
+        if let Some(rest) = content.strip_prefix(marker) {
+            content = rest;
+            break;
+        }
+    }
+    let digits = content.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 {
+        if let Some(rest) = content[digits..].strip_prefix(". ") {
+            content = rest;
+        }
+    }
+    if content.starts_with("```")
+        || content
+            .chars()
+            .all(|ch| matches!(ch, '-' | '*' | '_' | '=' | ' '))
+    {
+        return String::new();
+    }
+    strip_markdown_inline(content)
+}
+
+pub(crate) fn description_preview(description: &str, format: &str) -> Option<String> {
+    let flattened = if format == DESCRIPTION_FORMAT_MARKDOWN {
+        description
+            .lines()
+            .map(strip_markdown_line)
+            .collect::<Vec<_>>()
+            .join(" ")
+    } else {
+        description.to_string()
+    };
+    let collapsed = flattened.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.is_empty() {
+        return None;
+    }
+    Some(
+        collapsed
+            .chars()
+            .take(DESCRIPTION_PREVIEW_MAX_CHARS)
+            .collect(),
+    )
+}
+
+pub(crate) fn refresh_item_description_hints_in_tx(
+    transaction: &Transaction<'_>,
+    item_id: &str,
+) -> Result<(), String> {
+    let (description, format) = transaction
+        .query_row(
+            "SELECT description, description_format FROM items WHERE id = ?1",
+            params![item_id],
+            |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, String>(1)?)),
+        )
+        .map_err(|err| format!("failed to read item description: {}", err))?;
+    let format = normalize_description_format(&format)
+        .unwrap_or_else(|_| DESCRIPTION_FORMAT_PLAIN.to_string());
+    let (first_url, heading) = description
+        .as_deref()
+        .map(|value| extract_description_hints(value, &format))
+        .unwrap_or((None, None));
+    transaction
+        .execute(
+            "UPDATE items SET description_first_url = ?1, description_heading = ?2 WHERE id = ?3",
+            params![first_url, heading, item_id],
+        )
+        .map_err(|err| format!("failed to update item description hints: {}", err))?;
+    Ok(())
+}
+
+pub(crate) fn update_item_description_internal(
+    item_id: &str,
+    description: &str,
+    format: &str,
+) -> Result<i64, String> {
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    let updated_at = Utc::now().timestamp_millis();
+    let previous_description = if item_audit_enabled(&transaction)? {
+        transaction
+            .query_row(
+                "SELECT description FROM items WHERE id = ?1",
+                params![item_id],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()
+            .map_err(|err| format!("failed to read item description: {}", err))?
+    } else {
+        None
+    };
+    let (first_url, heading) = extract_description_hints(description, format);
+    let affected_rows = transaction
+        .execute(
+            "UPDATE items
+             SET description = ?1,
+                 description_format = ?2,
+                 description_first_url = ?3,
+                 description_heading = ?4,
+                 updated_at = ?5
+             WHERE id = ?6",
+            params![description, format, first_url, heading, updated_at, item_id],
+        )
+        .map_err(|err| format!("failed to update item description: {}", err))?;
+
+    if affected_rows == 0 {
+        return Err("item not found while updating description".to_string());
+    }
+    if let Some(previous_description) = previous_description {
+        record_item_audit_in_tx(
+            &transaction,
+            item_id,
+            "description",
+            previous_description.as_deref(),
+            Some(description),
+            updated_at,
+        )?;
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit update description transaction: {}", err))?;
+    Ok(updated_at)
+}
+
+#[tauri::command]
+pub(crate) fn update_item_description(item_id: String, description: String) -> Result<i64, String> {
+    update_item_description_internal(&item_id, &description, DESCRIPTION_FORMAT_PLAIN)
+}
+
+#[tauri::command]
+pub(crate) fn update_item_description_v2(
+    item_id: String,
+    description: String,
+    format: String,
+) -> Result<i64, String> {
+    let format = normalize_description_format(&format)?;
+    update_item_description_internal(&item_id, &description, &format)
+}
+
+pub(crate) fn normalize_item_link_input(
+    from_item_id: &str,
+    to_item_id: &str,
+    link_type: Option<String>,
+) -> Result<(String, String, String), String> {
+    let from_id = normalize_trimmed_id(from_item_id)
+        .ok_or_else(|| "source item id cannot be empty".to_string())?;
+    let to_id = normalize_trimmed_id(to_item_id)
+        .ok_or_else(|| "target item id cannot be empty".to_string())?;
+    if from_id == to_id {
+        return Err("an item cannot be linked to itself".to_string());
+    }
+    let normalized_link_type = normalize_optional_trimmed_string(link_type)
+        .map(|value| value.to_lowercase())
+        .unwrap_or_else(|| DEFAULT_ITEM_LINK_TYPE.to_string());
+    Ok((from_id, to_id, normalized_link_type))
+}
+
+#[tauri::command]
+pub(crate) fn link_items(
+    from_item_id: String,
+    to_item_id: String,
+    link_type: Option<String>,
+) -> Result<i64, String> {
+    let (from_id, to_id, normalized_link_type) =
+        normalize_item_link_input(&from_item_id, &to_item_id, link_type)?;
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    for item_id in [&from_id, &to_id] {
+        let exists = transaction
+            .query_row(
+                "SELECT 1 FROM items WHERE id = ?1",
+                params![item_id],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()
+            .map_err(|err| format!("failed to verify item before linking: {}", err))?;
+        if exists.is_none() {
+            return Err(format!("item not found while linking: {}", item_id));
+        }
+    }
+
+    let existing = transaction
+        .query_row(
+            "SELECT 1 FROM item_links
+             WHERE link_type = ?3
+               AND ((from_item_id = ?1 AND to_item_id = ?2) OR (from_item_id = ?2 AND to_item_id = ?1))",
+            params![&from_id, &to_id, &normalized_link_type],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to check existing item link: {}", err))?;
+    if existing.is_some() {
+        return Err("these items are already linked with this link type".to_string());
+    }
+
+    let created_at = Utc::now().timestamp_millis();
+    transaction
+        .execute(
+            "INSERT INTO item_links (from_item_id, to_item_id, link_type, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![&from_id, &to_id, &normalized_link_type, created_at],
+        )
+        .map_err(|err| format!("failed to insert item link: {}", err))?;
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit sqlite transaction: {}", err))?;
+    Ok(created_at)
+}
+
+#[tauri::command]
+pub(crate) fn unlink_items(
+    from_item_id: String,
+    to_item_id: String,
+    link_type: Option<String>,
+) -> Result<usize, String> {
+    let (from_id, to_id, normalized_link_type) =
+        normalize_item_link_input(&from_item_id, &to_item_id, link_type)?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    connection
+        .execute(
+            "DELETE FROM item_links
+             WHERE link_type = ?3
+               AND ((from_item_id = ?1 AND to_item_id = ?2) OR (from_item_id = ?2 AND to_item_id = ?1))",
+            params![&from_id, &to_id, &normalized_link_type],
+        )
+        .map_err(|err| format!("failed to delete item link: {}", err))
+}
+
+#[tauri::command]
+pub(crate) fn get_linked_items(item_id: String) -> Result<Vec<LinkedItem>, String> {
+    let normalized_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let mut stmt = connection
+        .prepare(
+            "SELECT to_item_id, link_type, 'outgoing', created_at
+             FROM item_links
+             WHERE from_item_id = ?1
+             UNION ALL
+             SELECT from_item_id, link_type, 'incoming', created_at
+             FROM item_links
+             WHERE to_item_id = ?1
+             ORDER BY 4 DESC",
+        )
+        .map_err(|err| format!("failed to prepare linked items query: {}", err))?;
+    let rows = stmt
+        .query_map(params![&normalized_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })
+        .map_err(|err| format!("failed to query linked items: {}", err))?;
+
+    let mut links = Vec::new();
+    for row_result in rows {
+        links.push(row_result.map_err(|err| format!("failed to read item link row: {}", err))?);
+    }
+
+    let linked_ids = normalize_item_ids_input(links.iter().map(|link| link.0.clone()).collect());
+    let rows_by_id: HashMap<String, DbItemRow> = load_item_rows_in_order(&connection, &linked_ids)?
+        .into_iter()
+        .map(|row| (row.id.clone(), row))
+        .collect();
+
+    let mut linked_items = Vec::new();
+    for (linked_id, link_type, direction, created_at) in links {
+        let Some(item) = rows_by_id.get(&linked_id).cloned() else {
+            continue;
+        };
+        linked_items.push(LinkedItem {
+            item,
+            link_type,
+            direction,
+            created_at,
+        });
+    }
+    Ok(linked_items)
+}
+
+pub(crate) fn normalize_item_note_body(body: &str) -> Result<String, String> {
+    let trimmed = body.trim();
+    if trimmed.is_empty() {
+        return Err("note body cannot be empty".to_string());
+    }
+    Ok(trimmed.to_string())
+}
+
+pub(crate) fn item_note_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ItemNote> {
+    Ok(ItemNote {
+        id: row.get(0)?,
+        item_id: row.get(1)?,
+        body: row.get(2)?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn add_item_note(item_id: String, body: String) -> Result<ItemNote, String> {
+    let normalized_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+    let normalized_body = normalize_item_note_body(&body)?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let item_exists = connection
+        .query_row(
+            "SELECT 1 FROM items WHERE id = ?1",
+            params![&normalized_item_id],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to verify item before adding note: {}", err))?;
+    if item_exists.is_none() {
+        return Err("item not found while adding note".to_string());
+    }
+
+    let now = Utc::now().timestamp_millis();
+    let note = ItemNote {
+        id: Uuid::new_v4().to_string(),
+        item_id: normalized_item_id,
+        body: normalized_body,
+        created_at: now,
+        updated_at: now,
+    };
+    connection
+        .execute(
+            "INSERT INTO item_notes (id, item_id, body, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?4)",
+            params![&note.id, &note.item_id, &note.body, now],
+        )
+        .map_err(|err| format!("failed to insert item note: {}", err))?;
+    Ok(note)
+}
+
+#[tauri::command]
+pub(crate) fn update_item_note(note_id: String, body: String) -> Result<ItemNote, String> {
+    let normalized_note_id =
+        normalize_trimmed_id(&note_id).ok_or_else(|| "note id cannot be empty".to_string())?;
+    let normalized_body = normalize_item_note_body(&body)?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let updated_at = Utc::now().timestamp_millis();
+    let affected_rows = connection
+        .execute(
+            "UPDATE item_notes
+             SET body = ?1,
+                 updated_at = ?2
+             WHERE id = ?3",
+            params![&normalized_body, updated_at, &normalized_note_id],
+        )
+        .map_err(|err| format!("failed to update item note: {}", err))?;
+    if affected_rows == 0 {
+        return Err("note not found while updating".to_string());
+    }
+
+    connection
+        .query_row(
+            "SELECT id, item_id, body, created_at, updated_at FROM item_notes WHERE id = ?1",
+            params![&normalized_note_id],
+            item_note_from_row,
+        )
+        .map_err(|err| format!("failed to read updated item note: {}", err))
+}
+
+#[tauri::command]
+pub(crate) fn delete_item_note(note_id: String) -> Result<usize, String> {
+    let normalized_note_id =
+        normalize_trimmed_id(&note_id).ok_or_else(|| "note id cannot be empty".to_string())?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    connection
+        .execute(
+            "DELETE FROM item_notes WHERE id = ?1",
+            params![&normalized_note_id],
+        )
+        .map_err(|err| format!("failed to delete item note: {}", err))
+}
+
+#[tauri::command]
+pub(crate) fn list_item_notes(item_id: String) -> Result<Vec<ItemNote>, String> {
+    let normalized_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let mut stmt = connection
+        .prepare(
+            "SELECT id, item_id, body, created_at, updated_at
+             FROM item_notes
+             WHERE item_id = ?1
+             ORDER BY created_at DESC, id DESC",
+        )
+        .map_err(|err| format!("failed to prepare item notes query: {}", err))?;
+    let rows = stmt
+        .query_map(params![&normalized_item_id], item_note_from_row)
+        .map_err(|err| format!("failed to query item notes: {}", err))?;
+
+    let mut notes = Vec::new();
+    for row_result in rows {
+        notes.push(row_result.map_err(|err| format!("failed to read item note row: {}", err))?);
+    }
+    Ok(notes)
+}
+
+#[tauri::command]
+pub(crate) fn load_item_overlay(item_id: String) -> Result<Option<serde_json::Value>, String> {
+    let normalized_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+
+    let strokes_json = connection
+        .query_row(
+            "SELECT strokes_json FROM item_overlays WHERE item_id = ?1",
+            params![normalized_item_id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to load item overlay: {}", err))?;
+
+    let Some(strokes_json) = strokes_json else {
+        return Ok(None);
+    };
+
+    let parsed = serde_json::from_str::<serde_json::Value>(&strokes_json)
+        .map_err(|err| format!("failed to parse stored item overlay JSON: {}", err))?;
+    Ok(Some(parsed))
+}
+
+#[tauri::command]
+pub(crate) fn save_item_overlay(
+    item_id: String,
+    strokes: serde_json::Value,
+) -> Result<i64, String> {
+    let normalized_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+    if !strokes.is_array() {
+        return Err("overlay strokes payload must be an array".to_string());
+    }
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let updated_at = Utc::now().timestamp_millis();
+    let strokes_json = serde_json::to_string(&strokes)
+        .map_err(|err| format!("failed to serialize item overlay JSON: {}", err))?;
+
+    let affected_rows = connection
+        .execute(
+            "INSERT INTO item_overlays (item_id, strokes_json, updated_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(item_id) DO UPDATE SET
+               strokes_json = excluded.strokes_json,
+               updated_at = excluded.updated_at",
+            params![normalized_item_id, strokes_json, updated_at],
+        )
+        .map_err(|err| format!("failed to save item overlay: {}", err))?;
+
+    if affected_rows == 0 {
+        return Err("failed to save item overlay".to_string());
+    }
+
+    Ok(updated_at)
+}
+
+#[tauri::command]
+pub(crate) fn update_item_preferences(input: UpdateItemPreferencesInput) -> Result<i64, String> {
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    let updated_at = Utc::now().timestamp_millis();
+
+    let normalized_rating = input.rating.map(normalize_item_rating);
+    let normalized_is_favorite = input.is_favorite.map(normalize_is_favorite_int);
+    if normalized_rating.is_none() && normalized_is_favorite.is_none() {
+        return Err("no item preference fields provided".to_string());
+    }
+
+    let previous_preferences = if item_audit_enabled(&transaction)? {
+        transaction
+            .query_row(
+                "SELECT rating, is_favorite FROM items WHERE id = ?1",
+                params![&input.item_id],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+            )
+            .optional()
+            .map_err(|err| format!("failed to read item preferences: {}", err))?
+    } else {
+        None
+    };
+
+    let affected_rows = transaction
+        .execute(
+            "UPDATE items
+             SET rating = COALESCE(?1, rating),
+                 is_favorite = COALESCE(?2, is_favorite),
+                 updated_at = ?3
+             WHERE id = ?4",
+            params![
+                normalized_rating,
+                normalized_is_favorite,
+                updated_at,
+                input.item_id
+            ],
+        )
+        .map_err(|err| format!("failed to update item preferences: {}", err))?;
+
+    if affected_rows == 0 {
+        return Err("item not found while updating preferences".to_string());
+    }
+    if let Some((previous_rating, previous_is_favorite)) = previous_preferences {
+        if let Some(rating) = normalized_rating {
+            record_item_audit_in_tx(
+                &transaction,
+                &input.item_id,
+                "rating",
+                Some(&previous_rating.to_string()),
+                Some(&rating.to_string()),
+                updated_at,
+            )?;
+        }
+        if let Some(is_favorite) = normalized_is_favorite {
+            record_item_audit_in_tx(
+                &transaction,
+                &input.item_id,
+                "is_favorite",
+                Some(&previous_is_favorite.to_string()),
+                Some(&is_favorite.to_string()),
+                updated_at,
+            )?;
+        }
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit update preferences transaction: {}", err))?;
+    Ok(updated_at)
+}
+
+#[tauri::command]
+pub(crate) fn get_item_history(
+    item_id: String,
+    limit: Option<usize>,
+) -> Result<Vec<ItemAuditEntry>, String> {
+    let normalized_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+    let bounded_limit = limit
+        .unwrap_or(ITEM_HISTORY_DEFAULT_LIMIT)
+        .clamp(1, ITEM_AUDIT_MAX_ENTRIES_PER_ITEM as usize);
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let mut stmt = connection
+        .prepare(
+            "SELECT id, item_id, field, old_value, new_value, changed_at
+             FROM item_audit_log
+             WHERE item_id = ?1
+             ORDER BY id DESC
+             LIMIT ?2",
+        )
+        .map_err(|err| format!("failed to prepare item history query: {}", err))?;
+    let rows = stmt
+        .query_map(params![normalized_item_id, bounded_limit as i64], |row| {
+            Ok(ItemAuditEntry {
+                id: row.get(0)?,
+                item_id: row.get(1)?,
+                field: row.get(2)?,
+                old_value: row.get(3)?,
+                new_value: row.get(4)?,
+                changed_at: row.get(5)?,
+            })
+        })
+        .map_err(|err| format!("failed to query item history: {}", err))?;
+
+    let mut entries = Vec::new();
+    for row_result in rows {
+        entries
+            .push(row_result.map_err(|err| format!("failed to read item history row: {}", err))?);
+    }
+    Ok(entries)
+}
+
+#[tauri::command]
+pub(crate) fn prune_item_history(older_than_days: Option<i64>) -> Result<usize, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let mut deleted_rows = connection
+        .execute(
+            "DELETE FROM item_audit_log
+             WHERE id IN (
+               SELECT id FROM (
+                 SELECT id,
+                        ROW_NUMBER() OVER (PARTITION BY item_id ORDER BY id DESC) AS position
+                 FROM item_audit_log
+               )
+               WHERE position > ?1
+             )",
+            params![ITEM_AUDIT_MAX_ENTRIES_PER_ITEM],
+        )
+        .map_err(|err| format!("failed to prune capped item history: {}", err))?;
+
+    if let Some(days) = older_than_days {
+        if days < 0 {
+            return Err("history retention cannot be negative".to_string());
+        }
+        let cutoff = Utc::now().timestamp_millis() - days.saturating_mul(24 * 60 * 60 * 1000);
+        deleted_rows += connection
+            .execute(
+                "DELETE FROM item_audit_log WHERE changed_at < ?1",
+                params![cutoff],
+            )
+            .map_err(|err| format!("failed to prune old item history: {}", err))?;
+    }
+    Ok(deleted_rows)
+}
+
+#[tauri::command]
+pub(crate) fn get_item_audit_enabled() -> Result<bool, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    item_audit_enabled(&connection)
+}
+
+#[tauri::command]
+pub(crate) fn set_item_audit_enabled(enabled: bool) -> Result<bool, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    write_app_setting(
+        &connection,
+        SETTING_ITEM_AUDIT_ENABLED,
+        if enabled { "1" } else { "0" },
+    )?;
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub(crate) fn update_item_media_state(input: UpdateItemMediaStateInput) -> Result<i64, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let updated_at = Utc::now().timestamp_millis();
+    let normalized_thumb_status = input.thumb_status.as_deref().map(normalize_thumb_status);
+
+    let affected_rows = connection
+        .execute(
+            "UPDATE items
+             SET width = CASE WHEN ?6 THEN NULL ELSE COALESCE(?1, width) END,
+                 height = CASE WHEN ?6 THEN NULL ELSE COALESCE(?2, height) END,
+                 thumb_status = COALESCE(?3, thumb_status),
+                 updated_at = ?4
+             WHERE id = ?5",
+            params![
+                input.width,
+                input.height,
+                normalized_thumb_status,
+                updated_at,
+                input.item_id,
+                input.clear_dimensions
+            ],
+        )
+        .map_err(|err| format!("failed to update item media state: {}", err))?;
+
+    if affected_rows == 0 {
+        return Err("item not found while updating media state".to_string());
+    }
+
+    Ok(updated_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    #[test]
+    fn insert_item_round_trips_through_get_item() {
+        let library = TestLibrary::new();
+        let mut input = note_input("note-1", "  First note ");
+        input.tags = vec![
+            "ideas".to_string(),
+            " ideas ".to_string(),
+            "todo".to_string(),
+        ];
+        let outcome = insert_item(input).unwrap();
+        assert_eq!(outcome.item_id, "note-1");
+        assert!(!outcome.already_existed);
+
+        let detail = get_item("note-1".to_string()).unwrap();
+        assert_eq!(detail.item.title, "  First note ");
+        assert_eq!(detail.item.item_type, "note");
+        let mut tags = detail.item.tags.clone();
+        tags.sort();
+        assert_eq!(tags, vec!["ideas".to_string(), "todo".to_string()]);
+        assert_eq!(library.count("SELECT COUNT(*) FROM tags"), 2);
+    }
+
+    #[test]
+    fn inserting_the_same_id_twice_reports_existing_item() {
+        let _library = TestLibrary::new();
+        insert_note("note-1", "First", None);
+        let outcome = insert_item(note_input("note-1", "Second")).unwrap();
+        assert!(outcome.already_existed);
+        assert_eq!(get_item("note-1".to_string()).unwrap().item.title, "First");
+    }
+
+    #[test]
+    fn insert_items_batch_is_all_or_nothing() {
+        let library = TestLibrary::new();
+        let mut invalid = note_input("bad", "Bad");
+        invalid.item_type = "spaceship".to_string();
+        let result = insert_items_batch(vec![note_input("good", "Good"), invalid]);
+        assert!(result.is_err());
+        assert_eq!(library.count("SELECT COUNT(*) FROM items"), 0);
+
+        let outcomes =
+            insert_items_batch(vec![note_input("a", "A"), note_input("b", "B")]).unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(library.count("SELECT COUNT(*) FROM items"), 2);
+    }
+
+    #[test]
+    fn delete_items_removes_rows_memberships_and_tag_mappings() {
+        let library = TestLibrary::new();
+        let collection_id = create_test_collection("Board", None);
+        let mut input = note_input("note-1", "Doomed");
+        input.collection_id = Some(collection_id.clone());
+        input.tags = vec!["keep".to_string()];
+        insert_item(input).unwrap();
+        insert_note("note-2", "Survivor", Some(&collection_id));
+
+        let deleted = delete_items(vec!["note-1".to_string(), "missing".to_string()]).unwrap();
+        assert_eq!(deleted, 1);
+        assert!(get_item("note-1".to_string()).is_err());
+        assert_eq!(
+            library.count("SELECT COUNT(*) FROM collection_items WHERE item_id = 'note-1'"),
+            0
+        );
+        assert_eq!(library.count("SELECT COUNT(*) FROM item_tags"), 0);
+        assert_eq!(library.count("SELECT COUNT(*) FROM tags"), 1);
+        assert_eq!(item_collection_ids("note-2"), vec![collection_id]);
+    }
+
+    fn item_dimensions(library: &TestLibrary, item_id: &str) -> (Option<i64>, Option<i64>, String) {
+        library
+            .connection()
+            .query_row(
+                "SELECT width, height, thumb_status FROM items WHERE id = ?1",
+                params![item_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap()
+    }
+
+    fn media_state_input(item_id: &str) -> UpdateItemMediaStateInput {
+        UpdateItemMediaStateInput {
+            item_id: item_id.to_string(),
+            width: None,
+            height: None,
+            thumb_status: None,
+            clear_dimensions: false,
+        }
+    }
+
+    #[test]
+    fn update_item_media_state_keeps_or_clears_dimensions() {
+        let library = TestLibrary::new();
+        let mut input = note_input("image-1", "Image");
+        input.width = Some(640);
+        input.height = Some(480);
+        insert_item(input).unwrap();
+
+        update_item_media_state(UpdateItemMediaStateInput {
+            thumb_status: Some("ready".to_string()),
+            ..media_state_input("image-1")
+        })
+        .unwrap();
+        assert_eq!(
+            item_dimensions(&library, "image-1"),
+            (Some(640), Some(480), "ready".to_string())
+        );
+
+        update_item_media_state(UpdateItemMediaStateInput {
+            width: Some(800),
+            ..media_state_input("image-1")
+        })
+        .unwrap();
+        assert_eq!(
+            item_dimensions(&library, "image-1"),
+            (Some(800), Some(480), "ready".to_string())
+        );
+
+        update_item_media_state(UpdateItemMediaStateInput {
+            clear_dimensions: true,
+            ..media_state_input("image-1")
+        })
+        .unwrap();
+        assert_eq!(
+            item_dimensions(&library, "image-1"),
+            (None, None, "ready".to_string())
+        );
+    }
+
+    #[test]
+    fn clearing_dimensions_wins_over_new_values() {
+        let library = TestLibrary::new();
+        let mut input = note_input("image-1", "Image");
+        input.width = Some(640);
+        input.height = Some(480);
+        insert_item(input).unwrap();
+
+        update_item_media_state(UpdateItemMediaStateInput {
+            width: Some(1),
+            height: Some(1),
+            clear_dimensions: true,
+            ..media_state_input("image-1")
+        })
+        .unwrap();
+        assert_eq!(item_dimensions(&library, "image-1").0, None);
+        assert_eq!(item_dimensions(&library, "image-1").1, None);
+        assert!(update_item_media_state(media_state_input("missing")).is_err());
+    }
+
+    fn directory_reads_during(operation: impl FnOnce()) -> usize {
+        let before = DIRECTORY_READS.with(|reads| reads.get());
+        operation();
+        DIRECTORY_READS.with(|reads| reads.get()) - before
+    }
+
+    #[test]
+    fn update_item_preferences_never_reads_directories() {
+        let library = TestLibrary::new();
+        insert_note("note-1", "Note", None);
+        library
+            .connection()
+            .execute(
+                "INSERT INTO vault_files (vault_key, vault_path, sha256, ext, size_bytes, ref_count, created_at, updated_at)
+                 VALUES ('orphan.png', '2024/01/orphan.png', 'orphan', 'png', 10, 0, 0, 0)",
+                [],
+            )
+            .unwrap();
+        let preferences = |rating| UpdateItemPreferencesInput {
+            item_id: "note-1".to_string(),
+            rating: Some(rating),
+            is_favorite: Some(true),
+        };
+
+        let warm_reads = directory_reads_during(|| {
+            update_item_preferences(preferences(3)).unwrap();
+        });
+        library.simulate_restart();
+        let cold_reads = directory_reads_during(|| {
+            update_item_preferences(preferences(4)).unwrap();
+        });
+        let cleanup_reads = directory_reads_during(|| {
+            cleanup_zero_ref_vault_files_reporting(
+                &library.connection(),
+                &ProgressReporter::silent("vault-cleanup"),
+            )
+            .unwrap();
+        });
+
+        assert_eq!((warm_reads, cold_reads), (0, 0));
+        assert!(cleanup_reads > 0, "the counter must see the storage walk");
+        assert_eq!(
+            library.count("SELECT rating FROM items WHERE id = 'note-1'"),
+            4
+        );
+    }
+
+    fn seed_keyset_notes() {
+        for note in KEYSET_SEED_NOTES {
+            insert_item(keyset_note_input(*note)).unwrap();
+        }
+    }
+
+    fn insert_keyset_note_between(step: usize) -> Option<String> {
+        let note = KEYSET_INSERTED_NOTES.get(step)?;
+        insert_item(keyset_note_input(*note)).unwrap();
+        Some(note.0.to_string())
+    }
+
+    #[test]
+    fn favorite_keyset_pages_survive_inserts_for_every_sort() {
+        for spec in ITEM_SORT_SPECS {
+            let _library = TestLibrary::new();
+            seed_keyset_notes();
+            assert_keyset_walk(
+                spec.name,
+                |limit, cursor| {
+                    get_favorite_items(
+                        Some(limit),
+                        None,
+                        Some(spec.name.to_string()),
+                        Some(true),
+                        cursor,
+                    )
+                    .unwrap()
+                },
+                insert_keyset_note_between,
+            );
+        }
+    }
+
+    #[test]
+    fn rating_keyset_pages_survive_inserts() {
+        let _library = TestLibrary::new();
+        seed_keyset_notes();
+        assert_keyset_walk(
+            "min-rating",
+            |limit, cursor| {
+                get_items_by_min_rating(1, Some(limit), None, Some(true), cursor).unwrap()
+            },
+            insert_keyset_note_between,
+        );
+    }
+
+    #[test]
+    fn name_asc_cursor_continues_through_case_only_ties() {
+        let _library = TestLibrary::new();
+        seed_keyset_notes();
+        let sort = Some("name-asc".to_string());
+        let first = get_favorite_items(Some(2), None, sort.clone(), Some(true), None).unwrap();
+        let first_ids: Vec<&str> = first.items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(first_ids, vec!["k-12", "k-11"]);
+
+        let second =
+            get_favorite_items(Some(2), None, sort, Some(true), first.next_cursor).unwrap();
+        let second_ids: Vec<&str> = second.items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(second_ids, vec!["k-10", "k-16"]);
+    }
+}
@@ -0,0 +1,1684 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use uuid::Uuid;
+
+use crate::collections::*;
+use crate::commands::*;
+use crate::db::*;
+use crate::items::*;
+
+pub(crate) const DEFAULT_TAG_COLOR: &str = "#64748b";
+pub(crate) const TAG_COLOR_PALETTE: [&str; 12] = [
+    "#ef4444", "#f97316", "#f59e0b", "#84cc16", "#22c55e", "#14b8a6", "#06b6d4", "#3b82f6",
+    "#6366f1", "#a855f7", "#ec4899", "#64748b",
+];
+pub(crate) const SYSTEM_TAG_SOURCE_CLIPBOARD: &str = "source:clipboard";
+pub(crate) const SYSTEM_TAG_SOURCE_WATCH: &str = "source:watch";
+pub(crate) const SYSTEM_TAG_SOURCE_WEB: &str = "source:web";
+pub(crate) const ITEM_SOURCES: [(&str, &str); 3] = [
+    ("clipboard", SYSTEM_TAG_SOURCE_CLIPBOARD),
+    ("watch", SYSTEM_TAG_SOURCE_WATCH),
+    ("web", SYSTEM_TAG_SOURCE_WEB),
+];
+pub(crate) const DB_TAG_ROW_COLUMNS: &str =
+    "id, name, color, sort_index, created_at, updated_at, is_system";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DbTagRow {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) color: String,
+    pub(crate) sort_index: i64,
+    pub(crate) created_at: i64,
+    pub(crate) updated_at: i64,
+    pub(crate) is_system: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UntaggedItemsCount {
+    pub(crate) total: i64,
+    pub(crate) hidden_count: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CreateTagInput {
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) color: Option<String>,
+    /// Ignore `color` and take the next palette color (see [`next_tag_palette_color`]).
+    #[serde(default)]
+    pub(crate) auto_color: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateTagNameInput {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    /// Maintenance override for renaming system tags.
+    #[serde(default)]
+    pub(crate) allow_system: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateTagColorInput {
+    pub(crate) id: String,
+    pub(crate) color: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeleteTagInput {
+    pub(crate) id: String,
+    /// Maintenance override for deleting system tags.
+    #[serde(default)]
+    pub(crate) allow_system: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TagsExportFile {
+    pub(crate) version: i64,
+    pub(crate) exported_at: i64,
+    pub(crate) tags: Vec<TagsExportEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TagsExportEntry {
+    pub(crate) name: String,
+    pub(crate) color: String,
+    #[serde(default)]
+    pub(crate) sort_index: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExportTagsJsonResult {
+    pub(crate) path: String,
+    pub(crate) tags: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportTagsJsonResult {
+    pub(crate) created: usize,
+    pub(crate) updated: usize,
+    pub(crate) skipped: usize,
+    pub(crate) deleted: usize,
+    pub(crate) blocked: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateItemTagsInput {
+    pub(crate) item_id: String,
+    pub(crate) tag_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TagRatingSummary {
+    pub(crate) tag_id: String,
+    pub(crate) name: String,
+    pub(crate) item_count: i64,
+    pub(crate) rated_count: i64,
+    pub(crate) average_rating: Option<f64>,
+}
+
+pub(crate) fn item_tag_names_for_audit(
+    connection: &Connection,
+    item_id: &str,
+) -> Result<String, String> {
+    connection
+        .query_row(
+            "SELECT COALESCE(GROUP_CONCAT(name, ', '), '')
+             FROM (
+               SELECT t.name
+               FROM item_tags it
+               JOIN tags t ON t.id = it.tag_id
+               WHERE it.item_id = ?1
+               ORDER BY t.sort_index ASC, t.name COLLATE NATURAL_TITLE ASC
+             )",
+            params![item_id],
+            |row| row.get::<_, String>(0),
+        )
+        .map_err(|err| format!("failed to read item tags for audit: {}", err))
+}
+
+pub(crate) fn normalize_tag_name(raw: &str) -> Result<String, String> {
+    let normalized = collapse_whitespace(raw.trim());
+    if normalized.is_empty() {
+        return Err("tag name cannot be empty".to_string());
+    }
+    Ok(normalized)
+}
+
+/// Accepts `#rgb` or `#rrggbb` (any case) and returns the lowercase `#rrggbb` form.
+pub(crate) fn normalize_tag_color(raw: &str) -> Result<String, String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err("tag color cannot be empty".to_string());
+    }
+    let digits = trimmed
+        .strip_prefix('#')
+        .filter(|digits| {
+            (digits.len() == 3 || digits.len() == 6)
+                && digits.chars().all(|c| c.is_ascii_hexdigit())
+        })
+        .ok_or_else(|| {
+            format!(
+                "invalid tag color \"{}\": expected #rgb or #rrggbb",
+                trimmed
+            )
+        })?
+        .to_ascii_lowercase();
+    if digits.len() == 3 {
+        Ok(digits.chars().fold(String::from("#"), |mut color, c| {
+            color.push(c);
+            color.push(c);
+            color
+        }))
+    } else {
+        Ok(format!("#{}", digits))
+    }
+}
+
+/// Cycles through [`TAG_COLOR_PALETTE`] by the number of existing tags.
+pub(crate) fn next_tag_palette_color(connection: &Connection) -> Result<String, String> {
+    let tag_count = connection
+        .query_row("SELECT COUNT(*) FROM tags", [], |row| row.get::<_, i64>(0))
+        .map_err(|err| format!("failed to count tags for palette color: {}", err))?;
+    Ok(
+        TAG_COLOR_PALETTE[tag_count.rem_euclid(TAG_COLOR_PALETTE.len() as i64) as usize]
+            .to_string(),
+    )
+}
+
+/// Rewrites stored colors into the canonical `#rrggbb` form. Colors that cannot be parsed
+/// are reset to [`DEFAULT_TAG_COLOR`] and logged so the tag can be fixed by hand.
+pub(crate) fn normalize_stored_tag_colors(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("SELECT id, name, color FROM tags")
+        .map_err(|err| format!("failed to prepare tag color scan: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map_err(|err| format!("failed to scan tag colors: {}", err))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to read tag color row: {}", err))?;
+    drop(stmt);
+
+    for (id, name, color) in rows {
+        let normalized = match normalize_tag_color(&color) {
+            Ok(normalized) => normalized,
+            Err(_) => {
+                log_eprintln!(
+                    "[tags] tag \"{}\" had invalid color \"{}\"; reset to {}",
+                    name,
+                    color,
+                    DEFAULT_TAG_COLOR
+                );
+                DEFAULT_TAG_COLOR.to_string()
+            }
+        };
+        if normalized != color {
+            connection
+                .execute(
+                    "UPDATE tags SET color = ?1 WHERE id = ?2",
+                    params![normalized, id],
+                )
+                .map_err(|err| format!("failed to normalize tag color: {}", err))?;
+        }
+    }
+    Ok(())
+}
+
+/// Maps an item `source` ("clipboard", "watch", "web") to its hidden provenance tag name.
+pub(crate) fn system_tag_for_item_source(value: &str) -> Result<&'static str, String> {
+    let normalized = value.trim().to_ascii_lowercase();
+    ITEM_SOURCES
+        .iter()
+        .find(|(source, _)| *source == normalized)
+        .map(|(_, tag_name)| *tag_name)
+        .ok_or_else(|| {
+            format!(
+                "invalid item source \"{}\"; expected one of: {}",
+                value,
+                ITEM_SOURCES
+                    .iter()
+                    .map(|(source, _)| *source)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+pub(crate) fn db_tag_row_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<DbTagRow> {
+    Ok(DbTagRow {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        sort_index: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+        is_system: row.get::<_, i64>(6)? != 0,
+    })
+}
+
+/// Rejects changes to system tags unless the caller passed the maintenance flag.
+pub(crate) fn ensure_tag_editable(
+    connection: &Connection,
+    tag_id: &str,
+    allow_system: bool,
+    action: &str,
+) -> Result<(), String> {
+    let system_name = connection
+        .query_row(
+            "SELECT name FROM tags WHERE id = ?1 AND is_system = 1",
+            params![tag_id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to check system tag: {}", err))?;
+    match system_name {
+        Some(name) if !allow_system => Err(format!("system tag \"{}\" cannot be {}", name, action)),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn find_tag_row_by_name_in_tx(
+    transaction: &Transaction<'_>,
+    tag_name: &str,
+) -> Result<Option<DbTagRow>, String> {
+    transaction
+        .query_row(
+            &format!(
+                "SELECT {}
+                 FROM tags
+                 WHERE name = ?1
+                 LIMIT 1",
+                DB_TAG_ROW_COLUMNS
+            ),
+            params![tag_name],
+            db_tag_row_from_row,
+        )
+        .optional()
+        .map_err(|err| format!("failed to query tag by name: {}", err))
+}
+
+pub(crate) fn find_tag_row_by_name_nocase_in_tx(
+    transaction: &Transaction<'_>,
+    tag_name: &str,
+) -> Result<Option<DbTagRow>, String> {
+    transaction
+        .query_row(
+            &format!(
+                "SELECT {}
+                 FROM tags
+                 WHERE LOWER(name) = LOWER(?1)
+                 ORDER BY name = ?1 DESC, created_at ASC
+                 LIMIT 1",
+                DB_TAG_ROW_COLUMNS
+            ),
+            params![tag_name],
+            db_tag_row_from_row,
+        )
+        .optional()
+        .map_err(|err| format!("failed to query tag by name: {}", err))
+}
+
+pub(crate) fn next_tag_sort_index_in_tx(transaction: &Transaction<'_>) -> Result<i64, String> {
+    transaction
+        .query_row(
+            "SELECT COALESCE(MAX(sort_index), -1) + 1 FROM tags",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .map_err(|err| format!("failed to resolve next tag sort index: {}", err))
+}
+
+pub(crate) fn insert_tag_row_in_tx(
+    transaction: &Transaction<'_>,
+    name: &str,
+    color: &str,
+    now: i64,
+) -> Result<DbTagRow, String> {
+    let tag_id = Uuid::new_v4().to_string();
+    let sort_index = next_tag_sort_index_in_tx(transaction)?;
+    transaction
+        .execute(
+            "INSERT INTO tags (id, name, color, sort_index, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            params![&tag_id, name, color, sort_index, now],
+        )
+        .map_err(|err| format!("failed to insert tag row: {}", err))?;
+    Ok(DbTagRow {
+        id: tag_id,
+        name: name.to_string(),
+        color: color.to_string(),
+        sort_index,
+        created_at: now,
+        updated_at: now,
+        is_system: false,
+    })
+}
+
+pub(crate) fn ensure_tag_exists_by_name_in_tx(
+    transaction: &Transaction<'_>,
+    tag_name: &str,
+    now: i64,
+) -> Result<String, String> {
+    if let Some(existing) = find_tag_row_by_name_in_tx(transaction, tag_name)? {
+        return Ok(existing.id);
+    }
+    let created = insert_tag_row_in_tx(transaction, tag_name, DEFAULT_TAG_COLOR, now)?;
+    Ok(created.id)
+}
+
+/// Like [`ensure_tag_exists_by_name_in_tx`], but also marks the tag as a hidden system tag.
+pub(crate) fn ensure_system_tag_in_tx(
+    transaction: &Transaction<'_>,
+    tag_name: &str,
+    now: i64,
+) -> Result<String, String> {
+    let tag_id = ensure_tag_exists_by_name_in_tx(transaction, tag_name, now)?;
+    transaction
+        .execute(
+            "UPDATE tags SET is_system = 1, updated_at = ?1 WHERE id = ?2 AND is_system = 0",
+            params![now, &tag_id],
+        )
+        .map_err(|err| format!("failed to mark system tag: {}", err))?;
+    Ok(tag_id)
+}
+
+pub(crate) fn next_duplicate_tag_name(
+    connection: &Connection,
+    source_name: &str,
+) -> Result<String, String> {
+    let base = format!("{} copy", source_name.trim());
+    let base = collapse_whitespace(&base);
+    if base.is_empty() {
+        return Err("tag name cannot be empty".to_string());
+    }
+
+    let mut candidate = base.clone();
+    let mut suffix = 2usize;
+    loop {
+        let exists = connection
+            .query_row(
+                "SELECT 1 FROM tags WHERE name = ?1 LIMIT 1",
+                params![&candidate],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()
+            .map_err(|err| format!("failed to check duplicate tag name: {}", err))?;
+        if exists.is_none() {
+            return Ok(candidate);
+        }
+        candidate = format!("{} {}", base, suffix);
+        suffix += 1;
+    }
+}
+
+#[tauri::command]
+pub(crate) fn get_items_by_tag(
+    tag_id: String,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    sort: Option<String>,
+    include_sensitive: Option<bool>,
+    cursor: Option<String>,
+) -> Result<ItemPage, String> {
+    let normalized_tag_id =
+        normalize_trimmed_id(&tag_id).ok_or_else(|| "tag id cannot be empty".to_string())?;
+    let spec = item_sort_spec(sort.as_deref())?;
+    let cursor = normalize_optional_trimmed_string(cursor);
+    let (limit, offset) = normalize_item_page(limit, offset, cursor.as_deref());
+    let keyset = item_keyset_condition(spec, cursor.as_deref(), 5)?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let show_sensitive = resolve_show_sensitive(&connection, include_sensitive)?;
+    prepare_locked_collection_scope(&connection)?;
+    let (matched, sensitive_count) = connection
+        .query_row(
+            &format!(
+                "SELECT COUNT(*), COALESCE(SUM(i.is_sensitive), 0)
+                 FROM item_tags AS it
+                 JOIN items AS i ON i.id = it.item_id
+                 WHERE it.tag_id = ?1 AND i.is_archived = 0 AND {}",
+                LOCKED_SCOPE_VISIBLE_CONDITION
+            ),
+            params![&normalized_tag_id],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .map_err(|err| format!("failed to count items for tag: {}", err))?;
+    let hidden_count = hidden_sensitive_count(show_sensitive, sensitive_count);
+    let total = matched - hidden_count;
+    if total == 0 {
+        return build_item_page(spec, Vec::new(), total, hidden_count, limit, offset);
+    }
+
+    let items = query_db_item_rows(
+        &connection,
+        &format!(
+            "{} WHERE i.id IN (SELECT item_id FROM item_tags WHERE tag_id = ?1)
+               AND i.is_archived = 0
+               AND (?4 OR i.is_sensitive = 0)
+               AND {}
+               AND {}
+             GROUP BY i.id
+             ORDER BY {}
+             LIMIT ?2 OFFSET ?3",
+            DB_ITEM_ROW_SELECT, LOCKED_SCOPE_VISIBLE_CONDITION, keyset.condition, spec.order_by
+        ),
+        item_page_params(
+            vec![
+                normalized_tag_id.into(),
+                limit.into(),
+                offset.into(),
+                show_sensitive.into(),
+            ],
+            keyset,
+        ),
+    )?;
+
+    build_item_page(spec, items, total, hidden_count, limit, offset)
+}
+
+#[tauri::command]
+pub(crate) fn get_tag_rating_summary() -> Result<Vec<TagRatingSummary>, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let show_sensitive = resolve_show_sensitive(&connection, None)?;
+    prepare_locked_collection_scope(&connection)?;
+
+    let mut stmt = connection
+        .prepare(&format!(
+            "SELECT t.id, t.name, COUNT(i.id), COUNT(NULLIF(i.rating, 0)), AVG(NULLIF(i.rating, 0))
+             FROM tags AS t
+             LEFT JOIN item_tags AS it ON it.tag_id = t.id
+             LEFT JOIN items AS i
+               ON i.id = it.item_id
+              AND i.is_archived = 0
+              AND (?1 OR i.is_sensitive = 0)
+              AND {}
+             WHERE t.is_system = 0
+             GROUP BY t.id
+             ORDER BY t.name COLLATE NATURAL_TITLE ASC",
+            LOCKED_SCOPE_VISIBLE_CONDITION
+        ))
+        .map_err(|err| format!("failed to prepare tag rating summary query: {}", err))?;
+    let rows = stmt
+        .query_map(params![show_sensitive], |row| {
+            Ok(TagRatingSummary {
+                tag_id: row.get(0)?,
+                name: row.get(1)?,
+                item_count: row.get(2)?,
+                rated_count: row.get(3)?,
+                average_rating: row.get(4)?,
+            })
+        })
+        .map_err(|err| format!("failed to query tag rating summary: {}", err))?;
+    let mut summaries = Vec::new();
+    for row_result in rows {
+        summaries.push(
+            row_result.map_err(|err| format!("failed to read tag rating summary row: {}", err))?,
+        );
+    }
+    Ok(summaries)
+}
+
+pub(crate) const UNTAGGED_ITEMS_SUBTREE_CTE: &str = "WITH RECURSIVE subtree(id) AS (
+        SELECT ?1 WHERE ?1 IS NOT NULL
+        UNION
+        SELECT c.id FROM collections AS c JOIN subtree AS s ON c.parent_id = s.id
+     ) ";
+
+pub(crate) const UNTAGGED_ITEMS_CONDITIONS: &str = "NOT EXISTS (
+         SELECT 1 FROM item_tags AS ut JOIN tags AS st ON st.id = ut.tag_id
+         WHERE ut.item_id = i.id AND st.is_system = 0
+       )
+       AND i.is_archived = 0
+       AND (?2 IS NULL OR i.type = ?2)
+       AND (?1 IS NULL OR i.id IN (
+         SELECT ci.item_id FROM collection_items AS ci JOIN subtree AS s ON s.id = ci.collection_id
+       ))";
+
+pub(crate) fn normalize_untagged_items_filter(
+    collection_id: Option<String>,
+    item_type: Option<String>,
+) -> Result<(Option<String>, Option<String>), String> {
+    let normalized_type = match normalize_optional_trimmed_string(item_type) {
+        Some(value) => Some(normalize_item_type(&value)?),
+        None => None,
+    };
+    Ok((
+        normalize_optional_trimmed_id(collection_id),
+        normalized_type,
+    ))
+}
+
+pub(crate) fn count_untagged_items_internal(
+    connection: &Connection,
+    collection_id: Option<&str>,
+    item_type: Option<&str>,
+    show_sensitive: bool,
+) -> Result<(i64, i64), String> {
+    prepare_locked_collection_scope(connection)?;
+    let (matched, sensitive_count) = connection
+        .query_row(
+            &format!(
+                "{}SELECT COUNT(*), COALESCE(SUM(i.is_sensitive), 0) FROM items AS i WHERE {} AND {}",
+                UNTAGGED_ITEMS_SUBTREE_CTE, UNTAGGED_ITEMS_CONDITIONS, LOCKED_SCOPE_VISIBLE_CONDITION
+            ),
+            params![collection_id, item_type],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .map_err(|err| format!("failed to count untagged items: {}", err))?;
+    let hidden_count = hidden_sensitive_count(show_sensitive, sensitive_count);
+    Ok((matched - hidden_count, hidden_count))
+}
+
+#[tauri::command]
+pub(crate) fn get_untagged_items(
+    limit: Option<u32>,
+    offset: Option<u32>,
+    collection_id: Option<String>,
+    item_type: Option<String>,
+    include_sensitive: Option<bool>,
+    cursor: Option<String>,
+) -> Result<ItemPage, String> {
+    let (collection_id, item_type) = normalize_untagged_items_filter(collection_id, item_type)?;
+    let spec = item_sort_spec(Some("newest"))?;
+    let cursor = normalize_optional_trimmed_string(cursor);
+    let (limit, offset) = normalize_item_page(limit, offset, cursor.as_deref());
+    let keyset = item_keyset_condition(spec, cursor.as_deref(), 6)?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let show_sensitive = resolve_show_sensitive(&connection, include_sensitive)?;
+    let (total, hidden_count) = count_untagged_items_internal(
+        &connection,
+        collection_id.as_deref(),
+        item_type.as_deref(),
+        show_sensitive,
+    )?;
+    let items = query_db_item_rows(
+        &connection,
+        &format!(
+            "{}{} WHERE {}
+               AND (?5 OR i.is_sensitive = 0)
+               AND {}
+               AND {}
+             GROUP BY i.id
+             ORDER BY {}
+             LIMIT ?3 OFFSET ?4",
+            UNTAGGED_ITEMS_SUBTREE_CTE,
+            DB_ITEM_ROW_SELECT,
+            UNTAGGED_ITEMS_CONDITIONS,
+            LOCKED_SCOPE_VISIBLE_CONDITION,
+            keyset.condition,
+            spec.order_by
+        ),
+        item_page_params(
+            vec![
+                collection_id.into(),
+                item_type.into(),
+                limit.into(),
+                offset.into(),
+                show_sensitive.into(),
+            ],
+            keyset,
+        ),
+    )?;
+
+    build_item_page(spec, items, total, hidden_count, limit, offset)
+}
+
+#[tauri::command]
+pub(crate) fn count_untagged_items(
+    collection_id: Option<String>,
+    item_type: Option<String>,
+    include_sensitive: Option<bool>,
+) -> Result<UntaggedItemsCount, String> {
+    let (collection_id, item_type) = normalize_untagged_items_filter(collection_id, item_type)?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let show_sensitive = resolve_show_sensitive(&connection, include_sensitive)?;
+    let (total, hidden_count) = count_untagged_items_internal(
+        &connection,
+        collection_id.as_deref(),
+        item_type.as_deref(),
+        show_sensitive,
+    )?;
+    Ok(UntaggedItemsCount {
+        total,
+        hidden_count,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn create_tag(input: CreateTagInput) -> Result<DbTagRow, String> {
+    let now = Utc::now().timestamp_millis();
+    with_db_transaction(|transaction| create_tag_in_tx(transaction, input, now))
+}
+
+pub(crate) fn create_tag_in_tx(
+    transaction: &Transaction<'_>,
+    input: CreateTagInput,
+    now: i64,
+) -> Result<DbTagRow, String> {
+    let normalized_name = normalize_tag_name(&input.name)?;
+    let normalized_color = match input.color {
+        Some(color) if !input.auto_color => normalize_tag_color(&color)?,
+        None if !input.auto_color => return Err("tag color cannot be empty".to_string()),
+        _ => next_tag_palette_color(transaction)?,
+    };
+
+    if find_tag_row_by_name_in_tx(transaction, &normalized_name)?.is_some() {
+        return Err("tag name already exists".to_string());
+    }
+
+    insert_tag_row_in_tx(transaction, &normalized_name, &normalized_color, now)
+}
+
+#[tauri::command]
+pub(crate) fn suggest_tag_color() -> Result<String, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    next_tag_palette_color(&connection)
+}
+
+#[tauri::command]
+pub(crate) fn get_all_tags(include_system: Option<bool>) -> Result<Vec<DbTagRow>, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let mut stmt = connection
+        .prepare(&format!(
+            "SELECT {}
+             FROM tags
+             WHERE (?1 OR is_system = 0)
+             ORDER BY sort_index ASC, created_at ASC, name COLLATE NATURAL_TITLE ASC",
+            DB_TAG_ROW_COLUMNS
+        ))
+        .map_err(|err| format!("failed to prepare all tags query: {}", err))?;
+    let row_iter = stmt
+        .query_map(
+            params![include_system.unwrap_or(false)],
+            db_tag_row_from_row,
+        )
+        .map_err(|err| format!("failed to query all tags: {}", err))?;
+    let mut tags = Vec::new();
+    for row_result in row_iter {
+        tags.push(row_result.map_err(|err| format!("failed to read tag row: {}", err))?);
+    }
+    Ok(tags)
+}
+
+#[tauri::command]
+pub(crate) fn reorder_tags(
+    ordered_tag_ids: Vec<String>,
+) -> Result<UpdateCollectionOrderResult, String> {
+    let normalized_tag_ids = normalize_item_ids_input(ordered_tag_ids);
+    let updated_at = Utc::now().timestamp_millis();
+
+    if normalized_tag_ids.is_empty() {
+        return Ok(UpdateCollectionOrderResult {
+            updated_rows: 0,
+            skipped_rows: 0,
+            updated_at,
+        });
+    }
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    let mut updated_rows = 0usize;
+    let mut skipped_rows = 0usize;
+    for (index, tag_id) in normalized_tag_ids.iter().enumerate() {
+        let affected = transaction
+            .execute(
+                "UPDATE tags
+                 SET sort_index = ?1,
+                     updated_at = ?2
+                 WHERE id = ?3",
+                params![index as i64, updated_at, tag_id],
+            )
+            .map_err(|err| format!("failed to reorder tag row: {}", err))?;
+        if affected == 0 {
+            skipped_rows += 1;
+        } else {
+            updated_rows += affected;
+        }
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit reorder tags transaction: {}", err))?;
+
+    Ok(UpdateCollectionOrderResult {
+        updated_rows,
+        skipped_rows,
+        updated_at,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn update_tag_name(input: UpdateTagNameInput) -> Result<i64, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let tag_id =
+        normalize_trimmed_id(&input.id).ok_or_else(|| "tag id cannot be empty".to_string())?;
+    let normalized_name = normalize_tag_name(&input.name)?;
+    ensure_tag_editable(&connection, &tag_id, input.allow_system, "renamed")?;
+    let updated_at = Utc::now().timestamp_millis();
+
+    let updated_rows = connection
+        .execute(
+            "UPDATE tags
+             SET name = ?1,
+                 updated_at = ?2
+             WHERE id = ?3",
+            params![normalized_name, updated_at, tag_id],
+        )
+        .map_err(|err| format!("failed to update tag name: {}", err))?;
+    if updated_rows == 0 {
+        return Err("tag not found while updating name".to_string());
+    }
+    Ok(updated_at)
+}
+
+#[tauri::command]
+pub(crate) fn update_tag_color(input: UpdateTagColorInput) -> Result<i64, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let tag_id =
+        normalize_trimmed_id(&input.id).ok_or_else(|| "tag id cannot be empty".to_string())?;
+    let normalized_color = normalize_tag_color(&input.color)?;
+    let updated_at = Utc::now().timestamp_millis();
+
+    let updated_rows = connection
+        .execute(
+            "UPDATE tags
+             SET color = ?1,
+                 updated_at = ?2
+             WHERE id = ?3",
+            params![normalized_color, updated_at, tag_id],
+        )
+        .map_err(|err| format!("failed to update tag color: {}", err))?;
+    if updated_rows == 0 {
+        return Err("tag not found while updating color".to_string());
+    }
+    Ok(updated_at)
+}
+
+#[tauri::command]
+pub(crate) fn duplicate_tag(id: String) -> Result<DbTagRow, String> {
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    let tag_id = normalize_trimmed_id(&id).ok_or_else(|| "tag id cannot be empty".to_string())?;
+
+    let source = transaction
+        .query_row(
+            &format!(
+                "SELECT {}
+                 FROM tags
+                 WHERE id = ?1",
+                DB_TAG_ROW_COLUMNS
+            ),
+            params![&tag_id],
+            db_tag_row_from_row,
+        )
+        .optional()
+        .map_err(|err| format!("failed to load tag for duplicate: {}", err))?
+        .ok_or_else(|| "tag not found while duplicating".to_string())?;
+
+    let duplicate_name = next_duplicate_tag_name(&transaction, &source.name)?;
+    let now = Utc::now().timestamp_millis();
+    let duplicated = insert_tag_row_in_tx(&transaction, &duplicate_name, &source.color, now)?;
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit duplicate tag transaction: {}", err))?;
+    Ok(duplicated)
+}
+
+#[tauri::command]
+pub(crate) fn delete_tag(input: DeleteTagInput) -> Result<usize, String> {
+    let tag_id =
+        normalize_trimmed_id(&input.id).ok_or_else(|| "tag id cannot be empty".to_string())?;
+    let updated_at = Utc::now().timestamp_millis();
+    with_db_transaction(|transaction| {
+        delete_tag_in_tx(transaction, &tag_id, input.allow_system, updated_at)
+    })
+}
+
+pub(crate) fn delete_tag_in_tx(
+    transaction: &Transaction<'_>,
+    tag_id: &str,
+    allow_system: bool,
+    updated_at: i64,
+) -> Result<usize, String> {
+    ensure_tag_editable(transaction, tag_id, allow_system, "deleted")?;
+
+    transaction
+        .execute(
+            "UPDATE items
+             SET updated_at = ?1
+             WHERE id IN (
+               SELECT DISTINCT item_id FROM item_tags WHERE tag_id = ?2
+             )",
+            params![updated_at, tag_id],
+        )
+        .map_err(|err| format!("failed to update item timestamps for tag delete: {}", err))?;
+
+    transaction
+        .execute("DELETE FROM tags WHERE id = ?1", params![tag_id])
+        .map_err(|err| format!("failed to delete tag: {}", err))
+}
+
+pub(crate) const TAGS_EXPORT_VERSION: i64 = 1;
+
+#[tauri::command]
+pub(crate) fn export_tags_json(path: Option<String>) -> Result<ExportTagsJsonResult, String> {
+    let tags = get_all_tags(None)?;
+    let output_path = match path
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+    {
+        Some(path) => {
+            let path = path_from_ipc_string(&path);
+            ensure_path_outside_storage(&path)?;
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                fs::create_dir_all(parent).map_err(|err| {
+                    format!(
+                        "failed to create export directory {}: {}",
+                        parent.display(),
+                        err
+                    )
+                })?;
+            }
+            path
+        }
+        None => ensure_exports_root_internal()?
+            .join(format!("tags-{}.json", Utc::now().format("%Y%m%d-%H%M%S"))),
+    };
+
+    let export = TagsExportFile {
+        version: TAGS_EXPORT_VERSION,
+        exported_at: Utc::now().timestamp_millis(),
+        tags: tags
+            .into_iter()
+            .map(|tag| TagsExportEntry {
+                name: tag.name,
+                color: tag.color,
+                sort_index: tag.sort_index,
+            })
+            .collect(),
+    };
+    let json = serde_json::to_string_pretty(&export)
+        .map_err(|err| format!("failed to serialize tags export: {}", err))?;
+    fs::write(&output_path, json).map_err(|err| {
+        format!(
+            "failed to write tags export {}: {}",
+            output_path.display(),
+            err
+        )
+    })?;
+
+    Ok(ExportTagsJsonResult {
+        path: path_to_string(&output_path)?,
+        tags: export.tags.len(),
+    })
+}
+
+#[tauri::command]
+pub(crate) fn import_tags_json(path: String, mode: String) -> Result<ImportTagsJsonResult, String> {
+    let replace = match mode.trim() {
+        "merge" => false,
+        "replace" => true,
+        other => return Err(format!("unsupported tag import mode: {}", other)),
+    };
+    let input_path = path_from_ipc_string(path.trim());
+    let contents = fs::read_to_string(&input_path)
+        .map_err(|err| format!("failed to read tags file {}: {}", input_path.display(), err))?;
+    let import: TagsExportFile = serde_json::from_str(&contents)
+        .map_err(|err| format!("failed to parse tags file: {}", err))?;
+    if import.version > TAGS_EXPORT_VERSION {
+        return Err(format!("unsupported tags file version: {}", import.version));
+    }
+
+    let mut entries = Vec::new();
+    let mut seen_names = BTreeSet::new();
+    let mut skipped = 0usize;
+    for entry in import.tags {
+        let name = normalize_tag_name(&entry.name)?;
+        if !seen_names.insert(name.to_lowercase()) {
+            skipped += 1;
+            continue;
+        }
+        let color = normalize_tag_color(&entry.color)?;
+        entries.push((name, color, entry.sort_index));
+    }
+    entries.sort_by_key(|(_, _, sort_index)| *sort_index);
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    let now = Utc::now().timestamp_millis();
+
+    let mut result = ImportTagsJsonResult {
+        created: 0,
+        updated: 0,
+        skipped,
+        deleted: 0,
+        blocked: Vec::new(),
+    };
+    let mut kept_ids = BTreeSet::new();
+    for (index, (name, color, _)) in entries.iter().enumerate() {
+        let Some(existing) = find_tag_row_by_name_nocase_in_tx(&transaction, name)? else {
+            let created = insert_tag_row_in_tx(&transaction, name, color, now)?;
+            if replace {
+                transaction
+                    .execute(
+                        "UPDATE tags SET sort_index = ?1 WHERE id = ?2",
+                        params![index as i64, &created.id],
+                    )
+                    .map_err(|err| format!("failed to update imported tag order: {}", err))?;
+            }
+            kept_ids.insert(created.id);
+            result.created += 1;
+            continue;
+        };
+        if existing.is_system {
+            result.skipped += 1;
+            continue;
+        }
+
+        let sort_index = if replace {
+            index as i64
+        } else {
+            existing.sort_index
+        };
+        if existing.color == *color && existing.sort_index == sort_index {
+            result.skipped += 1;
+        } else {
+            transaction
+                .execute(
+                    "UPDATE tags
+                     SET color = ?1,
+                         sort_index = ?2,
+                         updated_at = ?3
+                     WHERE id = ?4",
+                    params![color, sort_index, now, &existing.id],
+                )
+                .map_err(|err| format!("failed to update imported tag: {}", err))?;
+            result.updated += 1;
+        }
+        kept_ids.insert(existing.id);
+    }
+
+    if replace {
+        let mut stmt = transaction
+            .prepare(
+                "SELECT t.id, t.name, EXISTS(SELECT 1 FROM item_tags it WHERE it.tag_id = t.id)
+                 FROM tags t
+                 WHERE t.is_system = 0
+                 ORDER BY t.sort_index ASC, t.name COLLATE NATURAL_TITLE ASC",
+            )
+            .map_err(|err| format!("failed to prepare tag usage query: {}", err))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)? != 0,
+                ))
+            })
+            .map_err(|err| format!("failed to query tag usage: {}", err))?;
+        let mut stale_tags = Vec::new();
+        for row_result in rows {
+            let (id, name, in_use) =
+                row_result.map_err(|err| format!("failed to read tag usage row: {}", err))?;
+            if !kept_ids.contains(&id) {
+                stale_tags.push((id, name, in_use));
+            }
+        }
+        drop(stmt);
+
+        let mut next_sort_index = entries.len() as i64;
+        for (id, name, in_use) in stale_tags {
+            if in_use {
+                transaction
+                    .execute(
+                        "UPDATE tags SET sort_index = ?1 WHERE id = ?2",
+                        params![next_sort_index, &id],
+                    )
+                    .map_err(|err| format!("failed to update blocked tag order: {}", err))?;
+                next_sort_index += 1;
+                result.blocked.push(name);
+            } else {
+                result.deleted += transaction
+                    .execute(
+                        "DELETE FROM tags
+                         WHERE id = ?1
+                           AND NOT EXISTS (SELECT 1 FROM item_tags WHERE tag_id = ?1)",
+                        params![&id],
+                    )
+                    .map_err(|err| format!("failed to delete unused tag: {}", err))?;
+            }
+        }
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit tag import transaction: {}", err))?;
+    Ok(result)
+}
+
+#[tauri::command]
+pub(crate) fn update_item_tags(input: UpdateItemTagsInput) -> Result<i64, String> {
+    let item_id = normalize_trimmed_id(&input.item_id)
+        .ok_or_else(|| "item id cannot be empty".to_string())?;
+    let tag_ids = normalize_item_ids_input(input.tag_ids);
+    let updated_at = Utc::now().timestamp_millis();
+    with_db_transaction(|transaction| {
+        update_item_tags_in_tx(transaction, &item_id, &tag_ids, updated_at)
+    })?;
+    Ok(updated_at)
+}
+
+pub(crate) fn update_item_tags_in_tx(
+    transaction: &Transaction<'_>,
+    item_id: &str,
+    tag_ids: &[String],
+    updated_at: i64,
+) -> Result<(), String> {
+    let item_exists = transaction
+        .query_row(
+            "SELECT 1 FROM items WHERE id = ?1",
+            params![item_id],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to validate item for tag update: {}", err))?;
+    if item_exists.is_none() {
+        return Err("item not found while updating tags".to_string());
+    }
+    let audit_enabled = item_audit_enabled(transaction)?;
+    let previous_tag_names = if audit_enabled {
+        Some(item_tag_names_for_audit(transaction, item_id)?)
+    } else {
+        None
+    };
+
+    for tag_id in tag_ids {
+        let is_system = transaction
+            .query_row(
+                "SELECT is_system FROM tags WHERE id = ?1",
+                params![tag_id],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()
+            .map_err(|err| format!("failed to validate tag for item tag update: {}", err))?;
+        match is_system {
+            None => return Err(format!("tag not found while assigning to item: {}", tag_id)),
+            Some(1) => {
+                return Err(format!(
+                    "system tag cannot be assigned manually: {}",
+                    tag_id
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+
+    // System tags record provenance and are not part of the editable tag list.
+    transaction
+        .execute(
+            "DELETE FROM item_tags
+             WHERE item_id = ?1
+               AND tag_id NOT IN (SELECT id FROM tags WHERE is_system = 1)",
+            params![item_id],
+        )
+        .map_err(|err| format!("failed to clear item tag mappings: {}", err))?;
+
+    for tag_id in tag_ids {
+        transaction
+            .execute(
+                "INSERT INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+                params![item_id, tag_id],
+            )
+            .map_err(|err| format!("failed to insert item tag mapping: {}", err))?;
+    }
+
+    let updated_rows = transaction
+        .execute(
+            "UPDATE items
+             SET updated_at = ?1
+             WHERE id = ?2",
+            params![updated_at, item_id],
+        )
+        .map_err(|err| format!("failed to update item timestamp for tag update: {}", err))?;
+    if updated_rows == 0 {
+        return Err("item not found while finalizing tag update".to_string());
+    }
+    if let Some(previous_tag_names) = previous_tag_names {
+        let next_tag_names = item_tag_names_for_audit(transaction, item_id)?;
+        record_item_audit_in_tx(
+            transaction,
+            item_id,
+            "tags",
+            Some(&previous_tag_names),
+            Some(&next_tag_names),
+            updated_at,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use std::path::Path;
+
+    fn create_named_tag(name: &str) -> DbTagRow {
+        create_tag(CreateTagInput {
+            name: name.to_string(),
+            color: Some("#ABC".to_string()),
+            auto_color: false,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn create_tag_normalizes_and_rejects_duplicates() {
+        let _library = TestLibrary::new();
+        let tag = create_named_tag("  Road   trip ");
+        assert_eq!(tag.name, "Road trip");
+        assert_eq!(tag.color, "#aabbcc");
+        assert!(!tag.is_system);
+        assert!(create_tag(CreateTagInput {
+            name: "Road trip".to_string(),
+            color: Some("#000000".to_string()),
+            auto_color: false,
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn update_item_tags_replaces_the_tag_set() {
+        let _library = TestLibrary::new();
+        insert_note("note-1", "Tagged", None);
+        let first = create_named_tag("first");
+        let second = create_named_tag("second");
+
+        update_item_tags(UpdateItemTagsInput {
+            item_id: "note-1".to_string(),
+            tag_ids: vec![first.id.clone(), second.id.clone()],
+        })
+        .unwrap();
+        let mut tag_ids = get_item("note-1".to_string()).unwrap().item.tag_ids;
+        tag_ids.sort();
+        let mut expected = vec![first.id.clone(), second.id.clone()];
+        expected.sort();
+        assert_eq!(tag_ids, expected);
+
+        update_item_tags(UpdateItemTagsInput {
+            item_id: "note-1".to_string(),
+            tag_ids: vec![second.id.clone()],
+        })
+        .unwrap();
+        assert_eq!(
+            get_item("note-1".to_string()).unwrap().item.tag_ids,
+            vec![second.id]
+        );
+    }
+
+    #[test]
+    fn update_item_tags_rejects_unknown_tags_atomically() {
+        let library = TestLibrary::new();
+        insert_note("note-1", "Tagged", None);
+        let tag = create_named_tag("real");
+        update_item_tags(UpdateItemTagsInput {
+            item_id: "note-1".to_string(),
+            tag_ids: vec![tag.id.clone()],
+        })
+        .unwrap();
+
+        let result = update_item_tags(UpdateItemTagsInput {
+            item_id: "note-1".to_string(),
+            tag_ids: vec!["missing".to_string()],
+        });
+        assert!(result.is_err());
+        assert_eq!(library.count("SELECT COUNT(*) FROM item_tags"), 1);
+    }
+
+    #[test]
+    fn delete_tag_removes_mappings_but_keeps_items() {
+        let library = TestLibrary::new();
+        insert_note("note-1", "Tagged", None);
+        let tag = create_named_tag("temporary");
+        update_item_tags(UpdateItemTagsInput {
+            item_id: "note-1".to_string(),
+            tag_ids: vec![tag.id.clone()],
+        })
+        .unwrap();
+
+        let deleted = delete_tag(DeleteTagInput {
+            id: tag.id,
+            allow_system: false,
+        })
+        .unwrap();
+        assert_eq!(deleted, 1);
+        assert_eq!(library.count("SELECT COUNT(*) FROM item_tags"), 0);
+        assert!(get_item("note-1".to_string()).unwrap().item.tags.is_empty());
+    }
+
+    #[test]
+    fn system_tags_are_hidden_and_protected() {
+        let _library = TestLibrary::new();
+        let mut input = note_input("clip-1", "Pasted");
+        input.source = Some("clipboard".to_string());
+        insert_item(input).unwrap();
+
+        assert!(get_all_tags(None).unwrap().is_empty());
+        let all = get_all_tags(Some(true)).unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].name, SYSTEM_TAG_SOURCE_CLIPBOARD);
+        assert!(all[0].is_system);
+        assert!(get_item("clip-1".to_string()).unwrap().item.tags.is_empty());
+
+        let rename = update_tag_name(UpdateTagNameInput {
+            id: all[0].id.clone(),
+            name: "renamed".to_string(),
+            allow_system: false,
+        });
+        assert!(rename.is_err());
+        assert!(delete_tag(DeleteTagInput {
+            id: all[0].id.clone(),
+            allow_system: false,
+        })
+        .is_err());
+
+        let other = create_named_tag("visible");
+        update_item_tags(UpdateItemTagsInput {
+            item_id: "clip-1".to_string(),
+            tag_ids: vec![other.id],
+        })
+        .unwrap();
+        let filtered = get_random_items(
+            10,
+            Some(RandomItemsFilter {
+                sources: vec!["clipboard".to_string()],
+                ..RandomItemsFilter::default()
+            }),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id, "clip-1");
+    }
+
+    fn create_colored_tag(name: &str, color: &str) -> DbTagRow {
+        create_tag(CreateTagInput {
+            name: name.to_string(),
+            color: Some(color.to_string()),
+            auto_color: false,
+        })
+        .unwrap()
+    }
+
+    fn tag_summary() -> Vec<(String, String)> {
+        get_all_tags(None)
+            .unwrap()
+            .into_iter()
+            .map(|tag| (tag.name, tag.color))
+            .collect()
+    }
+
+    fn write_tags_file(dir: &Path, tags: &[(&str, &str, i64)]) -> String {
+        let path = dir.join("tags.json");
+        let export = TagsExportFile {
+            version: TAGS_EXPORT_VERSION,
+            exported_at: 0,
+            tags: tags
+                .iter()
+                .map(|(name, color, sort_index)| TagsExportEntry {
+                    name: name.to_string(),
+                    color: color.to_string(),
+                    sort_index: *sort_index,
+                })
+                .collect(),
+        };
+        fs::write(&path, serde_json::to_string(&export).unwrap()).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn exported_tags_import_into_an_empty_library() {
+        let exports = tempfile::tempdir().unwrap();
+        let export_path = exports.path().join("nested").join("tags.json");
+        let expected = {
+            let _library = TestLibrary::new();
+            create_colored_tag("Zebra", "#111111");
+            create_colored_tag("apple", "#abc");
+            create_colored_tag("Mango 10", "#333333");
+            let exported =
+                export_tags_json(Some(export_path.to_string_lossy().into_owned())).unwrap();
+            assert_eq!(exported.tags, 3);
+            assert_eq!(Path::new(&exported.path), export_path);
+            tag_summary()
+        };
+
+        let _library = TestLibrary::new();
+        let imported = import_tags_json(
+            export_path.to_string_lossy().into_owned(),
+            "merge".to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            (imported.created, imported.updated, imported.deleted),
+            (3, 0, 0)
+        );
+        assert!(imported.blocked.is_empty());
+        assert_eq!(tag_summary(), expected);
+    }
+
+    #[test]
+    fn merge_import_updates_colors_and_keeps_other_tags() {
+        let dir = tempfile::tempdir().unwrap();
+        let _library = TestLibrary::new();
+        create_colored_tag("keep", "#000000");
+        create_colored_tag("recolor", "#000000");
+        create_colored_tag("same", "#222222");
+        let path = write_tags_file(
+            dir.path(),
+            &[
+                ("RECOLOR", "#ff0000", 0),
+                ("same", "#222222", 2),
+                ("Same", "#999999", 3),
+                ("fresh", "#00ff00", 4),
+            ],
+        );
+
+        let imported = import_tags_json(path, "merge".to_string()).unwrap();
+
+        assert_eq!(
+            (imported.created, imported.updated, imported.skipped),
+            (1, 1, 2)
+        );
+        assert_eq!(imported.deleted, 0);
+        assert_eq!(
+            tag_summary(),
+            vec![
+                ("keep".to_string(), "#000000".to_string()),
+                ("recolor".to_string(), "#ff0000".to_string()),
+                ("same".to_string(), "#222222".to_string()),
+                ("fresh".to_string(), "#00ff00".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn replace_import_deletes_unused_tags_and_blocks_used_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = TestLibrary::new();
+        insert_note("note-1", "Tagged", None);
+        let used = create_colored_tag("used", "#000000");
+        create_colored_tag("unused", "#000000");
+        create_colored_tag("kept", "#000000");
+        update_item_tags(UpdateItemTagsInput {
+            item_id: "note-1".to_string(),
+            tag_ids: vec![used.id.clone()],
+        })
+        .unwrap();
+        let path = write_tags_file(
+            dir.path(),
+            &[("second", "#222222", 9), ("kept", "#111111", 1)],
+        );
+
+        let imported = import_tags_json(path, " replace ".to_string()).unwrap();
+
+        assert_eq!(
+            (imported.created, imported.updated, imported.deleted),
+            (1, 1, 1)
+        );
+        assert_eq!(imported.blocked, vec!["used".to_string()]);
+        assert_eq!(
+            tag_summary(),
+            vec![
+                ("kept".to_string(), "#111111".to_string()),
+                ("second".to_string(), "#222222".to_string()),
+                ("used".to_string(), "#000000".to_string()),
+            ]
+        );
+        assert_eq!(
+            get_item("note-1".to_string()).unwrap().item.tag_ids,
+            vec![used.id]
+        );
+        assert_eq!(
+            library.count("SELECT COUNT(*) FROM tags WHERE name = 'unused'"),
+            0
+        );
+    }
+
+    #[test]
+    fn tag_import_rejects_bad_modes_versions_and_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let _library = TestLibrary::new();
+        create_colored_tag("existing", "#000000");
+        let path = write_tags_file(dir.path(), &[("ok", "#123456", 0)]);
+        assert_eq!(
+            import_tags_json(path.clone(), "append".to_string())
+                .err()
+                .as_deref(),
+            Some("unsupported tag import mode: append")
+        );
+
+        let newer = dir.path().join("newer.json");
+        fs::write(
+            &newer,
+            format!(
+                r#"{{"version": {}, "exportedAt": 0, "tags": []}}"#,
+                TAGS_EXPORT_VERSION + 1
+            ),
+        )
+        .unwrap();
+        assert!(
+            import_tags_json(newer.to_string_lossy().into_owned(), "replace".to_string())
+                .err()
+                .unwrap()
+                .starts_with("unsupported tags file version")
+        );
+
+        let bad_color = write_tags_file(dir.path(), &[("ok", "#123456", 0), ("bad", "red", 1)]);
+        assert!(import_tags_json(bad_color, "replace".to_string()).is_err());
+        assert_eq!(
+            tag_summary(),
+            vec![("existing".to_string(), "#000000".to_string())]
+        );
+    }
+
+    fn insert_tagged_keyset_note(note: (&str, &str, i64, i64), tags: &[&str]) {
+        let mut input = keyset_note_input(note);
+        input.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        insert_item(input).unwrap();
+    }
+
+    #[test]
+    fn tag_keyset_pages_survive_inserts_for_every_sort() {
+        for spec in ITEM_SORT_SPECS {
+            let library = TestLibrary::new();
+            for note in KEYSET_SEED_NOTES {
+                insert_tagged_keyset_note(*note, &["paged"]);
+            }
+            let tag_id: String = library
+                .connection()
+                .query_row("SELECT id FROM tags WHERE name = 'paged'", [], |row| {
+                    row.get(0)
+                })
+                .unwrap();
+            assert_keyset_walk(
+                spec.name,
+                |limit, cursor| {
+                    get_items_by_tag(
+                        tag_id.clone(),
+                        Some(limit),
+                        None,
+                        Some(spec.name.to_string()),
+                        Some(true),
+                        cursor,
+                    )
+                    .unwrap()
+                },
+                |step| {
+                    let note = KEYSET_INSERTED_NOTES.get(step)?;
+                    insert_tagged_keyset_note(*note, &["paged"]);
+                    Some(note.0.to_string())
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn untagged_keyset_pages_survive_inserts() {
+        let _library = TestLibrary::new();
+        for note in KEYSET_SEED_NOTES {
+            insert_tagged_keyset_note(*note, &[]);
+        }
+        insert_tagged_keyset_note(("tagged", "tagged", 1_700_000_001_000, 1), &["other"]);
+        assert_keyset_walk(
+            "untagged",
+            |limit, cursor| {
+                let page =
+                    get_untagged_items(Some(limit), None, None, None, Some(true), cursor).unwrap();
+                assert!(page.items.iter().all(|item| item.id != "tagged"));
+                page
+            },
+            |step| {
+                let note = KEYSET_INSERTED_NOTES.get(step)?;
+                insert_tagged_keyset_note(*note, &[]);
+                Some(note.0.to_string())
+            },
+        );
+    }
+
+    #[test]
+    fn normalize_tag_color_accepts_short_and_long_hex_only() {
+        let cases: [(&str, Result<&str, &str>); 10] = [
+            ("#abc", Ok("#aabbcc")),
+            ("#ABC", Ok("#aabbcc")),
+            ("  #123  ", Ok("#112233")),
+            ("#AbCdEf", Ok("#abcdef")),
+            ("#64748b", Ok("#64748b")),
+            ("", Err("tag color cannot be empty")),
+            (
+                "#ggg",
+                Err("invalid tag color \"#ggg\": expected #rgb or #rrggbb"),
+            ),
+            (
+                "blueish",
+                Err("invalid tag color \"blueish\": expected #rgb or #rrggbb"),
+            ),
+            (
+                "abc",
+                Err("invalid tag color \"abc\": expected #rgb or #rrggbb"),
+            ),
+            (
+                "#abcd",
+                Err("invalid tag color \"#abcd\": expected #rgb or #rrggbb"),
+            ),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(
+                normalize_tag_color(raw),
+                expected.map(str::to_string).map_err(str::to_string),
+                "normalizing {:?}",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn stored_tag_colors_are_normalized_on_startup() {
+        let library = TestLibrary::new();
+        let connection = library.connection();
+        for (id, color) in [
+            ("tag-short", "#ABC"),
+            ("tag-bad-hex", "#ggg"),
+            ("tag-word", "blueish"),
+            ("tag-canonical", "#112233"),
+        ] {
+            connection
+                .execute(
+                    "INSERT INTO tags (id, name, color) VALUES (?1, ?1, ?2)",
+                    params![id, color],
+                )
+                .unwrap();
+        }
+
+        library.simulate_restart();
+        initialize_db().unwrap();
+
+        let stored_color = |id: &str| -> String {
+            library
+                .connection()
+                .query_row("SELECT color FROM tags WHERE id = ?1", [id], |row| {
+                    row.get(0)
+                })
+                .unwrap()
+        };
+        assert_eq!(stored_color("tag-short"), "#aabbcc");
+        assert_eq!(stored_color("tag-bad-hex"), DEFAULT_TAG_COLOR);
+        assert_eq!(stored_color("tag-word"), DEFAULT_TAG_COLOR);
+        assert_eq!(stored_color("tag-canonical"), "#112233");
+    }
+
+    #[test]
+    fn suggested_tag_colors_cycle_through_the_palette() {
+        let library = TestLibrary::new();
+        let existing = library.count("SELECT COUNT(*) FROM tags") as usize;
+        for offset in 0..TAG_COLOR_PALETTE.len() + 2 {
+            let expected = TAG_COLOR_PALETTE[(existing + offset) % TAG_COLOR_PALETTE.len()];
+            assert_eq!(suggest_tag_color().unwrap(), expected);
+            let tag = create_tag(CreateTagInput {
+                name: format!("Palette {}", offset),
+                color: None,
+                auto_color: true,
+            })
+            .unwrap();
+            assert_eq!(tag.color, expected);
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Shared fixtures for the in-crate tests.
+//!
+//! Every test that touches the library takes a [`TestLibrary`], which points
+//! `STUMBLE_APP_ROOT` at a fresh temp directory and runs the normal migrations against a real
+//! SQLite file. The app root is process-wide, so libraries are handed out one at a time.
+
+use rusqlite::Connection;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use tempfile::TempDir;
+
+use crate::collections::*;
+use crate::db::*;
+use crate::items::*;
+
+static TEST_LIBRARY_LOCK: Mutex<()> = Mutex::new(());
+
+pub(crate) struct TestLibrary {
+    root: TempDir,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl TestLibrary {
+    pub(crate) fn new() -> Self {
+        let guard = TEST_LIBRARY_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let root = tempfile::Builder::new()
+            .prefix("stumble-test-")
+            .tempdir()
+            .expect("create temp app root");
+        switch_app_root(root.path());
+        initialize_db().expect("initialize test library");
+        Self {
+            root,
+            _guard: guard,
+        }
+    }
+
+    /// Forgets process-wide library state, as if the app had been restarted.
+    pub(crate) fn simulate_restart(&self) {
+        switch_app_root(self.root.path());
+    }
+
+    pub(crate) fn storage_root(&self) -> PathBuf {
+        self.root.path().join("storage")
+    }
+
+    pub(crate) fn connection(&self) -> Connection {
+        open_db_connection().expect("open test library connection")
+    }
+
+    pub(crate) fn count(&self, sql: &str) -> i64 {
+        self.connection()
+            .query_row(sql, [], |row| row.get(0))
+            .unwrap_or_else(|err| panic!("count query failed ({}): {}", sql, err))
+    }
+}
+
+pub(crate) fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+pub(crate) fn fixture_bytes(name: &str) -> Vec<u8> {
+    let path = fixture_path(name);
+    std::fs::read(&path).unwrap_or_else(|err| panic!("read fixture {}: {}", path.display(), err))
+}
+
+pub(crate) fn note_input(id: &str, title: &str) -> InsertItemInput {
+    InsertItemInput {
+        id: id.to_string(),
+        collection_id: None,
+        item_type: "note".to_string(),
+        title: title.to_string(),
+        filename: None,
+        vault_key: None,
+        vault_path: None,
+        preview_url: None,
+        width: None,
+        height: None,
+        thumb_status: "skipped".to_string(),
+        import_status: DEFAULT_IMPORT_STATUS.to_string(),
+        url: None,
+        favicon_path: None,
+        meta_status: None,
+        description: None,
+        description_format: None,
+        rating: 0,
+        is_favorite: false,
+        created_at: 1_700_000_000_000,
+        updated_at: 1_700_000_000_000,
+        tags: Vec::new(),
+        original_source_path: None,
+        source: None,
+    }
+}
+
+pub(crate) fn insert_note(id: &str, title: &str, collection_id: Option<&str>) {
+    let mut input = note_input(id, title);
+    input.collection_id = collection_id.map(str::to_string);
+    insert_item(input).expect("insert test note");
+}
+
+pub(crate) fn create_test_collection(name: &str, parent_id: Option<&str>) -> String {
+    create_collection(
+        name.to_string(),
+        parent_id.map(str::to_string),
+        DEFAULT_ROOT_COLLECTION_ICON.to_string(),
+        DEFAULT_ROOT_COLLECTION_COLOR.to_string(),
+        None,
+        None,
+        None,
+    )
+    .expect("create test collection")
+    .id
+}
+
+pub(crate) fn item_collection_ids(item_id: &str) -> Vec<String> {
+    let connection = open_db_connection().expect("open connection");
+    let mut stmt = connection
+        .prepare(
+            "SELECT collection_id FROM collection_items WHERE item_id = ?1 ORDER BY collection_id",
+        )
+        .expect("prepare membership query");
+    let rows = stmt
+        .query_map([item_id], |row| row.get::<_, String>(0))
+        .expect("query memberships");
+    rows.map(|row| row.expect("membership row")).collect()
+}
+
+/// (id, title, created_at, rating) rows with ties on every sort key, including titles that
+/// only differ by case and so compare equal under `COLLATE NOCASE`.
+pub(crate) const KEYSET_SEED_NOTES: &[(&str, &str, i64, i64)] = &[
+    ("k-10", "alpha", 1_700_000_000_000, 3),
+    ("k-11", "Alpha", 1_700_000_000_000, 3),
+    ("k-12", "ALPHA", 1_700_000_001_000, 5),
+    ("k-13", "beta", 1_700_000_001_000, 1),
+    ("k-14", "img2", 1_700_000_002_000, 3),
+    ("k-15", "img10", 1_700_000_002_000, 1),
+    ("k-16", "Beta", 1_700_000_003_000, 5),
+];
+
+/// Rows inserted between page fetches: some sort before the cursor in every order, some
+/// after, and the rest tie with seeded rows and only differ by id.
+pub(crate) const KEYSET_INSERTED_NOTES: &[(&str, &str, i64, i64)] = &[
+    ("k-05", "aardvark", 1_699_000_000_000, 5),
+    ("k-19", "alpha", 1_700_000_000_000, 3),
+    ("k-20", "zebra", 1_701_000_000_000, 1),
+    ("k-01", "ALPHA", 1_700_000_001_000, 5),
+    ("k-21", "img3", 1_700_000_002_000, 3),
+    ("k-22", "BETA", 1_700_000_003_000, 1),
+];
+
+pub(crate) fn keyset_note_input(note: (&str, &str, i64, i64)) -> InsertItemInput {
+    let (id, title, created_at, rating) = note;
+    let mut input = note_input(id, title);
+    input.created_at = created_at;
+    input.updated_at = created_at;
+    input.rating = rating;
+    input.is_favorite = true;
+    input
+}
+
+/// Walks a keyset-paginated view two rows at a time, calling `insert_between` after every
+/// page, and checks the walk against a final unpaged fetch: no row repeats, rows come back in
+/// the final order, every row that existed up front is visited, and a row inserted mid-walk
+/// is visited exactly when it sorts after the cursor it was inserted behind.
+pub(crate) fn assert_keyset_walk(
+    label: &str,
+    mut fetch: impl FnMut(u32, Option<String>) -> ItemPage,
+    mut insert_between: impl FnMut(usize) -> Option<String>,
+) {
+    let page_ids = |page: &ItemPage| -> Vec<String> {
+        page.items.iter().map(|item| item.id.clone()).collect()
+    };
+    let initial = page_ids(&fetch(ITEM_PAGE_MAX_LIMIT, None));
+    let mut visited: Vec<String> = Vec::new();
+    let mut inserted: Vec<(String, String)> = Vec::new();
+    let mut cursor = None;
+    for step in 0.. {
+        assert!(step < 100, "{}: keyset walk did not terminate", label);
+        let page = fetch(2, cursor.take());
+        visited.extend(page_ids(&page));
+        let Some(next_cursor) = page.next_cursor else {
+            break;
+        };
+        if let Some(id) = insert_between(step) {
+            inserted.push((id, visited.last().cloned().expect("cursor row")));
+        }
+        cursor = Some(next_cursor);
+    }
+
+    let final_ids = page_ids(&fetch(ITEM_PAGE_MAX_LIMIT, None));
+    let position = |id: &str| {
+        final_ids
+            .iter()
+            .position(|candidate| candidate == id)
+            .unwrap_or_else(|| panic!("{}: {} missing from the final order", label, id))
+    };
+    let positions: Vec<usize> = visited.iter().map(|id| position(id)).collect();
+    assert!(
+        positions.windows(2).all(|pair| pair[0] < pair[1]),
+        "{}: walk {:?} repeats or reorders rows of {:?}",
+        label,
+        visited,
+        final_ids
+    );
+    for id in &initial {
+        assert!(visited.contains(id), "{}: skipped {}", label, id);
+    }
+    for (id, cursor_id) in &inserted {
+        assert_eq!(
+            visited.contains(id),
+            position(id) > position(cursor_id),
+            "{}: {} inserted behind {} in {:?}",
+            label,
+            id,
+            cursor_id,
+            final_ids
+        );
+    }
+}
+
+/// Serves `routes` (path, content type, body) over plain HTTP on a loopback port for the rest
+/// of the test process and returns the base URL. Unknown paths get a 404.
+pub(crate) fn serve_http(routes: Vec<(&'static str, &'static str, Vec<u8>)>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind test http server");
+    let base_url = format!(
+        "http://{}",
+        listener.local_addr().expect("test server address")
+    );
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let mut reader = BufReader::new(stream.try_clone().expect("clone test stream"));
+            let mut request_line = String::new();
+            if reader.read_line(&mut request_line).is_err() {
+                continue;
+            }
+            loop {
+                let mut header = String::new();
+                match reader.read_line(&mut header) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) if header.trim().is_empty() => break,
+                    Ok(_) => {}
+                }
+            }
+            let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+            let (status, content_type, body) = routes
+                .iter()
+                .find(|(route, _, _)| *route == path)
+                .map(|(_, content_type, body)| ("200 OK", *content_type, body.as_slice()))
+                .unwrap_or(("404 Not Found", "text/plain", b"not found".as_slice()));
+            let head = format!(
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                content_type,
+                body.len()
+            );
+            let _ = stream.write_all(head.as_bytes());
+            let _ = stream.write_all(body);
+        }
+    });
+    base_url
+}
@@ -0,0 +1,1234 @@
+use chrono::Utc;
+use image::{imageops::FilterType, DynamicImage, GenericImageView, ImageError, ImageReader};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::*;
+use crate::db::*;
+use crate::items::*;
+use crate::vault::*;
+
+pub(crate) const IMPORT_THUMB_MAX_SIZE: u32 = 480;
+pub(crate) const THUMB_WEBP_QUALITY: f32 = 60.0;
+pub(crate) const THUMB_MIN_FREE_SPACE_BYTES: u64 = 128 * 1024 * 1024;
+pub(crate) const DIMENSION_BACKFILL_BATCH_SIZE: usize = 64;
+pub(crate) const DIMENSION_BACKFILL_IDLE_LIMIT: usize = 32;
+pub(crate) const DIMENSION_BACKFILL_POLL_SECS: u64 = 5 * 60;
+pub(crate) const SETTING_IMPORT_THUMB_POLICY: &str = "import_thumb_policy";
+pub(crate) const IMPORT_THUMB_POLICY_IMMEDIATE: &str = "immediate";
+pub(crate) const IMPORT_THUMB_POLICY_DEFERRED: &str = "deferred";
+pub(crate) const IMPORT_THUMB_POLICY_NEVER: &str = "never";
+pub(crate) const OCR_PROGRESS_EVENT: &str = "ocr://progress";
+pub(crate) const OCR_BATCH_MAX_LIMIT: u32 = 500;
+pub(crate) const OCR_ENGINE_NAME: &str = "tesseract";
+pub(crate) const IMAGE_DECODE_FAILURE_TRUNCATED: &str = "truncated";
+pub(crate) const IMAGE_DECODE_FAILURE_UNSUPPORTED: &str = "unsupported";
+pub(crate) const IMAGE_DECODE_FAILURE_CORRUPT: &str = "corrupt";
+
+pub(crate) struct ThumbnailTimings {
+    pub(crate) source_width: u32,
+    pub(crate) source_height: u32,
+    pub(crate) target_width: u32,
+    pub(crate) target_height: u32,
+    pub(crate) resize_ms: u64,
+    pub(crate) encode_ms: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DimensionBackfillResult {
+    pub(crate) scanned: usize,
+    pub(crate) updated: usize,
+    pub(crate) failed: usize,
+    pub(crate) skipped_locked: usize,
+    pub(crate) remaining: i64,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ItemOcrResult {
+    pub(crate) item_id: String,
+    pub(crate) status: String,
+    pub(crate) text: String,
+    pub(crate) confidence: Option<f64>,
+    pub(crate) error: Option<String>,
+    pub(crate) updated_at: i64,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OcrProgressPayload {
+    pub(crate) processed: usize,
+    pub(crate) total: usize,
+    pub(crate) item_id: String,
+    pub(crate) status: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OcrBatchResult {
+    pub(crate) processed: usize,
+    pub(crate) done: usize,
+    pub(crate) empty: usize,
+    pub(crate) failed: usize,
+    pub(crate) unavailable: usize,
+    pub(crate) cancelled: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct OcrQueueState {
+    pub(crate) running: AtomicBool,
+    pub(crate) cancel_requested: AtomicBool,
+}
+
+pub(crate) fn thumbnail_disk_space_shortfall() -> Result<Option<DiskSpaceLowPayload>, String> {
+    let root = ensure_thumbs_root_internal()?;
+    let available = read_available_disk_space(&root)?;
+    if available >= THUMB_MIN_FREE_SPACE_BYTES {
+        return Ok(None);
+    }
+    Ok(Some(DiskSpaceLowPayload {
+        path: path_to_string(&root)?,
+        required_bytes: THUMB_MIN_FREE_SPACE_BYTES,
+        available_bytes: available,
+        operation: "thumbnails".to_string(),
+    }))
+}
+
+pub(crate) fn ensure_thumbnail_disk_space(app: &AppHandle) -> Result<(), String> {
+    let Some(shortfall) = thumbnail_disk_space_shortfall()? else {
+        return Ok(());
+    };
+    if let Err(err) = app.emit(DISK_SPACE_LOW_EVENT, shortfall.clone()) {
+        log_eprintln!("[disk-space] failed to emit low-space event: {}", err);
+    }
+    Err(format!(
+        "thumbnail generation paused: {}",
+        insufficient_disk_space_error(
+            Path::new(&shortfall.path),
+            shortfall.required_bytes,
+            shortfall.available_bytes
+        )
+    ))
+}
+
+pub(crate) fn start_idle_dimension_backfill(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(DIMENSION_BACKFILL_POLL_SECS));
+        let idle = DB_ACTIVITY
+            .lock()
+            .map(|activity| activity.imports_in_flight == 0 && !activity.maintenance_running)
+            .unwrap_or(false);
+        let maintenance_running = app
+            .try_state::<VaultMaintenanceState>()
+            .map(|state| state.running.load(Ordering::SeqCst))
+            .unwrap_or(false);
+        if !idle || maintenance_running {
+            continue;
+        }
+        let reporter = ProgressReporter::silent("dimension-backfill");
+        let outcome = initialize_db()
+            .and_then(|_| open_db_connection())
+            .and_then(|connection| {
+                backfill_item_dimensions_internal(
+                    &connection,
+                    &reporter,
+                    Some(DIMENSION_BACKFILL_IDLE_LIMIT),
+                )
+            });
+        match outcome {
+            Ok(result) if result.scanned > 0 => log_println!(
+                "[dimension-backfill] idle batch updated {} item(s), {} failed, {} remaining",
+                result.updated,
+                result.failed,
+                result.remaining
+            ),
+            Ok(_) => {}
+            Err(err) => log_eprintln!("[dimension-backfill] idle batch failed: {}", err),
+        }
+    });
+}
+
+pub(crate) fn thumbs_root_path() -> Result<PathBuf, String> {
+    Ok(app_root_path()?.join("thumbs"))
+}
+
+pub(crate) fn ensure_thumbs_root_internal() -> Result<PathBuf, String> {
+    let root = thumbs_root_path()?;
+    fs::create_dir_all(&root)
+        .map_err(|err| format!("failed to create thumbs root {}: {}", root.display(), err))?;
+    Ok(root)
+}
+
+pub(crate) fn thumb_filename_for_vault_key(vault_key: &str) -> Result<String, String> {
+    let trimmed = vault_key.trim();
+    if trimmed.is_empty() {
+        return Err("cannot build thumb filename from empty vault key".to_string());
+    }
+
+    let sanitized: String = trimmed
+        .chars()
+        .filter(|ch| ch.is_ascii_alphanumeric() || *ch == '.' || *ch == '-' || *ch == '_')
+        .collect();
+    if sanitized.is_empty() {
+        return Err(format!(
+            "invalid vault key for thumb filename: {}",
+            vault_key
+        ));
+    }
+
+    Ok(format!("{sanitized}.webp"))
+}
+
+pub(crate) fn thumb_output_path_for_vault_key(vault_key: &str) -> Result<PathBuf, String> {
+    let root = ensure_thumbs_root_internal()?;
+    let filename = thumb_filename_for_vault_key(vault_key)?;
+    Ok(root.join(filename))
+}
+
+pub(crate) fn remove_thumbnail_for_vault_key(vault_key: &str) -> Result<bool, String> {
+    let thumb_path = thumb_output_path_for_vault_key(vault_key)?;
+    if !thumb_path.exists() {
+        return Ok(true);
+    }
+    remove_cleanup_file(&thumb_path)
+}
+
+pub(crate) fn generate_thumbnail_internal(
+    input_path: &Path,
+    output_path: &Path,
+    max_size: u32,
+) -> Result<(), String> {
+    let total_started_at = Instant::now();
+
+    if !input_path.exists() {
+        return Err(format!(
+            "thumbnail source file does not exist: {}",
+            input_path.display()
+        ));
+    }
+    if !input_path.is_file() {
+        return Err(format!(
+            "thumbnail source is not a file: {}",
+            input_path.display()
+        ));
+    }
+
+    if output_path.exists() {
+        log_println!(
+            "[thumb-gen] skip-existing source={} output={}",
+            input_path.display(),
+            output_path.display()
+        );
+        return Ok(());
+    }
+
+    let decode_started_at = Instant::now();
+    let source_image = decode_image_file(input_path)?;
+    let decode_ms = decode_started_at.elapsed().as_millis() as u64;
+
+    let timings = generate_thumbnail_from_image(source_image, input_path, output_path, max_size)?;
+    log_thumbnail_generation(
+        input_path,
+        output_path,
+        max_size,
+        decode_ms,
+        &timings,
+        total_started_at.elapsed().as_millis() as u64,
+    );
+
+    Ok(())
+}
+
+pub(crate) fn generate_thumbnail_from_image(
+    source_image: DynamicImage,
+    source_label: &Path,
+    output_path: &Path,
+    max_size: u32,
+) -> Result<ThumbnailTimings, String> {
+    if is_encrypted_vault_path(source_label) {
+        return Err(format!(
+            "thumbnails are not written for encrypted vault files: {}",
+            source_label.display()
+        ));
+    }
+    let (width, height) = source_image.dimensions();
+    if width == 0 || height == 0 {
+        return Err(format!(
+            "invalid image dimensions for thumbnail source {}: {}x{}",
+            source_label.display(),
+            width,
+            height
+        ));
+    }
+
+    if let Some(parent_dir) = output_path.parent() {
+        fs::create_dir_all(parent_dir).map_err(|err| {
+            format!(
+                "failed to create thumbnail output directory {}: {}",
+                parent_dir.display(),
+                err
+            )
+        })?;
+    }
+
+    let bounded_max = max_size.max(1);
+    let longest_side = width.max(height);
+    let resize_started_at = Instant::now();
+    let resized_image = if longest_side > bounded_max {
+        let scale = bounded_max as f64 / longest_side as f64;
+        let target_width = ((width as f64) * scale).round().max(1.0) as u32;
+        let target_height = ((height as f64) * scale).round().max(1.0) as u32;
+        source_image.resize(target_width, target_height, FilterType::Triangle)
+    } else {
+        source_image
+    };
+    let resize_ms = resize_started_at.elapsed().as_millis() as u64;
+    let (resized_width, resized_height) = resized_image.dimensions();
+
+    let encode_started_at = Instant::now();
+    let rgba = resized_image.to_rgba8();
+    let encoder = webp::Encoder::from_rgba(rgba.as_raw(), resized_width, resized_height);
+    let encoded = encoder.encode(THUMB_WEBP_QUALITY);
+    let mut output_file = File::create(output_path).map_err(|err| {
+        format!(
+            "failed to create thumbnail output {}: {}",
+            output_path.display(),
+            err
+        )
+    })?;
+
+    output_file.write_all(encoded.as_ref()).map_err(|err| {
+        format!(
+            "failed to write thumbnail output {}: {}",
+            output_path.display(),
+            err
+        )
+    })?;
+    output_file.flush().map_err(|err| {
+        format!(
+            "failed to flush thumbnail output {}: {}",
+            output_path.display(),
+            err
+        )
+    })?;
+    let encode_ms = encode_started_at.elapsed().as_millis() as u64;
+
+    Ok(ThumbnailTimings {
+        source_width: width,
+        source_height: height,
+        target_width: resized_width,
+        target_height: resized_height,
+        resize_ms,
+        encode_ms,
+    })
+}
+
+pub(crate) fn log_thumbnail_generation(
+    input_path: &Path,
+    output_path: &Path,
+    max_size: u32,
+    decode_ms: u64,
+    timings: &ThumbnailTimings,
+    total_ms: u64,
+) {
+    log_println!(
+        "[thumb-gen] source={} output={} source_w={} source_h={} target_w={} target_h={} max_size={} quality={} decode_ms={} resize_ms={} encode_ms={} total_ms={}",
+        input_path.display(),
+        output_path.display(),
+        timings.source_width,
+        timings.source_height,
+        timings.target_width,
+        timings.target_height,
+        max_size.max(1),
+        THUMB_WEBP_QUALITY,
+        decode_ms,
+        timings.resize_ms,
+        timings.encode_ms,
+        total_ms
+    );
+}
+
+pub(crate) fn is_image_extension(ext: &str) -> bool {
+    matches!(
+        normalize_ext(ext).as_str(),
+        "png" | "jpg" | "jpeg" | "webp" | "gif" | "bmp"
+    )
+}
+
+pub(crate) fn decode_image_file(input_path: &Path) -> Result<DynamicImage, String> {
+    if is_encrypted_vault_path(input_path) {
+        return ImageReader::new(Cursor::new(decrypt_vault_file_to_vec(input_path)?))
+            .with_guessed_format()
+            .map_err(|err| {
+                format!(
+                    "failed to detect image format {}: {}",
+                    input_path.display(),
+                    err
+                )
+            })?
+            .decode()
+            .map_err(|err| format!("failed to decode image {}: {}", input_path.display(), err));
+    }
+    ImageReader::open(input_path)
+        .map_err(|err| format!("failed to open image {}: {}", input_path.display(), err))?
+        .with_guessed_format()
+        .map_err(|err| {
+            format!(
+                "failed to detect image format {}: {}",
+                input_path.display(),
+                err
+            )
+        })?
+        .decode()
+        .map_err(|err| format!("failed to decode image {}: {}", input_path.display(), err))
+}
+
+pub(crate) fn classify_image_decode_error(err: &ImageError) -> &'static str {
+    match err {
+        ImageError::Unsupported(_) => IMAGE_DECODE_FAILURE_UNSUPPORTED,
+        ImageError::IoError(io_err) if io_err.kind() == std::io::ErrorKind::UnexpectedEof => {
+            IMAGE_DECODE_FAILURE_TRUNCATED
+        }
+        ImageError::Decoding(_) | ImageError::IoError(_) => {
+            let message = err.to_string().to_ascii_lowercase();
+            if [
+                "eof",
+                "end of file",
+                "unexpected end",
+                "truncated",
+                "not enough data",
+            ]
+            .iter()
+            .any(|needle| message.contains(needle))
+            {
+                IMAGE_DECODE_FAILURE_TRUNCATED
+            } else {
+                IMAGE_DECODE_FAILURE_CORRUPT
+            }
+        }
+        _ => IMAGE_DECODE_FAILURE_CORRUPT,
+    }
+}
+
+pub(crate) fn diagnose_image_decode_failure(input_path: &Path) -> Option<(&'static str, String)> {
+    let bytes = if is_encrypted_vault_path(input_path) {
+        decrypt_vault_file_to_vec(input_path).ok()?
+    } else {
+        fs::read(input_path).ok()?
+    };
+    if bytes.is_empty() {
+        return Some((IMAGE_DECODE_FAILURE_TRUNCATED, "file is empty".to_string()));
+    }
+    let reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?;
+    if reader.format().is_none() {
+        return Some((
+            IMAGE_DECODE_FAILURE_UNSUPPORTED,
+            "unrecognized image format".to_string(),
+        ));
+    }
+    match reader.decode() {
+        Ok(_) => None,
+        Err(err) => Some((classify_image_decode_error(&err), err.to_string())),
+    }
+}
+
+pub(crate) fn read_image_dimensions(input_path: &Path) -> Result<(u32, u32), String> {
+    if is_encrypted_vault_path(input_path) {
+        return ImageReader::new(Cursor::new(decrypt_vault_file_to_vec(input_path)?))
+            .with_guessed_format()
+            .map_err(|err| {
+                format!(
+                    "failed to detect image format {}: {}",
+                    input_path.display(),
+                    err
+                )
+            })?
+            .into_dimensions()
+            .map_err(|err| {
+                format!(
+                    "failed to read image dimensions {}: {}",
+                    input_path.display(),
+                    err
+                )
+            });
+    }
+    let reader = ImageReader::open(input_path)
+        .map_err(|err| format!("failed to open image {}: {}", input_path.display(), err))?
+        .with_guessed_format()
+        .map_err(|err| {
+            format!(
+                "failed to detect image format {}: {}",
+                input_path.display(),
+                err
+            )
+        })?;
+    reader.into_dimensions().map_err(|err| {
+        format!(
+            "failed to read image dimensions {}: {}",
+            input_path.display(),
+            err
+        )
+    })
+}
+
+pub(crate) fn read_image_source_bytes(input_path: &Path) -> Result<Vec<u8>, String> {
+    if is_encrypted_vault_path(input_path) {
+        return decrypt_vault_file_to_vec(input_path);
+    }
+    fs::read(input_path)
+        .map_err(|err| format!("failed to read image {}: {}", input_path.display(), err))
+}
+
+pub(crate) fn image_reader_for_bytes(
+    bytes: &[u8],
+) -> Result<ImageReader<Cursor<&[u8]>>, ImageError> {
+    ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(ImageError::IoError)
+}
+
+pub(crate) fn read_image_dimensions_from_bytes(
+    bytes: &[u8],
+    source_label: &Path,
+) -> Result<(u32, u32), String> {
+    image_reader_for_bytes(bytes)
+        .and_then(|reader| reader.into_dimensions())
+        .map_err(|err| {
+            format!(
+                "failed to read image dimensions {}: {}",
+                source_label.display(),
+                err
+            )
+        })
+}
+
+pub(crate) fn decode_image_bytes(bytes: &[u8]) -> Result<DynamicImage, ImageError> {
+    image_reader_for_bytes(bytes)?.decode()
+}
+
+pub(crate) fn normalize_import_thumb_policy(value: &str) -> Result<String, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        IMPORT_THUMB_POLICY_IMMEDIATE => Ok(IMPORT_THUMB_POLICY_IMMEDIATE.to_string()),
+        IMPORT_THUMB_POLICY_DEFERRED => Ok(IMPORT_THUMB_POLICY_DEFERRED.to_string()),
+        IMPORT_THUMB_POLICY_NEVER => Ok(IMPORT_THUMB_POLICY_NEVER.to_string()),
+        other => Err(format!(
+            "unsupported import thumbnail policy \"{}\"; expected immediate, deferred or never",
+            other
+        )),
+    }
+}
+
+pub(crate) fn configured_import_thumb_policy(
+    connection: &Connection,
+) -> Result<Option<String>, String> {
+    Ok(read_app_setting(connection, SETTING_IMPORT_THUMB_POLICY)?
+        .and_then(|value| normalize_import_thumb_policy(&value).ok()))
+}
+
+pub(crate) fn load_import_thumb_policy(fallback: &str) -> Result<String, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    Ok(configured_import_thumb_policy(&connection)?.unwrap_or_else(|| fallback.to_string()))
+}
+
+pub(crate) fn resolve_import_thumb_policy(generate_thumb: Option<bool>) -> Result<String, String> {
+    match generate_thumb {
+        Some(true) => Ok(IMPORT_THUMB_POLICY_IMMEDIATE.to_string()),
+        Some(false) => Ok(IMPORT_THUMB_POLICY_DEFERRED.to_string()),
+        None => load_import_thumb_policy(IMPORT_THUMB_POLICY_IMMEDIATE),
+    }
+}
+
+#[tauri::command]
+pub(crate) fn get_import_thumb_policy() -> Result<Option<String>, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    configured_import_thumb_policy(&connection)
+}
+
+#[tauri::command]
+pub(crate) fn set_import_thumb_policy(policy: Option<String>) -> Result<Option<String>, String> {
+    let normalized = normalize_optional_trimmed_string(policy)
+        .map(|value| normalize_import_thumb_policy(&value))
+        .transpose()?;
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    write_app_setting(
+        &connection,
+        SETTING_IMPORT_THUMB_POLICY,
+        normalized.as_deref().unwrap_or(""),
+    )?;
+    Ok(normalized)
+}
+
+pub(crate) fn ocr_engine_command(engine_path: &Path) -> Command {
+    #[allow(unused_mut)]
+    let mut command = Command::new(engine_path);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(0x0800_0000);
+    }
+    command
+}
+
+pub(crate) fn resolve_ocr_engine_path() -> Option<PathBuf> {
+    let executable = if cfg!(windows) {
+        "tesseract.exe"
+    } else {
+        "tesseract"
+    };
+    if let Ok(app_root) = app_root_path() {
+        let bundled = app_root.join("tesseract").join(executable);
+        if bundled.is_file() {
+            return Some(bundled);
+        }
+    }
+    let on_path = PathBuf::from(executable);
+    ocr_engine_command(&on_path)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|_| on_path)
+}
+
+pub(crate) fn parse_tesseract_tsv(tsv: &str) -> (String, Option<f64>) {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current_line_key: Option<(String, String, String, String)> = None;
+    let mut current_words: Vec<String> = Vec::new();
+    let mut confidence_sum = 0.0;
+    let mut confidence_count = 0usize;
+
+    for row in tsv.lines().skip(1) {
+        let columns: Vec<&str> = row.splitn(12, '\t').collect();
+        if columns.len() < 12 || columns[0] != "5" {
+            continue;
+        }
+        let word = columns[11].trim();
+        let confidence = columns[10].trim().parse::<f64>().unwrap_or(-1.0);
+        if word.is_empty() || confidence < 0.0 {
+            continue;
+        }
+
+        let line_key = (
+            columns[1].to_string(),
+            columns[2].to_string(),
+            columns[3].to_string(),
+            columns[4].to_string(),
+        );
+        if current_line_key.as_ref() != Some(&line_key) {
+            if !current_words.is_empty() {
+                lines.push(current_words.join(" "));
+                current_words.clear();
+            }
+            current_line_key = Some(line_key);
+        }
+        current_words.push(word.to_string());
+        confidence_sum += confidence;
+        confidence_count += 1;
+    }
+    if !current_words.is_empty() {
+        lines.push(current_words.join(" "));
+    }
+
+    let confidence =
+        (confidence_count > 0).then(|| confidence_sum / confidence_count as f64 / 100.0);
+    (lines.join("\n"), confidence)
+}
+
+pub(crate) fn run_ocr_engine(
+    engine_path: &Path,
+    image_path: &Path,
+) -> Result<(String, Option<f64>), String> {
+    let output = ocr_engine_command(engine_path)
+        .arg(image_path)
+        .arg("stdout")
+        .arg("tsv")
+        .output()
+        .map_err(|err| format!("failed to start ocr engine: {}", err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "ocr engine exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(parse_tesseract_tsv(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+pub(crate) fn ocr_item_internal(
+    item_id: &str,
+    engine_path: Option<&Path>,
+) -> Result<ItemOcrResult, String> {
+    let connection = open_db_connection()?;
+    let (item_type, vault_path) = connection
+        .query_row(
+            "SELECT type, vault_path FROM items WHERE id = ?1",
+            params![item_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()
+        .map_err(|err| format!("failed to read item for ocr: {}", err))?
+        .ok_or_else(|| "item not found while running ocr".to_string())?;
+    if item_type != "image" {
+        return Err("ocr is only supported for image items".to_string());
+    }
+
+    let (status, text, confidence, error) = match engine_path {
+        None => (
+            "unavailable",
+            String::new(),
+            None,
+            Some("no ocr engine is installed".to_string()),
+        ),
+        Some(engine_path) => match run_ocr_engine(engine_path, Path::new(&vault_path)) {
+            Ok((text, _)) if text.trim().is_empty() => ("empty", String::new(), None, None),
+            Ok((text, confidence)) => ("done", text, confidence, None),
+            Err(err) => ("failed", String::new(), None, Some(err)),
+        },
+    };
+
+    let updated_at = Utc::now().timestamp_millis();
+    connection
+        .execute(
+            "INSERT INTO item_ocr (item_id, status, text, confidence, engine, error, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(item_id) DO UPDATE SET
+                status = excluded.status,
+                text = excluded.text,
+                confidence = excluded.confidence,
+                engine = excluded.engine,
+                error = excluded.error,
+                updated_at = excluded.updated_at",
+            params![
+                item_id,
+                status,
+                &text,
+                confidence,
+                engine_path.map(|_| OCR_ENGINE_NAME),
+                &error,
+                updated_at
+            ],
+        )
+        .map_err(|err| format!("failed to store ocr result: {}", err))?;
+
+    Ok(ItemOcrResult {
+        item_id: item_id.to_string(),
+        status: status.to_string(),
+        text,
+        confidence,
+        error,
+        updated_at,
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn ocr_item(item_id: String) -> Result<ItemOcrResult, String> {
+    let normalized_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+    initialize_db()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let engine_path = resolve_ocr_engine_path();
+        ocr_item_internal(&normalized_id, engine_path.as_deref())
+    })
+    .await
+    .map_err(|err| format!("ocr thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) async fn ocr_pending_items(
+    app: AppHandle,
+    state: State<'_, OcrQueueState>,
+    limit: Option<u32>,
+) -> Result<OcrBatchResult, String> {
+    if state.running.swap(true, Ordering::SeqCst) {
+        return Err("an ocr batch is already running".to_string());
+    }
+    state.cancel_requested.store(false, Ordering::SeqCst);
+
+    let batch_limit = limit.unwrap_or(50).clamp(1, OCR_BATCH_MAX_LIMIT);
+    let outcome = tauri::async_runtime::spawn_blocking(move || {
+        initialize_db()?;
+        let connection = open_db_connection()?;
+        let mut stmt = connection
+            .prepare(
+                "SELECT i.id
+                 FROM items AS i
+                 LEFT JOIN item_ocr AS o ON o.item_id = i.id
+                 WHERE i.type = 'image' AND o.item_id IS NULL
+                 ORDER BY i.created_at DESC
+                 LIMIT ?1",
+            )
+            .map_err(|err| format!("failed to prepare pending ocr query: {}", err))?;
+        let rows = stmt
+            .query_map(params![batch_limit], |row| row.get::<_, String>(0))
+            .map_err(|err| format!("failed to query pending ocr items: {}", err))?;
+        let mut item_ids = Vec::new();
+        for row_result in rows {
+            item_ids.push(
+                row_result.map_err(|err| format!("failed to read pending ocr item: {}", err))?,
+            );
+        }
+        drop(stmt);
+        drop(connection);
+
+        let engine_path = resolve_ocr_engine_path();
+        let queue_state = app.state::<OcrQueueState>();
+        let total = item_ids.len();
+        let mut result = OcrBatchResult {
+            processed: 0,
+            done: 0,
+            empty: 0,
+            failed: 0,
+            unavailable: 0,
+            cancelled: false,
+        };
+        for item_id in item_ids {
+            if queue_state.cancel_requested.load(Ordering::SeqCst) {
+                result.cancelled = true;
+                break;
+            }
+            let status = match ocr_item_internal(&item_id, engine_path.as_deref()) {
+                Ok(ocr) => ocr.status,
+                Err(err) => {
+                    log_eprintln!("[ocr] failed to process item {}: {}", item_id, err);
+                    "failed".to_string()
+                }
+            };
+            match status.as_str() {
+                "done" => result.done += 1,
+                "empty" => result.empty += 1,
+                "unavailable" => result.unavailable += 1,
+                _ => result.failed += 1,
+            }
+            result.processed += 1;
+            let _ = app.emit(
+                OCR_PROGRESS_EVENT,
+                OcrProgressPayload {
+                    processed: result.processed,
+                    total,
+                    item_id,
+                    status,
+                },
+            );
+        }
+        Ok::<OcrBatchResult, String>(result)
+    })
+    .await
+    .map_err(|err| format!("ocr batch thread join failed: {}", err));
+
+    state.running.store(false, Ordering::SeqCst);
+    outcome?
+}
+
+#[tauri::command]
+pub(crate) fn cancel_ocr_batch(state: State<'_, OcrQueueState>) -> bool {
+    let running = state.running.load(Ordering::SeqCst);
+    if running {
+        state.cancel_requested.store(true, Ordering::SeqCst);
+    }
+    running
+}
+
+pub(crate) fn refresh_vault_file_media(
+    vault_key: &str,
+    vault_path: &Path,
+) -> (Option<i64>, Option<i64>, String) {
+    let _in_use = VaultKeyUseGuard::acquire(vault_key);
+    let ext = parse_vault_key(vault_key)
+        .map(|(_, ext)| ext)
+        .unwrap_or_else(|_| extension_from_path(vault_path));
+    if !is_image_extension(&ext) {
+        return (None, None, "ready".to_string());
+    }
+    let (width, height) = match read_image_dimensions(vault_path) {
+        Ok((width, height)) => (width, height),
+        Err(err) => {
+            log_eprintln!(
+                "[item-media] failed to read dimensions for {}: {}",
+                vault_path.display(),
+                err
+            );
+            return (None, None, "error".to_string());
+        }
+    };
+    if width.max(height) <= IMPORT_THUMB_MAX_SIZE || is_encrypted_vault_path(vault_path) {
+        return (
+            Some(i64::from(width)),
+            Some(i64::from(height)),
+            "skipped".to_string(),
+        );
+    }
+    let thumb_status = match thumb_output_path_for_vault_key(vault_key) {
+        Ok(path) if path.is_file() => "ready".to_string(),
+        Ok(path) => match generate_thumbnail_internal(vault_path, &path, IMPORT_THUMB_MAX_SIZE) {
+            Ok(_) => "ready".to_string(),
+            Err(err) => {
+                log_eprintln!(
+                    "[item-media] failed to generate thumbnail for {}: {}",
+                    vault_key,
+                    err
+                );
+                "error".to_string()
+            }
+        },
+        Err(err) => {
+            log_eprintln!(
+                "[item-media] failed to compute thumbnail path for {}: {}",
+                vault_key,
+                err
+            );
+            "error".to_string()
+        }
+    };
+    (
+        Some(i64::from(width)),
+        Some(i64::from(height)),
+        thumb_status,
+    )
+}
+
+#[tauri::command]
+pub(crate) async fn generate_thumbnail_for_item(
+    app: AppHandle,
+    item_id: String,
+) -> Result<DbItemRow, String> {
+    let normalized_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        initialize_db()?;
+        ensure_thumbnail_disk_space(&app)?;
+        let connection = open_db_connection()?;
+        let (item_type, vault_key, vault_path) = connection
+            .query_row(
+                "SELECT i.type, i.vault_key, COALESCE(vf.vault_path, i.vault_path)
+                 FROM items AS i
+                 LEFT JOIN vault_files AS vf ON vf.vault_key = i.vault_key
+                 WHERE i.id = ?1",
+                params![&normalized_item_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(|err| format!("failed to read item for thumbnail generation: {}", err))?
+            .ok_or_else(|| "item not found".to_string())?;
+        if item_type != "image" {
+            return Err("thumbnails can only be generated for image items".to_string());
+        }
+
+        let stored_path = path_from_ipc_string(&vault_path);
+        let source_path = if stored_path.is_file() {
+            stored_path
+        } else {
+            find_existing_vault_file(&ensure_storage_root_internal()?, &vault_key)?
+                .ok_or_else(|| format!("vault file is missing for item: {}", vault_key))?
+        };
+        let (width, height, thumb_status) = refresh_vault_file_media(&vault_key, &source_path);
+        drop(connection);
+        update_item_media_state(UpdateItemMediaStateInput {
+            item_id: normalized_item_id.clone(),
+            width,
+            height,
+            thumb_status: Some(thumb_status),
+            clear_dimensions: false,
+        })?;
+
+        let connection = open_db_connection()?;
+        query_db_item_rows(
+            &connection,
+            &format!("{} WHERE i.id = ?1 GROUP BY i.id", DB_ITEM_ROW_SELECT),
+            params![&normalized_item_id],
+        )?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "item not found after generating thumbnail".to_string())
+    })
+    .await
+    .map_err(|err| format!("item thumbnail thread join failed: {}", err))?
+}
+
+pub(crate) const DIMENSION_BACKFILL_CANDIDATE_CONDITION: &str = "i.type = 'image'
+     AND (i.width IS NULL OR i.height IS NULL)
+     AND i.import_status = 'ready'
+     AND i.thumb_status <> 'error'";
+
+pub(crate) fn count_dimension_backfill_candidates(connection: &Connection) -> Result<i64, String> {
+    connection
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) FROM items AS i
+                 JOIN vault_files AS vf ON vf.vault_key = i.vault_key
+                 WHERE {}",
+                DIMENSION_BACKFILL_CANDIDATE_CONDITION
+            ),
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|err| format!("failed to count items missing dimensions: {}", err))
+}
+
+pub(crate) fn read_dimension_batch(
+    candidates: &[(String, String)],
+) -> Vec<Option<Result<(u32, u32), String>>> {
+    let encryption_unlocked = current_vault_data_key().is_ok();
+    let worker_count = std::thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+        .clamp(1, candidates.len().max(1));
+    let chunk_size = candidates.len().div_ceil(worker_count).max(1);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = candidates
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|(_, vault_path)| {
+                            let path = path_from_ipc_string(vault_path);
+                            if is_encrypted_vault_path(&path) && !encryption_unlocked {
+                                return None;
+                            }
+                            Some(read_image_dimensions(&path))
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|_| vec![Some(Err("dimension worker panicked".to_string()))])
+            })
+            .collect()
+    })
+}
+
+pub(crate) fn backfill_item_dimensions_internal(
+    connection: &Connection,
+    reporter: &ProgressReporter,
+    limit: Option<usize>,
+) -> Result<DimensionBackfillResult, String> {
+    let mut stmt = connection
+        .prepare(&format!(
+            "SELECT i.id, vf.vault_path
+             FROM items AS i
+             JOIN vault_files AS vf ON vf.vault_key = i.vault_key
+             WHERE {}
+             ORDER BY i.created_at DESC
+             LIMIT ?1",
+            DIMENSION_BACKFILL_CANDIDATE_CONDITION
+        ))
+        .map_err(|err| format!("failed to prepare dimension backfill query: {}", err))?;
+    let rows = stmt
+        .query_map(params![limit.map_or(-1, |value| value as i64)], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|err| format!("failed to query items missing dimensions: {}", err))?;
+    let mut candidates = Vec::new();
+    for row_result in rows {
+        candidates.push(
+            row_result
+                .map_err(|err| format!("failed to decode dimension backfill row: {}", err))?,
+        );
+    }
+    drop(stmt);
+
+    let total = candidates.len() as u64;
+    let mut result = DimensionBackfillResult {
+        scanned: 0,
+        updated: 0,
+        failed: 0,
+        skipped_locked: 0,
+        remaining: 0,
+    };
+    reporter.report("read", 0, total, None);
+    for batch in candidates.chunks(DIMENSION_BACKFILL_BATCH_SIZE) {
+        let dimensions = read_dimension_batch(batch);
+        let updated_at = Utc::now().timestamp_millis();
+        let transaction = connection
+            .unchecked_transaction()
+            .map_err(|err| format!("failed to start dimension backfill transaction: {}", err))?;
+        for ((item_id, vault_path), outcome) in batch.iter().zip(dimensions) {
+            match outcome {
+                Some(Ok((width, height))) => {
+                    transaction
+                        .execute(
+                            "UPDATE items SET width = ?1, height = ?2, updated_at = ?3 WHERE id = ?4",
+                            params![i64::from(width), i64::from(height), updated_at, item_id],
+                        )
+                        .map_err(|err| format!("failed to store item dimensions: {}", err))?;
+                    result.updated += 1;
+                }
+                Some(Err(err)) => {
+                    log_eprintln!(
+                        "[dimension-backfill] failed to read {}: {}",
+                        vault_path,
+                        err
+                    );
+                    transaction
+                        .execute(
+                            "UPDATE items SET thumb_status = 'error', updated_at = ?1 WHERE id = ?2",
+                            params![updated_at, item_id],
+                        )
+                        .map_err(|err| format!("failed to mark item dimensions as failed: {}", err))?;
+                    result.failed += 1;
+                }
+                None => result.skipped_locked += 1,
+            }
+        }
+        transaction
+            .commit()
+            .map_err(|err| format!("failed to commit dimension backfill batch: {}", err))?;
+        result.scanned += batch.len();
+        reporter.report("read", result.scanned as u64, total, None);
+    }
+    result.remaining = count_dimension_backfill_candidates(connection)?;
+    Ok(result)
+}
+
+#[tauri::command]
+pub(crate) async fn backfill_item_dimensions(
+    app: AppHandle,
+    limit: Option<u32>,
+) -> Result<DimensionBackfillResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        initialize_db()?;
+        let connection = open_db_connection()?;
+        let reporter = ProgressReporter::start(&app, "dimension-backfill");
+        let outcome = backfill_item_dimensions_internal(
+            &connection,
+            &reporter,
+            limit.map(|value| value as usize),
+        );
+        reporter.finish(&outcome.as_ref().map(|_| ()).map_err(Clone::clone));
+        outcome
+    })
+    .await
+    .map_err(|err| format!("dimension backfill thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn ensure_thumbs_root() -> Result<String, String> {
+    let root = ensure_thumbs_root_internal()?;
+    path_to_string(&root)
+}
+
+#[tauri::command]
+pub(crate) async fn generate_thumbnail(
+    app: AppHandle,
+    input_path: String,
+    output_path: String,
+    max_size: Option<u32>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        ensure_thumbnail_disk_space(&app)?;
+        let source = path_from_ipc_string(&input_path);
+        let destination = path_from_ipc_string(&output_path);
+        let _in_use =
+            vault_key_from_path(&source).map(|vault_key| VaultKeyUseGuard::acquire(&vault_key));
+        let bounded_max = max_size.unwrap_or(IMPORT_THUMB_MAX_SIZE).max(1);
+        generate_thumbnail_internal(&source, &destination, bounded_max)?;
+        path_to_string(&destination)
+    })
+    .await
+    .map_err(|err| format!("generate thumbnail thread join failed: {}", err))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    const RASTER_FIXTURES: [(&str, (u32, u32)); 6] = [
+        ("images/sample.png", (64, 40)),
+        ("images/sample.jpg", (48, 30)),
+        ("images/sample.gif", (20, 12)),
+        ("images/sample.webp", (30, 18)),
+        ("images/sample.ico", (16, 16)),
+        ("images/sample.bmp", (10, 6)),
+    ];
+
+    #[test]
+    fn read_image_dimensions_reads_fixture_files() {
+        for (fixture, expected) in RASTER_FIXTURES {
+            let path = fixture_path(fixture);
+            assert_eq!(
+                read_image_dimensions(&path).unwrap(),
+                expected,
+                "{}",
+                fixture
+            );
+            let bytes = fixture_bytes(fixture);
+            assert_eq!(
+                read_image_dimensions_from_bytes(&bytes, &path).unwrap(),
+                expected,
+                "bytes of {}",
+                fixture
+            );
+        }
+    }
+
+    #[test]
+    fn read_image_dimensions_rejects_non_images() {
+        let svg = fixture_path("images/sample.svg");
+        assert!(read_image_dimensions(&svg).is_err());
+        assert!(read_image_dimensions_from_bytes(b"not an image", &svg).is_err());
+    }
+
+    #[test]
+    fn generate_thumbnail_internal_bounds_the_longest_side() {
+        let output_dir = tempfile::tempdir().unwrap();
+        for (fixture, (width, height)) in RASTER_FIXTURES {
+            let output_path = output_dir
+                .path()
+                .join(format!("{}.webp", fixture.replace('/', "-")));
+            generate_thumbnail_internal(&fixture_path(fixture), &output_path, 32).unwrap();
+
+            let thumb = fs::read(&output_path).unwrap();
+            assert_eq!(sniff_extension(&thumb), Some("webp"), "{}", fixture);
+            let (thumb_width, thumb_height) =
+                read_image_dimensions_from_bytes(&thumb, &output_path).unwrap();
+            if width.max(height) > 32 {
+                assert_eq!(thumb_width.max(thumb_height), 32, "{}", fixture);
+            } else {
+                assert_eq!((thumb_width, thumb_height), (width, height), "{}", fixture);
+            }
+        }
+    }
+
+    #[test]
+    fn generate_thumbnail_internal_keeps_existing_output() {
+        let output_dir = tempfile::tempdir().unwrap();
+        let output_path = output_dir.path().join("existing.webp");
+        fs::write(&output_path, b"keep").unwrap();
+        generate_thumbnail_internal(&fixture_path("images/sample.png"), &output_path, 32).unwrap();
+        assert_eq!(fs::read(&output_path).unwrap(), b"keep");
+        assert!(generate_thumbnail_internal(
+            &output_dir.path().join("missing.png"),
+            &output_dir.path().join("missing.webp"),
+            32
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,7711 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use chrono::{Datelike, Utc};
+use rayon::prelude::*;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{BufReader, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager, State};
+use url::Url;
+use uuid::Uuid;
+
+use crate::bookmarks::*;
+use crate::collections::*;
+use crate::commands::*;
+use crate::db::*;
+use crate::items::*;
+use crate::thumbs::*;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VaultImportResult {
+    pub(crate) vault_path: String,
+    pub(crate) sha256: String,
+    pub(crate) ext: String,
+    pub(crate) claimed_ext: String,
+    pub(crate) size: u64,
+    pub(crate) created_at: String,
+    pub(crate) original_filename: String,
+}
+
+pub(crate) const IMPORT_METRICS_MAX_ROWS: i64 = 10_000;
+pub(crate) const IMPORT_REPORTS_TO_KEEP: i64 = 5;
+pub(crate) const INSUFFICIENT_DISK_SPACE_ERROR_PREFIX: &str = "insufficient disk space";
+pub(crate) const IMPORT_DISK_SAFETY_MARGIN_BYTES: u64 = 256 * 1024 * 1024;
+pub(crate) const IMPORT_DISK_PREFLIGHT_MIN_BYTES: u64 = 64 * 1024 * 1024;
+pub(crate) const DISK_SPACE_LOW_EVENT: &str = "disk://low-space";
+pub(crate) const ARCHIVE_PASSWORD_PROTECTED_ERROR: &str = "archive is password-protected";
+pub(crate) const CONTENT_SNIFF_HEAD_BYTES: usize = 4096;
+pub(crate) const QUARANTINE_DIRECTORY_NAME: &str = "quarantine";
+pub(crate) const ITEM_IMPORT_ERROR_MAX_CHARS: usize = 500;
+pub(crate) const IMPORT_QUARANTINED_ERROR_PREFIX: &str = "import quarantined";
+pub(crate) const SETTING_MAX_IMPORT_FILE_BYTES: &str = "max_import_file_bytes";
+pub(crate) const SETTING_IMPORT_EXTENSION_ALLOWLIST: &str = "import_extension_allowlist";
+pub(crate) const SETTING_IMPORT_EXTENSION_DENYLIST: &str = "import_extension_denylist";
+pub(crate) const SETTING_VAULT_LAYOUT: &str = "vault_layout";
+pub(crate) const VAULT_LAYOUT_MONTH: &str = "month";
+pub(crate) const VAULT_LAYOUT_BY_YEAR: &str = "by_year";
+pub(crate) const VAULT_LAYOUT_FLAT: &str = "flat";
+pub(crate) const INTEGRITY_STATUS_OK: &str = "ok";
+pub(crate) const INTEGRITY_STATUS_MODIFIED: &str = "modified";
+pub(crate) const INTEGRITY_STATUS_MISSING: &str = "missing";
+pub(crate) const SETTING_VAULT_ENCRYPTION_SALT: &str = "vault_encryption_salt";
+pub(crate) const SETTING_VAULT_ENCRYPTION_WRAPPED_KEY: &str = "vault_encryption_wrapped_key";
+pub(crate) const VAULT_ENCRYPTED_SUFFIX: &str = "enc";
+pub(crate) const VAULT_ENCRYPTION_MAGIC: &[u8; 8] = b"STMBLENC";
+pub(crate) const VAULT_ENCRYPTION_NONCE_PREFIX_BYTES: usize = 19;
+pub(crate) const VAULT_ENCRYPTION_CHUNK_BYTES: usize = 64 * 1024;
+pub(crate) const VAULT_ENCRYPTION_TAG_BYTES: usize = 16;
+pub(crate) const VAULT_ENCRYPTION_SALT_BYTES: usize = 16;
+pub(crate) const VAULT_PASSPHRASE_MIN_CHARS: usize = 8;
+pub(crate) const VAULT_LOCKED_ERROR: &str =
+    "library is locked: unlock it with the vault passphrase first";
+pub(crate) const VAULT_PROTOCOL_SCHEME: &str = "stumble-vault";
+pub(crate) const SETTING_FILE_VERSIONS_TO_KEEP: &str = "file_versions_to_keep";
+pub(crate) const DEFAULT_FILE_VERSIONS_TO_KEEP: usize = 3;
+pub(crate) const VAULT_KEY_MAX_EXT_LEN: usize = 32;
+pub(crate) const VAULT_ZERO_REF_GRACE_MS: i64 = 15 * 60 * 1000;
+pub(crate) const BROKEN_ITEMS_PROGRESS_EVENT: &str = "broken-items://progress";
+pub(crate) const BROKEN_ITEMS_PROGRESS_INTERVAL: usize = 100;
+pub(crate) const IMPORT_DIRECTORY_PROGRESS_EVENT: &str = "import-directory://progress";
+pub(crate) const RAW_PATH_IPC_PREFIX: &str = "raw-path:";
+pub(crate) const DEFAULT_MAX_IMPORT_FILE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+pub(crate) const SETTING_IMPORT_HASH_PARALLELISM: &str = "import_hash_parallelism";
+pub(crate) const DEFAULT_IMPORT_HASH_PARALLELISM_CAP: usize = 8;
+pub(crate) const IMPORT_HASH_MAX_PARALLELISM: usize = 32;
+pub(crate) const SEEN_SOURCES_MAX_ROWS: i64 = 100_000;
+pub(crate) const ARCHIVE_ENTRY_IN_MEMORY_MAX_BYTES: usize = 16 * 1024 * 1024;
+pub(crate) const ARCHIVE_SPOOL_FILE_PREFIX: &str = ".archive-";
+pub(crate) const ARCHIVE_SPOOL_FILE_SUFFIX: &str = ".partial";
+pub(crate) const ARCHIVE_SPOOL_STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VaultEncryptionStatus {
+    pub(crate) enabled: bool,
+    pub(crate) unlocked: bool,
+    pub(crate) pending_files: i64,
+    pub(crate) migration_job_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FinalizeItemImportInput {
+    pub(crate) item_id: String,
+    pub(crate) title: String,
+    pub(crate) filename: String,
+    pub(crate) vault_key: String,
+    pub(crate) vault_path: String,
+    pub(crate) width: Option<i64>,
+    pub(crate) height: Option<i64>,
+    pub(crate) thumb_status: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MarkItemImportErrorInput {
+    pub(crate) item_id: String,
+    #[serde(default)]
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ItemFileVersion {
+    pub(crate) id: i64,
+    pub(crate) item_id: String,
+    pub(crate) vault_key: String,
+    pub(crate) vault_path: String,
+    pub(crate) replaced_at: i64,
+    pub(crate) file_exists: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RecentImportPreview {
+    pub(crate) item_id: String,
+    #[serde(rename = "type")]
+    pub(crate) item_type: String,
+    pub(crate) title: String,
+    pub(crate) thumb_path: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RecentImportGroup {
+    pub(crate) group_key: String,
+    pub(crate) import_batch_id: Option<String>,
+    pub(crate) started_at: i64,
+    pub(crate) finished_at: i64,
+    pub(crate) item_count: i64,
+    pub(crate) previews: Vec<RecentImportPreview>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RecentlyImported {
+    pub(crate) hours: u32,
+    pub(crate) since: i64,
+    pub(crate) groups: Vec<RecentImportGroup>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VaultFileReference {
+    pub(crate) item_id: String,
+    pub(crate) item_type: String,
+    pub(crate) title: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VaultFileInfo {
+    pub(crate) vault_key: String,
+    pub(crate) vault_path: String,
+    pub(crate) sha256: String,
+    pub(crate) ext: String,
+    pub(crate) size_bytes: i64,
+    pub(crate) ref_count: i64,
+    pub(crate) is_encrypted: bool,
+    pub(crate) is_protected: bool,
+    pub(crate) created_at: i64,
+    pub(crate) updated_at: i64,
+    pub(crate) file_exists: bool,
+    pub(crate) thumb_path: Option<String>,
+    pub(crate) thumb_exists: bool,
+    pub(crate) version_count: i64,
+    pub(crate) items: Vec<VaultFileReference>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LargestVaultFile {
+    pub(crate) vault_key: String,
+    pub(crate) vault_path: String,
+    pub(crate) ext: String,
+    pub(crate) size_bytes: i64,
+    pub(crate) ref_count: i64,
+    pub(crate) item_count: i64,
+    pub(crate) sample_title: Option<String>,
+    pub(crate) file_exists: bool,
+    pub(crate) is_protected: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProtectItemFilesResult {
+    pub(crate) vault_keys: Vec<String>,
+    pub(crate) updated_at: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VaultCompactionMove {
+    pub(crate) vault_key: String,
+    pub(crate) from_path: String,
+    pub(crate) to_path: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VaultCompactionReport {
+    pub(crate) strategy: String,
+    pub(crate) dry_run: bool,
+    pub(crate) planned: Vec<VaultCompactionMove>,
+    pub(crate) moved: usize,
+    pub(crate) failed: usize,
+    pub(crate) removed_directories: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VaultCleanupEntry {
+    pub(crate) vault_key: String,
+    pub(crate) vault_path: String,
+    pub(crate) sha256: String,
+    pub(crate) ext: String,
+    pub(crate) deleted_from_disk: bool,
+    pub(crate) is_protected: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ItemIntegrityReport {
+    pub(crate) item_id: String,
+    pub(crate) vault_key: String,
+    pub(crate) integrity_status: String,
+    pub(crate) expected_size: Option<i64>,
+    pub(crate) actual_size: Option<i64>,
+    pub(crate) expected_mtime: Option<i64>,
+    pub(crate) actual_mtime: Option<i64>,
+    pub(crate) rehashed: bool,
+    pub(crate) actual_sha256: Option<String>,
+    pub(crate) checked_at: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RepairModifiedVaultFileResult {
+    pub(crate) item_id: String,
+    pub(crate) previous_vault_key: String,
+    pub(crate) vault_key: String,
+    pub(crate) vault_path: String,
+    pub(crate) rekeyed: bool,
+    pub(crate) item: DbItemRow,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportPipelineMetrics {
+    pub(crate) hash_ms: u64,
+    pub(crate) copy_ms: u64,
+    pub(crate) metadata_ms: u64,
+    pub(crate) thumb_ms: u64,
+    pub(crate) decode_ms: u64,
+    pub(crate) resize_ms: u64,
+    pub(crate) encode_ms: u64,
+    pub(crate) total_ms: u64,
+    pub(crate) deduped: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportPipelineResult {
+    pub(crate) vault_path: String,
+    pub(crate) sha256: String,
+    pub(crate) ext: String,
+    pub(crate) claimed_ext: String,
+    pub(crate) size: u64,
+    pub(crate) created_at: String,
+    pub(crate) original_filename: String,
+    pub(crate) width: Option<u32>,
+    pub(crate) height: Option<u32>,
+    pub(crate) thumb_status: String,
+    pub(crate) thumb_path: Option<String>,
+    pub(crate) metrics: ImportPipelineMetrics,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportMetricsSummary {
+    pub(crate) days: u32,
+    pub(crate) import_count: i64,
+    pub(crate) success_count: i64,
+    pub(crate) failure_count: i64,
+    pub(crate) deduped_count: i64,
+    pub(crate) total_bytes: i64,
+    pub(crate) avg_hash_ms: f64,
+    pub(crate) avg_copy_ms: f64,
+    pub(crate) avg_metadata_ms: f64,
+    pub(crate) avg_thumb_ms: f64,
+    pub(crate) avg_decode_ms: f64,
+    pub(crate) avg_resize_ms: f64,
+    pub(crate) avg_encode_ms: f64,
+    pub(crate) avg_total_ms: f64,
+    pub(crate) p50_total_ms: i64,
+    pub(crate) p95_total_ms: i64,
+    pub(crate) max_total_ms: i64,
+    pub(crate) bytes_per_second: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FailedImportEntry {
+    pub(crate) item_id: String,
+    #[serde(rename = "type")]
+    pub(crate) item_type: String,
+    pub(crate) title: String,
+    pub(crate) filename: String,
+    pub(crate) vault_path: String,
+    pub(crate) original_source_path: Option<String>,
+    pub(crate) source_available: bool,
+    pub(crate) updated_at: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InvalidVaultKeyEntry {
+    pub(crate) source: String,
+    pub(crate) id: String,
+    pub(crate) vault_key: String,
+    pub(crate) error: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExtensionMismatchEntry {
+    pub(crate) vault_key: String,
+    pub(crate) vault_path: String,
+    pub(crate) stored_ext: String,
+    pub(crate) sniffed_ext: String,
+    pub(crate) item_count: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExtensionMismatchReport {
+    pub(crate) scanned: usize,
+    pub(crate) unreadable: usize,
+    pub(crate) mismatches: Vec<ExtensionMismatchEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QuarantinedFile {
+    pub(crate) file_name: String,
+    pub(crate) path: String,
+    pub(crate) vault_key: String,
+    pub(crate) original_filename: String,
+    pub(crate) reason: String,
+    pub(crate) detail: String,
+    pub(crate) size_bytes: i64,
+    pub(crate) quarantined_at: i64,
+    pub(crate) exists: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PurgeQuarantinedFilesResult {
+    pub(crate) removed: usize,
+    pub(crate) missing: usize,
+    pub(crate) deferred: usize,
+    pub(crate) freed_bytes: u64,
+}
+
+pub(crate) struct HeadCapture {
+    pub(crate) head: Vec<u8>,
+    pub(crate) limit: usize,
+}
+
+impl Write for HeadCapture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let remaining = self.limit.saturating_sub(self.head.len());
+        self.head
+            .extend_from_slice(&buf[..buf.len().min(remaining)]);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BrokenItemEntry {
+    pub(crate) item_id: String,
+    #[serde(rename = "type")]
+    pub(crate) item_type: String,
+    pub(crate) title: String,
+    pub(crate) filename: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BrokenVaultEntry {
+    pub(crate) vault_key: String,
+    pub(crate) expected_path: String,
+    pub(crate) thumb_exists: bool,
+    pub(crate) items: Vec<BrokenItemEntry>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BrokenItemsProgressPayload {
+    pub(crate) checked: usize,
+    pub(crate) total: usize,
+    pub(crate) broken: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RelinkItemFileResult {
+    pub(crate) item_id: String,
+    pub(crate) vault_key: String,
+    pub(crate) vault_path: String,
+    pub(crate) reimported: bool,
+    pub(crate) relinked_items: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RetryFailedImportError {
+    pub(crate) item_id: String,
+    pub(crate) error: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RetryFailedImportsResult {
+    pub(crate) recovered: Vec<String>,
+    pub(crate) still_failing: Vec<RetryFailedImportError>,
+    pub(crate) unrecoverable: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportLimits {
+    pub(crate) max_file_bytes: u64,
+    pub(crate) allowlist: Vec<String>,
+    pub(crate) denylist: Vec<String>,
+}
+
+pub(crate) struct VaultWriteDirectory {
+    pub(crate) root: PathBuf,
+    pub(crate) layout: String,
+    pub(crate) directory: PathBuf,
+}
+
+pub(crate) static VAULT_DATA_KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+pub(crate) static VAULT_WRITE_DIRECTORY: Mutex<Option<VaultWriteDirectory>> = Mutex::new(None);
+pub(crate) static VAULT_KEYS_IN_USE: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+pub(crate) static DEFERRED_VAULT_CLEANUPS: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) struct VaultKeyUseGuard {
+    pub(crate) vault_key: String,
+}
+
+impl VaultKeyUseGuard {
+    pub(crate) fn acquire(vault_key: &str) -> Self {
+        acquire_vault_key_use(vault_key);
+        Self {
+            vault_key: vault_key.to_string(),
+        }
+    }
+}
+
+impl Drop for VaultKeyUseGuard {
+    fn drop(&mut self) {
+        release_vault_key_use(&self.vault_key);
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct VaultMaintenanceState {
+    pub(crate) running: AtomicBool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VaultMaintenanceStatus {
+    pub(crate) running: bool,
+    pub(crate) in_use_keys: usize,
+    pub(crate) deferred_cleanups: u64,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiskUsage {
+    pub(crate) path: String,
+    pub(crate) total_bytes: u64,
+    pub(crate) available_bytes: u64,
+    pub(crate) used_bytes: u64,
+    pub(crate) thumbnails_paused: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiskSpaceLowPayload {
+    pub(crate) path: String,
+    pub(crate) required_bytes: u64,
+    pub(crate) available_bytes: u64,
+    pub(crate) operation: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportDirectoryFailure {
+    pub(crate) path: String,
+    pub(crate) error: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ArchiveEntry {
+    pub(crate) name: String,
+    pub(crate) size: u64,
+    pub(crate) compressed_size: u64,
+    pub(crate) extension: String,
+    pub(crate) is_image: bool,
+    pub(crate) is_dir: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportDirectoryResult {
+    pub(crate) item_ids: Vec<String>,
+    pub(crate) failed: Vec<ImportDirectoryFailure>,
+    pub(crate) skipped: Vec<ImportDirectoryFailure>,
+    pub(crate) collections_created: usize,
+    pub(crate) deduplicated: Vec<ImportDedupEntry>,
+    pub(crate) import_batch_id: String,
+    pub(crate) thumb_policy: String,
+    pub(crate) hash_summary: Option<ImportHashSummary>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportHashSummary {
+    pub(crate) parallelism: usize,
+    pub(crate) hashed_files: usize,
+    pub(crate) cached_files: usize,
+    pub(crate) hashed_bytes: u64,
+    pub(crate) hash_wall_ms: u64,
+    pub(crate) hash_mb_per_sec: f64,
+}
+
+pub(crate) struct SeenSource {
+    pub(crate) size_bytes: u64,
+    pub(crate) mtime_ms: i64,
+    pub(crate) sha256: String,
+}
+
+pub(crate) struct PrehashedImportSource {
+    pub(crate) path: PathBuf,
+    pub(crate) size_bytes: u64,
+    pub(crate) mtime_ms: Option<i64>,
+    pub(crate) sha256: Option<Result<String, String>>,
+    pub(crate) cached: bool,
+    pub(crate) finished_at: Instant,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportBatch {
+    pub(crate) id: String,
+    pub(crate) source: String,
+    pub(crate) description: String,
+    pub(crate) started_at: i64,
+    pub(crate) finished_at: Option<i64>,
+    pub(crate) imported_count: i64,
+    pub(crate) failed_count: i64,
+    pub(crate) skipped_count: i64,
+    pub(crate) deduplicated_count: i64,
+    pub(crate) item_count: i64,
+}
+
+pub(crate) struct ImportedFileItem {
+    pub(crate) item_id: String,
+    pub(crate) vault_key: String,
+    pub(crate) deduped: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportDedupEntry {
+    pub(crate) source_path: String,
+    pub(crate) vault_key: String,
+    pub(crate) matched_items: Vec<VaultFileReference>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportReport {
+    pub(crate) operation: String,
+    pub(crate) created_at: i64,
+    pub(crate) imported: usize,
+    pub(crate) failed: usize,
+    pub(crate) skipped: usize,
+    pub(crate) deduplicated: Vec<ImportDedupEntry>,
+    #[serde(default)]
+    pub(crate) thumb_policy: String,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportPreviewExtensionStats {
+    pub(crate) count: usize,
+    pub(crate) bytes: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportDirectoryPreview {
+    pub(crate) files: Vec<String>,
+    pub(crate) total_files: usize,
+    pub(crate) total_bytes: u64,
+    pub(crate) by_extension: BTreeMap<String, ImportPreviewExtensionStats>,
+    pub(crate) oversized: usize,
+    pub(crate) empty: usize,
+    pub(crate) unsupported: usize,
+    pub(crate) estimated_duplicates: usize,
+    pub(crate) exact_duplicates: Option<usize>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportDirectoryProgressPayload {
+    pub(crate) processed: usize,
+    pub(crate) total: usize,
+    pub(crate) imported: usize,
+    pub(crate) failed: usize,
+    pub(crate) import_batch_id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DroppedPathsJob {
+    pub(crate) job_id: String,
+    pub(crate) kind: String,
+    pub(crate) path: Option<String>,
+    pub(crate) import_batch_id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DroppedPathsSummary {
+    pub(crate) jobs: Vec<DroppedPathsJob>,
+    pub(crate) files: usize,
+    pub(crate) directories: usize,
+    pub(crate) bookmarks: usize,
+    pub(crate) rejected: Vec<ImportDirectoryFailure>,
+}
+
+pub(crate) fn parse_import_extension_list(value: &str) -> Vec<String> {
+    value
+        .split([',', ' ', ';'])
+        .map(|ext| normalize_ext(ext.trim().trim_start_matches('.')))
+        .filter(|ext| !ext.is_empty())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+pub(crate) fn load_import_limits(connection: &Connection) -> Result<ImportLimits, String> {
+    let max_file_bytes = read_app_setting(connection, SETTING_MAX_IMPORT_FILE_BYTES)?
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_MAX_IMPORT_FILE_BYTES);
+    let allowlist = read_app_setting(connection, SETTING_IMPORT_EXTENSION_ALLOWLIST)?
+        .map(|value| parse_import_extension_list(&value))
+        .unwrap_or_default();
+    let denylist = read_app_setting(connection, SETTING_IMPORT_EXTENSION_DENYLIST)?
+        .map(|value| parse_import_extension_list(&value))
+        .unwrap_or_default();
+    Ok(ImportLimits {
+        max_file_bytes,
+        allowlist,
+        denylist,
+    })
+}
+
+pub(crate) fn validate_import_against_limits(
+    limits: &ImportLimits,
+    size_bytes: u64,
+    ext: &str,
+) -> Result<(), String> {
+    if size_bytes == 0 {
+        return Err("validation: cannot import an empty file".to_string());
+    }
+    if limits.max_file_bytes > 0 && size_bytes > limits.max_file_bytes {
+        return Err(format!(
+            "validation: file is {} bytes, which exceeds the {} byte import limit",
+            size_bytes, limits.max_file_bytes
+        ));
+    }
+    if !limits.allowlist.is_empty() && !limits.allowlist.iter().any(|allowed| allowed == ext) {
+        return Err(format!(
+            "validation: .{} files are not in the import allowlist",
+            ext
+        ));
+    }
+    if limits.denylist.iter().any(|denied| denied == ext) {
+        return Err(format!(
+            "validation: .{} files are blocked by the import denylist",
+            ext
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn enforce_import_limits(
+    source_path: Option<&Path>,
+    source_bytes: Option<&[u8]>,
+    ext: &str,
+) -> Result<(), String> {
+    let size_bytes = match (source_path, source_bytes) {
+        (Some(path), None) => fs::metadata(path)
+            .map_err(|err| format!("failed to read import source {}: {}", path.display(), err))?
+            .len(),
+        (None, Some(bytes)) => bytes.len() as u64,
+        _ => return Ok(()),
+    };
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    validate_import_against_limits(&load_import_limits(&connection)?, size_bytes, ext)?;
+    if size_bytes >= IMPORT_DISK_PREFLIGHT_MIN_BYTES {
+        ensure_import_disk_space(size_bytes)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn read_available_disk_space(directory: &Path) -> Result<u64, String> {
+    fs2::available_space(directory).map_err(|err| {
+        format!(
+            "failed to read free disk space for {}: {}",
+            directory.display(),
+            err
+        )
+    })
+}
+
+pub(crate) fn insufficient_disk_space_error(
+    directory: &Path,
+    required: u64,
+    available: u64,
+) -> String {
+    format!(
+        "{}: required {} bytes, available {} bytes in {}",
+        INSUFFICIENT_DISK_SPACE_ERROR_PREFIX,
+        required,
+        available,
+        directory.display()
+    )
+}
+
+pub(crate) fn ensure_import_disk_space(incoming_bytes: u64) -> Result<(), String> {
+    let root = ensure_storage_root_internal()?;
+    let available = read_available_disk_space(&root)?;
+    let required = incoming_bytes.saturating_add(IMPORT_DISK_SAFETY_MARGIN_BYTES);
+    if available < required {
+        return Err(insufficient_disk_space_error(&root, required, available));
+    }
+    Ok(())
+}
+
+pub(crate) fn import_paths_total_bytes(paths: &[PathBuf]) -> u64 {
+    paths
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .fold(0_u64, u64::saturating_add)
+}
+
+pub(crate) fn run_vault_maintenance_step(
+    app: &AppHandle,
+    operation: &str,
+    step: fn(&Connection, &ProgressReporter) -> Result<(), String>,
+) {
+    let reporter = ProgressReporter::start(app, operation);
+    let outcome = initialize_db()
+        .and_then(|_| open_db_connection())
+        .and_then(|connection| step(&connection, &reporter));
+    if let Err(err) = &outcome {
+        log_eprintln!("[maintenance] {} failed: {}", operation, err);
+    }
+    reporter.finish(&outcome);
+}
+
+pub(crate) fn run_vault_maintenance(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<VaultMaintenanceState>();
+    if state.running.swap(true, Ordering::SeqCst) {
+        return Err("vault maintenance is already running".to_string());
+    }
+    run_vault_maintenance_step(app, "vault-ref-backfill", backfill_vault_refs_if_needed);
+    run_vault_maintenance_step(app, "vault-cleanup", cleanup_zero_ref_vault_files_reporting);
+    state.running.store(false, Ordering::SeqCst);
+    Ok(())
+}
+
+pub(crate) fn start_startup_vault_maintenance(app: AppHandle) {
+    std::thread::spawn(move || {
+        let cutoff = SystemTime::now() - ARCHIVE_SPOOL_STALE_AFTER;
+        let mut removed = sweep_stale_archive_spool_files(&std::env::temp_dir(), 0, cutoff);
+        // Older builds spooled archive entries next to vault files, at most two levels deep.
+        if let Ok(root) = storage_root_path() {
+            removed += sweep_stale_archive_spool_files(&root, 2, cutoff);
+        }
+        if removed > 0 {
+            log_println!(
+                "[maintenance] removed {} stale archive spool files",
+                removed
+            );
+        }
+        if let Err(err) = run_vault_maintenance(&app) {
+            log_eprintln!("[maintenance] startup vault maintenance skipped: {}", err);
+        }
+    });
+}
+
+/// Deletes `.archive-*.partial` files last modified before `cutoff` in `directory` and up to
+/// `depth` levels of subdirectories below it. These are left behind when an archive import
+/// crashes before its spool file is dropped.
+pub(crate) fn sweep_stale_archive_spool_files(
+    directory: &Path,
+    depth: usize,
+    cutoff: SystemTime,
+) -> usize {
+    let Ok(entries) = read_directory(directory) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if depth > 0 {
+                removed += sweep_stale_archive_spool_files(&entry.path(), depth - 1, cutoff);
+            }
+            continue;
+        }
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+        if !file_type.is_file()
+            || !file_name.starts_with(ARCHIVE_SPOOL_FILE_PREFIX)
+            || !file_name.ends_with(ARCHIVE_SPOOL_FILE_SUFFIX)
+        {
+            continue;
+        }
+        let is_stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified < cutoff);
+        if is_stale && fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// Reduces a claimed extension to the form stored in vault keys. Only the last dot segment
+/// counts ("tar.gz" -> "gz"), it is lowercased and stripped to ASCII alphanumerics, and
+/// anything left empty or longer than `VAULT_KEY_MAX_EXT_LEN` chars becomes "bin".
+pub(crate) fn normalize_ext(ext: &str) -> String {
+    let cleaned = ext.trim().trim_matches('.').to_ascii_lowercase();
+    let last_segment = cleaned.rsplit('.').next().unwrap_or_default();
+    if last_segment.is_empty() {
+        return "bin".to_string();
+    }
+
+    let sanitized: String = last_segment
+        .chars()
+        .filter(|ch| ch.is_ascii_alphanumeric())
+        .collect();
+    if sanitized.is_empty() || sanitized.len() > VAULT_KEY_MAX_EXT_LEN {
+        "bin".to_string()
+    } else {
+        sanitized
+    }
+}
+
+pub(crate) fn extension_from_filename(filename: &str) -> Option<String> {
+    Path::new(filename)
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(normalize_ext)
+}
+
+pub(crate) fn extension_from_path(path: &Path) -> String {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .map(normalize_ext)
+        .unwrap_or_else(|| "bin".to_string())
+}
+
+pub(crate) fn storage_root_path() -> Result<PathBuf, String> {
+    Ok(app_root_path()?.join("storage"))
+}
+
+pub(crate) fn ensure_storage_root_internal() -> Result<PathBuf, String> {
+    let root = storage_root_path()?;
+    fs::create_dir_all(&root)
+        .map_err(|err| format!("failed to create storage root {}: {}", root.display(), err))?;
+    Ok(root)
+}
+
+pub(crate) fn quarantine_root_path() -> Result<PathBuf, String> {
+    Ok(storage_root_path()?.join(QUARANTINE_DIRECTORY_NAME))
+}
+
+pub(crate) fn ensure_quarantine_root_internal() -> Result<PathBuf, String> {
+    let root = quarantine_root_path()?;
+    fs::create_dir_all(&root).map_err(|err| {
+        format!(
+            "failed to create quarantine root {}: {}",
+            root.display(),
+            err
+        )
+    })?;
+    Ok(root)
+}
+
+pub(crate) fn icons_root_path() -> Result<PathBuf, String> {
+    Ok(app_root_path()?.join("icons"))
+}
+
+pub(crate) fn ensure_icons_root_internal() -> Result<PathBuf, String> {
+    let root = icons_root_path()?;
+    fs::create_dir_all(&root)
+        .map_err(|err| format!("failed to create icons root {}: {}", root.display(), err))?;
+    Ok(root)
+}
+
+pub(crate) fn normalize_vault_layout(value: &str) -> Result<String, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        VAULT_LAYOUT_MONTH => Ok(VAULT_LAYOUT_MONTH.to_string()),
+        VAULT_LAYOUT_BY_YEAR => Ok(VAULT_LAYOUT_BY_YEAR.to_string()),
+        VAULT_LAYOUT_FLAT => Ok(VAULT_LAYOUT_FLAT.to_string()),
+        other => Err(format!(
+            "unsupported vault layout \"{}\"; expected month, by_year or flat",
+            other
+        )),
+    }
+}
+
+pub(crate) fn load_vault_layout(connection: &Connection) -> Result<String, String> {
+    Ok(read_app_setting(connection, SETTING_VAULT_LAYOUT)?
+        .and_then(|value| normalize_vault_layout(&value).ok())
+        .unwrap_or_else(|| VAULT_LAYOUT_MONTH.to_string()))
+}
+
+pub(crate) fn vault_layout_directory(root: &Path, layout: &str, timestamp_ms: i64) -> PathBuf {
+    let at = chrono::DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_else(Utc::now);
+    match layout {
+        VAULT_LAYOUT_FLAT => root.to_path_buf(),
+        VAULT_LAYOUT_BY_YEAR => root.join(format!("{:04}", at.year())),
+        _ => root
+            .join(format!("{:04}", at.year()))
+            .join(format!("{:02}", at.month())),
+    }
+}
+
+pub(crate) fn cached_vault_write_directory(root: &Path, now: i64) -> Option<PathBuf> {
+    let cached = VAULT_WRITE_DIRECTORY.lock().ok()?;
+    let cached = cached.as_ref()?;
+    let still_valid = cached.root == root
+        && vault_layout_directory(root, &cached.layout, now) == cached.directory
+        && cached.directory.is_dir();
+    still_valid.then(|| cached.directory.clone())
+}
+
+pub(crate) fn invalidate_vault_write_directory() {
+    if let Ok(mut cached) = VAULT_WRITE_DIRECTORY.lock() {
+        *cached = None;
+    }
+}
+
+pub(crate) fn ensure_current_month_directory(root: &Path) -> Result<PathBuf, String> {
+    ensure_vault_write_directory_at(root, Utc::now().timestamp_millis())
+}
+
+pub(crate) fn ensure_vault_write_directory_at(root: &Path, now: i64) -> Result<PathBuf, String> {
+    if let Some(directory) = cached_vault_write_directory(root, now) {
+        return Ok(directory);
+    }
+
+    initialize_db()?;
+    let layout = load_vault_layout(&open_db_connection()?)?;
+    let directory = vault_layout_directory(root, &layout, now);
+    fs::create_dir_all(&directory).map_err(|err| {
+        format!(
+            "failed to create vault directory {}: {}",
+            directory.display(),
+            err
+        )
+    })?;
+    if let Ok(mut cached) = VAULT_WRITE_DIRECTORY.lock() {
+        *cached = Some(VaultWriteDirectory {
+            root: root.to_path_buf(),
+            layout,
+            directory: directory.clone(),
+        });
+    }
+    Ok(directory)
+}
+
+pub(crate) fn resolve_batch_vault_directory() -> Result<PathBuf, String> {
+    let root = ensure_storage_root_internal()?;
+    ensure_current_month_directory(&root)
+}
+
+pub(crate) fn build_vault_filename(sha256: &str, ext: &str) -> String {
+    format!("{sha256}.{}", normalize_ext(ext))
+}
+
+pub(crate) fn parse_vault_key(vault_key: &str) -> Result<(String, String), String> {
+    let trimmed = vault_key.trim();
+    let (sha256, ext) = trimmed
+        .rsplit_once('.')
+        .ok_or_else(|| format!("invalid vault key \"{}\": missing extension", vault_key))?;
+    if sha256.len() != 64
+        || !sha256
+            .chars()
+            .all(|ch| ch.is_ascii_digit() || ('a'..='f').contains(&ch))
+    {
+        return Err(format!(
+            "invalid vault key \"{}\": expected a 64-character lowercase hex sha256",
+            vault_key
+        ));
+    }
+    if ext.is_empty()
+        || ext.len() > VAULT_KEY_MAX_EXT_LEN
+        || !ext
+            .chars()
+            .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit())
+    {
+        return Err(format!(
+            "invalid vault key \"{}\": extension must be 1-{} lowercase alphanumeric characters",
+            vault_key, VAULT_KEY_MAX_EXT_LEN
+        ));
+    }
+    Ok((sha256.to_string(), ext.to_string()))
+}
+
+pub(crate) fn increment_vault_ref_in_tx(
+    transaction: &Transaction<'_>,
+    vault_key: &str,
+    vault_path: &str,
+) -> Result<(), String> {
+    if vault_key.trim().is_empty() {
+        return Ok(());
+    }
+
+    let (sha256, ext) = parse_vault_key(vault_key)?;
+    let size_bytes = fs::metadata(vault_path)
+        .map(|metadata| metadata.len() as i64)
+        .unwrap_or(0);
+    let now = Utc::now().timestamp_millis();
+    transaction
+        .execute(
+            "INSERT INTO vault_files (
+                vault_key,
+                vault_path,
+                sha256,
+                ext,
+                size_bytes,
+                ref_count,
+                created_at,
+                updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, 1, ?6, ?6)
+            ON CONFLICT(vault_key) DO UPDATE SET
+                ref_count = vault_files.ref_count + 1,
+                vault_path = excluded.vault_path,
+                sha256 = excluded.sha256,
+                ext = excluded.ext,
+                size_bytes = CASE
+                                WHEN excluded.size_bytes > 0 THEN excluded.size_bytes
+                                ELSE vault_files.size_bytes
+                             END,
+                updated_at = excluded.updated_at",
+            params![vault_key, vault_path, sha256, ext, size_bytes, now],
+        )
+        .map_err(|err| format!("failed to increment vault ref count: {}", err))?;
+    Ok(())
+}
+
+pub(crate) fn decrement_vault_ref_in_tx(
+    transaction: &Transaction<'_>,
+    vault_key: &str,
+    decrement_by: i64,
+) -> Result<i64, String> {
+    if vault_key.trim().is_empty() {
+        return Ok(0);
+    }
+
+    let bounded_decrement = decrement_by.max(0);
+    let now = Utc::now().timestamp_millis();
+    transaction
+        .execute(
+            "UPDATE vault_files
+             SET ref_count = CASE
+                                WHEN ref_count > ?2 THEN ref_count - ?2
+                                ELSE 0
+                             END,
+                 updated_at = ?3
+             WHERE vault_key = ?1",
+            params![vault_key, bounded_decrement, now],
+        )
+        .map_err(|err| format!("failed to decrement vault ref count: {}", err))?;
+
+    let refs = transaction
+        .query_row(
+            "SELECT ref_count FROM vault_files WHERE vault_key = ?1",
+            params![vault_key],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to read vault ref count after decrement: {}", err))?
+        .unwrap_or(0);
+
+    Ok(refs)
+}
+
+pub(crate) fn backfill_vault_refs_if_needed(
+    connection: &Connection,
+    reporter: &ProgressReporter,
+) -> Result<(), String> {
+    let vault_file_rows: i64 = connection
+        .query_row("SELECT COUNT(*) FROM vault_files", [], |row| row.get(0))
+        .map_err(|err| format!("failed to count vault rows: {}", err))?;
+    if vault_file_rows > 0 {
+        return Ok(());
+    }
+
+    let mut counts_by_key: HashMap<String, (String, i64)> = HashMap::new();
+    let mut items_stmt = connection
+        .prepare("SELECT vault_key, vault_path FROM items WHERE vault_key <> ''")
+        .map_err(|err| format!("failed to prepare vault backfill query: {}", err))?;
+    let items_iter = items_stmt
+        .query_map([], |row| {
+            let vault_key: String = row.get(0)?;
+            let vault_path: String = row.get(1)?;
+            Ok((vault_key, vault_path))
+        })
+        .map_err(|err| format!("failed to query item vault keys for backfill: {}", err))?;
+
+    for row_result in items_iter {
+        let (vault_key, vault_path) =
+            row_result.map_err(|err| format!("failed to read backfill row: {}", err))?;
+        let entry = counts_by_key.entry(vault_key).or_insert((vault_path, 0));
+        entry.1 += 1;
+    }
+
+    if counts_by_key.is_empty() {
+        return Ok(());
+    }
+
+    let now = Utc::now().timestamp_millis();
+    let total = counts_by_key.len() as u64;
+    for (index, (vault_key, (vault_path, ref_count))) in counts_by_key.into_iter().enumerate() {
+        reporter.report("backfill", index as u64, total, Some(&vault_key));
+        let (sha256, ext) = match parse_vault_key(&vault_key) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                log_eprintln!("skipping vault key during backfill: {}", err);
+                continue;
+            }
+        };
+
+        connection
+            .execute(
+                "INSERT OR REPLACE INTO vault_files (
+                    vault_key,
+                    vault_path,
+                    sha256,
+                    ext,
+                    size_bytes,
+                    ref_count,
+                    created_at,
+                    updated_at
+                ) VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6, ?6)",
+                params![vault_key, vault_path, sha256, ext, ref_count, now],
+            )
+            .map_err(|err| format!("failed to insert vault backfill row: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn acquire_vault_key_use(vault_key: &str) {
+    if let Ok(mut in_use) = VAULT_KEYS_IN_USE.lock() {
+        *in_use.entry(vault_key.to_string()).or_insert(0) += 1;
+    }
+}
+
+pub(crate) fn release_vault_key_use(vault_key: &str) {
+    if let Ok(mut in_use) = VAULT_KEYS_IN_USE.lock() {
+        if let Some(count) = in_use.get_mut(vault_key) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                in_use.remove(vault_key);
+            }
+        }
+    }
+}
+
+pub(crate) fn vault_key_in_use(vault_key: &str) -> bool {
+    VAULT_KEYS_IN_USE
+        .lock()
+        .map(|in_use| in_use.contains_key(vault_key))
+        .unwrap_or(false)
+}
+
+pub(crate) fn vault_key_from_path(path: &Path) -> Option<String> {
+    let plaintext_path = if is_encrypted_vault_path(path) {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
+    };
+    let filename = plaintext_path.file_name()?.to_str()?;
+    parse_vault_key(filename).ok().map(|_| filename.to_string())
+}
+
+pub(crate) fn is_sharing_violation(err: &std::io::Error) -> bool {
+    cfg!(windows) && matches!(err.raw_os_error(), Some(32) | Some(33))
+}
+
+pub(crate) fn remove_cleanup_file(path: &Path) -> Result<bool, String> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(true),
+        Err(err) if is_sharing_violation(&err) => Ok(false),
+        Err(err) => Err(format!("failed to remove {}: {}", path.display(), err)),
+    }
+}
+
+pub(crate) fn cleanup_zero_ref_vault_files_reporting(
+    connection: &Connection,
+    reporter: &ProgressReporter,
+) -> Result<(), String> {
+    let grace_cutoff = Utc::now().timestamp_millis() - VAULT_ZERO_REF_GRACE_MS;
+    let mut stmt = connection
+        .prepare(
+            "SELECT vault_key, vault_path, sha256, ext
+             FROM vault_files
+             WHERE ref_count <= 0 AND updated_at <= ?1 AND is_protected = 0",
+        )
+        .map_err(|err| format!("failed to prepare zero-ref vault query: {}", err))?;
+    let rows = stmt
+        .query_map(params![grace_cutoff], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })
+        .map_err(|err| format!("failed to query zero-ref vault rows: {}", err))?;
+
+    let mut pending_rows = Vec::new();
+    for row_result in rows {
+        pending_rows
+            .push(row_result.map_err(|err| format!("failed to read zero-ref vault row: {}", err))?);
+    }
+    let protected_count: i64 = connection
+        .query_row(
+            "SELECT COUNT(*) FROM vault_files WHERE ref_count <= 0 AND is_protected = 1",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|err| format!("failed to count protected zero-ref vault rows: {}", err))?;
+    if protected_count > 0 {
+        log_println!(
+            "[vault-cleanup] keeping {} protected zero-ref vault file(s)",
+            protected_count
+        );
+    }
+    if pending_rows.is_empty() {
+        return Ok(());
+    }
+
+    let storage_root = ensure_storage_root_internal()?;
+    let mut prune_keys = Vec::new();
+    let mut deferred = 0_u64;
+    let total = pending_rows.len() as u64;
+    for (index, (vault_key, _vault_path, sha256, ext)) in pending_rows.into_iter().enumerate() {
+        reporter.report("scan", index as u64, total, Some(&vault_key));
+        if vault_key_in_use(&vault_key) {
+            deferred += 1;
+            continue;
+        }
+        let vault_filename = build_vault_filename(&sha256, &ext);
+        let existing_paths = find_vault_files(&storage_root, &vault_filename)
+            .map_err(|err| format!("failed to find zero-ref vault files: {}", err))?;
+
+        let mut cleanup_ok = true;
+        let mut cleanup_deferred = false;
+        for path in existing_paths {
+            match remove_cleanup_file(&path) {
+                Ok(true) => {}
+                Ok(false) => cleanup_deferred = true,
+                Err(err) => {
+                    cleanup_ok = false;
+                    log_eprintln!("failed to cleanup zero-ref vault file: {}", err);
+                }
+            }
+        }
+
+        match remove_thumbnail_for_vault_key(&vault_key) {
+            Ok(true) => {}
+            Ok(false) => cleanup_deferred = true,
+            Err(err) => {
+                cleanup_ok = false;
+                log_eprintln!(
+                    "failed to cleanup zero-ref thumbnail for vault key {}: {}",
+                    vault_key,
+                    err
+                );
+            }
+        }
+
+        if cleanup_deferred {
+            deferred += 1;
+        } else if cleanup_ok {
+            prune_keys.push(vault_key);
+        }
+    }
+    if deferred > 0 {
+        DEFERRED_VAULT_CLEANUPS.fetch_add(deferred, Ordering::Relaxed);
+        log_println!(
+            "[vault-cleanup] deferred {} in-use vault file(s) to the next pass",
+            deferred
+        );
+    }
+
+    for vault_key in prune_keys {
+        connection
+            .execute(
+                "DELETE FROM vault_files WHERE vault_key = ?1 AND ref_count <= 0 AND is_protected = 0",
+                params![vault_key],
+            )
+            .map_err(|err| format!("failed to prune zero-ref vault row: {}", err))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn find_vault_files(root: &Path, vault_filename: &str) -> Result<Vec<PathBuf>, String> {
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut matches = Vec::new();
+    let mut push_candidates = |directory: &Path| {
+        let candidate = directory.join(vault_filename);
+        let encrypted_candidate = encrypted_vault_path(&candidate);
+        if candidate.exists() {
+            matches.push(candidate);
+        }
+        if encrypted_candidate.exists() {
+            matches.push(encrypted_candidate);
+        }
+    };
+    push_candidates(root);
+    let years = read_directory(root)
+        .map_err(|err| format!("failed to read storage root {}: {}", root.display(), err))?;
+    for year_entry_result in years {
+        let year_entry = year_entry_result
+            .map_err(|err| format!("failed to read year folder in storage root: {}", err))?;
+        let year_path = year_entry.path();
+        if !year_path.is_dir() || year_entry.file_name() == QUARANTINE_DIRECTORY_NAME {
+            continue;
+        }
+        push_candidates(&year_path);
+
+        let months = read_directory(&year_path).map_err(|err| {
+            format!(
+                "failed to read year directory {}: {}",
+                year_path.display(),
+                err
+            )
+        })?;
+
+        for month_entry_result in months {
+            let month_entry = month_entry_result
+                .map_err(|err| format!("failed to read month folder in storage root: {}", err))?;
+            let month_path = month_entry.path();
+            if !month_path.is_dir() {
+                continue;
+            }
+            push_candidates(&month_path);
+        }
+    }
+
+    Ok(matches)
+}
+
+pub(crate) fn find_existing_vault_file(
+    root: &Path,
+    vault_filename: &str,
+) -> Result<Option<PathBuf>, String> {
+    let mut matches = find_vault_files(root, vault_filename)?;
+    Ok(matches.pop())
+}
+
+pub(crate) fn encrypted_vault_path(path: &Path) -> PathBuf {
+    let mut raw = path.as_os_str().to_os_string();
+    raw.push(".");
+    raw.push(VAULT_ENCRYPTED_SUFFIX);
+    PathBuf::from(raw)
+}
+
+pub(crate) fn is_encrypted_vault_path(path: &Path) -> bool {
+    let has_suffix = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(|ext| ext.eq_ignore_ascii_case(VAULT_ENCRYPTED_SUFFIX))
+        .unwrap_or(false);
+    has_suffix
+        && path
+            .file_stem()
+            .map(Path::new)
+            .and_then(Path::extension)
+            .is_some()
+}
+
+pub(crate) fn encrypted_vault_plaintext_len(encrypted_len: u64) -> u64 {
+    let header_len = (VAULT_ENCRYPTION_MAGIC.len() + VAULT_ENCRYPTION_NONCE_PREFIX_BYTES) as u64;
+    let body_len = encrypted_len.saturating_sub(header_len);
+    let sealed_chunk_len = (VAULT_ENCRYPTION_CHUNK_BYTES + VAULT_ENCRYPTION_TAG_BYTES) as u64;
+    let chunk_count = body_len / sealed_chunk_len + 1;
+    body_len.saturating_sub(chunk_count * VAULT_ENCRYPTION_TAG_BYTES as u64)
+}
+
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn hex_decode(value: &str) -> Option<Vec<u8>> {
+    let value = value.trim();
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(value.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+pub(crate) fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(filled)
+}
+
+pub(crate) fn vault_chunk_nonce(prefix: &[u8], counter: u32, last: bool) -> XNonce {
+    let mut nonce = [0_u8; 24];
+    nonce[..VAULT_ENCRYPTION_NONCE_PREFIX_BYTES].copy_from_slice(prefix);
+    nonce[VAULT_ENCRYPTION_NONCE_PREFIX_BYTES..23].copy_from_slice(&counter.to_be_bytes());
+    nonce[23] = u8::from(last);
+    XNonce::clone_from_slice(&nonce)
+}
+
+pub(crate) fn encrypt_vault_stream<R: Read, W: Write>(
+    key: &[u8; 32],
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<u64, String> {
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let mut prefix = [0_u8; VAULT_ENCRYPTION_NONCE_PREFIX_BYTES];
+    OsRng.fill_bytes(&mut prefix);
+    writer
+        .write_all(VAULT_ENCRYPTION_MAGIC)
+        .and_then(|_| writer.write_all(&prefix))
+        .map_err(|err| format!("failed to write encrypted vault header: {}", err))?;
+
+    let mut buffer = vec![0_u8; VAULT_ENCRYPTION_CHUNK_BYTES];
+    let mut counter = 0_u32;
+    let mut plaintext_len = 0_u64;
+    loop {
+        let read = read_full(reader, &mut buffer)
+            .map_err(|err| format!("failed to read vault plaintext: {}", err))?;
+        let last = read < buffer.len();
+        let sealed = cipher
+            .encrypt(&vault_chunk_nonce(&prefix, counter, last), &buffer[..read])
+            .map_err(|_| "failed to encrypt vault chunk".to_string())?;
+        writer
+            .write_all(&sealed)
+            .map_err(|err| format!("failed to write encrypted vault chunk: {}", err))?;
+        plaintext_len += read as u64;
+        if last {
+            break;
+        }
+        counter = counter
+            .checked_add(1)
+            .ok_or_else(|| "file is too large to encrypt".to_string())?;
+    }
+    writer
+        .flush()
+        .map_err(|err| format!("failed to flush encrypted vault file: {}", err))?;
+    Ok(plaintext_len)
+}
+
+pub(crate) fn decrypt_vault_stream<R: Read, W: Write>(
+    key: &[u8; 32],
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<u64, String> {
+    decrypt_vault_stream_prefix(key, reader, writer, u64::MAX)
+}
+
+/// Decrypts chunk by chunk and stops after the chunk that brings the output to `limit`
+/// plaintext bytes, so callers that only need a file's head skip the rest of it.
+pub(crate) fn decrypt_vault_stream_prefix<R: Read, W: Write>(
+    key: &[u8; 32],
+    reader: &mut R,
+    writer: &mut W,
+    limit: u64,
+) -> Result<u64, String> {
+    let mut header = [0_u8; 8 + VAULT_ENCRYPTION_NONCE_PREFIX_BYTES];
+    let header_read = read_full(reader, &mut header)
+        .map_err(|err| format!("failed to read encrypted vault header: {}", err))?;
+    if header_read < header.len() || &header[..8] != VAULT_ENCRYPTION_MAGIC {
+        return Err("file is not an encrypted vault file".to_string());
+    }
+    let prefix = &header[8..];
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+
+    let mut buffer = vec![0_u8; VAULT_ENCRYPTION_CHUNK_BYTES + VAULT_ENCRYPTION_TAG_BYTES];
+    let mut counter = 0_u32;
+    let mut plaintext_len = 0_u64;
+    loop {
+        let read = read_full(reader, &mut buffer)
+            .map_err(|err| format!("failed to read encrypted vault chunk: {}", err))?;
+        let last = read < buffer.len();
+        let opened = cipher
+            .decrypt(&vault_chunk_nonce(prefix, counter, last), &buffer[..read])
+            .map_err(|_| "failed to decrypt vault file: wrong key or corrupted data".to_string())?;
+        writer
+            .write_all(&opened)
+            .map_err(|err| format!("failed to write decrypted vault data: {}", err))?;
+        plaintext_len += opened.len() as u64;
+        if last || plaintext_len >= limit {
+            break;
+        }
+        counter = counter
+            .checked_add(1)
+            .ok_or_else(|| "encrypted vault file has too many chunks".to_string())?;
+    }
+    Ok(plaintext_len)
+}
+
+pub(crate) fn current_vault_data_key() -> Result<[u8; 32], String> {
+    VAULT_DATA_KEY
+        .lock()
+        .map_err(|_| "vault key lock poisoned".to_string())?
+        .ok_or_else(|| VAULT_LOCKED_ERROR.to_string())
+}
+
+pub(crate) fn set_vault_data_key(key: Option<[u8; 32]>) -> Result<(), String> {
+    *VAULT_DATA_KEY
+        .lock()
+        .map_err(|_| "vault key lock poisoned".to_string())? = key;
+    Ok(())
+}
+
+pub(crate) fn decrypt_vault_file_to_vec(path: &Path) -> Result<Vec<u8>, String> {
+    let key = current_vault_data_key()?;
+    let file = File::open(extended_length_path(path)).map_err(|err| {
+        format!(
+            "failed to open encrypted vault file {}: {}",
+            path.display(),
+            err
+        )
+    })?;
+    let mut reader = BufReader::new(file);
+    let mut plaintext = Vec::new();
+    decrypt_vault_stream(&key, &mut reader, &mut plaintext)?;
+    Ok(plaintext)
+}
+
+pub(crate) fn read_vault_file_bytes(path: &Path) -> Result<Vec<u8>, String> {
+    if is_encrypted_vault_path(path) {
+        return decrypt_vault_file_to_vec(path);
+    }
+    fs::read(extended_length_path(path))
+        .map_err(|err| format!("failed to read vault file {}: {}", path.display(), err))
+}
+
+pub(crate) fn read_vault_file_head(path: &Path, limit: usize) -> Result<Vec<u8>, String> {
+    if !is_encrypted_vault_path(path) {
+        return read_file_head(path, limit);
+    }
+    let key = current_vault_data_key()?;
+    let file = File::open(extended_length_path(path)).map_err(|err| {
+        format!(
+            "failed to open encrypted vault file {}: {}",
+            path.display(),
+            err
+        )
+    })?;
+    let mut capture = HeadCapture {
+        head: Vec::with_capacity(limit),
+        limit,
+    };
+    decrypt_vault_stream_prefix(&key, &mut BufReader::new(file), &mut capture, limit as u64)?;
+    Ok(capture.head)
+}
+
+pub(crate) fn copy_vault_file_plaintext(
+    vault_path: &Path,
+    destination: &Path,
+) -> Result<(), String> {
+    if !is_encrypted_vault_path(vault_path) {
+        fs::copy(
+            extended_length_path(vault_path),
+            extended_length_path(destination),
+        )
+        .map_err(|err| err.to_string())?;
+        return Ok(());
+    }
+    let key = current_vault_data_key()?;
+    let input = File::open(extended_length_path(vault_path)).map_err(|err| err.to_string())?;
+    let output = File::create(extended_length_path(destination)).map_err(|err| err.to_string())?;
+    let mut writer = std::io::BufWriter::new(output);
+    decrypt_vault_stream(&key, &mut BufReader::new(input), &mut writer)?;
+    writer.flush().map_err(|err| err.to_string())
+}
+
+pub(crate) fn encrypt_into_vault_file<R: Read>(
+    key: &[u8; 32],
+    reader: &mut R,
+    destination: &Path,
+) -> Result<u64, String> {
+    let mut partial_path = destination.as_os_str().to_os_string();
+    partial_path.push(".partial");
+    let partial_path = PathBuf::from(partial_path);
+    let outcome = File::create(extended_length_path(&partial_path))
+        .map_err(|err| format!("failed to create {}: {}", partial_path.display(), err))
+        .and_then(|file| {
+            let mut writer = std::io::BufWriter::new(file);
+            encrypt_vault_stream(key, reader, &mut writer)
+        })
+        .and_then(|plaintext_len| {
+            fs::rename(
+                extended_length_path(&partial_path),
+                extended_length_path(destination),
+            )
+            .map_err(|err| {
+                format!(
+                    "failed to move encrypted file into place {}: {}",
+                    destination.display(),
+                    err
+                )
+            })?;
+            Ok(plaintext_len)
+        });
+    if outcome.is_err() {
+        let _ = fs::remove_file(extended_length_path(&partial_path));
+    }
+    outcome
+}
+
+pub(crate) fn load_vault_encryption_params(
+    connection: &Connection,
+) -> Result<Option<VaultEncryptionParams>, String> {
+    let salt = read_app_setting(connection, SETTING_VAULT_ENCRYPTION_SALT)?;
+    let wrapped_key = read_app_setting(connection, SETTING_VAULT_ENCRYPTION_WRAPPED_KEY)?;
+    match (salt, wrapped_key) {
+        (Some(salt), Some(wrapped_key)) => Ok(Some(VaultEncryptionParams {
+            salt: hex_decode(&salt).ok_or_else(|| "stored vault salt is invalid".to_string())?,
+            wrapped_key: hex_decode(&wrapped_key)
+                .ok_or_else(|| "stored vault key is invalid".to_string())?,
+        })),
+        _ => Ok(None),
+    }
+}
+
+pub(crate) fn derive_passphrase_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut output = [0_u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut output)
+        .map_err(|err| format!("failed to derive vault key: {}", err))?;
+    Ok(output)
+}
+
+pub(crate) fn wrap_vault_data_key(
+    wrapping_key: &[u8; 32],
+    data_key: &[u8; 32],
+) -> Result<Vec<u8>, String> {
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(wrapping_key));
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let sealed = cipher
+        .encrypt(&nonce, data_key.as_slice())
+        .map_err(|_| "failed to wrap vault key".to_string())?;
+    let mut wrapped = nonce.to_vec();
+    wrapped.extend_from_slice(&sealed);
+    Ok(wrapped)
+}
+
+pub(crate) fn unwrap_vault_data_key(
+    wrapping_key: &[u8; 32],
+    wrapped: &[u8],
+) -> Result<[u8; 32], String> {
+    if wrapped.len() <= 24 {
+        return Err("stored vault key is invalid".to_string());
+    }
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(wrapping_key));
+    let opened = cipher
+        .decrypt(XNonce::from_slice(&wrapped[..24]), &wrapped[24..])
+        .map_err(|_| "incorrect vault passphrase".to_string())?;
+    opened
+        .try_into()
+        .map_err(|_| "stored vault key is invalid".to_string())
+}
+
+pub(crate) fn unlock_vault_data_key(
+    connection: &Connection,
+    passphrase: &str,
+) -> Result<[u8; 32], String> {
+    let params = load_vault_encryption_params(connection)?
+        .ok_or_else(|| "vault encryption is not enabled".to_string())?;
+    let wrapping_key = derive_passphrase_key(passphrase, &params.salt)?;
+    unwrap_vault_data_key(&wrapping_key, &params.wrapped_key)
+}
+
+/// Writes the salt and wrapped key together; a salt paired with a key wrapped under a
+/// different salt can never be unwrapped again.
+pub(crate) fn store_vault_data_key(
+    transaction: &Transaction<'_>,
+    passphrase: &str,
+    data_key: &[u8; 32],
+) -> Result<(), String> {
+    let mut salt = [0_u8; VAULT_ENCRYPTION_SALT_BYTES];
+    OsRng.fill_bytes(&mut salt);
+    let wrapping_key = derive_passphrase_key(passphrase, &salt)?;
+    let wrapped_key = wrap_vault_data_key(&wrapping_key, data_key)?;
+    write_app_setting(
+        transaction,
+        SETTING_VAULT_ENCRYPTION_SALT,
+        &hex_encode(&salt),
+    )?;
+    write_app_setting(
+        transaction,
+        SETTING_VAULT_ENCRYPTION_WRAPPED_KEY,
+        &hex_encode(&wrapped_key),
+    )?;
+    Ok(())
+}
+
+pub(crate) fn create_vault_data_key(passphrase: &str) -> Result<[u8; 32], String> {
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    if load_vault_encryption_params(&transaction)?.is_some() {
+        return Err("vault encryption is already enabled".to_string());
+    }
+    let mut data_key = [0_u8; 32];
+    OsRng.fill_bytes(&mut data_key);
+    store_vault_data_key(&transaction, passphrase, &data_key)?;
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit sqlite transaction: {}", err))?;
+    Ok(data_key)
+}
+
+pub(crate) fn rewrap_vault_data_key(
+    old_passphrase: &str,
+    new_passphrase: &str,
+) -> Result<[u8; 32], String> {
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    let data_key = unlock_vault_data_key(&transaction, old_passphrase)?;
+    store_vault_data_key(&transaction, new_passphrase, &data_key)?;
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit sqlite transaction: {}", err))?;
+    Ok(data_key)
+}
+
+pub(crate) fn validate_vault_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.chars().count() < VAULT_PASSPHRASE_MIN_CHARS {
+        return Err(format!(
+            "passphrase must be at least {} characters",
+            VAULT_PASSPHRASE_MIN_CHARS
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn vault_write_key() -> Result<Option<[u8; 32]>, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    if load_vault_encryption_params(&connection)?.is_none() {
+        return Ok(None);
+    }
+    current_vault_data_key().map(Some)
+}
+
+pub(crate) fn sha256_for_file(file_path: &Path) -> Result<String, String> {
+    let file = File::open(extended_length_path(file_path))
+        .map_err(|err| format!("failed to open file {}: {}", file_path.display(), err))?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+    let mut chunk = [0_u8; 64 * 1024];
+
+    loop {
+        let bytes_read = reader
+            .read(&mut chunk)
+            .map_err(|err| format!("failed to read file {}: {}", file_path.display(), err))?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&chunk[..bytes_read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub(crate) fn sha256_for_bytes(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+pub(crate) fn looks_like_svg(bytes: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(256)]).to_ascii_lowercase();
+    head.contains("<svg")
+}
+
+pub(crate) fn sniff_extension(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0x89, b'P', b'N', b'G']) {
+        return Some("png");
+    }
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some("jpg");
+    }
+    if bytes.starts_with(b"GIF8") {
+        return Some("gif");
+    }
+    if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Some("webp");
+    }
+    if bytes.starts_with(b"BM") && bytes.len() >= 14 {
+        return Some("bmp");
+    }
+    if bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+        return Some("tiff");
+    }
+    if bytes.starts_with(b"%PDF-") {
+        return Some("pdf");
+    }
+    if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" {
+        match &bytes[8..12] {
+            b"avif" | b"avis" => return Some("avif"),
+            b"heic" | b"heix" | b"mif1" => return Some("heic"),
+            _ => {}
+        }
+    }
+    if bytes.len() >= 4
+        && bytes[0] == 0x00
+        && bytes[1] == 0x00
+        && (bytes[2] == 0x01 || bytes[2] == 0x02)
+        && bytes[3] == 0x00
+    {
+        return Some("ico");
+    }
+    if looks_like_svg(bytes) {
+        return Some("svg");
+    }
+    None
+}
+
+pub(crate) fn canonical_sniffable_extension(ext: &str) -> Option<&'static str> {
+    match ext {
+        "png" => Some("png"),
+        "jpg" | "jpeg" | "jpe" | "jfif" => Some("jpg"),
+        "gif" => Some("gif"),
+        "webp" => Some("webp"),
+        "bmp" => Some("bmp"),
+        "tif" | "tiff" => Some("tiff"),
+        "pdf" => Some("pdf"),
+        "avif" => Some("avif"),
+        "heic" | "heif" => Some("heic"),
+        "ico" | "cur" => Some("ico"),
+        "svg" => Some("svg"),
+        _ => None,
+    }
+}
+
+pub(crate) fn resolve_sniffed_extension(claimed_ext: &str, sniffed_ext: Option<&str>) -> String {
+    let Some(sniffed_ext) = sniffed_ext else {
+        return claimed_ext.to_string();
+    };
+    match canonical_sniffable_extension(claimed_ext) {
+        Some(claimed) if claimed != sniffed_ext => sniffed_ext.to_string(),
+        None if claimed_ext == "bin" => sniffed_ext.to_string(),
+        _ => claimed_ext.to_string(),
+    }
+}
+
+pub(crate) fn read_file_head(path: &Path, limit: usize) -> Result<Vec<u8>, String> {
+    let file = File::open(extended_length_path(path))
+        .map_err(|err| format!("failed to open {}: {}", path.display(), err))?;
+    let mut head = Vec::with_capacity(limit);
+    file.take(limit as u64)
+        .read_to_end(&mut head)
+        .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+    Ok(head)
+}
+
+pub(crate) struct VaultEncryptionParams {
+    pub(crate) salt: Vec<u8>,
+    pub(crate) wrapped_key: Vec<u8>,
+}
+
+pub(crate) struct VaultImportComputation {
+    pub(crate) result: VaultImportResult,
+    pub(crate) hash_ms: u64,
+    pub(crate) copy_ms: u64,
+    pub(crate) deduped: bool,
+}
+
+pub(crate) fn register_imported_vault_file(
+    sha256: &str,
+    ext: &str,
+    vault_path: &Path,
+    size: u64,
+) -> Result<(), String> {
+    let is_encrypted = is_encrypted_vault_path(vault_path);
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let now = Utc::now().timestamp_millis();
+    connection
+        .execute(
+            "INSERT INTO vault_files (
+                vault_key,
+                vault_path,
+                sha256,
+                ext,
+                size_bytes,
+                ref_count,
+                is_encrypted,
+                created_at,
+                updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6, ?7, ?7)
+            ON CONFLICT(vault_key) DO UPDATE SET
+                vault_path = excluded.vault_path,
+                size_bytes = excluded.size_bytes,
+                is_encrypted = excluded.is_encrypted,
+                updated_at = excluded.updated_at",
+            params![
+                build_vault_filename(sha256, ext),
+                path_to_string(vault_path)?,
+                sha256,
+                ext,
+                size as i64,
+                i64::from(is_encrypted),
+                now
+            ],
+        )
+        .map_err(|err| format!("failed to register imported vault file: {}", err))?;
+    Ok(())
+}
+
+pub(crate) fn quarantine_imported_vault_file(
+    vault_key: &str,
+    vault_path: &Path,
+    original_filename: &str,
+    reason: &str,
+    detail: &str,
+    size: u64,
+) -> Result<Option<String>, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let ref_count = connection
+        .query_row(
+            "SELECT ref_count FROM vault_files WHERE vault_key = ?1",
+            params![vault_key],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to read vault ref count for {}: {}", vault_key, err))?
+        .unwrap_or(0);
+    if ref_count > 0 || vault_key_in_use(vault_key) {
+        return Ok(None);
+    }
+
+    let file_name = vault_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| {
+            format!(
+                "invalid vault path for quarantine: {}",
+                vault_path.display()
+            )
+        })?;
+    let destination = ensure_quarantine_root_internal()?.join(&file_name);
+    fs::rename(
+        extended_length_path(vault_path),
+        extended_length_path(&destination),
+    )
+    .map_err(|err| {
+        format!(
+            "failed to move {} into quarantine: {}",
+            vault_path.display(),
+            err
+        )
+    })?;
+
+    connection
+        .execute(
+            "DELETE FROM vault_files WHERE vault_key = ?1 AND ref_count = 0",
+            params![vault_key],
+        )
+        .map_err(|err| format!("failed to unregister quarantined vault file: {}", err))?;
+    connection
+        .execute(
+            "INSERT INTO quarantined_files (
+                file_name,
+                vault_key,
+                original_filename,
+                reason,
+                detail,
+                size_bytes,
+                quarantined_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT(file_name) DO UPDATE SET
+                original_filename = excluded.original_filename,
+                reason = excluded.reason,
+                detail = excluded.detail,
+                size_bytes = excluded.size_bytes,
+                quarantined_at = excluded.quarantined_at",
+            params![
+                file_name,
+                vault_key,
+                original_filename,
+                reason,
+                detail,
+                size as i64,
+                Utc::now().timestamp_millis()
+            ],
+        )
+        .map_err(|err| format!("failed to record quarantined file: {}", err))?;
+
+    log_println!(
+        "[import-quarantine] file={} vault_key={} reason={} detail={}",
+        original_filename,
+        vault_key,
+        reason,
+        detail
+    );
+    Ok(Some(format!(
+        "{}: {} image {} ({})",
+        IMPORT_QUARANTINED_ERROR_PREFIX, reason, original_filename, detail
+    )))
+}
+
+pub(crate) fn import_with_metadata_detailed(
+    source_path: Option<&Path>,
+    source_bytes: Option<&[u8]>,
+    requested_ext: Option<&str>,
+    original_filename: Option<&str>,
+    vault_directory: Option<&Path>,
+    known_sha256: Option<&str>,
+) -> Result<VaultImportComputation, String> {
+    let claimed_ext = match source_path {
+        Some(path) => extension_from_path(path),
+        None => requested_ext
+            .map(normalize_ext)
+            .or_else(|| original_filename.and_then(extension_from_filename))
+            .unwrap_or_else(|| "bin".to_string()),
+    };
+    let sniffed_ext = match (source_path, source_bytes) {
+        (Some(path), None) => read_file_head(path, CONTENT_SNIFF_HEAD_BYTES)
+            .ok()
+            .and_then(|head| sniff_extension(&head)),
+        (None, Some(bytes)) => sniff_extension(&bytes[..bytes.len().min(CONTENT_SNIFF_HEAD_BYTES)]),
+        _ => None,
+    };
+    let ext = resolve_sniffed_extension(&claimed_ext, sniffed_ext);
+    if ext != claimed_ext {
+        log_println!(
+            "[import-sniff] claimed={} sniffed={} filename={}",
+            claimed_ext,
+            ext,
+            original_filename.unwrap_or_default()
+        );
+    }
+    enforce_import_limits(source_path, source_bytes, &ext)?;
+    let write_key = vault_write_key()?;
+
+    let root = ensure_storage_root_internal()?;
+    let month_dir = match vault_directory.filter(|directory| directory.is_dir()) {
+        Some(directory) => directory.to_path_buf(),
+        None => ensure_current_month_directory(&root)?,
+    };
+
+    let hash_started_at = Instant::now();
+    let (sha256, fallback_filename) = match (source_path, source_bytes) {
+        (Some(path), None) => {
+            let sha = match known_sha256 {
+                Some(sha) => sha.to_string(),
+                None => sha256_for_file(path)?,
+            };
+            let filename = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "imported.bin".to_string());
+            (sha, filename)
+        }
+        (None, Some(bytes)) => {
+            let sha = sha256_for_bytes(bytes);
+            let filename = original_filename.unwrap_or("clipboard-image").to_string();
+            (sha, filename)
+        }
+        _ => {
+            return Err(
+                "invalid import request: provide either source_path or source_bytes".to_string(),
+            )
+        }
+    };
+    let hash_ms = hash_started_at.elapsed().as_millis() as u64;
+
+    let copy_started_at = Instant::now();
+    let vault_filename = build_vault_filename(&sha256, &ext);
+    let existing_path = find_existing_vault_file(&root, &vault_filename)?;
+
+    let (final_path, deduped) = if let Some(path) = existing_path {
+        (path, true)
+    } else {
+        let destination = month_dir.join(&vault_filename);
+        let written_path = match (source_path, source_bytes, write_key) {
+            (Some(path), None, Some(key)) => {
+                let destination = encrypted_vault_path(&destination);
+                let source = File::open(extended_length_path(path))
+                    .map_err(|err| format!("failed to open {}: {}", path.display(), err))?;
+                encrypt_into_vault_file(&key, &mut BufReader::new(source), &destination)?;
+                destination
+            }
+            (None, Some(bytes), Some(key)) => {
+                let destination = encrypted_vault_path(&destination);
+                encrypt_into_vault_file(&key, &mut Cursor::new(bytes), &destination)?;
+                destination
+            }
+            (Some(path), None, None) => {
+                fs::copy(
+                    extended_length_path(path),
+                    extended_length_path(&destination),
+                )
+                .map_err(|err| {
+                    format!(
+                        "failed to copy {} to {}: {}",
+                        path.display(),
+                        destination.display(),
+                        err
+                    )
+                })?;
+                destination
+            }
+            (None, Some(bytes), None) => {
+                let mut output =
+                    File::create(extended_length_path(&destination)).map_err(|err| {
+                        format!(
+                            "failed to create destination {}: {}",
+                            destination.display(),
+                            err
+                        )
+                    })?;
+                output.write_all(bytes).map_err(|err| {
+                    format!(
+                        "failed to write destination {}: {}",
+                        destination.display(),
+                        err
+                    )
+                })?;
+                output.flush().map_err(|err| {
+                    format!(
+                        "failed to flush destination {}: {}",
+                        destination.display(),
+                        err
+                    )
+                })?;
+                destination
+            }
+            _ => return Err("invalid import request while writing destination".to_string()),
+        };
+        (written_path, false)
+    };
+    let copy_ms = copy_started_at.elapsed().as_millis() as u64;
+
+    let stored_size = fs::metadata(extended_length_path(&final_path))
+        .map_err(|err| format!("failed to read metadata {}: {}", final_path.display(), err))?
+        .len();
+    let size = if is_encrypted_vault_path(&final_path) {
+        encrypted_vault_plaintext_len(stored_size)
+    } else {
+        stored_size
+    };
+    register_imported_vault_file(&sha256, &ext, &final_path, size)?;
+
+    Ok(VaultImportComputation {
+        result: VaultImportResult {
+            vault_path: path_to_string(&final_path)?,
+            sha256,
+            ext,
+            claimed_ext,
+            size,
+            created_at: Utc::now().to_rfc3339(),
+            original_filename: original_filename
+                .map(str::to_string)
+                .unwrap_or(fallback_filename),
+        },
+        hash_ms,
+        copy_ms,
+        deduped,
+    })
+}
+
+pub(crate) fn import_with_metadata(
+    source_path: Option<&Path>,
+    source_bytes: Option<&[u8]>,
+    requested_ext: Option<&str>,
+    original_filename: Option<&str>,
+) -> Result<VaultImportResult, String> {
+    Ok(import_with_metadata_detailed(
+        source_path,
+        source_bytes,
+        requested_ext,
+        original_filename,
+        None,
+        None,
+    )?
+    .result)
+}
+
+pub(crate) fn item_type_for_extension(ext: &str) -> &'static str {
+    match normalize_ext(ext).as_str() {
+        "png" | "jpg" | "jpeg" | "webp" | "gif" | "bmp" | "svg" => "image",
+        "mp4" | "webm" | "mov" | "mkv" | "avi" => "video",
+        "pdf" => "pdf",
+        _ => "file",
+    }
+}
+
+pub(crate) fn record_import_metrics(
+    filename: &str,
+    ext: &str,
+    size_bytes: u64,
+    metrics: &ImportPipelineMetrics,
+    success: bool,
+) -> Result<(), String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    connection
+        .execute(
+            "INSERT INTO import_metrics (
+                created_at,
+                filename,
+                ext,
+                size_bytes,
+                hash_ms,
+                copy_ms,
+                metadata_ms,
+                thumb_ms,
+                decode_ms,
+                resize_ms,
+                encode_ms,
+                total_ms,
+                deduped,
+                success
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                Utc::now().timestamp_millis(),
+                filename,
+                ext,
+                size_bytes as i64,
+                metrics.hash_ms as i64,
+                metrics.copy_ms as i64,
+                metrics.metadata_ms as i64,
+                metrics.thumb_ms as i64,
+                metrics.decode_ms as i64,
+                metrics.resize_ms as i64,
+                metrics.encode_ms as i64,
+                metrics.total_ms as i64,
+                metrics.deduped,
+                success
+            ],
+        )
+        .map_err(|err| format!("failed to insert import metrics row: {}", err))?;
+    connection
+        .execute(
+            "DELETE FROM import_metrics
+             WHERE id <= (
+               SELECT id FROM import_metrics ORDER BY id DESC LIMIT 1 OFFSET ?1
+             )",
+            params![IMPORT_METRICS_MAX_ROWS],
+        )
+        .map_err(|err| format!("failed to prune import metrics rows: {}", err))?;
+    Ok(())
+}
+
+pub(crate) fn run_import_pipeline_internal(
+    source_path: Option<PathBuf>,
+    source_bytes: Option<Vec<u8>>,
+    requested_ext: Option<String>,
+    original_filename: Option<String>,
+    thumb_policy: &str,
+    vault_directory: Option<&Path>,
+) -> Result<ImportPipelineResult, String> {
+    run_import_pipeline_prehashed(
+        source_path,
+        source_bytes,
+        requested_ext,
+        original_filename,
+        thumb_policy,
+        vault_directory,
+        None,
+    )
+}
+
+pub(crate) fn run_import_pipeline_prehashed(
+    source_path: Option<PathBuf>,
+    source_bytes: Option<Vec<u8>>,
+    requested_ext: Option<String>,
+    original_filename: Option<String>,
+    thumb_policy: &str,
+    vault_directory: Option<&Path>,
+    known_sha256: Option<String>,
+) -> Result<ImportPipelineResult, String> {
+    let started_at = Instant::now();
+    let fallback_filename = original_filename
+        .clone()
+        .or_else(|| {
+            source_path
+                .as_deref()
+                .and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_default();
+    let fallback_ext = requested_ext
+        .as_deref()
+        .map(normalize_ext)
+        .or_else(|| source_path.as_deref().map(extension_from_path))
+        .unwrap_or_else(|| "bin".to_string());
+    enforce_import_limits(
+        source_path.as_deref(),
+        source_bytes.as_deref(),
+        &fallback_ext,
+    )?;
+
+    let _activity = ImportActivityGuard::begin()?;
+    let outcome = run_import_pipeline_steps(
+        source_path,
+        source_bytes,
+        requested_ext,
+        original_filename,
+        thumb_policy,
+        vault_directory,
+        known_sha256,
+    );
+
+    let recorded = match &outcome {
+        Ok(result) => record_import_metrics(
+            &result.original_filename,
+            &result.ext,
+            result.size,
+            &result.metrics,
+            true,
+        ),
+        Err(_) => {
+            let total_ms = started_at.elapsed().as_millis() as u64;
+            let metrics = ImportPipelineMetrics {
+                hash_ms: 0,
+                copy_ms: 0,
+                metadata_ms: 0,
+                thumb_ms: 0,
+                decode_ms: 0,
+                resize_ms: 0,
+                encode_ms: 0,
+                total_ms,
+                deduped: false,
+            };
+            record_import_metrics(&fallback_filename, &fallback_ext, 0, &metrics, false)
+        }
+    };
+    if let Err(err) = recorded {
+        log_eprintln!("[import-pipeline] failed to record import metrics: {}", err);
+    }
+
+    outcome
+}
+
+pub(crate) fn run_import_pipeline_steps(
+    source_path: Option<PathBuf>,
+    source_bytes: Option<Vec<u8>>,
+    requested_ext: Option<String>,
+    original_filename: Option<String>,
+    thumb_policy: &str,
+    vault_directory: Option<&Path>,
+    known_sha256: Option<String>,
+) -> Result<ImportPipelineResult, String> {
+    let started_at = Instant::now();
+    let computation = import_with_metadata_detailed(
+        source_path.as_deref(),
+        source_bytes.as_deref(),
+        requested_ext.as_deref(),
+        original_filename.as_deref(),
+        vault_directory,
+        known_sha256.as_deref(),
+    )?;
+    let imported = computation.result;
+    let vault_key = build_vault_filename(&imported.sha256, &imported.ext);
+    let vault_path = PathBuf::from(&imported.vault_path);
+
+    let is_image = is_image_extension(&imported.ext);
+    let mut width = None;
+    let mut height = None;
+    let mut metadata_ms = 0_u64;
+    let mut thumb_ms = 0_u64;
+    let mut decode_ms = 0_u64;
+    let mut resize_ms = 0_u64;
+    let mut encode_ms = 0_u64;
+    let mut thumb_status = if is_image {
+        DEFAULT_THUMB_STATUS.to_string()
+    } else {
+        "ready".to_string()
+    };
+    let mut thumb_path: Option<String> = None;
+    let mut decode_failure: Option<(&'static str, String)> = None;
+
+    if is_image {
+        // Only an immediate thumbnail with room on disk and no existing output needs the
+        // full decode; read the source once so the header probe and decode share the bytes.
+        let thumb_output = (thumb_policy == IMPORT_THUMB_POLICY_IMMEDIATE
+            && matches!(thumbnail_disk_space_shortfall(), Ok(None)))
+        .then(|| thumb_output_path_for_vault_key(&vault_key));
+        let needs_decode = matches!(&thumb_output, Some(Ok(path)) if !path.exists());
+
+        let metadata_started_at = Instant::now();
+        let mut image_bytes: Option<Vec<u8>> = None;
+        let dimensions = if needs_decode {
+            read_image_source_bytes(&vault_path).and_then(|bytes| {
+                let dimensions = read_image_dimensions_from_bytes(&bytes, &vault_path)?;
+                image_bytes = Some(bytes);
+                Ok(dimensions)
+            })
+        } else {
+            read_image_dimensions(&vault_path)
+        };
+        match dimensions {
+            Ok((w, h)) => {
+                width = Some(w);
+                height = Some(h);
+            }
+            Err(err) => {
+                log_eprintln!(
+                    "[import-pipeline] failed to read dimensions for {}: {}",
+                    vault_path.display(),
+                    err
+                );
+                decode_failure = diagnose_image_decode_failure(&vault_path);
+                thumb_status = "error".to_string();
+            }
+        }
+        metadata_ms = metadata_started_at.elapsed().as_millis() as u64;
+
+        let should_skip_thumb = match (width, height) {
+            (Some(w), Some(h)) => w.max(h) <= IMPORT_THUMB_MAX_SIZE,
+            _ => false,
+        };
+
+        if thumb_status != "error" {
+            // Thumbnails are stored in plaintext, so encrypted files never get one.
+            if should_skip_thumb
+                || thumb_policy == IMPORT_THUMB_POLICY_NEVER
+                || is_encrypted_vault_path(&vault_path)
+            {
+                thumb_status = "skipped".to_string();
+            } else if let Some(thumb_output) = thumb_output {
+                let thumb_started_at = Instant::now();
+                match thumb_output {
+                    Ok(path) => {
+                        let generated = match image_bytes.take() {
+                            Some(bytes) => {
+                                let decode_started_at = Instant::now();
+                                let decoded = decode_image_bytes(&bytes);
+                                drop(bytes);
+                                decode_ms = decode_started_at.elapsed().as_millis() as u64;
+                                match decoded {
+                                    Ok(image) => generate_thumbnail_from_image(
+                                        image,
+                                        &vault_path,
+                                        &path,
+                                        IMPORT_THUMB_MAX_SIZE,
+                                    )
+                                    .map(|timings| {
+                                        resize_ms = timings.resize_ms;
+                                        encode_ms = timings.encode_ms;
+                                        log_thumbnail_generation(
+                                            &vault_path,
+                                            &path,
+                                            IMPORT_THUMB_MAX_SIZE,
+                                            decode_ms,
+                                            &timings,
+                                            thumb_started_at.elapsed().as_millis() as u64,
+                                        );
+                                    }),
+                                    Err(err) => {
+                                        decode_failure = Some((
+                                            classify_image_decode_error(&err),
+                                            err.to_string(),
+                                        ));
+                                        Err(format!(
+                                            "failed to decode image {}: {}",
+                                            vault_path.display(),
+                                            err
+                                        ))
+                                    }
+                                }
+                            }
+                            None => generate_thumbnail_internal(
+                                &vault_path,
+                                &path,
+                                IMPORT_THUMB_MAX_SIZE,
+                            ),
+                        };
+                        match generated {
+                            Ok(_) => {
+                                thumb_status = "ready".to_string();
+                                thumb_path = Some(path_to_string(&path)?);
+                            }
+                            Err(err) => {
+                                log_eprintln!(
+                                    "[import-pipeline] failed to generate thumbnail for {}: {}",
+                                    vault_path.display(),
+                                    err
+                                );
+                                if decode_failure.is_none() {
+                                    decode_failure = diagnose_image_decode_failure(&vault_path);
+                                }
+                                thumb_status = "error".to_string();
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        log_eprintln!(
+                            "[import-pipeline] failed to compute thumbnail path for key {}: {}",
+                            vault_key,
+                            err
+                        );
+                        thumb_status = "error".to_string();
+                    }
+                }
+                thumb_ms = thumb_started_at.elapsed().as_millis() as u64;
+            } else {
+                thumb_status = DEFAULT_THUMB_STATUS.to_string();
+            }
+        }
+    }
+
+    if let Some((reason, detail)) = decode_failure {
+        if computation.deduped {
+            log_eprintln!(
+                "[import-pipeline] {} image {} is already in the vault; leaving it in place",
+                reason,
+                imported.original_filename
+            );
+        } else if let Some(message) = quarantine_imported_vault_file(
+            &vault_key,
+            &vault_path,
+            &imported.original_filename,
+            reason,
+            &detail,
+            imported.size,
+        )? {
+            return Err(message);
+        }
+    }
+
+    let total_ms = started_at.elapsed().as_millis() as u64;
+    let metrics = ImportPipelineMetrics {
+        hash_ms: computation.hash_ms,
+        copy_ms: computation.copy_ms,
+        metadata_ms,
+        thumb_ms,
+        decode_ms,
+        resize_ms,
+        encode_ms,
+        total_ms,
+        deduped: computation.deduped,
+    };
+
+    log_println!(
+        "[import-pipeline] file={} hash_ms={} copy_ms={} metadata_ms={} thumb_ms={} decode_ms={} resize_ms={} encode_ms={} total_ms={} deduped={} thumb_status={}",
+        imported.original_filename,
+        metrics.hash_ms,
+        metrics.copy_ms,
+        metrics.metadata_ms,
+        metrics.thumb_ms,
+        metrics.decode_ms,
+        metrics.resize_ms,
+        metrics.encode_ms,
+        metrics.total_ms,
+        metrics.deduped,
+        thumb_status
+    );
+
+    Ok(ImportPipelineResult {
+        vault_path: imported.vault_path,
+        sha256: imported.sha256,
+        ext: imported.ext,
+        claimed_ext: imported.claimed_ext,
+        size: imported.size,
+        created_at: imported.created_at,
+        original_filename: imported.original_filename,
+        width,
+        height,
+        thumb_status,
+        thumb_path,
+        metrics,
+    })
+}
+
+pub(crate) fn import_metrics_total_ms_percentile(
+    connection: &Connection,
+    since_ms: i64,
+    sample_count: i64,
+    percentile: f64,
+) -> Result<i64, String> {
+    if sample_count <= 0 {
+        return Ok(0);
+    }
+    let rank = ((percentile * sample_count as f64).ceil() as i64).clamp(1, sample_count);
+    connection
+        .query_row(
+            "SELECT total_ms
+             FROM import_metrics
+             WHERE created_at >= ?1 AND success = 1
+             ORDER BY total_ms ASC
+             LIMIT 1 OFFSET ?2",
+            params![since_ms, rank - 1],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to read import metrics percentile: {}", err))
+        .map(|value| value.unwrap_or(0))
+}
+
+#[tauri::command]
+pub(crate) fn get_import_metrics_summary(
+    days: Option<u32>,
+) -> Result<ImportMetricsSummary, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let bounded_days = days.unwrap_or(30).max(1);
+    let since_ms = Utc::now().timestamp_millis() - i64::from(bounded_days) * 24 * 60 * 60 * 1000;
+
+    let (import_count, success_count, deduped_count) = connection
+        .query_row(
+            "SELECT
+                COUNT(*),
+                COALESCE(SUM(success), 0),
+                COALESCE(SUM(CASE WHEN success = 1 THEN deduped ELSE 0 END), 0)
+             FROM import_metrics
+             WHERE created_at >= ?1",
+            params![since_ms],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            },
+        )
+        .map_err(|err| format!("failed to count import metrics: {}", err))?;
+
+    let (
+        total_bytes,
+        avg_hash_ms,
+        avg_copy_ms,
+        avg_metadata_ms,
+        avg_thumb_ms,
+        avg_decode_ms,
+        avg_resize_ms,
+        avg_encode_ms,
+        avg_total_ms,
+        sum_total_ms,
+        max_total_ms,
+    ) = connection
+        .query_row(
+            "SELECT
+                COALESCE(SUM(size_bytes), 0),
+                COALESCE(AVG(hash_ms), 0.0),
+                COALESCE(AVG(copy_ms), 0.0),
+                COALESCE(AVG(metadata_ms), 0.0),
+                COALESCE(AVG(thumb_ms), 0.0),
+                COALESCE(AVG(decode_ms), 0.0),
+                COALESCE(AVG(resize_ms), 0.0),
+                COALESCE(AVG(encode_ms), 0.0),
+                COALESCE(AVG(total_ms), 0.0),
+                COALESCE(SUM(total_ms), 0),
+                COALESCE(MAX(total_ms), 0)
+             FROM import_metrics
+             WHERE created_at >= ?1 AND success = 1",
+            params![since_ms],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, f64>(1)?,
+                    row.get::<_, f64>(2)?,
+                    row.get::<_, f64>(3)?,
+                    row.get::<_, f64>(4)?,
+                    row.get::<_, f64>(5)?,
+                    row.get::<_, f64>(6)?,
+                    row.get::<_, f64>(7)?,
+                    row.get::<_, f64>(8)?,
+                    row.get::<_, i64>(9)?,
+                    row.get::<_, i64>(10)?,
+                ))
+            },
+        )
+        .map_err(|err| format!("failed to aggregate import metrics: {}", err))?;
+
+    let p50_total_ms =
+        import_metrics_total_ms_percentile(&connection, since_ms, success_count, 0.5)?;
+    let p95_total_ms =
+        import_metrics_total_ms_percentile(&connection, since_ms, success_count, 0.95)?;
+    let bytes_per_second = if sum_total_ms > 0 {
+        total_bytes as f64 / (sum_total_ms as f64 / 1000.0)
+    } else {
+        0.0
+    };
+
+    Ok(ImportMetricsSummary {
+        days: bounded_days,
+        import_count,
+        success_count,
+        failure_count: import_count - success_count,
+        deduped_count,
+        total_bytes,
+        avg_hash_ms,
+        avg_copy_ms,
+        avg_metadata_ms,
+        avg_thumb_ms,
+        avg_decode_ms,
+        avg_resize_ms,
+        avg_encode_ms,
+        avg_total_ms,
+        p50_total_ms,
+        p95_total_ms,
+        max_total_ms,
+        bytes_per_second,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn get_import_limits() -> Result<ImportLimits, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    load_import_limits(&connection)
+}
+
+#[tauri::command]
+pub(crate) fn update_import_limits(
+    max_file_bytes: Option<u64>,
+    allowlist: Option<Vec<String>>,
+    denylist: Option<Vec<String>>,
+) -> Result<ImportLimits, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    if let Some(value) = max_file_bytes {
+        write_app_setting(
+            &connection,
+            SETTING_MAX_IMPORT_FILE_BYTES,
+            &value.to_string(),
+        )?;
+    }
+    if let Some(values) = allowlist {
+        let normalized = parse_import_extension_list(&values.join(","));
+        write_app_setting(
+            &connection,
+            SETTING_IMPORT_EXTENSION_ALLOWLIST,
+            &normalized.join(","),
+        )?;
+    }
+    if let Some(values) = denylist {
+        let normalized = parse_import_extension_list(&values.join(","));
+        write_app_setting(
+            &connection,
+            SETTING_IMPORT_EXTENSION_DENYLIST,
+            &normalized.join(","),
+        )?;
+    }
+    load_import_limits(&connection)
+}
+
+#[tauri::command]
+pub(crate) async fn run_vault_cleanup(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || run_vault_maintenance(&app))
+        .await
+        .map_err(|err| format!("vault cleanup thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn get_vault_maintenance_status(
+    state: State<'_, VaultMaintenanceState>,
+) -> Result<VaultMaintenanceStatus, String> {
+    let in_use_keys = VAULT_KEYS_IN_USE
+        .lock()
+        .map_err(|_| "vault key usage lock poisoned".to_string())?
+        .len();
+    Ok(VaultMaintenanceStatus {
+        running: state.running.load(Ordering::SeqCst),
+        in_use_keys,
+        deferred_cleanups: DEFERRED_VAULT_CLEANUPS.load(Ordering::Relaxed),
+    })
+}
+
+#[tauri::command]
+pub(crate) fn acquire_vault_keys(vault_keys: Vec<String>) {
+    for vault_key in vault_keys {
+        acquire_vault_key_use(vault_key.trim());
+    }
+}
+
+#[tauri::command]
+pub(crate) fn release_vault_keys(vault_keys: Vec<String>) {
+    for vault_key in vault_keys {
+        release_vault_key_use(vault_key.trim());
+    }
+}
+
+#[tauri::command]
+pub(crate) fn get_disk_usage() -> Result<DiskUsage, String> {
+    let root = ensure_storage_root_internal()?;
+    let total_bytes = fs2::total_space(&root).map_err(|err| {
+        format!(
+            "failed to read total disk space for {}: {}",
+            root.display(),
+            err
+        )
+    })?;
+    let available_bytes = read_available_disk_space(&root)?;
+    let free_bytes = fs2::free_space(&root).map_err(|err| {
+        format!(
+            "failed to read free disk space for {}: {}",
+            root.display(),
+            err
+        )
+    })?;
+    Ok(DiskUsage {
+        path: path_to_string(&root)?,
+        total_bytes,
+        available_bytes,
+        used_bytes: total_bytes.saturating_sub(free_bytes),
+        thumbnails_paused: thumbnail_disk_space_shortfall()?.is_some(),
+    })
+}
+
+pub(crate) const RECENT_IMPORT_DEFAULT_HOURS: u32 = 24;
+pub(crate) const RECENT_IMPORT_MAX_HOURS: u32 = 24 * 30;
+pub(crate) const RECENT_IMPORT_DEFAULT_GROUPS: u32 = 24;
+pub(crate) const RECENT_IMPORT_MAX_GROUPS: u32 = 200;
+pub(crate) const RECENT_IMPORT_PREVIEWS_PER_GROUP: i64 = 4;
+pub(crate) const RECENT_IMPORT_BUCKET_MS: i64 = 60 * 60 * 1000;
+
+#[tauri::command]
+pub(crate) fn get_recently_imported(
+    hours: Option<u32>,
+    limit: Option<u32>,
+) -> Result<RecentlyImported, String> {
+    let hours = hours
+        .unwrap_or(RECENT_IMPORT_DEFAULT_HOURS)
+        .clamp(1, RECENT_IMPORT_MAX_HOURS);
+    let limit = limit
+        .unwrap_or(RECENT_IMPORT_DEFAULT_GROUPS)
+        .clamp(1, RECENT_IMPORT_MAX_GROUPS);
+    let since = Utc::now().timestamp_millis() - i64::from(hours) * RECENT_IMPORT_BUCKET_MS;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let show_sensitive = resolve_show_sensitive(&connection, None)?;
+    prepare_locked_collection_scope(&connection)?;
+    let thumbs_root = thumbs_root_path()?;
+
+    let mut stmt = connection
+        .prepare(&format!(
+            "WITH recent AS (
+                SELECT i.id, i.type, i.title, i.vault_key, i.thumb_status, i.created_at, i.import_batch_id,
+                       COALESCE(i.import_batch_id, CAST((i.created_at / ?2) * ?2 AS TEXT)) AS group_key
+                FROM items AS i
+                WHERE i.created_at >= ?1
+                  AND i.is_archived = 0
+                  AND (?3 OR i.is_sensitive = 0)
+                  AND {}
+             ),
+             ranked AS (
+                SELECT recent.*,
+                       ROW_NUMBER() OVER (PARTITION BY group_key ORDER BY created_at DESC, id DESC) AS position,
+                       COUNT(*) OVER (PARTITION BY group_key) AS group_count,
+                       MIN(created_at) OVER (PARTITION BY group_key) AS group_started_at,
+                       MAX(created_at) OVER (PARTITION BY group_key) AS group_finished_at
+                FROM recent
+             ),
+             ordered AS (
+                SELECT ranked.*,
+                       DENSE_RANK() OVER (ORDER BY group_finished_at DESC, group_key DESC) AS group_rank
+                FROM ranked
+                WHERE position <= ?4
+             )
+             SELECT group_key, group_started_at, group_finished_at, group_count,
+                    id, type, title, vault_key, thumb_status, import_batch_id
+             FROM ordered
+             WHERE group_rank <= ?5
+             ORDER BY group_rank ASC, position ASC",
+            LOCKED_SCOPE_VISIBLE_CONDITION
+        ))
+        .map_err(|err| format!("failed to prepare recently imported query: {}", err))?;
+    let rows = stmt
+        .query_map(
+            params![
+                since,
+                RECENT_IMPORT_BUCKET_MS,
+                show_sensitive,
+                RECENT_IMPORT_PREVIEWS_PER_GROUP,
+                limit
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, String>(6)?,
+                    row.get::<_, String>(7)?,
+                    row.get::<_, String>(8)?,
+                    row.get::<_, Option<String>>(9)?,
+                ))
+            },
+        )
+        .map_err(|err| format!("failed to query recently imported items: {}", err))?;
+
+    let mut groups: Vec<RecentImportGroup> = Vec::new();
+    for row_result in rows {
+        let (
+            group_key,
+            started_at,
+            finished_at,
+            item_count,
+            item_id,
+            item_type,
+            title,
+            vault_key,
+            thumb_status,
+            import_batch_id,
+        ) = row_result.map_err(|err| format!("failed to read recently imported row: {}", err))?;
+        let thumb_path = if thumb_status == "ready" {
+            thumb_filename_for_vault_key(&vault_key)
+                .ok()
+                .map(|filename| thumbs_root.join(filename).to_string_lossy().to_string())
+        } else {
+            None
+        };
+        let preview = RecentImportPreview {
+            item_id,
+            item_type,
+            title,
+            thumb_path,
+        };
+        match groups.last_mut() {
+            Some(group) if group.group_key == group_key => group.previews.push(preview),
+            _ => groups.push(RecentImportGroup {
+                group_key,
+                import_batch_id,
+                started_at,
+                finished_at,
+                item_count,
+                previews: vec![preview],
+            }),
+        }
+    }
+
+    Ok(RecentlyImported {
+        hours,
+        since,
+        groups,
+    })
+}
+
+pub(crate) fn backfill_vault_file_sizes(connection: &Connection) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("SELECT vault_key, vault_path FROM vault_files WHERE size_bytes <= 0")
+        .map_err(|err| format!("failed to prepare vault size backfill query: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|err| format!("failed to query vault rows missing sizes: {}", err))?;
+
+    let mut sizes = Vec::new();
+    for row_result in rows {
+        let (vault_key, vault_path) =
+            row_result.map_err(|err| format!("failed to read vault size backfill row: {}", err))?;
+        if let Ok(metadata) = fs::metadata(&vault_path) {
+            if metadata.len() > 0 {
+                sizes.push((vault_key, metadata.len() as i64));
+            }
+        }
+    }
+
+    for (vault_key, size_bytes) in sizes {
+        connection
+            .execute(
+                "UPDATE vault_files SET size_bytes = ?1 WHERE vault_key = ?2",
+                params![size_bytes, vault_key],
+            )
+            .map_err(|err| format!("failed to backfill vault file size: {}", err))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn get_vault_file_info(vault_key: String) -> Result<VaultFileInfo, String> {
+    let vault_key = vault_key.trim().to_string();
+    if vault_key.is_empty() {
+        return Err("vault key cannot be empty".to_string());
+    }
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    backfill_vault_file_sizes(&connection)?;
+
+    let mut info = connection
+        .query_row(
+            "SELECT vault_key, vault_path, sha256, ext, size_bytes, ref_count, is_encrypted, created_at, updated_at,
+                    (SELECT COUNT(*) FROM item_file_versions AS v WHERE v.vault_key = vault_files.vault_key),
+                    is_protected
+             FROM vault_files
+             WHERE vault_key = ?1",
+            params![&vault_key],
+            |row| {
+                Ok(VaultFileInfo {
+                    vault_key: row.get(0)?,
+                    vault_path: row.get(1)?,
+                    sha256: row.get(2)?,
+                    ext: row.get(3)?,
+                    size_bytes: row.get(4)?,
+                    ref_count: row.get(5)?,
+                    is_encrypted: row.get::<_, i64>(6)? != 0,
+                    is_protected: row.get::<_, i64>(10)? != 0,
+                    created_at: row.get(7)?,
+                    updated_at: row.get(8)?,
+                    file_exists: false,
+                    thumb_path: None,
+                    thumb_exists: false,
+                    version_count: row.get(9)?,
+                    items: Vec::new(),
+                })
+            },
+        )
+        .optional()
+        .map_err(|err| format!("failed to read vault file: {}", err))?
+        .ok_or_else(|| format!("vault file not found: {}", vault_key))?;
+
+    info.file_exists = path_from_ipc_string(&info.vault_path).is_file();
+    if is_image_extension(&info.ext) {
+        let thumb_path = thumbs_root_path()?.join(thumb_filename_for_vault_key(&info.vault_key)?);
+        info.thumb_exists = thumb_path.is_file();
+        info.thumb_path = Some(path_to_string(&thumb_path)?);
+    }
+
+    let mut stmt = connection
+        .prepare("SELECT id, type, title FROM items WHERE vault_key = ?1 ORDER BY created_at ASC")
+        .map_err(|err| format!("failed to prepare vault file references query: {}", err))?;
+    let rows = stmt
+        .query_map(params![&vault_key], |row| {
+            Ok(VaultFileReference {
+                item_id: row.get(0)?,
+                item_type: row.get(1)?,
+                title: row.get(2)?,
+            })
+        })
+        .map_err(|err| format!("failed to query vault file references: {}", err))?;
+    for row_result in rows {
+        info.items.push(
+            row_result
+                .map_err(|err| format!("failed to read vault file reference row: {}", err))?,
+        );
+    }
+    Ok(info)
+}
+
+pub(crate) fn load_protected_vault_keys(
+    connection: &Connection,
+) -> Result<BTreeSet<String>, String> {
+    let mut stmt = connection
+        .prepare("SELECT vault_key FROM vault_files WHERE is_protected = 1")
+        .map_err(|err| format!("failed to prepare protected vault files query: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to query protected vault files: {}", err))?;
+    let mut keys = BTreeSet::new();
+    for row_result in rows {
+        keys.insert(
+            row_result.map_err(|err| format!("failed to read protected vault key: {}", err))?,
+        );
+    }
+    Ok(keys)
+}
+
+#[tauri::command]
+pub(crate) fn set_vault_file_protected(vault_key: String, protected: bool) -> Result<i64, String> {
+    let vault_key = vault_key.trim().to_string();
+    if vault_key.is_empty() {
+        return Err("vault key cannot be empty".to_string());
+    }
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let updated_at = Utc::now().timestamp_millis();
+    let affected = connection
+        .execute(
+            "UPDATE vault_files SET is_protected = ?1, updated_at = ?2 WHERE vault_key = ?3",
+            params![i64::from(protected), updated_at, &vault_key],
+        )
+        .map_err(|err| format!("failed to update vault file protection: {}", err))?;
+    if affected == 0 {
+        return Err(format!("vault file not found: {}", vault_key));
+    }
+    Ok(updated_at)
+}
+
+#[tauri::command]
+pub(crate) fn protect_item_files(item_ids: Vec<String>) -> Result<ProtectItemFilesResult, String> {
+    let normalized_item_ids = normalize_item_ids_input(item_ids);
+    let updated_at = Utc::now().timestamp_millis();
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    let mut vault_keys = BTreeSet::new();
+    for item_id in &normalized_item_ids {
+        let vault_key = transaction
+            .query_row(
+                "SELECT vault_key FROM items WHERE id = ?1",
+                params![item_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(|err| format!("failed to read item vault key: {}", err))?
+            .ok_or_else(|| format!("item not found: {}", item_id))?;
+        if !vault_key.trim().is_empty() {
+            vault_keys.insert(vault_key);
+        }
+        for (version_key, _) in load_item_file_version_keys_in_tx(&transaction, item_id)? {
+            vault_keys.insert(version_key);
+        }
+    }
+
+    let mut protected_keys = Vec::new();
+    for vault_key in vault_keys {
+        let affected = transaction
+            .execute(
+                "UPDATE vault_files SET is_protected = 1, updated_at = ?1 WHERE vault_key = ?2",
+                params![updated_at, &vault_key],
+            )
+            .map_err(|err| format!("failed to protect vault file: {}", err))?;
+        if affected > 0 {
+            protected_keys.push(vault_key);
+        }
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit sqlite transaction: {}", err))?;
+
+    Ok(ProtectItemFilesResult {
+        vault_keys: protected_keys,
+        updated_at,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn list_largest_vault_files(
+    limit: Option<u32>,
+) -> Result<Vec<LargestVaultFile>, String> {
+    let limit = limit.unwrap_or(50).clamp(1, ITEM_PAGE_MAX_LIMIT);
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    backfill_vault_file_sizes(&connection)?;
+    query_vault_file_summaries(&connection, "1 = 1", limit)
+}
+
+#[tauri::command]
+pub(crate) fn list_protected_vault_files() -> Result<Vec<LargestVaultFile>, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    backfill_vault_file_sizes(&connection)?;
+    query_vault_file_summaries(&connection, "v.is_protected = 1", u32::MAX)
+}
+
+#[tauri::command]
+pub(crate) fn list_quarantined_files() -> Result<Vec<QuarantinedFile>, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let quarantine_root = quarantine_root_path()?;
+    let mut stmt = connection
+        .prepare(
+            "SELECT file_name, vault_key, original_filename, reason, detail, size_bytes, quarantined_at
+             FROM quarantined_files
+             ORDER BY quarantined_at DESC, file_name ASC",
+        )
+        .map_err(|err| format!("failed to prepare quarantined files query: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, i64>(5)?,
+                row.get::<_, i64>(6)?,
+            ))
+        })
+        .map_err(|err| format!("failed to query quarantined files: {}", err))?;
+
+    let mut files = Vec::new();
+    for row in rows {
+        let (file_name, vault_key, original_filename, reason, detail, size_bytes, quarantined_at) =
+            row.map_err(|err| format!("failed to parse quarantined file row: {}", err))?;
+        let path = quarantine_root.join(&file_name);
+        files.push(QuarantinedFile {
+            exists: path.is_file(),
+            path: path_to_string(&path)?,
+            file_name,
+            vault_key,
+            original_filename,
+            reason,
+            detail,
+            size_bytes,
+            quarantined_at,
+        });
+    }
+    Ok(files)
+}
+
+#[tauri::command]
+pub(crate) fn purge_quarantined_files(
+    file_names: Option<Vec<String>>,
+) -> Result<PurgeQuarantinedFilesResult, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let quarantine_root = quarantine_root_path()?;
+    let requested: Option<BTreeSet<String>> = file_names.map(|names| {
+        names
+            .into_iter()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect()
+    });
+
+    let mut stmt = connection
+        .prepare("SELECT file_name FROM quarantined_files ORDER BY file_name ASC")
+        .map_err(|err| format!("failed to prepare quarantined files query: {}", err))?;
+    let known = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to query quarantined files: {}", err))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to parse quarantined file row: {}", err))?;
+
+    let mut result = PurgeQuarantinedFilesResult {
+        removed: 0,
+        missing: 0,
+        deferred: 0,
+        freed_bytes: 0,
+    };
+    for file_name in known {
+        if requested
+            .as_ref()
+            .is_some_and(|requested| !requested.contains(&file_name))
+        {
+            continue;
+        }
+        if Path::new(&file_name).file_name().and_then(OsStr::to_str) != Some(file_name.as_str()) {
+            log_eprintln!("[quarantine] skipping invalid file name {}", file_name);
+            continue;
+        }
+        let path = quarantine_root.join(&file_name);
+        match fs::metadata(&path) {
+            Ok(metadata) => {
+                if !remove_cleanup_file(&path)? {
+                    result.deferred += 1;
+                    continue;
+                }
+                result.removed += 1;
+                result.freed_bytes += metadata.len();
+            }
+            Err(_) => result.missing += 1,
+        }
+        connection
+            .execute(
+                "DELETE FROM quarantined_files WHERE file_name = ?1",
+                params![file_name],
+            )
+            .map_err(|err| format!("failed to delete quarantined file row: {}", err))?;
+    }
+
+    log_println!(
+        "[quarantine] purged removed={} missing={} deferred={} freed_bytes={}",
+        result.removed,
+        result.missing,
+        result.deferred,
+        result.freed_bytes
+    );
+    Ok(result)
+}
+
+pub(crate) fn query_vault_file_summaries(
+    connection: &Connection,
+    condition: &str,
+    limit: u32,
+) -> Result<Vec<LargestVaultFile>, String> {
+    let mut stmt = connection
+        .prepare(&format!(
+            "SELECT v.vault_key, v.vault_path, v.ext, v.size_bytes, v.ref_count,
+                    (SELECT COUNT(*) FROM items AS i WHERE i.vault_key = v.vault_key),
+                    (SELECT i.title FROM items AS i WHERE i.vault_key = v.vault_key ORDER BY i.created_at ASC LIMIT 1),
+                    v.is_protected
+             FROM vault_files AS v
+             WHERE {}
+             ORDER BY v.size_bytes DESC, v.vault_key ASC
+             LIMIT ?1",
+            condition
+        ))
+        .map_err(|err| format!("failed to prepare largest vault files query: {}", err))?;
+    let rows = stmt
+        .query_map(params![limit], |row| {
+            Ok(LargestVaultFile {
+                vault_key: row.get(0)?,
+                vault_path: row.get(1)?,
+                ext: row.get(2)?,
+                size_bytes: row.get(3)?,
+                ref_count: row.get(4)?,
+                item_count: row.get(5)?,
+                sample_title: row.get(6)?,
+                file_exists: false,
+                is_protected: row.get::<_, i64>(7)? != 0,
+            })
+        })
+        .map_err(|err| format!("failed to query largest vault files: {}", err))?;
+    let mut files = Vec::new();
+    for row_result in rows {
+        let mut file =
+            row_result.map_err(|err| format!("failed to read largest vault file row: {}", err))?;
+        file.file_exists = path_from_ipc_string(&file.vault_path).is_file();
+        files.push(file);
+    }
+    Ok(files)
+}
+
+pub(crate) fn import_file_item_internal(
+    path: PathBuf,
+    collection_id: Option<String>,
+    import_batch_id: Option<&str>,
+    thumb_policy: &str,
+    vault_directory: Option<&Path>,
+    known_sha256: Option<String>,
+) -> Result<ImportedFileItem, String> {
+    let original_source_path = path_to_string(&path).ok();
+    let original_filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "imported-file".to_string());
+    let imported = run_import_pipeline_prehashed(
+        Some(path),
+        None,
+        None,
+        Some(original_filename),
+        thumb_policy,
+        vault_directory,
+        known_sha256,
+    )?;
+    insert_imported_file_item(
+        &imported,
+        collection_id,
+        import_batch_id,
+        original_source_path,
+    )
+}
+
+pub(crate) fn insert_imported_file_item(
+    imported: &ImportPipelineResult,
+    collection_id: Option<String>,
+    import_batch_id: Option<&str>,
+    original_source_path: Option<String>,
+) -> Result<ImportedFileItem, String> {
+    let item_type = item_type_for_extension(&imported.ext);
+    let title = Path::new(&imported.original_filename)
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .filter(|stem| !stem.trim().is_empty())
+        .unwrap_or(&imported.original_filename)
+        .to_string();
+    let thumb_status = if item_type == "image" {
+        imported.thumb_status.clone()
+    } else {
+        "ready".to_string()
+    };
+    let item_id = Uuid::new_v4().to_string();
+    let vault_key = build_vault_filename(&imported.sha256, &imported.ext);
+    let now = Utc::now().timestamp_millis();
+
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    if let Some(target_id) = collection_id.as_deref() {
+        validate_collection_exists_in_tx(&transaction, target_id)?;
+    }
+
+    insert_item_in_tx(
+        &transaction,
+        InsertItemInput {
+            id: item_id.clone(),
+            collection_id,
+            item_type: item_type.to_string(),
+            title,
+            filename: Some(imported.original_filename.clone()),
+            vault_key: Some(vault_key.clone()),
+            vault_path: Some(imported.vault_path.clone()),
+            preview_url: None,
+            width: imported.width.map(i64::from),
+            height: imported.height.map(i64::from),
+            thumb_status,
+            import_status: "ready".to_string(),
+            url: None,
+            favicon_path: None,
+            meta_status: None,
+            description: None,
+            description_format: None,
+            rating: 0,
+            is_favorite: false,
+            created_at: now,
+            updated_at: now,
+            tags: Vec::new(),
+            original_source_path,
+            source: None,
+        },
+    )?;
+    if let Some(import_batch_id) = import_batch_id {
+        set_item_import_batch(&transaction, &item_id, import_batch_id)?;
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit file import transaction: {}", err))?;
+
+    Ok(ImportedFileItem {
+        item_id,
+        vault_key,
+        deduped: imported.metrics.deduped,
+    })
+}
+
+pub(crate) fn build_import_dedup_entries(
+    connection: &Connection,
+    deduped_files: Vec<(String, String)>,
+    imported_item_ids: &BTreeSet<String>,
+) -> Result<Vec<ImportDedupEntry>, String> {
+    let mut matches_by_key: HashMap<String, Vec<VaultFileReference>> = HashMap::new();
+    let mut stmt = connection
+        .prepare("SELECT id, type, title FROM items WHERE vault_key = ?1 ORDER BY created_at ASC")
+        .map_err(|err| format!("failed to prepare dedup match query: {}", err))?;
+    for (_, vault_key) in &deduped_files {
+        if matches_by_key.contains_key(vault_key) {
+            continue;
+        }
+        let rows = stmt
+            .query_map(params![vault_key], |row| {
+                Ok(VaultFileReference {
+                    item_id: row.get(0)?,
+                    item_type: row.get(1)?,
+                    title: row.get(2)?,
+                })
+            })
+            .map_err(|err| format!("failed to query dedup matches: {}", err))?;
+        let mut matched_items = Vec::new();
+        for row_result in rows {
+            let reference =
+                row_result.map_err(|err| format!("failed to read dedup match row: {}", err))?;
+            if !imported_item_ids.contains(&reference.item_id) {
+                matched_items.push(reference);
+            }
+        }
+        matches_by_key.insert(vault_key.clone(), matched_items);
+    }
+
+    Ok(deduped_files
+        .into_iter()
+        .map(|(source_path, vault_key)| ImportDedupEntry {
+            matched_items: matches_by_key.get(&vault_key).cloned().unwrap_or_default(),
+            source_path,
+            vault_key,
+        })
+        .collect())
+}
+
+pub(crate) fn store_import_report(
+    connection: &Connection,
+    report: &ImportReport,
+) -> Result<(), String> {
+    let report_json = serde_json::to_string(report)
+        .map_err(|err| format!("failed to serialize import report: {}", err))?;
+    connection
+        .execute(
+            "INSERT INTO import_reports (operation, created_at, report_json) VALUES (?1, ?2, ?3)",
+            params![&report.operation, report.created_at, report_json],
+        )
+        .map_err(|err| format!("failed to store import report: {}", err))?;
+    connection
+        .execute(
+            "DELETE FROM import_reports
+             WHERE id <= (
+               SELECT id FROM import_reports ORDER BY id DESC LIMIT 1 OFFSET ?1
+             )",
+            params![IMPORT_REPORTS_TO_KEEP],
+        )
+        .map_err(|err| format!("failed to prune import reports: {}", err))?;
+    Ok(())
+}
+
+pub(crate) const IMPORT_BATCHES_DEFAULT_LIMIT: u32 = 50;
+pub(crate) const IMPORT_BATCHES_MAX_LIMIT: u32 = 500;
+
+pub(crate) fn begin_import_batch(
+    connection: &Connection,
+    import_batch_id: &str,
+    source: &str,
+    description: &str,
+) -> Result<(), String> {
+    connection
+        .execute(
+            "INSERT INTO import_batches (id, source, description, started_at) VALUES (?1, ?2, ?3, ?4)",
+            params![import_batch_id, source, description, Utc::now().timestamp_millis()],
+        )
+        .map_err(|err| format!("failed to create import batch: {}", err))?;
+    Ok(())
+}
+
+pub(crate) fn finish_import_batch(
+    connection: &Connection,
+    import_batch_id: &str,
+    imported: usize,
+    failed: usize,
+    skipped: usize,
+    deduplicated: usize,
+) -> Result<(), String> {
+    connection
+        .execute(
+            "UPDATE import_batches
+             SET finished_at = ?1, imported_count = ?2, failed_count = ?3,
+                 skipped_count = ?4, deduplicated_count = ?5
+             WHERE id = ?6",
+            params![
+                Utc::now().timestamp_millis(),
+                imported as i64,
+                failed as i64,
+                skipped as i64,
+                deduplicated as i64,
+                import_batch_id
+            ],
+        )
+        .map_err(|err| format!("failed to finish import batch: {}", err))?;
+    Ok(())
+}
+
+pub(crate) fn set_item_import_batch(
+    connection: &Connection,
+    item_id: &str,
+    import_batch_id: &str,
+) -> Result<(), String> {
+    connection
+        .execute(
+            "UPDATE items SET import_batch_id = ?1 WHERE id = ?2",
+            params![import_batch_id, item_id],
+        )
+        .map_err(|err| format!("failed to set item import batch: {}", err))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn list_import_batches(limit: Option<u32>) -> Result<Vec<ImportBatch>, String> {
+    let limit = limit
+        .unwrap_or(IMPORT_BATCHES_DEFAULT_LIMIT)
+        .clamp(1, IMPORT_BATCHES_MAX_LIMIT);
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let mut stmt = connection
+        .prepare(
+            "SELECT b.id, b.source, b.description, b.started_at, b.finished_at,
+                    b.imported_count, b.failed_count, b.skipped_count, b.deduplicated_count,
+                    (SELECT COUNT(*) FROM items AS i WHERE i.import_batch_id = b.id)
+             FROM import_batches AS b
+             ORDER BY b.started_at DESC, b.id DESC
+             LIMIT ?1",
+        )
+        .map_err(|err| format!("failed to prepare import batches query: {}", err))?;
+    let rows = stmt
+        .query_map(params![limit], |row| {
+            Ok(ImportBatch {
+                id: row.get(0)?,
+                source: row.get(1)?,
+                description: row.get(2)?,
+                started_at: row.get(3)?,
+                finished_at: row.get(4)?,
+                imported_count: row.get(5)?,
+                failed_count: row.get(6)?,
+                skipped_count: row.get(7)?,
+                deduplicated_count: row.get(8)?,
+                item_count: row.get(9)?,
+            })
+        })
+        .map_err(|err| format!("failed to query import batches: {}", err))?;
+    let mut batches = Vec::new();
+    for row_result in rows {
+        batches
+            .push(row_result.map_err(|err| format!("failed to read import batch row: {}", err))?);
+    }
+    Ok(batches)
+}
+
+#[tauri::command]
+pub(crate) fn get_batch_items(
+    batch_id: String,
+    include_sensitive: Option<bool>,
+) -> Result<Vec<DbItemRow>, String> {
+    let normalized_batch_id = normalize_trimmed_id(&batch_id)
+        .ok_or_else(|| "import batch id cannot be empty".to_string())?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let show_sensitive = resolve_show_sensitive(&connection, include_sensitive)?;
+    prepare_locked_collection_scope(&connection)?;
+    query_db_item_rows(
+        &connection,
+        &format!(
+            "{} WHERE i.import_batch_id = ?1
+               AND (?2 OR i.is_sensitive = 0)
+               AND {}
+             GROUP BY i.id
+             ORDER BY i.created_at ASC, i.id ASC",
+            DB_ITEM_ROW_SELECT, LOCKED_SCOPE_VISIBLE_CONDITION
+        ),
+        params![normalized_batch_id, show_sensitive],
+    )
+}
+
+#[tauri::command]
+pub(crate) fn delete_batch_items(batch_id: String) -> Result<DeleteItemsResult, String> {
+    let normalized_batch_id = normalize_trimmed_id(&batch_id)
+        .ok_or_else(|| "import batch id cannot be empty".to_string())?;
+
+    initialize_db()?;
+    let item_ids = {
+        let connection = open_db_connection()?;
+        let mut stmt = connection
+            .prepare("SELECT id FROM items WHERE import_batch_id = ?1")
+            .map_err(|err| format!("failed to prepare import batch items query: {}", err))?;
+        let rows = stmt
+            .query_map(params![normalized_batch_id], |row| row.get::<_, String>(0))
+            .map_err(|err| format!("failed to query import batch items: {}", err))?;
+        let mut item_ids = Vec::new();
+        for row_result in rows {
+            item_ids.push(
+                row_result
+                    .map_err(|err| format!("failed to read import batch item row: {}", err))?,
+            );
+        }
+        item_ids
+    };
+
+    let result = delete_items_with_cleanup_internal(item_ids)?;
+    let connection = open_db_connection()?;
+    connection
+        .execute(
+            "DELETE FROM import_batches WHERE id = ?1",
+            params![normalized_batch_id],
+        )
+        .map_err(|err| format!("failed to delete import batch: {}", err))?;
+    log_println!(
+        "[import-batch] deleted batch={} items={}",
+        normalized_batch_id,
+        result.deleted_rows
+    );
+    Ok(result)
+}
+
+#[tauri::command]
+pub(crate) fn get_last_import_report() -> Result<Option<ImportReport>, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let report_json = connection
+        .query_row(
+            "SELECT report_json FROM import_reports ORDER BY id DESC LIMIT 1",
+            [],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to read last import report: {}", err))?;
+    report_json
+        .map(|json| {
+            serde_json::from_str(&json)
+                .map_err(|err| format!("failed to parse import report: {}", err))
+        })
+        .transpose()
+}
+
+pub(crate) fn collect_import_directory_files(
+    root: &Path,
+    recursive: bool,
+) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(directory) = pending.pop() {
+        let entries = read_directory(&directory)
+            .map_err(|err| format!("failed to read directory {}: {}", directory.display(), err))?;
+        for entry_result in entries {
+            let entry =
+                entry_result.map_err(|err| format!("failed to read directory entry: {}", err))?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if recursive {
+                    pending.push(entry.path());
+                }
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+pub(crate) fn normalize_import_extensions_filter(
+    extensions: Option<Vec<String>>,
+) -> Option<BTreeSet<String>> {
+    let normalized: BTreeSet<String> = extensions
+        .unwrap_or_default()
+        .iter()
+        .map(|ext| normalize_ext(ext.trim().trim_start_matches('.')))
+        .filter(|ext| !ext.is_empty())
+        .collect();
+    if normalized.is_empty() {
+        None
+    } else {
+        Some(normalized)
+    }
+}
+
+pub(crate) fn load_existing_import_fingerprints(
+    connection: &Connection,
+) -> Result<BTreeSet<(String, i64)>, String> {
+    let mut stmt = connection
+        .prepare(
+            "SELECT DISTINCT LOWER(i.filename), vf.size_bytes
+             FROM items i
+             JOIN vault_files vf ON vf.vault_key = i.vault_key
+             WHERE i.vault_key <> ''",
+        )
+        .map_err(|err| format!("failed to prepare import fingerprint query: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(|err| format!("failed to query import fingerprints: {}", err))?;
+    let mut fingerprints = BTreeSet::new();
+    for row_result in rows {
+        fingerprints.insert(
+            row_result.map_err(|err| format!("failed to read import fingerprint row: {}", err))?,
+        );
+    }
+    Ok(fingerprints)
+}
+
+pub(crate) fn preview_import_directory_internal(
+    root: &Path,
+    recursive: bool,
+    extensions: Option<BTreeSet<String>>,
+    hash_duplicates: bool,
+) -> Result<ImportDirectoryPreview, String> {
+    if !root.is_dir() {
+        return Err(format!("directory not found: {}", root.display()));
+    }
+    ensure_path_outside_storage(root)?;
+    let walked = collect_import_directory_files(root, recursive)?;
+
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let fingerprints = load_existing_import_fingerprints(&connection)?;
+    let limits = load_import_limits(&connection)?;
+
+    let mut preview = ImportDirectoryPreview {
+        files: Vec::new(),
+        total_files: 0,
+        total_bytes: 0,
+        by_extension: BTreeMap::new(),
+        oversized: 0,
+        empty: 0,
+        unsupported: 0,
+        estimated_duplicates: 0,
+        exact_duplicates: if hash_duplicates { Some(0) } else { None },
+    };
+    for path in walked {
+        let ext = extension_from_path(&path);
+        if extensions
+            .as_ref()
+            .is_some_and(|allowed| !allowed.contains(&ext))
+        {
+            preview.unsupported += 1;
+            continue;
+        }
+        let Ok(metadata) = fs::metadata(&path) else {
+            continue;
+        };
+        let size_bytes = metadata.len();
+        if let Err(error) = validate_import_against_limits(&limits, size_bytes, &ext) {
+            if size_bytes == 0 {
+                preview.empty += 1;
+            } else if limits.max_file_bytes > 0 && size_bytes > limits.max_file_bytes {
+                preview.oversized += 1;
+            } else {
+                preview.unsupported += 1;
+            }
+            log_println!("[import-preview] skipping {}: {}", path.display(), error);
+            continue;
+        }
+
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if fingerprints.contains(&(filename, size_bytes as i64)) {
+            preview.estimated_duplicates += 1;
+        }
+        if let Some(exact_duplicates) = preview.exact_duplicates.as_mut() {
+            let sha256 = sha256_for_file(&path)?;
+            let exists = connection
+                .query_row(
+                    "SELECT 1 FROM vault_files WHERE vault_key = ?1",
+                    params![build_vault_filename(&sha256, &ext)],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()
+                .map_err(|err| format!("failed to check vault file for duplicate: {}", err))?;
+            if exists.is_some() {
+                *exact_duplicates += 1;
+            }
+        }
+
+        let stats = preview.by_extension.entry(ext).or_default();
+        stats.count += 1;
+        stats.bytes += size_bytes;
+        preview.total_files += 1;
+        preview.total_bytes += size_bytes;
+        preview.files.push(path_to_string(&path)?);
+    }
+    Ok(preview)
+}
+
+pub(crate) fn default_import_hash_parallelism() -> usize {
+    std::thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+        .min(DEFAULT_IMPORT_HASH_PARALLELISM_CAP)
+}
+
+pub(crate) fn load_import_hash_parallelism(connection: &Connection) -> Result<usize, String> {
+    Ok(
+        read_app_setting(connection, SETTING_IMPORT_HASH_PARALLELISM)?
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .filter(|value| *value > 0)
+            .map(|value| value.min(IMPORT_HASH_MAX_PARALLELISM))
+            .unwrap_or_else(default_import_hash_parallelism),
+    )
+}
+
+#[tauri::command]
+pub(crate) fn get_import_hash_parallelism() -> Result<usize, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    load_import_hash_parallelism(&connection)
+}
+
+#[tauri::command]
+pub(crate) fn set_import_hash_parallelism(parallelism: Option<usize>) -> Result<usize, String> {
+    if parallelism == Some(0) {
+        return Err("import hash parallelism must be at least 1".to_string());
+    }
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let stored = parallelism
+        .map(|value| value.min(IMPORT_HASH_MAX_PARALLELISM).to_string())
+        .unwrap_or_default();
+    write_app_setting(&connection, SETTING_IMPORT_HASH_PARALLELISM, &stored)?;
+    load_import_hash_parallelism(&connection)
+}
+
+pub(crate) fn load_seen_sources(
+    connection: &Connection,
+    root: &Path,
+) -> Result<HashMap<String, SeenSource>, String> {
+    let mut stmt = connection
+        .prepare("SELECT source_path, size_bytes, mtime_ms, sha256 FROM seen_sources WHERE source_root = ?1")
+        .map_err(|err| format!("failed to prepare seen sources query: {}", err))?;
+    let rows = stmt
+        .query_map(params![root.to_string_lossy()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                SeenSource {
+                    size_bytes: row.get::<_, i64>(1)?.max(0) as u64,
+                    mtime_ms: row.get(2)?,
+                    sha256: row.get(3)?,
+                },
+            ))
+        })
+        .map_err(|err| format!("failed to query seen sources: {}", err))?;
+    rows.collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|err| format!("failed to read seen source row: {}", err))
+}
+
+pub(crate) fn record_seen_sources(
+    connection: &mut Connection,
+    root: &Path,
+    entries: &[(String, SeenSource)],
+) -> Result<(), String> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let source_root = root.to_string_lossy().to_string();
+    let seen_at = Utc::now().timestamp_millis();
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start seen sources transaction: {}", err))?;
+    for (source_path, seen) in entries {
+        transaction
+            .execute(
+                "INSERT INTO seen_sources (source_path, source_root, size_bytes, mtime_ms, sha256, seen_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(source_path) DO UPDATE SET
+                    source_root = excluded.source_root,
+                    size_bytes = excluded.size_bytes,
+                    mtime_ms = excluded.mtime_ms,
+                    sha256 = excluded.sha256,
+                    seen_at = excluded.seen_at",
+                params![
+                    source_path,
+                    source_root,
+                    seen.size_bytes as i64,
+                    seen.mtime_ms,
+                    seen.sha256,
+                    seen_at
+                ],
+            )
+            .map_err(|err| format!("failed to record seen source {}: {}", source_path, err))?;
+    }
+    transaction
+        .execute(
+            "DELETE FROM seen_sources
+             WHERE source_path NOT IN (
+                SELECT source_path FROM seen_sources ORDER BY seen_at DESC LIMIT ?1
+             )",
+            params![SEEN_SOURCES_MAX_ROWS],
+        )
+        .map_err(|err| format!("failed to prune seen sources: {}", err))?;
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit seen sources: {}", err))
+}
+
+pub(crate) fn prehash_import_source(
+    root: &Path,
+    path: PathBuf,
+    limits: &ImportLimits,
+    seen_sources: &HashMap<String, SeenSource>,
+) -> PrehashedImportSource {
+    let metadata = fs::metadata(&path).ok();
+    let size_bytes = metadata
+        .as_ref()
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    let mtime_ms = metadata
+        .and_then(|metadata| metadata.modified().ok())
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as i64);
+    let importable = path.starts_with(root)
+        && validate_import_against_limits(limits, size_bytes, &extension_from_path(&path)).is_ok();
+    let cached_sha256 = mtime_ms.and_then(|mtime_ms| {
+        seen_sources
+            .get(path.to_string_lossy().as_ref())
+            .filter(|seen| seen.size_bytes == size_bytes && seen.mtime_ms == mtime_ms)
+            .map(|seen| seen.sha256.clone())
+    });
+    let cached = importable && cached_sha256.is_some();
+    let sha256 = if !importable {
+        None
+    } else if let Some(sha256) = cached_sha256 {
+        Some(Ok(sha256))
+    } else {
+        Some(sha256_for_file(&path))
+    };
+    PrehashedImportSource {
+        path,
+        size_bytes,
+        mtime_ms,
+        sha256,
+        cached,
+        finished_at: Instant::now(),
+    }
+}
+
+/// Hashes `files` on a dedicated rayon pool; the returned receiver yields each source as
+/// soon as its hash is ready. Keep the pool alive until the receiver is drained.
+pub(crate) fn spawn_import_prehash(
+    root: &Path,
+    files: Vec<PathBuf>,
+    limits: ImportLimits,
+    seen_sources: HashMap<String, SeenSource>,
+    parallelism: usize,
+) -> Result<(rayon::ThreadPool, mpsc::Receiver<PrehashedImportSource>), String> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(parallelism.max(1))
+        .thread_name(|index| format!("import-hash-{}", index))
+        .build()
+        .map_err(|err| format!("failed to start import hash pool: {}", err))?;
+    let (sender, receiver) = mpsc::channel();
+    let root = root.to_path_buf();
+    pool.spawn(move || {
+        files.into_par_iter().for_each_with(sender, |sender, path| {
+            let _ = sender.send(prehash_import_source(&root, path, &limits, &seen_sources));
+        });
+    });
+    Ok((pool, receiver))
+}
+
+pub(crate) fn import_directory_internal(
+    app: &AppHandle,
+    reporter: &ProgressReporter,
+    root: &Path,
+    recursive: bool,
+    collection_id: Option<String>,
+    mirror_folders: bool,
+    files: Option<Vec<String>>,
+) -> Result<ImportDirectoryResult, String> {
+    if !root.is_dir() {
+        return Err(format!("directory not found: {}", root.display()));
+    }
+    ensure_path_outside_storage(root)?;
+    let import_batch_id = reporter
+        .import_batch_id
+        .clone()
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let files = match files {
+        Some(files) => files
+            .iter()
+            .map(|file| path_from_ipc_string(file))
+            .collect(),
+        None => collect_import_directory_files(root, recursive)?,
+    };
+
+    let target_collection_id = match collection_id {
+        Some(collection_id) => Some(collection_id),
+        None => root_collection_id_if_present()?,
+    };
+    let mut result = ImportDirectoryResult {
+        item_ids: Vec::new(),
+        failed: Vec::new(),
+        skipped: Vec::new(),
+        collections_created: 0,
+        deduplicated: Vec::new(),
+        import_batch_id: import_batch_id.clone(),
+        thumb_policy: load_import_thumb_policy(IMPORT_THUMB_POLICY_DEFERRED)?,
+        hash_summary: None,
+    };
+    ensure_import_disk_space(import_paths_total_bytes(&files))?;
+    let vault_directory = resolve_batch_vault_directory()?;
+    let mut deduped_files = Vec::new();
+    let limits = {
+        let connection = open_db_connection()?;
+        begin_import_batch(
+            &connection,
+            &import_batch_id,
+            "directory",
+            &root.to_string_lossy(),
+        )?;
+        load_import_limits(&connection)?
+    };
+    let (parallelism, seen_sources) = {
+        let connection = open_db_connection()?;
+        (
+            load_import_hash_parallelism(&connection)?,
+            load_seen_sources(&connection, root)?,
+        )
+    };
+    let mut collection_cache: HashMap<PathBuf, String> = HashMap::new();
+    let mut seen_updates = Vec::new();
+    let mut hash_summary = ImportHashSummary {
+        parallelism,
+        hashed_files: 0,
+        cached_files: 0,
+        hashed_bytes: 0,
+        hash_wall_ms: 0,
+        hash_mb_per_sec: 0.0,
+    };
+    let total = files.len();
+    let hash_started_at = Instant::now();
+    let (_hash_pool, prehashed_sources) =
+        spawn_import_prehash(root, files, limits.clone(), seen_sources, parallelism)?;
+    // Hashes arrive in completion order; copying and DB writes stay on this thread.
+    for (index, prehashed) in prehashed_sources.into_iter().enumerate() {
+        let path = prehashed.path;
+        let size_bytes = prehashed.size_bytes;
+        let hash_elapsed_ms = prehashed
+            .finished_at
+            .duration_since(hash_started_at)
+            .as_millis() as u64;
+        hash_summary.hash_wall_ms = hash_summary.hash_wall_ms.max(hash_elapsed_ms);
+        let seen_update = match (&prehashed.sha256, prehashed.mtime_ms) {
+            (Some(Ok(sha256)), Some(mtime_ms)) => Some((
+                path.to_string_lossy().to_string(),
+                SeenSource {
+                    size_bytes,
+                    mtime_ms,
+                    sha256: sha256.clone(),
+                },
+            )),
+            _ => None,
+        };
+        if prehashed.cached {
+            hash_summary.cached_files += 1;
+        } else if matches!(prehashed.sha256, Some(Ok(_))) {
+            hash_summary.hashed_files += 1;
+            hash_summary.hashed_bytes += size_bytes;
+        }
+
+        let relative_dir = path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(root).ok())
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let outcome = if let Err(error) =
+            validate_import_against_limits(&limits, size_bytes, &extension_from_path(&path))
+        {
+            Err(error)
+        } else if !path.starts_with(root) {
+            Err("file is outside the import directory".to_string())
+        } else {
+            prehashed.sha256.transpose()
+        }
+        .and_then(|known_sha256| {
+            let item_collection_id = if mirror_folders {
+                ensure_mirrored_collection_chain(
+                    target_collection_id.as_deref(),
+                    &relative_dir,
+                    &mut collection_cache,
+                    &mut result.collections_created,
+                )?
+            } else {
+                target_collection_id.clone()
+            };
+            import_file_item_internal(
+                path.clone(),
+                item_collection_id,
+                Some(&import_batch_id),
+                &result.thumb_policy,
+                Some(&vault_directory),
+                known_sha256,
+            )
+        });
+
+        match outcome {
+            Ok(imported) => {
+                seen_updates.extend(seen_update);
+                if imported.deduped {
+                    deduped_files.push((path.to_string_lossy().to_string(), imported.vault_key));
+                }
+                result.item_ids.push(imported.item_id);
+            }
+            Err(error) if error.starts_with("validation:") => {
+                result.skipped.push(ImportDirectoryFailure {
+                    path: path.to_string_lossy().to_string(),
+                    error,
+                });
+            }
+            Err(error) => {
+                log_eprintln!(
+                    "[import-directory] failed to import {}: {}",
+                    path.display(),
+                    error
+                );
+                result.failed.push(ImportDirectoryFailure {
+                    path: path.to_string_lossy().to_string(),
+                    error,
+                });
+            }
+        }
+        let _ = app.emit(
+            IMPORT_DIRECTORY_PROGRESS_EVENT,
+            ImportDirectoryProgressPayload {
+                processed: index + 1,
+                total,
+                imported: result.item_ids.len(),
+                failed: result.failed.len(),
+                import_batch_id: import_batch_id.clone(),
+            },
+        );
+        reporter.report("importing", (index + 1) as u64, total as u64, None);
+    }
+
+    if hash_summary.hash_wall_ms > 0 {
+        hash_summary.hash_mb_per_sec = (hash_summary.hashed_bytes as f64 / (1024.0 * 1024.0))
+            / (hash_summary.hash_wall_ms as f64 / 1000.0);
+    }
+    result.hash_summary = Some(hash_summary);
+
+    let mut connection = open_db_connection()?;
+    if let Err(err) = record_seen_sources(&mut connection, root, &seen_updates) {
+        log_eprintln!("[import-directory] failed to record seen sources: {}", err);
+    }
+    let imported_item_ids = result.item_ids.iter().cloned().collect();
+    result.deduplicated =
+        build_import_dedup_entries(&connection, deduped_files, &imported_item_ids)?;
+    store_import_report(
+        &connection,
+        &ImportReport {
+            operation: "import-directory".to_string(),
+            created_at: Utc::now().timestamp_millis(),
+            imported: result.item_ids.len(),
+            failed: result.failed.len(),
+            skipped: result.skipped.len(),
+            deduplicated: result.deduplicated.clone(),
+            thumb_policy: result.thumb_policy.clone(),
+        },
+    )?;
+    finish_import_batch(
+        &connection,
+        &import_batch_id,
+        result.item_ids.len(),
+        result.failed.len(),
+        result.skipped.len(),
+        result.deduplicated.len(),
+    )?;
+
+    let hash_summary = result.hash_summary.as_ref();
+    log_println!(
+        "[import-directory] root={} imported={} failed={} skipped={} deduplicated={} collections_created={} thumb_policy={} hash_parallelism={} hashed={} hash_cached={} hash_mb_per_sec={:.1}",
+        root.display(),
+        result.item_ids.len(),
+        result.failed.len(),
+        result.skipped.len(),
+        result.deduplicated.len(),
+        result.collections_created,
+        result.thumb_policy,
+        hash_summary.map(|summary| summary.parallelism).unwrap_or(0),
+        hash_summary.map(|summary| summary.hashed_files).unwrap_or(0),
+        hash_summary.map(|summary| summary.cached_files).unwrap_or(0),
+        hash_summary.map(|summary| summary.hash_mb_per_sec).unwrap_or(0.0)
+    );
+    Ok(result)
+}
+
+pub(crate) fn open_zip_archive(path: &Path) -> Result<zip::ZipArchive<BufReader<File>>, String> {
+    let file = File::open(extended_length_path(path))
+        .map_err(|err| format!("failed to open archive {}: {}", path.display(), err))?;
+    zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|err| format!("failed to read archive {}: {}", path.display(), err))
+}
+
+pub(crate) fn archive_entry_filename(entry: &zip::read::ZipFile) -> Option<String> {
+    entry
+        .enclosed_name()?
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .filter(|name| !name.trim().is_empty())
+}
+
+pub(crate) fn inspect_archive_internal(path: &Path) -> Result<Vec<ArchiveEntry>, String> {
+    let mut archive = open_zip_archive(path)?;
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index_raw(index)
+            .map_err(|err| format!("failed to read archive entry {}: {}", index, err))?;
+        if entry.encrypted() {
+            return Err(format!(
+                "{}: {}",
+                ARCHIVE_PASSWORD_PROTECTED_ERROR,
+                path.display()
+            ));
+        }
+        let extension = archive_entry_filename(&entry)
+            .and_then(|name| extension_from_filename(&name))
+            .unwrap_or_default();
+        entries.push(ArchiveEntry {
+            name: entry.name().to_string(),
+            size: entry.size(),
+            compressed_size: entry.compressed_size(),
+            is_image: !entry.is_dir() && is_image_extension(&extension),
+            is_dir: entry.is_dir(),
+            extension,
+        });
+    }
+    Ok(entries)
+}
+
+pub(crate) enum ArchiveEntryPayload {
+    Bytes(Vec<u8>),
+    Spooled(SpooledArchiveEntry),
+}
+
+/// Temporary copy of an oversized archive entry; removed when dropped.
+pub(crate) struct SpooledArchiveEntry {
+    pub(crate) path: tempfile::TempPath,
+}
+
+impl ArchiveEntryPayload {
+    pub(crate) fn into_pipeline_source(
+        self,
+    ) -> (
+        Option<PathBuf>,
+        Option<Vec<u8>>,
+        Option<SpooledArchiveEntry>,
+    ) {
+        match self {
+            ArchiveEntryPayload::Bytes(bytes) => (None, Some(bytes), None),
+            ArchiveEntryPayload::Spooled(spooled) => {
+                (Some(spooled.path.to_path_buf()), None, Some(spooled))
+            }
+        }
+    }
+}
+
+/// Entries up to `in_memory_max` bytes are buffered; larger ones are streamed into a
+/// temp file in `spool_directory`. The header size is never trusted for allocation.
+pub(crate) fn read_archive_entry(
+    archive: &mut zip::ZipArchive<BufReader<File>>,
+    name: &str,
+    limits: &ImportLimits,
+    spool_directory: &Path,
+    in_memory_max: usize,
+) -> Result<(String, String, ArchiveEntryPayload), String> {
+    let mut entry = archive
+        .by_name(name)
+        .map_err(|err| format!("failed to open archive entry: {}", err))?;
+    if entry.encrypted() {
+        return Err(ARCHIVE_PASSWORD_PROTECTED_ERROR.to_string());
+    }
+    if entry.is_dir() {
+        return Err("validation: archive entry is a directory".to_string());
+    }
+    let filename = archive_entry_filename(&entry)
+        .ok_or_else(|| "validation: archive entry has an unsafe path".to_string())?;
+    let ext = extension_from_filename(&filename).unwrap_or_else(|| "bin".to_string());
+    validate_import_against_limits(limits, entry.size(), &ext)?;
+    let read_limit = if limits.max_file_bytes > 0 {
+        limits.max_file_bytes.saturating_add(1)
+    } else {
+        u64::MAX
+    };
+    let mut limited = (&mut entry).take(read_limit);
+    let mut bytes = Vec::new();
+    (&mut limited)
+        .take(in_memory_max as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|err| format!("failed to extract archive entry: {}", err))?;
+    if bytes.len() <= in_memory_max {
+        validate_import_against_limits(limits, bytes.len() as u64, &ext)?;
+        return Ok((filename, ext, ArchiveEntryPayload::Bytes(bytes)));
+    }
+
+    let (file, path) = tempfile::Builder::new()
+        .prefix(ARCHIVE_SPOOL_FILE_PREFIX)
+        .suffix(ARCHIVE_SPOOL_FILE_SUFFIX)
+        .tempfile_in(spool_directory)
+        .map_err(|err| {
+            format!(
+                "failed to create archive spool file in {}: {}",
+                spool_directory.display(),
+                err
+            )
+        })?
+        .into_parts();
+    let spooled = SpooledArchiveEntry { path };
+    let mut writer = std::io::BufWriter::new(file);
+    writer
+        .write_all(&bytes)
+        .map_err(|err| format!("failed to extract archive entry: {}", err))?;
+    drop(bytes);
+    let copied = std::io::copy(&mut limited, &mut writer)
+        .map_err(|err| format!("failed to extract archive entry: {}", err))?;
+    writer
+        .flush()
+        .map_err(|err| format!("failed to extract archive entry: {}", err))?;
+    let total = (in_memory_max as u64 + 1).saturating_add(copied);
+    validate_import_against_limits(limits, total, &ext)?;
+    Ok((filename, ext, ArchiveEntryPayload::Spooled(spooled)))
+}
+
+pub(crate) fn import_from_archive_internal(
+    reporter: &ProgressReporter,
+    path: &Path,
+    entry_names: Option<Vec<String>>,
+    collection_id: Option<String>,
+) -> Result<ImportDirectoryResult, String> {
+    if !path.is_file() {
+        return Err(format!("archive not found: {}", path.display()));
+    }
+    let import_batch_id = reporter
+        .import_batch_id
+        .clone()
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let entries = inspect_archive_internal(path)?;
+    let selected_names: Vec<String> = match entry_names {
+        Some(names) => names,
+        None => entries
+            .iter()
+            .filter(|entry| entry.is_image)
+            .map(|entry| entry.name.clone())
+            .collect(),
+    };
+    let target_collection_id = match collection_id {
+        Some(collection_id) => Some(collection_id),
+        None => root_collection_id_if_present()?,
+    };
+    let mut result = ImportDirectoryResult {
+        item_ids: Vec::new(),
+        failed: Vec::new(),
+        skipped: Vec::new(),
+        collections_created: 0,
+        deduplicated: Vec::new(),
+        import_batch_id: import_batch_id.clone(),
+        thumb_policy: load_import_thumb_policy(IMPORT_THUMB_POLICY_DEFERRED)?,
+        hash_summary: None,
+    };
+    let selected_bytes = entries
+        .iter()
+        .filter(|entry| selected_names.contains(&entry.name))
+        .map(|entry| entry.size)
+        .fold(0_u64, u64::saturating_add);
+    ensure_import_disk_space(selected_bytes)?;
+    let vault_directory = resolve_batch_vault_directory()?;
+    let limits = {
+        let connection = open_db_connection()?;
+        begin_import_batch(
+            &connection,
+            &import_batch_id,
+            "archive",
+            &path.to_string_lossy(),
+        )?;
+        load_import_limits(&connection)?
+    };
+
+    let mut archive = open_zip_archive(path)?;
+    let mut deduped_files = Vec::new();
+    let total = selected_names.len();
+    for (index, name) in selected_names.into_iter().enumerate() {
+        let outcome = if entries.iter().any(|entry| entry.name == name) {
+            read_archive_entry(
+                &mut archive,
+                &name,
+                &limits,
+                &std::env::temp_dir(),
+                ARCHIVE_ENTRY_IN_MEMORY_MAX_BYTES,
+            )
+        } else {
+            Err("validation: entry not found in archive".to_string())
+        }
+        .and_then(|(filename, ext, payload)| {
+            let (source_path, source_bytes, _spooled) = payload.into_pipeline_source();
+            run_import_pipeline_internal(
+                source_path,
+                source_bytes,
+                Some(ext),
+                Some(filename),
+                &result.thumb_policy,
+                Some(&vault_directory),
+            )
+        })
+        .and_then(|imported| {
+            insert_imported_file_item(
+                &imported,
+                target_collection_id.clone(),
+                Some(&import_batch_id),
+                None,
+            )
+        });
+
+        match outcome {
+            Ok(imported) => {
+                if imported.deduped {
+                    deduped_files.push((name.clone(), imported.vault_key));
+                }
+                result.item_ids.push(imported.item_id);
+            }
+            Err(error) if error.starts_with("validation:") => {
+                result.skipped.push(ImportDirectoryFailure {
+                    path: name.clone(),
+                    error,
+                });
+            }
+            Err(error) => {
+                log_eprintln!("[import-archive] failed to import {}: {}", name, error);
+                result.failed.push(ImportDirectoryFailure {
+                    path: name.clone(),
+                    error,
+                });
+            }
+        }
+        reporter.report("importing", (index + 1) as u64, total as u64, Some(&name));
+    }
+
+    let connection = open_db_connection()?;
+    let imported_item_ids = result.item_ids.iter().cloned().collect();
+    result.deduplicated =
+        build_import_dedup_entries(&connection, deduped_files, &imported_item_ids)?;
+    store_import_report(
+        &connection,
+        &ImportReport {
+            operation: "import-archive".to_string(),
+            created_at: Utc::now().timestamp_millis(),
+            imported: result.item_ids.len(),
+            failed: result.failed.len(),
+            skipped: result.skipped.len(),
+            deduplicated: result.deduplicated.clone(),
+            thumb_policy: result.thumb_policy.clone(),
+        },
+    )?;
+    finish_import_batch(
+        &connection,
+        &import_batch_id,
+        result.item_ids.len(),
+        result.failed.len(),
+        result.skipped.len(),
+        result.deduplicated.len(),
+    )?;
+
+    log_println!(
+        "[import-archive] path={} imported={} failed={} skipped={} deduplicated={}",
+        path.display(),
+        result.item_ids.len(),
+        result.failed.len(),
+        result.skipped.len(),
+        result.deduplicated.len()
+    );
+    Ok(result)
+}
+
+#[tauri::command]
+pub(crate) async fn inspect_archive(path: String) -> Result<Vec<ArchiveEntry>, String> {
+    let archive_path = path_from_ipc_string(path.trim());
+    tauri::async_runtime::spawn_blocking(move || inspect_archive_internal(&archive_path))
+        .await
+        .map_err(|err| format!("inspect archive thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) async fn import_from_archive(
+    app: AppHandle,
+    path: String,
+    entry_names: Option<Vec<String>>,
+    collection_id: Option<String>,
+) -> Result<ImportDirectoryResult, String> {
+    let archive_path = path_from_ipc_string(path.trim());
+    let normalized_collection_id = normalize_optional_trimmed_id(collection_id);
+    tauri::async_runtime::spawn_blocking(move || {
+        initialize_db()?;
+        let import_batch_id = Uuid::new_v4().to_string();
+        let reporter = ProgressReporter::start_with_import_batch(
+            &app,
+            "import-archive",
+            Some(&import_batch_id),
+        );
+        let outcome = import_from_archive_internal(
+            &reporter,
+            &archive_path,
+            entry_names,
+            normalized_collection_id,
+        );
+        reporter.finish(&outcome.as_ref().map(|_| ()).map_err(Clone::clone));
+        outcome
+    })
+    .await
+    .map_err(|err| format!("import archive thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) async fn import_directory(
+    app: AppHandle,
+    path: String,
+    recursive: Option<bool>,
+    collection_id: Option<String>,
+    mirror_folders: Option<bool>,
+    files: Option<Vec<String>>,
+) -> Result<ImportDirectoryResult, String> {
+    let root = path_from_ipc_string(path.trim());
+    let normalized_collection_id = normalize_optional_trimmed_id(collection_id);
+    tauri::async_runtime::spawn_blocking(move || {
+        let import_batch_id = Uuid::new_v4().to_string();
+        let reporter = ProgressReporter::start_with_import_batch(
+            &app,
+            "import-directory",
+            Some(&import_batch_id),
+        );
+        let outcome = import_directory_internal(
+            &app,
+            &reporter,
+            &root,
+            recursive.unwrap_or(true),
+            normalized_collection_id,
+            mirror_folders.unwrap_or(false),
+            files,
+        );
+        reporter.finish(&outcome.as_ref().map(|_| ()).map_err(Clone::clone));
+        outcome
+    })
+    .await
+    .map_err(|err| format!("import directory thread join failed: {}", err))?
+}
+
+pub(crate) fn store_dropped_files_import_report(
+    failed: usize,
+    deduped_files: Vec<(String, String)>,
+    imported_item_ids: &BTreeSet<String>,
+    thumb_policy: &str,
+) -> Result<usize, String> {
+    let connection = open_db_connection()?;
+    let deduplicated = build_import_dedup_entries(&connection, deduped_files, imported_item_ids)?;
+    let deduplicated_count = deduplicated.len();
+    store_import_report(
+        &connection,
+        &ImportReport {
+            operation: "import-dropped-files".to_string(),
+            created_at: Utc::now().timestamp_millis(),
+            imported: imported_item_ids.len(),
+            failed,
+            skipped: 0,
+            deduplicated,
+            thumb_policy: thumb_policy.to_string(),
+        },
+    )?;
+    Ok(deduplicated_count)
+}
+
+pub(crate) async fn import_dropped_files_job(
+    reporter: ProgressReporter,
+    import_batch_id: String,
+    files: Vec<PathBuf>,
+    bookmarks: Vec<Url>,
+    collection_id: Option<String>,
+) {
+    let total = (files.len() + bookmarks.len()) as u64;
+    let mut done = 0_u64;
+    let mut failed = 0_usize;
+    let mut imported_item_ids = BTreeSet::new();
+    let mut deduped_files = Vec::new();
+    let mut deduplicated_count = 0_usize;
+    let has_files = !files.is_empty();
+    let batch_description = format!("{} dropped entries", total);
+    let thumb_policy =
+        load_import_thumb_policy(IMPORT_THUMB_POLICY_DEFERRED).unwrap_or_else(|err| {
+            log_eprintln!("[drop-import] failed to load thumbnail policy: {}", err);
+            IMPORT_THUMB_POLICY_DEFERRED.to_string()
+        });
+    let vault_directory = match resolve_batch_vault_directory() {
+        Ok(directory) => Some(directory),
+        Err(err) => {
+            log_eprintln!("[drop-import] failed to resolve vault directory: {}", err);
+            None
+        }
+    };
+    if let Err(err) = open_db_connection().and_then(|connection| {
+        begin_import_batch(&connection, &import_batch_id, "drop", &batch_description)
+    }) {
+        log_eprintln!("[drop-import] failed to create import batch: {}", err);
+    }
+    for path in files {
+        let label = path.display().to_string();
+        let target_collection_id = collection_id.clone();
+        let batch_id = import_batch_id.clone();
+        let file_thumb_policy = thumb_policy.clone();
+        let file_vault_directory = vault_directory.clone();
+        let outcome = tauri::async_runtime::spawn_blocking(move || {
+            import_file_item_internal(
+                path,
+                target_collection_id,
+                Some(&batch_id),
+                &file_thumb_policy,
+                file_vault_directory.as_deref(),
+                None,
+            )
+        })
+        .await
+        .map_err(|err| format!("dropped file import thread join failed: {}", err))
+        .and_then(|result| result);
+        match outcome {
+            Ok(imported) => {
+                if imported.deduped {
+                    deduped_files.push((label.clone(), imported.vault_key));
+                }
+                imported_item_ids.insert(imported.item_id);
+            }
+            Err(err) => {
+                log_eprintln!("[drop-import] failed to import {}: {}", label, err);
+                failed += 1;
+            }
+        }
+        done += 1;
+        reporter.report("importing", done, total, Some(&label));
+    }
+    if has_files {
+        match store_dropped_files_import_report(
+            failed,
+            deduped_files,
+            &imported_item_ids,
+            &thumb_policy,
+        ) {
+            Ok(count) => deduplicated_count = count,
+            Err(err) => log_eprintln!("[drop-import] failed to store import report: {}", err),
+        }
+    }
+    for url in bookmarks {
+        let label = url.to_string();
+        match create_bookmark_item_internal(url, Vec::new(), collection_id.clone(), None).await {
+            Ok(created) => {
+                if let Err(err) = open_db_connection().and_then(|connection| {
+                    set_item_import_batch(&connection, &created.item_id, &import_batch_id)
+                }) {
+                    log_eprintln!("[drop-import] failed to tag bookmark {}: {}", label, err);
+                }
+                imported_item_ids.insert(created.item_id);
+            }
+            Err(err) => {
+                log_eprintln!("[drop-import] failed to add bookmark {}: {}", label, err);
+                failed += 1;
+            }
+        }
+        done += 1;
+        reporter.report("importing", done, total, Some(&label));
+    }
+    if let Err(err) = open_db_connection().and_then(|connection| {
+        finish_import_batch(
+            &connection,
+            &import_batch_id,
+            imported_item_ids.len(),
+            failed,
+            0,
+            deduplicated_count,
+        )
+    }) {
+        log_eprintln!("[drop-import] failed to finish import batch: {}", err);
+    }
+    let outcome = if failed == 0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} of {} dropped entries failed to import",
+            failed, total
+        ))
+    };
+    reporter.finish(&outcome);
+}
+
+#[tauri::command]
+pub(crate) fn handle_dropped_paths(
+    app: AppHandle,
+    paths: Vec<String>,
+    collection_id: Option<String>,
+) -> Result<DroppedPathsSummary, String> {
+    let target_collection_id = match normalize_optional_trimmed_id(collection_id) {
+        Some(collection_id) => Some(collection_id),
+        None => root_collection_id_if_present()?,
+    };
+
+    let mut summary = DroppedPathsSummary {
+        jobs: Vec::new(),
+        files: 0,
+        directories: 0,
+        bookmarks: 0,
+        rejected: Vec::new(),
+    };
+    let mut files = Vec::new();
+    let mut bookmarks = Vec::new();
+    let mut directories = Vec::new();
+    for raw_path in paths {
+        let path = path_from_ipc_string(raw_path.trim());
+        if path.is_dir() {
+            directories.push(path);
+            continue;
+        }
+        if !path.is_file() {
+            summary.rejected.push(ImportDirectoryFailure {
+                path: raw_path,
+                error: "file not found".to_string(),
+            });
+            continue;
+        }
+        let ext = extension_from_path(&path);
+        if ext == "url" || ext == "webloc" {
+            match read_dropped_shortcut_url(&path, &ext) {
+                Ok(url) => bookmarks.push(url),
+                Err(error) => summary.rejected.push(ImportDirectoryFailure {
+                    path: raw_path,
+                    error,
+                }),
+            }
+        } else {
+            files.push(path);
+        }
+    }
+
+    summary.files = files.len();
+    summary.bookmarks = bookmarks.len();
+    summary.directories = directories.len();
+    if !files.is_empty() {
+        ensure_import_disk_space(import_paths_total_bytes(&files))?;
+    }
+    if !files.is_empty() || !bookmarks.is_empty() {
+        let import_batch_id = Uuid::new_v4().to_string();
+        let reporter = ProgressReporter::start_with_import_batch(
+            &app,
+            "import-dropped-files",
+            Some(&import_batch_id),
+        );
+        summary.jobs.push(DroppedPathsJob {
+            job_id: reporter.operation_id.clone(),
+            kind: "files".to_string(),
+            path: None,
+            import_batch_id: import_batch_id.clone(),
+        });
+        let job_collection_id = target_collection_id.clone();
+        tauri::async_runtime::spawn(import_dropped_files_job(
+            reporter,
+            import_batch_id,
+            files,
+            bookmarks,
+            job_collection_id,
+        ));
+    }
+    for directory in directories {
+        let import_batch_id = Uuid::new_v4().to_string();
+        let reporter = ProgressReporter::start_with_import_batch(
+            &app,
+            "import-directory",
+            Some(&import_batch_id),
+        );
+        summary.jobs.push(DroppedPathsJob {
+            job_id: reporter.operation_id.clone(),
+            kind: "directory".to_string(),
+            path: Some(path_to_ipc_string(&directory)),
+            import_batch_id,
+        });
+        let job_app = app.clone();
+        let job_collection_id = target_collection_id.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let outcome = import_directory_internal(
+                &job_app,
+                &reporter,
+                &directory,
+                true,
+                job_collection_id,
+                false,
+                None,
+            );
+            if let Err(err) = &outcome {
+                log_eprintln!(
+                    "[drop-import] failed to import directory {}: {}",
+                    directory.display(),
+                    err
+                );
+            }
+            reporter.finish(&outcome.map(|_| ()));
+        });
+    }
+    Ok(summary)
+}
+
+#[tauri::command]
+pub(crate) async fn preview_import_directory(
+    path: String,
+    recursive: Option<bool>,
+    extensions: Option<Vec<String>>,
+    hash_duplicates: Option<bool>,
+) -> Result<ImportDirectoryPreview, String> {
+    let root = path_from_ipc_string(path.trim());
+    let extensions = normalize_import_extensions_filter(extensions);
+    tauri::async_runtime::spawn_blocking(move || {
+        preview_import_directory_internal(
+            &root,
+            recursive.unwrap_or(true),
+            extensions,
+            hash_duplicates.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|err| format!("import preview thread join failed: {}", err))?
+}
+
+pub(crate) fn mark_orphaned_vault_file_in_tx(
+    transaction: &Transaction<'_>,
+    vault_key: &str,
+    vault_path: &str,
+) -> Result<(), String> {
+    let Ok((sha256, ext)) = parse_vault_key(vault_key) else {
+        return Ok(());
+    };
+    let item_refs: i64 = transaction
+        .query_row(
+            "SELECT COUNT(*) FROM items WHERE vault_key = ?1",
+            params![vault_key],
+            |row| row.get(0),
+        )
+        .map_err(|err| format!("failed to count vault refs for orphaned import: {}", err))?;
+    if item_refs > 0 {
+        return Ok(());
+    }
+
+    let now = Utc::now().timestamp_millis();
+    transaction
+        .execute(
+            "INSERT OR IGNORE INTO vault_files (
+                vault_key,
+                vault_path,
+                sha256,
+                ext,
+                ref_count,
+                created_at,
+                updated_at
+             ) VALUES (?1, ?2, ?3, ?4, 0, ?5, ?5)",
+            params![vault_key, vault_path, sha256, ext, now],
+        )
+        .map_err(|err| format!("failed to record orphaned vault file: {}", err))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn finalize_item_import(input: FinalizeItemImportInput) -> Result<i64, String> {
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+
+    let next_vault_key = input.vault_key.trim().to_string();
+    let next_vault_path = input.vault_path.trim().to_string();
+    if next_vault_key.is_empty() || next_vault_path.is_empty() {
+        return Err("cannot finalize import without a vault key/path".to_string());
+    }
+
+    let current_vault = transaction
+        .query_row(
+            "SELECT vault_key, import_status, type FROM items WHERE id = ?1",
+            params![&input.item_id],
+            |row| {
+                let vault_key: String = row.get(0)?;
+                let import_status: String = row.get(1)?;
+                let item_type: String = row.get(2)?;
+                Ok((vault_key, import_status, item_type))
+            },
+        )
+        .optional()
+        .map_err(|err| format!("failed to read current item import state: {}", err))?;
+    let Some(current_vault) = current_vault else {
+        mark_orphaned_vault_file_in_tx(&transaction, &next_vault_key, &next_vault_path)?;
+        transaction
+            .commit()
+            .map_err(|err| format!("failed to commit orphaned import cleanup: {}", err))?;
+        return Err("item not found while finalizing import".to_string());
+    };
+
+    let (current_vault_key, current_import_status, item_type) = current_vault;
+    if item_type == "bookmark" || item_type == "note" {
+        return Err(format!(
+            "{} items have no vault file to finalize",
+            item_type
+        ));
+    }
+    if !current_vault_key.trim().is_empty() && current_vault_key != next_vault_key {
+        let versions_to_keep = load_file_versions_to_keep(&transaction)?;
+        if versions_to_keep > 0 {
+            record_item_file_version_in_tx(
+                &transaction,
+                &input.item_id,
+                &current_vault_key,
+                Utc::now().timestamp_millis(),
+            )?;
+            prune_item_file_versions_in_tx(&transaction, &input.item_id, versions_to_keep)?;
+        } else {
+            let _ = decrement_vault_ref_in_tx(&transaction, &current_vault_key, 1)?;
+        }
+    }
+    if current_vault_key != next_vault_key {
+        increment_vault_ref_in_tx(&transaction, &next_vault_key, &next_vault_path)?;
+    }
+
+    let updated_at = Utc::now().timestamp_millis();
+    let affected_rows = transaction
+        .execute(
+            "UPDATE items
+             SET title = ?1,
+                 filename = ?2,
+                 vault_key = ?3,
+                 vault_path = ?4,
+                 width = ?5,
+                 height = ?6,
+                 thumb_status = ?7,
+                 import_status = 'ready',
+                 import_error = NULL,
+                 updated_at = ?8
+             WHERE id = ?9",
+            params![
+                input.title,
+                input.filename,
+                next_vault_key,
+                next_vault_path,
+                input.width,
+                input.height,
+                normalize_thumb_status(&input.thumb_status),
+                updated_at,
+                input.item_id
+            ],
+        )
+        .map_err(|err| format!("failed to finalize imported item row: {}", err))?;
+
+    if affected_rows == 0 {
+        return Err("item not found while finalizing import".to_string());
+    }
+    if item_audit_enabled(&transaction)? {
+        record_item_audit_in_tx(
+            &transaction,
+            &input.item_id,
+            "import_status",
+            Some(&current_import_status),
+            Some("ready"),
+            updated_at,
+        )?;
+        record_item_audit_in_tx(
+            &transaction,
+            &input.item_id,
+            "vault_key",
+            Some(&current_vault_key),
+            Some(&next_vault_key),
+            updated_at,
+        )?;
+    }
+
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit finalize import transaction: {}", err))?;
+
+    Ok(updated_at)
+}
+
+pub(crate) fn load_file_versions_to_keep(connection: &Connection) -> Result<usize, String> {
+    Ok(read_app_setting(connection, SETTING_FILE_VERSIONS_TO_KEEP)?
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_FILE_VERSIONS_TO_KEEP))
+}
+
+pub(crate) fn load_item_file_version_keys_in_tx(
+    transaction: &Transaction<'_>,
+    item_id: &str,
+) -> Result<Vec<(String, String)>, String> {
+    let mut stmt = transaction
+        .prepare("SELECT vault_key, vault_path FROM item_file_versions WHERE item_id = ?1")
+        .map_err(|err| format!("failed to prepare item file versions query: {}", err))?;
+    let rows = stmt
+        .query_map(params![item_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|err| format!("failed to query item file versions: {}", err))?;
+    let mut versions = Vec::new();
+    for row_result in rows {
+        versions.push(
+            row_result.map_err(|err| format!("failed to read item file version row: {}", err))?,
+        );
+    }
+    Ok(versions)
+}
+
+pub(crate) fn record_item_file_version_in_tx(
+    transaction: &Transaction<'_>,
+    item_id: &str,
+    vault_key: &str,
+    replaced_at: i64,
+) -> Result<(), String> {
+    let vault_path = transaction
+        .query_row(
+            "SELECT COALESCE(
+                (SELECT vault_path FROM vault_files WHERE vault_key = ?1),
+                (SELECT vault_path FROM items WHERE id = ?2),
+                ''
+             )",
+            params![vault_key, item_id],
+            |row| row.get::<_, String>(0),
+        )
+        .map_err(|err| format!("failed to resolve vault path for file version: {}", err))?;
+    transaction
+        .execute(
+            "INSERT INTO item_file_versions (item_id, vault_key, vault_path, replaced_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![item_id, vault_key, vault_path, replaced_at],
+        )
+        .map_err(|err| format!("failed to record item file version: {}", err))?;
+    Ok(())
+}
+
+pub(crate) fn prune_item_file_versions_in_tx(
+    transaction: &Transaction<'_>,
+    item_id: &str,
+    versions_to_keep: usize,
+) -> Result<usize, String> {
+    let mut stmt = transaction
+        .prepare(
+            "SELECT id, vault_key
+             FROM item_file_versions
+             WHERE item_id = ?1
+             ORDER BY id DESC
+             LIMIT -1 OFFSET ?2",
+        )
+        .map_err(|err| format!("failed to prepare file version prune query: {}", err))?;
+    let rows = stmt
+        .query_map(params![item_id, versions_to_keep as i64], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|err| format!("failed to query file versions to prune: {}", err))?;
+    let mut stale_versions = Vec::new();
+    for row_result in rows {
+        stale_versions
+            .push(row_result.map_err(|err| format!("failed to read file version row: {}", err))?);
+    }
+    drop(stmt);
+
+    for (version_id, vault_key) in &stale_versions {
+        transaction
+            .execute(
+                "DELETE FROM item_file_versions WHERE id = ?1",
+                params![version_id],
+            )
+            .map_err(|err| format!("failed to prune file version: {}", err))?;
+        let _ = decrement_vault_ref_in_tx(transaction, vault_key, 1)?;
+    }
+    Ok(stale_versions.len())
+}
+
+pub(crate) fn vault_file_disk_stats(path: &Path) -> Option<(i64, i64)> {
+    let metadata = fs::metadata(extended_length_path(path)).ok()?;
+    let modified_ms = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or(0);
+    Some((metadata.len() as i64, modified_ms))
+}
+
+pub(crate) fn vault_file_plaintext_sha256(path: &Path) -> Result<String, String> {
+    if !is_encrypted_vault_path(path) {
+        return sha256_for_file(path);
+    }
+    let key = current_vault_data_key()?;
+    let file = File::open(extended_length_path(path)).map_err(|err| {
+        format!(
+            "failed to open encrypted vault file {}: {}",
+            path.display(),
+            err
+        )
+    })?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+    decrypt_vault_stream(&key, &mut reader, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub(crate) fn load_item_vault_file_state(
+    connection: &Connection,
+    item_id: &str,
+) -> Result<(String, PathBuf, Option<i64>, Option<i64>), String> {
+    let (vault_key, vault_path, disk_size, disk_mtime) = connection
+        .query_row(
+            "SELECT i.vault_key, COALESCE(vf.vault_path, i.vault_path), vf.disk_size_bytes, vf.disk_mtime_ms
+             FROM items AS i
+             LEFT JOIN vault_files AS vf ON vf.vault_key = i.vault_key
+             WHERE i.id = ?1",
+            params![item_id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<i64>>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|err| format!("failed to read item vault file: {}", err))?
+        .ok_or_else(|| "item not found".to_string())?;
+    if vault_key.trim().is_empty() {
+        return Err("item has no vault file".to_string());
+    }
+    let stored_path = path_from_ipc_string(&vault_path);
+    let path = if stored_path.is_file() {
+        stored_path
+    } else {
+        find_existing_vault_file(&ensure_storage_root_internal()?, &vault_key)?
+            .unwrap_or(stored_path)
+    };
+    Ok((vault_key, path, disk_size, disk_mtime))
+}
+
+pub(crate) fn store_vault_file_disk_stats(
+    connection: &Connection,
+    vault_key: &str,
+    stats: Option<(i64, i64)>,
+) -> Result<(), String> {
+    connection
+        .execute(
+            "UPDATE vault_files SET disk_size_bytes = ?1, disk_mtime_ms = ?2 WHERE vault_key = ?3",
+            params![
+                stats.map(|(size, _)| size),
+                stats.map(|(_, mtime)| mtime),
+                vault_key
+            ],
+        )
+        .map_err(|err| format!("failed to cache vault file stats: {}", err))?;
+    Ok(())
+}
+
+pub(crate) fn verify_item_file_internal(
+    connection: &Connection,
+    item_id: &str,
+    rehash: bool,
+) -> Result<ItemIntegrityReport, String> {
+    let (vault_key, path, expected_size, expected_mtime) =
+        load_item_vault_file_state(connection, item_id)?;
+    let actual = if path.is_file() {
+        vault_file_disk_stats(&path)
+    } else {
+        None
+    };
+    let has_baseline = expected_size.is_some() && expected_mtime.is_some();
+    let mut actual_sha256 = None;
+    let integrity_status = match actual {
+        None => INTEGRITY_STATUS_MISSING,
+        Some(_) if rehash => {
+            let (expected_sha256, _) = parse_vault_key(&vault_key)?;
+            let sha256 = vault_file_plaintext_sha256(&path)?;
+            let matches = sha256 == expected_sha256;
+            actual_sha256 = Some(sha256);
+            if matches {
+                store_vault_file_disk_stats(connection, &vault_key, actual)?;
+                INTEGRITY_STATUS_OK
+            } else {
+                INTEGRITY_STATUS_MODIFIED
+            }
+        }
+        Some(stats) if !has_baseline => {
+            store_vault_file_disk_stats(connection, &vault_key, Some(stats))?;
+            INTEGRITY_STATUS_OK
+        }
+        Some((size, mtime)) if Some(size) == expected_size && Some(mtime) == expected_mtime => {
+            INTEGRITY_STATUS_OK
+        }
+        Some(_) => INTEGRITY_STATUS_MODIFIED,
+    };
+
+    connection
+        .execute(
+            "UPDATE items SET integrity_status = ?1 WHERE vault_key = ?2",
+            params![integrity_status, &vault_key],
+        )
+        .map_err(|err| format!("failed to update item integrity status: {}", err))?;
+
+    Ok(ItemIntegrityReport {
+        item_id: item_id.to_string(),
+        vault_key,
+        integrity_status: integrity_status.to_string(),
+        expected_size,
+        actual_size: actual.map(|(size, _)| size),
+        expected_mtime,
+        actual_mtime: actual.map(|(_, mtime)| mtime),
+        rehashed: actual_sha256.is_some(),
+        actual_sha256,
+        checked_at: Utc::now().timestamp_millis(),
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn verify_item_file(
+    item_id: String,
+    rehash: Option<bool>,
+) -> Result<ItemIntegrityReport, String> {
+    let normalized_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        initialize_db()?;
+        let connection = open_db_connection()?;
+        verify_item_file_internal(&connection, &normalized_item_id, rehash.unwrap_or(false))
+    })
+    .await
+    .map_err(|err| format!("item verification thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn record_item_opened(item_id: String) -> Result<Option<ItemIntegrityReport>, String> {
+    let normalized_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    connection
+        .execute(
+            "UPDATE items SET last_opened_at = ?1 WHERE id = ?2",
+            params![Utc::now().timestamp_millis(), &normalized_item_id],
+        )
+        .map_err(|err| format!("failed to record item open: {}", err))?;
+    if !auto_verify_on_open_enabled(&connection)? {
+        return Ok(None);
+    }
+    let has_vault_file = connection
+        .query_row(
+            "SELECT vault_key <> '' FROM items WHERE id = ?1",
+            params![&normalized_item_id],
+            |row| row.get::<_, bool>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to read opened item: {}", err))?
+        .ok_or_else(|| "item not found".to_string())?;
+    if !has_vault_file {
+        return Ok(None);
+    }
+    verify_item_file_internal(&connection, &normalized_item_id, false).map(Some)
+}
+
+#[tauri::command]
+pub(crate) async fn repair_modified_vault_file(
+    item_id: String,
+) -> Result<RepairModifiedVaultFileResult, String> {
+    let normalized_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        initialize_db()?;
+        let mut connection = open_db_connection()?;
+        let (previous_vault_key, source_path, _, _) =
+            load_item_vault_file_state(&connection, &normalized_item_id)?;
+        if !source_path.is_file() {
+            return Err(format!("vault file is missing for item: {}", previous_vault_key));
+        }
+        let (previous_sha256, ext) = parse_vault_key(&previous_vault_key)?;
+        let sha256 = vault_file_plaintext_sha256(&source_path)?;
+        let rekeyed = sha256 != previous_sha256;
+        let vault_key = if rekeyed {
+            build_vault_filename(&sha256, &ext)
+        } else {
+            previous_vault_key.clone()
+        };
+
+        let target_path = if rekeyed {
+            let existing_path = connection
+                .query_row(
+                    "SELECT vault_path FROM vault_files WHERE vault_key = ?1",
+                    params![&vault_key],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()
+                .map_err(|err| format!("failed to look up repaired vault file: {}", err))?
+                .map(|path| path_from_ipc_string(&path))
+                .filter(|path| path.is_file());
+            match existing_path {
+                Some(path) => path,
+                None => {
+                    let directory = source_path
+                        .parent()
+                        .ok_or_else(|| format!("vault file has no parent directory: {}", source_path.display()))?;
+                    let plain_target = directory.join(&vault_key);
+                    let target = if is_encrypted_vault_path(&source_path) {
+                        encrypted_vault_path(&plain_target)
+                    } else {
+                        plain_target
+                    };
+                    fs::copy(extended_length_path(&source_path), extended_length_path(&target)).map_err(
+                        |err| {
+                            format!(
+                                "failed to copy modified vault file {} to {}: {}",
+                                source_path.display(),
+                                target.display(),
+                                err
+                            )
+                        },
+                    )?;
+                    target
+                }
+            }
+        } else {
+            source_path.clone()
+        };
+        let target_path_string = path_to_string(&target_path)?;
+
+        let updated_at = Utc::now().timestamp_millis();
+        let transaction = connection
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+            .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+        if rekeyed {
+            increment_vault_ref_in_tx(&transaction, &vault_key, &target_path_string)?;
+            decrement_vault_ref_in_tx(&transaction, &previous_vault_key, 1)?;
+            transaction
+                .execute(
+                    "UPDATE items
+                     SET vault_key = ?1, vault_path = ?2, integrity_status = ?3, thumb_status = 'pending', updated_at = ?4
+                     WHERE id = ?5",
+                    params![
+                        &vault_key,
+                        &target_path_string,
+                        INTEGRITY_STATUS_OK,
+                        updated_at,
+                        &normalized_item_id
+                    ],
+                )
+                .map_err(|err| format!("failed to re-key item vault file: {}", err))?;
+            transaction
+                .execute(
+                    "UPDATE items SET integrity_status = ?1 WHERE vault_key = ?2",
+                    params![INTEGRITY_STATUS_MODIFIED, &previous_vault_key],
+                )
+                .map_err(|err| format!("failed to flag items sharing modified file: {}", err))?;
+        } else {
+            transaction
+                .execute(
+                    "UPDATE items SET integrity_status = ?1 WHERE vault_key = ?2",
+                    params![INTEGRITY_STATUS_OK, &vault_key],
+                )
+                .map_err(|err| format!("failed to clear item integrity status: {}", err))?;
+        }
+        transaction
+            .execute(
+                "UPDATE vault_files
+                 SET is_encrypted = ?1, disk_size_bytes = ?2, disk_mtime_ms = ?3
+                 WHERE vault_key = ?4",
+                params![
+                    is_encrypted_vault_path(&target_path),
+                    vault_file_disk_stats(&target_path).map(|(size, _)| size),
+                    vault_file_disk_stats(&target_path).map(|(_, mtime)| mtime),
+                    &vault_key
+                ],
+            )
+            .map_err(|err| format!("failed to cache repaired vault file stats: {}", err))?;
+        transaction
+            .commit()
+            .map_err(|err| format!("failed to commit vault file repair: {}", err))?;
+        drop(connection);
+
+        if rekeyed {
+            let (width, height, thumb_status) = refresh_vault_file_media(&vault_key, &target_path);
+            update_item_media_state(UpdateItemMediaStateInput {
+                item_id: normalized_item_id.clone(),
+                width,
+                height,
+                thumb_status: Some(thumb_status),
+                clear_dimensions: false,
+            })?;
+        }
+
+        let connection = open_db_connection()?;
+        let item = query_db_item_rows(
+            &connection,
+            &format!("{} WHERE i.id = ?1 GROUP BY i.id", DB_ITEM_ROW_SELECT),
+            params![&normalized_item_id],
+        )?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "item not found after repairing vault file".to_string())?;
+
+        Ok(RepairModifiedVaultFileResult {
+            item_id: normalized_item_id,
+            previous_vault_key,
+            vault_key,
+            vault_path: target_path_string,
+            rekeyed,
+            item,
+        })
+    })
+    .await
+    .map_err(|err| format!("vault file repair thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn list_item_file_versions(item_id: String) -> Result<Vec<ItemFileVersion>, String> {
+    let normalized_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let mut stmt = connection
+        .prepare(
+            "SELECT v.id, v.item_id, v.vault_key, COALESCE(vf.vault_path, v.vault_path), v.replaced_at
+             FROM item_file_versions v
+             LEFT JOIN vault_files vf ON vf.vault_key = v.vault_key
+             WHERE v.item_id = ?1
+             ORDER BY v.id DESC",
+        )
+        .map_err(|err| format!("failed to prepare item file versions query: {}", err))?;
+    let rows = stmt
+        .query_map(params![normalized_item_id], |row| {
+            let vault_path: String = row.get(3)?;
+            Ok(ItemFileVersion {
+                id: row.get(0)?,
+                item_id: row.get(1)?,
+                vault_key: row.get(2)?,
+                file_exists: Path::new(&vault_path).is_file(),
+                vault_path,
+                replaced_at: row.get(4)?,
+            })
+        })
+        .map_err(|err| format!("failed to query item file versions: {}", err))?;
+    let mut versions = Vec::new();
+    for row_result in rows {
+        versions.push(
+            row_result.map_err(|err| format!("failed to read item file version row: {}", err))?,
+        );
+    }
+    Ok(versions)
+}
+
+#[tauri::command]
+pub(crate) async fn restore_item_file_version(
+    item_id: String,
+    vault_key: String,
+) -> Result<DbItemRow, String> {
+    let normalized_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+    let normalized_vault_key = vault_key.trim().to_string();
+    parse_vault_key(&normalized_vault_key)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        initialize_db()?;
+        let mut connection = open_db_connection()?;
+        let item_vault_key = |connection: &Connection| {
+            connection
+                .query_row(
+                    "SELECT vault_key FROM items WHERE id = ?1",
+                    params![&normalized_item_id],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()
+                .map_err(|err| format!("failed to read item for version restore: {}", err))?
+                .ok_or_else(|| "item not found while restoring file version".to_string())
+        };
+        if item_vault_key(&connection)? == normalized_vault_key {
+            return Err("item already uses this file version".to_string());
+        }
+        let version_path = connection
+            .query_row(
+                "SELECT COALESCE(vf.vault_path, v.vault_path)
+                 FROM item_file_versions v
+                 LEFT JOIN vault_files vf ON vf.vault_key = v.vault_key
+                 WHERE v.item_id = ?1 AND v.vault_key = ?2
+                 ORDER BY v.id DESC
+                 LIMIT 1",
+                params![&normalized_item_id, &normalized_vault_key],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(|err| format!("failed to read item file version: {}", err))?
+            .ok_or_else(|| "file version not found for item".to_string())?;
+        let storage_root = ensure_storage_root_internal()?;
+        let version_path = if Path::new(&version_path).is_file() {
+            PathBuf::from(&version_path)
+        } else {
+            find_existing_vault_file(&storage_root, &normalized_vault_key)?.ok_or_else(|| {
+                format!(
+                    "file version is missing from the vault: {}",
+                    normalized_vault_key
+                )
+            })?
+        };
+        let (width, height, thumb_status) =
+            refresh_vault_file_media(&normalized_vault_key, &version_path);
+        let vault_path = path_to_string(&version_path)?;
+
+        let transaction = connection
+            .transaction()
+            .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+        let current_vault_key = item_vault_key(&transaction)?;
+        if current_vault_key == normalized_vault_key {
+            return Err("item already uses this file version".to_string());
+        }
+
+        let updated_at = Utc::now().timestamp_millis();
+        transaction
+            .execute(
+                "DELETE FROM item_file_versions
+                 WHERE id = (
+                   SELECT id FROM item_file_versions
+                   WHERE item_id = ?1 AND vault_key = ?2
+                   ORDER BY id DESC
+                   LIMIT 1
+                 )",
+                params![&normalized_item_id, &normalized_vault_key],
+            )
+            .map_err(|err| format!("failed to remove restored file version: {}", err))?;
+        if !current_vault_key.trim().is_empty() {
+            record_item_file_version_in_tx(
+                &transaction,
+                &normalized_item_id,
+                &current_vault_key,
+                updated_at,
+            )?;
+        }
+        transaction
+            .execute(
+                "UPDATE vault_files SET vault_path = ?1, updated_at = ?2 WHERE vault_key = ?3",
+                params![&vault_path, updated_at, &normalized_vault_key],
+            )
+            .map_err(|err| format!("failed to update restored vault file row: {}", err))?;
+        transaction
+            .execute(
+                "UPDATE items
+                 SET vault_key = ?1,
+                     vault_path = ?2,
+                     width = ?3,
+                     height = ?4,
+                     thumb_status = ?5,
+                     import_status = 'ready',
+                     import_error = NULL,
+                     updated_at = ?6
+                 WHERE id = ?7",
+                params![
+                    &normalized_vault_key,
+                    &vault_path,
+                    width,
+                    height,
+                    thumb_status,
+                    updated_at,
+                    &normalized_item_id
+                ],
+            )
+            .map_err(|err| format!("failed to restore item file version: {}", err))?;
+        if item_audit_enabled(&transaction)? {
+            record_item_audit_in_tx(
+                &transaction,
+                &normalized_item_id,
+                "vault_key",
+                Some(&current_vault_key),
+                Some(&normalized_vault_key),
+                updated_at,
+            )?;
+        }
+        transaction
+            .commit()
+            .map_err(|err| format!("failed to commit file version restore: {}", err))?;
+
+        query_db_item_rows(
+            &connection,
+            &format!("{} WHERE i.id = ?1 GROUP BY i.id", DB_ITEM_ROW_SELECT),
+            params![&normalized_item_id],
+        )?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "item not found after restoring file version".to_string())
+    })
+    .await
+    .map_err(|err| format!("file version restore thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn get_file_versions_to_keep() -> Result<usize, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    load_file_versions_to_keep(&connection)
+}
+
+#[tauri::command]
+pub(crate) fn set_file_versions_to_keep(versions_to_keep: usize) -> Result<usize, String> {
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    write_app_setting(
+        &transaction,
+        SETTING_FILE_VERSIONS_TO_KEEP,
+        &versions_to_keep.to_string(),
+    )?;
+
+    let mut stmt = transaction
+        .prepare("SELECT DISTINCT item_id FROM item_file_versions")
+        .map_err(|err| format!("failed to prepare versioned items query: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to query versioned items: {}", err))?;
+    let mut item_ids = Vec::new();
+    for row_result in rows {
+        item_ids
+            .push(row_result.map_err(|err| format!("failed to read versioned item row: {}", err))?);
+    }
+    drop(stmt);
+
+    let mut pruned = 0usize;
+    for item_id in item_ids {
+        pruned += prune_item_file_versions_in_tx(&transaction, &item_id, versions_to_keep)?;
+    }
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit file version settings: {}", err))?;
+    Ok(pruned)
+}
+
+#[tauri::command]
+pub(crate) fn mark_item_import_error(input: MarkItemImportErrorInput) -> Result<i64, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let updated_at = Utc::now().timestamp_millis();
+    let import_error = input
+        .error
+        .as_deref()
+        .map(str::trim)
+        .filter(|error| !error.is_empty())
+        .map(|error| {
+            error
+                .chars()
+                .take(ITEM_IMPORT_ERROR_MAX_CHARS)
+                .collect::<String>()
+        });
+    let affected_rows = connection
+        .execute(
+            "UPDATE items
+             SET import_status = 'error',
+                 thumb_status = CASE
+                     WHEN type = 'image' THEN 'error'
+                     ELSE thumb_status
+                 END,
+                 import_error = ?3,
+                 updated_at = ?1
+             WHERE id = ?2",
+            params![updated_at, input.item_id, import_error],
+        )
+        .map_err(|err| format!("failed to mark item import error: {}", err))?;
+
+    if affected_rows == 0 {
+        return Err("item not found while marking import error".to_string());
+    }
+
+    Ok(updated_at)
+}
+
+pub(crate) fn failed_import_source_path(entry: &FailedImportEntry) -> Option<PathBuf> {
+    let vault_path = entry.vault_path.trim();
+    if !vault_path.is_empty() && Path::new(vault_path).is_file() {
+        return Some(PathBuf::from(vault_path));
+    }
+    entry
+        .original_source_path
+        .as_deref()
+        .map(path_from_ipc_string)
+        .filter(|path| path.is_file())
+}
+
+pub(crate) fn load_failed_imports(
+    connection: &Connection,
+) -> Result<Vec<FailedImportEntry>, String> {
+    let mut stmt = connection
+        .prepare(
+            "SELECT id, type, title, filename, vault_path, original_source_path, updated_at
+             FROM items
+             WHERE import_status = 'error'
+             ORDER BY updated_at DESC, id ASC",
+        )
+        .map_err(|err| format!("failed to prepare failed imports query: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(FailedImportEntry {
+                item_id: row.get(0)?,
+                item_type: row.get(1)?,
+                title: row.get(2)?,
+                filename: row.get(3)?,
+                vault_path: row.get(4)?,
+                original_source_path: row.get(5)?,
+                source_available: false,
+                updated_at: row.get(6)?,
+            })
+        })
+        .map_err(|err| format!("failed to query failed imports: {}", err))?;
+
+    let mut entries = Vec::new();
+    for row_result in rows {
+        let mut entry =
+            row_result.map_err(|err| format!("failed to parse failed import row: {}", err))?;
+        entry.source_available = failed_import_source_path(&entry).is_some();
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+pub(crate) fn retry_failed_import_internal(
+    entry: &FailedImportEntry,
+    source: PathBuf,
+) -> Result<(), String> {
+    let imported = run_import_pipeline_internal(
+        Some(source),
+        None,
+        None,
+        Some(entry.filename.clone()),
+        IMPORT_THUMB_POLICY_IMMEDIATE,
+        None,
+    )?;
+    finalize_item_import(FinalizeItemImportInput {
+        item_id: entry.item_id.clone(),
+        title: entry.title.clone(),
+        filename: entry.filename.clone(),
+        vault_key: build_vault_filename(&imported.sha256, &imported.ext),
+        vault_path: imported.vault_path,
+        width: imported.width.map(i64::from),
+        height: imported.height.map(i64::from),
+        thumb_status: imported.thumb_status,
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn list_failed_imports() -> Result<Vec<FailedImportEntry>, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    load_failed_imports(&connection)
+}
+
+#[tauri::command]
+pub(crate) async fn retry_failed_imports(
+    item_ids: Option<Vec<String>>,
+) -> Result<RetryFailedImportsResult, String> {
+    let requested_ids = item_ids.map(|ids| {
+        ids.iter()
+            .filter_map(|id| normalize_trimmed_id(id))
+            .collect::<BTreeSet<String>>()
+    });
+
+    tauri::async_runtime::spawn_blocking(move || {
+        initialize_db()?;
+        let connection = open_db_connection()?;
+        let entries = load_failed_imports(&connection)?;
+        drop(connection);
+
+        let mut result = RetryFailedImportsResult {
+            recovered: Vec::new(),
+            still_failing: Vec::new(),
+            unrecoverable: Vec::new(),
+        };
+        for entry in entries {
+            if let Some(ids) = requested_ids.as_ref() {
+                if !ids.contains(&entry.item_id) {
+                    continue;
+                }
+            }
+            let Some(source) = failed_import_source_path(&entry) else {
+                result.unrecoverable.push(entry.item_id);
+                continue;
+            };
+            match retry_failed_import_internal(&entry, source) {
+                Ok(()) => result.recovered.push(entry.item_id),
+                Err(error) => {
+                    log_eprintln!(
+                        "[import-retry] failed to retry import for {}: {}",
+                        entry.item_id,
+                        error
+                    );
+                    result.still_failing.push(RetryFailedImportError {
+                        item_id: entry.item_id,
+                        error,
+                    });
+                }
+            }
+        }
+
+        log_println!(
+            "[import-retry] recovered={} still_failing={} unrecoverable={}",
+            result.recovered.len(),
+            result.still_failing.len(),
+            result.unrecoverable.len()
+        );
+        Ok(result)
+    })
+    .await
+    .map_err(|err| format!("retry failed imports thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn validate_vault_keys() -> Result<Vec<InvalidVaultKeyEntry>, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let mut invalid = Vec::new();
+    for (source, sql) in [
+        (
+            "items",
+            "SELECT id, vault_key FROM items WHERE TRIM(vault_key) <> '' ORDER BY id ASC",
+        ),
+        (
+            "vault_files",
+            "SELECT vault_key, vault_key FROM vault_files ORDER BY vault_key ASC",
+        ),
+    ] {
+        let mut stmt = connection
+            .prepare(sql)
+            .map_err(|err| format!("failed to prepare {} vault key query: {}", source, err))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|err| format!("failed to query {} vault keys: {}", source, err))?;
+        for row_result in rows {
+            let (id, vault_key) = row_result
+                .map_err(|err| format!("failed to read {} vault key: {}", source, err))?;
+            if let Err(error) = parse_vault_key(&vault_key) {
+                invalid.push(InvalidVaultKeyEntry {
+                    source: source.to_string(),
+                    id,
+                    vault_key,
+                    error,
+                });
+            }
+        }
+    }
+    Ok(invalid)
+}
+
+#[tauri::command]
+pub(crate) async fn fix_mismatched_extensions() -> Result<ExtensionMismatchReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        initialize_db()?;
+        let connection = open_db_connection()?;
+        let mut stmt = connection
+            .prepare(
+                "SELECT vf.vault_key, vf.vault_path, vf.ext,
+                        (SELECT COUNT(*) FROM items AS i WHERE i.vault_key = vf.vault_key)
+                 FROM vault_files AS vf
+                 ORDER BY vf.vault_key ASC",
+            )
+            .map_err(|err| format!("failed to prepare vault extension query: {}", err))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })
+            .map_err(|err| format!("failed to query vault extensions: {}", err))?;
+        let mut vault_files = Vec::new();
+        for row_result in rows {
+            vault_files.push(
+                row_result.map_err(|err| format!("failed to read vault extension row: {}", err))?,
+            );
+        }
+        drop(stmt);
+        drop(connection);
+
+        let mut report = ExtensionMismatchReport {
+            scanned: 0,
+            unreadable: 0,
+            mismatches: Vec::new(),
+        };
+        for (vault_key, vault_path, stored_ext, item_count) in vault_files {
+            report.scanned += 1;
+            let head = match read_vault_file_head(
+                &path_from_ipc_string(&vault_path),
+                CONTENT_SNIFF_HEAD_BYTES,
+            ) {
+                Ok(head) => head,
+                Err(_) => {
+                    report.unreadable += 1;
+                    continue;
+                }
+            };
+            let resolved_ext = resolve_sniffed_extension(&stored_ext, sniff_extension(&head));
+            if resolved_ext != stored_ext {
+                report.mismatches.push(ExtensionMismatchEntry {
+                    vault_key,
+                    vault_path,
+                    stored_ext,
+                    sniffed_ext: resolved_ext,
+                    item_count,
+                });
+            }
+        }
+
+        log_println!(
+            "[vault-check] extension_scan scanned={} mismatched={} unreadable={}",
+            report.scanned,
+            report.mismatches.len(),
+            report.unreadable
+        );
+        Ok(report)
+    })
+    .await
+    .map_err(|err| format!("extension mismatch scan thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) async fn find_broken_items(app: AppHandle) -> Result<Vec<BrokenVaultEntry>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        initialize_db()?;
+        let connection = open_db_connection()?;
+        let mut stmt = connection
+            .prepare(
+                "SELECT i.vault_key, i.vault_path, vf.vault_path, i.id, i.type, i.title, i.filename
+                 FROM items AS i
+                 LEFT JOIN vault_files AS vf ON vf.vault_key = i.vault_key
+                 WHERE TRIM(i.vault_key) <> ''
+                 ORDER BY i.vault_key ASC, i.created_at ASC",
+            )
+            .map_err(|err| format!("failed to prepare broken items query: {}", err))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    BrokenItemEntry {
+                        item_id: row.get(3)?,
+                        item_type: row.get(4)?,
+                        title: row.get(5)?,
+                        filename: row.get(6)?,
+                    },
+                ))
+            })
+            .map_err(|err| format!("failed to query vault items: {}", err))?;
+
+        let mut items_by_vault_key: BTreeMap<String, (BTreeSet<String>, Vec<BrokenItemEntry>)> =
+            BTreeMap::new();
+        for row_result in rows {
+            let (vault_key, item_vault_path, recorded_vault_path, item) =
+                row_result.map_err(|err| format!("failed to read vault item row: {}", err))?;
+            let entry = items_by_vault_key.entry(vault_key).or_default();
+            if let Some(path) = recorded_vault_path.filter(|path| !path.trim().is_empty()) {
+                entry.0.insert(path);
+            }
+            if !item_vault_path.trim().is_empty() {
+                entry.0.insert(item_vault_path);
+            }
+            entry.1.push(item);
+        }
+        drop(stmt);
+        drop(connection);
+
+        let storage_root = ensure_storage_root_internal()?;
+        let total = items_by_vault_key.len();
+        let mut checked = 0_usize;
+        let mut broken = Vec::new();
+        for (vault_key, (candidate_paths, items)) in items_by_vault_key {
+            checked += 1;
+            let present = candidate_paths.iter().any(|path| Path::new(path).is_file())
+                || find_existing_vault_file(&storage_root, &vault_key)?.is_some();
+            if !present {
+                let thumb_exists = thumb_output_path_for_vault_key(&vault_key)
+                    .map(|path| path.is_file())
+                    .unwrap_or(false);
+                broken.push(BrokenVaultEntry {
+                    expected_path: candidate_paths.into_iter().next().unwrap_or_default(),
+                    vault_key,
+                    thumb_exists,
+                    items,
+                });
+            }
+            if checked.is_multiple_of(BROKEN_ITEMS_PROGRESS_INTERVAL) || checked == total {
+                let _ = app.emit(
+                    BROKEN_ITEMS_PROGRESS_EVENT,
+                    BrokenItemsProgressPayload {
+                        checked,
+                        total,
+                        broken: broken.len(),
+                    },
+                );
+            }
+        }
+
+        log_println!(
+            "[vault-check] checked_keys={} broken_keys={}",
+            total,
+            broken.len()
+        );
+        Ok(broken)
+    })
+    .await
+    .map_err(|err| format!("find broken items thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) async fn relink_item_file(
+    item_id: String,
+    new_source_path: String,
+    force: Option<bool>,
+) -> Result<RelinkItemFileResult, String> {
+    let normalized_item_id =
+        normalize_trimmed_id(&item_id).ok_or_else(|| "item id cannot be empty".to_string())?;
+    let source = path_from_ipc_string(new_source_path.trim());
+    if !source.is_file() {
+        return Err(format!(
+            "replacement file does not exist: {}",
+            source.display()
+        ));
+    }
+    let force = force.unwrap_or(false);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        initialize_db()?;
+        let mut connection = open_db_connection()?;
+        let (vault_key, title, filename) = connection
+            .query_row(
+                "SELECT vault_key, title, filename FROM items WHERE id = ?1",
+                params![&normalized_item_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(|err| format!("failed to read item for relink: {}", err))?
+            .ok_or_else(|| "item not found while relinking file".to_string())?;
+        let (expected_sha256, _) = parse_vault_key(&vault_key)
+            .map_err(|err| format!("item has no valid vault file to relink: {}", err))?;
+
+        let actual_sha256 = sha256_for_file(&source)?;
+        if actual_sha256 == expected_sha256 {
+            let storage_root = ensure_storage_root_internal()?;
+            let destination = match find_existing_vault_file(&storage_root, &vault_key)? {
+                Some(existing) => existing,
+                None => {
+                    let destination = ensure_current_month_directory(&storage_root)?.join(&vault_key);
+                    if let Some(key) = vault_write_key()? {
+                        let destination = encrypted_vault_path(&destination);
+                        let input = File::open(extended_length_path(&source))
+                            .map_err(|err| format!("failed to open {}: {}", source.display(), err))?;
+                        encrypt_into_vault_file(&key, &mut BufReader::new(input), &destination)?;
+                        destination
+                    } else {
+                        fs::copy(extended_length_path(&source), extended_length_path(&destination)).map_err(|err| {
+                            format!(
+                                "failed to copy {} to {}: {}",
+                                source.display(),
+                                destination.display(),
+                                err
+                            )
+                        })?;
+                        destination
+                    }
+                }
+            };
+            let vault_path = path_to_string(&destination)?;
+            let is_encrypted = is_encrypted_vault_path(&destination);
+            let size_bytes = fs::metadata(&destination)
+                .map(|metadata| {
+                    if is_encrypted {
+                        encrypted_vault_plaintext_len(metadata.len()) as i64
+                    } else {
+                        metadata.len() as i64
+                    }
+                })
+                .unwrap_or(0);
+
+            let updated_at = Utc::now().timestamp_millis();
+            let transaction = connection
+                .transaction()
+                .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+            let relinked_items = transaction
+                .execute(
+                    "UPDATE items SET vault_path = ?1, updated_at = ?2 WHERE vault_key = ?3",
+                    params![&vault_path, updated_at, &vault_key],
+                )
+                .map_err(|err| format!("failed to relink item vault paths: {}", err))?;
+            transaction
+                .execute(
+                    "UPDATE vault_files
+                     SET vault_path = ?1,
+                         size_bytes = ?2,
+                         is_encrypted = ?3,
+                         updated_at = ?4
+                     WHERE vault_key = ?5",
+                    params![&vault_path, size_bytes, i64::from(is_encrypted), updated_at, &vault_key],
+                )
+                .map_err(|err| format!("failed to relink vault file row: {}", err))?;
+            transaction
+                .commit()
+                .map_err(|err| format!("failed to commit relink transaction: {}", err))?;
+
+            return Ok(RelinkItemFileResult {
+                item_id: normalized_item_id,
+                vault_key,
+                vault_path,
+                reimported: false,
+                relinked_items,
+            });
+        }
+
+        if !force {
+            return Err(format!(
+                "replacement file does not match the item's content (expected sha256 {}, got {}); use force to import it as a new file",
+                expected_sha256, actual_sha256
+            ));
+        }
+
+        let imported = run_import_pipeline_internal(
+            Some(source),
+            None,
+            None,
+            Some(filename.clone()),
+            IMPORT_THUMB_POLICY_IMMEDIATE,
+            None,
+        )?;
+        let next_vault_key = build_vault_filename(&imported.sha256, &imported.ext);
+        let next_vault_path = imported.vault_path.clone();
+        finalize_item_import(FinalizeItemImportInput {
+            item_id: normalized_item_id.clone(),
+            title,
+            filename,
+            vault_key: next_vault_key.clone(),
+            vault_path: next_vault_path.clone(),
+            width: imported.width.map(i64::from),
+            height: imported.height.map(i64::from),
+            thumb_status: imported.thumb_status,
+        })?;
+
+        Ok(RelinkItemFileResult {
+            item_id: normalized_item_id,
+            vault_key: next_vault_key,
+            vault_path: next_vault_path,
+            reimported: true,
+            relinked_items: 1,
+        })
+    })
+    .await
+    .map_err(|err| format!("relink item file thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn ensure_storage_root() -> Result<String, String> {
+    let root = ensure_storage_root_internal()?;
+    let _ = ensure_current_month_directory(&root)?;
+    path_to_string(&root)
+}
+
+pub(crate) fn move_vault_file_to_layout(
+    connection: &mut Connection,
+    vault_key: &str,
+    from_path: &Path,
+    to_path: &Path,
+) -> Result<(), String> {
+    if let Some(parent) = to_path.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            format!(
+                "failed to create vault directory {}: {}",
+                parent.display(),
+                err
+            )
+        })?;
+    }
+    if from_path.is_file() {
+        if to_path.is_file() {
+            fs::remove_file(extended_length_path(from_path)).map_err(|err| {
+                format!(
+                    "failed to remove duplicate vault file {}: {}",
+                    from_path.display(),
+                    err
+                )
+            })?;
+        } else {
+            fs::rename(
+                extended_length_path(from_path),
+                extended_length_path(to_path),
+            )
+            .map_err(|err| {
+                format!(
+                    "failed to move vault file {} to {}: {}",
+                    from_path.display(),
+                    to_path.display(),
+                    err
+                )
+            })?;
+        }
+    } else if !to_path.is_file() {
+        return Err(format!("vault file is missing: {}", from_path.display()));
+    }
+
+    let to_path_string = path_to_string(to_path)?;
+    let updated_at = Utc::now().timestamp_millis();
+    let transaction = connection
+        .transaction()
+        .map_err(|err| format!("failed to start sqlite transaction: {}", err))?;
+    transaction
+        .execute(
+            "UPDATE vault_files SET vault_path = ?1, updated_at = ?2 WHERE vault_key = ?3",
+            params![&to_path_string, updated_at, vault_key],
+        )
+        .map_err(|err| format!("failed to update vault file path: {}", err))?;
+    transaction
+        .execute(
+            "UPDATE items SET vault_path = ?1 WHERE vault_key = ?2",
+            params![&to_path_string, vault_key],
+        )
+        .map_err(|err| format!("failed to update item vault paths: {}", err))?;
+    transaction
+        .execute(
+            "UPDATE item_file_versions SET vault_path = ?1 WHERE vault_key = ?2",
+            params![&to_path_string, vault_key],
+        )
+        .map_err(|err| format!("failed to update file version paths: {}", err))?;
+    transaction
+        .commit()
+        .map_err(|err| format!("failed to commit vault file move: {}", err))
+}
+
+pub(crate) fn remove_empty_vault_directories(root: &Path) -> usize {
+    let Ok(years) = read_directory(root) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for year_entry in years.flatten() {
+        let year_path = year_entry.path();
+        if !year_path.is_dir() {
+            continue;
+        }
+        if let Ok(months) = read_directory(&year_path) {
+            for month_entry in months.flatten() {
+                let month_path = month_entry.path();
+                if month_path.is_dir() && fs::remove_dir(&month_path).is_ok() {
+                    removed += 1;
+                }
+            }
+        }
+        if fs::remove_dir(&year_path).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+pub(crate) fn compact_vault_internal(
+    reporter: &ProgressReporter,
+    strategy: &str,
+    dry_run: bool,
+) -> Result<VaultCompactionReport, String> {
+    initialize_db()?;
+    let mut connection = open_db_connection()?;
+    let root = ensure_storage_root_internal()?;
+
+    let mut candidates = Vec::new();
+    {
+        let mut stmt = connection
+            .prepare(
+                "SELECT vault_key, vault_path, created_at FROM vault_files ORDER BY created_at ASC",
+            )
+            .map_err(|err| format!("failed to prepare vault compaction query: {}", err))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })
+            .map_err(|err| format!("failed to query vault files for compaction: {}", err))?;
+        for row_result in rows {
+            candidates.push(
+                row_result
+                    .map_err(|err| format!("failed to read vault compaction row: {}", err))?,
+            );
+        }
+    }
+
+    let mut planned = Vec::new();
+    for (vault_key, vault_path, created_at) in candidates {
+        let from_path = path_from_ipc_string(&vault_path);
+        let Some(file_name) = from_path.file_name() else {
+            continue;
+        };
+        let to_path = vault_layout_directory(&root, strategy, created_at).join(file_name);
+        if to_path == from_path {
+            continue;
+        }
+        planned.push(VaultCompactionMove {
+            vault_key,
+            from_path: vault_path,
+            to_path: path_to_string(&to_path)?,
+        });
+    }
+
+    let mut report = VaultCompactionReport {
+        strategy: strategy.to_string(),
+        dry_run,
+        planned,
+        moved: 0,
+        failed: 0,
+        removed_directories: 0,
+    };
+    if dry_run {
+        return Ok(report);
+    }
+
+    write_app_setting(&connection, SETTING_VAULT_LAYOUT, strategy)?;
+    invalidate_vault_write_directory();
+    let total = report.planned.len() as u64;
+    reporter.report("move", 0, total, None);
+    for (index, planned_move) in report.planned.iter().enumerate() {
+        let outcome = move_vault_file_to_layout(
+            &mut connection,
+            &planned_move.vault_key,
+            &path_from_ipc_string(&planned_move.from_path),
+            &path_from_ipc_string(&planned_move.to_path),
+        );
+        match outcome {
+            Ok(()) => report.moved += 1,
+            Err(err) => {
+                log_eprintln!("[vault-compaction] {}", err);
+                report.failed += 1;
+            }
+        }
+        reporter.report(
+            "move",
+            index as u64 + 1,
+            total,
+            Some(&planned_move.vault_key),
+        );
+    }
+    report.removed_directories = remove_empty_vault_directories(&root);
+    Ok(report)
+}
+
+#[tauri::command]
+pub(crate) async fn compact_vault(
+    app: AppHandle,
+    strategy: String,
+    dry_run: Option<bool>,
+) -> Result<VaultCompactionReport, String> {
+    let strategy = normalize_vault_layout(&strategy)?;
+    let dry_run = dry_run.unwrap_or(false);
+    tauri::async_runtime::spawn_blocking(move || {
+        let _activity = MaintenanceActivityGuard::begin()?;
+        let state = app.state::<VaultMaintenanceState>();
+        if state.running.swap(true, Ordering::SeqCst) {
+            return Err("vault maintenance is already running".to_string());
+        }
+        let reporter = ProgressReporter::start(&app, "vault-compaction");
+        let outcome = compact_vault_internal(&reporter, &strategy, dry_run);
+        reporter.finish(&outcome.as_ref().map(|_| ()).map_err(Clone::clone));
+        state.running.store(false, Ordering::SeqCst);
+        if let Ok(report) = &outcome {
+            log_println!(
+                "[vault-compaction] strategy={} dry_run={} planned={} moved={} failed={}",
+                report.strategy,
+                report.dry_run,
+                report.planned.len(),
+                report.moved,
+                report.failed
+            );
+        }
+        outcome
+    })
+    .await
+    .map_err(|err| format!("vault compaction thread join failed: {}", err))?
+}
+
+pub(crate) fn encrypt_existing_vault_files(
+    connection: &Connection,
+    reporter: &ProgressReporter,
+    key: &[u8; 32],
+) -> Result<(), String> {
+    let mut stmt = connection
+        .prepare("SELECT vault_key, vault_path FROM vault_files WHERE is_encrypted = 0 ORDER BY vault_key")
+        .map_err(|err| format!("failed to prepare plaintext vault files query: {}", err))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|err| format!("failed to query plaintext vault files: {}", err))?;
+    let mut pending = Vec::new();
+    for row_result in rows {
+        pending.push(
+            row_result
+                .map_err(|err| format!("failed to read plaintext vault file row: {}", err))?,
+        );
+    }
+    drop(stmt);
+
+    let storage_root = ensure_storage_root_internal()?;
+    let total = pending.len() as u64;
+    for (index, (vault_key, vault_path)) in pending.into_iter().enumerate() {
+        reporter.report("encrypt", index as u64, total, Some(&vault_key));
+        let stored_path = PathBuf::from(&vault_path);
+        let plaintext_path = if stored_path.is_file() && !is_encrypted_vault_path(&stored_path) {
+            stored_path
+        } else {
+            match find_vault_files(&storage_root, &vault_key)?
+                .into_iter()
+                .find(|path| !is_encrypted_vault_path(path))
+            {
+                Some(path) => path,
+                None => {
+                    log_eprintln!(
+                        "[vault-encryption] skipping missing vault file {}",
+                        vault_key
+                    );
+                    continue;
+                }
+            }
+        };
+
+        let encrypted_path = encrypted_vault_path(&plaintext_path);
+        let input = File::open(extended_length_path(&plaintext_path))
+            .map_err(|err| format!("failed to open {}: {}", plaintext_path.display(), err))?;
+        encrypt_into_vault_file(key, &mut BufReader::new(input), &encrypted_path)?;
+        let encrypted_path_string = path_to_string(&encrypted_path)?;
+        let updated_at = Utc::now().timestamp_millis();
+        connection
+            .execute(
+                "UPDATE vault_files
+                 SET vault_path = ?1, is_encrypted = 1, disk_size_bytes = NULL, disk_mtime_ms = NULL, updated_at = ?2
+                 WHERE vault_key = ?3",
+                params![&encrypted_path_string, updated_at, &vault_key],
+            )
+            .map_err(|err| format!("failed to mark vault file as encrypted: {}", err))?;
+        connection
+            .execute(
+                "UPDATE items SET vault_path = ?1 WHERE vault_key = ?2",
+                params![&encrypted_path_string, &vault_key],
+            )
+            .map_err(|err| format!("failed to update item vault paths: {}", err))?;
+        connection
+            .execute(
+                "UPDATE item_file_versions SET vault_path = ?1 WHERE vault_key = ?2",
+                params![&encrypted_path_string, &vault_key],
+            )
+            .map_err(|err| format!("failed to update file version vault paths: {}", err))?;
+        if let Err(err) = fs::remove_file(extended_length_path(&plaintext_path)) {
+            log_eprintln!(
+                "[vault-encryption] failed to remove plaintext {}: {}",
+                plaintext_path.display(),
+                err
+            );
+        }
+        remove_plaintext_thumbnail(connection, &vault_key)?;
+    }
+    reporter.report("encrypt", total, total, None);
+    Ok(())
+}
+
+/// A thumbnail is a plaintext copy of the image, so it goes away once its file is encrypted;
+/// the UI falls back to the decrypted original.
+pub(crate) fn remove_plaintext_thumbnail(
+    connection: &Connection,
+    vault_key: &str,
+) -> Result<(), String> {
+    match remove_thumbnail_for_vault_key(vault_key) {
+        Ok(true) => {}
+        Ok(false) => log_eprintln!(
+            "[vault-encryption] thumbnail for {} is in use; it will be removed with the file",
+            vault_key
+        ),
+        Err(err) => log_eprintln!(
+            "[vault-encryption] failed to remove thumbnail for {}: {}",
+            vault_key,
+            err
+        ),
+    }
+    connection
+        .execute(
+            "UPDATE items SET thumb_status = 'skipped'
+             WHERE vault_key = ?1 AND thumb_status IN ('ready', 'pending')",
+            params![vault_key],
+        )
+        .map_err(|err| format!("failed to reset thumbnail status: {}", err))?;
+    Ok(())
+}
+
+pub(crate) fn count_plaintext_vault_files(connection: &Connection) -> Result<i64, String> {
+    connection
+        .query_row(
+            "SELECT COUNT(*) FROM vault_files WHERE is_encrypted = 0",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|err| format!("failed to count plaintext vault files: {}", err))
+}
+
+pub(crate) fn start_vault_encryption_migration(
+    app: &AppHandle,
+    key: [u8; 32],
+) -> Result<String, String> {
+    let state = app.state::<VaultMaintenanceState>();
+    if state.running.swap(true, Ordering::SeqCst) {
+        return Err("vault maintenance is already running".to_string());
+    }
+    let reporter = ProgressReporter::start(app, "vault-encryption");
+    let job_id = reporter.operation_id.clone();
+    let job_app = app.clone();
+    std::thread::spawn(move || {
+        let outcome = initialize_db()
+            .and_then(|_| open_db_connection())
+            .and_then(|connection| encrypt_existing_vault_files(&connection, &reporter, &key));
+        if let Err(err) = &outcome {
+            log_eprintln!("[vault-encryption] migration failed: {}", err);
+        }
+        reporter.finish(&outcome);
+        job_app
+            .state::<VaultMaintenanceState>()
+            .running
+            .store(false, Ordering::SeqCst);
+    });
+    Ok(job_id)
+}
+
+pub(crate) fn vault_encryption_status(
+    connection: &Connection,
+    migration_job_id: Option<String>,
+) -> Result<VaultEncryptionStatus, String> {
+    let enabled = load_vault_encryption_params(connection)?.is_some();
+    Ok(VaultEncryptionStatus {
+        enabled,
+        unlocked: current_vault_data_key().is_ok(),
+        pending_files: if enabled {
+            count_plaintext_vault_files(connection)?
+        } else {
+            0
+        },
+        migration_job_id,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn get_vault_encryption_status() -> Result<VaultEncryptionStatus, String> {
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    vault_encryption_status(&connection, None)
+}
+
+#[tauri::command]
+pub(crate) async fn enable_vault_encryption(
+    app: AppHandle,
+    passphrase: String,
+) -> Result<VaultEncryptionStatus, String> {
+    validate_vault_passphrase(&passphrase)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let data_key = create_vault_data_key(&passphrase)?;
+        set_vault_data_key(Some(data_key))?;
+        let job_id = start_vault_encryption_migration(&app, data_key)?;
+        vault_encryption_status(&open_db_connection()?, Some(job_id))
+    })
+    .await
+    .map_err(|err| format!("enable vault encryption thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) async fn unlock_library(
+    app: AppHandle,
+    passphrase: String,
+) -> Result<VaultEncryptionStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        initialize_db()?;
+        let connection = open_db_connection()?;
+        let data_key = unlock_vault_data_key(&connection, &passphrase)?;
+        set_vault_data_key(Some(data_key))?;
+        let job_id = if count_plaintext_vault_files(&connection)? > 0 {
+            match start_vault_encryption_migration(&app, data_key) {
+                Ok(job_id) => Some(job_id),
+                Err(err) => {
+                    log_eprintln!("[vault-encryption] could not resume migration: {}", err);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        vault_encryption_status(&connection, job_id)
+    })
+    .await
+    .map_err(|err| format!("unlock library thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn lock_library() -> Result<(), String> {
+    set_vault_data_key(None)
+}
+
+#[tauri::command]
+pub(crate) async fn change_passphrase(
+    old_passphrase: String,
+    new_passphrase: String,
+) -> Result<(), String> {
+    validate_vault_passphrase(&new_passphrase)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let data_key = rewrap_vault_data_key(&old_passphrase, &new_passphrase)?;
+        set_vault_data_key(Some(data_key))
+    })
+    .await
+    .map_err(|err| format!("change passphrase thread join failed: {}", err))?
+}
+
+pub(crate) fn vault_protocol_content_type(ext: &str) -> &'static str {
+    match normalize_ext(ext).as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+pub(crate) fn load_vault_protocol_bytes(request_path: &str) -> Result<(Vec<u8>, String), String> {
+    let vault_key = request_path.trim_matches('/');
+    let (_, ext) = parse_vault_key(vault_key)?;
+    initialize_db()?;
+    let connection = open_db_connection()?;
+    let vault_path = connection
+        .query_row(
+            "SELECT vault_path FROM vault_files WHERE vault_key = ?1",
+            params![vault_key],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|err| format!("failed to read vault file: {}", err))?
+        .ok_or_else(|| format!("vault file not found: {}", vault_key))?;
+    let _in_use = VaultKeyUseGuard::acquire(vault_key);
+    Ok((
+        read_vault_file_bytes(&path_from_ipc_string(&vault_path))?,
+        ext,
+    ))
+}
+
+pub(crate) fn vault_protocol_response(request_path: &str) -> tauri::http::Response<Vec<u8>> {
+    let (status, content_type, body) = match load_vault_protocol_bytes(request_path) {
+        Ok((bytes, ext)) => (200, vault_protocol_content_type(&ext), bytes),
+        Err(err) if err == VAULT_LOCKED_ERROR => (423, "text/plain", err.into_bytes()),
+        Err(err) => (404, "text/plain", err.into_bytes()),
+    };
+    tauri::http::Response::builder()
+        .status(status)
+        .header("Content-Type", content_type)
+        .header("Access-Control-Allow-Origin", "*")
+        .body(body)
+        .unwrap_or_else(|_| tauri::http::Response::new(Vec::new()))
+}
+
+#[tauri::command]
+pub(crate) fn compute_sha256(file_path: String) -> Result<String, String> {
+    let path = path_from_ipc_string(&file_path);
+    if !path.exists() {
+        return Err(format!("file does not exist: {}", path.display()));
+    }
+    if !path.is_file() {
+        return Err(format!("path is not a file: {}", path.display()));
+    }
+    sha256_for_file(&path)
+}
+
+#[tauri::command]
+pub(crate) async fn process_import_path_job(
+    original_path: String,
+    generate_thumb: Option<bool>,
+) -> Result<ImportPipelineResult, String> {
+    let path = path_from_ipc_string(&original_path);
+    if !path.exists() {
+        return Err(format!("file does not exist: {}", path.display()));
+    }
+    if !path.is_file() {
+        return Err(format!("path is not a file: {}", path.display()));
+    }
+    let original_filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "imported-file".to_string());
+    let thumb_policy = resolve_import_thumb_policy(generate_thumb)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        run_import_pipeline_internal(
+            Some(path),
+            None,
+            None,
+            Some(original_filename),
+            &thumb_policy,
+            None,
+        )
+    })
+    .await
+    .map_err(|err| format!("import path job thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) async fn process_import_bytes_job(
+    bytes: Vec<u8>,
+    original_filename: Option<String>,
+    ext: Option<String>,
+    generate_thumb: Option<bool>,
+) -> Result<ImportPipelineResult, String> {
+    if bytes.is_empty() {
+        return Err("cannot import empty byte buffer".to_string());
+    }
+    let thumb_policy = resolve_import_thumb_policy(generate_thumb)?;
+    let fallback_filename = original_filename.clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        run_import_pipeline_internal(
+            None,
+            Some(bytes),
+            ext,
+            fallback_filename,
+            &thumb_policy,
+            None,
+        )
+    })
+    .await
+    .map_err(|err| format!("import bytes job thread join failed: {}", err))?
+}
+
+#[tauri::command]
+pub(crate) fn import_to_vault(original_path: String) -> Result<VaultImportResult, String> {
+    let path = path_from_ipc_string(&original_path);
+    if !path.exists() {
+        return Err(format!("file does not exist: {}", path.display()));
+    }
+    if !path.is_file() {
+        return Err(format!("path is not a file: {}", path.display()));
+    }
+
+    let original_filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "imported-file".to_string());
+
+    import_with_metadata(Some(&path), None, None, Some(&original_filename))
+}
+
+#[tauri::command]
+pub(crate) fn import_bytes_to_vault(
+    bytes: Vec<u8>,
+    original_filename: Option<String>,
+    ext: Option<String>,
+) -> Result<VaultImportResult, String> {
+    if bytes.is_empty() {
+        return Err("cannot import empty byte buffer".to_string());
+    }
+
+    import_with_metadata(
+        None,
+        Some(&bytes),
+        ext.as_deref(),
+        original_filename.as_deref(),
+    )
+}
+
+#[tauri::command]
+pub(crate) fn remove_from_vault(sha256: String, ext: String) -> Result<bool, String> {
+    let root = ensure_storage_root_internal()?;
+    let vault_filename = build_vault_filename(&sha256, &ext);
+    initialize_db()?;
+    if load_protected_vault_keys(&open_db_connection()?)?.contains(&vault_filename) {
+        return Err(format!("vault file is protected: {}", vault_filename));
+    }
+    let existing_paths = find_vault_files(&root, &vault_filename)?;
+    if existing_paths.is_empty() {
+        Ok(false)
+    } else {
+        for path in existing_paths {
+            fs::remove_file(&path).map_err(|err| {
+                format!("failed to remove vault file {}: {}", path.display(), err)
+            })?;
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use proptest::prelude::*;
+
+    const IMAGE_FIXTURES: [(&str, &str); 7] = [
+        ("images/sample.png", "png"),
+        ("images/sample.jpg", "jpg"),
+        ("images/sample.gif", "gif"),
+        ("images/sample.webp", "webp"),
+        ("images/sample.ico", "ico"),
+        ("images/sample.bmp", "bmp"),
+        ("images/sample.svg", "svg"),
+    ];
+
+    #[test]
+    fn normalize_ext_table() {
+        let long = "a".repeat(VAULT_KEY_MAX_EXT_LEN);
+        let too_long = "a".repeat(VAULT_KEY_MAX_EXT_LEN + 1);
+        let cases: [(&str, &str); 15] = [
+            ("png", "png"),
+            ("PNG", "png"),
+            (".jpeg", "jpeg"),
+            ("  WebP  ", "webp"),
+            ("png.", "png"),
+            ("tar.gz", "gz"),
+            ("backup.TAR.GZ", "gz"),
+            ("jp-g", "jpg"),
+            ("héic", "hic"),
+            ("", "bin"),
+            (".", "bin"),
+            ("..", "bin"),
+            ("gz.--", "bin"),
+            (long.as_str(), long.as_str()),
+            (too_long.as_str(), "bin"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_ext(input), expected, "normalize_ext({:?})", input);
+        }
+    }
+
+    #[test]
+    fn extension_from_filename_uses_the_last_segment() {
+        assert_eq!(
+            extension_from_filename("Holiday.Photo.JPG").as_deref(),
+            Some("jpg")
+        );
+        assert_eq!(
+            extension_from_filename("archive.tar.gz").as_deref(),
+            Some("gz")
+        );
+        assert_eq!(extension_from_filename("README"), None);
+    }
+
+    proptest! {
+        #[test]
+        fn normalize_ext_output_is_a_valid_vault_extension(input in any::<String>()) {
+            let normalized = normalize_ext(&input);
+            prop_assert!(!normalized.is_empty());
+            prop_assert!(normalized.len() <= VAULT_KEY_MAX_EXT_LEN);
+            prop_assert!(normalized
+                .chars()
+                .all(|ch| ch.is_ascii_digit() || ch.is_ascii_lowercase()));
+        }
+
+        #[test]
+        fn normalize_ext_is_idempotent(input in any::<String>()) {
+            let normalized = normalize_ext(&input);
+            prop_assert_eq!(normalize_ext(&normalized), normalized);
+        }
+
+        #[test]
+        fn normalize_ext_only_reads_the_last_dot_segment(
+            prefix in "[a-zA-Z0-9.]{0,20}",
+            last in "[a-zA-Z0-9]{1,32}",
+        ) {
+            prop_assert_eq!(
+                normalize_ext(&format!("{}.{}", prefix, last)),
+                last.to_ascii_lowercase()
+            );
+        }
+
+        #[test]
+        fn built_vault_keys_parse_back(sha256 in "[0-9a-f]{64}", ext in any::<String>()) {
+            let vault_key = build_vault_filename(&sha256, &ext);
+            prop_assert_eq!(
+                parse_vault_key(&vault_key),
+                Ok((sha256.clone(), normalize_ext(&ext)))
+            );
+        }
+
+        #[test]
+        fn parsed_vault_keys_rebuild_unchanged(sha256 in "[0-9a-f]{64}", ext in "[a-z0-9]{1,32}") {
+            let vault_key = format!("{}.{}", sha256, ext);
+            let (parsed_sha256, parsed_ext) = parse_vault_key(&vault_key).unwrap();
+            prop_assert_eq!(build_vault_filename(&parsed_sha256, &parsed_ext), vault_key);
+        }
+
+        #[test]
+        fn vault_keys_with_a_malformed_hash_are_rejected(
+            sha256 in "[0-9a-f]{0,63}|[0-9a-f]{65,80}|[0-9a-f]{0,40}[A-Fg-z][0-9a-f]{0,23}",
+            ext in "[a-z0-9]{1,32}",
+        ) {
+            let vault_key = format!("{}.{}", sha256, ext);
+            prop_assert!(parse_vault_key(&vault_key).is_err());
+        }
+    }
+
+    #[test]
+    fn finalize_after_placeholder_delete_is_swept_at_startup() {
+        let library = TestLibrary::new();
+        let mut placeholder = note_input("img-1", "photo.png");
+        placeholder.item_type = "image".to_string();
+        placeholder.filename = Some("photo.png".to_string());
+        placeholder.import_status = "processing".to_string();
+        insert_item(placeholder).unwrap();
+
+        let imported = run_import_pipeline_internal(
+            Some(fixture_path("images/sample.png")),
+            None,
+            None,
+            Some("photo.png".to_string()),
+            IMPORT_THUMB_POLICY_NEVER,
+            None,
+        )
+        .unwrap();
+        let vault_key = build_vault_filename(&imported.sha256, &imported.ext);
+        assert!(Path::new(&imported.vault_path).is_file());
+
+        delete_items(vec!["img-1".to_string()]).unwrap();
+        let finalized = finalize_item_import(FinalizeItemImportInput {
+            item_id: "img-1".to_string(),
+            title: "photo.png".to_string(),
+            filename: "photo.png".to_string(),
+            vault_key: vault_key.clone(),
+            vault_path: imported.vault_path.clone(),
+            width: imported.width.map(i64::from),
+            height: imported.height.map(i64::from),
+            thumb_status: imported.thumb_status.clone(),
+        });
+        assert_eq!(
+            finalized.err().as_deref(),
+            Some("item not found while finalizing import")
+        );
+        let connection = library.connection();
+        let ref_count: i64 = connection
+            .query_row(
+                "SELECT ref_count FROM vault_files WHERE vault_key = ?1",
+                params![&vault_key],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(ref_count, 0);
+
+        // Within the grace period the startup sweep leaves the copy alone.
+        let sweep = ProgressReporter::silent("vault-cleanup");
+        cleanup_zero_ref_vault_files_reporting(&connection, &sweep).unwrap();
+        assert!(Path::new(&imported.vault_path).is_file());
+
+        connection
+            .execute(
+                "UPDATE vault_files SET updated_at = updated_at - ?1",
+                params![VAULT_ZERO_REF_GRACE_MS + 1],
+            )
+            .unwrap();
+        cleanup_zero_ref_vault_files_reporting(&connection, &sweep).unwrap();
+        assert_eq!(library.count("SELECT COUNT(*) FROM vault_files"), 0);
+        assert!(!Path::new(&imported.vault_path).exists());
+        assert!(find_vault_files(&library.storage_root(), &vault_key)
+            .unwrap()
+            .is_empty());
+    }
+
+    fn import_fixture_bytes(name: &str) -> ImportPipelineResult {
+        run_import_pipeline_internal(
+            None,
+            Some(fixture_bytes(name)),
+            None,
+            Some(name.rsplit('/').next().unwrap().to_string()),
+            IMPORT_THUMB_POLICY_NEVER,
+            None,
+        )
+        .unwrap()
+    }
+
+    fn vault_row(
+        library: &TestLibrary,
+        imported: &ImportPipelineResult,
+    ) -> Option<(i64, i64, i64)> {
+        library
+            .connection()
+            .query_row(
+                "SELECT ref_count, size_bytes, updated_at FROM vault_files WHERE vault_key = ?1",
+                params![build_vault_filename(&imported.sha256, &imported.ext)],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .unwrap()
+    }
+
+    fn age_vault_rows(library: &TestLibrary, by_ms: i64) {
+        library
+            .connection()
+            .execute(
+                "UPDATE vault_files SET updated_at = updated_at - ?1",
+                params![by_ms],
+            )
+            .unwrap();
+    }
+
+    fn sweep_zero_ref_vault_files(library: &TestLibrary) {
+        cleanup_zero_ref_vault_files_reporting(
+            &library.connection(),
+            &ProgressReporter::silent("vault-cleanup"),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn imports_register_a_zero_ref_vault_row_immediately() {
+        let library = TestLibrary::new();
+        let started_at = Utc::now().timestamp_millis();
+
+        let imported = import_fixture_bytes("images/sample.jpg");
+
+        let (ref_count, size_bytes, updated_at) = vault_row(&library, &imported).unwrap();
+        assert_eq!(ref_count, 0);
+        assert_eq!(size_bytes, fixture_bytes("images/sample.jpg").len() as i64);
+        assert!(updated_at >= started_at);
+        assert!(Path::new(&imported.vault_path).is_file());
+        assert_eq!(library.count("SELECT COUNT(*) FROM items"), 0);
+    }
+
+    #[test]
+    fn abandoned_imports_are_reclaimed_only_after_the_grace_period() {
+        let library = TestLibrary::new();
+        let abandoned = import_fixture_bytes("images/sample.gif");
+        let finalized = import_fixture_bytes("images/sample.png");
+        insert_imported_file_item(&finalized, None, None, None).unwrap();
+
+        age_vault_rows(&library, VAULT_ZERO_REF_GRACE_MS - 60_000);
+        sweep_zero_ref_vault_files(&library);
+        assert!(vault_row(&library, &abandoned).is_some());
+        assert!(Path::new(&abandoned.vault_path).is_file());
+
+        age_vault_rows(&library, 60_001);
+        sweep_zero_ref_vault_files(&library);
+        assert!(vault_row(&library, &abandoned).is_none());
+        assert!(!Path::new(&abandoned.vault_path).exists());
+        assert_eq!(vault_row(&library, &finalized).unwrap().0, 1);
+        assert!(Path::new(&finalized.vault_path).is_file());
+    }
+
+    #[test]
+    fn reimporting_an_abandoned_file_restarts_its_grace_period() {
+        let library = TestLibrary::new();
+        let first = import_fixture_bytes("images/sample.bmp");
+        age_vault_rows(&library, VAULT_ZERO_REF_GRACE_MS + 1);
+
+        let second = import_fixture_bytes("images/sample.bmp");
+        sweep_zero_ref_vault_files(&library);
+
+        assert_eq!(first.vault_path, second.vault_path);
+        assert_eq!(vault_row(&library, &second).unwrap().0, 0);
+        assert!(Path::new(&second.vault_path).is_file());
+    }
+
+    fn utc_millis(year: i32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> i64 {
+        chrono::NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|date| date.and_hms_milli_opt(hour, minute, second, 999))
+            .unwrap()
+            .and_utc()
+            .timestamp_millis()
+    }
+
+    fn cached_write_directory() -> Option<PathBuf> {
+        VAULT_WRITE_DIRECTORY
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|cached| cached.directory.clone())
+    }
+
+    #[test]
+    fn vault_write_directory_rolls_over_at_month_end() {
+        let library = TestLibrary::new();
+        let root = library.storage_root();
+        let january_end = utc_millis(2026, 1, 31, 23, 59, 59);
+        let february_start = january_end + 1;
+
+        let january = ensure_vault_write_directory_at(&root, january_end).unwrap();
+        assert_eq!(january, root.join("2026").join("01"));
+        assert!(january.is_dir());
+        assert_eq!(cached_write_directory(), Some(january.clone()));
+        assert_eq!(
+            cached_vault_write_directory(&root, utc_millis(2026, 1, 2, 0, 0, 0)),
+            Some(january.clone())
+        );
+        assert_eq!(cached_vault_write_directory(&root, february_start), None);
+
+        let february = ensure_vault_write_directory_at(&root, february_start).unwrap();
+        assert_eq!(february, root.join("2026").join("02"));
+        assert!(february.is_dir());
+        assert_eq!(cached_write_directory(), Some(february));
+        assert_eq!(cached_vault_write_directory(&root, january_end), None);
+    }
+
+    #[test]
+    fn vault_write_directory_cache_follows_layout_and_missing_folders() {
+        let library = TestLibrary::new();
+        let root = library.storage_root();
+        let year_end = utc_millis(2026, 12, 31, 23, 59, 59);
+
+        let december = ensure_vault_write_directory_at(&root, year_end).unwrap();
+        fs::remove_dir(&december).unwrap();
+        assert_eq!(cached_vault_write_directory(&root, year_end), None);
+        assert_eq!(
+            ensure_vault_write_directory_at(&root, year_end).unwrap(),
+            december
+        );
+
+        write_app_setting(
+            &library.connection(),
+            SETTING_VAULT_LAYOUT,
+            VAULT_LAYOUT_BY_YEAR,
+        )
+        .unwrap();
+        invalidate_vault_write_directory();
+        assert_eq!(
+            ensure_vault_write_directory_at(&root, year_end).unwrap(),
+            root.join("2026")
+        );
+        assert_eq!(
+            ensure_vault_write_directory_at(&root, year_end + 1).unwrap(),
+            root.join("2027")
+        );
+        assert_eq!(
+            cached_vault_write_directory(&root.join("elsewhere"), year_end + 1),
+            None
+        );
+    }
+
+    fn write_wide_png(dir: &Path) -> PathBuf {
+        let path = dir.join("wide.png");
+        image::RgbaImage::from_pixel(
+            IMPORT_THUMB_MAX_SIZE * 2,
+            20,
+            image::Rgba([9, 99, 199, 255]),
+        )
+        .save(&path)
+        .unwrap();
+        path
+    }
+
+    fn thumb_file_count() -> usize {
+        match fs::read_dir(thumbs_root_path().unwrap()) {
+            Ok(entries) => entries.count(),
+            Err(_) => 0,
+        }
+    }
+
+    fn import_wide_png(source: &Path) -> ImportPipelineResult {
+        run_import_pipeline_internal(
+            Some(source.to_path_buf()),
+            None,
+            None,
+            Some("wide.png".to_string()),
+            IMPORT_THUMB_POLICY_IMMEDIATE,
+            None,
+        )
+        .unwrap()
+    }
+
+    fn enable_test_encryption(library: &TestLibrary) -> [u8; 32] {
+        let key = [7_u8; 32];
+        let mut connection = library.connection();
+        let transaction = connection.transaction().unwrap();
+        store_vault_data_key(&transaction, "correct horse battery", &key).unwrap();
+        transaction.commit().unwrap();
+        set_vault_data_key(Some(key)).unwrap();
+        key
+    }
+
+    #[test]
+    fn encrypted_imports_write_no_plaintext_thumbnail() {
+        let library = TestLibrary::new();
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = write_wide_png(source_dir.path());
+        enable_test_encryption(&library);
+
+        let imported = import_wide_png(&source);
+        assert!(is_encrypted_vault_path(Path::new(&imported.vault_path)));
+        assert_eq!(imported.thumb_status, "skipped");
+        assert!(imported.thumb_path.is_none());
+        assert_eq!(imported.width, Some(IMPORT_THUMB_MAX_SIZE * 2));
+        assert_eq!(thumb_file_count(), 0);
+
+        let vault_key = build_vault_filename(&imported.sha256, &imported.ext);
+        let (_, _, thumb_status) =
+            refresh_vault_file_media(&vault_key, Path::new(&imported.vault_path));
+        assert_eq!(thumb_status, "skipped");
+        assert_eq!(thumb_file_count(), 0);
+
+        let output_dir = tempfile::tempdir().unwrap();
+        assert!(generate_thumbnail_internal(
+            Path::new(&imported.vault_path),
+            &output_dir.path().join("thumb.webp"),
+            IMPORT_THUMB_MAX_SIZE
+        )
+        .is_err());
+        assert!(!output_dir.path().join("thumb.webp").exists());
+    }
+
+    #[test]
+    fn encrypting_existing_files_removes_their_thumbnails() {
+        let library = TestLibrary::new();
+        let source_dir = tempfile::tempdir().unwrap();
+        let imported = import_wide_png(&write_wide_png(source_dir.path()));
+        assert_eq!(imported.thumb_status, "ready");
+        assert_eq!(thumb_file_count(), 1);
+
+        let vault_key = build_vault_filename(&imported.sha256, &imported.ext);
+        let mut input = note_input("img-1", "wide");
+        input.item_type = "image".to_string();
+        input.filename = Some("wide.png".to_string());
+        input.vault_key = Some(vault_key.clone());
+        input.vault_path = Some(imported.vault_path.clone());
+        input.thumb_status = "ready".to_string();
+        insert_item(input).unwrap();
+
+        let key = enable_test_encryption(&library);
+        let connection = library.connection();
+        encrypt_existing_vault_files(
+            &connection,
+            &ProgressReporter::silent("vault-encryption"),
+            &key,
+        )
+        .unwrap();
+
+        assert_eq!(thumb_file_count(), 0);
+        let detail = get_item("img-1".to_string()).unwrap();
+        assert_eq!(detail.item.thumb_status, "skipped");
+        assert!(is_encrypted_vault_path(Path::new(&detail.item.vault_path)));
+    }
+
+    #[test]
+    fn read_vault_file_head_stops_after_the_first_chunk() {
+        let _library = TestLibrary::new();
+        let key = [3_u8; 32];
+        set_vault_data_key(Some(key)).unwrap();
+        let plaintext: Vec<u8> = (0..VAULT_ENCRYPTION_CHUNK_BYTES * 3)
+            .map(|index| (index % 251) as u8)
+            .collect();
+        let dir = tempfile::tempdir().unwrap();
+        let path = encrypted_vault_path(&dir.path().join(format!("{}.bin", "a".repeat(64))));
+        encrypt_into_vault_file(&key, &mut Cursor::new(plaintext.clone()), &path).unwrap();
+        assert_eq!(
+            read_vault_file_head(&path, 4096).unwrap(),
+            plaintext[..4096]
+        );
+
+        // Damage the last chunk: a full decrypt now fails, but the head never reaches it.
+        let mut sealed = fs::read(&path).unwrap();
+        let last = sealed.len() - 1;
+        sealed[last] ^= 0xFF;
+        fs::write(&path, &sealed).unwrap();
+        assert!(decrypt_vault_file_to_vec(&path).is_err());
+        assert_eq!(
+            read_vault_file_head(&path, 4096).unwrap(),
+            plaintext[..4096]
+        );
+        assert_eq!(
+            read_vault_file_head(&path, VAULT_ENCRYPTION_CHUNK_BYTES + 10).unwrap(),
+            plaintext[..VAULT_ENCRYPTION_CHUNK_BYTES + 10]
+        );
+    }
+
+    fn write_test_archive(dir: &Path, entries: &[(&str, &[u8])]) -> PathBuf {
+        let path = dir.join("entries.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        for (name, bytes) in entries {
+            writer
+                .start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(bytes).unwrap();
+        }
+        writer.finish().unwrap();
+        path
+    }
+
+    fn unlimited_import_limits() -> ImportLimits {
+        ImportLimits {
+            max_file_bytes: 0,
+            allowlist: Vec::new(),
+            denylist: Vec::new(),
+        }
+    }
+
+    fn spool_file_count(dir: &Path) -> usize {
+        fs::read_dir(dir)
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .ends_with(".partial")
+            })
+            .count()
+    }
+
+    #[test]
+    fn small_archive_entries_stay_in_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = write_test_archive(dir.path(), &[("small.png", &[7_u8; 100])]);
+        let mut archive = open_zip_archive(&archive_path).unwrap();
+
+        let (filename, ext, payload) = read_archive_entry(
+            &mut archive,
+            "small.png",
+            &unlimited_import_limits(),
+            dir.path(),
+            1024,
+        )
+        .unwrap();
+
+        assert_eq!((filename.as_str(), ext.as_str()), ("small.png", "png"));
+        match payload {
+            ArchiveEntryPayload::Bytes(bytes) => assert_eq!(bytes, vec![7_u8; 100]),
+            ArchiveEntryPayload::Spooled(_) => panic!("small entry was spooled"),
+        }
+        assert_eq!(spool_file_count(dir.path()), 0);
+    }
+
+    #[test]
+    fn oversized_archive_entries_are_spooled_and_cleaned_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents: Vec<u8> = (0..5000_u32).map(|value| (value % 251) as u8).collect();
+        let archive_path = write_test_archive(dir.path(), &[("large.bin", &contents)]);
+        let mut archive = open_zip_archive(&archive_path).unwrap();
+
+        let (_, _, payload) = read_archive_entry(
+            &mut archive,
+            "large.bin",
+            &unlimited_import_limits(),
+            dir.path(),
+            1024,
+        )
+        .unwrap();
+
+        let (source_path, source_bytes, spooled) = payload.into_pipeline_source();
+        assert!(source_bytes.is_none());
+        assert_eq!(fs::read(source_path.unwrap()).unwrap(), contents);
+        assert_eq!(spool_file_count(dir.path()), 1);
+        drop(spooled);
+        assert_eq!(spool_file_count(dir.path()), 0);
+    }
+
+    #[test]
+    fn stale_archive_spool_files_are_swept_from_nested_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let month = dir.path().join("2024").join("05");
+        let too_deep = month.join("nested");
+        fs::create_dir_all(&too_deep).unwrap();
+        for directory in [dir.path(), month.as_path(), too_deep.as_path()] {
+            fs::write(directory.join(".archive-stale.partial"), b"spooled").unwrap();
+        }
+        fs::write(month.join("download.partial"), b"other").unwrap();
+        fs::write(month.join(".archive-notes.txt"), b"other").unwrap();
+
+        let before = SystemTime::now() - ARCHIVE_SPOOL_STALE_AFTER;
+        assert_eq!(sweep_stale_archive_spool_files(dir.path(), 2, before), 0);
+        assert_eq!(spool_file_count(&month), 2);
+
+        let after = SystemTime::now() + Duration::from_secs(1);
+        assert_eq!(sweep_stale_archive_spool_files(dir.path(), 2, after), 2);
+        assert_eq!(spool_file_count(dir.path()), 0);
+        assert_eq!(spool_file_count(&month), 1);
+        assert!(month.join(".archive-notes.txt").exists());
+        assert_eq!(spool_file_count(&too_deep), 1);
+    }
+
+    #[test]
+    fn entries_over_the_size_limit_leave_no_spool_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = write_test_archive(dir.path(), &[("large.bin", &[1_u8; 5000])]);
+        let mut archive = open_zip_archive(&archive_path).unwrap();
+        let limits = ImportLimits {
+            max_file_bytes: 4000,
+            ..unlimited_import_limits()
+        };
+
+        let error = match read_archive_entry(&mut archive, "large.bin", &limits, dir.path(), 1024) {
+            Ok(_) => panic!("oversized entry was accepted"),
+            Err(error) => error,
+        };
+
+        assert!(error.starts_with("validation:"), "{}", error);
+        assert_eq!(spool_file_count(dir.path()), 0);
+    }
+
+    #[test]
+    fn sniff_extension_recognizes_fixture_images() {
+        for (fixture, expected) in IMAGE_FIXTURES {
+            let bytes = fixture_bytes(fixture);
+            assert_eq!(sniff_extension(&bytes), Some(expected), "{}", fixture);
+            let head = &bytes[..bytes.len().min(64)];
+            assert_eq!(sniff_extension(head), Some(expected), "head of {}", fixture);
+        }
+    }
+
+    #[test]
+    fn sniff_extension_rejects_truncated_and_unknown_bytes() {
+        assert_eq!(sniff_extension(b""), None);
+        assert_eq!(sniff_extension(b"RIFF\0\0\0\0WAVE"), None);
+        assert_eq!(sniff_extension(b"BM"), None);
+        assert_eq!(sniff_extension(b"plain text notes"), None);
+    }
+
+    #[test]
+    fn resolve_sniffed_extension_prefers_content_over_wrong_claims() {
+        let png = sniff_extension(&fixture_bytes("images/sample.png"));
+        assert_eq!(resolve_sniffed_extension("jpg", png), "png");
+        assert_eq!(resolve_sniffed_extension("bin", png), "png");
+        assert_eq!(resolve_sniffed_extension("png", png), "png");
+        assert_eq!(resolve_sniffed_extension("psd", png), "psd");
+        let jpg = sniff_extension(&fixture_bytes("images/sample.jpg"));
+        assert_eq!(resolve_sniffed_extension("jpeg", jpg), "jpeg");
+        assert_eq!(resolve_sniffed_extension("txt", None), "txt");
+    }
+
+    #[test]
+    fn changing_the_passphrase_rewraps_the_same_data_key() {
+        let library = TestLibrary::new();
+        let data_key = create_vault_data_key("first passphrase").unwrap();
+        assert_eq!(
+            create_vault_data_key("second passphrase").err().as_deref(),
+            Some("vault encryption is already enabled")
+        );
+
+        assert_eq!(
+            rewrap_vault_data_key("wrong passphrase", "new passphrase")
+                .err()
+                .as_deref(),
+            Some("incorrect vault passphrase")
+        );
+        let connection = library.connection();
+        assert_eq!(
+            unlock_vault_data_key(&connection, "first passphrase").unwrap(),
+            data_key
+        );
+
+        assert_eq!(
+            rewrap_vault_data_key("first passphrase", "new passphrase").unwrap(),
+            data_key
+        );
+        assert_eq!(
+            unlock_vault_data_key(&connection, "new passphrase").unwrap(),
+            data_key
+        );
+        assert_eq!(
+            unlock_vault_data_key(&connection, "first passphrase")
+                .err()
+                .as_deref(),
+            Some("incorrect vault passphrase")
+        );
+    }
+
+    fn replace_item_file(item_id: &str, imported: &ImportPipelineResult) {
+        finalize_item_import(FinalizeItemImportInput {
+            item_id: item_id.to_string(),
+            title: imported.original_filename.clone(),
+            filename: imported.original_filename.clone(),
+            vault_key: build_vault_filename(&imported.sha256, &imported.ext),
+            vault_path: imported.vault_path.clone(),
+            width: None,
+            height: None,
+            thumb_status: "skipped".to_string(),
+        })
+        .unwrap();
+    }
+
+    fn version_keys(item_id: &str) -> Vec<String> {
+        list_item_file_versions(item_id.to_string())
+            .unwrap()
+            .into_iter()
+            .map(|version| version.vault_key)
+            .collect()
+    }
+
+    fn ref_count(library: &TestLibrary, imported: &ImportPipelineResult) -> Option<i64> {
+        vault_row(library, imported).map(|(ref_count, _, _)| ref_count)
+    }
+
+    #[test]
+    fn restoring_a_file_version_swaps_keys_without_losing_refs() {
+        let library = TestLibrary::new();
+        let original = import_fixture_bytes("images/sample.png");
+        let replacement = import_fixture_bytes("images/sample.jpg");
+        let original_key = build_vault_filename(&original.sha256, &original.ext);
+        let replacement_key = build_vault_filename(&replacement.sha256, &replacement.ext);
+        let item_id = insert_imported_file_item(&original, None, None, None)
+            .unwrap()
+            .item_id;
+
+        replace_item_file(&item_id, &replacement);
+        assert_eq!(ref_count(&library, &original), Some(1));
+        assert_eq!(ref_count(&library, &replacement), Some(1));
+        let versions = list_item_file_versions(item_id.clone()).unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].vault_key, original_key);
+        assert!(versions[0].file_exists);
+
+        let restored = tauri::async_runtime::block_on(restore_item_file_version(
+            item_id.clone(),
+            original_key.clone(),
+        ))
+        .unwrap();
+        assert_eq!(restored.vault_key, original_key);
+        assert_eq!(version_keys(&item_id), vec![replacement_key.clone()]);
+        assert_eq!(ref_count(&library, &original), Some(1));
+        assert_eq!(ref_count(&library, &replacement), Some(1));
+
+        age_vault_rows(&library, VAULT_ZERO_REF_GRACE_MS + 1);
+        sweep_zero_ref_vault_files(&library);
+        assert!(Path::new(&original.vault_path).is_file());
+        assert!(Path::new(&replacement.vault_path).is_file());
+
+        assert_eq!(
+            tauri::async_runtime::block_on(restore_item_file_version(
+                item_id.clone(),
+                original_key,
+            ))
+            .err()
+            .as_deref(),
+            Some("item already uses this file version")
+        );
+        assert_eq!(version_keys(&item_id), vec![replacement_key]);
+    }
+
+    #[test]
+    fn pruning_file_versions_only_frees_unreferenced_files() {
+        let library = TestLibrary::new();
+        let files: Vec<ImportPipelineResult> = ["png", "jpg", "gif", "webp", "bmp"]
+            .into_iter()
+            .map(|ext| import_fixture_bytes(&format!("images/sample.{}", ext)))
+            .collect();
+        let key =
+            |imported: &ImportPipelineResult| build_vault_filename(&imported.sha256, &imported.ext);
+        let item_id = insert_imported_file_item(&files[0], None, None, None)
+            .unwrap()
+            .item_id;
+        insert_imported_file_item(&files[1], None, None, None).unwrap();
+        for replacement in &files[1..] {
+            replace_item_file(&item_id, replacement);
+        }
+
+        // Three versions are kept by default, so the original png is pruned.
+        assert_eq!(
+            version_keys(&item_id),
+            vec![key(&files[3]), key(&files[2]), key(&files[1])]
+        );
+        assert_eq!(ref_count(&library, &files[0]), Some(0));
+        assert_eq!(ref_count(&library, &files[1]), Some(2));
+
+        assert_eq!(set_file_versions_to_keep(1).unwrap(), 2);
+        assert_eq!(version_keys(&item_id), vec![key(&files[3])]);
+        assert_eq!(ref_count(&library, &files[1]), Some(1));
+        assert_eq!(ref_count(&library, &files[2]), Some(0));
+
+        age_vault_rows(&library, VAULT_ZERO_REF_GRACE_MS + 1);
+        sweep_zero_ref_vault_files(&library);
+        for (index, imported) in files.iter().enumerate() {
+            let kept = !matches!(index, 0 | 2);
+            assert_eq!(
+                Path::new(&imported.vault_path).is_file(),
+                kept,
+                "{}",
+                imported.ext
+            );
+        }
+    }
+}
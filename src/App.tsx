@@ -24,6 +24,7 @@ import {
   type DbCollectionItemRecord,
   type DbInsertItemInput,
   type DbItemRecord,
+  type DbItemSource,
   type Tag,
 } from "./lib/db";
 import {
@@ -81,7 +82,7 @@ import {
 } from "./lib/collections";
 import {
   createDefaultAdvancedItemFilters,
-  filterItems,
+  filterItemsWithOverrideMatches,
   formatAdvancedItemFilterTypeLabel,
   hasActiveAdvancedItemFilters,
   normalizeAdvancedItemFilters,
@@ -666,7 +667,12 @@ function filenameFromPath(filePath: string): string {
 }
 
 function previewUrlFromVaultPath(vaultPath: string): string {
-  return convertFileSrc(normalizeAssetPath(vaultPath));
+  const normalized = normalizeAssetPath(vaultPath);
+  if (/\.[^./]+\.enc$/i.test(normalized)) {
+    const vaultKey = filenameFromPath(normalized).replace(/\.enc$/i, "");
+    return convertFileSrc(vaultKey, "stumble-vault");
+  }
+  return convertFileSrc(normalized);
 }
 
 function formatSize(sizeBytes: number): string {
@@ -705,7 +711,13 @@ function readPersistedPanelWidth(args: {
 }
 
 function isTopbarSortOption(value: unknown): value is TopbarSortOption {
-  return value === "newest" || value === "oldest" || value === "name-asc" || value === "rating-desc";
+  return (
+    value === "newest" ||
+    value === "oldest" ||
+    value === "name-asc" ||
+    value === "name-natural" ||
+    value === "rating-desc"
+  );
 }
 
 function readPersistedListViewControls(): PersistedListViewControls {
@@ -1113,6 +1125,7 @@ type ImportSource =
       filename: string;
       type: ItemType;
       ext?: string;
+      source?: DbItemSource;
     };
 
 function previewUrlFromImportSource(source: ImportSource): string | undefined {
@@ -1136,20 +1149,38 @@ function previewUrlFromImportSource(source: ImportSource): string | undefined {
   return URL.createObjectURL(new Blob([source.bytes], { type: mimeType }));
 }
 
+function importSourceOriginalPath(source: ImportSource | undefined): string | null {
+  if (!source) return null;
+  if (source.kind === "path") return source.originalPath;
+  if (source.kind === "file") {
+    const maybePath = (source.file as File & { path?: string }).path;
+    if (typeof maybePath === "string" && maybePath.trim().length > 0) {
+      return maybePath;
+    }
+  }
+  return null;
+}
+
+function importSourceProvenance(source: ImportSource | undefined): DbItemSource | null {
+  return source?.kind === "bytes" ? (source.source ?? null) : null;
+}
+
 function toDbInsertItem(args: {
   item: Item;
   createdAtMs: number;
   updatedAtMs: number;
+  originalSourcePath?: string | null;
+  source?: DbItemSource | null;
 }): DbInsertItemInput {
-  const { item, createdAtMs, updatedAtMs } = args;
+  const { item, createdAtMs, updatedAtMs, originalSourcePath, source } = args;
   return {
     id: item.id,
     collectionId: item.collectionId,
     type: item.type,
     title: item.title,
     filename: item.filename,
-    vaultKey: item.vaultKey ?? "",
-    vaultPath: item.vaultPath ?? "",
+    vaultKey: item.vaultKey ?? null,
+    vaultPath: item.vaultPath ?? null,
     previewUrl: null,
     width: item.width ?? null,
     height: item.height ?? null,
@@ -1164,6 +1195,8 @@ function toDbInsertItem(args: {
     createdAt: createdAtMs,
     updatedAt: updatedAtMs,
     tags: item.tags,
+    originalSourcePath: originalSourcePath ?? null,
+    source: source ?? null,
   };
 }
 
@@ -3586,7 +3619,8 @@ function App() {
   const markImportAsError = useCallback((itemId: string, error: unknown) => {
     console.error("Import job failed:", itemId, error);
     releaseTransientPreviewUrl(itemId);
-    void markItemImportErrorInDb(itemId).catch((dbError) => {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    void markItemImportErrorInDb(itemId, errorMessage).catch((dbError) => {
       console.error("Failed to persist import error state:", itemId, dbError);
     });
     setItems((currentItems) =>
@@ -3679,6 +3713,9 @@ function App() {
         copyMs: imported.metrics.copyMs,
         metadataMs: imported.metrics.metadataMs,
         thumbMs: imported.metrics.thumbMs,
+        decodeMs: imported.metrics.decodeMs,
+        resizeMs: imported.metrics.resizeMs,
+        encodeMs: imported.metrics.encodeMs,
         totalMs: imported.metrics.totalMs,
         deduped: imported.metrics.deduped,
         thumbStatus: imported.thumbStatus,
@@ -3769,6 +3806,8 @@ function App() {
               item,
               createdAtMs: baseTimestamp + index,
               updatedAtMs: baseTimestamp + index,
+              originalSourcePath: importSourceOriginalPath(sources[index]),
+              source: importSourceProvenance(sources[index]),
             }),
           ),
         );
@@ -4221,6 +4260,7 @@ function App() {
                 filename: `clipboard-${Date.now()}.png`,
                 type: "image",
                 ext: "png",
+                source: "clipboard",
               },
             ]);
             return;
@@ -4279,8 +4319,8 @@ function App() {
     return viewScopedItems.filter((item) => item.tagIds.includes(selectedTagId));
   }, [viewScopedItems, selectedTagId]);
 
-  const filteredItems = useMemo(() => {
-    return filterItems({
+  const filteredItemsResult = useMemo(() => {
+    return filterItemsWithOverrideMatches({
       items: tagFilteredItems,
       searchQuery: deferredSearchQuery,
       filters: advancedFilters,
@@ -4288,6 +4328,29 @@ function App() {
       skipSort: canUseManualGridReorder,
     });
   }, [advancedFilters, canUseManualGridReorder, deferredSearchQuery, sortOption, tagFilteredItems]);
+  const filteredItems = filteredItemsResult.items;
+
+  const overrideMatchLabelsByItemId = useMemo(() => {
+    const labels = new Map<string, { collectionId: string; label: string }>();
+    filteredItemsResult.overrideMatchesByItemId.forEach((collectionIds, itemId) => {
+      const collectionId =
+        collectionIds.find((id) => id !== selectedCollectionId && collectionPathById.has(id)) ?? null;
+      if (collectionId === null) {
+        return;
+      }
+      labels.set(itemId, { collectionId, label: collectionPathById.get(collectionId) ?? "" });
+    });
+    return labels;
+  }, [collectionPathById, filteredItemsResult, selectedCollectionId]);
+
+  const handleOpenOverrideMatch = useCallback(
+    (collectionId: string, itemId: string) => {
+      handleSidebarSelectCollection(collectionId);
+      setSelectedIds([itemId]);
+      setSelectionAnchorId(itemId);
+    },
+    [handleSidebarSelectCollection],
+  );
 
   const selectedItem =
     selectedIds.length === 1
@@ -5547,6 +5610,7 @@ function App() {
             />
             <ItemGrid
               items={filteredItems}
+              overrideMatchLabelsByItemId={overrideMatchLabelsByItemId}
               selectedIds={selectedIds}
               tileSize={tileSize}
               reorderDropTargetItemId={gridReorderDropState?.itemId ?? null}
@@ -5559,6 +5623,7 @@ function App() {
               onImageThumbnailMissing={handleImageThumbnailMissing}
               onDropFiles={handleDropFiles}
               onDropTagOnItem={addTagToItem}
+              onOpenOverrideMatch={handleOpenOverrideMatch}
             />
             </>
           )}
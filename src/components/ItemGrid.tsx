@@ -9,6 +9,7 @@ const SIDEBAR_TAG_DRAG_MIME = "application/x-stumble-tag";
 
 type ItemGridProps = {
   items: Item[];
+  overrideMatchLabelsByItemId?: Map<string, { collectionId: string; label: string }>;
   selectedIds: string[];
   tileSize: number;
   reorderDropTargetItemId?: string | null;
@@ -21,10 +22,12 @@ type ItemGridProps = {
   onImageThumbnailMissing: (item: Item) => void;
   onDropFiles: (files: FileList) => void | Promise<void>;
   onDropTagOnItem?: (itemId: string, tagId: string) => void | Promise<void>;
+  onOpenOverrideMatch?: (collectionId: string, itemId: string) => void;
 };
 
 function ItemGrid({
   items,
+  overrideMatchLabelsByItemId,
   selectedIds,
   tileSize,
   reorderDropTargetItemId = null,
@@ -37,6 +40,7 @@ function ItemGrid({
   onImageThumbnailMissing,
   onDropFiles,
   onDropTagOnItem,
+  onOpenOverrideMatch,
 }: ItemGridProps) {
   const [isDragOver, setIsDragOver] = useState(false);
   const [tagDropTargetItemId, setTagDropTargetItemId] = useState<string | null>(null);
@@ -148,6 +152,7 @@ function ItemGrid({
       >
         {items.map((item) => {
           const isImporting = item.importStatus === "processing";
+          const overrideMatch = overrideMatchLabelsByItemId?.get(item.id) ?? null;
           const isBookmarkMetaPending =
             item.type === "bookmark" && item.importStatus === "ready" && item.metaStatus === "pending";
           const isThumbPending =
@@ -313,6 +318,20 @@ function ItemGrid({
                       : "unknown"
                     : ""}
                 </p>
+                {overrideMatch && (
+                  <span
+                    className="item-override-match"
+                    role="link"
+                    title={`Open in ${overrideMatch.label}`}
+                    onPointerDown={(event) => event.stopPropagation()}
+                    onClick={(event) => {
+                      event.stopPropagation();
+                      onOpenOverrideMatch?.(overrideMatch.collectionId, item.id);
+                    }}
+                  >
+                    Matched in {overrideMatch.label}
+                  </span>
+                )}
               </div>
             </button>
           );
@@ -242,6 +242,7 @@ function Topbar({
             <option value="newest">Newest</option>
             <option value="oldest">Oldest</option>
             <option value="name-asc">Name A-Z</option>
+            <option value="name-natural">Name (natural)</option>
             <option value="rating-desc">Rating high-low</option>
           </select>
         </label>
@@ -6,8 +6,95 @@ export type BookmarkMetadataFetchResult = {
   faviconPath: string | null;
   faviconExt: string | null;
   faviconUrlCandidate: string | null;
+  htmlParseMs: number | null;
+  htmlParseTimedOut: boolean;
 };
 
-export async function fetchBookmarkMetadata(url: string): Promise<BookmarkMetadataFetchResult> {
-  return invoke<BookmarkMetadataFetchResult>("fetch_bookmark_metadata", { url });
+export async function fetchBookmarkMetadata(
+  url: string,
+  requestId?: string,
+): Promise<BookmarkMetadataFetchResult> {
+  return invoke<BookmarkMetadataFetchResult>("fetch_bookmark_metadata", { url, requestId: requestId ?? null });
+}
+
+export async function cancelBookmarkFetch(requestId: string): Promise<boolean> {
+  return invoke<boolean>("cancel_bookmark_fetch", { requestId });
+}
+
+export type FetchHostStats = {
+  host: string;
+  inFlight: number;
+  queued: number;
+  lastRequestAt: number | null;
+};
+
+export type FetchCoordinatorStats = {
+  maxConcurrentPerHost: number;
+  minSpacingMs: number;
+  hosts: FetchHostStats[];
+};
+
+export async function getFetchCoordinatorStats(): Promise<FetchCoordinatorStats> {
+  return invoke<FetchCoordinatorStats>("get_fetch_coordinator_stats");
+}
+
+export async function setFetchHostLimits(
+  maxConcurrent: number,
+  minSpacingMs: number,
+): Promise<FetchCoordinatorStats> {
+  return invoke<FetchCoordinatorStats>("set_fetch_host_limits", { maxConcurrent, minSpacingMs });
+}
+
+export type BookmarkRefreshSettings = {
+  refreshDays: number;
+  perHour: number;
+};
+
+export type BookmarkRefreshStatus = {
+  enabled: boolean;
+  paused: boolean;
+  running: boolean;
+  refreshDays: number;
+  perHour: number;
+  staleCount: number;
+  lastRunAt: number | null;
+  lastChecked: number;
+  lastUpdated: number;
+  lastFailed: number;
+  backedOffHosts: string[];
+};
+
+export async function getBookmarkRefreshSettings(): Promise<BookmarkRefreshSettings> {
+  return invoke<BookmarkRefreshSettings>("get_bookmark_refresh_settings");
+}
+
+export async function setBookmarkRefreshSettings(
+  refreshDays: number,
+  perHour: number,
+): Promise<BookmarkRefreshSettings> {
+  return invoke<BookmarkRefreshSettings>("set_bookmark_refresh_settings", { refreshDays, perHour });
+}
+
+export async function pauseBackgroundRefresh(): Promise<BookmarkRefreshStatus> {
+  return invoke<BookmarkRefreshStatus>("pause_background_refresh");
+}
+
+export async function resumeBackgroundRefresh(): Promise<BookmarkRefreshStatus> {
+  return invoke<BookmarkRefreshStatus>("resume_background_refresh");
+}
+
+export type ItemPreviewCacheResult = {
+  itemId: string;
+  previewUrl: string | null;
+  sourceUrl: string | null;
+  downloaded: boolean;
+  notModified: boolean;
+};
+
+export async function cacheItemPreview(itemId: string): Promise<ItemPreviewCacheResult> {
+  return invoke<ItemPreviewCacheResult>("cache_item_preview", { itemId });
+}
+
+export async function refreshItemPreview(itemId: string): Promise<ItemPreviewCacheResult> {
+  return invoke<ItemPreviewCacheResult>("refresh_item_preview", { itemId });
 }
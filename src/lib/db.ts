@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import type { BookmarkRefreshStatus } from "./bookmarks";
 
 export type DbCollectionRecord = {
   id: string;
@@ -6,7 +7,12 @@ export type DbCollectionRecord = {
   name: string;
   description: string | null;
   icon: string;
+  iconType: "named" | "emoji" | "image";
   color: string;
+  isArchived: boolean;
+  coverItemId: string | null;
+  coverThumbPath: string | null;
+  sortIndex: number;
   createdAt: number;
   updatedAt: number;
 };
@@ -29,15 +35,18 @@ export interface Tag {
   sortIndex: number;
   createdAt: number;
   updatedAt: number;
+  isSystem?: boolean;
 }
 
 export type DbThumbStatus = "ready" | "pending" | "skipped" | "error";
 export type DbImportStatus = "ready" | "processing" | "error";
 export type DbMetaStatus = "ready" | "pending" | "error";
+export type DbDescriptionFormat = "plain" | "markdown";
 
 export type DbItemRecord = {
   id: string;
   collectionId: string | null;
+  collectionIds: string[];
   type: string;
   title: string;
   filename: string;
@@ -48,16 +57,24 @@ export type DbItemRecord = {
   height: number | null;
   thumbStatus: DbThumbStatus;
   importStatus: DbImportStatus;
+  importError: string | null;
   url: string | null;
   faviconPath: string | null;
   metaStatus: DbMetaStatus;
   description: string | null;
+  descriptionFormat: DbDescriptionFormat;
+  descriptionPreview: string | null;
   rating: number;
   isFavorite: boolean;
+  isArchived: boolean;
+  isSensitive: boolean;
+  integrityStatus: DbIntegrityStatus;
+  noteCount: number;
   createdAt: number;
   updatedAt: number;
   tagIds: string[];
   tags: string[];
+  addedAt?: number;
 };
 
 export type DbTagRecord = {
@@ -67,6 +84,7 @@ export type DbTagRecord = {
   sortIndex: number;
   createdAt: number;
   updatedAt: number;
+  isSystem: boolean;
 };
 
 export type DbCollectionItemRecord = {
@@ -76,6 +94,7 @@ export type DbCollectionItemRecord = {
   customTitle: string | null;
   customDescription: string | null;
   sortIndex: number;
+  isPinned: boolean;
   createdAt: number;
 };
 
@@ -84,16 +103,20 @@ export type DbAppState = {
   collectionItems: DbCollectionItemRecord[];
   tags: DbTagRecord[];
   items: DbItemRecord[];
+  hiddenCount: number;
+  lockedCollectionIds: string[];
 };
 
+export type DbItemSource = "clipboard" | "watch" | "web";
+
 export type DbInsertItemInput = {
   id: string;
   collectionId: string | null;
   type: string;
   title: string;
-  filename: string;
-  vaultKey: string;
-  vaultPath: string;
+  filename?: string | null;
+  vaultKey?: string | null;
+  vaultPath?: string | null;
   previewUrl: string | null;
   width: number | null;
   height: number | null;
@@ -108,6 +131,8 @@ export type DbInsertItemInput = {
   createdAt: number;
   updatedAt: number;
   tags: string[];
+  originalSourcePath?: string | null;
+  source?: DbItemSource | null;
 };
 
 export type DbVaultCleanupEntry = {
@@ -116,6 +141,14 @@ export type DbVaultCleanupEntry = {
   sha256: string;
   ext: string;
   deletedFromDisk: boolean;
+  isProtected: boolean;
+};
+
+export type DbInsertItemOutcome = {
+  itemId: string;
+  alreadyExisted: boolean;
+  duplicateOfItemId: string | null;
+  needsThumbnail: boolean;
 };
 
 export type DbDeleteItemsWithCleanupResult = {
@@ -123,11 +156,32 @@ export type DbDeleteItemsWithCleanupResult = {
   cleanup: DbVaultCleanupEntry[];
 };
 
+export type DbDeleteCollectionResult = {
+  deletedCollections: number;
+  deletedItems: number;
+  removedMemberships: number;
+  cleanup: DbVaultCleanupEntry[];
+};
+
 export type DbUpdateItemsCollectionResult = {
   updatedRows: number;
   updatedAt: number;
 };
 
+export type DbItemsCollectionMode = "add" | "move_primary" | "move_all";
+
+export type DbItemMembershipChange = {
+  itemId: string;
+  added: string[];
+  removed: string[];
+  primaryCollectionId: string | null;
+};
+
+export type DbItemsCollectionUpdateResult = DbUpdateItemsCollectionResult & {
+  mode: DbItemsCollectionMode;
+  items: DbItemMembershipChange[];
+};
+
 export type DbUpdateCollectionMembershipsResult = {
   createdRows: number;
   updatedRows: number;
@@ -169,6 +223,7 @@ function toTag(record: DbTagRecord): Tag {
     sortIndex: record.sortIndex,
     createdAt: record.createdAt,
     updatedAt: record.updatedAt,
+    isSystem: record.isSystem,
   };
 }
 
@@ -180,12 +235,16 @@ export async function loadDbAppState(): Promise<DbAppState> {
   return invoke<DbAppState>("load_app_state");
 }
 
+/** Sibling names are compared trimmed and case-insensitively. */
+export type CollectionNameConflictMode = "allow" | "reject" | "reuse";
+
 export async function createCollection(params: {
   name: string;
   parentId?: string | null;
   icon: string;
   color: string;
   description?: string;
+  onNameConflict?: CollectionNameConflictMode;
 }): Promise<Collection> {
   const row = await invoke<DbCollectionRecord>("create_collection", {
     name: params.name,
@@ -193,6 +252,7 @@ export async function createCollection(params: {
     icon: params.icon,
     color: params.color,
     description: params.description ?? null,
+    onNameConflict: params.onNameConflict ?? null,
   });
   return toCollection(row);
 }
@@ -202,24 +262,155 @@ export async function getAllCollections(): Promise<Collection[]> {
   return rows.map(toCollection);
 }
 
-export async function createTag(params: { name: string; color: string }): Promise<Tag> {
+export type DbCollectionRepairEntry = {
+  collectionId: string;
+  previousParentId: string | null;
+  newParentId: string | null;
+  reason: string;
+};
+
+export type DbCollectionTreeNode = {
+  id: string;
+  parentId: string | null;
+  name: string;
+  icon: string;
+  iconType: string;
+  color: string;
+  isArchived: boolean;
+  isLocked: boolean;
+  itemCount: number;
+  recursiveItemCount: number;
+  hasChildren: boolean;
+  children: DbCollectionTreeNode[];
+};
+
+export type DbCollectionTree = {
+  roots: DbCollectionTreeNode[];
+  repaired: DbCollectionRepairEntry[];
+};
+
+export async function getDbCollectionTree(includeArchived?: boolean): Promise<DbCollectionTree> {
+  return invoke<DbCollectionTree>("get_collection_tree", { includeArchived: includeArchived ?? null });
+}
+
+export type DbCollectionSearchResult = {
+  id: string;
+  parentId: string | null;
+  name: string;
+  description: string | null;
+  path: string;
+  isArchived: boolean;
+  itemCount: number;
+};
+
+export async function searchDbCollections(query: string): Promise<DbCollectionSearchResult[]> {
+  return invoke<DbCollectionSearchResult[]>("search_collections", { query });
+}
+
+export type DbMoveCollectionResult = {
+  collection: DbCollectionRecord;
+  reorderedSiblingIds: string[];
+};
+
+export async function moveDbCollection(
+  collectionId: string,
+  newParentId: string | null,
+  insertIndex?: number,
+): Promise<DbMoveCollectionResult> {
+  return invoke<DbMoveCollectionResult>("move_collection", {
+    collectionId,
+    newParentId,
+    insertIndex: insertIndex ?? null,
+  });
+}
+
+export type DbRatingCountEntry = {
+  key: string;
+  count: number;
+};
+
+export type DbRatingStats = {
+  total: number;
+  itemsByRating: DbRatingCountEntry[];
+  favoritesCount: number;
+  averageRating: number | null;
+};
+
+export type DbTagRatingSummary = {
+  tagId: string;
+  name: string;
+  itemCount: number;
+  ratedCount: number;
+  averageRating: number | null;
+};
+
+export async function getDbRatingStats(params?: {
+  collectionId?: string | null;
+  tagId?: string | null;
+}): Promise<DbRatingStats> {
+  return invoke<DbRatingStats>("get_rating_stats", {
+    collectionId: params?.collectionId ?? null,
+    tagId: params?.tagId ?? null,
+  });
+}
+
+export async function getDbTagRatingSummary(): Promise<DbTagRatingSummary[]> {
+  return invoke<DbTagRatingSummary[]>("get_tag_rating_summary");
+}
+
+export type DbRecentImportPreview = {
+  itemId: string;
+  type: string;
+  title: string;
+  thumbPath: string | null;
+};
+
+export type DbRecentImportGroup = {
+  groupKey: string;
+  importBatchId: string | null;
+  startedAt: number;
+  finishedAt: number;
+  itemCount: number;
+  previews: DbRecentImportPreview[];
+};
+
+export type DbRecentlyImported = {
+  hours: number;
+  since: number;
+  groups: DbRecentImportGroup[];
+};
+
+export async function getDbRecentlyImported(hours?: number, limit?: number): Promise<DbRecentlyImported> {
+  return invoke<DbRecentlyImported>("get_recently_imported", {
+    hours: hours ?? null,
+    limit: limit ?? null,
+  });
+}
+
+/** Colors must be `#rgb` or `#rrggbb`; pass `autoColor` to take the next palette color instead. */
+export async function createTag(params: { name: string; color?: string; autoColor?: boolean }): Promise<Tag> {
   const row = await invoke<DbTagRecord>("create_tag", {
     input: {
       name: params.name,
-      color: params.color,
+      color: params.color ?? null,
+      autoColor: params.autoColor ?? false,
     },
   });
   return toTag(row);
 }
 
-export async function getAllTags(): Promise<Tag[]> {
-  const rows = await invoke<DbTagRecord[]>("get_all_tags");
+export async function suggestTagColor(): Promise<string> {
+  return invoke<string>("suggest_tag_color");
+}
+
+export async function getAllTags(includeSystem = false): Promise<Tag[]> {
+  const rows = await invoke<DbTagRecord[]>("get_all_tags", { includeSystem });
   return rows.map(toTag);
 }
 
-export async function updateTagName(id: string, name: string): Promise<number> {
+export async function updateTagName(id: string, name: string, allowSystem = false): Promise<number> {
   return invoke<number>("update_tag_name", {
-    input: { id, name },
+    input: { id, name, allowSystem },
   });
 }
 
@@ -234,9 +425,9 @@ export async function duplicateTag(id: string): Promise<Tag> {
   return toTag(row);
 }
 
-export async function deleteTag(id: string): Promise<number> {
+export async function deleteTag(id: string, allowSystem = false): Promise<number> {
   return invoke<number>("delete_tag", {
-    input: { id },
+    input: { id, allowSystem },
   });
 }
 
@@ -246,20 +437,105 @@ export async function reorderTags(tagIds: string[]): Promise<DbReorderTagsResult
   });
 }
 
-export async function deleteCollection(id: string): Promise<number> {
-  return invoke<number>("delete_collection", { id });
+export async function deleteCollection(id: string): Promise<DbDeleteCollectionResult> {
+  return invoke<DbDeleteCollectionResult>("delete_collection", { id });
+}
+
+export async function updateCollectionName(
+  id: string,
+  name: string,
+  onNameConflict?: CollectionNameConflictMode,
+): Promise<number> {
+  return invoke<number>("update_collection_name", { id, name, onNameConflict: onNameConflict ?? null });
+}
+
+export type DuplicateCollectionGroup = {
+  parentId: string | null;
+  name: string;
+  collectionIds: string[];
+};
+
+export type MergeCollectionsResult = {
+  targetCollectionId: string;
+  mergedCollections: number;
+  movedMemberships: number;
+  collapsedMemberships: number;
+  movedChildren: number;
+  updatedAt: number;
+};
+
+export async function findDuplicateSiblingCollections(): Promise<DuplicateCollectionGroup[]> {
+  return invoke<DuplicateCollectionGroup[]>("find_duplicate_sibling_collections");
+}
+
+export async function mergeCollections(
+  sourceCollectionId: string,
+  targetCollectionId: string,
+): Promise<MergeCollectionsResult> {
+  return invoke<MergeCollectionsResult>("merge_collections", { sourceCollectionId, targetCollectionId });
+}
+
+export async function mergeDuplicateCollections(collectionIds: string[]): Promise<MergeCollectionsResult> {
+  return invoke<MergeCollectionsResult>("merge_duplicate_collections", { collectionIds });
+}
+
+export async function insertDbItem(item: DbInsertItemInput): Promise<DbInsertItemOutcome> {
+  return invoke<DbInsertItemOutcome>("insert_item", { item });
+}
+
+export async function generateDbItemThumbnail(itemId: string): Promise<DbItemRecord> {
+  return invoke<DbItemRecord>("generate_thumbnail_for_item", { itemId });
 }
 
-export async function updateCollectionName(id: string, name: string): Promise<number> {
-  return invoke<number>("update_collection_name", { id, name });
+export type DbDimensionBackfillResult = {
+  scanned: number;
+  updated: number;
+  failed: number;
+  skippedLocked: number;
+  remaining: number;
+};
+
+export async function backfillDbItemDimensions(limit?: number): Promise<DbDimensionBackfillResult> {
+  return invoke<DbDimensionBackfillResult>("backfill_item_dimensions", { limit: limit ?? null });
 }
 
-export async function insertDbItem(item: DbInsertItemInput): Promise<void> {
-  return invoke<void>("insert_item", { item });
+export type DbProcessingCountEntry = {
+  key: string;
+  count: number;
+};
+
+export type DbProcessingHealth = {
+  thumbStatus: DbProcessingCountEntry[];
+  importStatus: DbProcessingCountEntry[];
+  metaStatus: DbProcessingCountEntry[];
+  staleBefore: number;
+  stuckImportItemIds: string[];
+  stuckThumbItemIds: string[];
+  stuckMetaItemIds: string[];
+  bookmarkRefresh: BookmarkRefreshStatus;
+};
+
+export type DbStuckStateRepairResult = {
+  staleBefore: number;
+  failedImportItemIds: string[];
+  requeuedThumbItemIds: string[];
+  requeuedMetaItemIds: string[];
+};
+
+export async function getDbProcessingHealth(): Promise<DbProcessingHealth> {
+  return invoke<DbProcessingHealth>("get_processing_health");
 }
 
-export async function insertDbItemsBatch(items: DbInsertItemInput[]): Promise<void> {
-  return invoke<void>("insert_items_batch", { items });
+export async function repairDbStuckStates(): Promise<DbStuckStateRepairResult> {
+  return invoke<DbStuckStateRepairResult>("repair_stuck_states");
+}
+
+export async function takeDbStartupStuckStateRepair(): Promise<DbStuckStateRepairResult | null> {
+  return invoke<DbStuckStateRepairResult | null>("take_startup_stuck_state_repair");
+}
+
+export async function insertDbItemsBatch(items: DbInsertItemInput[]): Promise<DbInsertItemOutcome[]> {
+  return invoke<DbInsertItemOutcome[]>("insert_items_batch", { items });
 }
 
 export async function deleteDbItems(itemIds: string[]): Promise<number> {
@@ -272,13 +548,46 @@ export async function deleteDbItemsWithCleanup(
   return invoke<DbDeleteItemsWithCleanupResult>("delete_items_with_cleanup", { itemIds });
 }
 
+export type DbImportBatch = {
+  id: string;
+  source: string;
+  description: string;
+  startedAt: number;
+  finishedAt: number | null;
+  importedCount: number;
+  failedCount: number;
+  skippedCount: number;
+  deduplicatedCount: number;
+  itemCount: number;
+};
+
+export async function listDbImportBatches(limit?: number): Promise<DbImportBatch[]> {
+  return invoke<DbImportBatch[]>("list_import_batches", { limit: limit ?? null });
+}
+
+export async function getDbBatchItems(
+  batchId: string,
+  includeSensitive?: boolean,
+): Promise<DbItemRecord[]> {
+  return invoke<DbItemRecord[]>("get_batch_items", {
+    batchId,
+    includeSensitive: includeSensitive ?? null,
+  });
+}
+
+export async function deleteDbBatchItems(batchId: string): Promise<DbDeleteItemsWithCleanupResult> {
+  return invoke<DbDeleteItemsWithCleanupResult>("delete_batch_items", { batchId });
+}
+
 export async function updateDbItemsCollection(
   itemIds: string[],
   collectionId: string | null,
-): Promise<DbUpdateItemsCollectionResult> {
-  return invoke<DbUpdateItemsCollectionResult>("update_items_collection", {
+  mode: DbItemsCollectionMode = "add",
+): Promise<DbItemsCollectionUpdateResult> {
+  return invoke<DbItemsCollectionUpdateResult>("update_items_collection", {
     itemIds,
     collectionId,
+    mode,
   });
 }
 
@@ -314,6 +623,27 @@ export async function reorderDbCollectionItems(
   });
 }
 
+export type DbApplyCollectionLayoutResult = {
+  applied: number;
+  skipped: number;
+  appended: number;
+  updatedAt: number;
+};
+
+export async function exportDbCollectionLayout(collectionId: string): Promise<string> {
+  return invoke<string>("export_collection_layout", { collectionId });
+}
+
+export async function applyDbCollectionLayout(
+  collectionId: string,
+  layoutJson: string,
+): Promise<DbApplyCollectionLayoutResult> {
+  return invoke<DbApplyCollectionLayoutResult>("apply_collection_layout", {
+    collectionId,
+    layoutJson,
+  });
+}
+
 export async function updateDbItemDescription(
   itemId: string,
   description: string,
@@ -321,6 +651,89 @@ export async function updateDbItemDescription(
   return invoke<number>("update_item_description", { itemId, description });
 }
 
+export async function updateDbItemDescriptionV2(
+  itemId: string,
+  description: string,
+  format: DbDescriptionFormat,
+): Promise<number> {
+  return invoke<number>("update_item_description_v2", { itemId, description, format });
+}
+
+export async function setDbItemsSensitive(
+  itemIds: string[],
+  sensitive: boolean,
+): Promise<{ updatedRows: number; updatedAt: number }> {
+  return invoke("set_items_sensitive", { itemIds, sensitive });
+}
+
+export async function setCollectionLock(collectionId: string, passphrase: string): Promise<number> {
+  return invoke<number>("set_collection_lock", { collectionId, passphrase });
+}
+
+export async function unlockCollection(collectionId: string, passphrase: string): Promise<boolean> {
+  return invoke<boolean>("unlock_collection", { collectionId, passphrase });
+}
+
+export async function lockCollection(collectionId: string): Promise<boolean> {
+  return invoke<boolean>("lock_collection", { collectionId });
+}
+
+export async function removeCollectionLock(collectionId: string, passphrase: string): Promise<boolean> {
+  return invoke<boolean>("remove_collection_lock", { collectionId, passphrase });
+}
+
+export type DbIntegrityStatus = "ok" | "modified" | "missing";
+
+export type DbItemIntegrityReport = {
+  itemId: string;
+  vaultKey: string;
+  integrityStatus: DbIntegrityStatus;
+  expectedSize: number | null;
+  actualSize: number | null;
+  expectedMtime: number | null;
+  actualMtime: number | null;
+  rehashed: boolean;
+  actualSha256: string | null;
+  checkedAt: number;
+};
+
+export type DbRepairModifiedVaultFileResult = {
+  itemId: string;
+  previousVaultKey: string;
+  vaultKey: string;
+  vaultPath: string;
+  rekeyed: boolean;
+  item: DbItemRecord;
+};
+
+export async function verifyDbItemFile(itemId: string, rehash?: boolean): Promise<DbItemIntegrityReport> {
+  return invoke<DbItemIntegrityReport>("verify_item_file", { itemId, rehash: rehash ?? null });
+}
+
+export async function recordDbItemOpened(itemId: string): Promise<DbItemIntegrityReport | null> {
+  return invoke<DbItemIntegrityReport | null>("record_item_opened", { itemId });
+}
+
+export async function getAutoVerifyOnOpen(): Promise<boolean> {
+  return invoke<boolean>("get_auto_verify_on_open");
+}
+
+export async function setAutoVerifyOnOpen(enabled: boolean): Promise<boolean> {
+  return invoke<boolean>("set_auto_verify_on_open", { enabled });
+}
+
+export async function repairDbModifiedVaultFile(itemId: string): Promise<DbRepairModifiedVaultFileResult> {
+  return invoke<DbRepairModifiedVaultFileResult>("repair_modified_vault_file", { itemId });
+}
+
+export async function getHideSensitive(): Promise<boolean> {
+  return invoke<boolean>("get_hide_sensitive");
+}
+
+export async function setHideSensitive(hidden: boolean): Promise<boolean> {
+  return invoke<boolean>("set_hide_sensitive", { hidden });
+}
+
 export async function updateDbItemPreferences(input: {
   itemId: string;
   rating?: number;
@@ -349,6 +762,7 @@ export async function updateDbItemMediaState(params: {
   width?: number | null;
   height?: number | null;
   thumbStatus?: DbThumbStatus;
+  clearDimensions?: boolean;
 }): Promise<number> {
   return invoke<number>("update_item_media_state", {
     input: {
@@ -356,6 +770,7 @@ export async function updateDbItemMediaState(params: {
       width: params.width ?? null,
       height: params.height ?? null,
       thumbStatus: params.thumbStatus ?? null,
+      clearDimensions: params.clearDimensions ?? false,
     },
   });
 }
@@ -367,6 +782,8 @@ export async function updateDbItemBookmarkMetadata(input: {
   filename?: string | null;
   faviconPath?: string | null;
   metaStatus: DbMetaStatus;
+  clearUrl?: boolean;
+  clearFaviconPath?: boolean;
 }): Promise<number> {
   return invoke<number>("update_item_bookmark_metadata", {
     input: {
@@ -376,6 +793,8 @@ export async function updateDbItemBookmarkMetadata(input: {
       filename: input.filename ?? null,
       faviconPath: input.faviconPath ?? null,
       metaStatus: input.metaStatus,
+      clearUrl: input.clearUrl ?? false,
+      clearFaviconPath: input.clearFaviconPath ?? false,
     },
   });
 }
@@ -404,10 +823,54 @@ export async function finalizeDbItemImport(input: {
   });
 }
 
-export async function markDbItemImportError(itemId: string): Promise<number> {
+export async function markDbItemImportError(itemId: string, error?: string | null): Promise<number> {
   return invoke<number>("mark_item_import_error", {
     input: {
       itemId,
+      error: error ?? null,
     },
   });
 }
+
+export type DbWeeklyDigestSection = {
+  count: number;
+  itemIds: string[];
+};
+
+export type DbWeeklyDigestTag = {
+  tagId: string;
+  name: string;
+  color: string;
+  count: number;
+};
+
+export type DbWeeklyDigest = {
+  weekStart: string;
+  isoYear: number;
+  isoWeek: number;
+  from: number;
+  to: number;
+  added: DbWeeklyDigestSection;
+  addedByType: DbProcessingCountEntry[];
+  bookmarksRead: DbWeeklyDigestSection;
+  ratedHigh: DbWeeklyDigestSection;
+  topTags: DbWeeklyDigestTag[];
+  bytesImported: number;
+};
+
+/** `weekOffset` 0 is the current local week, -1 the previous one. */
+export async function getWeeklyDigest(weekOffset: number): Promise<DbWeeklyDigest> {
+  return invoke<DbWeeklyDigest>("get_weekly_digest", {
+    weekOffset,
+    utcOffsetMinutes: -new Date().getTimezoneOffset(),
+  });
+}
+
+export type DbExportDiagnosticsResult = {
+  path: string;
+  sizeBytes: number;
+};
+
+export async function exportDiagnostics(path?: string): Promise<DbExportDiagnosticsResult> {
+  return invoke<DbExportDiagnosticsResult>("export_diagnostics", { path: path ?? null });
+}
@@ -1,4 +1,4 @@
-export type ItemListSortOption = "newest" | "oldest" | "name-asc" | "rating-desc";
+export type ItemListSortOption = "newest" | "oldest" | "name-asc" | "name-natural" | "rating-desc";
 
 export const ADVANCED_ITEM_FILTER_TYPE_OPTIONS = [
   "image",
@@ -19,6 +19,12 @@ export type AdvancedItemFilters = {
 
 type FilterableItemType = "bookmark" | "image" | "video" | "pdf" | "file" | "note";
 
+type FilterableCollectionOverride = {
+  collectionId: string;
+  customTitle?: string;
+  customDescription?: string;
+};
+
 type FilterableItem = {
   id: string;
   filename: string;
@@ -34,6 +40,7 @@ type FilterableItem = {
   url?: string;
   hostname?: string;
   noteText?: string;
+  collectionInstancesByCollectionId?: Record<string, FilterableCollectionOverride>;
 };
 
 type FilterItemsArgs<TItem extends FilterableItem> = {
@@ -44,6 +51,12 @@ type FilterItemsArgs<TItem extends FilterableItem> = {
   skipSort?: boolean;
 };
 
+export type FilteredItemsResult<TItem extends FilterableItem> = {
+  items: TItem[];
+  /** Collection ids whose per-collection overrides matched, for items that matched only that way. */
+  overrideMatchesByItemId: Map<string, string[]>;
+};
+
 export function createDefaultAdvancedItemFilters(): AdvancedItemFilters {
   return {
     types: [],
@@ -144,6 +157,17 @@ function compareItemsBySortOption<TItem extends FilterableItem>(
     return right.id.localeCompare(left.id);
   }
 
+  if (sortOption === "name-natural") {
+    const nameCompare = left.title.localeCompare(right.title, undefined, {
+      sensitivity: "base",
+      numeric: true,
+    });
+    if (nameCompare !== 0) {
+      return nameCompare;
+    }
+    return right.id.localeCompare(left.id);
+  }
+
   if (sortOption === "rating-desc") {
     const leftRating = normalizeItemRating(left.rating);
     const rightRating = normalizeItemRating(right.rating);
@@ -183,12 +207,36 @@ function itemTypeToFilterBucket(itemType: FilterableItemType): AdvancedItemFilte
   }
 }
 
+export function getCollectionOverrideSearchMatches(
+  item: Pick<FilterableItem, "collectionInstancesByCollectionId">,
+  searchQuery: string,
+): string[] {
+  const normalizedQuery = searchQuery.trim().toLowerCase();
+  if (!normalizedQuery || !item.collectionInstancesByCollectionId) {
+    return [];
+  }
+  return Object.values(item.collectionInstancesByCollectionId)
+    .filter((instance) =>
+      [instance.customTitle, instance.customDescription].some(
+        (value) => typeof value === "string" && value.toLowerCase().includes(normalizedQuery),
+      ),
+    )
+    .map((instance) => instance.collectionId);
+}
+
 export function filterItems<TItem extends FilterableItem>(args: FilterItemsArgs<TItem>): TItem[] {
+  return filterItemsWithOverrideMatches(args).items;
+}
+
+export function filterItemsWithOverrideMatches<TItem extends FilterableItem>(
+  args: FilterItemsArgs<TItem>,
+): FilteredItemsResult<TItem> {
   const { items, searchQuery, filters, sortOption, skipSort = false } = args;
   const normalizedQuery = searchQuery.trim().toLowerCase();
   const selectedTypes = filters.types.length > 0 ? new Set(filters.types) : null;
   const selectedTagIds = filters.tagIds.length > 0 ? new Set(filters.tagIds) : null;
   const minRating = filters.minRating > 0 ? filters.minRating : 0;
+  const overrideMatchesByItemId = new Map<string, string[]>();
 
   const filtered = items.filter((item) => {
     if (filters.favoritesOnly && !item.isFavorite) {
@@ -227,14 +275,26 @@ export function filterItems<TItem extends FilterableItem>(args: FilterItemsArgs<
       item.tags?.join(" "),
     ];
 
-    return searchableValues
+    const matchesItemFields = searchableValues
       .filter((value): value is string => typeof value === "string" && value.length > 0)
       .some((value) => value.toLowerCase().includes(normalizedQuery));
+    if (matchesItemFields) {
+      return true;
+    }
+    const overrideMatches = getCollectionOverrideSearchMatches(item, normalizedQuery);
+    if (overrideMatches.length === 0) {
+      return false;
+    }
+    overrideMatchesByItemId.set(item.id, overrideMatches);
+    return true;
   });
 
   if (skipSort) {
-    return filtered;
+    return { items: filtered, overrideMatchesByItemId };
   }
 
-  return filtered.slice().sort((left, right) => compareItemsBySortOption(left, right, sortOption));
+  return {
+    items: filtered.slice().sort((left, right) => compareItemsBySortOption(left, right, sortOption)),
+    overrideMatchesByItemId,
+  };
 }
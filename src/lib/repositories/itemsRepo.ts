@@ -15,7 +15,8 @@ import {
   updateDbItemBookmarkMetadata,
   finalizeDbItemImport,
   markDbItemImportError,
-  type DbUpdateItemsCollectionResult,
+  type DbItemsCollectionMode,
+  type DbItemsCollectionUpdateResult,
   type DbUpdateCollectionMembershipsResult,
   type DbReorderCollectionItemsResult,
   type DbInsertItemInput,
@@ -44,8 +45,9 @@ export async function deleteItemsByIdsWithCleanup(
 export async function moveItemsToCollection(
   itemIds: string[],
   collectionId: string | null,
-): Promise<DbUpdateItemsCollectionResult> {
-  return updateDbItemsCollection(itemIds, collectionId);
+  mode: DbItemsCollectionMode = "add",
+): Promise<DbItemsCollectionUpdateResult> {
+  return updateDbItemsCollection(itemIds, collectionId, mode);
 }
 
 export async function moveCollectionItemMemberships(params: {
@@ -125,6 +127,6 @@ export async function finalizeItemImport(params: {
   return finalizeDbItemImport(params);
 }
 
-export async function markItemImportError(itemId: string): Promise<number> {
-  return markDbItemImportError(itemId);
+export async function markItemImportError(itemId: string, error?: string | null): Promise<number> {
+  return markDbItemImportError(itemId, error);
 }
@@ -4,16 +4,69 @@ export type VaultImportResult = {
   vaultPath: string;
   sha256: string;
   ext: string;
+  claimedExt: string;
   size: number;
   createdAt: string;
   originalFilename: string;
 };
 
+export type LibraryStatus = "ok" | "missing_root" | "read_only" | "db_locked";
+
+export type LibraryAvailability = {
+  status: LibraryStatus;
+  appRoot: string | null;
+  storageRoot: string | null;
+  message: string | null;
+  checkedAt: number;
+};
+
+export const LIBRARY_READY_EVENT = "library://ready";
+
+export function isLibraryUnavailableError(error: unknown): boolean {
+  return String(error).startsWith("library unavailable");
+}
+
+export async function checkLibraryAvailable(): Promise<LibraryAvailability> {
+  return invoke<LibraryAvailability>("check_library_available");
+}
+
+export async function reconnectLibrary(): Promise<LibraryAvailability> {
+  return invoke<LibraryAvailability>("reconnect_library");
+}
+
+export type DiskUsage = {
+  path: string;
+  totalBytes: number;
+  availableBytes: number;
+  usedBytes: number;
+  thumbnailsPaused: boolean;
+};
+
+export type DiskSpaceLowPayload = {
+  path: string;
+  requiredBytes: number;
+  availableBytes: number;
+  operation: string;
+};
+
+export const DISK_SPACE_LOW_EVENT = "disk://low-space";
+
+export function isInsufficientDiskSpaceError(error: unknown): boolean {
+  return String(error).includes("insufficient disk space");
+}
+
+export async function getDiskUsage(): Promise<DiskUsage> {
+  return invoke<DiskUsage>("get_disk_usage");
+}
+
 export type ImportPipelineMetrics = {
   hashMs: number;
   copyMs: number;
   metadataMs: number;
   thumbMs: number;
+  decodeMs: number;
+  resizeMs: number;
+  encodeMs: number;
   totalMs: number;
   deduped: boolean;
 };
@@ -100,6 +153,29 @@ export async function pickFiles(): Promise<string[]> {
   return invoke<string[]>("pick_files");
 }
 
+export type DialogPurpose = "import" | "export";
+
+export type DialogFileFilter = {
+  name: string;
+  extensions: string[];
+};
+
+export async function pickFolder(purpose: DialogPurpose = "import"): Promise<string | null> {
+  return invoke<string | null>("pick_folder", { purpose });
+}
+
+export async function pickSavePath(params: {
+  defaultName?: string;
+  filters?: DialogFileFilter[];
+  purpose?: DialogPurpose;
+}): Promise<string | null> {
+  return invoke<string | null>("pick_save_path", {
+    defaultName: params.defaultName,
+    filters: params.filters,
+    purpose: params.purpose ?? "export",
+  });
+}
+
 export async function removeFromVaultIfUnreferenced(params: {
   sha256: string;
   ext: string;
@@ -113,3 +189,262 @@ export async function removeFromVaultIfUnreferenced(params: {
     ext: params.ext,
   });
 }
+
+export type VaultEncryptionStatus = {
+  enabled: boolean;
+  unlocked: boolean;
+  pendingFiles: number;
+  migrationJobId: string | null;
+};
+
+export async function getVaultEncryptionStatus(): Promise<VaultEncryptionStatus> {
+  return invoke<VaultEncryptionStatus>("get_vault_encryption_status");
+}
+
+export async function enableVaultEncryption(passphrase: string): Promise<VaultEncryptionStatus> {
+  return invoke<VaultEncryptionStatus>("enable_vault_encryption", { passphrase });
+}
+
+export async function unlockLibrary(passphrase: string): Promise<VaultEncryptionStatus> {
+  return invoke<VaultEncryptionStatus>("unlock_library", { passphrase });
+}
+
+export async function lockLibrary(): Promise<void> {
+  return invoke<void>("lock_library");
+}
+
+export async function changeVaultPassphrase(
+  oldPassphrase: string,
+  newPassphrase: string,
+): Promise<void> {
+  return invoke<void>("change_passphrase", { oldPassphrase, newPassphrase });
+}
+
+export type VaultFileReference = {
+  itemId: string;
+  itemType: string;
+  title: string;
+};
+
+export type VaultFileInfo = {
+  vaultKey: string;
+  vaultPath: string;
+  sha256: string;
+  ext: string;
+  sizeBytes: number;
+  refCount: number;
+  isEncrypted: boolean;
+  isProtected: boolean;
+  createdAt: number;
+  updatedAt: number;
+  fileExists: boolean;
+  thumbPath: string | null;
+  thumbExists: boolean;
+  versionCount: number;
+  items: VaultFileReference[];
+};
+
+export type LargestVaultFile = {
+  vaultKey: string;
+  vaultPath: string;
+  ext: string;
+  sizeBytes: number;
+  refCount: number;
+  itemCount: number;
+  sampleTitle: string | null;
+  fileExists: boolean;
+  isProtected: boolean;
+};
+
+export type ProtectItemFilesResult = {
+  vaultKeys: string[];
+  updatedAt: number;
+};
+
+export async function getVaultFileInfo(vaultKey: string): Promise<VaultFileInfo> {
+  return invoke<VaultFileInfo>("get_vault_file_info", { vaultKey });
+}
+
+export async function listLargestVaultFiles(limit?: number): Promise<LargestVaultFile[]> {
+  return invoke<LargestVaultFile[]>("list_largest_vault_files", { limit: limit ?? null });
+}
+
+export async function listProtectedVaultFiles(): Promise<LargestVaultFile[]> {
+  return invoke<LargestVaultFile[]>("list_protected_vault_files");
+}
+
+export type QuarantinedFile = {
+  fileName: string;
+  path: string;
+  vaultKey: string;
+  originalFilename: string;
+  reason: "truncated" | "unsupported" | "corrupt";
+  detail: string;
+  sizeBytes: number;
+  quarantinedAt: number;
+  exists: boolean;
+};
+
+export type PurgeQuarantinedFilesResult = {
+  removed: number;
+  missing: number;
+  deferred: number;
+  freedBytes: number;
+};
+
+export async function listQuarantinedFiles(): Promise<QuarantinedFile[]> {
+  return invoke<QuarantinedFile[]>("list_quarantined_files");
+}
+
+export async function purgeQuarantinedFiles(fileNames?: string[]): Promise<PurgeQuarantinedFilesResult> {
+  return invoke<PurgeQuarantinedFilesResult>("purge_quarantined_files", { fileNames: fileNames ?? null });
+}
+
+export async function setVaultFileProtected(vaultKey: string, isProtected: boolean): Promise<number> {
+  return invoke<number>("set_vault_file_protected", { vaultKey, protected: isProtected });
+}
+
+export async function protectItemFiles(itemIds: string[]): Promise<ProtectItemFilesResult> {
+  return invoke<ProtectItemFilesResult>("protect_item_files", { itemIds });
+}
+
+export type VaultLayout = "month" | "by_year" | "flat";
+
+export type VaultCompactionMove = {
+  vaultKey: string;
+  fromPath: string;
+  toPath: string;
+};
+
+export type VaultCompactionReport = {
+  strategy: VaultLayout;
+  dryRun: boolean;
+  planned: VaultCompactionMove[];
+  moved: number;
+  failed: number;
+  removedDirectories: number;
+};
+
+export async function compactVault(strategy: VaultLayout, dryRun = false): Promise<VaultCompactionReport> {
+  return invoke<VaultCompactionReport>("compact_vault", { strategy, dryRun });
+}
+
+export type ImportDedupEntry = {
+  sourcePath: string;
+  vaultKey: string;
+  matchedItems: VaultFileReference[];
+};
+
+export type ImportReport = {
+  operation: string;
+  createdAt: number;
+  imported: number;
+  failed: number;
+  skipped: number;
+  deduplicated: ImportDedupEntry[];
+  thumbPolicy: ImportThumbPolicy | "";
+};
+
+export type ImportThumbPolicy = "immediate" | "deferred" | "never";
+
+export async function getImportThumbPolicy(): Promise<ImportThumbPolicy | null> {
+  return invoke<ImportThumbPolicy | null>("get_import_thumb_policy");
+}
+
+export async function setImportThumbPolicy(
+  policy: ImportThumbPolicy | null,
+): Promise<ImportThumbPolicy | null> {
+  return invoke<ImportThumbPolicy | null>("set_import_thumb_policy", { policy });
+}
+
+export async function getImportHashParallelism(): Promise<number> {
+  return invoke<number>("get_import_hash_parallelism");
+}
+
+export async function setImportHashParallelism(parallelism: number | null): Promise<number> {
+  return invoke<number>("set_import_hash_parallelism", { parallelism });
+}
+
+export async function getLastImportReport(): Promise<ImportReport | null> {
+  return invoke<ImportReport | null>("get_last_import_report");
+}
+
+export type ArchiveEntry = {
+  name: string;
+  size: number;
+  compressedSize: number;
+  extension: string;
+  isImage: boolean;
+  isDir: boolean;
+};
+
+export type ImportEntryFailure = {
+  path: string;
+  error: string;
+};
+
+export type ArchiveImportResult = {
+  itemIds: string[];
+  failed: ImportEntryFailure[];
+  skipped: ImportEntryFailure[];
+  collectionsCreated: number;
+  deduplicated: ImportDedupEntry[];
+  importBatchId: string;
+  thumbPolicy: ImportThumbPolicy;
+};
+
+export function isArchivePasswordProtectedError(error: unknown): boolean {
+  return String(error).includes("archive is password-protected");
+}
+
+export async function inspectArchive(path: string): Promise<ArchiveEntry[]> {
+  return invoke<ArchiveEntry[]>("inspect_archive", { path });
+}
+
+export async function importFromArchive(params: {
+  path: string;
+  entryNames?: string[] | null;
+  collectionId?: string | null;
+}): Promise<ArchiveImportResult> {
+  return invoke<ArchiveImportResult>("import_from_archive", {
+    path: params.path,
+    entryNames: params.entryNames ?? null,
+    collectionId: params.collectionId ?? null,
+  });
+}
+
+export type ExtensionMismatchEntry = {
+  vaultKey: string;
+  vaultPath: string;
+  storedExt: string;
+  sniffedExt: string;
+  itemCount: number;
+};
+
+export type ExtensionMismatchReport = {
+  scanned: number;
+  unreadable: number;
+  mismatches: ExtensionMismatchEntry[];
+};
+
+export async function fixMismatchedExtensions(): Promise<ExtensionMismatchReport> {
+  return invoke<ExtensionMismatchReport>("fix_mismatched_extensions");
+}
+
+export type VaultMaintenanceStatus = {
+  running: boolean;
+  inUseKeys: number;
+  deferredCleanups: number;
+};
+
+export async function getVaultMaintenanceStatus(): Promise<VaultMaintenanceStatus> {
+  return invoke<VaultMaintenanceStatus>("get_vault_maintenance_status");
+}
+
+export async function acquireVaultKeys(vaultKeys: string[]): Promise<void> {
+  return invoke<void>("acquire_vault_keys", { vaultKeys });
+}
+
+export async function releaseVaultKeys(vaultKeys: string[]): Promise<void> {
+  return invoke<void>("release_vault_keys", { vaultKeys });
+}
@@ -60,6 +60,11 @@ const DEFAULT_THUMBNAIL_TIMEOUT_MS = 60_000;
 const DEFAULT_THUMBNAIL_MAX_RETRIES = 1;
 const DEFAULT_THUMBNAIL_QUEUE_START_DELAY_MS = 400;
 const MAX_THUMB_CONCURRENCY = 3;
+const DISK_SPACE_PAUSE_RETRY_MS = 60_000;
+
+export function isThumbnailPausedError(error: unknown): boolean {
+  return String(error).startsWith("thumbnail generation paused");
+}
 
 class ThumbnailTimeoutError extends Error {
   readonly timeoutMs: number;
@@ -152,6 +157,7 @@ export class ThumbnailQueue {
   private activeCount = 0;
   private disposed = false;
   private delayedPumpTimerId: ReturnType<typeof setTimeout> | null = null;
+  private pausedTimerId: ReturnType<typeof setTimeout> | null = null;
   private concurrency: number;
   private readonly startDelayMs: number;
   private readonly throttleMs: number;
@@ -192,10 +198,41 @@ export class ThumbnailQueue {
       clearTimeout(this.delayedPumpTimerId);
       this.delayedPumpTimerId = null;
     }
+    if (this.pausedTimerId !== null) {
+      clearTimeout(this.pausedTimerId);
+      this.pausedTimerId = null;
+    }
     this.pendingByKey.clear();
     this.activeKeys.clear();
   }
 
+  isPaused(): boolean {
+    return this.pausedTimerId !== null;
+  }
+
+  pause(durationMs = DISK_SPACE_PAUSE_RETRY_MS): void {
+    if (this.disposed || this.pausedTimerId !== null) {
+      return;
+    }
+    console.warn("[thumb-queue] paused", {
+      durationMs,
+      pending: this.pendingByKey.size,
+    });
+    this.pausedTimerId = setTimeout(() => {
+      this.pausedTimerId = null;
+      this.schedulePump();
+    }, durationMs);
+  }
+
+  resume(): void {
+    if (this.pausedTimerId === null) {
+      return;
+    }
+    clearTimeout(this.pausedTimerId);
+    this.pausedTimerId = null;
+    this.schedulePump();
+  }
+
   setConcurrency(nextConcurrency: number): void {
     if (this.disposed) {
       return;
@@ -264,6 +301,9 @@ export class ThumbnailQueue {
         return { ok: true, outputPath, durationMs, attempts: attempt, itemId };
       } catch (error) {
         lastError = error;
+        if (isThumbnailPausedError(error)) {
+          break;
+        }
         if (error instanceof ThumbnailTimeoutError) {
           console.error("[thumb-job] timeout", {
             itemId,
@@ -306,7 +346,11 @@ export class ThumbnailQueue {
       });
     }
 
-    while (this.activeCount < this.concurrency && this.pendingByKey.size > 0) {
+    while (
+      this.pausedTimerId === null &&
+      this.activeCount < this.concurrency &&
+      this.pendingByKey.size > 0
+    ) {
       const nextEntry = this.pendingByKey.entries().next();
       if (nextEntry.done) {
         return;
@@ -322,6 +366,12 @@ export class ThumbnailQueue {
           if (this.disposed) {
             return;
           }
+          if (!result.ok && isThumbnailPausedError(result.error)) {
+            this.activeKeys.delete(dedupeKey);
+            this.pendingByKey.set(dedupeKey, task);
+            this.pause();
+            return;
+          }
           if (result.ok) {
             task.onSuccess?.(result.outputPath);
           } else {